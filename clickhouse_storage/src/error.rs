@@ -0,0 +1,85 @@
+use indexer_errors::{Classify, ErrorClass, IndexerError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Failed to parse storage url: {0}")]
+    Dsn(#[from] dsn::ParseError),
+
+    #[error("Unknown protocol: {0}")]
+    UnknownProtocol(String),
+
+    #[error("Unknown migration version: {0}")]
+    UnknownMigration(String),
+
+    #[error("Migration {0} has no down script")]
+    IrreversibleMigration(String),
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[error(
+        "Tables don't match the rows written to them:\n{}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+    )]
+    SchemaMismatch(Vec<crate::schema::TableDiff>),
+
+    #[cfg(feature = "http")]
+    #[error("ClickHouse HTTP error: {0}")]
+    Http(#[from] clickhouse::error::Error),
+
+    #[cfg(feature = "tcp")]
+    #[error("ClickHouse TCP error: {0}")]
+    Tcp(#[from] clickhouse_rs::errors::Error),
+
+    #[cfg(feature = "postgres")]
+    #[error("PostgreSQL error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+}
+
+impl Classify for StorageError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            StorageError::Dsn(_)
+            | StorageError::UnknownProtocol(_)
+            | StorageError::UnknownMigration(_)
+            | StorageError::IrreversibleMigration(_) => ErrorClass::Configuration,
+            #[cfg(any(feature = "http", feature = "tcp"))]
+            StorageError::SchemaMismatch(_) => ErrorClass::Configuration,
+            #[cfg(feature = "http")]
+            StorageError::Http(_) => ErrorClass::Transient,
+            #[cfg(feature = "tcp")]
+            StorageError::Tcp(_) => ErrorClass::Transient,
+            #[cfg(feature = "postgres")]
+            StorageError::Postgres(_) => ErrorClass::Transient,
+        }
+    }
+}
+
+impl From<StorageError> for IndexerError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::UnknownProtocol(protocol) => IndexerError::UnknownProtocol(protocol),
+            err => IndexerError::new(err.classify(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexer_error_keeps_the_message() {
+        let err = IndexerError::from(StorageError::UnknownProtocol("ftp".to_string()));
+        assert_eq!(err.to_string(), "Unknown protocol: ftp");
+        assert_eq!(err.classify(), ErrorClass::Configuration);
+
+        let err = IndexerError::from(StorageError::IrreversibleMigration(
+            "00000000000019_entangler_swaps_setup".to_string(),
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Migration 00000000000019_entangler_swaps_setup has no down script"
+        );
+        assert_eq!(err.classify(), ErrorClass::Configuration);
+    }
+}
@@ -0,0 +1,34 @@
+//! Connection and migrations machinery shared by the services which store data in ClickHouse
+//! (and the PostgreSQL migrations runner of `epoch_tracker`).
+//!
+//! Everything backend specific is behind a feature, so a service pulls only the clients it uses.
+
+pub mod error;
+pub mod migrations;
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub mod connection;
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub mod retention;
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub mod schema;
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub mod storage;
+
+pub use error::StorageError;
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub use connection::{connect, Connect};
+#[cfg(feature = "postgres")]
+pub use migrations::postgres::PostgresMigrations;
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub use migrations::{is_valid_table_prefix, Migrations};
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub use retention::{apply_retention, TableRetention};
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub use schema::{check_schema, ClickhouseTable, WrittenTable};
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub use storage::ClickhouseStorage;
+
+#[cfg(feature = "http")]
+pub use clickhouse::Row;
@@ -0,0 +1,739 @@
+//! Migrations are embedded into the services as a table of `(name, up.sql)` pairs. The version
+//! of a migration is the leading part of its name, e.g. `20221218013028` for
+//! `2022-12-18-013028_create_table_epochs`.
+//!
+//! The ClickHouse scripts name their tables as `{table_prefix}instructions`, so the services
+//! sharing one database keep their tables apart by the prefix they are migrated with
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+use crate::{storage::ClickhouseStorage, StorageError};
+
+#[cfg(any(feature = "http", feature = "tcp", feature = "postgres"))]
+pub type Scripts<'a> = &'a [(&'a str, &'a str)];
+
+#[cfg(any(feature = "http", feature = "tcp", feature = "postgres"))]
+fn version(name: &str) -> String {
+    let v: Vec<&str> = name.split('_').collect();
+    if !v.is_empty() {
+        v[0].replace('-', "")
+    } else {
+        "".to_string()
+    }
+}
+
+/// Placeholder of the table prefix in the ClickHouse scripts
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub const TABLE_PREFIX: &str = "{table_prefix}";
+
+/// A table prefix makes part of an identifier, so it may hold only letters, digits and
+/// underscores and can't start with a digit. The empty prefix keeps the bare table names
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub fn is_valid_table_prefix(table_prefix: &str) -> bool {
+    !table_prefix.starts_with(|c: char| c.is_ascii_digit())
+        && table_prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+const LOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(any(feature = "http", feature = "tcp"))]
+const LOCK_TTL: Duration = Duration::from_secs(600);
+
+/// Unique name of a migrations run, the lock rows of the instances are told apart by it
+#[cfg(any(feature = "http", feature = "tcp"))]
+fn lock_owner() -> String {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or_default();
+
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        nanos,
+        RUNS.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+/// Runs the ClickHouse migrations and tracks them in `__schema_migrations`.
+///
+/// The instances started at the same time take turns through `__schema_migrations_lock`: every
+/// run inserts its row there and the run with the oldest row executes the scripts, the others
+/// wait for it to delete the row and then find the migrations applied
+#[cfg(any(feature = "http", feature = "tcp"))]
+pub struct Migrations<'a> {
+    scripts: Scripts<'a>,
+    scripts_down: Scripts<'a>,
+    lock_poll_interval: Duration,
+    lock_ttl: Duration,
+    table_prefix: &'a str,
+}
+
+#[cfg(any(feature = "http", feature = "tcp"))]
+impl<'a> Migrations<'a> {
+    pub fn new(scripts: Scripts<'a>) -> Self {
+        Self {
+            scripts,
+            scripts_down: &[],
+            lock_poll_interval: LOCK_POLL_INTERVAL,
+            lock_ttl: LOCK_TTL,
+            table_prefix: "",
+        }
+    }
+
+    /// Registers the `(name, down.sql)` pairs used by [`Migrations::rollback_to`]
+    pub fn with_down(mut self, scripts_down: Scripts<'a>) -> Self {
+        self.scripts_down = scripts_down;
+        self
+    }
+
+    /// Sets how often a waiting run checks the lock and after how long the lock of a run which
+    /// died without releasing it is ignored
+    pub fn with_lock(mut self, poll_interval: Duration, ttl: Duration) -> Self {
+        self.lock_poll_interval = poll_interval;
+        self.lock_ttl = ttl;
+        self
+    }
+
+    /// Sets the prefix the tables of the scripts are created with. The versions are recorded
+    /// with the prefix too, the migrations of every prefix are tracked apart
+    pub fn with_table_prefix(mut self, table_prefix: &'a str) -> Self {
+        self.table_prefix = table_prefix;
+        self
+    }
+
+    /// The script with its tables named by the prefix
+    fn script(&self, script: &str) -> String {
+        script.replace(TABLE_PREFIX, self.table_prefix)
+    }
+
+    /// Version of the migration as it is recorded in `__schema_migrations`
+    fn recorded_version(&self, name: &str) -> String {
+        format!("{}{}", self.table_prefix, version(name))
+    }
+
+    async fn create_table<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+    ) -> Result<(), StorageError> {
+        log::debug!("creating migration table __schema_migrations");
+
+        #[cfg(feature = "on_ch_cluster")]
+        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations ON CLUSTER '{cluster}'
+            (
+                version String,
+                run_on DateTime('UTC')
+            ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+            ORDER BY (version)
+            SETTINGS index_granularity = 8192"#;
+
+        #[cfg(not(feature = "on_ch_cluster"))]
+        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations
+            (
+                version String,
+                run_on DateTime('UTC')
+            ) ENGINE = MergeTree()
+            ORDER BY (version)
+            SETTINGS index_granularity = 8192"#;
+
+        log::debug!("{}", &query);
+
+        storage.execute(query).await
+    }
+
+    async fn create_lock_table<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+    ) -> Result<(), StorageError> {
+        log::debug!("creating migration lock table __schema_migrations_lock");
+
+        #[cfg(feature = "on_ch_cluster")]
+        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations_lock ON CLUSTER '{cluster}'
+            (
+                owner String,
+                acquired_at DateTime64(6, 'UTC')
+            ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+            ORDER BY (acquired_at, owner)
+            TTL toDateTime(acquired_at) + INTERVAL 1 DAY
+            SETTINGS index_granularity = 8192"#;
+
+        #[cfg(not(feature = "on_ch_cluster"))]
+        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations_lock
+            (
+                owner String,
+                acquired_at DateTime64(6, 'UTC')
+            ) ENGINE = MergeTree()
+            ORDER BY (acquired_at, owner)
+            TTL toDateTime(acquired_at) + INTERVAL 1 DAY
+            SETTINGS index_granularity = 8192"#;
+
+        log::debug!("{}", &query);
+
+        storage.execute(query).await
+    }
+
+    async fn insert_lock<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        owner: &str,
+    ) -> Result<(), StorageError> {
+        let ddl = &format!(
+            "INSERT INTO __schema_migrations_lock (owner, acquired_at) VALUES ('{}', now64(6))",
+            owner
+        );
+        storage.execute(ddl).await
+    }
+
+    /// Waits until the row of `owner` is the oldest one in the lock table
+    async fn acquire_lock<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        owner: &str,
+    ) -> Result<(), StorageError> {
+        self.create_lock_table(storage).await?;
+        self.insert_lock(storage, owner).await?;
+
+        loop {
+            // The rows of the runs started at the same time have to land before the check
+            tokio::time::sleep(self.lock_poll_interval).await;
+
+            match storage
+                .migration_lock_holder(owner, self.lock_ttl.as_secs())
+                .await?
+            {
+                Some(holder) if holder == owner => return Ok(()),
+                Some(holder) => log::info!("waiting for migrations of {}", holder),
+                // The own row has expired while waiting
+                None => self.insert_lock(storage, owner).await?,
+            }
+        }
+    }
+
+    async fn release_lock<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        owner: &str,
+    ) -> Result<(), StorageError> {
+        #[cfg(feature = "on_ch_cluster")]
+        let on_cluster = " ON CLUSTER '{cluster}'";
+        #[cfg(not(feature = "on_ch_cluster"))]
+        let on_cluster = "";
+
+        // The next run checks the lock right after the row is gone
+        let ddl = &format!(
+            "ALTER TABLE __schema_migrations_lock{} DELETE WHERE owner = '{}' SETTINGS mutations_sync = 2",
+            on_cluster, owner
+        );
+        storage.execute(ddl).await
+    }
+
+    async fn insert_migration<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        version: &str,
+    ) -> Result<(), StorageError> {
+        let ddl = &format!(
+            "INSERT INTO __schema_migrations (version, run_on) VALUES ('{}', now())",
+            version
+        );
+        storage.execute(ddl).await
+    }
+
+    async fn delete_migration<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        version: &str,
+    ) -> Result<(), StorageError> {
+        #[cfg(feature = "on_ch_cluster")]
+        let on_cluster = " ON CLUSTER '{cluster}'";
+        #[cfg(not(feature = "on_ch_cluster"))]
+        let on_cluster = "";
+
+        // The mutation has to be finished before the next `migration_exists` check
+        let ddl = &format!(
+            "ALTER TABLE __schema_migrations{} DELETE WHERE version = '{}' SETTINGS mutations_sync = 2",
+            on_cluster, version
+        );
+        storage.execute(ddl).await
+    }
+
+    /// Returns the migrations `up` would run, in the order they would be run
+    pub async fn pending<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+    ) -> Result<Vec<(&'a str, &'a str)>, StorageError> {
+        if !storage.migrations_table_exists().await? {
+            return Ok(self.scripts.to_vec());
+        }
+
+        let mut pending = Vec::new();
+        for (name, script) in self.scripts {
+            let version = &self.recorded_version(name);
+            log::trace!("check if migration {} exists", version);
+            if !storage.migration_exists(version).await? {
+                pending.push((*name, *script));
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Returns the down scripts `rollback_to(target_version)` would run, latest migration first
+    pub async fn rollback_plan<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        target_version: &str,
+    ) -> Result<Vec<(&'a str, &'a str)>, StorageError> {
+        if !self
+            .scripts
+            .iter()
+            .any(|(name, _)| version(name) == target_version)
+        {
+            return Err(StorageError::UnknownMigration(target_version.to_string()));
+        }
+
+        if !storage.migrations_table_exists().await? {
+            return Ok(Vec::new());
+        }
+
+        let mut plan = Vec::new();
+        for (name, _) in self.scripts.iter().rev() {
+            if version(name).as_str() <= target_version
+                || !storage
+                    .migration_exists(&self.recorded_version(name))
+                    .await?
+            {
+                continue;
+            }
+
+            match self.scripts_down.iter().find(|(down, _)| down == name) {
+                Some((_, script)) => plan.push((*name, *script)),
+                None => return Err(StorageError::IrreversibleMigration(name.to_string())),
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Migrate all scripts up
+    pub async fn up<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+    ) -> Result<(), StorageError> {
+        log::info!("migrating up to __schema_migrations");
+        self.create_table(storage).await?;
+
+        let owner = lock_owner();
+        self.acquire_lock(storage, &owner).await?;
+
+        let result = self.run_up(storage).await;
+        let released = self.release_lock(storage, &owner).await;
+        result.and(released)
+    }
+
+    async fn run_up<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+    ) -> Result<(), StorageError> {
+        for (name, script) in self.pending(storage).await? {
+            log::debug!("run migration {}", name);
+            storage.execute(&self.script(script)).await?;
+            self.insert_migration(storage, &self.recorded_version(name))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Reverts every applied migration newer than `target_version`, latest one first
+    pub async fn rollback_to<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        target_version: &str,
+    ) -> Result<(), StorageError> {
+        log::info!("rolling back __schema_migrations to {}", target_version);
+
+        let owner = lock_owner();
+        self.acquire_lock(storage, &owner).await?;
+
+        let result = self.run_rollback(storage, target_version).await;
+        let released = self.release_lock(storage, &owner).await;
+        result.and(released)
+    }
+
+    async fn run_rollback<S: ClickhouseStorage + ?Sized>(
+        &self,
+        storage: &mut S,
+        target_version: &str,
+    ) -> Result<(), StorageError> {
+        for (name, script) in self.rollback_plan(storage, target_version).await? {
+            log::debug!("revert migration {}", name);
+            storage.execute(&self.script(script)).await?;
+            self.delete_migration(storage, &self.recorded_version(name))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub mod postgres {
+    use tokio_postgres::GenericClient;
+
+    use super::{version, Scripts};
+    use crate::StorageError;
+
+    /// Runs the PostgreSQL migrations and tracks them in `__diesel_schema_migrations`,
+    /// so the tables stay compatible with the diesel CLI
+    pub struct PostgresMigrations<'a> {
+        scripts: Scripts<'a>,
+    }
+
+    impl<'a> PostgresMigrations<'a> {
+        pub fn new(scripts: Scripts<'a>) -> Self {
+            Self { scripts }
+        }
+
+        async fn execute_script<C: GenericClient>(
+            &self,
+            client: &C,
+            content: &str,
+        ) -> Result<(), StorageError> {
+            let stmt = client.prepare(content).await?;
+            client.execute(&stmt, &[]).await?;
+            Ok(())
+        }
+
+        async fn insert_migration<C: GenericClient>(
+            &self,
+            client: &C,
+            version: &str,
+        ) -> Result<(), StorageError> {
+            let query = "INSERT INTO __diesel_schema_migrations (version) VALUES ($1)";
+            let stmt = client.prepare(query).await?;
+            client.execute(&stmt, &[&version]).await?;
+            Ok(())
+        }
+
+        async fn create_table<C: GenericClient>(&self, client: &C) -> Result<(), StorageError> {
+            log::debug!("creating migration table __diesel_schema_migrations",);
+            let query = r#"CREATE TABLE IF NOT EXISTS __diesel_schema_migrations ( 
+                version VARCHAR(50) PRIMARY KEY NOT NULL,
+                run_on TIMESTAMP NOT NULL DEFAULT current_timestamp
+            )"#;
+            self.execute_script(client, query).await
+        }
+
+        async fn exists<C: GenericClient>(
+            &self,
+            client: &C,
+            version: &str,
+        ) -> Result<bool, StorageError> {
+            log::trace!("check if migration {} exists", version);
+            let query = "SELECT COUNT(*) FROM __diesel_schema_migrations WHERE version = $1";
+            let stmt = client.prepare(query).await?;
+            let row = client.query_one(&stmt, &[&version]).await?;
+            let count: i64 = row.get(0);
+
+            Ok(count > 0)
+        }
+
+        /// Migrate all scripts up
+        pub async fn up<C: GenericClient>(&self, client: &mut C) -> Result<(), StorageError> {
+            log::info!("migrating up to __diesel_schema_migrations");
+            self.create_table(client).await?;
+            for (name, script) in self.scripts {
+                let version = &version(name);
+                if !self.exists(client, version).await? {
+                    log::debug!("run migration {}", name);
+                    self.execute_script(client, script).await?;
+                    self.insert_migration(client, version).await?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "http", feature = "tcp", feature = "postgres")))]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    use std::sync::{Arc, Mutex};
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    const SCRIPTS: Scripts = &[
+        ("00000000000000_initial_setup", "CREATE TABLE instructions"),
+        ("00000000000001_initial_setup", "CREATE TABLE balances"),
+        ("00000000000002_blocks_setup", "CREATE TABLE blocks"),
+    ];
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    const SCRIPTS_DOWN: Scripts = &[
+        ("00000000000001_initial_setup", "DROP TABLE balances"),
+        ("00000000000002_blocks_setup", "DROP TABLE blocks"),
+    ];
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    struct MockStorage {
+        table_exists: bool,
+        applied: Vec<String>,
+        executed: Vec<String>,
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    impl MockStorage {
+        fn new(applied: &[&str]) -> Self {
+            Self {
+                table_exists: true,
+                applied: applied.iter().map(|version| version.to_string()).collect(),
+                executed: Vec::new(),
+            }
+        }
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[async_trait::async_trait]
+    impl ClickhouseStorage for MockStorage {
+        async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+            // The lock is checked by `concurrent_runs_execute_scripts_once`
+            if !ddl.contains("__schema_migrations_lock") {
+                self.executed.push(ddl.to_string());
+            }
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(self.table_exists)
+        }
+
+        async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+            Ok(self.applied.iter().any(|applied| applied == version))
+        }
+    }
+
+    /// The tables of the instances sharing one ClickHouse
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[derive(Default)]
+    struct SharedTables {
+        applied: Vec<String>,
+        lock: Vec<String>,
+        executed: Vec<String>,
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    struct SharedStorage(Arc<Mutex<SharedTables>>);
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[async_trait::async_trait]
+    impl ClickhouseStorage for SharedStorage {
+        async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+            let mut tables = self.0.lock().unwrap();
+            let quoted = || ddl.rsplit('\'').nth(1).unwrap().to_string();
+
+            if ddl.starts_with("CREATE TABLE IF NOT EXISTS __schema_migrations") {
+                return Ok(());
+            } else if ddl.starts_with("INSERT INTO __schema_migrations_lock") {
+                tables.lock.push(quoted());
+            } else if ddl.starts_with("ALTER TABLE __schema_migrations_lock") {
+                let owner = quoted();
+                tables.lock.retain(|holder| *holder != owner);
+            } else if ddl.starts_with("INSERT INTO __schema_migrations") {
+                tables.applied.push(quoted());
+            } else {
+                tables.executed.push(ddl.to_string());
+            }
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+            let tables = self.0.lock().unwrap();
+            Ok(tables.applied.iter().any(|applied| applied == version))
+        }
+
+        async fn migration_lock_holder(
+            &mut self,
+            _owner: &str,
+            _ttl_secs: u64,
+        ) -> Result<Option<String>, StorageError> {
+            Ok(self.0.lock().unwrap().lock.first().cloned())
+        }
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    fn names<'a>(plan: Vec<(&'a str, &'a str)>) -> Vec<&'a str> {
+        plan.into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn version_from_name() {
+        assert_eq!(
+            version("2022-12-18-013028_create_table_epochs"),
+            "20221218013028"
+        );
+        assert_eq!(version("00000000000007_blocks_setup"), "00000000000007");
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[tokio::test]
+    async fn pending_migrations() {
+        let migrations = Migrations::new(SCRIPTS);
+
+        let mut fresh = MockStorage::new(&[]);
+        fresh.table_exists = false;
+        assert_eq!(
+            names(migrations.pending(&mut fresh).await.unwrap()).len(),
+            3
+        );
+
+        let mut partial = MockStorage::new(&["00000000000000", "00000000000002"]);
+        assert_eq!(
+            names(migrations.pending(&mut partial).await.unwrap()),
+            vec!["00000000000001_initial_setup"]
+        );
+
+        let mut up_to_date =
+            MockStorage::new(&["00000000000000", "00000000000001", "00000000000002"]);
+        assert!(migrations
+            .pending(&mut up_to_date)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Planning must not touch the storage
+        assert!(fresh.executed.is_empty() && partial.executed.is_empty());
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[tokio::test]
+    async fn rollback_migrations() {
+        let migrations = Migrations::new(SCRIPTS)
+            .with_down(SCRIPTS_DOWN)
+            .with_lock(Duration::from_millis(1), LOCK_TTL);
+
+        let mut storage = MockStorage::new(&["00000000000000", "00000000000001", "00000000000002"]);
+        assert_eq!(
+            names(
+                migrations
+                    .rollback_plan(&mut storage, "00000000000000")
+                    .await
+                    .unwrap()
+            ),
+            vec![
+                "00000000000002_blocks_setup",
+                "00000000000001_initial_setup"
+            ]
+        );
+
+        // Not applied migrations are skipped
+        let mut storage = MockStorage::new(&["00000000000000", "00000000000001"]);
+        migrations
+            .rollback_to(&mut storage, "00000000000000")
+            .await
+            .unwrap();
+        assert_eq!(storage.executed[0], "DROP TABLE balances");
+        assert!(storage.executed[1].contains("DELETE WHERE version = '00000000000001'"));
+        assert_eq!(storage.executed.len(), 2);
+
+        assert!(matches!(
+            migrations
+                .rollback_plan(&mut storage, "00000000000009")
+                .await,
+            Err(StorageError::UnknownMigration(_))
+        ));
+
+        let mut storage = MockStorage::new(&["00000000000000", "00000000000001"]);
+        assert!(matches!(
+            Migrations::new(SCRIPTS)
+                .rollback_plan(&mut storage, "00000000000000")
+                .await,
+            Err(StorageError::IrreversibleMigration(_))
+        ));
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[tokio::test]
+    async fn prefixed_migrations() {
+        const PREFIXED: Scripts = &[
+            (
+                "00000000000000_initial_setup",
+                "CREATE TABLE {table_prefix}instructions",
+            ),
+            (
+                "00000000000001_initial_setup",
+                "CREATE TABLE {table_prefix}balances",
+            ),
+        ];
+        let migrations = Migrations::new(PREFIXED)
+            .with_table_prefix("staging_")
+            .with_lock(Duration::from_millis(1), LOCK_TTL);
+
+        // The migrations of the bare tables don't count for the prefixed ones
+        let mut storage =
+            MockStorage::new(&["00000000000000", "00000000000001", "staging_00000000000000"]);
+        migrations.up(&mut storage).await.unwrap();
+
+        assert_eq!(
+            storage.executed[1..],
+            [
+                "CREATE TABLE staging_balances".to_string(),
+                "INSERT INTO __schema_migrations (version, run_on) \
+                VALUES ('staging_00000000000001', now())"
+                    .to_string()
+            ]
+        );
+        assert_eq!(storage.executed.len(), 3);
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[test]
+    fn table_prefixes() {
+        assert!(is_valid_table_prefix(""));
+        assert!(is_valid_table_prefix("staging_"));
+        assert!(is_valid_table_prefix("_Tenant2_"));
+
+        assert!(!is_valid_table_prefix("2staging_"));
+        assert!(!is_valid_table_prefix("staging-"));
+        assert!(!is_valid_table_prefix("db.staging_"));
+        assert!(!is_valid_table_prefix(
+            "staging_`; DROP TABLE instructions; --"
+        ));
+        assert!(!is_valid_table_prefix("stäging_"));
+    }
+
+    #[cfg(any(feature = "http", feature = "tcp"))]
+    #[tokio::test]
+    async fn concurrent_runs_execute_scripts_once() {
+        let tables = Arc::new(Mutex::new(SharedTables::default()));
+        let mut first = SharedStorage(tables.clone());
+        let mut second = SharedStorage(tables.clone());
+
+        let migrations = Migrations::new(SCRIPTS).with_lock(Duration::from_millis(10), LOCK_TTL);
+        let (first, second) = tokio::join!(migrations.up(&mut first), migrations.up(&mut second));
+        first.unwrap();
+        second.unwrap();
+
+        let tables = tables.lock().unwrap();
+        assert_eq!(
+            tables.executed,
+            vec![
+                "CREATE TABLE instructions",
+                "CREATE TABLE balances",
+                "CREATE TABLE blocks"
+            ]
+        );
+        assert_eq!(tables.applied.len(), 3);
+        assert!(tables.lock.is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+//! Retention of the rows of a table by its table TTL. The days are taken from the config of a
+//! service, not from a migration script, so at every start the configured TTL is compared with
+//! the one of the live table and the table is altered only when they differ.
+//!
+//! ClickHouse rewrites `INTERVAL 90 DAY` as `toIntervalDay(90)` in `system.tables`, the TTL is
+//! generated in that form to compare equal to what it reads back
+
+use crate::{storage::ClickhouseStorage, StorageError};
+
+/// A table whose rows are deleted `days` days after the time in `column`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRetention {
+    pub table: &'static str,
+    /// `DateTime` column the age of a row is counted from
+    pub column: &'static str,
+    /// 0 keeps the rows forever
+    pub days: u32,
+}
+
+impl TableRetention {
+    /// The TTL expression of the table, None when the rows are kept forever
+    pub fn ttl(&self) -> Option<String> {
+        (self.days > 0).then(|| format!("{} + toIntervalDay({})", self.column, self.days))
+    }
+
+    /// `ALTER TABLE` setting the TTL of the table named with `table_prefix` or removing it
+    pub fn ddl(&self, table_prefix: &str) -> String {
+        #[cfg(feature = "on_ch_cluster")]
+        let on_cluster = " ON CLUSTER '{cluster}'";
+        #[cfg(not(feature = "on_ch_cluster"))]
+        let on_cluster = "";
+
+        match self.ttl() {
+            Some(ttl) => format!(
+                "ALTER TABLE {}{}{} MODIFY TTL {}",
+                table_prefix, self.table, on_cluster, ttl
+            ),
+            None => format!(
+                "ALTER TABLE {}{}{} REMOVE TTL",
+                table_prefix, self.table, on_cluster
+            ),
+        }
+    }
+}
+
+/// The table TTL of `engine_full`, None for a table without one
+pub fn live_ttl(engine_full: &str) -> Option<&str> {
+    let (_, ttl) = engine_full.split_once(" TTL ")?;
+    let ttl = ttl.split_once(" SETTINGS ").map_or(ttl, |(ttl, _)| ttl);
+
+    Some(ttl.trim())
+}
+
+/// Sets the configured TTL of every table named with `table_prefix` whose live TTL differs from
+/// it, the tables of a storage which doesn't report their engine are skipped
+pub async fn apply_retention<S: ClickhouseStorage + ?Sized>(
+    storage: &mut S,
+    tables: &[TableRetention],
+    table_prefix: &str,
+) -> Result<(), StorageError> {
+    for table in tables {
+        let name = format!("{}{}", table_prefix, table.table);
+        let Some(engine_full) = storage.table_engine(&name).await? else {
+            continue;
+        };
+
+        let live = live_ttl(&engine_full);
+        let configured = table.ttl();
+
+        if live == configured.as_deref() {
+            log::debug!("TTL of {} is up to date", name);
+            continue;
+        }
+
+        log::warn!(
+            "TTL of {} is {}, the configured one is {}",
+            name,
+            live.unwrap_or("not set"),
+            configured.as_deref().unwrap_or("not set")
+        );
+
+        storage.execute(&table.ddl(table_prefix)).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct MockStorage {
+        engines: HashMap<&'static str, String>,
+        executed: Vec<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl ClickhouseStorage for MockStorage {
+        async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+            self.executed.push(ddl.to_string());
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn table_engine(&mut self, table: &str) -> Result<Option<String>, StorageError> {
+            Ok(self.engines.get(table).cloned())
+        }
+    }
+
+    fn retention(table: &'static str, days: u32) -> TableRetention {
+        TableRetention {
+            table,
+            column: "inserted_at",
+            days,
+        }
+    }
+
+    #[test]
+    fn ttl_clause() {
+        assert_eq!(
+            retention("instruction_arguments", 90).ttl().as_deref(),
+            Some("inserted_at + toIntervalDay(90)")
+        );
+        assert_eq!(retention("instructions", 0).ttl(), None);
+    }
+
+    #[cfg(not(feature = "on_ch_cluster"))]
+    #[test]
+    fn ttl_ddl() {
+        assert_eq!(
+            retention("instruction_arguments", 90).ddl(""),
+            "ALTER TABLE instruction_arguments MODIFY TTL inserted_at + toIntervalDay(90)"
+        );
+        assert_eq!(
+            retention("instructions", 0).ddl(""),
+            "ALTER TABLE instructions REMOVE TTL"
+        );
+        assert_eq!(
+            retention("instructions", 0).ddl("staging_"),
+            "ALTER TABLE staging_instructions REMOVE TTL"
+        );
+    }
+
+    #[cfg(feature = "on_ch_cluster")]
+    #[test]
+    fn ttl_ddl() {
+        assert_eq!(
+            retention("instruction_arguments", 90).ddl(""),
+            "ALTER TABLE instruction_arguments ON CLUSTER '{cluster}' \
+            MODIFY TTL inserted_at + toIntervalDay(90)"
+        );
+        assert_eq!(
+            retention("instructions", 0).ddl(""),
+            "ALTER TABLE instructions ON CLUSTER '{cluster}' REMOVE TTL"
+        );
+        assert_eq!(
+            retention("instructions", 0).ddl("staging_"),
+            "ALTER TABLE staging_instructions ON CLUSTER '{cluster}' REMOVE TTL"
+        );
+    }
+
+    #[test]
+    fn ttl_of_engine_full() {
+        assert_eq!(
+            live_ttl(
+                "MergeTree ORDER BY (tx_signature, program) \
+                TTL inserted_at + toIntervalDay(90) SETTINGS index_granularity = 8192"
+            ),
+            Some("inserted_at + toIntervalDay(90)")
+        );
+        assert_eq!(
+            live_ttl(
+                "MergeTree ORDER BY (acquired_at, owner) \
+                TTL toDateTime(acquired_at) + toIntervalDay(1)"
+            ),
+            Some("toDateTime(acquired_at) + toIntervalDay(1)")
+        );
+        assert_eq!(
+            live_ttl(
+                "MergeTree ORDER BY (tx_signature, program) SETTINGS index_granularity = 8192"
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn only_differing_ttls_are_altered() {
+        let mut storage = MockStorage {
+            engines: HashMap::from([
+                (
+                    "instruction_arguments",
+                    "MergeTree ORDER BY (tx_signature, program) \
+                    TTL inserted_at + toIntervalDay(30) SETTINGS index_granularity = 8192"
+                        .to_string(),
+                ),
+                (
+                    "instructions",
+                    "MergeTree ORDER BY (program, slot, tx_signature) \
+                    SETTINGS index_granularity = 8192"
+                        .to_string(),
+                ),
+                (
+                    "balances",
+                    "MergeTree ORDER BY (tx_signature, account) \
+                    TTL inserted_at + toIntervalDay(7) SETTINGS index_granularity = 8192"
+                        .to_string(),
+                ),
+            ]),
+            executed: Vec::new(),
+        };
+        let tables = [
+            retention("instruction_arguments", 90),
+            // Kept forever and has no TTL
+            retention("instructions", 0),
+            retention("balances", 0),
+            // Not reported by the storage
+            retention("token_transfers", 30),
+        ];
+
+        apply_retention(&mut storage, &tables, "").await.unwrap();
+
+        assert_eq!(storage.executed, vec![tables[0].ddl(""), tables[2].ddl("")]);
+    }
+
+    #[tokio::test]
+    async fn matching_ttl_is_not_altered() {
+        let mut storage = MockStorage {
+            engines: HashMap::from([(
+                "instruction_arguments",
+                "MergeTree ORDER BY (tx_signature, program) \
+                TTL inserted_at + toIntervalDay(90) SETTINGS index_granularity = 8192"
+                    .to_string(),
+            )]),
+            executed: Vec::new(),
+        };
+
+        apply_retention(&mut storage, &[retention("instruction_arguments", 90)], "")
+            .await
+            .unwrap();
+
+        assert!(storage.executed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn prefixed_tables_are_altered() {
+        let mut storage = MockStorage {
+            engines: HashMap::from([
+                (
+                    "instruction_arguments",
+                    "MergeTree ORDER BY (tx_signature, program) \
+                    SETTINGS index_granularity = 8192"
+                        .to_string(),
+                ),
+                (
+                    "staging_instruction_arguments",
+                    "MergeTree ORDER BY (tx_signature, program) \
+                    SETTINGS index_granularity = 8192"
+                        .to_string(),
+                ),
+            ]),
+            executed: Vec::new(),
+        };
+        let tables = [retention("instruction_arguments", 90)];
+
+        apply_retention(&mut storage, &tables, "staging_")
+            .await
+            .unwrap();
+
+        assert_eq!(storage.executed, vec![tables[0].ddl("staging_")]);
+        assert!(storage.executed[0].starts_with("ALTER TABLE staging_instruction_arguments"));
+    }
+}
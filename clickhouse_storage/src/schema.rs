@@ -0,0 +1,327 @@
+//! Startup check of the tables a service inserts into against the rows it writes. A table
+//! lacking a column of a row fails every insert into it, so the service stops right after the
+//! migrations instead of hours later, on the first insert.
+//!
+//! The inserts name their columns, so only the names are compared, not their order
+
+use std::fmt;
+
+use crate::{storage::ClickhouseStorage, StorageError};
+
+/// A column of a table as `DESCRIBE TABLE` lists it
+#[cfg_attr(feature = "http", derive(clickhouse::Row, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableColumn {
+    pub name: String,
+    /// `DEFAULT`, `MATERIALIZED`, `ALIAS`, `EPHEMERAL` or empty for a column without one
+    pub default_type: String,
+}
+
+impl TableColumn {
+    /// MATERIALIZED and ALIAS columns are computed by ClickHouse and can't be inserted
+    fn is_insertable(&self) -> bool {
+        !matches!(self.default_type.as_str(), "MATERIALIZED" | "ALIAS")
+    }
+}
+
+/// A table and the columns of the row a service writes to it, the names of the row fields
+pub struct WrittenTable {
+    pub table: &'static str,
+    pub columns: &'static [&'static str],
+}
+
+impl WrittenTable {
+    /// The table of a row with the columns generated from its fields
+    pub const fn of<T: ClickhouseTable>() -> Self {
+        Self {
+            table: T::TABLE,
+            columns: T::COLUMNS,
+        }
+    }
+}
+
+/// A table generated from the fields of the row written to it, implemented with
+/// `#[derive(ClickhouseTable)]`. The scripts name the table with the `{table_prefix}` placeholder
+/// as the migrations do
+pub trait ClickhouseTable {
+    const TABLE: &'static str;
+    /// Names of the columns in the order of the fields
+    const COLUMNS: &'static [&'static str];
+    /// ClickHouse types of the `COLUMNS`
+    const COLUMN_TYPES: &'static [&'static str];
+    /// `CREATE TABLE IF NOT EXISTS` of the table on a single server
+    const CREATE_TABLE: &'static str;
+    /// `CREATE TABLE IF NOT EXISTS` of the table replicated `ON CLUSTER '{cluster}'`
+    const CREATE_TABLE_ON_CLUSTER: &'static str;
+}
+
+/// Whether both lists name the same columns in the same order. It's a `const fn`, so a service
+/// asserts at compile time that the columns it generates are the ones its rows are written with
+pub const fn same_columns(left: &[&str], right: &[&str]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let mut idx = 0;
+    while idx < left.len() {
+        let (left, right) = (left[idx].as_bytes(), right[idx].as_bytes());
+        if left.len() != right.len() {
+            return false;
+        }
+
+        let mut byte = 0;
+        while byte < left.len() {
+            if left[byte] != right[byte] {
+                return false;
+            }
+            byte += 1;
+        }
+        idx += 1;
+    }
+
+    true
+}
+
+/// Difference between a table and the row written to it
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableDiff {
+    pub table: String,
+    /// Columns of the row the table lacks, the inserts fail on them
+    pub missing: Vec<String>,
+    /// Columns of the table without a default the row lacks, they would be filled with zeroes
+    pub unwritten: Vec<String>,
+}
+
+impl TableDiff {
+    pub fn new(table: &WrittenTable, columns: &[TableColumn]) -> Option<Self> {
+        let missing: Vec<String> = table
+            .columns
+            .iter()
+            .filter(|name| {
+                !columns
+                    .iter()
+                    .any(|column| column.name == **name && column.is_insertable())
+            })
+            .map(|name| name.to_string())
+            .collect();
+
+        let unwritten: Vec<String> = columns
+            .iter()
+            .filter(|column| column.default_type.is_empty())
+            .filter(|column| !table.columns.contains(&column.name.as_str()))
+            .map(|column| column.name.clone())
+            .collect();
+
+        if missing.is_empty() && unwritten.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            table: table.table.to_string(),
+            missing,
+            unwritten,
+        })
+    }
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.table)?;
+
+        for column in &self.missing {
+            write!(
+                f,
+                "\n  + {} (written by the service, missing in the table)",
+                column
+            )?;
+        }
+        for column in &self.unwritten {
+            write!(
+                f,
+                "\n  - {} (in the table without a default, not written)",
+                column
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares every table named with `table_prefix` with the row written to it, the tables of a
+/// storage which doesn't describe them are skipped
+pub async fn check_schema<S: ClickhouseStorage + ?Sized>(
+    storage: &mut S,
+    tables: &[WrittenTable],
+    table_prefix: &str,
+) -> Result<(), StorageError> {
+    let mut diffs = Vec::new();
+
+    for table in tables {
+        let name = format!("{}{}", table_prefix, table.table);
+        let Some(columns) = storage.describe_table(&name).await? else {
+            continue;
+        };
+
+        diffs.extend(TableDiff::new(table, &columns).map(|diff| TableDiff {
+            table: name,
+            ..diff
+        }));
+    }
+
+    if diffs.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::SchemaMismatch(diffs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct MockStorage {
+        tables: HashMap<&'static str, Vec<TableColumn>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ClickhouseStorage for MockStorage {
+        async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn describe_table(
+            &mut self,
+            table: &str,
+        ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+            Ok(self.tables.get(table).cloned())
+        }
+    }
+
+    fn column(name: &str, default_type: &str) -> TableColumn {
+        TableColumn {
+            name: name.to_string(),
+            default_type: default_type.to_string(),
+        }
+    }
+
+    const TABLES: &[WrittenTable] = &[
+        WrittenTable {
+            table: "instructions",
+            columns: &["program", "tx_signature", "tx_version", "data"],
+        },
+        WrittenTable {
+            table: "blocks",
+            columns: &["slot", "blockhash"],
+        },
+    ];
+
+    #[tokio::test]
+    async fn matching_tables() {
+        let mut storage = MockStorage {
+            tables: HashMap::from([
+                (
+                    "instructions",
+                    vec![
+                        column("program", ""),
+                        column("tx_signature", ""),
+                        // The order of the columns doesn't matter
+                        column("data", ""),
+                        column("tx_version", ""),
+                        column("account_roles", "DEFAULT"),
+                        column("raw_instruction_idx", "MATERIALIZED"),
+                    ],
+                ),
+                ("blocks", vec![column("slot", ""), column("blockhash", "")]),
+            ]),
+        };
+
+        assert!(check_schema(&mut storage, TABLES, "").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn mismatched_tables() {
+        let mut storage = MockStorage {
+            tables: HashMap::from([
+                (
+                    "instructions",
+                    vec![
+                        column("program", ""),
+                        column("tx_signature", ""),
+                        column("data", "MATERIALIZED"),
+                        column("num_signatures", ""),
+                    ],
+                ),
+                ("blocks", vec![column("slot", ""), column("blockhash", "")]),
+            ]),
+        };
+
+        let err = check_schema(&mut storage, TABLES, "").await.unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Tables don't match the rows written to them:\n\
+            instructions:\n  \
+            + tx_version (written by the service, missing in the table)\n  \
+            + data (written by the service, missing in the table)\n  \
+            - num_signatures (in the table without a default, not written)"
+        );
+    }
+
+    #[tokio::test]
+    async fn prefixed_tables() {
+        let mut storage = MockStorage {
+            tables: HashMap::from([
+                (
+                    "staging_instructions",
+                    vec![column("program", ""), column("tx_signature", "")],
+                ),
+                (
+                    "staging_blocks",
+                    vec![column("slot", ""), column("blockhash", "")],
+                ),
+            ]),
+        };
+
+        let err = check_schema(&mut storage, TABLES, "staging_")
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Tables don't match the rows written to them:\n\
+            staging_instructions:\n  \
+            + tx_version (written by the service, missing in the table)\n  \
+            + data (written by the service, missing in the table)"
+        );
+    }
+
+    #[test]
+    fn columns_are_compared_in_order() {
+        assert!(same_columns(&["slot", "blockhash"], &["slot", "blockhash"]));
+        assert!(!same_columns(
+            &["slot", "blockhash"],
+            &["blockhash", "slot"]
+        ));
+        assert!(!same_columns(&["slot", "blockhash"], &["slot", "block"]));
+        assert!(!same_columns(&["slot"], &["slot", "blockhash"]));
+    }
+
+    #[tokio::test]
+    async fn undescribed_tables_are_skipped() {
+        let mut storage = MockStorage {
+            tables: HashMap::new(),
+        };
+
+        assert!(check_schema(&mut storage, TABLES, "").await.is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+use async_trait::async_trait;
+
+use crate::{schema::TableColumn, StorageError};
+
+/// The part of a service storage the migrations runner relies on
+#[async_trait]
+pub trait ClickhouseStorage: Send {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError>;
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError>;
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError>;
+
+    /// Owner of the oldest migrations lock taken within the last `ttl_secs` seconds, `owner` is
+    /// the instance asking. A storage which is not shared between the instances hands the lock
+    /// to the one asking right away
+    async fn migration_lock_holder(
+        &mut self,
+        owner: &str,
+        _ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        Ok(Some(owner.to_string()))
+    }
+
+    /// Columns of the `table` as `DESCRIBE TABLE` lists them. A storage which is not
+    /// ClickHouse returns None, it has no tables to check
+    async fn describe_table(
+        &mut self,
+        _table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        Ok(None)
+    }
+
+    /// `engine_full` of the `table` in `system.tables`, the engine with its clauses. A storage
+    /// which is not ClickHouse returns None, it has no table TTL to compare
+    async fn table_engine(&mut self, _table: &str) -> Result<Option<String>, StorageError> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "http")]
+pub mod http {
+    use clickhouse::Client;
+
+    use crate::{schema::TableColumn, StorageError};
+
+    pub async fn execute(client: &Client, ddl: &str) -> Result<(), StorageError> {
+        client.query(ddl).execute().await?;
+        Ok(())
+    }
+
+    pub async fn migrations_table_exists(client: &Client) -> Result<bool, StorageError> {
+        let mut cursor = client
+            .query("EXISTS TABLE __schema_migrations")
+            .fetch::<u8>()?;
+
+        Ok(cursor.next().await? == Some(1))
+    }
+
+    pub async fn migration_exists(client: &Client, version: &str) -> Result<bool, StorageError> {
+        let mut cursor = client
+            .query("SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = ?")
+            .bind(version)
+            .fetch::<u64>()?;
+
+        if let Some(count) = cursor.next().await? {
+            Ok(count > 0)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn migration_lock_holder(
+        client: &Client,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        let mut cursor = client
+            .query(
+                "SELECT owner FROM __schema_migrations_lock \
+                WHERE acquired_at > now64(6) - INTERVAL ? SECOND \
+                ORDER BY acquired_at, owner LIMIT 1",
+            )
+            .bind(ttl_secs)
+            .fetch::<String>()?;
+
+        Ok(cursor.next().await?)
+    }
+
+    pub async fn describe_table(
+        client: &Client,
+        table: &str,
+    ) -> Result<Vec<TableColumn>, StorageError> {
+        let mut cursor = client
+            .query(&format!(
+                "SELECT name, default_type FROM (DESCRIBE TABLE {})",
+                table
+            ))
+            .fetch::<TableColumn>()?;
+
+        let mut columns = Vec::new();
+
+        while let Some(column) = cursor.next().await? {
+            columns.push(column);
+        }
+
+        Ok(columns)
+    }
+
+    pub async fn table_engine(client: &Client, table: &str) -> Result<String, StorageError> {
+        let mut cursor = client
+            .query(
+                "SELECT engine_full FROM system.tables \
+                WHERE database = currentDatabase() AND name = ?",
+            )
+            .bind(table)
+            .fetch::<String>()?;
+
+        Ok(cursor.next().await?.unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "tcp")]
+pub mod tcp {
+    use clickhouse_rs::ClientHandle;
+
+    use crate::{schema::TableColumn, StorageError};
+
+    pub async fn execute(client: &mut ClientHandle, ddl: &str) -> Result<(), StorageError> {
+        client.execute(ddl).await?;
+        Ok(())
+    }
+
+    pub async fn migrations_table_exists(client: &mut ClientHandle) -> Result<bool, StorageError> {
+        let block = client
+            .query("EXISTS TABLE __schema_migrations")
+            .fetch_all()
+            .await?;
+
+        if let Some(row) = block.rows().next() {
+            let result: u8 = row.get("result")?;
+            Ok(result == 1)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn migration_exists(
+        client: &mut ClientHandle,
+        version: &str,
+    ) -> Result<bool, StorageError> {
+        let query = &format!(
+            "SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = '{}'",
+            version
+        );
+
+        let block = client.query(query).fetch_all().await?;
+
+        if let Some(row) = block.rows().next() {
+            let count: u64 = row.get("count")?;
+            Ok(count > 0)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub async fn migration_lock_holder(
+        client: &mut ClientHandle,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        let query = &format!(
+            "SELECT owner FROM __schema_migrations_lock \
+            WHERE acquired_at > now64(6) - INTERVAL {} SECOND \
+            ORDER BY acquired_at, owner LIMIT 1",
+            ttl_secs
+        );
+
+        let block = client.query(query).fetch_all().await?;
+
+        if let Some(row) = block.rows().next() {
+            let owner: String = row.get("owner")?;
+            Ok(Some(owner))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn describe_table(
+        client: &mut ClientHandle,
+        table: &str,
+    ) -> Result<Vec<TableColumn>, StorageError> {
+        let query = &format!("DESCRIBE TABLE {}", table);
+
+        let block = client.query(query).fetch_all().await?;
+
+        let mut columns = Vec::new();
+
+        for row in block.rows() {
+            columns.push(TableColumn {
+                name: row.get("name")?,
+                default_type: row.get("default_type")?,
+            });
+        }
+
+        Ok(columns)
+    }
+
+    pub async fn table_engine(
+        client: &mut ClientHandle,
+        table: &str,
+    ) -> Result<String, StorageError> {
+        let query = &format!(
+            "SELECT engine_full FROM system.tables \
+            WHERE database = currentDatabase() AND name = '{}'",
+            table
+        );
+
+        let block = client.query(query).fetch_all().await?;
+
+        if let Some(row) = block.rows().next() {
+            let engine_full: String = row.get("engine_full")?;
+            Ok(engine_full)
+        } else {
+            Ok(String::new())
+        }
+    }
+}
@@ -1,29 +1,64 @@
+use super::decoding_coverage::DecodingCoverage;
 use super::main_storage_manager::MainStorageManagerHandle;
-use super::transaction_parser::{Delegations, Undelegations};
+use super::transaction_parser::program_name;
+use crate::backpressure::InFlightBatches;
+use crate::configuration::{CollectorConfig, MainStorageConfig, ProgramsFilter};
 use crate::metrics_update;
-use crate::storages::main_storage::{Balance, Delegation, InstructionArgument};
+use crate::signature_tracing::{SignatureTracer, TraceEvent};
+use crate::storages::main_storage::bisect::{bisect, BisectionLimits, TxRow};
+use crate::storages::main_storage::row_buffer::{RowBuffer, RowSize};
+use crate::storages::main_storage::{
+    AnchorEvent, Balance, BatchMarker, BidEvent, CandyMint, ClaimEvent, CommissionChange,
+    Delegation, DelegationsDaily, EntanglerSwap, InstructionArgument, NftEvent, PackEvent,
+    SaleEvent, SolTransfer, StakeAccountEvent, TokenTransfer,
+};
 use crate::{register::Register, storages::main_storage::Instruction};
 use anyhow::Result;
-use log::{error, info};
+use log::{debug, error, info};
 use macros::{ActorInstance, HandleInstance};
-use std::time::Duration;
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::sleep;
 
-const BUFFER_SIZE: usize = 100_000;
-const FLUSH_BUFFER_TIMEOUT: u64 = 3000;
-
 #[derive(ActorInstance)]
 struct Collector {
-    instructions: Vec<Instruction>,
-    balances: Vec<Balance>,
-    instruction_arguments: Vec<InstructionArgument>,
-    delegations: Vec<Delegation>,
-    undelegations: Vec<Delegation>,
+    instructions: RowBuffer<Instruction>,
+    balances: RowBuffer<Balance>,
+    instruction_arguments: RowBuffer<InstructionArgument>,
+    delegations: RowBuffer<Delegation>,
+    undelegations: RowBuffer<Delegation>,
+    token_transfers: RowBuffer<TokenTransfer>,
+    nft_events: RowBuffer<NftEvent>,
+    commission_changes: RowBuffer<CommissionChange>,
+    claim_events: RowBuffer<ClaimEvent>,
+    entangler_swaps: RowBuffer<EntanglerSwap>,
+    anchor_events: RowBuffer<AnchorEvent>,
+    sale_events: RowBuffer<SaleEvent>,
+    pack_events: RowBuffer<PackEvent>,
+    candy_mints: RowBuffer<CandyMint>,
+    stake_account_events: RowBuffer<StakeAccountEvent>,
+    bid_events: RowBuffer<BidEvent>,
+    sol_transfers: RowBuffer<SolTransfer>,
+    programs_filter: ProgramsFilter,
+    decoding_coverage: DecodingCoverage,
+    /// Rows a buffer is flushed at, the rest is flushed by ticks
+    max_block_rows: usize,
+    /// Approximate bytes of all the buffers at which they are flushed
+    max_buffer_bytes: usize,
+    /// Bounds of the bisection of a block which failed to be inserted
+    bisection_limits: BisectionLimits,
     main_storage_manager: MainStorageManagerHandle,
+    tracer: SignatureTracer,
+    in_flight_batches: InFlightBatches,
+    /// Ended batches whose rows may be buffered along with the senders of their completion, they
+    /// are stored as complete and given back once all the buffers are flushed
+    ended_batches: Vec<(BatchMarker, oneshot::Sender<()>)>,
+    /// Id of the last begun batch
+    last_batch_id: u64,
     receiver: mpsc::Receiver<CollectorMessage>,
     tick_receiver: mpsc::Receiver<()>,
-    ticks: u8,
 }
 
 enum CollectorMessage {
@@ -47,52 +82,147 @@ enum CollectorMessage {
         undelegation: Delegation,
         respond_to: oneshot::Sender<()>,
     },
+    SaveTokenTransfer {
+        token_transfer: TokenTransfer,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveNftEvent {
+        nft_event: NftEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveCommissionChange {
+        commission_change: CommissionChange,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveClaimEvent {
+        claim_event: ClaimEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveEntanglerSwap {
+        entangler_swap: EntanglerSwap,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveAnchorEvent {
+        anchor_event: AnchorEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveSaleEvent {
+        sale_event: SaleEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SavePackEvent {
+        pack_event: PackEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveCandyMint {
+        candy_mint: CandyMint,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveStakeAccountEvent {
+        stake_account_event: StakeAccountEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveBidEvent {
+        bid_event: BidEvent,
+        respond_to: oneshot::Sender<()>,
+    },
+    SaveSolTransfer {
+        sol_transfer: SolTransfer,
+        respond_to: oneshot::Sender<()>,
+    },
+    BeginBatch {
+        min_slot: u64,
+        max_slot: u64,
+        respond_to: oneshot::Sender<Result<BatchMarker>>,
+    },
+    EndBatch {
+        batch: BatchMarker,
+        completed: oneshot::Sender<()>,
+    },
 }
 
 impl Collector {
-    async fn new(
-        register: &Register,
+    fn new(
+        main_storage_manager: MainStorageManagerHandle,
+        max_block_rows: usize,
+        collector_config: &CollectorConfig,
+        programs_filter: ProgramsFilter,
+        tracer: SignatureTracer,
+        in_flight_batches: InFlightBatches,
         receiver: mpsc::Receiver<CollectorMessage>,
         tick_receiver: mpsc::Receiver<()>,
-    ) -> Result<Self> {
-        let instructions = Vec::with_capacity(BUFFER_SIZE);
-        let balances = Vec::with_capacity(BUFFER_SIZE);
-        let instruction_arguments = Vec::with_capacity(BUFFER_SIZE);
-        let delegations = Delegations::with_capacity(BUFFER_SIZE);
-        let undelegations = Undelegations::with_capacity(BUFFER_SIZE);
-
-        let main_storage_manager = MainStorageManagerHandle::new(register).await?;
+    ) -> Self {
+        let instructions = RowBuffer::with_capacity(max_block_rows);
+        let balances = RowBuffer::with_capacity(max_block_rows);
+        let instruction_arguments = RowBuffer::with_capacity(max_block_rows);
+        let delegations = RowBuffer::with_capacity(max_block_rows);
+        let undelegations = RowBuffer::with_capacity(max_block_rows);
+        let token_transfers = RowBuffer::with_capacity(max_block_rows);
+        let nft_events = RowBuffer::with_capacity(max_block_rows);
+        let commission_changes = RowBuffer::with_capacity(max_block_rows);
+        let claim_events = RowBuffer::with_capacity(max_block_rows);
+        let entangler_swaps = RowBuffer::with_capacity(max_block_rows);
+        let anchor_events = RowBuffer::with_capacity(max_block_rows);
+        let sale_events = RowBuffer::with_capacity(max_block_rows);
+        let pack_events = RowBuffer::with_capacity(max_block_rows);
+        let candy_mints = RowBuffer::with_capacity(max_block_rows);
+        let stake_account_events = RowBuffer::with_capacity(max_block_rows);
+        let bid_events = RowBuffer::with_capacity(max_block_rows);
+        let sol_transfers = RowBuffer::with_capacity(max_block_rows);
 
         metrics_update!(inc total ACTIVE_ACTOR_INSTANCES_COUNT, &["instructions_collector"]);
 
-        Ok(Collector {
+        Collector {
             instructions,
             balances,
             instruction_arguments,
             delegations,
             undelegations,
+            token_transfers,
+            nft_events,
+            commission_changes,
+            claim_events,
+            entangler_swaps,
+            anchor_events,
+            sale_events,
+            pack_events,
+            candy_mints,
+            stake_account_events,
+            bid_events,
+            sol_transfers,
+            programs_filter,
+            decoding_coverage: DecodingCoverage::new(collector_config),
+            max_block_rows,
+            max_buffer_bytes: collector_config.max_buffer_bytes,
+            bisection_limits: BisectionLimits {
+                max_inserts: collector_config.max_bisection_inserts,
+                max_duration: Duration::from_secs(collector_config.max_bisection_secs),
+            },
             main_storage_manager,
+            tracer,
+            in_flight_batches,
+            ended_batches: Vec::new(),
+            last_batch_id: 0,
             receiver,
             tick_receiver,
-            ticks: 0,
-        })
+        }
     }
 
     async fn handle_message(&mut self, msg: CollectorMessage) {
-        match msg {
+        let respond_to = match msg {
             CollectorMessage::SaveInstruction {
                 instruction,
                 respond_to,
             } => {
                 self.collect_instruction(instruction).await;
-                let _ = respond_to.send(());
+                respond_to
             }
             CollectorMessage::SaveBalance {
                 balance,
                 respond_to,
             } => {
                 self.collect_balance(balance).await;
-                let _ = respond_to.send(());
+                respond_to
             }
             CollectorMessage::SaveInstructionArgument {
                 instruction_argument,
@@ -100,35 +230,214 @@ impl Collector {
             } => {
                 self.collect_instruction_argument(instruction_argument)
                     .await;
-                let _ = respond_to.send(());
+                respond_to
             }
             CollectorMessage::SaveDelegation {
                 delegation,
                 respond_to,
             } => {
                 self.collect_delegation(delegation).await;
-                let _ = respond_to.send(());
+                respond_to
             }
             CollectorMessage::SaveUndelegation {
                 undelegation,
                 respond_to,
             } => {
                 self.collect_undelegation(undelegation).await;
-                let _ = respond_to.send(());
+                respond_to
+            }
+            CollectorMessage::SaveTokenTransfer {
+                token_transfer,
+                respond_to,
+            } => {
+                self.collect_token_transfer(token_transfer).await;
+                respond_to
+            }
+            CollectorMessage::SaveNftEvent {
+                nft_event,
+                respond_to,
+            } => {
+                self.collect_nft_event(nft_event).await;
+                respond_to
+            }
+            CollectorMessage::SaveCommissionChange {
+                commission_change,
+                respond_to,
+            } => {
+                self.collect_commission_change(commission_change).await;
+                respond_to
+            }
+            CollectorMessage::SaveClaimEvent {
+                claim_event,
+                respond_to,
+            } => {
+                self.collect_claim_event(claim_event).await;
+                respond_to
+            }
+            CollectorMessage::SaveEntanglerSwap {
+                entangler_swap,
+                respond_to,
+            } => {
+                self.collect_entangler_swap(entangler_swap).await;
+                respond_to
+            }
+            CollectorMessage::SaveAnchorEvent {
+                anchor_event,
+                respond_to,
+            } => {
+                self.collect_anchor_event(anchor_event).await;
+                respond_to
+            }
+            CollectorMessage::SaveSaleEvent {
+                sale_event,
+                respond_to,
+            } => {
+                self.collect_sale_event(sale_event).await;
+                respond_to
+            }
+            CollectorMessage::SavePackEvent {
+                pack_event,
+                respond_to,
+            } => {
+                self.collect_pack_event(pack_event).await;
+                respond_to
+            }
+            CollectorMessage::SaveCandyMint {
+                candy_mint,
+                respond_to,
+            } => {
+                self.collect_candy_mint(candy_mint).await;
+                respond_to
+            }
+            CollectorMessage::SaveStakeAccountEvent {
+                stake_account_event,
+                respond_to,
+            } => {
+                self.collect_stake_account_event(stake_account_event).await;
+                respond_to
+            }
+            CollectorMessage::SaveBidEvent {
+                bid_event,
+                respond_to,
+            } => {
+                self.collect_bid_event(bid_event).await;
+                respond_to
             }
+            CollectorMessage::SaveSolTransfer {
+                sol_transfer,
+                respond_to,
+            } => {
+                self.collect_sol_transfer(sol_transfer).await;
+                respond_to
+            }
+            CollectorMessage::BeginBatch {
+                min_slot,
+                max_slot,
+                respond_to,
+            } => {
+                let _ = respond_to.send(self.begin_batch(min_slot, max_slot).await);
+                return;
+            }
+            CollectorMessage::EndBatch { batch, completed } => {
+                self.ended_batches.push((batch, completed));
+                self.complete_flushed_batches().await;
+                return;
+            }
+        };
+
+        if self.buffered_bytes() >= self.max_buffer_bytes {
+            self.flush_buffer().await;
+            info!("1. Flushed collector's buffer because max_buffer_bytes is reached");
         }
+        self.update_buffered_bytes();
+        self.complete_flushed_batches().await;
+
+        let _ = respond_to.send(());
     }
 
     async fn handle_tick_message(&mut self) {
-        self.ticks += 1;
+        self.flush_buffer().await;
+        self.update_buffered_bytes();
+        self.complete_flushed_batches().await;
+        self.decoding_coverage.report();
+        debug!("Flushed collector's buffer because flush interval expired");
+    }
 
-        if self.ticks >= 2 {
-            self.flush_buffer().await;
-            self.ticks = 0;
-            info!("Flushed collector's buffer because timeout expired");
+    fn buffered_bytes(&self) -> usize {
+        self.instructions.bytes()
+            + self.balances.bytes()
+            + self.instruction_arguments.bytes()
+            + self.delegations.bytes()
+            + self.undelegations.bytes()
+            + self.token_transfers.bytes()
+            + self.nft_events.bytes()
+            + self.commission_changes.bytes()
+            + self.claim_events.bytes()
+            + self.entangler_swaps.bytes()
+            + self.anchor_events.bytes()
+            + self.sale_events.bytes()
+            + self.pack_events.bytes()
+            + self.candy_mints.bytes()
+            + self.stake_account_events.bytes()
+            + self.bid_events.bytes()
+            + self.sol_transfers.bytes()
+    }
+
+    fn is_flushed(&self) -> bool {
+        self.instructions.is_empty()
+            && self.balances.is_empty()
+            && self.instruction_arguments.is_empty()
+            && self.delegations.is_empty()
+            && self.undelegations.is_empty()
+            && self.token_transfers.is_empty()
+            && self.nft_events.is_empty()
+            && self.commission_changes.is_empty()
+            && self.claim_events.is_empty()
+            && self.entangler_swaps.is_empty()
+            && self.anchor_events.is_empty()
+            && self.sale_events.is_empty()
+            && self.pack_events.is_empty()
+            && self.candy_mints.is_empty()
+            && self.stake_account_events.is_empty()
+            && self.bid_events.is_empty()
+            && self.sol_transfers.is_empty()
+    }
+
+    /// Rows of a batch may be spread over all the buffers, so the ended batches are stored as
+    /// complete and given back only once nothing is buffered. The ones whose markers are not
+    /// stored are completed after the next flush
+    async fn complete_flushed_batches(&mut self) {
+        if self.ended_batches.is_empty() || !self.is_flushed() {
+            return;
+        }
+
+        let markers = self
+            .ended_batches
+            .iter()
+            .map(|(batch, _)| BatchMarker {
+                complete: true,
+                ..batch.clone()
+            })
+            .collect();
+        if let Err(err) = self.main_storage_manager.store_batches_block(markers).await {
+            error!("Batches were not stored as complete: {:#?}", err);
+            return;
+        }
+
+        self.in_flight_batches.release(self.ended_batches.len());
+        for (_, completed) in self.ended_batches.drain(..) {
+            let _ = completed.send(());
         }
     }
 
+    fn update_buffered_bytes(&self) {
+        metrics_update!(
+            set COLLECTOR_BUFFERED_BYTES,
+            &["instructions_collector"],
+            self.buffered_bytes() as f64
+        );
+    }
+
     async fn run(&mut self) {
         loop {
             tokio::select! {
@@ -143,11 +452,43 @@ impl Collector {
         }
     }
 
+    /// Stores the marker of a new batch before any of its rows. The ids grow with the time the
+    /// batches are begun at, in microseconds
+    async fn begin_batch(&mut self, min_slot: u64, max_slot: u64) -> Result<BatchMarker> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_micros() as u64)
+            .unwrap_or_default();
+        self.last_batch_id = now.max(self.last_batch_id + 1);
+
+        let batch = BatchMarker {
+            batch_id: self.last_batch_id,
+            min_slot,
+            max_slot,
+            complete: false,
+        };
+        self.main_storage_manager
+            .store_batches_block(vec![batch.clone()])
+            .await?;
+
+        Ok(batch)
+    }
+
     async fn collect_instruction(&mut self, instruction: Instruction) {
+        if !self.programs_filter.is_persisted(&instruction.program) {
+            metrics_update!(
+                inc ROWS_SKIPPED_COUNT,
+                &["instructions", program_name(&instruction.program)]
+            );
+            return;
+        }
+
+        self.decoding_coverage.count(&instruction);
+        self.tracer
+            .trace(&instruction.tx_signature, TraceEvent::InstructionsBuffered);
         self.instructions.push(instruction);
-        self.ticks = 0;
 
-        if self.instructions.len() >= BUFFER_SIZE {
+        if self.instructions.len() >= self.max_block_rows {
             self.flush_instructions().await;
             info!("1. Flushed instructions buffer because a threshold is reached");
         }
@@ -155,19 +496,31 @@ impl Collector {
 
     async fn collect_balance(&mut self, balance: Balance) {
         self.balances.push(balance);
-        self.ticks = 0;
 
-        if self.balances.len() >= BUFFER_SIZE {
+        if self.balances.len() >= self.max_block_rows {
             self.flush_balances().await;
             info!("1. Flushed balances buffer because a threshold is reached");
         }
     }
 
     async fn collect_instruction_argument(&mut self, instruction_argument: InstructionArgument) {
+        if !self
+            .programs_filter
+            .is_persisted(&instruction_argument.program)
+        {
+            metrics_update!(
+                inc ROWS_SKIPPED_COUNT,
+                &[
+                    "instruction_arguments",
+                    program_name(&instruction_argument.program)
+                ]
+            );
+            return;
+        }
+
         self.instruction_arguments.push(instruction_argument);
-        self.ticks = 0;
 
-        if self.instruction_arguments.len() >= BUFFER_SIZE {
+        if self.instruction_arguments.len() >= self.max_block_rows {
             self.flush_instruction_arguments().await;
             info!("1. Flushed instruction arguments buffer because a threshold is reached");
         }
@@ -175,9 +528,8 @@ impl Collector {
 
     async fn collect_delegation(&mut self, delegation: Delegation) {
         self.delegations.push(delegation);
-        self.ticks = 0;
 
-        if self.delegations.len() >= BUFFER_SIZE {
+        if self.delegations.len() >= self.max_block_rows {
             self.flush_delegations().await;
             info!("1. Flushed delegations buffer because a threshold is reached");
         }
@@ -185,20 +537,139 @@ impl Collector {
 
     async fn collect_undelegation(&mut self, undelegation: Delegation) {
         self.undelegations.push(undelegation);
-        self.ticks = 0;
 
-        if self.undelegations.len() >= BUFFER_SIZE {
+        if self.undelegations.len() >= self.max_block_rows {
             self.flush_undelegations().await;
             info!("1. Flushed undelegations buffer because a threshold is reached");
         }
     }
 
+    async fn collect_token_transfer(&mut self, token_transfer: TokenTransfer) {
+        self.token_transfers.push(token_transfer);
+
+        if self.token_transfers.len() >= self.max_block_rows {
+            self.flush_token_transfers().await;
+            info!("1. Flushed token transfers buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_nft_event(&mut self, nft_event: NftEvent) {
+        self.nft_events.push(nft_event);
+
+        if self.nft_events.len() >= self.max_block_rows {
+            self.flush_nft_events().await;
+            info!("1. Flushed NFT events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_commission_change(&mut self, commission_change: CommissionChange) {
+        self.commission_changes.push(commission_change);
+
+        if self.commission_changes.len() >= self.max_block_rows {
+            self.flush_commission_changes().await;
+            info!("1. Flushed commission changes buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_claim_event(&mut self, claim_event: ClaimEvent) {
+        self.claim_events.push(claim_event);
+
+        if self.claim_events.len() >= self.max_block_rows {
+            self.flush_claim_events().await;
+            info!("1. Flushed claim events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_entangler_swap(&mut self, entangler_swap: EntanglerSwap) {
+        self.entangler_swaps.push(entangler_swap);
+
+        if self.entangler_swaps.len() >= self.max_block_rows {
+            self.flush_entangler_swaps().await;
+            info!("1. Flushed entangler swaps buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_anchor_event(&mut self, anchor_event: AnchorEvent) {
+        self.anchor_events.push(anchor_event);
+
+        if self.anchor_events.len() >= self.max_block_rows {
+            self.flush_anchor_events().await;
+            info!("1. Flushed anchor events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_sale_event(&mut self, sale_event: SaleEvent) {
+        self.sale_events.push(sale_event);
+
+        if self.sale_events.len() >= self.max_block_rows {
+            self.flush_sale_events().await;
+            info!("1. Flushed sale events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_pack_event(&mut self, pack_event: PackEvent) {
+        self.pack_events.push(pack_event);
+
+        if self.pack_events.len() >= self.max_block_rows {
+            self.flush_pack_events().await;
+            info!("1. Flushed pack events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_candy_mint(&mut self, candy_mint: CandyMint) {
+        self.candy_mints.push(candy_mint);
+
+        if self.candy_mints.len() >= self.max_block_rows {
+            self.flush_candy_mints().await;
+            info!("1. Flushed candy mints buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_stake_account_event(&mut self, stake_account_event: StakeAccountEvent) {
+        self.stake_account_events.push(stake_account_event);
+
+        if self.stake_account_events.len() >= self.max_block_rows {
+            self.flush_stake_account_events().await;
+            info!("1. Flushed stake account events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_bid_event(&mut self, bid_event: BidEvent) {
+        self.bid_events.push(bid_event);
+
+        if self.bid_events.len() >= self.max_block_rows {
+            self.flush_bid_events().await;
+            info!("1. Flushed bid events buffer because a threshold is reached");
+        }
+    }
+
+    async fn collect_sol_transfer(&mut self, sol_transfer: SolTransfer) {
+        self.sol_transfers.push(sol_transfer);
+
+        if self.sol_transfers.len() >= self.max_block_rows {
+            self.flush_sol_transfers().await;
+            info!("1. Flushed SOL transfers buffer because a threshold is reached");
+        }
+    }
+
     async fn flush_buffer(&mut self) {
         self.flush_instructions().await;
-        self.flush_balances().await;
         self.flush_instruction_arguments().await;
+        self.flush_balances().await;
         self.flush_delegations().await;
         self.flush_undelegations().await;
+        self.flush_token_transfers().await;
+        self.flush_nft_events().await;
+        self.flush_commission_changes().await;
+        self.flush_claim_events().await;
+        self.flush_entangler_swaps().await;
+        self.flush_anchor_events().await;
+        self.flush_sale_events().await;
+        self.flush_pack_events().await;
+        self.flush_candy_mints().await;
+        self.flush_stake_account_events().await;
+        self.flush_bid_events().await;
+        self.flush_sol_transfers().await;
     }
 
     async fn flush_instructions(&mut self) {
@@ -211,9 +682,36 @@ impl Collector {
             match result {
                 Ok(..) => {
                     info!("2. Stored {} instructions", self.instructions.len());
+                    self.trace_flushed_instructions(self.instructions.as_slice());
                     self.instructions.clear();
                 }
-                Err(err) => error!("Instructions were not stored: {:#?}", err),
+                Err(err) => {
+                    error!("Instructions were not stored: {:#?}", err);
+
+                    let stored = self
+                        .bisect_block(
+                            "instructions",
+                            |collector| &mut collector.instructions,
+                            |mut manager, rows| async move {
+                                manager.store_instructions_block(&rows).await
+                            },
+                        )
+                        .await;
+                    self.trace_flushed_instructions(&stored);
+                }
+            }
+        }
+    }
+
+    fn trace_flushed_instructions(&self, instructions: &[Instruction]) {
+        let mut traced = HashSet::new();
+
+        for instruction in instructions {
+            if self.tracer.is_traced(&instruction.tx_signature)
+                && traced.insert(instruction.tx_signature.as_str())
+            {
+                self.tracer
+                    .trace(&instruction.tx_signature, TraceEvent::Flushed);
             }
         }
     }
@@ -229,7 +727,16 @@ impl Collector {
                     info!("2. Stored {} balances", self.balances.len());
                     self.balances.clear();
                 }
-                Err(err) => error!("Balances were not stored: {:#?}", err),
+                Err(err) => {
+                    error!("Balances were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "balances",
+                        |collector| &mut collector.balances,
+                        |mut manager, rows| async move { manager.store_balances_block(&rows).await },
+                    )
+                    .await;
+                }
             }
         }
     }
@@ -249,7 +756,18 @@ impl Collector {
                     );
                     self.instruction_arguments.clear();
                 }
-                Err(err) => error!("Instruction arguments were not stored: {:#?}", err),
+                Err(err) => {
+                    error!("Instruction arguments were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "instruction_arguments",
+                        |collector| &mut collector.instruction_arguments,
+                        |mut manager, rows| async move {
+                            manager.store_instruction_arguments_block(&rows).await
+                        },
+                    )
+                    .await;
+                }
             }
         }
     }
@@ -258,15 +776,38 @@ impl Collector {
         if !self.delegations.is_empty() {
             let result = self
                 .main_storage_manager
-                .store_delegations_block(self.delegations.clone())
+                .store_delegations_block(self.delegations.as_slice().to_vec())
                 .await;
 
             match result {
                 Ok(..) => {
                     info!("2. Stored {} delegations", self.delegations.len());
+                    metrics_update!(inc by DELEGATIONS_COUNT, self.delegations.len() as u64);
+                    self.store_delegations_daily(DelegationsDaily::aggregate(
+                        self.delegations.as_slice(),
+                        &[],
+                    ))
+                    .await;
                     self.delegations.clear();
                 }
-                Err(err) => error!("Delegations were not stored: {:#?}", err),
+                Err(err) => {
+                    error!("Delegations were not stored: {:#?}", err);
+
+                    let stored = self
+                        .bisect_block(
+                            "delegations",
+                            |collector| &mut collector.delegations,
+                            |mut manager, rows| async move {
+                                manager.store_delegations_block(rows).await
+                            },
+                        )
+                        .await;
+                    if !stored.is_empty() {
+                        metrics_update!(inc by DELEGATIONS_COUNT, stored.len() as u64);
+                        self.store_delegations_daily(DelegationsDaily::aggregate(&stored, &[]))
+                            .await;
+                    }
+                }
             }
         }
     }
@@ -275,96 +816,566 @@ impl Collector {
         if !self.undelegations.is_empty() {
             let result = self
                 .main_storage_manager
-                .store_undelegations_block(self.undelegations.clone())
+                .store_undelegations_block(self.undelegations.as_slice().to_vec())
                 .await;
 
             match result {
                 Ok(..) => {
                     info!("2. Stored {} undelegations", self.undelegations.len());
+                    metrics_update!(inc by UNDELEGATIONS_COUNT, self.undelegations.len() as u64);
+                    self.store_delegations_daily(DelegationsDaily::aggregate(
+                        &[],
+                        self.undelegations.as_slice(),
+                    ))
+                    .await;
                     self.undelegations.clear();
                 }
-                Err(err) => error!("Unelegations were not stored: {:#?}", err),
+                Err(err) => {
+                    error!("Unelegations were not stored: {:#?}", err);
+
+                    let stored = self
+                        .bisect_block(
+                            "undelegations",
+                            |collector| &mut collector.undelegations,
+                            |mut manager, rows| async move {
+                                manager.store_undelegations_block(rows).await
+                            },
+                        )
+                        .await;
+                    if !stored.is_empty() {
+                        metrics_update!(inc by UNDELEGATIONS_COUNT, stored.len() as u64);
+                        self.store_delegations_daily(DelegationsDaily::aggregate(&[], &stored))
+                            .await;
+                    }
+                }
             }
         }
     }
-}
 
-#[derive(HandleInstance)]
-pub struct CollectorHandle {
-    sender: mpsc::Sender<CollectorMessage>,
-}
+    /// Bisects the block of the buffer which failed to be inserted. The rows the main storage
+    /// rejects on their own are stored into `erroneous_rows` with the error of their insert and
+    /// the rows which are not stored yet stay in the buffer. Returns the stored rows
+    async fn bisect_block<T, F, Fut>(
+        &mut self,
+        table: &'static str,
+        select: fn(&mut Self) -> &mut RowBuffer<T>,
+        mut store: F,
+    ) -> Vec<T>
+    where
+        T: RowSize + TxRow + Clone,
+        F: FnMut(MainStorageManagerHandle, Vec<T>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        if self.bisection_limits.max_inserts == 0 {
+            return Vec::new();
+        }
+        metrics_update!(inc INSERT_BISECTIONS_COUNT, &[table]);
 
-impl CollectorHandle {
-    pub async fn new(register: &Register) -> Result<Self> {
-        let (sender, receiver) = mpsc::channel(100);
-        let (tick_sender, tick_receiver) = mpsc::channel(1);
-        let mut instructions_collector = Collector::new(register, receiver, tick_receiver).await?;
+        let rows = select(self).as_slice().to_vec();
+        let block_rows = rows.len();
+        let main_storage_manager = self.main_storage_manager.clone();
+        let bisection = bisect(rows, self.bisection_limits, |rows| {
+            store(main_storage_manager.clone(), rows)
+        })
+        .await;
 
-        tokio::spawn(async move { instructions_collector.run().await });
+        let buffer = select(self);
+        buffer.clear();
+        for row in bisection.remaining {
+            buffer.push(row);
+        }
 
-        tokio::spawn(async move {
-            loop {
-                sleep(Duration::from_millis(FLUSH_BUFFER_TIMEOUT)).await;
-                tick_sender.send(()).await.unwrap();
+        if !bisection.rejected.is_empty() {
+            let erroneous_rows: Vec<_> = bisection
+                .rejected
+                .iter()
+                .map(|(row, cause)| row.erroneous(table, cause.clone()))
+                .collect();
+
+            for erroneous_row in &erroneous_rows {
+                error!(
+                    "Row of {} of transaction {} was rejected: {}",
+                    table, erroneous_row.tx_signature, erroneous_row.cause
+                );
             }
-        });
+            metrics_update!(inc by REJECTED_ROWS_COUNT, &[table], erroneous_rows.len() as u64);
 
-        metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &["instructions_collector_handle"]);
+            if let Err(err) = self
+                .main_storage_manager
+                .store_erroneous_rows_block(erroneous_rows)
+                .await
+            {
+                error!("Rejected rows of {} were not stored: {:#?}", table, err);
+            }
+        }
 
-        Ok(Self { sender })
-    }
+        info!(
+            "2. Stored {} of {} rows of the failed {} block, {} rejected, {} left in the buffer",
+            bisection.stored.len(),
+            block_rows,
+            table,
+            bisection.rejected.len(),
+            select(self).len()
+        );
 
-    pub async fn save_instruction(&mut self, instruction: Instruction) {
-        let (sender, receiver) = oneshot::channel();
-        let msg = CollectorMessage::SaveInstruction {
-            instruction,
-            respond_to: sender,
-        };
+        bisection.stored
+    }
 
-        let _ = self.sender.send(msg).await;
+    /// The daily aggregate follows the stored block, a failure leaves the block stored
+    async fn store_delegations_daily(&mut self, delegations_daily: Vec<DelegationsDaily>) {
+        let days = delegations_daily.len();
+        let result = self
+            .main_storage_manager
+            .store_delegations_daily_block(delegations_daily)
+            .await;
 
-        receiver.await.expect("Collector task has been killed")
+        match result {
+            Ok(..) => info!("2. Stored {} daily delegations", days),
+            Err(err) => error!("Daily delegations were not stored: {:#?}", err),
+        }
     }
 
-    pub async fn save_balance(&mut self, balance: Balance) {
-        let (sender, receiver) = oneshot::channel();
-        let msg = CollectorMessage::SaveBalance {
-            balance,
-            respond_to: sender,
-        };
+    async fn flush_token_transfers(&mut self) {
+        if !self.token_transfers.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_token_transfers_block(self.token_transfers.as_slice().to_vec())
+                .await;
 
-        let _ = self.sender.send(msg).await;
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} token transfers", self.token_transfers.len());
+                    self.token_transfers.clear();
+                }
+                Err(err) => {
+                    error!("Token transfers were not stored: {:#?}", err);
 
-        receiver.await.expect("Collector task has been killed")
+                    self.bisect_block(
+                        "token_transfers",
+                        |collector| &mut collector.token_transfers,
+                        |mut manager, rows| async move {
+                            manager.store_token_transfers_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
     }
 
-    pub async fn save_instruction_argument(&mut self, instruction_argument: InstructionArgument) {
-        let (sender, receiver) = oneshot::channel();
-        let msg = CollectorMessage::SaveInstructionArgument {
-            instruction_argument,
-            respond_to: sender,
-        };
+    async fn flush_nft_events(&mut self) {
+        if !self.nft_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_nft_events_block(self.nft_events.as_slice().to_vec())
+                .await;
 
-        let _ = self.sender.send(msg).await;
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} NFT events", self.nft_events.len());
+                    self.nft_events.clear();
+                }
+                Err(err) => {
+                    error!("NFT events were not stored: {:#?}", err);
 
-        receiver.await.expect("Collector task has been killed")
+                    self.bisect_block(
+                        "nft_events",
+                        |collector| &mut collector.nft_events,
+                        |mut manager, rows| async move { manager.store_nft_events_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
     }
 
-    pub async fn save_delegation(&mut self, delegation: Delegation) {
-        let (sender, receiver) = oneshot::channel();
-        let msg = CollectorMessage::SaveDelegation {
-            delegation,
-            respond_to: sender,
-        };
+    async fn flush_commission_changes(&mut self) {
+        if !self.commission_changes.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_commission_changes_block(self.commission_changes.as_slice().to_vec())
+                .await;
 
-        let _ = self.sender.send(msg).await;
+            match result {
+                Ok(..) => {
+                    info!(
+                        "2. Stored {} commission changes",
+                        self.commission_changes.len()
+                    );
+                    self.commission_changes.clear();
+                }
+                Err(err) => {
+                    error!("Commission changes were not stored: {:#?}", err);
 
-        receiver.await.expect("Collector task has been killed")
+                    self.bisect_block(
+                        "commission_changes",
+                        |collector| &mut collector.commission_changes,
+                        |mut manager, rows| async move {
+                            manager.store_commission_changes_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
     }
 
-    pub async fn save_undelegation(&mut self, undelegation: Delegation) {
-        let (sender, receiver) = oneshot::channel();
-        let msg = CollectorMessage::SaveUndelegation {
+    async fn flush_claim_events(&mut self) {
+        if !self.claim_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_claim_events_block(self.claim_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} claim events", self.claim_events.len());
+                    self.claim_events.clear();
+                }
+                Err(err) => {
+                    error!("Claim events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "claim_events",
+                        |collector| &mut collector.claim_events,
+                        |mut manager, rows| async move { manager.store_claim_events_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_entangler_swaps(&mut self) {
+        if !self.entangler_swaps.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_entangler_swaps_block(self.entangler_swaps.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} entangler swaps", self.entangler_swaps.len());
+                    self.entangler_swaps.clear();
+                }
+                Err(err) => {
+                    error!("Entangler swaps were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "entangler_swaps",
+                        |collector| &mut collector.entangler_swaps,
+                        |mut manager, rows| async move {
+                            manager.store_entangler_swaps_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_anchor_events(&mut self) {
+        if !self.anchor_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_anchor_events_block(self.anchor_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} anchor events", self.anchor_events.len());
+                    self.anchor_events.clear();
+                }
+                Err(err) => {
+                    error!("Anchor events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "anchor_events",
+                        |collector| &mut collector.anchor_events,
+                        |mut manager, rows| async move { manager.store_anchor_events_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_sale_events(&mut self) {
+        if !self.sale_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_sale_events_block(self.sale_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} sale events", self.sale_events.len());
+                    self.sale_events.clear();
+                }
+                Err(err) => {
+                    error!("Sale events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "nft_sales",
+                        |collector| &mut collector.sale_events,
+                        |mut manager, rows| async move { manager.store_sale_events_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_pack_events(&mut self) {
+        if !self.pack_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_pack_events_block(self.pack_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} pack events", self.pack_events.len());
+                    self.pack_events.clear();
+                }
+                Err(err) => {
+                    error!("Pack events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "nft_pack_events",
+                        |collector| &mut collector.pack_events,
+                        |mut manager, rows| async move { manager.store_pack_events_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_candy_mints(&mut self) {
+        if !self.candy_mints.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_candy_mints_block(self.candy_mints.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} candy mints", self.candy_mints.len());
+                    self.candy_mints.clear();
+                }
+                Err(err) => {
+                    error!("Candy mints were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "candy_mints",
+                        |collector| &mut collector.candy_mints,
+                        |mut manager, rows| async move { manager.store_candy_mints_block(rows).await },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_stake_account_events(&mut self) {
+        if !self.stake_account_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_stake_account_events_block(self.stake_account_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!(
+                        "2. Stored {} stake account events",
+                        self.stake_account_events.len()
+                    );
+                    self.stake_account_events.clear();
+                }
+                Err(err) => {
+                    error!("Stake account events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "stake_account_events",
+                        |collector| &mut collector.stake_account_events,
+                        |mut manager, rows| async move {
+                            manager.store_stake_account_events_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_bid_events(&mut self) {
+        if !self.bid_events.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_bid_events_block(self.bid_events.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} bid events", self.bid_events.len());
+                    self.bid_events.clear();
+                }
+                Err(err) => {
+                    error!("Bid events were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "bid_events",
+                        |collector| &mut collector.bid_events,
+                        |mut manager, rows| async move {
+                            manager.store_bid_events_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    async fn flush_sol_transfers(&mut self) {
+        if !self.sol_transfers.is_empty() {
+            let result = self
+                .main_storage_manager
+                .store_sol_transfers_block(self.sol_transfers.as_slice().to_vec())
+                .await;
+
+            match result {
+                Ok(..) => {
+                    info!("2. Stored {} SOL transfers", self.sol_transfers.len());
+                    self.sol_transfers.clear();
+                }
+                Err(err) => {
+                    error!("SOL transfers were not stored: {:#?}", err);
+
+                    self.bisect_block(
+                        "sol_transfers",
+                        |collector| &mut collector.sol_transfers,
+                        |mut manager, rows| async move {
+                            manager.store_sol_transfers_block(rows).await
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+}
+
+#[derive(HandleInstance)]
+pub struct CollectorHandle {
+    sender: mpsc::Sender<CollectorMessage>,
+}
+
+impl CollectorHandle {
+    pub async fn new(register: &Register) -> Result<Self> {
+        let main_storage_manager = MainStorageManagerHandle::new(register).await?;
+
+        Ok(Self::with_storage_manager(
+            main_storage_manager,
+            register.config.get_main_storage_config(),
+            register.config.get_collector_config(),
+            register.config.get_analysis_config().programs.clone(),
+            register.tracer.clone(),
+            register.in_flight_batches.clone(),
+        ))
+    }
+
+    /// Rows are coalesced into blocks of `max_block_rows`, whatever is collected is flushed
+    /// every `flush_interval_ms` or once the buffers take `max_buffer_bytes`
+    fn with_storage_manager(
+        main_storage_manager: MainStorageManagerHandle,
+        main_storage_config: &MainStorageConfig,
+        collector_config: &CollectorConfig,
+        programs_filter: ProgramsFilter,
+        tracer: SignatureTracer,
+        in_flight_batches: InFlightBatches,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(100);
+        let (tick_sender, tick_receiver) = mpsc::channel(1);
+        let mut instructions_collector = Collector::new(
+            main_storage_manager,
+            main_storage_config.max_block_rows,
+            collector_config,
+            programs_filter,
+            tracer,
+            in_flight_batches,
+            receiver,
+            tick_receiver,
+        );
+
+        tokio::spawn(async move { instructions_collector.run().await });
+
+        let flush_interval = Duration::from_millis(main_storage_config.flush_interval_ms);
+        tokio::spawn(async move {
+            loop {
+                sleep(flush_interval).await;
+                if tick_sender.send(()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &["instructions_collector_handle"]);
+
+        Self { sender }
+    }
+
+    pub async fn save_instruction(&mut self, instruction: Instruction) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveInstruction {
+            instruction,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_balance(&mut self, balance: Balance) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveBalance {
+            balance,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_instruction_argument(&mut self, instruction_argument: InstructionArgument) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveInstructionArgument {
+            instruction_argument,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_delegation(&mut self, delegation: Delegation) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveDelegation {
+            delegation,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_undelegation(&mut self, undelegation: Delegation) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveUndelegation {
             undelegation,
             respond_to: sender,
         };
@@ -373,4 +1384,783 @@ impl CollectorHandle {
 
         receiver.await.expect("Collector task has been killed")
     }
+
+    pub async fn save_token_transfer(&mut self, token_transfer: TokenTransfer) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveTokenTransfer {
+            token_transfer,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_nft_event(&mut self, nft_event: NftEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveNftEvent {
+            nft_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_commission_change(&mut self, commission_change: CommissionChange) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveCommissionChange {
+            commission_change,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_claim_event(&mut self, claim_event: ClaimEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveClaimEvent {
+            claim_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_entangler_swap(&mut self, entangler_swap: EntanglerSwap) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveEntanglerSwap {
+            entangler_swap,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_anchor_event(&mut self, anchor_event: AnchorEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveAnchorEvent {
+            anchor_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_sale_event(&mut self, sale_event: SaleEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveSaleEvent {
+            sale_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_pack_event(&mut self, pack_event: PackEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SavePackEvent {
+            pack_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_candy_mint(&mut self, candy_mint: CandyMint) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveCandyMint {
+            candy_mint,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_stake_account_event(&mut self, stake_account_event: StakeAccountEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveStakeAccountEvent {
+            stake_account_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_bid_event(&mut self, bid_event: BidEvent) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveBidEvent {
+            bid_event,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    pub async fn save_sol_transfer(&mut self, sol_transfer: SolTransfer) {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::SaveSolTransfer {
+            sol_transfer,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    /// Begins a batch taken from the queue with the slots of its transactions, the batch is
+    /// stored into `batches` before its rows are saved
+    pub async fn begin_batch(&mut self, min_slot: u64, max_slot: u64) -> Result<BatchMarker> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::BeginBatch {
+            min_slot,
+            max_slot,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver.await.expect("Collector task has been killed")
+    }
+
+    /// Marks the end of the rows of the batch, they are flushed by the size and time triggers
+    /// like the others. The returned receiver resolves once they are all stored and the batch is
+    /// stored as complete and given back to the queue manager, the transactions of the batch are
+    /// marked as parsed only then
+    pub async fn end_batch(&mut self, batch: BatchMarker) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = CollectorMessage::EndBatch {
+            batch,
+            completed: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storages::main_storage::https_client::{BalancesRow, InstructionRow};
+    use crate::storages::main_storage::row_buffer::RowSize;
+    use crate::storages::main_storage::{
+        Block, ErroneousRow, ErroneousTransaction, MainStorage, Page, QueueCursor,
+    };
+    use async_trait::async_trait;
+    use clickhouse_storage::{ClickhouseStorage, StorageError};
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the number of rows of every instructions insert, the number of rows and bytes
+    /// of every instruction arguments insert, the inserted daily delegations, erroneous rows and
+    /// batches, instructions take `insert_delay` to insert. A block with an instruction of the
+    /// `rejected_tx` transaction fails. The tables of the instructions, instruction arguments,
+    /// balances and batches inserts are recorded in order, once `failing_after` of them are
+    /// stored the following ones fail, as if the process died
+    #[derive(Clone, Default)]
+    struct MockStorage {
+        inserts: Arc<Mutex<Vec<usize>>>,
+        erroneous_rows: Arc<Mutex<Vec<ErroneousRow>>>,
+        rejected_tx: Option<String>,
+        argument_inserts: Arc<Mutex<Vec<(usize, usize)>>>,
+        delegations_daily: Arc<Mutex<Vec<DelegationsDaily>>>,
+        batches: Arc<Mutex<Vec<BatchMarker>>>,
+        tables: Arc<Mutex<Vec<&'static str>>>,
+        failing_after: Arc<Mutex<Option<usize>>>,
+        insert_delay: Duration,
+    }
+
+    impl MockStorage {
+        fn insert(&self, table: &'static str) -> Result<()> {
+            let mut tables = self.tables.lock().unwrap();
+            let failing_after = *self.failing_after.lock().unwrap();
+            if failing_after.map_or(false, |failing_after| tables.len() >= failing_after) {
+                return Err(anyhow::anyhow!("Connection refused"));
+            }
+
+            tables.push(table);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ClickhouseStorage for MockStorage {
+        async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl MainStorage for MockStorage {
+        async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
+            sleep(self.insert_delay).await;
+            if instructions
+                .iter()
+                .any(|instruction| Some(&instruction.tx_signature) == self.rejected_tx.as_ref())
+            {
+                return Err(anyhow::anyhow!(
+                    "Code: 27. DB::Exception: Cannot parse input: expected '\"' before: '\\uD800'"
+                ));
+            }
+            self.insert("instructions")?;
+            self.inserts.lock().unwrap().push(instructions.len());
+            Ok(())
+        }
+
+        async fn store_instruction_arguments_block(
+            &mut self,
+            instruction_arguments: Vec<InstructionArgument>,
+        ) -> Result<()> {
+            self.insert("instruction_arguments")?;
+            self.argument_inserts.lock().unwrap().push((
+                instruction_arguments.len(),
+                instruction_arguments.iter().map(RowSize::row_size).sum(),
+            ));
+            Ok(())
+        }
+
+        async fn store_balances_block(&mut self, _balances: Vec<Balance>) -> Result<()> {
+            self.insert("balances")
+        }
+
+        async fn store_erroneous_transaction_block(
+            &mut self,
+            _erroneous_transactions: Vec<ErroneousTransaction>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_erroneous_rows_block(
+            &mut self,
+            erroneous_rows: Vec<ErroneousRow>,
+        ) -> Result<()> {
+            self.erroneous_rows.lock().unwrap().extend(erroneous_rows);
+            Ok(())
+        }
+
+        async fn store_delegations_block(&mut self, _delegations: Vec<Delegation>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_undelegations_block(
+            &mut self,
+            _undelegations: Vec<Delegation>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_delegations_daily_block(
+            &mut self,
+            delegations_daily: Vec<DelegationsDaily>,
+        ) -> Result<()> {
+            self.delegations_daily
+                .lock()
+                .unwrap()
+                .extend(delegations_daily);
+            Ok(())
+        }
+
+        async fn store_blocks_block(&mut self, _blocks: Vec<Block>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_token_transfers_block(
+            &mut self,
+            _token_transfers: Vec<TokenTransfer>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_nft_events_block(&mut self, _nft_events: Vec<NftEvent>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_commission_changes_block(
+            &mut self,
+            _commission_changes: Vec<CommissionChange>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_claim_events_block(&mut self, _claim_events: Vec<ClaimEvent>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_entangler_swaps_block(
+            &mut self,
+            _entangler_swaps: Vec<EntanglerSwap>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_anchor_events_block(
+            &mut self,
+            _anchor_events: Vec<AnchorEvent>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_sale_events_block(&mut self, _sale_events: Vec<SaleEvent>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_pack_events_block(&mut self, _pack_events: Vec<PackEvent>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_candy_mints_block(&mut self, _candy_mints: Vec<CandyMint>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_stake_account_events_block(
+            &mut self,
+            _stake_account_events: Vec<StakeAccountEvent>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_bid_events_block(&mut self, _bid_events: Vec<BidEvent>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_sol_transfers_block(
+            &mut self,
+            _sol_transfers: Vec<SolTransfer>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+            self.insert("batches")?;
+            self.batches.lock().unwrap().extend(batches);
+            Ok(())
+        }
+
+        async fn get_block_time(&mut self, _slot: u64) -> Result<Option<i64>> {
+            Ok(None)
+        }
+
+        async fn get_delegations_for_stake_acc(
+            &mut self,
+            _stake_acc: &str,
+            _from_slot: u64,
+            _to_slot: u64,
+        ) -> Result<Vec<Delegation>> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_instructions_by_signature(
+            &mut self,
+            _tx_signature: &str,
+            _page: Page,
+        ) -> Result<Vec<InstructionRow>> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_delegations_for_stake_acc(
+            &mut self,
+            _stake_acc: &str,
+            _from_slot: u64,
+            _to_slot: u64,
+            _page: Page,
+        ) -> Result<Vec<Delegation>> {
+            Ok(Vec::new())
+        }
+
+        async fn fetch_balances_by_account(
+            &mut self,
+            _account: &str,
+            _page: Page,
+        ) -> Result<Vec<BalancesRow>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_queue_cursor(&mut self, _queue: &str) -> Result<Option<QueueCursor>> {
+            Ok(None)
+        }
+
+        async fn store_queue_cursor(&mut self, _queue: &str, _cursor: QueueCursor) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+            Ok(None)
+        }
+
+        async fn store_indexing_watermark(&mut self, _slot: u64) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn collector(
+        storage: &MockStorage,
+        max_block_rows: usize,
+        max_buffer_bytes: usize,
+        in_flight_batches: InFlightBatches,
+    ) -> CollectorHandle {
+        let main_storage_config = MainStorageConfig {
+            database_url: String::new(),
+            storage_type: Default::default(),
+            max_block_rows,
+            flush_interval_ms: 200,
+            async_insert: false,
+            max_file_rows: 0,
+            retention: None,
+        };
+
+        CollectorHandle::with_storage_manager(
+            MainStorageManagerHandle::with_storage(Box::new(storage.clone())),
+            &main_storage_config,
+            &CollectorConfig {
+                max_buffer_bytes,
+                ..Default::default()
+            },
+            ProgramsFilter::default(),
+            SignatureTracer::default(),
+            in_flight_batches,
+        )
+    }
+
+    fn instruction() -> Instruction {
+        Instruction::new(&Pubkey::default(), &Signature::default())
+    }
+
+    #[tokio::test]
+    async fn small_blocks_are_coalesced() {
+        let storage = MockStorage::default();
+        let mut collector = collector(&storage, 1000, usize::MAX, InFlightBatches::default());
+
+        collector.save_instruction(instruction()).await;
+        collector.save_instruction(instruction()).await;
+
+        sleep(Duration::from_millis(500)).await;
+
+        assert_eq!(*storage.inserts.lock().unwrap(), [2]);
+    }
+
+    #[tokio::test]
+    async fn full_block_is_flushed_at_once() {
+        let storage = MockStorage::default();
+        let mut collector = collector(&storage, 2, usize::MAX, InFlightBatches::default());
+
+        collector.save_instruction(instruction()).await;
+        collector.save_instruction(instruction()).await;
+
+        // The interval has not expired yet
+        assert_eq!(*storage.inserts.lock().unwrap(), [2]);
+    }
+
+    #[tokio::test]
+    async fn buffer_is_flushed_at_max_buffer_bytes() {
+        let storage = MockStorage::default();
+        let argument = InstructionArgument {
+            string_value: Some("a".repeat(1024)),
+            ..Default::default()
+        };
+        let row_size = argument.row_size();
+        let max_buffer_bytes = 10 * row_size;
+        let mut collector = collector(&storage, 1000, max_buffer_bytes, InFlightBatches::default());
+
+        for _ in 0..95 {
+            collector.save_instruction_argument(argument.clone()).await;
+        }
+
+        // Rows over the limit have been inserted before the interval expired
+        let inserts = storage.argument_inserts.lock().unwrap().clone();
+        assert_eq!(inserts.len(), 9);
+        for (rows, bytes) in inserts {
+            assert_eq!(rows, 10);
+            assert!(bytes < max_buffer_bytes + row_size);
+        }
+
+        sleep(Duration::from_millis(500)).await;
+
+        let inserts = storage.argument_inserts.lock().unwrap().clone();
+        assert_eq!(inserts.last(), Some(&(5, 5 * row_size)));
+    }
+
+    #[tokio::test]
+    async fn unflushed_batches_are_bounded_with_slow_storage() {
+        let storage = MockStorage {
+            insert_delay: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let in_flight_batches = InFlightBatches::new(2);
+        let mut collector = collector(&storage, 1000, usize::MAX, in_flight_batches.clone());
+
+        let mut max_in_flight = 0;
+        for _ in 0..6 {
+            // As the queue manager does before fetching a batch
+            in_flight_batches.acquire().await;
+            max_in_flight = max_in_flight.max(in_flight_batches.count());
+
+            let batch = collector.begin_batch(1, 1).await.unwrap();
+            collector.save_instruction(instruction()).await;
+            collector.save_instruction(instruction()).await;
+            collector.end_batch(batch).await;
+        }
+        assert_eq!(max_in_flight, 2);
+
+        for _ in 0..50 {
+            if in_flight_batches.count() == 0 {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        // Every batch has been given back once its rows were inserted
+        assert_eq!(in_flight_batches.count(), 0);
+        assert_eq!(storage.inserts.lock().unwrap().iter().sum::<usize>(), 12);
+    }
+
+    #[tokio::test]
+    async fn rejected_row_is_isolated_from_its_block() {
+        let rejected = Signature::new_unique();
+        let storage = MockStorage {
+            rejected_tx: Some(rejected.to_string()),
+            ..Default::default()
+        };
+        let mut collector = collector(&storage, 10, usize::MAX, InFlightBatches::default());
+
+        for position in 0..10 {
+            let signature = if position == 6 {
+                rejected
+            } else {
+                Signature::new_unique()
+            };
+            collector
+                .save_instruction(Instruction::new(&Pubkey::default(), &signature))
+                .await;
+        }
+
+        // The rest of the block is stored as the bisection halves it
+        assert_eq!(storage.inserts.lock().unwrap().iter().sum::<usize>(), 9);
+
+        let erroneous_rows = storage.erroneous_rows.lock().unwrap().clone();
+        assert_eq!(erroneous_rows.len(), 1);
+        assert_eq!(erroneous_rows[0].target_table, "instructions");
+        assert_eq!(erroneous_rows[0].tx_signature, rejected.to_string());
+        assert!(erroneous_rows[0].cause.contains("Cannot parse input"));
+
+        // Nothing is left to be inserted by the next flush
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(storage.inserts.lock().unwrap().iter().sum::<usize>(), 9);
+    }
+
+    fn delegation(block_time: u64, vote_acc: Option<&str>, amount: u64) -> Delegation {
+        Delegation {
+            block_time,
+            vote_acc: vote_acc.map(str::to_string),
+            amount,
+            ..Default::default()
+        }
+    }
+
+    fn day(
+        date: u16,
+        vote_acc: &str,
+        delegated_lamports: u64,
+        undelegated_lamports: u64,
+    ) -> DelegationsDaily {
+        DelegationsDaily {
+            date,
+            vote_acc: vote_acc.to_string(),
+            delegated_lamports,
+            undelegated_lamports,
+            net: delegated_lamports as i64 - undelegated_lamports as i64,
+        }
+    }
+
+    #[tokio::test]
+    async fn delegation_blocks_are_aggregated_daily() {
+        const DAY: u64 = 24 * 60 * 60;
+
+        let storage = MockStorage::default();
+        let mut collector = collector(&storage, 2, usize::MAX, InFlightBatches::default());
+
+        for (block_time, vote_acc, amount) in [
+            (19000 * DAY + 10, "vote_1", 100),
+            (19000 * DAY + 20, "vote_2", 50),
+            (19000 * DAY + 30, "vote_1", 200),
+            (19001 * DAY, "vote_1", 300),
+        ] {
+            collector
+                .save_delegation(delegation(block_time, Some(vote_acc), amount))
+                .await;
+        }
+        collector
+            .save_undelegation(delegation(19000 * DAY + 40, Some("vote_1"), 70))
+            .await;
+        collector
+            .save_undelegation(delegation(19001 * DAY + 1, None, 10))
+            .await;
+
+        // Every block adds its own rows, the table sums them up
+        assert_eq!(
+            *storage.delegations_daily.lock().unwrap(),
+            [
+                day(19000, "vote_1", 100, 0),
+                day(19000, "vote_2", 50, 0),
+                day(19000, "vote_1", 200, 0),
+                day(19001, "vote_1", 300, 0),
+                day(19000, "vote_1", 0, 70),
+                day(19001, "", 0, 10),
+            ]
+        );
+
+        let vote_1 = storage
+            .delegations_daily
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|row| row.date == 19000 && row.vote_acc == "vote_1")
+            .fold(day(19000, "vote_1", 0, 0), |sum, row| {
+                day(
+                    19000,
+                    "vote_1",
+                    sum.delegated_lamports + row.delegated_lamports,
+                    sum.undelegated_lamports + row.undelegated_lamports,
+                )
+            });
+        assert_eq!(vote_1.net, 230);
+    }
+
+    fn balance() -> Balance {
+        Balance {
+            tx_signature: Signature::default().to_string(),
+            account: Pubkey::new_unique().to_string(),
+            pre_balance: Some(1000),
+            post_balance: Some(995),
+            pre_token_balance_mint: None,
+            pre_token_balance_owner: None,
+            pre_token_balance_amount: None,
+            pre_token_balance_program_id: None,
+            post_token_balance_mint: None,
+            post_token_balance_owner: None,
+            post_token_balance_amount: None,
+            post_token_balance_program_id: None,
+            inner_instructions_set: None,
+            attributed_amount: None,
+        }
+    }
+
+    async fn save_batch_rows(collector: &mut CollectorHandle) {
+        collector.save_instruction(instruction()).await;
+        collector
+            .save_instruction_argument(InstructionArgument::default())
+            .await;
+        collector.save_balance(balance()).await;
+    }
+
+    #[tokio::test]
+    async fn batch_rows_are_stored_between_its_markers() {
+        let storage = MockStorage::default();
+        let mut collector = collector(&storage, 1000, usize::MAX, InFlightBatches::default());
+
+        let batch = collector.begin_batch(100, 120).await.unwrap();
+        save_batch_rows(&mut collector).await;
+        collector.end_batch(batch.clone()).await.await.unwrap();
+
+        assert_eq!(
+            *storage.tables.lock().unwrap(),
+            [
+                "batches",
+                "instructions",
+                "instruction_arguments",
+                "balances",
+                "batches"
+            ]
+        );
+        assert_eq!(
+            *storage.batches.lock().unwrap(),
+            [
+                batch.clone(),
+                BatchMarker {
+                    complete: true,
+                    ..batch
+                }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_is_left_incomplete_by_a_crash() {
+        // The process dies after the marker and the instructions are stored
+        let storage = MockStorage {
+            failing_after: Arc::new(Mutex::new(Some(2))),
+            ..Default::default()
+        };
+        let mut collector = collector(&storage, 1000, usize::MAX, InFlightBatches::default());
+
+        let batch = collector.begin_batch(100, 120).await.unwrap();
+        save_batch_rows(&mut collector).await;
+
+        // The transactions of the batch are not marked as parsed
+        let mut completed = collector.end_batch(batch.clone()).await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), &mut completed)
+                .await
+                .is_err()
+        );
+        assert_eq!(*storage.tables.lock().unwrap(), ["batches", "instructions"]);
+        assert_eq!(*storage.batches.lock().unwrap(), [batch.clone()]);
+
+        // The buffered rows complete the batch once the storage is back
+        *storage.failing_after.lock().unwrap() = None;
+        completed.await.unwrap();
+
+        assert_eq!(
+            storage.tables.lock().unwrap()[2..],
+            ["instruction_arguments", "balances", "batches"]
+        );
+        assert_eq!(
+            storage.batches.lock().unwrap().last(),
+            Some(&BatchMarker {
+                complete: true,
+                ..batch
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_ids_grow() {
+        let storage = MockStorage::default();
+        let mut collector = collector(&storage, 1000, usize::MAX, InFlightBatches::default());
+
+        let first = collector.begin_batch(1, 1).await.unwrap();
+        let second = collector.begin_batch(1, 1).await.unwrap();
+
+        assert!(second.batch_id > first.batch_id);
+    }
 }
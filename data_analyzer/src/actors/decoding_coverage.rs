@@ -0,0 +1,181 @@
+use crate::configuration::CollectorConfig;
+use crate::metrics_update;
+use crate::storages::main_storage::Instruction;
+use log::warn;
+use std::collections::HashMap;
+
+/// Label of the unnamed instructions of the programs out of the top ones of an interval
+const OTHER_PROGRAMS: &str = "other";
+
+/// Named and unnamed instructions stored per program since the last report. An instruction is
+/// unnamed if its program is not decoded by the parser
+pub struct DecodingCoverage {
+    /// Programs with the most unnamed instructions which get their own label
+    top_unnamed_programs: usize,
+    /// Share of the stored instructions of an unnamed program a warning is logged at
+    unnamed_share_warning: f64,
+    named: HashMap<String, u64>,
+    unnamed: HashMap<String, u64>,
+}
+
+#[derive(Debug, PartialEq)]
+struct CoverageReport {
+    named: Vec<(String, u64)>,
+    /// The top unnamed programs, the most frequent first, and `OTHER_PROGRAMS` for the rest
+    unnamed: Vec<(String, u64)>,
+    /// Unnamed programs over the warning share with their share
+    undecoded: Vec<(String, f64)>,
+}
+
+impl DecodingCoverage {
+    pub fn new(config: &CollectorConfig) -> Self {
+        Self {
+            top_unnamed_programs: config.coverage_top_unnamed_programs,
+            unnamed_share_warning: config.coverage_unnamed_share_warning,
+            named: HashMap::new(),
+            unnamed: HashMap::new(),
+        }
+    }
+
+    pub fn count(&mut self, instruction: &Instruction) {
+        let counts = if instruction.instruction_name.is_empty() {
+            &mut self.unnamed
+        } else {
+            &mut self.named
+        };
+
+        *counts.entry(instruction.program.clone()).or_default() += 1;
+    }
+
+    /// Exports the counts of the interval and starts a new one
+    pub fn report(&mut self) {
+        let report = self.take_report();
+
+        for (program, count) in &report.named {
+            metrics_update!(inc by INSTRUCTIONS_NAMED_COUNT, &[program], *count);
+        }
+
+        for (program, count) in &report.unnamed {
+            metrics_update!(inc by INSTRUCTIONS_UNNAMED_COUNT, &[program], *count);
+        }
+
+        for (program, share) in &report.undecoded {
+            warn!(
+                actor = "instructions_collector",
+                program = program.as_str();
+                "{}: {:.1}% of the stored instructions are not decoded, the program may need a decoder",
+                program,
+                share * 100.0
+            );
+        }
+    }
+
+    fn take_report(&mut self) -> CoverageReport {
+        let named = std::mem::take(&mut self.named);
+        let mut unnamed: Vec<(String, u64)> =
+            std::mem::take(&mut self.unnamed).into_iter().collect();
+
+        let total =
+            named.values().sum::<u64>() + unnamed.iter().map(|(_, count)| count).sum::<u64>();
+
+        unnamed.sort_by(|(program_a, count_a), (program_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| program_a.cmp(program_b))
+        });
+
+        let undecoded = unnamed
+            .iter()
+            .map(|(program, count)| (program.clone(), *count as f64 / total as f64))
+            .filter(|(_, share)| *share > self.unnamed_share_warning)
+            .collect();
+
+        if unnamed.len() > self.top_unnamed_programs {
+            let others = unnamed
+                .split_off(self.top_unnamed_programs)
+                .into_iter()
+                .map(|(_, count)| count)
+                .sum();
+            unnamed.push((OTHER_PROGRAMS.to_string(), others));
+        }
+
+        let mut named: Vec<(String, u64)> = named.into_iter().collect();
+        named.sort();
+
+        CoverageReport {
+            named,
+            unnamed,
+            undecoded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+    use std::str::FromStr;
+
+    const STAKE_PROGRAM: &str = "Stake11111111111111111111111111111111111111";
+
+    fn coverage(top_unnamed_programs: usize, unnamed_share_warning: f64) -> DecodingCoverage {
+        DecodingCoverage::new(&CollectorConfig {
+            coverage_top_unnamed_programs: top_unnamed_programs,
+            coverage_unnamed_share_warning: unnamed_share_warning,
+            ..Default::default()
+        })
+    }
+
+    fn count(coverage: &mut DecodingCoverage, program: &str, name: &str, times: usize) {
+        let mut instruction =
+            Instruction::new(&Pubkey::from_str(program).unwrap(), &Signature::default());
+        instruction.instruction_name = name.to_string();
+
+        for _ in 0..times {
+            coverage.count(&instruction);
+        }
+    }
+
+    #[test]
+    fn unnamed_programs_out_of_top_are_aggregated() {
+        let programs: Vec<String> = (0..4).map(|_| Pubkey::new_unique().to_string()).collect();
+        let mut coverage = coverage(2, 1.0);
+
+        count(&mut coverage, STAKE_PROGRAM, "DelegateStake", 5);
+        count(&mut coverage, &programs[0], "", 1);
+        count(&mut coverage, &programs[1], "", 4);
+        count(&mut coverage, &programs[2], "", 3);
+        count(&mut coverage, &programs[3], "", 2);
+
+        let report = coverage.take_report();
+        assert_eq!(report.named, vec![(STAKE_PROGRAM.to_string(), 5)]);
+        assert_eq!(
+            report.unnamed,
+            vec![
+                (programs[1].clone(), 4),
+                (programs[2].clone(), 3),
+                (OTHER_PROGRAMS.to_string(), 3),
+            ]
+        );
+        assert!(report.undecoded.is_empty());
+    }
+
+    #[test]
+    fn unnamed_programs_over_share_are_reported() {
+        let program = Pubkey::new_unique().to_string();
+        let mut coverage = coverage(10, 0.25);
+
+        count(&mut coverage, STAKE_PROGRAM, "DelegateStake", 6);
+        count(&mut coverage, &program, "", 4);
+
+        assert_eq!(coverage.take_report().undecoded, vec![(program, 0.4)]);
+
+        // Every interval starts from scratch
+        assert_eq!(
+            coverage.take_report(),
+            CoverageReport {
+                named: Vec::new(),
+                unnamed: Vec::new(),
+                undecoded: Vec::new(),
+            }
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use super::main_storage_manager::MainStorageManagerHandle;
 use crate::errors::ParseInstructionError;
 use crate::metrics_update;
+use crate::storages::main_storage::row_buffer::RowBuffer;
 use crate::{register::Register, storages::main_storage::ErroneousTransaction};
 use anyhow::Result;
 use log::{error, info};
@@ -15,7 +16,9 @@ const FLUSH_BUFFER_TIMEOUT: u64 = 3000;
 
 #[derive(ActorInstance)]
 struct ErroneousTransactionsCollector {
-    erroneous_transactions: Vec<ErroneousTransaction>,
+    erroneous_transactions: RowBuffer<ErroneousTransaction>,
+    /// Approximate bytes of the buffer at which it is flushed
+    max_buffer_bytes: usize,
     main_storage_manager: MainStorageManagerHandle,
     receiver: mpsc::Receiver<ErroneousTransactionsCollectorMessage>,
     tick_receiver: mpsc::Receiver<()>,
@@ -35,13 +38,15 @@ impl ErroneousTransactionsCollector {
         receiver: mpsc::Receiver<ErroneousTransactionsCollectorMessage>,
         tick_receiver: mpsc::Receiver<()>,
     ) -> Result<Self> {
-        let erroneous_transactions = Vec::with_capacity(ERRONEOUS_TRANSACTIONS_BUFFER_SIZE);
+        let erroneous_transactions = RowBuffer::with_capacity(ERRONEOUS_TRANSACTIONS_BUFFER_SIZE);
+        let max_buffer_bytes = register.config.get_collector_config().max_buffer_bytes;
         let main_storage_manager = MainStorageManagerHandle::new(register).await?;
 
         metrics_update!(inc total ACTIVE_ACTOR_INSTANCES_COUNT, &["erroneous_transactions_collector"]);
 
         Ok(ErroneousTransactionsCollector {
             erroneous_transactions,
+            max_buffer_bytes,
             main_storage_manager,
             receiver,
             tick_receiver,
@@ -72,6 +77,14 @@ impl ErroneousTransactionsCollector {
         }
     }
 
+    fn update_buffered_bytes(&self) {
+        metrics_update!(
+            set COLLECTOR_BUFFERED_BYTES,
+            &["erroneous_transactions_collector"],
+            self.erroneous_transactions.bytes() as f64
+        );
+    }
+
     async fn run(&mut self) {
         loop {
             tokio::select! {
@@ -90,10 +103,13 @@ impl ErroneousTransactionsCollector {
         self.erroneous_transactions.push(erroneous_transaction);
         self.ticks = 0;
 
-        if self.erroneous_transactions.len() >= ERRONEOUS_TRANSACTIONS_BUFFER_SIZE {
+        if self.erroneous_transactions.len() >= ERRONEOUS_TRANSACTIONS_BUFFER_SIZE
+            || self.erroneous_transactions.bytes() >= self.max_buffer_bytes
+        {
             self.flush_buffer().await;
             info!("1. Flushed erroneous_transactions buffer because a threshold is reached");
         }
+        self.update_buffered_bytes();
     }
 
     async fn flush_buffer(&mut self) {
@@ -114,6 +130,7 @@ impl ErroneousTransactionsCollector {
                 Err(err) => error!("Erroneous transactions were not stored: {:#?}", err),
             }
         }
+        self.update_buffered_bytes();
     }
 }
 
@@ -27,6 +27,14 @@ enum MainStorageManagerMessage {
         erroneous_transactions: Vec<ErroneousTransaction>,
         respond_to: oneshot::Sender<Result<()>>,
     },
+    StoreErroneousRowsBlock {
+        erroneous_rows: Vec<ErroneousRow>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreBatchesBlock {
+        batches: Vec<BatchMarker>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     StoreDelegationsBlock {
         delegations: Vec<Delegation>,
         respond_to: oneshot::Sender<Result<()>>,
@@ -35,19 +43,89 @@ enum MainStorageManagerMessage {
         undelegations: Vec<Delegation>,
         respond_to: oneshot::Sender<Result<()>>,
     },
+    StoreDelegationsDailyBlock {
+        delegations_daily: Vec<DelegationsDaily>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreBlocksBlock {
+        blocks: Vec<Block>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreTokenTransfersBlock {
+        token_transfers: Vec<TokenTransfer>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreNftEventsBlock {
+        nft_events: Vec<NftEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreCommissionChangesBlock {
+        commission_changes: Vec<CommissionChange>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreClaimEventsBlock {
+        claim_events: Vec<ClaimEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreEntanglerSwapsBlock {
+        entangler_swaps: Vec<EntanglerSwap>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreAnchorEventsBlock {
+        anchor_events: Vec<AnchorEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreSaleEventsBlock {
+        sale_events: Vec<SaleEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StorePackEventsBlock {
+        pack_events: Vec<PackEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreCandyMintsBlock {
+        candy_mints: Vec<CandyMint>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreStakeAccountEventsBlock {
+        stake_account_events: Vec<StakeAccountEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreBidEventsBlock {
+        bid_events: Vec<BidEvent>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    StoreSolTransfersBlock {
+        sol_transfers: Vec<SolTransfer>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    GetBlockTime {
+        slot: u64,
+        respond_to: oneshot::Sender<Result<Option<i64>>>,
+    },
+    GetDelegationsForStakeAcc {
+        stake_acc: String,
+        from_slot: u64,
+        to_slot: u64,
+        respond_to: oneshot::Sender<Result<Vec<Delegation>>>,
+    },
+    GetIndexingWatermark {
+        respond_to: oneshot::Sender<Result<Option<u64>>>,
+    },
+    StoreIndexingWatermark {
+        slot: u64,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
 }
 
 impl MainStorageManager {
-    async fn new(
-        register: &Register,
+    fn new(
+        storage: Box<dyn MainStorage>,
         receiver: mpsc::Receiver<MainStorageManagerMessage>,
-    ) -> Result<Self> {
+    ) -> Self {
         metrics_update!(inc total ACTIVE_ACTOR_INSTANCES_COUNT, &["main_storage_manager"]);
 
-        let storage =
-            connect_main_storage(&register.config.get_main_storage_config().database_url).await?;
-
-        Ok(MainStorageManager { receiver, storage })
+        MainStorageManager { receiver, storage }
     }
 
     async fn handle_message(&mut self, msg: MainStorageManagerMessage) {
@@ -86,6 +164,23 @@ impl MainStorageManager {
                     .await;
                 let _ = respond_to.send(result);
             }
+            MainStorageManagerMessage::StoreErroneousRowsBlock {
+                respond_to,
+                erroneous_rows,
+            } => {
+                let result = self
+                    .storage
+                    .store_erroneous_rows_block(erroneous_rows)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreBatchesBlock {
+                respond_to,
+                batches,
+            } => {
+                let result = self.storage.store_batches_block(batches).await;
+                let _ = respond_to.send(result);
+            }
             MainStorageManagerMessage::StoreDelegationsBlock {
                 respond_to,
                 delegations,
@@ -100,6 +195,140 @@ impl MainStorageManager {
                 let result = self.storage.store_undelegations_block(undelegations).await;
                 let _ = respond_to.send(result);
             }
+            MainStorageManagerMessage::StoreDelegationsDailyBlock {
+                respond_to,
+                delegations_daily,
+            } => {
+                let result = self
+                    .storage
+                    .store_delegations_daily_block(delegations_daily)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreBlocksBlock { respond_to, blocks } => {
+                let result = self.storage.store_blocks_block(blocks).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreTokenTransfersBlock {
+                respond_to,
+                token_transfers,
+            } => {
+                let result = self
+                    .storage
+                    .store_token_transfers_block(token_transfers)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreNftEventsBlock {
+                respond_to,
+                nft_events,
+            } => {
+                let result = self.storage.store_nft_events_block(nft_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreCommissionChangesBlock {
+                respond_to,
+                commission_changes,
+            } => {
+                let result = self
+                    .storage
+                    .store_commission_changes_block(commission_changes)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreClaimEventsBlock {
+                respond_to,
+                claim_events,
+            } => {
+                let result = self.storage.store_claim_events_block(claim_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreEntanglerSwapsBlock {
+                respond_to,
+                entangler_swaps,
+            } => {
+                let result = self
+                    .storage
+                    .store_entangler_swaps_block(entangler_swaps)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreAnchorEventsBlock {
+                respond_to,
+                anchor_events,
+            } => {
+                let result = self.storage.store_anchor_events_block(anchor_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreSaleEventsBlock {
+                respond_to,
+                sale_events,
+            } => {
+                let result = self.storage.store_sale_events_block(sale_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StorePackEventsBlock {
+                respond_to,
+                pack_events,
+            } => {
+                let result = self.storage.store_pack_events_block(pack_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreCandyMintsBlock {
+                respond_to,
+                candy_mints,
+            } => {
+                let result = self.storage.store_candy_mints_block(candy_mints).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreStakeAccountEventsBlock {
+                respond_to,
+                stake_account_events,
+            } => {
+                let result = self
+                    .storage
+                    .store_stake_account_events_block(stake_account_events)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreBidEventsBlock {
+                respond_to,
+                bid_events,
+            } => {
+                let result = self.storage.store_bid_events_block(bid_events).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreSolTransfersBlock {
+                respond_to,
+                sol_transfers,
+            } => {
+                let result = self.storage.store_sol_transfers_block(sol_transfers).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::GetBlockTime { slot, respond_to } => {
+                let result = self.storage.get_block_time(slot).await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::GetDelegationsForStakeAcc {
+                stake_acc,
+                from_slot,
+                to_slot,
+                respond_to,
+            } => {
+                let result = self
+                    .storage
+                    .get_delegations_for_stake_acc(&stake_acc, from_slot, to_slot)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::GetIndexingWatermark { respond_to } => {
+                let result = self.storage.get_indexing_watermark().await;
+                let _ = respond_to.send(result);
+            }
+            MainStorageManagerMessage::StoreIndexingWatermark { slot, respond_to } => {
+                let result = self.storage.store_indexing_watermark(slot).await;
+                let _ = respond_to.send(result);
+            }
         }
     }
 
@@ -117,13 +346,20 @@ pub struct MainStorageManagerHandle {
 
 impl MainStorageManagerHandle {
     pub async fn new(register: &Register) -> Result<Self> {
+        let storage = connect_main_storage(register.config.get_main_storage_config()).await?;
+
+        Ok(Self::with_storage(storage))
+    }
+
+    /// Runs the manager on top of an already connected storage
+    pub fn with_storage(storage: Box<dyn MainStorage>) -> Self {
         let (sender, receiver) = mpsc::channel(100);
-        let mut main_storage_manager = MainStorageManager::new(register, receiver).await?;
+        let mut main_storage_manager = MainStorageManager::new(storage, receiver);
         tokio::spawn(async move { main_storage_manager.run().await });
 
         metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &["main_storage_manager_handle"]);
 
-        Ok(Self { sender })
+        Self { sender }
     }
 
     pub async fn store_instructions_block(&mut self, instructions: &[Instruction]) -> Result<()> {
@@ -202,6 +438,283 @@ impl MainStorageManagerHandle {
             .expect("MainStorageManager task has been killed")
     }
 
+    pub async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreDelegationsDailyBlock {
+            delegations_daily,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_blocks_block(&mut self, blocks: Vec<Block>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreBlocksBlock {
+            blocks,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_token_transfers_block(
+        &mut self,
+        token_transfers: Vec<TokenTransfer>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreTokenTransfersBlock {
+            token_transfers,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreNftEventsBlock {
+            nft_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreCommissionChangesBlock {
+            commission_changes,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreClaimEventsBlock {
+            claim_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_entangler_swaps_block(
+        &mut self,
+        entangler_swaps: Vec<EntanglerSwap>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreEntanglerSwapsBlock {
+            entangler_swaps,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_anchor_events_block(
+        &mut self,
+        anchor_events: Vec<AnchorEvent>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreAnchorEventsBlock {
+            anchor_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreSaleEventsBlock {
+            sale_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StorePackEventsBlock {
+            pack_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreCandyMintsBlock {
+            candy_mints,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreStakeAccountEventsBlock {
+            stake_account_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreBidEventsBlock {
+            bid_events,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_sol_transfers_block(
+        &mut self,
+        sol_transfers: Vec<SolTransfer>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreSolTransfersBlock {
+            sol_transfers,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::GetBlockTime {
+            slot,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: String,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::GetDelegationsForStakeAcc {
+            stake_acc,
+            from_slot,
+            to_slot,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::GetIndexingWatermark { respond_to: sender };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreIndexingWatermark {
+            slot,
+            respond_to: sender,
+        };
+
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
     pub async fn store_erroneous_transactions_block(
         &mut self,
         erroneous_transactions: &[ErroneousTransaction],
@@ -217,4 +730,33 @@ impl MainStorageManagerHandle {
             .await
             .expect("MainStorageManager task has been killed")
     }
+
+    pub async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreErroneousRowsBlock {
+            erroneous_rows,
+            respond_to: sender,
+        };
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
+
+    pub async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = MainStorageManagerMessage::StoreBatchesBlock {
+            batches,
+            respond_to: sender,
+        };
+        let _ = self.sender.send(msg).await;
+
+        receiver
+            .await
+            .expect("MainStorageManager task has been killed")
+    }
 }
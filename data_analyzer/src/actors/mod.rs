@@ -1,4 +1,5 @@
 pub mod collector;
+pub mod decoding_coverage;
 pub mod erroneous_transactions_collector;
 pub mod main_storage_manager;
 pub mod prometheus_exporter;
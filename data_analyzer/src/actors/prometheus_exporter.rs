@@ -1,66 +1,324 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use hyper::{
     header::CONTENT_TYPE,
     service::{make_service_fn, service_fn},
-    Body, Response, Server,
+    Body, Request, Response, Server,
 };
 use lazy_static::lazy_static;
 use log::{error, info};
 use prometheus::{
-    register_gauge_vec_with_registry, register_gauge_with_registry,
-    register_histogram_vec_with_registry, register_histogram_with_registry, Encoder, Gauge,
-    GaugeVec, Histogram, HistogramVec, Registry, TextEncoder,
+    core::Collector, register_gauge_vec_with_registry, register_gauge_with_registry,
+    register_histogram_vec_with_registry, register_histogram_with_registry,
+    register_int_counter_vec_with_registry, register_int_counter_with_registry, Encoder, Gauge,
+    GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Registry,
+    TextEncoder,
 };
 
+use crate::actors::queue_manager::StorageType;
+use crate::configuration::{MainStorageConfig, QueueStorageConfig};
+use crate::health::{readiness_response, SharedHealthState};
 use crate::register::Register;
+use crate::storages::main_storage::connect_main_storage;
+use crate::storages::postgre_storage::PostgreStorage;
 
 struct PrometheusExporter {
     bind_address: String,
+    registry: Registry,
+    readiness: Readiness,
+}
+
+/// What the readiness probe checks
+#[derive(Clone)]
+struct Readiness {
+    queue_storage: QueueStorageConfig,
+    main_storage: MainStorageConfig,
+    health: SharedHealthState,
+    staleness: Duration,
+}
+
+impl Readiness {
+    async fn check_queue_storage(&self) -> Result<(), String> {
+        match self.queue_storage.storage_type {
+            StorageType::PostgreSQL => {
+                for storage_url in self.queue_storage.storage_urls() {
+                    tokio::task::spawn_blocking(move || PostgreStorage::ping(&storage_url))
+                        .await
+                        .map_err(|err| err.to_string())?
+                        .map_err(|err| err.to_string())?;
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn check_main_storage(&self) -> Result<(), String> {
+        let mut storage = connect_main_storage(&self.main_storage)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        storage
+            .execute("SELECT 1")
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    fn watermark(&self) -> Option<u64> {
+        self.health.read().unwrap().watermark()
+    }
+
+    async fn respond(&self) -> Response<Body> {
+        let last_batch = self.health.read().unwrap().check_staleness(self.staleness);
+
+        readiness_response(
+            &[
+                ("queue_storage", self.check_queue_storage().await),
+                ("main_storage", self.check_main_storage().await),
+                ("last_batch", last_batch),
+            ],
+            self.watermark(),
+        )
+    }
 }
 
 lazy_static! {
-    pub static ref REGISTRY: Registry =
-        Registry::new_custom(Some("analyzer".to_string()), None).unwrap();
+    /// Registry of the metrics of the service
+    pub static ref REGISTRY: Registry = indexer_telemetry::service_registry("data_analyzer");
+    pub static ref BUILD_INFO: IntGaugeVec =
+        indexer_telemetry::register_build_info("da", env!("CARGO_PKG_VERSION"), &REGISTRY).unwrap();
     pub static ref ACTIVE_WORKERS_COUNT: GaugeVec = register_gauge_vec_with_registry!(
-        "active_workers_counT",
+        "da_active_workers_counT",
         "Number of active workers which are processing transactions, metadata, etc.",
         &["worker"],
         REGISTRY
     )
     .unwrap();
     pub static ref ACTIVE_HANDLE_INSTANCES_COUNT: GaugeVec = register_gauge_vec_with_registry!(
-        "active_handle_instances_count",
+        "da_active_handle_instances_count",
         "Number of active 'handle' instances",
         &["instance"],
         REGISTRY
     )
     .unwrap();
     pub static ref ACTIVE_ACTOR_INSTANCES_COUNT: GaugeVec = register_gauge_vec_with_registry!(
-        "active_actor_instances_count",
+        "da_active_actor_instances_count",
         "Number of active 'actor' instances",
         &["instance"],
         REGISTRY
     )
     .unwrap();
     pub static ref ERRONEOUS_TRANSACTIONS_COUNT: Gauge = register_gauge_with_registry!(
-        "erroneous_transactions_count",
+        "da_erroneous_transactions_count",
         "Number of erroneous transactions stored",
         REGISTRY
     )
     .unwrap();
     pub static ref TRANSACTION_PARSING_TIME: Histogram = register_histogram_with_registry!(
-        "transaction_parsing_time",
+        "da_transaction_parsing_time",
         "Time spent in seconds parsing transaction",
         REGISTRY
     )
     .unwrap();
     pub static ref LOOP_TIME: HistogramVec = register_histogram_vec_with_registry!(
-        "loop_time",
+        "da_loop_time",
         "Time spent in seconds for one worker's loop",
         &["worker"],
         REGISTRY
     )
     .unwrap();
+    pub static ref TRANSACTIONS_PARSED_COUNT: IntCounter = register_int_counter_with_registry!(
+        "da_transactions_parsed_total",
+        "Number of successfully parsed transactions",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref INSTRUCTIONS_PARSED_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_instructions_parsed_total",
+            "Number of parsed instructions per program",
+            &["program"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref UNKNOWN_INSTRUCTIONS_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_unknown_instructions_total",
+            "Number of instructions of the supported programs failed to decode per program",
+            &["program"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref DUPLICATE_INNER_INSTRUCTION_SETS_COUNT: IntCounter =
+        register_int_counter_with_registry!(
+            "da_duplicate_inner_instruction_sets_total",
+            "Number of inner instruction sets merged into a preceding set with the same index",
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref INSTRUCTIONS_NAMED_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_instructions_named_total",
+            "Number of stored instructions decoded by name per program",
+            &["program"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref INSTRUCTIONS_UNNAMED_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_instructions_unnamed_total",
+            "Number of stored instructions of the programs which are not decoded per program, \
+            the programs out of the top ones of a flush interval are counted as \"other\"",
+            &["program"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref INSERT_BISECTIONS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "da_insert_bisections_total",
+        "Number of failed blocks the collector bisected to find the rows the main storage rejects per table",
+        &["table"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref REJECTED_ROWS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "da_rejected_rows_total",
+        "Number of rows the main storage rejected on their own which are stored into erroneous_rows per table",
+        &["table"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref ROWS_SKIPPED_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "da_rows_skipped_total",
+        "Number of rows not persisted because of the programs filter per table and program",
+        &["table", "program"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref ERRORS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "da_errors_total",
+        "Number of failed requests to the storages and actors which are retried per error class",
+        &["class"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref PARSE_ERRORS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "da_parse_errors_total",
+        "Number of transactions failed to parse per error kind",
+        &["kind"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref QUEUE_SHARD_OPERATIONS_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_queue_shard_operations_total",
+            "Number of operations of the queue storage run on the shard, a single database is \
+            shard 0",
+            &["shard", "operation"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref BLOCK_TIME_RESOLUTIONS_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "da_block_time_resolutions_total",
+            "Number of transactions loaded without block_time per source which resolved it, \
+            \"unresolved\" when none did and the instructions are stored without it",
+            &["source"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref COLLECTOR_BUFFERED_BYTES: GaugeVec = register_gauge_vec_with_registry!(
+        "da_collector_buffered_bytes",
+        "Approximate bytes of the rows buffered by a collector",
+        &["collector"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref IN_FLIGHT_BATCHES: Gauge = register_gauge_with_registry!(
+        "da_in_flight_batches",
+        "Number of batches of transactions taken from the queue whose rows are not flushed yet",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref QUEUE_PENDING_COUNT: Gauge = register_gauge_with_registry!(
+        "da_queue_pending_total",
+        "Number of transactions of the queue waiting to be parsed, all the shards together",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref QUEUE_OLDEST_PENDING_AGE: Gauge = register_gauge_with_registry!(
+        "da_queue_oldest_pending_age_seconds",
+        "Seconds since the block time of the oldest transaction waiting to be parsed, 0 when the queue is empty",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref INDEXING_WATERMARK: Gauge = register_gauge_with_registry!(
+        "da_indexing_watermark_slot",
+        "Highest slot up to which every signature of the queue is parsed or skipped, 0 until it is known",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref MAIN_STORAGE_REPLICA_HEALTHY: GaugeVec = register_gauge_vec_with_registry!(
+        "da_main_storage_replica_healthy",
+        "1 if the last request to the replica of the main storage succeeded, 0 if it failed to connect",
+        &["address"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref DELEGATIONS_COUNT: IntCounter = register_int_counter_with_registry!(
+        "da_delegations_total",
+        "Number of stored delegations",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref UNDELEGATIONS_COUNT: IntCounter = register_int_counter_with_registry!(
+        "da_undelegations_total",
+        "Number of stored undelegations",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref BATCH_PARSING_TIME: Histogram = register_histogram_with_registry!(
+        "da_batch_parse_seconds",
+        "Time spent in seconds parsing a batch of transactions taken from the queue",
+        REGISTRY
+    )
+    .unwrap();
+}
+
+/// Every metric of the service, so they are exported from the start instead of their first use
+pub fn metrics() -> Vec<&'static dyn Collector> {
+    vec![
+        &*BUILD_INFO,
+        &*ACTIVE_WORKERS_COUNT,
+        &*ACTIVE_HANDLE_INSTANCES_COUNT,
+        &*ACTIVE_ACTOR_INSTANCES_COUNT,
+        &*ERRONEOUS_TRANSACTIONS_COUNT,
+        &*TRANSACTION_PARSING_TIME,
+        &*LOOP_TIME,
+        &*TRANSACTIONS_PARSED_COUNT,
+        &*INSTRUCTIONS_PARSED_COUNT,
+        &*UNKNOWN_INSTRUCTIONS_COUNT,
+        &*DUPLICATE_INNER_INSTRUCTION_SETS_COUNT,
+        &*INSTRUCTIONS_NAMED_COUNT,
+        &*INSTRUCTIONS_UNNAMED_COUNT,
+        &*INSERT_BISECTIONS_COUNT,
+        &*REJECTED_ROWS_COUNT,
+        &*ROWS_SKIPPED_COUNT,
+        &*ERRORS_COUNT,
+        &*PARSE_ERRORS_COUNT,
+        &*QUEUE_SHARD_OPERATIONS_COUNT,
+        &*BLOCK_TIME_RESOLUTIONS_COUNT,
+        &*COLLECTOR_BUFFERED_BYTES,
+        &*IN_FLIGHT_BATCHES,
+        &*QUEUE_PENDING_COUNT,
+        &*QUEUE_OLDEST_PENDING_AGE,
+        &*INDEXING_WATERMARK,
+        &*MAIN_STORAGE_REPLICA_HEALTHY,
+        &*DELEGATIONS_COUNT,
+        &*UNDELEGATIONS_COUNT,
+        &*BATCH_PARSING_TIME,
+    ]
 }
 
 #[macro_export]
@@ -75,6 +333,16 @@ macro_rules! metrics_update {
             .inc();
     };
 
+    ( inc by $metric:ident, $labels:expr, $val:expr) => {
+        $crate::actors::prometheus_exporter::$metric
+            .with_label_values($labels)
+            .inc_by($val);
+    };
+
+    ( inc by $metric:ident, $val:expr) => {
+        $crate::actors::prometheus_exporter::$metric.inc_by($val);
+    };
+
     ( inc total $metric:ident, $labels:expr) => {
         $crate::actors::prometheus_exporter::$metric
             .with_label_values($labels)
@@ -147,33 +415,39 @@ macro_rules! metrics_update {
 impl PrometheusExporter {
     async fn new(register: &Register) -> Result<Self> {
         let bind_address = register.config.get_prometheus_exporter_bind_address();
-        Ok(PrometheusExporter { bind_address })
+        let readiness = Readiness {
+            queue_storage: register.config.get_queue_storage_config().clone(),
+            main_storage: register.config.get_main_storage_config().clone(),
+            health: register.health.clone(),
+            staleness: Duration::from_secs(register.config.get_readiness_staleness()),
+        };
+
+        metrics();
+
+        Ok(PrometheusExporter {
+            bind_address,
+            registry: register.registry.clone(),
+            readiness,
+        })
     }
 
     async fn start_server(&self) {
         let addr = self.bind_address.parse().unwrap();
+        let registry = self.registry.clone();
+        let readiness = self.readiness.clone();
 
         let prometheus_join_handle = tokio::spawn(async move {
             info!("Prometheus exporter started on http://{}", addr);
 
-            let serve_future = Server::bind(&addr).serve(make_service_fn(|_| async {
-                Ok::<_, hyper::Error>(service_fn(|_req| async {
-                    let encoder = TextEncoder::new();
-
-                    let metric_families = REGISTRY.gather();
-                    // let metric_families = prometheus::gather();
-                    let mut buffer = vec![];
+            let serve_future = Server::bind(&addr).serve(make_service_fn(move |_| {
+                let registry = registry.clone();
+                let readiness = readiness.clone();
 
-                    encoder.encode(&metric_families, &mut buffer).unwrap();
-
-                    let response = Response::builder()
-                        .status(200)
-                        .header(CONTENT_TYPE, encoder.format_type())
-                        .body(Body::from(buffer))
-                        .unwrap();
-
-                    Ok::<_, hyper::Error>(response)
-                }))
+                async move {
+                    Ok::<_, hyper::Error>(service_fn(move |request| {
+                        Self::respond(request, registry.clone(), readiness.clone())
+                    }))
+                }
             }));
 
             if let Err(err) = serve_future.await {
@@ -186,6 +460,33 @@ impl PrometheusExporter {
         }
     }
 
+    async fn respond(
+        request: Request<Body>,
+        registry: Registry,
+        readiness: Readiness,
+    ) -> Result<Response<Body>, hyper::Error> {
+        let response = match request.uri().path() {
+            "/healthz" => readiness_response(&[], readiness.watermark()),
+            "/readyz" => readiness.respond().await,
+            _ => {
+                let encoder = TextEncoder::new();
+
+                let metric_families = registry.gather();
+                let mut buffer = vec![];
+
+                encoder.encode(&metric_families, &mut buffer).unwrap();
+
+                Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, encoder.format_type())
+                    .body(Body::from(buffer))
+                    .unwrap()
+            }
+        };
+
+        Ok(response)
+    }
+
     async fn run(&mut self) {
         self.start_server().await;
     }
@@ -203,3 +504,111 @@ impl PrometheusExporterHandle {
         Ok(Self {})
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::transaction_parser::program_name;
+    use crate::errors::ParseInstructionError;
+    use prometheus::proto::MetricType;
+
+    /// `# HELP` and `# TYPE` lines of the metrics in the /metrics output, where the metrics with
+    /// labels appear after their first samples
+    fn descriptions() -> String {
+        let mut families: Vec<_> = metrics()
+            .iter()
+            .flat_map(|metric| metric.collect())
+            .collect();
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        families
+            .iter()
+            .map(|family| {
+                let metric_type = match family.get_field_type() {
+                    MetricType::COUNTER => "counter",
+                    MetricType::GAUGE => "gauge",
+                    MetricType::HISTOGRAM => "histogram",
+                    MetricType::SUMMARY => "summary",
+                    MetricType::UNTYPED => "untyped",
+                };
+
+                format!(
+                    "# HELP {name} {}\n# TYPE {name} {}\n",
+                    family.get_help(),
+                    metric_type,
+                    name = family.get_name()
+                )
+            })
+            .collect()
+    }
+
+    /// The dashboards and the alerts refer to the metrics by their names, a renamed metric has
+    /// to be renamed in `metrics.golden` as well
+    #[test]
+    fn metrics_are_not_renamed() {
+        assert_eq!(descriptions(), include_str!("metrics.golden"));
+    }
+
+    /// Labels of every sample of the metric as the registry exports them
+    fn sample_labels(name: &str) -> Vec<Vec<(String, String)>> {
+        REGISTRY
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == name)
+            .flat_map(|family| family.get_metric())
+            .map(|metric| {
+                let mut labels: Vec<_> = metric
+                    .get_label()
+                    .iter()
+                    .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+                    .collect();
+                labels.sort();
+                labels
+            })
+            .collect()
+    }
+
+    fn labels(labels: &[(&str, &str)]) -> Vec<(String, String)> {
+        labels
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// The dashboards select the samples of the parsing metrics by their labels, the program
+    /// is labelled by its name and the error by its kind
+    #[test]
+    fn parsing_metrics_are_labelled() {
+        TRANSACTIONS_PARSED_COUNT.inc_by(0);
+        BATCH_PARSING_TIME.observe(0.0);
+        for program in [
+            "Stake11111111111111111111111111111111111111",
+            "Unknown111111111111111111111111111111111111",
+        ] {
+            INSTRUCTIONS_PARSED_COUNT
+                .with_label_values(&[program_name(program)])
+                .inc_by(0);
+        }
+        PARSE_ERRORS_COUNT
+            .with_label_values(&[ParseInstructionError::DeserializeFromBase58Error.kind()])
+            .inc_by(0);
+
+        let service = ("service", "data_analyzer");
+
+        assert_eq!(
+            sample_labels("da_transactions_parsed_total"),
+            vec![labels(&[service])]
+        );
+        assert_eq!(
+            sample_labels("da_batch_parse_seconds"),
+            vec![labels(&[service])]
+        );
+
+        let instructions = sample_labels("da_instructions_parsed_total");
+        assert!(instructions.contains(&labels(&[("program", "stake"), service])));
+        assert!(instructions.contains(&labels(&[("program", "other"), service])));
+
+        assert!(sample_labels("da_parse_errors_total")
+            .contains(&labels(&[("kind", "DeserializeFromBase58Error"), service])));
+    }
+}
@@ -1,18 +1,105 @@
 use crate::{
-    errors::QueueManagerError, metrics_update, register::Register,
-    storages::postgre_storage::models::Delegation, storages::postgre_storage::*,
-    storages::QueueStorage,
+    actors::transaction_parser::transaction_signature,
+    backpressure::InFlightBatches,
+    errors::QueueManagerError,
+    metrics_update,
+    register::Register,
+    signature_tracing::{SignatureTracer, TraceEvent},
+    storages::main_storage::{connect_main_storage, Metadata},
+    storages::memory::MemoryQueueStorage,
+    storages::postgre_storage::models::Delegation,
+    storages::postgre_storage::*,
+    storages::read_only::{QueueReader, ReadOnlyQueueStorage},
+    storages::sharded::ShardedQueueStorage,
+    storages::{QueueProgress, QueueStats, QueueStorage},
 };
 use anyhow::Result;
 use macros::{ActorInstance, HandleInstance};
 use serde::Deserialize;
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
 
 #[derive(ActorInstance)]
 struct QueueManager {
     receiver: mpsc::Receiver<QueueManagerMessage>,
     storage: Box<dyn QueueStorage>,
+    delegations_cache: DelegationsCache,
+    tracer: SignatureTracer,
+    in_flight_batches: InFlightBatches,
+}
+
+/// Least recently used delegations of stake accounts as they are stored in the queue storage,
+/// including the stake accounts the storage has no delegation of. Only the queue manager writes
+/// delegations, so the cache is updated along with the storage; the ones saved by other
+/// analyzers sharing the queue are seen once the entries older than `ttl` are looked up again
+struct DelegationsCache {
+    capacity: usize,
+    /// Entries older than that are looked up in the storage again, `None` keeps them until they
+    /// are evicted
+    ttl: Option<Duration>,
+    /// Delegation of a stake account, `None` if it's unknown to the storage, the tick it has
+    /// been used at and the time it has been stored at
+    entries: HashMap<String, (Option<Delegation>, u64, Instant)>,
+    /// Stake accounts by the tick they have been used at, the first one is evicted
+    usage: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl DelegationsCache {
+    fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            usage: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// `Some(None)` if the storage is known to have no delegation of the stake account
+    fn get(&mut self, stake_acc: &str) -> Option<Option<Delegation>> {
+        self.tick += 1;
+
+        let (delegation, used_at, stored_at) = self.entries.get_mut(stake_acc)?;
+        if self.ttl.map_or(false, |ttl| stored_at.elapsed() >= ttl) {
+            self.usage.remove(used_at);
+            self.entries.remove(stake_acc);
+            return None;
+        }
+
+        self.usage.remove(used_at);
+        self.usage.insert(self.tick, stake_acc.to_string());
+        *used_at = self.tick;
+
+        Some(delegation.clone())
+    }
+
+    fn insert(&mut self, stake_acc: String, delegation: Option<Delegation>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+
+        if let Some((_, used_at, _)) = self
+            .entries
+            .insert(stake_acc.clone(), (delegation, self.tick, Instant::now()))
+        {
+            self.usage.remove(&used_at);
+        }
+        self.usage.insert(self.tick, stake_acc);
+
+        while self.entries.len() > self.capacity {
+            match self.usage.pop_first() {
+                Some((_, stake_acc)) => {
+                    self.entries.remove(&stake_acc);
+                }
+                None => break,
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,12 +119,35 @@ enum QueueManagerMessage {
         respond_to: oneshot::Sender<Result<()>>,
         transaction: String,
     },
+    MarkTransactionAsErroneous {
+        respond_to: oneshot::Sender<Result<()>>,
+        transaction: String,
+    },
+    ReclaimStuckTransactions {
+        respond_to: oneshot::Sender<Result<usize>>,
+        timeout: u64,
+    },
+    GetBlocksMetadata {
+        respond_to: oneshot::Sender<Result<Vec<Metadata>>>,
+    },
+    MarkBlocksMetadataAsParsed {
+        respond_to: oneshot::Sender<Result<()>>,
+        slots: Vec<u64>,
+    },
+    GetQueueStats {
+        respond_to: oneshot::Sender<Result<QueueStats>>,
+    },
+    GetQueueProgress {
+        respond_to: oneshot::Sender<Result<QueueProgress>>,
+    },
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub enum StorageType {
     RabbitMQ,
     PostgreSQL,
+    /// Kept in memory, storages with the same url share their data
+    Memory,
 }
 
 impl QueueManager {
@@ -46,49 +156,150 @@ impl QueueManager {
         receiver: mpsc::Receiver<QueueManagerMessage>,
     ) -> Result<Self> {
         let storage_type = register.config.get_storage_type();
-        let storage: Box<dyn QueueStorage> = match storage_type {
-            StorageType::RabbitMQ => {
-                unreachable!()
-            }
-            StorageType::PostgreSQL => {
-                let storage =
-                    PostgreStorage::new(&register.config.get_queue_storage_config().storage_url)
-                        .await?;
-                Box::new(storage)
-            }
+        let queue_storage_config = register.config.get_queue_storage_config();
+        let mut shards = Vec::new();
+
+        for (shard, storage_url) in queue_storage_config.storage_urls().into_iter().enumerate() {
+            let storage: Box<dyn QueueStorage> = if queue_storage_config.read_only {
+                let reader: Box<dyn QueueReader> = match storage_type {
+                    StorageType::RabbitMQ => {
+                        unreachable!()
+                    }
+                    StorageType::PostgreSQL => Box::new(PostgreStorage::new(&storage_url).await?),
+                    StorageType::Memory => Box::new(MemoryQueueStorage::connect(&storage_url)),
+                };
+                let cursors =
+                    connect_main_storage(register.config.get_main_storage_config()).await?;
+
+                Box::new(ReadOnlyQueueStorage::new(reader, cursors, shard).await?)
+            } else {
+                match storage_type {
+                    StorageType::RabbitMQ => {
+                        unreachable!()
+                    }
+                    StorageType::PostgreSQL => Box::new(PostgreStorage::new(&storage_url).await?),
+                    StorageType::Memory => Box::new(MemoryQueueStorage::connect(&storage_url)),
+                }
+            };
+            shards.push(storage);
+        }
+
+        let storage: Box<dyn QueueStorage> = if shards.len() == 1 {
+            shards.remove(0)
+        } else {
+            Box::new(ShardedQueueStorage::new(shards))
         };
 
+        Ok(Self::with_storage(
+            storage,
+            DelegationsCache::new(
+                queue_storage_config.delegations_cache_capacity,
+                (queue_storage_config.delegations_cache_ttl > 0)
+                    .then(|| Duration::from_secs(queue_storage_config.delegations_cache_ttl)),
+            ),
+            register.tracer.clone(),
+            register.in_flight_batches.clone(),
+            receiver,
+        ))
+    }
+
+    fn with_storage(
+        storage: Box<dyn QueueStorage>,
+        delegations_cache: DelegationsCache,
+        tracer: SignatureTracer,
+        in_flight_batches: InFlightBatches,
+        receiver: mpsc::Receiver<QueueManagerMessage>,
+    ) -> Self {
         metrics_update!(inc total ACTIVE_ACTOR_INSTANCES_COUNT, &["queue_manager"]);
 
-        Ok(QueueManager { receiver, storage })
+        QueueManager {
+            receiver,
+            storage,
+            delegations_cache,
+            tracer,
+            in_flight_batches,
+        }
     }
 
     async fn handle_message(&mut self, msg: QueueManagerMessage) {
         match msg {
             QueueManagerMessage::GetTransactions { respond_to } => {
+                // Waits for the collector to flush the previous batches if too many of them
+                // are buffered
+                self.in_flight_batches.acquire().await;
                 let transaction = self.storage.get_transactions().await;
 
+                if transaction.is_empty() {
+                    self.in_flight_batches.release(1);
+                }
+
+                for encoded_transaction in &transaction {
+                    self.tracer.trace(
+                        &transaction_signature(encoded_transaction),
+                        TraceEvent::FetchedByAnalyzer,
+                    );
+                }
+
                 let _ = respond_to.send(transaction);
             }
             QueueManagerMessage::GetDelegations {
                 respond_to,
                 stake_accs,
             } => {
-                let delegations = self.storage.get_delegations(stake_accs).await;
+                let delegations = self.get_delegations(stake_accs).await;
                 let _ = respond_to.send(delegations);
             }
             QueueManagerMessage::SaveDelegations {
                 respond_to,
                 delegations,
             } => {
-                let result = self.storage.save_delegations(delegations).await;
+                let result = self.save_delegations(delegations).await;
                 let _ = respond_to.send(result);
             }
             QueueManagerMessage::MarkTransactionAsParsed {
                 respond_to,
                 transaction,
             } => {
-                let result = self.storage.mark_transaction_as_parsed(transaction).await;
+                let result = self
+                    .storage
+                    .mark_transaction_as_parsed(transaction.clone())
+                    .await;
+                if result.is_ok() {
+                    self.tracer.trace(&transaction, TraceEvent::MarkedParsed);
+                }
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::MarkTransactionAsErroneous {
+                respond_to,
+                transaction,
+            } => {
+                let result = self
+                    .storage
+                    .mark_transaction_as_erroneous(transaction)
+                    .await;
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::ReclaimStuckTransactions {
+                respond_to,
+                timeout,
+            } => {
+                let result = self.storage.reclaim_stuck_transactions(timeout).await;
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::GetBlocksMetadata { respond_to } => {
+                let result = self.storage.get_blocks_metadata().await;
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::MarkBlocksMetadataAsParsed { respond_to, slots } => {
+                let result = self.storage.mark_blocks_metadata_as_parsed(slots).await;
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::GetQueueStats { respond_to } => {
+                let result = self.storage.queue_stats().await;
+                let _ = respond_to.send(result);
+            }
+            QueueManagerMessage::GetQueueProgress { respond_to } => {
+                let result = self.storage.queue_progress().await;
                 let _ = respond_to.send(result);
             }
         }
@@ -99,6 +310,50 @@ impl QueueManager {
             self.handle_message(msg).await;
         }
     }
+
+    /// Only the stake accounts missing from the cache are looked up in the storage, the ones
+    /// the storage has no delegation of are cached as well
+    async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+        let mut delegations = Vec::new();
+        let mut missing = Vec::new();
+
+        for stake_acc in stake_accs.into_iter().collect::<HashSet<_>>() {
+            match self.delegations_cache.get(&stake_acc) {
+                Some(delegation) => delegations.extend(delegation),
+                None => missing.push(stake_acc),
+            }
+        }
+
+        if !missing.is_empty() {
+            let mut unknown: HashSet<String> = missing.iter().cloned().collect();
+
+            for delegation in self.storage.get_delegations(missing).await? {
+                unknown.remove(&delegation.stake_acc);
+                self.delegations_cache
+                    .insert(delegation.stake_acc.clone(), Some(delegation.clone()));
+                delegations.push(delegation);
+            }
+
+            for stake_acc in unknown {
+                self.delegations_cache.insert(stake_acc, None);
+            }
+        }
+
+        Ok(delegations)
+    }
+
+    /// The cache is updated before the response is sent, so the next lookup of the parser
+    /// sees the delegations of the previous transaction
+    async fn save_delegations(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        self.storage.save_delegations(delegations.clone()).await?;
+
+        for delegation in delegations {
+            self.delegations_cache
+                .insert(delegation.stake_acc.clone(), Some(delegation));
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(HandleInstance)]
@@ -116,6 +371,26 @@ impl QueueManagerHandle {
         Ok(Self { sender })
     }
 
+    /// Runs the manager on top of an already connected storage
+    #[cfg(test)]
+    pub(crate) fn with_storage(
+        storage: Box<dyn QueueStorage>,
+        delegations_cache_capacity: usize,
+        in_flight_batches: InFlightBatches,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(100);
+        let mut queue_manager = QueueManager::with_storage(
+            storage,
+            DelegationsCache::new(delegations_cache_capacity, None),
+            SignatureTracer::default(),
+            in_flight_batches,
+            receiver,
+        );
+        tokio::spawn(async move { queue_manager.run().await });
+
+        Self { sender }
+    }
+
     pub async fn get_delegations(
         &mut self,
         stake_accs: Vec<String>,
@@ -130,16 +405,18 @@ impl QueueManagerHandle {
         Ok(receiver.await?)
     }
 
+    /// Saves the vote account and the delegated lamports of every stake account
     pub async fn save_delegations(
         &mut self,
-        delegations: Vec<(String, Option<String>)>,
+        delegations: Vec<(String, Option<String>, Option<u64>)>,
     ) -> Result<(), QueueManagerError> {
         let (sender, receiver) = oneshot::channel();
         let delegations = delegations
             .into_iter()
-            .map(|(stake_acc, vote_acc)| Delegation {
+            .map(|(stake_acc, vote_acc, delegated_amount)| Delegation {
                 stake_acc,
                 vote_acc,
+                delegated_amount: delegated_amount.map(|amount| amount as i64),
             })
             .collect();
 
@@ -176,4 +453,358 @@ impl QueueManagerHandle {
         let _ = self.sender.send(msg).await;
         Ok(receiver.await??)
     }
+
+    pub async fn mark_transaction_as_erroneous(
+        &mut self,
+        transaction: String,
+    ) -> Result<(), QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::MarkTransactionAsErroneous {
+            respond_to: sender,
+            transaction,
+        };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+
+    pub async fn reclaim_stuck_transactions(
+        &mut self,
+        timeout: u64,
+    ) -> Result<usize, QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::ReclaimStuckTransactions {
+            respond_to: sender,
+            timeout,
+        };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+
+    pub async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>, QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::GetBlocksMetadata { respond_to: sender };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+
+    pub async fn mark_blocks_metadata_as_parsed(
+        &mut self,
+        slots: Vec<u64>,
+    ) -> Result<(), QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::MarkBlocksMetadataAsParsed {
+            respond_to: sender,
+            slots,
+        };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+
+    pub async fn get_queue_stats(&mut self) -> Result<QueueStats, QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::GetQueueStats { respond_to: sender };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+
+    pub async fn get_queue_progress(&mut self) -> Result<QueueProgress, QueueManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = QueueManagerMessage::GetQueueProgress { respond_to: sender };
+
+        let _ = self.sender.send(msg).await;
+        Ok(receiver.await??)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// Keeps the delegations in memory and records the stake accounts of every lookup
+    #[derive(Clone, Default)]
+    struct MockStorage {
+        delegations: Arc<Mutex<HashMap<String, Delegation>>>,
+        lookups: Arc<Mutex<Vec<Vec<String>>>>,
+        stats_queries: Arc<Mutex<usize>>,
+    }
+
+    #[async_trait]
+    impl QueueStorage for MockStorage {
+        async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+            Vec::new()
+        }
+
+        async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+            let mut lookup = stake_accs.clone();
+            lookup.sort();
+            self.lookups.lock().unwrap().push(lookup);
+
+            let delegations = self.delegations.lock().unwrap();
+            Ok(stake_accs
+                .into_iter()
+                .filter_map(|stake_acc| delegations.get(&stake_acc).cloned())
+                .collect())
+        }
+
+        async fn save_delegations(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+            let mut stored = self.delegations.lock().unwrap();
+            for delegation in delegations {
+                stored.insert(delegation.stake_acc.clone(), delegation);
+            }
+            Ok(())
+        }
+
+        async fn mark_transaction_as_parsed(&mut self, _transactions: String) -> Result<()> {
+            Ok(())
+        }
+
+        async fn mark_transaction_as_erroneous(&mut self, _transaction: String) -> Result<()> {
+            Ok(())
+        }
+
+        async fn reclaim_stuck_transactions(&mut self, _timeout: u64) -> Result<usize> {
+            Ok(0)
+        }
+
+        async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+            Ok(Vec::new())
+        }
+
+        async fn mark_blocks_metadata_as_parsed(&mut self, _slots: Vec<u64>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn queue_stats(&mut self) -> Result<QueueStats> {
+            *self.stats_queries.lock().unwrap() += 1;
+
+            Ok(QueueStats {
+                pending: 7,
+                oldest_pending_slot: Some(100),
+                oldest_pending_block_time: Some(1643213404),
+            })
+        }
+
+        async fn queue_progress(&mut self) -> Result<QueueProgress> {
+            Ok(QueueProgress::default())
+        }
+    }
+
+    fn delegation(stake_acc: &str, vote_acc: &str) -> Delegation {
+        Delegation {
+            stake_acc: stake_acc.to_string(),
+            vote_acc: Some(vote_acc.to_string()),
+            delegated_amount: None,
+        }
+    }
+
+    async fn vote_acc(queue_manager: &mut QueueManagerHandle, stake_acc: &str) -> Option<String> {
+        queue_manager
+            .get_delegations(vec![stake_acc.to_string()])
+            .await
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .find(|delegation| delegation.stake_acc == stake_acc)
+            .and_then(|delegation| delegation.vote_acc)
+    }
+
+    #[tokio::test]
+    async fn cached_delegations_are_not_looked_up() {
+        let storage = MockStorage::default();
+        storage
+            .delegations
+            .lock()
+            .unwrap()
+            .insert("stake_a".to_string(), delegation("stake_a", "vote_a"));
+        let mut queue_manager = QueueManagerHandle::with_storage(
+            Box::new(storage.clone()),
+            10,
+            InFlightBatches::default(),
+        );
+
+        queue_manager
+            .save_delegations(vec![(
+                "stake_b".to_string(),
+                Some("vote_b".to_string()),
+                None,
+            )])
+            .await
+            .unwrap();
+
+        let mut delegations = queue_manager
+            .get_delegations(vec![
+                "stake_a".to_string(),
+                "stake_b".to_string(),
+                "stake_c".to_string(),
+            ])
+            .await
+            .unwrap()
+            .unwrap();
+        delegations.sort_by(|a, b| a.stake_acc.cmp(&b.stake_acc));
+
+        assert_eq!(
+            delegations,
+            vec![
+                delegation("stake_a", "vote_a"),
+                delegation("stake_b", "vote_b")
+            ]
+        );
+
+        assert_eq!(
+            vote_acc(&mut queue_manager, "stake_a").await.unwrap(),
+            "vote_a"
+        );
+
+        // Unknown to the storage, but cached as such
+        assert_eq!(vote_acc(&mut queue_manager, "stake_c").await, None);
+
+        // stake_b has been saved and stake_a has been loaded by the first lookup, stake_c
+        // is unknown to the storage
+        assert_eq!(
+            *storage.lookups.lock().unwrap(),
+            vec![vec!["stake_a".to_string(), "stake_c".to_string()]]
+        );
+    }
+
+    #[tokio::test]
+    async fn redelegation_within_batch() {
+        let storage = MockStorage::default();
+        storage
+            .delegations
+            .lock()
+            .unwrap()
+            .insert("stake_a".to_string(), delegation("stake_a", "vote_0"));
+        let mut queue_manager = QueueManagerHandle::with_storage(
+            Box::new(storage.clone()),
+            10,
+            InFlightBatches::default(),
+        );
+
+        // Every transaction of the batch looks up the vote account and saves the new one
+        for (previous, next) in [("vote_0", "vote_1"), ("vote_1", "vote_2")] {
+            assert_eq!(
+                vote_acc(&mut queue_manager, "stake_a").await.unwrap(),
+                previous
+            );
+
+            queue_manager
+                .save_delegations(vec![("stake_a".to_string(), Some(next.to_string()), None)])
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            vote_acc(&mut queue_manager, "stake_a").await.unwrap(),
+            "vote_2"
+        );
+        assert_eq!(storage.lookups.lock().unwrap().len(), 1);
+        assert_eq!(
+            storage.delegations.lock().unwrap()["stake_a"],
+            delegation("stake_a", "vote_2")
+        );
+    }
+
+    #[tokio::test]
+    async fn transactions_are_not_fetched_while_batches_are_unflushed() {
+        let in_flight_batches = InFlightBatches::new(1);
+        let mut queue_manager = QueueManagerHandle::with_storage(
+            Box::new(MockStorage::default()),
+            10,
+            in_flight_batches.clone(),
+        );
+
+        // Empty batches are given back at once
+        for _ in 0..3 {
+            assert!(queue_manager.get_transactions().await.unwrap().is_empty());
+        }
+        assert_eq!(in_flight_batches.count(), 0);
+
+        // The collector has not flushed a batch yet
+        in_flight_batches.acquire().await;
+        let mut fetch = tokio::spawn(async move { queue_manager.get_transactions().await });
+        assert!(
+            tokio::time::timeout(tokio::time::Duration::from_millis(100), &mut fetch)
+                .await
+                .is_err()
+        );
+
+        in_flight_batches.release(1);
+        assert!(fetch.await.unwrap().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn queue_stats_are_counted_only_on_request() {
+        let storage = MockStorage::default();
+        let mut queue_manager = QueueManagerHandle::with_storage(
+            Box::new(storage.clone()),
+            10,
+            InFlightBatches::default(),
+        );
+
+        // The parsing loop doesn't count the queue
+        queue_manager.get_transactions().await.unwrap();
+        queue_manager
+            .mark_transaction_as_parsed("signature".to_string())
+            .await
+            .unwrap();
+        queue_manager
+            .get_delegations(vec!["stake_a".to_string()])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*storage.stats_queries.lock().unwrap(), 0);
+
+        let stats = queue_manager.get_queue_stats().await.unwrap();
+
+        assert_eq!(stats.pending, 7);
+        assert_eq!(stats.oldest_pending_age(1643213464), 60);
+        assert_eq!(*storage.stats_queries.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let mut cache = DelegationsCache::new(2, None);
+
+        cache.insert("stake_a".to_string(), Some(delegation("stake_a", "vote_a")));
+        cache.insert("stake_b".to_string(), None);
+        assert_eq!(
+            cache.get("stake_a"),
+            Some(Some(delegation("stake_a", "vote_a")))
+        );
+
+        cache.insert("stake_c".to_string(), Some(delegation("stake_c", "vote_c")));
+
+        assert_eq!(cache.get("stake_b"), None);
+        assert_eq!(
+            cache.get("stake_a"),
+            Some(Some(delegation("stake_a", "vote_a")))
+        );
+        assert_eq!(
+            cache.get("stake_c"),
+            Some(Some(delegation("stake_c", "vote_c")))
+        );
+    }
+
+    #[test]
+    fn expired_entries_are_dropped() {
+        let mut cache = DelegationsCache::new(10, Some(Duration::from_millis(50)));
+
+        cache.insert("stake_a".to_string(), Some(delegation("stake_a", "vote_a")));
+        cache.insert("stake_b".to_string(), None);
+        assert_eq!(cache.get("stake_b"), Some(None));
+
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert_eq!(cache.get("stake_a"), None);
+        assert_eq!(cache.get("stake_b"), None);
+        assert!(cache.entries.is_empty());
+        assert!(cache.usage.is_empty());
+    }
 }
@@ -1,49 +1,83 @@
 use std::collections::HashMap;
 
-use crate::errors::ParseInstructionError;
+use crate::errors::{ActorError, ParseInstructionError};
 use crate::metrics_update;
-use crate::storages::main_storage::{Balance, Delegation, Instruction, InstructionArgument};
+use crate::signature_tracing::{SignatureTracer, TraceEvent};
+use crate::storages::main_storage::{Balance, Delegation, Instruction};
 
 use anyhow::Result;
 use log::debug;
 use macros::{ActorInstance, HandleInstance};
-use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
-use tokio::sync::{mpsc, oneshot};
+use solana_instruction_parser::{ParseOptions, TransactionParsingResult, UNKNOWN_INSTRUCTION_NAME};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInnerInstructions,
+};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    oneshot,
+};
 
 pub type Delegations = Vec<Delegation>;
 pub type Undelegations = Vec<Delegation>;
+pub type ParsingResult = Result<TransactionParsingResult, ParseInstructionError>;
 
+use super::main_storage_manager::MainStorageManagerHandle;
 use super::queue_manager::QueueManagerHandle;
 
 mod parse_delegations;
-mod parse_instructions;
 
 const STAKE_ACC_RENT_EXEMPTION: u64 = 2_282_880;
+const MAILBOX_CAPACITY: usize = 100;
+const ACTOR_NAME: &str = "TransactionParser";
 
 #[derive(ActorInstance)]
 struct TransactionParser {
     receiver: mpsc::Receiver<TransactionParserMessage>,
+    options: ParseOptions,
+    tracer: SignatureTracer,
 }
 
-type TransactionParsingResult = (Vec<Instruction>, Vec<Balance>, Vec<InstructionArgument>);
+/// Human readable name of the program, "other" for the programs which are not parsed
+pub fn program_name(program_address: &str) -> &'static str {
+    solana_instruction_parser::program_name(program_address)
+}
+
+/// Signature of a transaction before it is parsed, empty if it is not JSON-encoded
+pub fn transaction_signature(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> String {
+    match &transaction.transaction.transaction {
+        EncodedTransaction::Json(transaction) => {
+            transaction.signatures.first().cloned().unwrap_or_default()
+        }
+        _ => String::new(),
+    }
+}
 
 enum TransactionParserMessage {
     GetInstructions {
-        respond_to: oneshot::Sender<Result<TransactionParsingResult, ParseInstructionError>>,
+        respond_to: oneshot::Sender<ParsingResult>,
         encoded_confirmed_transaction: EncodedConfirmedTransactionWithStatusMeta,
     },
     GetDelegations {
         respond_to: oneshot::Sender<Result<(Delegations, Undelegations)>>,
         queue_manager: QueueManagerHandle,
+        main_storage_manager: MainStorageManagerHandle,
         instructions: Vec<Instruction>,
         pre_balances: HashMap<String, u64>,
     },
 }
 
 impl TransactionParser {
-    async fn new(receiver: mpsc::Receiver<TransactionParserMessage>) -> Self {
+    async fn new(
+        receiver: mpsc::Receiver<TransactionParserMessage>,
+        options: ParseOptions,
+        tracer: SignatureTracer,
+    ) -> Self {
         metrics_update!(inc total ACTIVE_ACTOR_INSTANCES_COUNT, &["transaction_parser"]);
-        TransactionParser { receiver }
+        TransactionParser {
+            receiver,
+            options,
+            tracer,
+        }
     }
 
     async fn handle_message(&mut self, msg: TransactionParserMessage) {
@@ -53,21 +87,73 @@ impl TransactionParser {
                 encoded_confirmed_transaction,
             } => {
                 debug!(
+                    actor = "transaction_parser";
                     "TransactionParser::handle_message: {:#?}",
                     encoded_confirmed_transaction
                 );
-                let parsing_result = Self::parse_transactions(encoded_confirmed_transaction);
+                let duplicate_inner_sets = encoded_confirmed_transaction
+                    .transaction
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| {
+                        Option::<&Vec<UiInnerInstructions>>::from(meta.inner_instructions.as_ref())
+                    })
+                    .map(|inner_instructions| {
+                        solana_instruction_parser::duplicate_inner_instruction_sets(
+                            inner_instructions,
+                        )
+                    })
+                    .unwrap_or_default();
+                if duplicate_inner_sets > 0 {
+                    metrics_update!(
+                        inc by DUPLICATE_INNER_INSTRUCTION_SETS_COUNT,
+                        duplicate_inner_sets as u64
+                    );
+                }
+
+                let parsing_result = solana_instruction_parser::parse_transaction_with_options(
+                    encoded_confirmed_transaction,
+                    &self.options,
+                );
+
+                if let Ok(TransactionParsingResult { instructions, .. }) = &parsing_result {
+                    if let Some(instruction) = instructions.first() {
+                        self.tracer
+                            .trace(&instruction.tx_signature, TraceEvent::Parsed);
+                    }
+
+                    for instruction in instructions {
+                        metrics_update!(
+                            inc INSTRUCTIONS_PARSED_COUNT,
+                            &[program_name(&instruction.program)]
+                        );
+
+                        if instruction.instruction_name == UNKNOWN_INSTRUCTION_NAME {
+                            metrics_update!(
+                                inc UNKNOWN_INSTRUCTIONS_COUNT,
+                                &[program_name(&instruction.program)]
+                            );
+                        }
+                    }
+                }
+
                 let _ = respond_to.send(parsing_result);
             }
 
             TransactionParserMessage::GetDelegations {
                 respond_to,
                 queue_manager,
+                main_storage_manager,
                 instructions,
                 pre_balances,
             } => {
-                let parsing_result =
-                    Self::parse_delegations(queue_manager, instructions, pre_balances).await;
+                let parsing_result = Self::parse_delegations(
+                    queue_manager,
+                    main_storage_manager,
+                    instructions,
+                    pre_balances,
+                )
+                .await;
                 let _ = respond_to.send(parsing_result);
             }
         }
@@ -80,15 +166,61 @@ impl TransactionParser {
     }
 }
 
+/// Delegations and undelegations of the transaction alone, the vote accounts of the stake
+/// accounts it doesn't delegate are unknown without the queue storage and the main storage
+pub fn collect_delegations(
+    instructions: Vec<Instruction>,
+    pre_balances: &HashMap<String, u64>,
+) -> (Delegations, Undelegations) {
+    TransactionParser::collect_delegations(
+        TransactionParser::delegation_instructions(instructions),
+        pre_balances,
+        &mut HashMap::new(),
+        &mut HashMap::new(),
+    )
+}
+
+/// Drops the balances of the accounts untouched by the transaction. The first balance belongs
+/// to the fee payer and is always kept since the fee changes it
+pub fn retain_changed_balances(balances: &mut Vec<Balance>) {
+    let mut idx = 0;
+
+    balances.retain(|balance| {
+        let is_fee_payer = idx == 0;
+        idx += 1;
+
+        is_fee_payer
+            || balance.pre_balance != balance.post_balance
+            || balance.pre_token_balance_mint.is_some()
+            || balance.pre_token_balance_owner.is_some()
+            || balance.pre_token_balance_amount.is_some()
+            || balance.pre_token_balance_program_id.is_some()
+            || balance.post_token_balance_mint.is_some()
+            || balance.post_token_balance_owner.is_some()
+            || balance.post_token_balance_amount.is_some()
+            || balance.post_token_balance_program_id.is_some()
+    });
+}
+
 #[derive(HandleInstance)]
 pub struct TransactionParserHandle {
     sender: mpsc::Sender<TransactionParserMessage>,
 }
 
 impl TransactionParserHandle {
-    pub async fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(100);
-        let mut parser_manager = TransactionParser::new(receiver).await;
+    pub async fn new(options: ParseOptions, tracer: SignatureTracer) -> Self {
+        Self::with_mailbox_capacity(options, tracer, MAILBOX_CAPACITY).await
+    }
+
+    /// Messages over `mailbox_capacity` wait in [`Self::parse_transaction`] and fail right away
+    /// in [`Self::try_parse_transaction`]
+    pub async fn with_mailbox_capacity(
+        options: ParseOptions,
+        tracer: SignatureTracer,
+        mailbox_capacity: usize,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(mailbox_capacity);
+        let mut parser_manager = TransactionParser::new(receiver, options, tracer).await;
         tokio::spawn(async move { parser_manager.run().await });
 
         metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &["transaction_parser_handle"]);
@@ -99,6 +231,7 @@ impl TransactionParserHandle {
     pub async fn parse_delegations(
         &mut self,
         queue_manager: QueueManagerHandle,
+        main_storage_manager: MainStorageManagerHandle,
         instructions: Vec<Instruction>,
         pre_balances: HashMap<String, u64>,
     ) -> Result<(Delegations, Undelegations)> {
@@ -106,30 +239,58 @@ impl TransactionParserHandle {
         let msg = TransactionParserMessage::GetDelegations {
             respond_to: sender,
             queue_manager,
+            main_storage_manager,
             instructions,
             pre_balances,
         };
 
-        let _ = self.sender.send(msg).await;
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| ActorError::ActorUnavailable(ACTOR_NAME))?;
         receiver
             .await
-            .expect("TransactionParser task has been killed")
+            .map_err(|_| ActorError::ActorUnavailable(ACTOR_NAME))?
     }
 
+    /// Waits for a free place in the mailbox, fails only if the actor is gone
     pub async fn parse_transaction(
         &mut self,
         encoded_confirmed_transaction: EncodedConfirmedTransactionWithStatusMeta,
-    ) -> Result<TransactionParsingResult, ParseInstructionError> {
+    ) -> Result<ParsingResult, ActorError> {
+        let (sender, receiver) = oneshot::channel();
+        let msg = TransactionParserMessage::GetInstructions {
+            respond_to: sender,
+            encoded_confirmed_transaction,
+        };
+
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| ActorError::ActorUnavailable(ACTOR_NAME))?;
+        receiver
+            .await
+            .map_err(|_| ActorError::ActorUnavailable(ACTOR_NAME))
+    }
+
+    /// Fails with [`ActorError::MailboxFull`] instead of waiting, so the caller can back off
+    pub async fn try_parse_transaction(
+        &mut self,
+        encoded_confirmed_transaction: EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Result<ParsingResult, ActorError> {
         let (sender, receiver) = oneshot::channel();
         let msg = TransactionParserMessage::GetInstructions {
             respond_to: sender,
             encoded_confirmed_transaction,
         };
 
-        let _ = self.sender.send(msg).await;
+        self.sender.try_send(msg).map_err(|err| match err {
+            TrySendError::Full(_) => ActorError::MailboxFull(ACTOR_NAME),
+            TrySendError::Closed(_) => ActorError::ActorUnavailable(ACTOR_NAME),
+        })?;
         receiver
             .await
-            .expect("TransactionParser task has been killed")
+            .map_err(|_| ActorError::ActorUnavailable(ACTOR_NAME))
     }
 }
 
@@ -423,39 +584,177 @@ async fn parse_instruction() -> Result<(), String> {
         block_time: Some(1643213404_i64),
     };
 
-    let mut transaction_parser = TransactionParserHandle::new().await;
+    let mut transaction_parser =
+        TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
     let parsed_transaction = transaction_parser
         .parse_transaction(encoded_confirmed_transaction)
         .await
+        .unwrap()
         .unwrap();
 
-    assert_eq!(parsed_transaction.0.len(), 18);
+    assert_eq!(parsed_transaction.balances.len(), 21);
+
+    // The fee payer, the accounts with changed lamports and the token account with unchanged
+    // lamports are left
+    let mut balances = parsed_transaction.balances.clone();
+    retain_changed_balances(&mut balances);
+    let changed_balances: Vec<_> = balances
+        .into_iter()
+        .map(|balance| balance.account)
+        .collect();
+
+    assert_eq!(
+        changed_balances,
+        vec![
+            "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+            "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+            "JB4vdpYFSG4xCqeZbMC8r96H81nB7oi2xBdMmVBGWWyy",
+            "6DnkBtW5UmsWRFCZBkihS1yZzUWWKpUZiHUwMPDx6c9C",
+            "Eozy2f2NoxvuRJcFdif8ma3rAuWvHJte937NEWH3Fhwr",
+            "CG18v8fAZusKkMzZp7kLbCpsYrDkLVDmqhbXu5v7hHwZ",
+        ]
+    );
+
+    assert_eq!(parsed_transaction.instructions.len(), 18);
 
     assert_eq!(
-        parsed_transaction.0[0].tx_signature,
+        parsed_transaction.instructions[0].tx_signature,
         "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
             .to_string()
     );
 
-    let mut accs: [Option<String>; crate::storages::main_storage::ACCOUNTS_ARRAY_SIZE] = [0;
-        crate::storages::main_storage::ACCOUNTS_ARRAY_SIZE]
-        .iter()
-        .map(|_| -> Option<String> { None })
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap(); // Will never fail because of the same size
+    // Both accounts of the CreateAccount instruction sign the transaction and are writable
+    assert_eq!(parsed_transaction.instructions[0].account_roles, "WW");
+    assert_eq!(parsed_transaction.instructions[0].tx_version, None);
+    assert_eq!(parsed_transaction.instructions[0].num_signatures, 2);
+
+    assert_eq!(
+        parsed_transaction.instructions[3].accounts,
+        [
+            "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+            "JB4vdpYFSG4xCqeZbMC8r96H81nB7oi2xBdMmVBGWWyy",
+            "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+        ]
+    );
+
+    assert_eq!(
+        parsed_transaction.instructions[4].instruction_name,
+        "ClaimPack"
+    );
 
-    accs[0] = Some("E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8".to_string());
-    accs[1] = Some("JB4vdpYFSG4xCqeZbMC8r96H81nB7oi2xBdMmVBGWWyy".to_string());
-    accs[2] = Some("GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm".to_string());
+    // The pack card is minted to the user, the voucher stays untouched
+    assert_eq!(
+        parsed_transaction.token_transfers,
+        vec![crate::storages::main_storage::TokenTransfer {
+            tx_signature: "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                .to_string(),
+            slot: 117946133,
+            block_time: 1643213404,
+            mint: "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8".to_string(),
+            source_owner: None,
+            destination_owner: Some("GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm".to_string()),
+            amount: 1,
+            decimals: 0,
+            program_id: None,
+        }]
+    );
 
-    assert_eq!(parsed_transaction.0[3].accounts, accs);
+    // The claimed card is printed as an edition of the mint initialized by the transaction
+    assert_eq!(
+        parsed_transaction.pack_events,
+        vec![crate::storages::main_storage::PackEvent {
+            tx_signature: "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                .to_string(),
+            slot: 117946133,
+            pack_set: "H6FEUafrGDeQsGnCerFomtzG3B3TctUaue8yM7heLi8W".to_string(),
+            pack_card: Some("4wawb6MxhWmANe4nDYB7Hy5tdFY3A5s1MyNSJHShnjz".to_string()),
+            claimer: Some("GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm".to_string()),
+            new_mint: Some("E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8".to_string()),
+            event_type: "Claim".to_string(),
+        }]
+    );
 
-    assert_eq!(parsed_transaction.0[4].instruction_name, "ClaimPack");
+    // The fee is not a system instruction and has no transfer
+    let sol_transfer = |instruction_idx,
+                        inner_instructions_set,
+                        transfer_kind: &str,
+                        destination: &str,
+                        lamports| {
+        crate::storages::main_storage::SolTransfer {
+            tx_signature: "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                .to_string(),
+            slot: 117946133,
+            instruction_idx,
+            inner_instructions_set,
+            source: "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm".to_string(),
+            destination: destination.to_string(),
+            lamports,
+            transfer_kind: transfer_kind.to_string(),
+        }
+    };
+    assert_eq!(
+        parsed_transaction.sol_transfers,
+        vec![
+            sol_transfer(
+                0,
+                None,
+                "CreateAccountFunding",
+                "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                1461600,
+            ),
+            sol_transfer(
+                0,
+                Some(0),
+                "Transfer",
+                "JB4vdpYFSG4xCqeZbMC8r96H81nB7oi2xBdMmVBGWWyy",
+                2039280,
+            ),
+            sol_transfer(
+                1,
+                Some(1),
+                "Transfer",
+                "6DnkBtW5UmsWRFCZBkihS1yZzUWWKpUZiHUwMPDx6c9C",
+                5616720,
+            ),
+            sol_transfer(
+                4,
+                Some(1),
+                "Transfer",
+                "Eozy2f2NoxvuRJcFdif8ma3rAuWvHJte937NEWH3Fhwr",
+                2568240,
+            ),
+        ]
+    );
 
     Ok(())
 }
 
+#[test]
+fn unchanged_fee_payer_balance_is_kept() {
+    let balance = |account: &str| Balance {
+        tx_signature: "signature".to_string(),
+        account: account.to_string(),
+        pre_balance: Some(1),
+        post_balance: Some(1),
+        pre_token_balance_mint: None,
+        pre_token_balance_owner: None,
+        pre_token_balance_amount: None,
+        pre_token_balance_program_id: None,
+        post_token_balance_mint: None,
+        post_token_balance_owner: None,
+        post_token_balance_amount: None,
+        post_token_balance_program_id: None,
+        inner_instructions_set: None,
+        attributed_amount: None,
+    };
+    let mut balances = vec![balance("fee_payer"), balance("untouched")];
+
+    retain_changed_balances(&mut balances);
+
+    assert_eq!(balances.len(), 1);
+    assert_eq!(balances[0].account, "fee_payer");
+}
+
 #[cfg(test)]
 mod parse_erroneous_transaction_tests {
     use super::*;
@@ -621,10 +920,12 @@ mod parse_erroneous_transaction_tests {
             block_time: Some(1643213404_i64),
         };
 
-        let mut transaction_parser = TransactionParserHandle::new().await;
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
         let result = transaction_parser
             .parse_transaction(encoded_confirmed_transaction)
-            .await;
+            .await
+            .unwrap();
 
         if let Err(ParseInstructionError::InvalidIndex {
             site,
@@ -634,7 +935,7 @@ mod parse_erroneous_transaction_tests {
         {
             assert_eq!(site, "post_token_balance".to_string());
             assert_eq!(index, 37);
-            assert_eq!(max_len, crate::storages::main_storage::ACCOUNTS_ARRAY_SIZE);
+            assert_eq!(max_len, 21);
         } else {
             panic!("Value is not \"ParseInstructionError::InvalidIndex\"");
         }
@@ -787,16 +1088,51 @@ mod parse_erroneous_transaction_tests {
         }
         }";
 
+        let mut transaction: serde_json::Value = serde_json::from_str(encoded_transaction).unwrap();
+
+        // A transaction may reference more accounts than ACCOUNTS_ARRAY_SIZE as long as
+        // every single instruction fits into the accounts array
+        let account_keys = transaction["transaction"]["message"]["accountKeys"]
+            .as_array_mut()
+            .unwrap();
+        for i in account_keys.len()..solana_instruction_parser::ACCOUNTS_ARRAY_SIZE + 10 {
+            account_keys.push(serde_json::Value::String(format!(
+                "LookupTableAccount{}",
+                i
+            )));
+        }
+
         let encoded_confirmed_transaction = EncodedConfirmedTransactionWithStatusMeta {
             slot: 117946133_u64,
-            transaction: serde_json::from_str(encoded_transaction).unwrap(),
+            transaction: serde_json::from_value(transaction.clone()).unwrap(),
+            block_time: Some(1643213404_i64),
+        };
+
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
+        let result = transaction_parser
+            .parse_transaction(encoded_confirmed_transaction)
+            .await
+            .unwrap();
+
+        assert!(result.is_ok());
+
+        transaction["transaction"]["message"]["instructions"][0]["accounts"] =
+            serde_json::Value::Array(vec![
+                0.into();
+                solana_instruction_parser::ACCOUNTS_ARRAY_SIZE + 1
+            ]);
+
+        let encoded_confirmed_transaction = EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133_u64,
+            transaction: serde_json::from_value(transaction).unwrap(),
             block_time: Some(1643213404_i64),
         };
 
-        let mut transaction_parser = TransactionParserHandle::new().await;
         let result = transaction_parser
             .parse_transaction(encoded_confirmed_transaction)
-            .await;
+            .await
+            .unwrap();
 
         if let Err(ParseInstructionError::InvalidLength {
             site,
@@ -804,12 +1140,9 @@ mod parse_erroneous_transaction_tests {
             expected_len,
         }) = result
         {
-            assert_eq!(site, "accounts".to_string());
-            assert_eq!(len, 36);
-            assert_eq!(
-                expected_len,
-                crate::storages::main_storage::ACCOUNTS_ARRAY_SIZE
-            );
+            assert_eq!(site, "instruction".to_string());
+            assert_eq!(len, solana_instruction_parser::ACCOUNTS_ARRAY_SIZE + 1);
+            assert_eq!(expected_len, solana_instruction_parser::ACCOUNTS_ARRAY_SIZE);
         } else {
             panic!("Value is not \"ParseInstructionError::InvalidLength\"");
         }
@@ -952,10 +1285,12 @@ mod parse_erroneous_transaction_tests {
             block_time: Some(1643213404_i64),
         };
 
-        let mut transaction_parser = TransactionParserHandle::new().await;
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
         let result = transaction_parser
             .parse_transaction(encoded_confirmed_transaction)
-            .await;
+            .await
+            .unwrap();
 
         if let Err(ParseInstructionError::DeserializeFromBase58Error) = result {
         } else {
@@ -1088,22 +1423,250 @@ mod parse_erroneous_transaction_tests {
             block_time: Some(1643213404_i64),
         };
 
-        let mut transaction_parser = TransactionParserHandle::new().await;
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
         let parsed_transaction = transaction_parser
             .parse_transaction(encoded_confirmed_transaction)
             .await
+            .unwrap()
             .unwrap();
 
-        println!("PREKOL: {:#?}", parsed_transaction.0[0]);
+        println!("PREKOL: {:#?}", parsed_transaction.instructions[0]);
 
-        assert_eq!(parsed_transaction.0.len(), 2);
-        assert_eq!(parsed_transaction.0[0].instruction_name, "".to_string());
+        assert_eq!(parsed_transaction.instructions.len(), 2);
+        assert_eq!(
+            parsed_transaction.instructions[0].instruction_name,
+            "".to_string()
+        );
         assert_eq!(
-            parsed_transaction.0[0].data,
+            parsed_transaction.instructions[0].data,
             "11114XtYk9gGfZoo968fyjNUYQJKf9gdmkGoaoBpzFv4vyaSMBn3VKxZdv7mZLzoyX5YNC".to_string()
         );
 
-        assert_eq!(parsed_transaction.0[1].instruction_name, "".to_string());
-        assert_eq!(parsed_transaction.0[1].data, "3Bxs4h24hBtQy9rw".to_string());
+        assert_eq!(
+            parsed_transaction.instructions[1].instruction_name,
+            "".to_string()
+        );
+        assert_eq!(
+            parsed_transaction.instructions[1].data,
+            "3Bxs4h24hBtQy9rw".to_string()
+        );
+    }
+}
+
+#[cfg(test)]
+mod instruction_failure_tests {
+    use super::*;
+    use crate::storages::main_storage::TxStatus;
+
+    fn failed_transaction(err: serde_json::Value) -> EncodedConfirmedTransactionWithStatusMeta {
+        let compiled_instruction = serde_json::json!({
+            "programIdIndex": 2,
+            "accounts": [0, 1],
+            "data": "3Bxs4h24hBtQy9rw"
+        });
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [
+                    "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                ],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [
+                        compiled_instruction,
+                        compiled_instruction,
+                        compiled_instruction
+                    ]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": { "Err": err },
+                "fee": 5000,
+                "preBalances": [1000000, 0, 1],
+                "postBalances": [995000, 0, 1],
+                "innerInstructions": [
+                    { "index": 1, "instructions": [compiled_instruction] }
+                ],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133_u64,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404_i64),
+        }
+    }
+
+    #[tokio::test]
+    async fn instruction_error() {
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
+        let instructions = transaction_parser
+            .parse_transaction(failed_transaction(serde_json::json!({
+                "InstructionError": [1, { "Custom": 6000 }]
+            })))
+            .await
+            .unwrap()
+            .unwrap()
+            .instructions;
+
+        let statuses: Vec<_> = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.transaction_instruction_idx,
+                    instruction.instruction_idx,
+                    instruction.tx_status,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            statuses,
+            [
+                (None, 0, TxStatus::Success),
+                (None, 1, TxStatus::Failed),
+                (Some(1), 0, TxStatus::Failed),
+                (None, 2, TxStatus::NotExecuted),
+            ]
+        );
+        assert!(instructions
+            .iter()
+            .all(|instruction| instruction.failed_instruction_idx == Some(1)));
+    }
+
+    #[tokio::test]
+    async fn transaction_error() {
+        let mut transaction_parser =
+            TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
+        let instructions = transaction_parser
+            .parse_transaction(failed_transaction(serde_json::json!("AccountInUse")))
+            .await
+            .unwrap()
+            .unwrap()
+            .instructions;
+
+        // Not caused by an instruction, so all of them are failed
+        assert_eq!(instructions.len(), 4);
+        assert!(instructions.iter().all(|instruction| {
+            instruction.tx_status == TxStatus::Failed
+                && instruction.failed_instruction_idx.is_none()
+        }));
+    }
+}
+
+#[cfg(test)]
+mod handle_tests {
+    use super::*;
+
+    /// Binary transactions are not parsed, so the actor responds with an error
+    fn transaction() -> EncodedConfirmedTransactionWithStatusMeta {
+        serde_json::from_value(serde_json::json!({
+            "slot": 117946133,
+            "blockTime": 1643213404,
+            "transaction": ["", "base64"],
+            "meta": null
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn killed_actor_is_unavailable() {
+        // The actor is gone before it takes the message
+        let (sender, receiver) = mpsc::channel(1);
+        drop(receiver);
+        let mut transaction_parser = TransactionParserHandle { sender };
+
+        assert_eq!(
+            transaction_parser
+                .parse_transaction(transaction())
+                .await
+                .unwrap_err(),
+            ActorError::ActorUnavailable(ACTOR_NAME)
+        );
+        assert_eq!(
+            transaction_parser
+                .try_parse_transaction(transaction())
+                .await
+                .unwrap_err(),
+            ActorError::ActorUnavailable(ACTOR_NAME)
+        );
+
+        // The actor is gone after it has taken the message
+        let (sender, mut receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let _message = receiver.recv().await;
+        });
+        let mut transaction_parser = TransactionParserHandle { sender };
+
+        assert_eq!(
+            transaction_parser
+                .parse_transaction(transaction())
+                .await
+                .unwrap_err(),
+            ActorError::ActorUnavailable(ACTOR_NAME)
+        );
+    }
+
+    #[tokio::test]
+    async fn full_mailbox_fails_fast() {
+        let (sender, _receiver) = mpsc::channel(1);
+        let mut transaction_parser = TransactionParserHandle { sender };
+
+        let (respond_to, _response) = oneshot::channel();
+        transaction_parser
+            .sender
+            .try_send(TransactionParserMessage::GetInstructions {
+                respond_to,
+                encoded_confirmed_transaction: transaction(),
+            })
+            .unwrap_or_else(|_| panic!("The mailbox has a place"));
+
+        assert_eq!(
+            transaction_parser
+                .try_parse_transaction(transaction())
+                .await
+                .unwrap_err(),
+            ActorError::MailboxFull(ACTOR_NAME)
+        );
+    }
+
+    #[tokio::test]
+    async fn cloned_handles_share_the_actor() {
+        let mut transaction_parser = TransactionParserHandle::with_mailbox_capacity(
+            ParseOptions::default(),
+            SignatureTracer::default(),
+            1,
+        )
+        .await;
+        let mut cloned = transaction_parser.clone();
+
+        assert!(transaction_parser.sender.same_channel(&cloned.sender));
+        assert!(cloned
+            .try_parse_transaction(transaction())
+            .await
+            .unwrap()
+            .is_err());
+        assert!(transaction_parser
+            .parse_transaction(transaction())
+            .await
+            .unwrap()
+            .is_err());
     }
 }
@@ -1,69 +1,168 @@
+use crate::actors::main_storage_manager::MainStorageManagerHandle;
 use crate::actors::queue_manager::QueueManagerHandle;
-use crate::errors::{ConvertingError, ParseInstructionError};
-use crate::storages::main_storage::{
-    Delegation, Instruction, InstructionArgument, TxStatus, ACCOUNTS_ARRAY_SIZE,
-};
+use crate::storages::main_storage::{Delegation, Instruction};
 
 use anyhow::Result;
-use rust_base58::FromBase58;
-use solana_transaction_status::{UiCompiledInstruction, UiInnerInstructions, UiInstruction};
-use std::collections::{BTreeSet, HashMap};
-use std::convert::TryInto;
+use std::collections::HashMap;
 
 use super::{Delegations, TransactionParser, Undelegations, STAKE_ACC_RENT_EXEMPTION};
 
 const FIRST_ACCOUNTS: usize = 2;
+const STAKE_PROGRAM: &str = "Stake11111111111111111111111111111111111111";
+const STAKE_INSTRUCTION_NAMES: [&str; 5] =
+    ["Withdraw", "Merge", "Split", "Deactivate", "DelegateStake"];
+const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+/// System instructions funding the stake accounts within the transaction, a freshly created
+/// account has no pre balance
+const SYSTEM_INSTRUCTION_NAMES: [&str; 3] = ["CreateAccount", "CreateAccountWithSeed", "Transfer"];
 
 impl TransactionParser {
     pub async fn parse_delegations(
         mut queue_manager: QueueManagerHandle,
+        mut main_storage_manager: MainStorageManagerHandle,
         instructions: Vec<Instruction>,
         pre_balances: HashMap<String, u64>,
     ) -> Result<(Delegations, Undelegations)> {
-        let mut previous_balance: HashMap<String, u64> = HashMap::new();
-        let mut delegations = Delegations::new();
-        let mut undelegations = Undelegations::new();
+        let instructions = Self::delegation_instructions(instructions);
+
+        // The system instructions alone only move lamports, the stake accounts are looked up
+        // only for a transaction with a stake instruction
+        if !instructions
+            .iter()
+            .any(|instruction| instruction.program == STAKE_PROGRAM)
+        {
+            return Ok((Delegations::new(), Undelegations::new()));
+        }
+
         let instructions_accounts = instructions
             .iter()
-            .flat_map(|instruction| instruction.accounts.clone())
-            .enumerate()
             // We are taking only first 2 accounts because only they are used in staking instructions
-            .filter(|(i, account)| account.is_some() && *i < FIRST_ACCOUNTS)
-            .map(|(_, account)| account.unwrap())
+            .flat_map(|instruction| instruction.accounts.iter().take(FIRST_ACCOUNTS).cloned())
             .collect();
 
-        let mut vote_accounts: HashMap<String, Option<String>> = queue_manager
+        let mut vote_accounts: HashMap<String, Option<String>> = HashMap::new();
+        let mut delegated_amounts: HashMap<String, u64> = HashMap::new();
+
+        for delegation in queue_manager
             .get_delegations(instructions_accounts)
             .await??
-            .into_iter()
-            .map(|d| (d.stake_acc, d.vote_acc))
-            .collect();
+        {
+            if let Some(delegated_amount) = delegation.delegated_amount {
+                delegated_amounts.insert(delegation.stake_acc.clone(), delegated_amount as u64);
+            }
+            vote_accounts.insert(delegation.stake_acc, delegation.vote_acc);
+        }
 
-        let instruction_names = vec![
-            "Withdraw",
-            "Merge",
-            "Split",
-            "Deactivate",
-            "DelegateStake",
-            "CreateAccount",
-            "CreateAccountWithSeed",
-            "Transfer",
-        ];
-        for instruction in instructions {
-            if !instruction_names.contains(&instruction.instruction_name.as_str())
-                || instruction.program != "Stake11111111111111111111111111111111111111"
+        // Vote accounts which are unknown to the queue storage are looked up in the main storage
+        // before giving up on them
+        for instruction in instructions.iter() {
+            if !["Deactivate", "Split", "Merge", "Withdraw"]
+                .contains(&instruction.instruction_name.as_str())
             {
                 continue;
             }
 
+            let stake_acc = match instruction.accounts.first() {
+                Some(stake_acc) => stake_acc.clone(),
+                None => continue,
+            };
+
+            // A stake account known to the queue storage without a vote account has been
+            // undelegated, the main storage still has its last delegation
+            if vote_accounts.contains_key(&stake_acc) {
+                continue;
+            }
+
+            if let Some(vote_acc) = Self::lookup_stored_vote_acc(
+                &mut main_storage_manager,
+                &stake_acc,
+                instruction.slot,
+            )
+            .await?
+            {
+                vote_accounts.insert(stake_acc, Some(vote_acc));
+            }
+        }
+
+        let (delegations, undelegations) = Self::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut delegated_amounts,
+        );
+
+        queue_manager
+            .save_delegations(
+                vote_accounts
+                    .into_iter()
+                    .map(|(stake_acc, vote_acc)| {
+                        let delegated_amount = delegated_amounts.get(&stake_acc).cloned();
+                        (stake_acc, vote_acc, delegated_amount)
+                    })
+                    .collect(),
+            )
+            .await?;
+
+        Ok((delegations, undelegations))
+    }
+
+    /// Keeps the stake instructions and the system instructions moving lamports, the
+    /// delegated amounts are computed from both
+    pub(super) fn delegation_instructions(instructions: Vec<Instruction>) -> Vec<Instruction> {
+        instructions
+            .into_iter()
+            .filter(|instruction| {
+                let instruction_names: &[&str] = match instruction.program.as_str() {
+                    STAKE_PROGRAM => &STAKE_INSTRUCTION_NAMES,
+                    SYSTEM_PROGRAM => &SYSTEM_INSTRUCTION_NAMES,
+                    _ => return false,
+                };
+
+                instruction_names.contains(&instruction.instruction_name.as_str())
+            })
+            .collect()
+    }
+
+    async fn lookup_stored_vote_acc(
+        main_storage_manager: &mut MainStorageManagerHandle,
+        stake_acc: &str,
+        slot: u64,
+    ) -> Result<Option<String>> {
+        Ok(main_storage_manager
+            .get_delegations_for_stake_acc(stake_acc.to_string(), 0, slot)
+            .await?
+            .into_iter()
+            .rev()
+            .find_map(|delegation| delegation.vote_acc))
+    }
+
+    /// Turns the stake instructions of a transaction into delegations and undelegations.
+    /// `vote_accounts` holds the known vote account of each stake account and
+    /// `delegated_amounts` the lamports it still delegates, both are updated as the
+    /// instructions are applied
+    pub(super) fn collect_delegations(
+        instructions: Vec<Instruction>,
+        pre_balances: &HashMap<String, u64>,
+        vote_accounts: &mut HashMap<String, Option<String>>,
+        delegated_amounts: &mut HashMap<String, u64>,
+    ) -> (Delegations, Undelegations) {
+        let mut previous_balance: HashMap<String, u64> = HashMap::new();
+        let mut delegations = Delegations::new();
+        let mut undelegations = Undelegations::new();
+
+        for instruction in instructions {
             let raw_instruction_idx = instruction.get_raw_instruction_idx();
             let instruction_name = instruction.instruction_name;
             let tx_signature = instruction.tx_signature.clone();
-            let account_0 = instruction.accounts[0].clone().unwrap();
-            let account_1 = instruction.accounts[1].clone().unwrap();
+            // Every instruction used here has at least two accounts, a malformed one is skipped
+            let (account_0, account_1) =
+                match (instruction.accounts.first(), instruction.accounts.get(1)) {
+                    (Some(account_0), Some(account_1)) => (account_0.clone(), account_1.clone()),
+                    _ => continue,
+                };
             let data = instruction.data;
             let slot = instruction.slot;
-            let block_time = instruction.block_time;
+            let block_time = instruction.block_time.unwrap_or_default();
 
             previous_balance
                 .entry(account_0.clone())
@@ -75,33 +174,37 @@ impl TransactionParser {
 
             match instruction_name.as_str() {
                 "DelegateStake" => {
+                    let amount =
+                        previous_balance[&account_0].saturating_sub(STAKE_ACC_RENT_EXEMPTION);
+
                     delegations.push(Delegation {
                         slot,
                         block_time,
                         stake_acc: account_0.clone(),
                         vote_acc: Some(account_1.clone()),
                         tx_signature,
-                        amount: previous_balance[&account_0]
-                            .saturating_sub(STAKE_ACC_RENT_EXEMPTION),
+                        amount,
                         raw_instruction_idx,
                     });
                     vote_accounts.insert(account_0.clone(), Some(account_1.clone()));
+                    delegated_amounts.insert(account_0.clone(), amount);
                 }
                 "Deactivate" => {
                     undelegations.push(Delegation {
                         slot,
                         block_time,
                         stake_acc: account_0.clone(),
-                        vote_acc: vote_accounts
-                            .get(&account_0.clone())
-                            .cloned()
-                            .unwrap_or_default(),
+                        vote_acc: vote_accounts.get(&account_0).cloned().unwrap_or_default(),
                         tx_signature,
-                        amount: previous_balance[&account_0]
-                            .saturating_sub(STAKE_ACC_RENT_EXEMPTION),
+                        amount: Self::delegated_amount(
+                            delegated_amounts,
+                            &previous_balance,
+                            &account_0,
+                        ),
                         raw_instruction_idx,
                     });
                     vote_accounts.insert(account_0.clone(), None);
+                    delegated_amounts.insert(account_0.clone(), 0);
                 }
                 "CreateAccountWithSeed" => {
                     *previous_balance.get_mut(&account_1).unwrap() +=
@@ -111,28 +214,53 @@ impl TransactionParser {
                             .unwrap();
                 }
                 "Withdraw" => {
-                    *previous_balance.get_mut(&account_0).unwrap() -=
-                        serde_json::from_str::<serde_json::Value>(&data).unwrap()["Withdraw"]
-                            .as_u64()
-                            .unwrap();
+                    let amount = serde_json::from_str::<serde_json::Value>(&data).unwrap()
+                        ["Withdraw"]
+                        .as_u64()
+                        .unwrap();
 
-                    *previous_balance.get_mut(&account_1).unwrap() +=
-                        serde_json::from_str::<serde_json::Value>(&data).unwrap()["Withdraw"]
-                            .as_u64()
-                            .unwrap();
+                    // Only the delegated lamports can be undelegated, so withdrawing of the rent
+                    // exempt reserve or of the lamports deposited after the delegation doesn't
+                    // produce an undelegation
+                    let delegated_amount =
+                        Self::delegated_amount(delegated_amounts, &previous_balance, &account_0);
+                    let undelegated_amount = amount.min(delegated_amount);
+
+                    if let Some(Some(vote_acc)) = vote_accounts.get(&account_0) {
+                        delegated_amounts
+                            .insert(account_0.clone(), delegated_amount - undelegated_amount);
+
+                        if undelegated_amount > 0 {
+                            undelegations.push(Delegation {
+                                slot,
+                                block_time,
+                                stake_acc: account_0.clone(),
+                                vote_acc: Some(vote_acc.clone()),
+                                tx_signature,
+                                amount: undelegated_amount,
+                                raw_instruction_idx,
+                            });
+                        }
+                    }
+
+                    *previous_balance.get_mut(&account_0).unwrap() = previous_balance
+                        .get(&account_0)
+                        .unwrap()
+                        .saturating_sub(amount);
+                    *previous_balance.get_mut(&account_1).unwrap() += amount;
                 }
                 "Transfer" => {
-                    *previous_balance.get_mut(&account_0).unwrap() -=
-                        serde_json::from_str::<serde_json::Value>(&data).unwrap()["Transfer"]
-                            ["lamports"]
-                            .as_u64()
-                            .unwrap();
+                    let lamports = serde_json::from_str::<serde_json::Value>(&data).unwrap()
+                        ["Transfer"]["lamports"]
+                        .as_u64()
+                        .unwrap();
 
-                    *previous_balance.get_mut(&account_1).unwrap() +=
-                        serde_json::from_str::<serde_json::Value>(&data).unwrap()["Transfer"]
-                            ["lamports"]
-                            .as_u64()
-                            .unwrap();
+                    // A failed transfer is parsed as well, the pre balance may be short of it
+                    *previous_balance.get_mut(&account_0).unwrap() = previous_balance
+                        .get(&account_0)
+                        .unwrap()
+                        .saturating_sub(lamports);
+                    *previous_balance.get_mut(&account_1).unwrap() += lamports;
                 }
                 "CreateAccount" => {
                     *previous_balance.get_mut(&account_1).unwrap() +=
@@ -148,25 +276,37 @@ impl TransactionParser {
 
                     let vote_acc = vote_accounts.get(&account_0).cloned().unwrap_or_default();
 
-                    undelegations.push(Delegation {
-                        slot,
-                        block_time,
-                        stake_acc: account_0.clone(),
-                        vote_acc: vote_acc.clone(),
-                        tx_signature: tx_signature.clone(),
-                        amount,
-                        raw_instruction_idx,
-                    });
+                    // Splitting of a not delegated stake account only moves lamports,
+                    // the new account has to be delegated separately
+                    if vote_acc.is_some() {
+                        if let Some(delegated_amount) = delegated_amounts.get_mut(&account_0) {
+                            *delegated_amount = delegated_amount.saturating_sub(amount);
+                        }
+                        delegated_amounts.insert(
+                            account_1.clone(),
+                            amount.saturating_sub(STAKE_ACC_RENT_EXEMPTION),
+                        );
 
-                    delegations.push(Delegation {
-                        slot,
-                        block_time,
-                        stake_acc: account_1.clone(),
-                        vote_acc: vote_acc.clone(),
-                        tx_signature,
-                        amount: amount.saturating_sub(STAKE_ACC_RENT_EXEMPTION),
-                        raw_instruction_idx,
-                    });
+                        undelegations.push(Delegation {
+                            slot,
+                            block_time,
+                            stake_acc: account_0.clone(),
+                            vote_acc: vote_acc.clone(),
+                            tx_signature: tx_signature.clone(),
+                            amount,
+                            raw_instruction_idx,
+                        });
+
+                        delegations.push(Delegation {
+                            slot,
+                            block_time,
+                            stake_acc: account_1.clone(),
+                            vote_acc: vote_acc.clone(),
+                            tx_signature,
+                            amount: amount.saturating_sub(STAKE_ACC_RENT_EXEMPTION),
+                            raw_instruction_idx,
+                        });
+                    }
 
                     vote_accounts.insert(account_1.clone(), vote_acc);
 
@@ -178,296 +318,677 @@ impl TransactionParser {
 
                     if *previous_balance.get_mut(&account_0).unwrap() < STAKE_ACC_RENT_EXEMPTION {
                         vote_accounts.insert(account_0.clone(), None);
+                        delegated_amounts.insert(account_0.clone(), 0);
                     }
                 }
                 "Merge" => {
+                    // Merge moves all the lamports of the source (account_1) to the destination (account_0)
                     let vote_acc = vote_accounts.get(&account_0).cloned().unwrap_or_default();
 
-                    delegations.push(Delegation {
-                        slot,
-                        block_time,
-                        stake_acc: account_0.clone(),
-                        vote_acc: vote_acc.clone(),
-                        tx_signature: tx_signature.clone(),
-                        amount: previous_balance[&account_1]
-                            .saturating_sub(STAKE_ACC_RENT_EXEMPTION),
-                        raw_instruction_idx,
-                    });
+                    if vote_acc.is_some() {
+                        let amount =
+                            previous_balance[&account_1].saturating_sub(STAKE_ACC_RENT_EXEMPTION);
 
-                    undelegations.push(Delegation {
-                        slot,
-                        block_time,
-                        stake_acc: account_1.clone(),
-                        vote_acc,
-                        tx_signature,
-                        amount: previous_balance[&account_1]
-                            .saturating_sub(STAKE_ACC_RENT_EXEMPTION),
-                        raw_instruction_idx,
-                    });
+                        if let Some(delegated_amount) = delegated_amounts.get_mut(&account_0) {
+                            *delegated_amount += amount;
+                        }
+
+                        delegations.push(Delegation {
+                            slot,
+                            block_time,
+                            stake_acc: account_0.clone(),
+                            vote_acc: vote_acc.clone(),
+                            tx_signature: tx_signature.clone(),
+                            amount,
+                            raw_instruction_idx,
+                        });
+
+                        undelegations.push(Delegation {
+                            slot,
+                            block_time,
+                            stake_acc: account_1.clone(),
+                            vote_acc,
+                            tx_signature,
+                            amount,
+                            raw_instruction_idx,
+                        });
+                    }
 
-                    vote_accounts.insert(account_0.clone(), None);
                     *previous_balance.get_mut(&account_0).unwrap() += previous_balance[&account_1];
                     *previous_balance.get_mut(&account_1).unwrap() = 0;
 
-                    vote_accounts.remove(&account_1);
+                    // The source account is closed by the merge
+                    vote_accounts.insert(account_1.clone(), None);
+                    delegated_amounts.insert(account_1.clone(), 0);
                 }
                 _ => unreachable!(),
             }
         }
 
-        queue_manager
-            .save_delegations(vote_accounts.into_iter().collect())
-            .await?;
+        (delegations, undelegations)
+    }
 
-        Ok((delegations, undelegations))
+    /// Lamports the stake account still delegates, the accounts delegated before the amount
+    /// was tracked delegate their whole balance above the rent exempt reserve
+    fn delegated_amount(
+        delegated_amounts: &HashMap<String, u64>,
+        previous_balance: &HashMap<String, u64>,
+        stake_acc: &str,
+    ) -> u64 {
+        delegated_amounts
+            .get(stake_acc)
+            .cloned()
+            .unwrap_or_else(|| previous_balance[stake_acc].saturating_sub(STAKE_ACC_RENT_EXEMPTION))
+    }
+}
+
+#[cfg(test)]
+mod parse_delegations_tests {
+    use super::*;
+    use crate::backpressure::InFlightBatches;
+    use crate::storages::main_storage::MainStorage;
+    use crate::storages::memory::{MemoryMainStorage, MemoryQueueStorage};
+    use crate::storages::postgre_storage::models;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+    use std::str::FromStr;
+
+    const SLOT: u64 = 100;
+
+    fn stake_instruction(
+        instruction_idx: u8,
+        instruction_name: &str,
+        data: &str,
+        accounts: &[&str],
+    ) -> Instruction {
+        program_instruction(
+            STAKE_PROGRAM,
+            instruction_idx,
+            instruction_name,
+            data,
+            accounts,
+        )
     }
 
-    pub fn append_instructions(
-        instructions: Vec<UiCompiledInstruction>,
-        inner_instructions: Option<Vec<UiInnerInstructions>>,
-        accounts: Vec<String>,
-        tx_signature: String,
-        slot: u64,
-        block_time: u64,
-        tx_status: TxStatus,
-        instructions_set: &mut BTreeSet<Instruction>,
-        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
-    ) -> Result<(), ParseInstructionError> {
-        Self::append_outer_instruction(
+    fn program_instruction(
+        program: &str,
+        instruction_idx: u8,
+        instruction_name: &str,
+        data: &str,
+        accounts: &[&str],
+    ) -> Instruction {
+        let mut instruction =
+            Instruction::new(&Pubkey::from_str(program).unwrap(), &Signature::default());
+
+        instruction.slot = SLOT;
+        instruction.instruction_idx = instruction_idx;
+        instruction.instruction_name = instruction_name.to_string();
+        instruction.data = data.to_string();
+        instruction.accounts = accounts.iter().map(|account| account.to_string()).collect();
+
+        instruction
+    }
+
+    fn delegation(stake_acc: &str, vote_acc: &str, amount: u64, raw_idx: u32) -> Delegation {
+        Delegation {
+            slot: SLOT,
+            block_time: 0,
+            stake_acc: stake_acc.to_string(),
+            vote_acc: Some(vote_acc.to_string()),
+            tx_signature: Signature::default().to_string(),
+            amount,
+            raw_instruction_idx: raw_idx,
+        }
+    }
+
+    #[test]
+    fn split_then_delegate() {
+        let instructions = vec![
+            stake_instruction(0, "Split", r#"{"Split":4000000000}"#, &["source", "new"]),
+            stake_instruction(1, "DelegateStake", r#""DelegateStake""#, &["new", "vote"]),
+        ];
+        let pre_balances = HashMap::from([("source".to_string(), 10_000_000_000)]);
+        let mut vote_accounts = HashMap::new();
+
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
             instructions,
-            accounts.clone(),
-            tx_signature.clone(),
-            slot,
-            block_time,
-            tx_status,
-            instructions_set,
-            parsed_instruction_arguments,
-        )?;
-
-        Self::append_inner_instruction(
-            inner_instructions,
-            accounts.clone(),
-            tx_signature.clone(),
-            slot,
-            block_time,
-            tx_status,
-            instructions_set,
-            parsed_instruction_arguments,
-        )?;
-
-        Ok(())
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert_eq!(
+            delegations,
+            vec![delegation(
+                "new",
+                "vote",
+                4_000_000_000 - STAKE_ACC_RENT_EXEMPTION,
+                256
+            )]
+        );
+        assert!(undelegations.is_empty());
+        assert_eq!(vote_accounts["new"], Some("vote".to_string()));
     }
 
-    fn append_inner_instruction(
-        inner_instructions: Option<Vec<UiInnerInstructions>>,
-        accounts: Vec<String>,
-        tx_signature: String,
-        slot: u64,
-        block_time: u64,
-        tx_status: TxStatus,
-        instructions_set: &mut BTreeSet<Instruction>,
-        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
-    ) -> Result<(), ParseInstructionError> {
-        if let Some(inner_instructions) = inner_instructions {
-            for (inner_instructions_set, instruction) in inner_instructions.iter().enumerate() {
-                let index = instruction.index;
-                for (instruction_idx, instruction) in instruction.instructions.iter().enumerate() {
-                    if let UiInstruction::Compiled(instruction) = instruction {
-                        let inner_program_address =
-                            accounts.get(instruction.program_id_index as usize);
-                        if inner_program_address.is_none() {
-                            return Err(ParseInstructionError::ParseError(
-                                "Failed to get inner_program_address".to_string(),
-                            ));
-                        }
-                        let inner_program_address = inner_program_address.unwrap();
-
-                        let mut inner_instruction_accounts = Vec::new();
-
-                        for account_idx in instruction.accounts.iter() {
-                            let inner_instruction_account = accounts.get(*account_idx as usize);
-                            if let Some(inner_instruction_account) = inner_instruction_account {
-                                inner_instruction_accounts
-                                    .push(Some(inner_instruction_account.to_owned()));
-                            } else {
-                                return Err(ParseInstructionError::InvalidIndex {
-                                    site: "inner_instruction".to_string(),
-                                    index: *account_idx as usize,
-                                    max_len: accounts.len(),
-                                });
-                            };
-                        }
+    #[test]
+    fn split_of_delegated_stake() {
+        let instructions = vec![stake_instruction(
+            0,
+            "Split",
+            r#"{"Split":4000000000}"#,
+            &["source", "new"],
+        )];
+        let pre_balances = HashMap::from([("source".to_string(), 10_000_000_000)]);
+        let mut vote_accounts = HashMap::from([("source".to_string(), Some("vote".to_string()))]);
 
-                        inner_instruction_accounts.resize(ACCOUNTS_ARRAY_SIZE, Default::default());
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert_eq!(
+            delegations,
+            vec![delegation(
+                "new",
+                "vote",
+                4_000_000_000 - STAKE_ACC_RENT_EXEMPTION,
+                0
+            )]
+        );
+        assert_eq!(
+            undelegations,
+            vec![delegation("source", "vote", 4_000_000_000, 0)]
+        );
+        assert_eq!(vote_accounts["source"], Some("vote".to_string()));
+        assert_eq!(vote_accounts["new"], Some("vote".to_string()));
+    }
 
-                        let parsed_data = TransactionParser::parse_instruction(
-                            inner_program_address,
-                            &instruction.data.from_base58()?,
-                        );
+    #[test]
+    fn merge_of_two_active_stakes() {
+        let instructions = vec![stake_instruction(
+            0,
+            "Merge",
+            r#""Merge""#,
+            &["destination", "source"],
+        )];
+        let pre_balances = HashMap::from([
+            (
+                "destination".to_string(),
+                5_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            ),
+            (
+                "source".to_string(),
+                3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            ),
+        ]);
+        let mut vote_accounts = HashMap::from([
+            ("destination".to_string(), Some("vote".to_string())),
+            ("source".to_string(), Some("vote".to_string())),
+        ]);
+
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert_eq!(
+            delegations,
+            vec![delegation("destination", "vote", 3_000_000_000, 0)]
+        );
+        assert_eq!(
+            undelegations,
+            vec![delegation("source", "vote", 3_000_000_000, 0)]
+        );
+        assert_eq!(vote_accounts["destination"], Some("vote".to_string()));
+        assert_eq!(vote_accounts["source"], None);
+    }
 
-                        let mut parsed_data =
-                            if let Err(ParseInstructionError::ProgramAddressMatchError) =
-                                parsed_data
-                            {
-                                (instruction.data.clone(), Vec::new())
-                            } else {
-                                parsed_data?
-                            };
-
-                        let data_cloned = parsed_data.0.clone();
-                        let splitted_data = data_cloned.split('\"').collect::<Vec<&str>>();
-
-                        let instruction_name = if splitted_data.len() > 2 {
-                            splitted_data[1].to_string()
-                        } else if splitted_data.len() == 1 {
-                            // splitted_data.len() == 1 means that parsed_data.0 is Base58 text (ProgramAddressMatchError occured)
-                            std::default::Default::default()
-                        } else {
-                            return Err(ParseInstructionError::InvalidInstructionName);
-                        };
-
-                        let accounts: Result<[Option<String>; ACCOUNTS_ARRAY_SIZE], _> =
-                            inner_instruction_accounts.try_into();
-
-                        if accounts.is_err() {
-                            Err(ConvertingError::DifferentLengths)?;
-                        }
-                        let accounts = accounts.unwrap();
+    #[test]
+    fn withdraw_from_delegated_stake() {
+        let instructions = vec![
+            stake_instruction(
+                0,
+                "Withdraw",
+                r#"{"Withdraw":1000000000}"#,
+                &["stake", "recipient"],
+            ),
+            stake_instruction(
+                1,
+                "Withdraw",
+                r#"{"Withdraw":5000000000}"#,
+                &["stake", "recipient"],
+            ),
+        ];
+        let pre_balances = HashMap::from([(
+            "stake".to_string(),
+            3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+        )]);
+        let mut vote_accounts = HashMap::from([("stake".to_string(), Some("vote".to_string()))]);
 
-                        let instr = Instruction {
-                            program: inner_program_address.clone(),
-                            tx_signature: tx_signature.clone(),
-                            slot,
-                            block_time: block_time as u64,
-                            tx_status,
-                            instruction_idx: instruction_idx as u8,
-                            inner_instructions_set: Some(inner_instructions_set as u8),
-                            transaction_instruction_idx: Some(index),
-                            accounts,
-                            instruction_name,
-                            data: parsed_data.0,
-                        };
-
-                        instructions_set.insert(instr);
-
-                        for instruction_argument in parsed_data.1.iter_mut() {
-                            instruction_argument.tx_signature = tx_signature.clone();
-                            instruction_argument.instruction_idx = instruction_idx as u8;
-                            instruction_argument.inner_instructions_set =
-                                Some(inner_instructions_set as u8);
-                            instruction_argument.program = inner_program_address.clone();
-                        }
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert!(delegations.is_empty());
+        // The second withdrawal is bounded by the remaining delegated amount
+        assert_eq!(
+            undelegations,
+            vec![
+                delegation("stake", "vote", 1_000_000_000, 0),
+                delegation("stake", "vote", 2_000_000_000, 512),
+            ]
+        );
+    }
 
-                        parsed_instruction_arguments.append(&mut parsed_data.1);
-                    } else {
-                        return Err(ParseInstructionError::Unsupported(
-                            "UiInstruction::Compiled in Inner instruction".to_string(),
-                        ));
-                    }
-                }
-            }
-        }
-        Ok(())
+    #[test]
+    fn withdraw_of_rent_exempt_reserve() {
+        let instructions = vec![stake_instruction(
+            0,
+            "Withdraw",
+            &format!(r#"{{"Withdraw":{}}}"#, STAKE_ACC_RENT_EXEMPTION),
+            &["stake", "recipient"],
+        )];
+        let pre_balances = HashMap::from([("stake".to_string(), STAKE_ACC_RENT_EXEMPTION)]);
+        let mut vote_accounts = HashMap::from([("stake".to_string(), Some("vote".to_string()))]);
+
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert!(delegations.is_empty());
+        assert!(undelegations.is_empty());
     }
 
-    fn append_outer_instruction(
-        instructions: Vec<UiCompiledInstruction>,
-        accounts: Vec<String>,
-        tx_signature: String,
-        slot: u64,
-        block_time: u64,
-        tx_status: TxStatus,
-        instructions_set: &mut BTreeSet<Instruction>,
-        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
-    ) -> Result<(), ParseInstructionError> {
-        for (instruction_idx, instruction) in instructions.iter().enumerate() {
-            let program_address = accounts.get(instruction.program_id_index as usize);
-
-            if program_address.is_none() {
-                return Err(ParseInstructionError::ParseError(
-                    "Failed to get program_address".to_string(),
-                ));
-            }
-            let program_address = program_address.unwrap();
-
-            let mut instruction_accounts = Vec::new();
-
-            for account_idx in instruction.accounts.iter() {
-                let instruction_account = accounts.get(*account_idx as usize);
-                if let Some(instruction_account) = instruction_account {
-                    instruction_accounts.push(Some(instruction_account.to_owned()));
-                } else {
-                    return Err(ParseInstructionError::InvalidIndex {
-                        site: "instruction".to_string(),
-                        index: *account_idx as usize,
-                        max_len: accounts.len(),
-                    });
-                };
-            }
+    #[test]
+    fn withdraw_from_never_delegated_stake() {
+        let instructions = vec![stake_instruction(
+            0,
+            "Withdraw",
+            r#"{"Withdraw":1000000000}"#,
+            &["stake", "recipient"],
+        )];
+        let pre_balances = HashMap::from([(
+            "stake".to_string(),
+            3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+        )]);
+        let mut vote_accounts = HashMap::new();
+
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
 
-            instruction_accounts.resize_with(ACCOUNTS_ARRAY_SIZE, Default::default);
-
-            // if program_address == "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk" {
-            //     log::error!("DATA: {:?}, tx: {}", instruction.data, tx_signature)
-            // }
-            let parsed_data = TransactionParser::parse_instruction(
-                program_address,
-                &instruction.data.from_base58()?,
-            );
-
-            let mut parsed_data =
-                if let Err(ParseInstructionError::ProgramAddressMatchError) = parsed_data {
-                    (instruction.data.clone(), Vec::new())
-                } else {
-                    parsed_data?
-                };
+        assert!(delegations.is_empty());
+        assert!(undelegations.is_empty());
+    }
 
-            let data_cloned = parsed_data.0.clone();
-            let splitted_data = data_cloned.split('\"').collect::<Vec<&str>>();
+    /// The lamports of a stake account created and delegated by the same transaction come with
+    /// the system instruction creating it
+    fn assert_created_and_delegated(create_instruction: Instruction) {
+        let instructions = TransactionParser::delegation_instructions(vec![
+            create_instruction,
+            stake_instruction(
+                1,
+                "Initialize",
+                r#"{"Initialize":[{"staker":"owner","withdrawer":"owner"},{"unix_timestamp":0,"epoch":0,"custodian":"owner"}]}"#,
+                &["new", "rent"],
+            ),
+            stake_instruction(2, "DelegateStake", r#""DelegateStake""#, &["new", "vote"]),
+        ]);
+        // The new account is in the transaction with a zero pre balance
+        let pre_balances = HashMap::from([
+            ("funding".to_string(), 10_000_000_000),
+            ("new".to_string(), 0),
+        ]);
+        let mut vote_accounts = HashMap::new();
+
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut vote_accounts,
+            &mut HashMap::new(),
+        );
+
+        assert_eq!(
+            delegations,
+            vec![delegation("new", "vote", 4_000_000_000, 512)]
+        );
+        assert!(undelegations.is_empty());
+        assert_eq!(vote_accounts["new"], Some("vote".to_string()));
+    }
 
-            let instruction_name = if splitted_data.len() > 2 {
-                splitted_data[1].to_string()
-            } else if splitted_data.len() == 1 {
-                // splitted_data.len() == 1 means that parsed_data.0 is Base58 text (ProgramAddressMatchError occured)
-                std::default::Default::default()
-            } else {
-                return Err(ParseInstructionError::InvalidInstructionName);
-            };
+    fn transfer(from: &str, to: &str, lamports: u64) -> Instruction {
+        program_instruction(
+            SYSTEM_PROGRAM,
+            0,
+            "Transfer",
+            &format!(r#"{{"Transfer":{{"lamports":{}}}}}"#, lamports),
+            &[from, to],
+        )
+    }
 
-            let accounts: Result<[Option<String>; ACCOUNTS_ARRAY_SIZE], _> =
-                instruction_accounts.try_into();
+    #[test]
+    fn transfer_beyond_the_pre_balance() {
+        // The transfer failed, the funding account never had the lamports
+        let instructions = vec![
+            transfer("funding", "stake", 5_000_000_000),
+            stake_instruction(1, "DelegateStake", r#""DelegateStake""#, &["stake", "vote"]),
+        ];
+        let pre_balances = HashMap::from([
+            ("funding".to_string(), 1_000),
+            ("stake".to_string(), STAKE_ACC_RENT_EXEMPTION),
+        ]);
 
-            if accounts.is_err() {
-                Err(ConvertingError::DifferentLengths)?;
-            }
-            let accounts = accounts.unwrap();
-
-            let instr = Instruction {
-                program: program_address.clone(),
-                tx_signature: tx_signature.clone(),
-                slot,
-                block_time,
-                tx_status,
-                instruction_idx: instruction_idx as u8,
-                inner_instructions_set: None,
-                transaction_instruction_idx: None,
-                accounts,
-                instruction_name,
-                data: parsed_data.0,
-            };
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &pre_balances,
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+        );
+
+        assert_eq!(
+            delegations,
+            vec![delegation("stake", "vote", 5_000_000_000, 256)]
+        );
+        assert!(undelegations.is_empty());
+    }
+
+    #[test]
+    fn instruction_without_two_accounts_is_skipped() {
+        let instructions = vec![
+            stake_instruction(0, "Deactivate", r#""Deactivate""#, &["stake"]),
+            program_instruction(
+                SYSTEM_PROGRAM,
+                1,
+                "Transfer",
+                r#"{"Transfer":{"lamports":1000000000}}"#,
+                &["funding"],
+            ),
+        ];
 
-            instructions_set.insert(instr);
+        let (delegations, undelegations) = TransactionParser::collect_delegations(
+            instructions,
+            &HashMap::new(),
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+        );
 
-            for instruction_argument in parsed_data.1.iter_mut() {
-                instruction_argument.tx_signature = tx_signature.clone();
-                instruction_argument.instruction_idx = instruction_idx as u8;
-                instruction_argument.inner_instructions_set = None;
-                instruction_argument.program = program_address.clone();
-            }
+        assert!(delegations.is_empty());
+        assert!(undelegations.is_empty());
+    }
 
-            parsed_instruction_arguments.append(&mut parsed_data.1);
-        }
+    #[test]
+    fn create_account_with_seed_then_delegate() {
+        assert_created_and_delegated(program_instruction(
+            SYSTEM_PROGRAM,
+            0,
+            "CreateAccountWithSeed",
+            &format!(
+                r#"{{"CreateAccountWithSeed":{{"base":"funding","seed":"stake:0","lamports":{},"space":200,"owner":"{}"}}}}"#,
+                4_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+                STAKE_PROGRAM
+            ),
+            &["funding", "new", "funding"],
+        ));
+    }
+
+    #[test]
+    fn create_account_then_delegate() {
+        assert_created_and_delegated(program_instruction(
+            SYSTEM_PROGRAM,
+            0,
+            "CreateAccount",
+            &format!(
+                r#"{{"CreateAccount":{{"lamports":{},"space":200,"owner":"{}"}}}}"#,
+                4_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+                STAKE_PROGRAM
+            ),
+            &["funding", "new"],
+        ));
+    }
+
+    /// Parses the `instructions` with the queue and the main storage of the `test`
+    async fn parse(
+        test: &str,
+        stored_delegations: Vec<Delegation>,
+        instructions: Vec<Instruction>,
+        pre_balances: HashMap<String, u64>,
+    ) -> (Delegations, Undelegations) {
+        let url = format!("memory://{}", test);
+        let mut main_storage = MemoryMainStorage::connect(&url);
+        main_storage
+            .store_delegations_block(stored_delegations)
+            .await
+            .unwrap();
+
+        let queue_manager = QueueManagerHandle::with_storage(
+            Box::new(MemoryQueueStorage::connect(&url)),
+            10,
+            InFlightBatches::default(),
+        );
+
+        TransactionParser::parse_delegations(
+            queue_manager,
+            MainStorageManagerHandle::with_storage(Box::new(main_storage)),
+            instructions,
+            pre_balances,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn vote_acc_unknown_to_the_queue_is_looked_up_in_the_main_storage() {
+        let stored = Delegation {
+            slot: SLOT - 10,
+            ..delegation("stake", "vote", 3_000_000_000, 0)
+        };
+
+        let (delegations, undelegations) = parse(
+            "vote_acc_unknown_to_the_queue_is_looked_up_in_the_main_storage",
+            vec![stored],
+            vec![stake_instruction(
+                0,
+                "Deactivate",
+                r#""Deactivate""#,
+                &["stake", "clock"],
+            )],
+            HashMap::from([(
+                "stake".to_string(),
+                3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            )]),
+        )
+        .await;
+
+        assert!(delegations.is_empty());
+        assert_eq!(
+            undelegations,
+            vec![delegation("stake", "vote", 3_000_000_000, 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn vote_acc_unknown_to_both_storages() {
+        let (delegations, undelegations) = parse(
+            "vote_acc_unknown_to_both_storages",
+            vec![],
+            vec![stake_instruction(
+                0,
+                "Deactivate",
+                r#""Deactivate""#,
+                &["stake", "clock"],
+            )],
+            HashMap::from([(
+                "stake".to_string(),
+                3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            )]),
+        )
+        .await;
+
+        assert!(delegations.is_empty());
+        assert_eq!(
+            undelegations,
+            vec![Delegation {
+                vote_acc: None,
+                ..delegation("stake", "vote", 3_000_000_000, 0)
+            }]
+        );
+    }
+
+    fn withdraw(amount: u64) -> Instruction {
+        stake_instruction(
+            0,
+            "Withdraw",
+            &format!(r#"{{"Withdraw":{}}}"#, amount),
+            &["stake", "recipient"],
+        )
+    }
+
+    #[tokio::test]
+    async fn withdraw_after_deactivation_in_a_previous_transaction() {
+        let test = "withdraw_after_deactivation_in_a_previous_transaction";
+        let balance = HashMap::from([(
+            "stake".to_string(),
+            3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+        )]);
+        let stored = Delegation {
+            slot: SLOT - 10,
+            ..delegation("stake", "vote", 3_000_000_000, 0)
+        };
+
+        let (_, undelegations) = parse(
+            test,
+            vec![stored],
+            vec![stake_instruction(
+                0,
+                "Deactivate",
+                r#""Deactivate""#,
+                &["stake", "clock"],
+            )],
+            balance.clone(),
+        )
+        .await;
+        assert_eq!(
+            undelegations,
+            vec![delegation("stake", "vote", 3_000_000_000, 0)]
+        );
+
+        // The queue storage knows the stake account is undelegated, so the delegation still
+        // stored in the main storage is not undelegated again
+        let (delegations, undelegations) = parse(
+            test,
+            vec![],
+            vec![withdraw(3_000_000_000 + STAKE_ACC_RENT_EXEMPTION)],
+            balance,
+        )
+        .await;
+        assert!(delegations.is_empty());
+        assert!(undelegations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn withdraw_of_lamports_deposited_after_delegation() {
+        let test = "withdraw_of_lamports_deposited_after_delegation";
+
+        parse(
+            test,
+            vec![],
+            vec![stake_instruction(
+                0,
+                "DelegateStake",
+                r#""DelegateStake""#,
+                &["stake", "vote"],
+            )],
+            HashMap::from([(
+                "stake".to_string(),
+                3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            )]),
+        )
+        .await;
+
+        // 2 SOL have been transferred to the stake account since, the withdrawal undelegates
+        // no more than the delegated 3 SOL
+        let (delegations, undelegations) = parse(
+            test,
+            vec![],
+            vec![withdraw(4_000_000_000), withdraw(2_000_000_000)],
+            HashMap::from([(
+                "stake".to_string(),
+                5_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            )]),
+        )
+        .await;
+        assert!(delegations.is_empty());
+        assert_eq!(
+            undelegations,
+            vec![delegation("stake", "vote", 3_000_000_000, 0)]
+        );
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn transfer_alone_does_not_look_up_the_stake_accounts() {
+        let url = "memory://transfer_alone_does_not_look_up_the_stake_accounts";
+        let queue_storage = MemoryQueueStorage::connect(url);
+        let queue_manager = QueueManagerHandle::with_storage(
+            Box::new(queue_storage.clone()),
+            10,
+            InFlightBatches::default(),
+        );
+        let main_storage_manager =
+            MainStorageManagerHandle::with_storage(Box::new(MemoryMainStorage::connect(url)));
+
+        let (delegations, undelegations) = TransactionParser::parse_delegations(
+            queue_manager.clone(),
+            main_storage_manager.clone(),
+            vec![transfer("funding", "stake", 1_000_000_000)],
+            HashMap::from([("funding".to_string(), 10_000_000_000)]),
+        )
+        .await
+        .unwrap();
+        assert!(delegations.is_empty());
+        assert!(undelegations.is_empty());
+
+        // Delegated since, a lookup for the transfer would have cached the stake account as
+        // unknown to the queue storage
+        queue_storage.queue().delegations.insert(
+            "stake".to_string(),
+            models::Delegation {
+                stake_acc: "stake".to_string(),
+                vote_acc: Some("vote".to_string()),
+                delegated_amount: Some(3_000_000_000),
+            },
+        );
+
+        let (_, undelegations) = TransactionParser::parse_delegations(
+            queue_manager,
+            main_storage_manager,
+            vec![stake_instruction(
+                0,
+                "Deactivate",
+                r#""Deactivate""#,
+                &["stake", "clock"],
+            )],
+            HashMap::from([(
+                "stake".to_string(),
+                3_000_000_000 + STAKE_ACC_RENT_EXEMPTION,
+            )]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            undelegations,
+            vec![delegation("stake", "vote", 3_000_000_000, 0)]
+        );
     }
 }
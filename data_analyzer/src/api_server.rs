@@ -0,0 +1,593 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    ops::{Deref, DerefMut},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{error, info};
+use serde::Serialize;
+use serde_json::json;
+use solana_sdk::{hash::hash, pubkey::Pubkey, signature::Signature};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{
+    configuration::ApiServerConfig,
+    register::Register,
+    storages::main_storage::{connect_main_storage, MainStorage, Page},
+};
+
+/// HTTP/JSON API serving the rows of the main storage, so its consumers don't have to know the
+/// tables: `GET /instructions?tx=`, `GET /delegations?stake_acc=&from_slot=&to_slot=` and
+/// `GET /balances?account=`. Every endpoint takes `limit` and `offset`
+pub struct ApiServer {
+    storages: StoragePool,
+    config: ApiServerConfig,
+}
+
+/// Connections of the main storage, a request takes a free one for its query and waits when
+/// all of them are taken
+struct StoragePool {
+    free: std::sync::Mutex<Vec<Box<dyn MainStorage>>>,
+    available: Semaphore,
+}
+
+impl StoragePool {
+    fn new(storages: Vec<Box<dyn MainStorage>>) -> Self {
+        Self {
+            available: Semaphore::new(storages.len()),
+            free: std::sync::Mutex::new(storages),
+        }
+    }
+
+    async fn get(&self) -> PooledStorage<'_> {
+        let permit = self
+            .available
+            .acquire()
+            .await
+            .expect("Storage pool semaphore is never closed");
+        let storage = self
+            .free
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("Every permit has a free storage");
+
+        PooledStorage {
+            pool: self,
+            storage: Some(storage),
+            _permit: permit,
+        }
+    }
+}
+
+/// Storage taken from the pool, it is put back when dropped
+struct PooledStorage<'a> {
+    pool: &'a StoragePool,
+    storage: Option<Box<dyn MainStorage>>,
+    // Released after the storage is put back
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Deref for PooledStorage<'_> {
+    type Target = Box<dyn MainStorage>;
+
+    fn deref(&self) -> &Self::Target {
+        self.storage.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledStorage<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.storage.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledStorage<'_> {
+    fn drop(&mut self) {
+        if let Some(storage) = self.storage.take() {
+            self.pool.free.lock().unwrap().push(storage);
+        }
+    }
+}
+
+/// Why a request is not answered with the rows
+enum ApiError {
+    /// The client has to fix the request
+    BadRequest(String),
+    Storage(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::Storage(err)
+    }
+}
+
+impl ApiServer {
+    /// The requests are served concurrently by as many `storages` as given
+    pub fn new(storages: Vec<Box<dyn MainStorage>>, config: ApiServerConfig) -> Self {
+        Self {
+            storages: StoragePool::new(storages),
+            config,
+        }
+    }
+
+    /// Opens `connections` to the main storage and serves the requests until the process exits
+    pub async fn setup_and_run(register: &Register) -> Result<()> {
+        let config = register
+            .config
+            .get_api_server_config()
+            .ok_or_else(|| anyhow!("The [api_server] section is not configured"))?
+            .clone();
+        let addr: SocketAddr = config.bind_address.parse()?;
+        let mut storages = Vec::with_capacity(config.connections);
+        for _ in 0..config.connections {
+            storages.push(connect_main_storage(register.config.get_main_storage_config()).await?);
+        }
+
+        let (addr, server) = Self::new(storages, config).bind(&addr)?;
+        info!("API server started on http://{}", addr);
+
+        tokio::spawn(server);
+
+        Ok(())
+    }
+
+    /// Binds the server to the `addr` and returns the actual address along with the future
+    /// serving the requests
+    fn bind(
+        self,
+        addr: &SocketAddr,
+    ) -> Result<(SocketAddr, impl std::future::Future<Output = ()>)> {
+        let api = Arc::new(self);
+
+        let server = Server::try_bind(addr)?.serve(make_service_fn(move |_| {
+            let api = api.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let api = api.clone();
+                    async move { Ok::<_, Infallible>(api.handle(request).await) }
+                }))
+            }
+        }));
+        let addr = server.local_addr();
+
+        Ok((addr, async move {
+            if let Err(err) = server.await {
+                error!("API server error: {}", err);
+            }
+        }))
+    }
+
+    pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::GET {
+            return Self::error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+        }
+
+        if !self.is_authorized(&request) {
+            let mut response = Self::error(StatusCode::UNAUTHORIZED, "Unauthorized");
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+            return response;
+        }
+
+        let query: HashMap<String, String> =
+            url::form_urlencoded::parse(request.uri().query().unwrap_or_default().as_bytes())
+                .into_owned()
+                .collect();
+
+        let result = match request.uri().path() {
+            "/instructions" => self.instructions(&query).await,
+            "/delegations" => self.delegations(&query).await,
+            "/balances" => self.balances(&query).await,
+            _ => return Self::error(StatusCode::NOT_FOUND, "Not found"),
+        };
+
+        match result {
+            Ok(response) => response,
+            Err(ApiError::BadRequest(message)) => Self::error(StatusCode::BAD_REQUEST, &message),
+            Err(ApiError::Storage(err)) => {
+                error!("Failed to serve {}: {:#}", request.uri(), err);
+                Self::error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+            }
+        }
+    }
+
+    /// The token is compared in constant time, so the time of a rejection doesn't tell how much
+    /// of the token was guessed. The hashes of both are compared to hide its length as well
+    fn is_authorized(&self, request: &Request<Body>) -> bool {
+        request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map_or(false, |token| {
+                let (token, expected) = (
+                    hash(token.as_bytes()).to_bytes(),
+                    hash(self.config.token.as_bytes()).to_bytes(),
+                );
+
+                token
+                    .iter()
+                    .zip(expected.iter())
+                    .fold(0, |difference, (a, b)| difference | (a ^ b))
+                    == 0
+            })
+    }
+
+    async fn instructions(
+        &self,
+        query: &HashMap<String, String>,
+    ) -> Result<Response<Body>, ApiError> {
+        let tx_signature: Signature = Self::required(query, "tx")?;
+        let page = self.page(query)?;
+
+        let instructions = self
+            .storages
+            .get()
+            .await
+            .fetch_instructions_by_signature(&tx_signature.to_string(), page)
+            .await?;
+
+        Ok(Self::json(&instructions))
+    }
+
+    async fn delegations(
+        &self,
+        query: &HashMap<String, String>,
+    ) -> Result<Response<Body>, ApiError> {
+        let stake_acc: Pubkey = Self::required(query, "stake_acc")?;
+        let from_slot = Self::optional(query, "from_slot")?.unwrap_or(u64::MIN);
+        let to_slot = Self::optional(query, "to_slot")?.unwrap_or(u64::MAX);
+        let page = self.page(query)?;
+
+        let delegations = self
+            .storages
+            .get()
+            .await
+            .fetch_delegations_for_stake_acc(&stake_acc.to_string(), from_slot, to_slot, page)
+            .await?;
+
+        Ok(Self::json(&delegations))
+    }
+
+    async fn balances(&self, query: &HashMap<String, String>) -> Result<Response<Body>, ApiError> {
+        let account: Pubkey = Self::required(query, "account")?;
+        let page = self.page(query)?;
+
+        let balances = self
+            .storages
+            .get()
+            .await
+            .fetch_balances_by_account(&account.to_string(), page)
+            .await?;
+
+        Ok(Self::json(&balances))
+    }
+
+    /// `limit` and `offset` of the request, the limit is `default_limit` without it and can't
+    /// exceed `max_limit`
+    fn page(&self, query: &HashMap<String, String>) -> Result<Page, ApiError> {
+        let limit = Self::optional(query, "limit")?.unwrap_or(self.config.default_limit);
+        if limit == 0 || limit > self.config.max_limit {
+            return Err(ApiError::BadRequest(format!(
+                "limit must be between 1 and {}",
+                self.config.max_limit
+            )));
+        }
+
+        Ok(Page {
+            limit,
+            offset: Self::optional(query, "offset")?.unwrap_or_default(),
+        })
+    }
+
+    /// The parameters are parsed before they get to the queries, so the TCP client can put
+    /// them into its SQL as they are
+    fn required<T: FromStr>(query: &HashMap<String, String>, name: &str) -> Result<T, ApiError> {
+        Self::optional(query, name)?
+            .ok_or_else(|| ApiError::BadRequest(format!("{} is required", name)))
+    }
+
+    fn optional<T: FromStr>(
+        query: &HashMap<String, String>,
+        name: &str,
+    ) -> Result<Option<T>, ApiError> {
+        query
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| ApiError::BadRequest(format!("Invalid {} {:?}", name, value)))
+            })
+            .transpose()
+    }
+
+    fn json<T: Serialize>(value: &T) -> Response<Body> {
+        Self::response(StatusCode::OK, serde_json::to_vec(value).unwrap())
+    }
+
+    fn error(status: StatusCode, message: &str) -> Response<Body> {
+        Self::response(status, json!({ "error": message }).to_string().into_bytes())
+    }
+
+    fn response(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storages::main_storage::{Balance, Delegation, Instruction};
+    use crate::storages::memory::MemoryMainStorage;
+    use hyper::body::to_bytes;
+    use serde_json::Value;
+
+    const TOKEN: &str = "secret";
+    const STAKE_ACC: &str = "Stake11111111111111111111111111111111111111";
+    const VOTE_ACC: &str = "Vote111111111111111111111111111111111111111";
+
+    /// Server of two connections to the memory storage named `url` with a limit of 2 rows
+    fn api_server(url: &str) -> ApiServer {
+        ApiServer::new(
+            vec![
+                Box::new(MemoryMainStorage::connect(url)),
+                Box::new(MemoryMainStorage::connect(url)),
+            ],
+            ApiServerConfig {
+                bind_address: "127.0.0.1:0".to_string(),
+                token: TOKEN.to_string(),
+                default_limit: 2,
+                max_limit: 2,
+                connections: 2,
+            },
+        )
+    }
+
+    async fn get_with_token(api: &ApiServer, uri: &str, token: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let response = api.handle(request).await;
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn get(api: &ApiServer, uri: &str) -> (StatusCode, Value) {
+        get_with_token(api, uri, TOKEN).await
+    }
+
+    #[tokio::test]
+    async fn instructions_of_the_transaction_in_execution_order() {
+        let api = api_server("api_instructions");
+        let tx_signature = Signature::new_unique();
+        let instruction = |instruction_idx, outer: Option<u8>| {
+            let mut instruction = Instruction::new(&Pubkey::default(), &tx_signature);
+            instruction.instruction_idx = instruction_idx;
+            instruction.inner_instructions_set = outer;
+            instruction.transaction_instruction_idx = outer;
+            instruction.instruction_name = format!("{}_{:?}", instruction_idx, outer);
+            instruction
+        };
+        api.storages
+            .get()
+            .await
+            .store_instructions_block(vec![
+                instruction(1, None),
+                instruction(0, Some(0)),
+                instruction(0, None),
+                Instruction::new(&Pubkey::default(), &Signature::new_unique()),
+            ])
+            .await
+            .unwrap();
+
+        let names = |body: Value| -> Vec<String> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|instruction| {
+                    instruction["instruction_name"]
+                        .as_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let (status, body) = get(&api, &format!("/instructions?tx={}", tx_signature)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(names(body), ["0_None", "0_Some(0)"]);
+
+        let (_, body) = get(&api, &format!("/instructions?tx={}&offset=2", tx_signature)).await;
+        assert_eq!(names(body), ["1_None"]);
+    }
+
+    #[tokio::test]
+    async fn delegations_of_the_stake_account_within_the_slots() {
+        let api = api_server("api_delegations");
+        let delegation = |slot| Delegation {
+            slot,
+            stake_acc: STAKE_ACC.to_string(),
+            vote_acc: Some(VOTE_ACC.to_string()),
+            amount: 1000,
+            ..Default::default()
+        };
+        api.storages
+            .get()
+            .await
+            .store_delegations_block(vec![delegation(30), delegation(10), delegation(20)])
+            .await
+            .unwrap();
+
+        let (status, body) = get(
+            &api,
+            &format!("/delegations?stake_acc={}&from_slot=15", STAKE_ACC),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!([
+                {
+                    "slot": 20,
+                    "block_time": 0,
+                    "stake_acc": STAKE_ACC,
+                    "vote_acc": VOTE_ACC,
+                    "tx_signature": "",
+                    "amount": 1000,
+                    "raw_instruction_idx": 0
+                },
+                {
+                    "slot": 30,
+                    "block_time": 0,
+                    "stake_acc": STAKE_ACC,
+                    "vote_acc": VOTE_ACC,
+                    "tx_signature": "",
+                    "amount": 1000,
+                    "raw_instruction_idx": 0
+                }
+            ])
+        );
+
+        let (_, body) = get(
+            &api,
+            &format!("/delegations?stake_acc={}&to_slot=15", STAKE_ACC),
+        )
+        .await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn balances_of_the_account() {
+        let api = api_server("api_balances");
+        let balance = |tx_signature: &str, account: &str| Balance {
+            tx_signature: tx_signature.to_string(),
+            account: account.to_string(),
+            pre_balance: Some(10),
+            post_balance: Some(5),
+            pre_token_balance_mint: None,
+            pre_token_balance_owner: None,
+            pre_token_balance_amount: None,
+            pre_token_balance_program_id: None,
+            post_token_balance_mint: None,
+            post_token_balance_owner: None,
+            post_token_balance_amount: None,
+            post_token_balance_program_id: None,
+            inner_instructions_set: None,
+            attributed_amount: None,
+        };
+        api.storages
+            .get()
+            .await
+            .store_balances_block(vec![
+                balance("b", VOTE_ACC),
+                balance("a", VOTE_ACC),
+                balance("a", STAKE_ACC),
+            ])
+            .await
+            .unwrap();
+
+        let (status, body) = get(&api, &format!("/balances?account={}&limit=1", VOTE_ACC)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!([{
+                "tx_signature": "a",
+                "account": VOTE_ACC,
+                "pre_balance": 10,
+                "post_balance": 5,
+                "pre_token_balance_mint": null,
+                "pre_token_balance_owner": null,
+                "pre_token_balance_amount": null,
+                "pre_token_balance_program_id": null,
+                "post_token_balance_mint": null,
+                "post_token_balance_owner": null,
+                "post_token_balance_amount": null,
+                "post_token_balance_program_id": null,
+                "inner_instructions_set": null,
+                "attributed_amount": null
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token() {
+        let api = api_server("api_token");
+        let uri = format!("/balances?account={}", VOTE_ACC);
+
+        for token in ["wrong", "secreT", "secret ", ""] {
+            let (status, _) = get_with_token(&api, &uri, token).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{:?}", token);
+        }
+
+        let response = api
+            .handle(Request::get(uri).body(Body::empty()).unwrap())
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn invalid_parameters_are_rejected() {
+        let api = api_server("api_invalid");
+
+        for (uri, error) in [
+            ("/instructions", "tx is required"),
+            ("/instructions?tx=1%27%20OR%201", r#"Invalid tx "1' OR 1""#),
+            (
+                "/delegations?stake_acc=Stake11111111111111111111111111111111111111&from_slot=x",
+                r#"Invalid from_slot "x""#,
+            ),
+            (
+                "/balances?account=Vote111111111111111111111111111111111111111&limit=3",
+                "limit must be between 1 and 2",
+            ),
+        ] {
+            let (status, body) = get(&api, uri).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body, json!({ "error": error }), "{}", uri);
+        }
+
+        let (status, _) = get(&api, "/transactions").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn request_is_served_while_another_holds_a_connection() {
+        let api = api_server("api_concurrent");
+        let timeout = std::time::Duration::from_secs(5);
+        let _busy = api.storages.get().await;
+
+        let uri = format!("/balances?account={}", VOTE_ACC);
+        let (status, _) = tokio::time::timeout(timeout, get(&api, &uri))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::OK);
+
+        // The connection of the request has been put back
+        tokio::time::timeout(timeout, api.storages.get())
+            .await
+            .unwrap();
+    }
+}
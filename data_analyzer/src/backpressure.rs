@@ -0,0 +1,95 @@
+use crate::metrics_update;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Batches of transactions taken from the queue whose rows are not flushed to the main storage
+/// yet. The queue manager takes a batch before fetching the next one and the collector gives the
+/// batches back once its buffers are flushed, so a slow main storage stops the fetching instead
+/// of piling the parsed rows up in memory
+#[derive(Debug, Clone)]
+pub struct InFlightBatches {
+    semaphore: Arc<Semaphore>,
+    count: Arc<AtomicUsize>,
+}
+
+impl Default for InFlightBatches {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl InFlightBatches {
+    /// At most `max` batches are in flight, 0 disables the limit
+    pub fn new(max: usize) -> Self {
+        let permits = if max == 0 {
+            Semaphore::MAX_PERMITS
+        } else {
+            max
+        };
+
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Waits until fewer than the maximum batches are in flight and takes one more
+    pub async fn acquire(&self) {
+        self.semaphore
+            .acquire()
+            .await
+            .expect("In-flight batches semaphore is never closed")
+            .forget();
+
+        let count = self.count.fetch_add(1, Ordering::SeqCst) + 1;
+        metrics_update!(set IN_FLIGHT_BATCHES, count as f64);
+    }
+
+    /// Gives back the batches which are flushed or turned out to be empty
+    pub fn release(&self, batches: usize) {
+        if batches == 0 {
+            return;
+        }
+
+        let count = self.count.fetch_sub(batches, Ordering::SeqCst) - batches;
+        metrics_update!(set IN_FLIGHT_BATCHES, count as f64);
+        self.semaphore.add_permits(batches);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn acquire_waits_for_release() {
+        let in_flight = InFlightBatches::new(2);
+
+        in_flight.acquire().await;
+        in_flight.acquire().await;
+        assert!(timeout(Duration::from_millis(100), in_flight.acquire())
+            .await
+            .is_err());
+        assert_eq!(in_flight.count(), 2);
+
+        in_flight.release(2);
+        in_flight.acquire().await;
+        assert_eq!(in_flight.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn zero_disables_the_limit() {
+        let in_flight = InFlightBatches::new(0);
+
+        for _ in 0..1000 {
+            in_flight.acquire().await;
+        }
+        assert_eq!(in_flight.count(), 1000);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::actors::main_storage_manager::MainStorageManagerHandle;
+use crate::configuration::BlockTimeConfig;
+use crate::metrics_update;
+use anyhow::Result;
+use async_trait::async_trait;
+use log::{error, info};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::collections::BTreeMap;
+use tokio::time::{sleep_until, Duration, Instant};
+
+/// Tells the block time of a slot, None if it is not known
+#[async_trait]
+pub trait BlockTimeSource: Send {
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>>;
+}
+
+/// The block time of the stored block
+#[async_trait]
+impl BlockTimeSource for MainStorageManagerHandle {
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        MainStorageManagerHandle::get_block_time(self, slot).await
+    }
+}
+
+/// `getBlockTime` of an RPC node, the requests are spaced out to send `requests_per_second` at
+/// most
+pub struct RpcBlockTimeSource {
+    client: RpcClient,
+    interval: Duration,
+    next_request: Instant,
+}
+
+impl RpcBlockTimeSource {
+    pub fn new(url: String, requests_per_second: u32) -> Self {
+        Self {
+            client: RpcClient::new(url),
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next_request: Instant::now(),
+        }
+    }
+}
+
+#[async_trait]
+impl BlockTimeSource for RpcBlockTimeSource {
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        sleep_until(self.next_request).await;
+        self.next_request = Instant::now() + self.interval;
+
+        match self.client.get_block_time(slot).await {
+            Ok(block_time) => Ok(Some(block_time)),
+            // The slot was skipped or the node doesn't keep its block
+            Err(ClientError {
+                kind: ClientErrorKind::RpcError(RpcError::RpcResponseError { .. }),
+                ..
+            }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Fills the block time of the transactions which some RPC providers load without it, so that
+/// their instructions are not stored as if they were executed at the epoch. A slot is looked up
+/// in the block times of the transactions of the batches seen so far, then in the blocks table,
+/// then with the RPC node if one is configured; the transaction keeps None when none of them
+/// knows it
+pub struct BlockTimeResolver {
+    /// Block times of the newest slots, the oldest ones are evicted above `cache_capacity`
+    cache: BTreeMap<u64, i64>,
+    cache_capacity: usize,
+    storage: Box<dyn BlockTimeSource>,
+    rpc: Option<Box<dyn BlockTimeSource>>,
+}
+
+impl BlockTimeResolver {
+    pub fn new(config: &BlockTimeConfig, storage: MainStorageManagerHandle) -> Self {
+        let rpc = config.rpc_url.clone().map(|url| {
+            info!(
+                "Missing block times are resolved with getBlockTime of {}",
+                url
+            );
+            Box::new(RpcBlockTimeSource::new(url, config.rpc_requests_per_second))
+                as Box<dyn BlockTimeSource>
+        });
+
+        Self::with_sources(config.cache_capacity, Box::new(storage), rpc)
+    }
+
+    pub fn with_sources(
+        cache_capacity: usize,
+        storage: Box<dyn BlockTimeSource>,
+        rpc: Option<Box<dyn BlockTimeSource>>,
+    ) -> Self {
+        Self {
+            cache: BTreeMap::new(),
+            cache_capacity,
+            storage,
+            rpc,
+        }
+    }
+
+    /// Sets the block time of the transactions of the batch which come without it, the ones
+    /// which have it are remembered first so that a slot is resolved within its batch
+    pub async fn resolve_batch(
+        &mut self,
+        transactions: &mut [EncodedConfirmedTransactionWithStatusMeta],
+    ) {
+        for transaction in transactions.iter() {
+            if let Some(block_time) = transaction.block_time {
+                self.remember(transaction.slot, block_time);
+            }
+        }
+
+        for transaction in transactions.iter_mut() {
+            if transaction.block_time.is_none() {
+                transaction.block_time = self.resolve(transaction.slot).await;
+            }
+        }
+    }
+
+    async fn resolve(&mut self, slot: u64) -> Option<i64> {
+        if let Some(block_time) = self.cache.get(&slot) {
+            metrics_update!(inc BLOCK_TIME_RESOLUTIONS_COUNT, &["cache"]);
+            return Some(*block_time);
+        }
+
+        let mut resolved = None;
+        let sources = std::iter::once(("storage", &mut self.storage))
+            .chain(self.rpc.as_mut().map(|rpc| ("rpc", rpc)));
+        for (name, source) in sources {
+            match source.get_block_time(slot).await {
+                Ok(Some(block_time)) => {
+                    resolved = Some((name, block_time));
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!(
+                        "Failed to get block_time of slot {} from the {}: {}",
+                        slot, name, err
+                    );
+                }
+            }
+        }
+
+        match resolved {
+            Some((name, block_time)) => {
+                metrics_update!(inc BLOCK_TIME_RESOLUTIONS_COUNT, &[name]);
+                self.remember(slot, block_time);
+                Some(block_time)
+            }
+            None => {
+                metrics_update!(inc BLOCK_TIME_RESOLUTIONS_COUNT, &["unresolved"]);
+                None
+            }
+        }
+    }
+
+    fn remember(&mut self, slot: u64, block_time: i64) {
+        self.cache.insert(slot, block_time);
+
+        while self.cache.len() > self.cache_capacity {
+            let oldest = *self.cache.keys().next().unwrap();
+            self.cache.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::prometheus_exporter::BLOCK_TIME_RESOLUTIONS_COUNT;
+    use std::sync::{Arc, Mutex};
+
+    /// Knows the block times of `block_times` and records the slots it is asked for
+    struct KnownBlockTimes {
+        block_times: Vec<(u64, i64)>,
+        requests: Arc<Mutex<Vec<u64>>>,
+    }
+
+    #[async_trait]
+    impl BlockTimeSource for KnownBlockTimes {
+        async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+            self.requests.lock().unwrap().push(slot);
+
+            Ok(self
+                .block_times
+                .iter()
+                .find(|(known_slot, _)| *known_slot == slot)
+                .map(|(_, block_time)| *block_time))
+        }
+    }
+
+    fn source(block_times: &[(u64, i64)]) -> (Box<dyn BlockTimeSource>, Arc<Mutex<Vec<u64>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let source = KnownBlockTimes {
+            block_times: block_times.to_vec(),
+            requests: requests.clone(),
+        };
+
+        (Box::new(source), requests)
+    }
+
+    fn transaction(
+        slot: u64,
+        block_time: Option<i64>,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [
+                    "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                ],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": "" }]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1000000, 1],
+                "postBalances": [995000, 1],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time,
+        }
+    }
+
+    #[tokio::test]
+    async fn block_time_of_another_transaction_of_the_slot() {
+        let (storage, storage_requests) = source(&[]);
+        let mut resolver = BlockTimeResolver::with_sources(16, storage, None);
+
+        let mut batch = vec![transaction(100, None), transaction(100, Some(1643213404))];
+        resolver.resolve_batch(&mut batch).await;
+        assert_eq!(batch[0].block_time, Some(1643213404));
+
+        // The slot is remembered for the next batches
+        let mut batch = vec![transaction(100, None)];
+        resolver.resolve_batch(&mut batch).await;
+        assert_eq!(batch[0].block_time, Some(1643213404));
+
+        assert!(storage_requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rpc_is_asked_after_the_storage() {
+        let (storage, storage_requests) = source(&[(100, 1643213404)]);
+        let (rpc, rpc_requests) = source(&[(101, 1643213405)]);
+        let mut resolver = BlockTimeResolver::with_sources(16, storage, Some(rpc));
+
+        let mut batch = vec![
+            transaction(100, None),
+            transaction(101, None),
+            transaction(101, None),
+        ];
+        resolver.resolve_batch(&mut batch).await;
+
+        let block_times: Vec<_> = batch
+            .iter()
+            .map(|transaction| transaction.block_time)
+            .collect();
+        assert_eq!(
+            block_times,
+            vec![Some(1643213404), Some(1643213405), Some(1643213405)]
+        );
+        assert_eq!(*storage_requests.lock().unwrap(), vec![100, 101]);
+        assert_eq!(*rpc_requests.lock().unwrap(), vec![101]);
+    }
+
+    #[tokio::test]
+    async fn unresolved_block_time_is_stored_as_null() {
+        let (storage, _) = source(&[]);
+        let (rpc, _) = source(&[]);
+        let mut resolver = BlockTimeResolver::with_sources(16, storage, Some(rpc));
+        let unresolved = BLOCK_TIME_RESOLUTIONS_COUNT.with_label_values(&["unresolved"]);
+        let unresolved_before = unresolved.get();
+
+        let mut batch = vec![transaction(100, None)];
+        resolver.resolve_batch(&mut batch).await;
+
+        assert_eq!(batch[0].block_time, None);
+        assert!(unresolved.get() > unresolved_before);
+
+        let instructions = solana_instruction_parser::parse_transaction(batch.remove(0))
+            .unwrap()
+            .instructions;
+        assert_eq!(instructions[0].block_time, None);
+    }
+
+    #[tokio::test]
+    async fn oldest_slots_are_evicted() {
+        let (storage, storage_requests) = source(&[]);
+        let mut resolver = BlockTimeResolver::with_sources(2, storage, None);
+
+        let mut batch = vec![
+            transaction(100, Some(1643213404)),
+            transaction(101, Some(1643213405)),
+            transaction(102, Some(1643213406)),
+        ];
+        resolver.resolve_batch(&mut batch).await;
+
+        let mut batch = vec![transaction(100, None), transaction(102, None)];
+        resolver.resolve_batch(&mut batch).await;
+
+        assert_eq!(batch[0].block_time, None);
+        assert_eq!(batch[1].block_time, Some(1643213406));
+        assert_eq!(*storage_requests.lock().unwrap(), vec![100]);
+    }
+}
@@ -1,43 +1,512 @@
 use crate::actors::queue_manager::StorageType;
+use crate::storages::main_storage::MainStorageType;
 use anyhow::Result;
+use clickhouse_storage::is_valid_table_prefix;
 use config::{Config, Environment};
+use indexer_errors::ConfigurationErrors;
+use indexer_telemetry::LogFormat;
 use serde::Deserialize;
+use solana_instruction_parser::ParseOptions;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::HashSet;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct QueueStorageConfig {
+    /// Url of the queue when it is not sharded
+    #[serde(default)]
     pub storage_url: String,
+    /// Urls of the databases the data_loader spreads the signatures over, in the same order as
+    /// in its config. `storage_url` is not used when they are set
+    #[serde(default)]
+    pub shards: Vec<String>,
     pub storage_type: StorageType,
+    #[serde(default = "default_parsing_claim_timeout")]
+    pub parsing_claim_timeout: u64,
+    /// Stake accounts whose vote account is kept in memory by the queue manager, 0 disables
+    /// the cache
+    #[serde(default = "default_delegations_cache_capacity")]
+    pub delegations_cache_capacity: usize,
+    /// Seconds after which a cached delegation is looked up in the queue storage again, so the
+    /// delegations saved by other analyzers sharing the queue are seen, 0 keeps it until it's
+    /// evicted, which is only right for a single analyzer
+    #[serde(default = "default_delegations_cache_ttl")]
+    pub delegations_cache_ttl: u64,
+    /// Batches of transactions whose rows are not flushed to the main storage yet at which the
+    /// next batch is not fetched until one of them is flushed, 0 disables the limit
+    #[serde(default = "default_max_unflushed_batches")]
+    pub max_unflushed_batches: usize,
+    /// Reads the queue after a cursor stored in the main storage instead of marking the rows,
+    /// for a read replica of the data_loader's database
+    #[serde(default)]
+    pub read_only: bool,
+    /// Seconds between the counts of the pending transactions exported as the queue metrics, 0
+    /// disables them
+    #[serde(default = "default_stats_interval")]
+    pub stats_interval: u64,
+    /// Seconds between the updates of the indexing watermark, 0 disables them
+    #[serde(default = "default_watermark_interval")]
+    pub watermark_interval: u64,
+}
+
+impl QueueStorageConfig {
+    /// Urls of the shards, the single storage is the only shard
+    pub fn storage_urls(&self) -> Vec<String> {
+        if self.shards.is_empty() {
+            vec![self.storage_url.clone()]
+        } else {
+            self.shards.clone()
+        }
+    }
+}
+
+fn default_parsing_claim_timeout() -> u64 {
+    600
+}
+
+fn default_delegations_cache_capacity() -> usize {
+    100_000
+}
+
+fn default_delegations_cache_ttl() -> u64 {
+    60
+}
+
+fn default_max_unflushed_batches() -> usize {
+    4
+}
+
+fn default_stats_interval() -> u64 {
+    60
+}
+
+fn default_watermark_interval() -> u64 {
+    60
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MainStorageConfig {
     pub database_url: String,
+    #[serde(default)]
+    pub storage_type: MainStorageType,
+    /// Rows the collector buffers for a table before inserting them as one block
+    #[serde(default = "default_max_block_rows")]
+    pub max_block_rows: usize,
+    /// Milliseconds after which the buffered rows are inserted even if the block is not full
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// Enables `async_insert` for the HTTP client
+    #[serde(default)]
+    pub async_insert: bool,
+    /// Rows written to a file by the `file://` storage before the next file is started
+    #[serde(default = "default_max_file_rows")]
+    pub max_file_rows: usize,
+    /// TTL of the tables, the TTLs of the live tables are left as they are without the section
+    #[serde(default)]
+    pub retention: Option<RetentionConfig>,
+    /// Prepended to the names of the ClickHouse tables, so several analyzers can share one
+    /// database. Empty keeps the bare names
+    #[serde(default)]
+    pub table_prefix: String,
+}
+
+/// Days the rows of a table are kept for, 0 keeps them forever
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub instructions: u32,
+    #[serde(default)]
+    pub instruction_arguments: u32,
+}
+
+fn default_max_block_rows() -> usize {
+    100_000
+}
+
+fn default_flush_interval_ms() -> u64 {
+    3000
+}
+
+fn default_max_file_rows() -> usize {
+    1_000_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionsParsingConfig {
+    /// Memos longer than this number of characters are truncated
+    #[serde(default = "default_max_memo_length")]
+    pub max_memo_length: usize,
+    /// Only this number of the newest lockouts of a vote is stored as its arguments
+    #[serde(default = "default_max_vote_lockouts")]
+    pub max_vote_lockouts: usize,
+}
+
+impl Default for TransactionsParsingConfig {
+    fn default() -> Self {
+        Self {
+            max_memo_length: default_max_memo_length(),
+            max_vote_lockouts: default_max_vote_lockouts(),
+        }
+    }
+}
+
+impl TransactionsParsingConfig {
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            max_memo_length: self.max_memo_length,
+            max_vote_lockouts: self.max_vote_lockouts,
+        }
+    }
+}
+
+fn default_max_memo_length() -> usize {
+    solana_instruction_parser::DEFAULT_MAX_MEMO_LENGTH
+}
+
+fn default_max_vote_lockouts() -> usize {
+    solana_instruction_parser::DEFAULT_MAX_VOTE_LOCKOUTS
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockTimeConfig {
+    /// Slots whose block time is remembered for the transactions which come without it
+    #[serde(default = "default_block_time_cache_capacity")]
+    pub cache_capacity: usize,
+    /// RPC node asked for the block time with `getBlockTime` when neither the batches nor the
+    /// blocks table know it, the RPC fallback is disabled if it is not set
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// `getBlockTime` requests sent to the RPC node per second at most
+    #[serde(default = "default_block_time_rpc_requests_per_second")]
+    pub rpc_requests_per_second: u32,
+}
+
+impl Default for BlockTimeConfig {
+    fn default() -> Self {
+        Self {
+            cache_capacity: default_block_time_cache_capacity(),
+            rpc_url: None,
+            rpc_requests_per_second: default_block_time_rpc_requests_per_second(),
+        }
+    }
+}
+
+fn default_block_time_cache_capacity() -> usize {
+    1024
+}
+
+fn default_block_time_rpc_requests_per_second() -> u32 {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CollectorConfig {
+    /// Approximate bytes of the rows buffered by a collector, all of its buffers are flushed
+    /// when it is reached
+    #[serde(default = "default_max_buffer_bytes")]
+    pub max_buffer_bytes: usize,
+    /// Programs with the most instructions which are not decoded exported with their own label
+    /// per flush interval, the rest are exported as "other"
+    #[serde(default = "default_coverage_top_unnamed_programs")]
+    pub coverage_top_unnamed_programs: usize,
+    /// Share of the instructions stored within a flush interval at which a program which is not
+    /// decoded is logged as missing a decoder
+    #[serde(default = "default_coverage_unnamed_share_warning")]
+    pub coverage_unnamed_share_warning: f64,
+    /// Inserts a failed block may be bisected with to find the rows the main storage rejects,
+    /// 0 disables the bisection
+    #[serde(default = "default_max_bisection_inserts")]
+    pub max_bisection_inserts: usize,
+    /// Seconds after which the bisection of a failed block stops
+    #[serde(default = "default_max_bisection_secs")]
+    pub max_bisection_secs: u64,
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        Self {
+            max_buffer_bytes: default_max_buffer_bytes(),
+            coverage_top_unnamed_programs: default_coverage_top_unnamed_programs(),
+            coverage_unnamed_share_warning: default_coverage_unnamed_share_warning(),
+            max_bisection_inserts: default_max_bisection_inserts(),
+            max_bisection_secs: default_max_bisection_secs(),
+        }
+    }
+}
+
+fn default_max_bisection_inserts() -> usize {
+    64
+}
+
+fn default_max_bisection_secs() -> u64 {
+    30
+}
+
+fn default_max_buffer_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_coverage_top_unnamed_programs() -> usize {
+    20
+}
+
+fn default_coverage_unnamed_share_warning() -> f64 {
+    0.1
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MigrationsConfig {
+    /// Only print the migrations which would be run and exit
+    #[serde(default)]
+    pub validate_only: bool,
+}
+
+/// Programs whose instructions are persisted. Everything is still parsed, so delegations and
+/// balances are not affected
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProgramsFilter {
+    /// If not empty, only these programs are persisted
+    #[serde(default)]
+    pub allow: HashSet<String>,
+    #[serde(default)]
+    pub deny: HashSet<String>,
+}
+
+impl ProgramsFilter {
+    pub fn is_persisted(&self, program: &str) -> bool {
+        (self.allow.is_empty() || self.allow.contains(program)) && !self.deny.contains(program)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnalysisConfig {
+    #[serde(default)]
+    pub programs: ProgramsFilter,
+    /// Stores balances of the accounts whose lamports and token balances are not changed by
+    /// the transaction
+    #[serde(default = "default_store_unchanged_balances")]
+    pub store_unchanged_balances: bool,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            programs: ProgramsFilter::default(),
+            store_unchanged_balances: default_store_unchanged_balances(),
+        }
+    }
+}
+
+fn default_store_unchanged_balances() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+/// Signatures whose parsing steps are logged, see `SignatureTracer`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub signatures: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct PrometheusExporter {
     bind_address: String,
+    /// Seconds without a parsed batch after which `/readyz` reports the parsing as stalled
+    #[serde(default = "default_readiness_staleness")]
+    readiness_staleness: u64,
+}
+
+fn default_readiness_staleness() -> u64 {
+    600
+}
+
+/// HTTP API serving the stored rows, run by the `serve-api` command
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiServerConfig {
+    pub bind_address: String,
+    /// Every request has to carry it as `Authorization: Bearer <token>`
+    pub token: String,
+    /// Rows of a response without `limit`
+    #[serde(default = "default_api_default_limit")]
+    pub default_limit: u64,
+    /// Highest `limit` of a request
+    #[serde(default = "default_api_max_limit")]
+    pub max_limit: u64,
+    /// Connections to the main storage, as many requests are queried at once
+    #[serde(default = "default_api_connections")]
+    pub connections: usize,
+}
+
+fn default_api_default_limit() -> u64 {
+    100
+}
+
+fn default_api_max_limit() -> u64 {
+    1000
+}
+
+fn default_api_connections() -> usize {
+    4
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Configuration {
     queue_storage: QueueStorageConfig,
     main_storage: MainStorageConfig,
+    #[serde(default)]
+    collector: CollectorConfig,
+    #[serde(default)]
+    migrations: MigrationsConfig,
+    #[serde(default)]
+    transactions_parsing: TransactionsParsingConfig,
+    #[serde(default)]
+    block_time: BlockTimeConfig,
+    #[serde(default)]
+    analysis: AnalysisConfig,
+    #[serde(default)]
+    logging: LoggingConfig,
+    #[serde(default)]
+    tracing: TracingConfig,
     prometheus_exporter: PrometheusExporter,
+    api_server: Option<ApiServerConfig>,
 }
 
 impl Configuration {
     pub fn new(filename: &str) -> Result<Self> {
-        Ok(Config::builder()
+        let config = Config::builder()
             .add_source(config::File::with_name(filename))
             .add_source(
                 Environment::with_prefix("da")
                     .prefix_separator("__")
                     .separator("__")
-                    .try_parsing(true),
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("queue_storage.shards")
+                    .with_list_parse_key("analysis.programs.allow")
+                    .with_list_parse_key("analysis.programs.deny")
+                    .with_list_parse_key("tracing.signatures"),
             )
             .build()?
-            .try_deserialize::<Configuration>()?)
+            .try_deserialize::<Configuration>()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads the configuration from a TOML string without the environment variables
+    #[cfg(test)]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let config = Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize::<Configuration>()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks the values serde can't, every problem is reported rather than the first one. The
+    /// urls of the in-memory storages are only names, so they are not checked
+    fn validate(&self) -> Result<(), ConfigurationErrors> {
+        let mut errors = ConfigurationErrors::new();
+
+        let queue_storage = &self.queue_storage;
+        let queue_schemes: &[&str] = match queue_storage.storage_type {
+            StorageType::RabbitMQ => &["amqp", "amqps"],
+            StorageType::PostgreSQL => &["postgres", "postgresql"],
+            StorageType::Memory => &[],
+        };
+        if !queue_schemes.is_empty() {
+            errors.check(
+                !queue_storage.storage_url.is_empty() || !queue_storage.shards.is_empty(),
+                || "Either storage_url or shards of the queue storage must be set".to_string(),
+            );
+            for (idx, url) in queue_storage.storage_urls().iter().enumerate() {
+                if !url.is_empty() {
+                    errors.check_url(&format!("queue_storage url {}", idx), url, queue_schemes);
+                }
+            }
+        }
+
+        let main_storage = &self.main_storage;
+        if let MainStorageType::ClickHouse = main_storage.storage_type {
+            errors.check_url(
+                "main_storage.database_url",
+                &main_storage.database_url,
+                &["tcp", "http", "https", "file"],
+            );
+        }
+        errors.check(main_storage.max_block_rows > 0, || {
+            "main_storage.max_block_rows must be positive".to_string()
+        });
+        errors.check(main_storage.max_file_rows > 0, || {
+            "main_storage.max_file_rows must be positive".to_string()
+        });
+        errors.check(is_valid_table_prefix(&main_storage.table_prefix), || {
+            format!(
+                "main_storage.table_prefix {:?} may hold only letters, digits and underscores \
+                and can't start with a digit",
+                main_storage.table_prefix
+            )
+        });
+        errors.check(self.collector.max_buffer_bytes > 0, || {
+            "collector.max_buffer_bytes must be positive".to_string()
+        });
+
+        if let Some(rpc_url) = &self.block_time.rpc_url {
+            errors.check_url("block_time.rpc_url", rpc_url, &["http", "https"]);
+            errors.check(self.block_time.rpc_requests_per_second > 0, || {
+                "block_time.rpc_requests_per_second must be positive".to_string()
+            });
+        }
+
+        let programs = &self.analysis.programs;
+        for program in programs.allow.iter().chain(&programs.deny) {
+            errors.check(Pubkey::from_str(program).is_ok(), || {
+                format!(
+                    "Program {:?} of analysis.programs is not a base58 public key",
+                    program
+                )
+            });
+        }
+
+        errors.check_bind_address(
+            "prometheus_exporter.bind_address",
+            &self.prometheus_exporter.bind_address,
+        );
+
+        for signature in &self.tracing.signatures {
+            errors.check(Signature::from_str(signature).is_ok(), || {
+                format!("Traced signature {:?} is not a base58 signature", signature)
+            });
+        }
+
+        if let Some(api_server) = &self.api_server {
+            errors.check_bind_address("api_server.bind_address", &api_server.bind_address);
+            errors.check(!api_server.token.is_empty(), || {
+                "api_server.token must be set".to_string()
+            });
+            errors.check(
+                (1..=api_server.max_limit).contains(&api_server.default_limit),
+                || {
+                    format!(
+                        "api_server.default_limit {} must be between 1 and max_limit {}",
+                        api_server.default_limit, api_server.max_limit
+                    )
+                },
+            );
+            errors.check(api_server.connections > 0, || {
+                "api_server.connections must be at least 1".to_string()
+            });
+        }
+
+        errors.into_result()
     }
 
     pub fn get_queue_storage_config(&self) -> &QueueStorageConfig {
@@ -48,10 +517,283 @@ impl Configuration {
         &self.main_storage
     }
 
+    pub fn get_collector_config(&self) -> &CollectorConfig {
+        &self.collector
+    }
+
+    pub fn get_migrations_config(&self) -> &MigrationsConfig {
+        &self.migrations
+    }
+
+    pub fn get_transactions_parsing_config(&self) -> &TransactionsParsingConfig {
+        &self.transactions_parsing
+    }
+
+    pub fn get_block_time_config(&self) -> &BlockTimeConfig {
+        &self.block_time
+    }
+
+    pub fn get_analysis_config(&self) -> &AnalysisConfig {
+        &self.analysis
+    }
+
+    pub fn get_logging_config(&self) -> &LoggingConfig {
+        &self.logging
+    }
+
+    pub fn get_tracing_config(&self) -> &TracingConfig {
+        &self.tracing
+    }
+
     pub fn get_storage_type(&self) -> &StorageType {
         &self.queue_storage.storage_type
     }
+
+    pub fn get_parsing_claim_timeout(&self) -> u64 {
+        self.queue_storage.parsing_claim_timeout
+    }
+
+    pub fn get_queue_stats_interval(&self) -> u64 {
+        self.queue_storage.stats_interval
+    }
+
+    pub fn get_watermark_interval(&self) -> u64 {
+        self.queue_storage.watermark_interval
+    }
+
+    pub fn get_api_server_config(&self) -> Option<&ApiServerConfig> {
+        self.api_server.as_ref()
+    }
+
     pub fn get_prometheus_exporter_bind_address(&self) -> String {
         self.prometheus_exporter.bind_address.clone()
     }
+
+    pub fn get_readiness_staleness(&self) -> u64 {
+        self.prometheus_exporter.readiness_staleness
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOTE_PROGRAM: &str = "Vote111111111111111111111111111111111111111";
+    const STAKE_PROGRAM: &str = "Stake11111111111111111111111111111111111111";
+
+    fn programs_filter(allow: &[&str], deny: &[&str]) -> ProgramsFilter {
+        ProgramsFilter {
+            allow: allow.iter().map(|program| program.to_string()).collect(),
+            deny: deny.iter().map(|program| program.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn programs_filter_is_persisted() {
+        assert!(ProgramsFilter::default().is_persisted(VOTE_PROGRAM));
+
+        let deny_vote = programs_filter(&[], &[VOTE_PROGRAM]);
+        assert!(!deny_vote.is_persisted(VOTE_PROGRAM));
+        assert!(deny_vote.is_persisted(STAKE_PROGRAM));
+
+        let allow_stake = programs_filter(&[STAKE_PROGRAM], &[]);
+        assert!(!allow_stake.is_persisted(VOTE_PROGRAM));
+        assert!(allow_stake.is_persisted(STAKE_PROGRAM));
+
+        // Deny wins over allow
+        let both = programs_filter(&[STAKE_PROGRAM], &[STAKE_PROGRAM]);
+        assert!(!both.is_persisted(STAKE_PROGRAM));
+    }
+
+    #[test]
+    fn retention_is_optional() {
+        let main_storage = r#"
+            [queue_storage]
+            storage_type = "PostgreSQL"
+            storage_url = "postgres://localhost/queue"
+
+            [main_storage]
+            database_url = "tcp://localhost:9000/default"
+
+            [prometheus_exporter]
+            bind_address = "127.0.0.1:0"
+        "#;
+
+        let config = Configuration::from_toml(main_storage).unwrap();
+        assert!(config.get_main_storage_config().retention.is_none());
+
+        let config = Configuration::from_toml(&format!(
+            "{}\n[main_storage.retention]\ninstruction_arguments = 90",
+            main_storage
+        ))
+        .unwrap();
+        let retention = config.get_main_storage_config().retention.clone().unwrap();
+        assert_eq!(retention.instruction_arguments, 90);
+        assert_eq!(retention.instructions, 0);
+    }
+
+    const VALID: &str = r#"
+        [queue_storage]
+        storage_type = "PostgreSQL"
+        storage_url = "postgres://localhost/queue"
+
+        [main_storage]
+        database_url = "tcp://localhost:9000/default"
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:0"
+    "#;
+
+    /// Problems of the valid configuration with the `replacements` applied
+    fn problems(replacements: &[(&str, &str)]) -> Vec<String> {
+        let toml = replacements
+            .iter()
+            .fold(VALID.to_string(), |toml, (from, to)| toml.replace(from, to));
+
+        Config::builder()
+            .add_source(config::File::from_str(&toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize::<Configuration>()
+            .unwrap()
+            .validate()
+            .map_or_else(|errors| errors.problems().to_vec(), |_| Vec::new())
+    }
+
+    #[test]
+    fn valid_configuration_has_no_problems() {
+        assert!(problems(&[]).is_empty());
+        assert!(
+            problems(&[("tcp://localhost:9000/default", "file:///var/lib/exports")]).is_empty()
+        );
+    }
+
+    #[test]
+    fn storage_urls_must_have_the_scheme_of_the_storage_type() {
+        let problems = problems(&[
+            ("postgres://localhost/queue", "amqp://localhost"),
+            (
+                "tcp://localhost:9000/default",
+                "clickhouse://localhost:9000",
+            ),
+        ]);
+
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("queue_storage url 0 has the amqp scheme"));
+        assert!(problems[1].starts_with("main_storage.database_url has the clickhouse scheme"));
+    }
+
+    #[test]
+    fn in_memory_storage_urls_are_not_checked() {
+        assert!(problems(&[
+            (
+                r#"storage_type = "PostgreSQL""#,
+                r#"storage_type = "Memory""#
+            ),
+            (
+                r#"database_url = "tcp://localhost:9000/default""#,
+                "storage_type = \"Memory\"\ndatabase_url = \"shared\"",
+            ),
+        ])
+        .is_empty());
+    }
+
+    #[test]
+    fn max_block_rows_must_be_positive() {
+        assert_eq!(
+            problems(&[(
+                "[prometheus_exporter]",
+                "max_block_rows = 0\n[prometheus_exporter]"
+            )]),
+            ["main_storage.max_block_rows must be positive"]
+        );
+    }
+
+    #[test]
+    fn table_prefix_must_make_identifiers() {
+        let table_prefix = |prefix: &str| {
+            problems(&[(
+                "[prometheus_exporter]",
+                &format!("table_prefix = {:?}\n[prometheus_exporter]", prefix),
+            )])
+        };
+
+        assert!(table_prefix("staging_").is_empty());
+        assert_eq!(
+            table_prefix("staging-"),
+            [
+                "main_storage.table_prefix \"staging-\" may hold only letters, digits and \
+                underscores and can't start with a digit"
+            ]
+        );
+        assert_eq!(table_prefix("1_").len(), 1);
+    }
+
+    #[test]
+    fn programs_must_be_public_keys() {
+        let problems = problems(&[(
+            "[prometheus_exporter]",
+            "[analysis.programs]\ndeny = [\"Vote1111\"]\n[prometheus_exporter]",
+        )]);
+
+        assert_eq!(
+            problems,
+            [r#"Program "Vote1111" of analysis.programs is not a base58 public key"#]
+        );
+    }
+
+    #[test]
+    fn bind_address_must_be_a_socket_address() {
+        let problems = problems(&[("127.0.0.1:0", ":9090")]);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("prometheus_exporter.bind_address"));
+    }
+
+    #[test]
+    fn unknown_storage_type_is_rejected() {
+        let toml = VALID.replace(
+            r#"storage_type = "PostgreSQL""#,
+            r#"storage_type = "Kafka""#,
+        );
+
+        assert!(Configuration::from_toml(&toml).is_err());
+    }
+
+    #[test]
+    fn api_server_is_checked_when_configured() {
+        let api_server = |section: &str| {
+            problems(&[(
+                "[prometheus_exporter]",
+                &format!("[api_server]\n{}\n[prometheus_exporter]", section),
+            )])
+        };
+
+        assert!(api_server("bind_address = \"127.0.0.1:8080\"\ntoken = \"secret\"").is_empty());
+        assert_eq!(
+            api_server(
+                "bind_address = \"127.0.0.1:8080\"\ntoken = \"\"\nmax_limit = 10\nconnections = 0"
+            ),
+            [
+                "api_server.token must be set",
+                "api_server.default_limit 100 must be between 1 and max_limit 10",
+                "api_server.connections must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let problems = problems(&[
+            ("postgres://localhost/queue", "localhost/queue"),
+            ("127.0.0.1:0", "localhost:0"),
+            (
+                "[prometheus_exporter]",
+                "[block_time]\nrpc_url = \"ws://localhost:8900\"\n[prometheus_exporter]",
+            ),
+        ]);
+
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(Configuration::from_toml(VALID).is_ok());
+    }
 }
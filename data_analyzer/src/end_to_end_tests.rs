@@ -0,0 +1,497 @@
+//! Parsing of a transaction queued by the data_loader in PostgreSQL into ClickHouse, both are
+//! started in Docker and migrated the way the services do it
+
+use crate::configuration::Configuration;
+use crate::register::Register;
+use crate::storages::main_storage::connect_main_storage;
+use crate::storages::main_storage::migrations::SCRIPTS_UP;
+use crate::storages::main_storage::schema::retained_tables;
+use crate::storages::main_storage::{Instruction, MainStorage, Page, TokenTransfer, TxStatus};
+use crate::storages::postgre_storage::schema;
+use crate::transactions_parsing_ctx::TransactionsParsingCtx;
+use anyhow::{anyhow, Result};
+use clickhouse_storage::{apply_retention, Migrations};
+use diesel::prelude::*;
+use integration_tests::{retry, Clickhouse, Postgres, TRANSACTION, TRANSACTION_SIGNATURE};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+/// Stores the transaction the way the data_loader does
+fn queue_transaction(postgres: &Postgres) -> Result<()> {
+    let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_str(TRANSACTION)?;
+
+    diesel::insert_into(schema::transactions::table)
+        .values((
+            schema::transactions::signature.eq(TRANSACTION_SIGNATURE),
+            schema::transactions::slot.eq(transaction.slot as i32),
+            schema::transactions::transaction.eq(serde_json::to_string(&transaction.transaction)?),
+            schema::transactions::block_time.eq(transaction.block_time.unwrap_or_default() as i32),
+            schema::transactions::parsing_status.eq(0),
+        ))
+        .execute(&postgres.connection()?)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn queued_transaction_is_stored_in_clickhouse() -> Result<()> {
+    let postgres = Postgres::start().await?;
+    let clickhouse = Clickhouse::start().await?;
+
+    postgres.run_queue_migrations()?;
+    queue_transaction(&postgres)?;
+
+    let register = Register::new(Configuration::from_toml(&format!(
+        r#"
+        [queue_storage]
+        storage_type = "PostgreSQL"
+        storage_url = "{}"
+
+        [main_storage]
+        database_url = "{}"
+        flush_interval_ms = 100
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:0"
+        "#,
+        postgres.url,
+        clickhouse.tcp_url()
+    ))?);
+
+    let mut storage = connect_main_storage(register.config.get_main_storage_config()).await?;
+    Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+
+    TransactionsParsingCtx::setup_and_run(&register).await?;
+
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+    let instructions = retry(|| async {
+        let block = pool
+            .get_handle()
+            .await?
+            .query(
+                "SELECT tx_signature, instruction_idx, slot, block_time FROM instructions
+                ORDER BY instruction_idx",
+            )
+            .fetch_all()
+            .await?;
+
+        if block.row_count() < 2 {
+            return Err(anyhow!("{} instructions are stored", block.row_count()));
+        }
+
+        block
+            .rows()
+            .map(|row| {
+                Ok((
+                    row.get::<String, _>("tx_signature")?,
+                    row.get::<u8, _>("instruction_idx")?,
+                    row.get::<u64, _>("slot")?,
+                    row.get::<Option<u64>, _>("block_time")?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()
+    })
+    .await?;
+
+    assert_eq!(
+        instructions,
+        [0, 1].map(|instruction_idx| (
+            TRANSACTION_SIGNATURE.to_string(),
+            instruction_idx,
+            117946133,
+            Some(1643213404),
+        ))
+    );
+
+    // The transaction is not claimed again
+    let parsing_status = schema::transactions::table
+        .filter(schema::transactions::signature.eq(TRANSACTION_SIGNATURE))
+        .select(schema::transactions::parsing_status)
+        .first::<Option<i32>>(&postgres.connection()?)?;
+    assert_eq!(parsing_status, Some(1));
+
+    Ok(())
+}
+
+/// A block re-sent after it has been stored, when its acknowledgement is lost or by a restarted
+/// analyzer, is dropped by ClickHouse by the deduplication token of the block
+#[tokio::test]
+async fn replayed_block_is_stored_once() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+
+    for (client, url) in [
+        ("tcp", clickhouse.tcp_url()),
+        ("http", clickhouse.http_url()),
+    ] {
+        let mut storage: Box<dyn MainStorage> = clickhouse_storage::connect(&url).await?;
+        Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+
+        let token_transfers: Vec<TokenTransfer> = (0..3)
+            .map(|idx| TokenTransfer {
+                tx_signature: format!("{}_{}", client, idx),
+                slot: idx,
+                mint: "mint".to_string(),
+                amount: 100,
+                ..Default::default()
+            })
+            .collect();
+
+        storage
+            .store_token_transfers_block(token_transfers.clone())
+            .await?;
+        storage.store_token_transfers_block(token_transfers).await?;
+
+        let block = pool
+            .get_handle()
+            .await?
+            .query(format!(
+                "SELECT count() AS count FROM token_transfers WHERE tx_signature LIKE '{}_%'",
+                client
+            ))
+            .fetch_all()
+            .await?;
+        let count: u64 = block
+            .rows()
+            .next()
+            .ok_or_else(|| anyhow!("No count"))?
+            .get("count")?;
+
+        assert_eq!(count, 3, "{} client", client);
+    }
+
+    Ok(())
+}
+
+/// An analyzer with a table prefix migrates and writes its own tables next to the bare ones of
+/// the analyzer without it, the rows of one are not seen in the other
+#[tokio::test]
+async fn prefixed_tables_are_isolated() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+
+    let count = |table: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let block = pool
+                .get_handle()
+                .await?
+                .query(format!("SELECT count() AS count FROM {}", table))
+                .fetch_all()
+                .await?;
+            let count: u64 = block
+                .rows()
+                .next()
+                .ok_or_else(|| anyhow!("No count"))?
+                .get("count")?;
+            Ok::<u64, anyhow::Error>(count)
+        }
+    };
+
+    for (stored, (client, url)) in [
+        ("tcp", clickhouse.tcp_url()),
+        ("http", clickhouse.http_url()),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let config = Configuration::from_toml(&format!(
+            r#"
+            [queue_storage]
+            storage_type = "Memory"
+
+            [main_storage]
+            database_url = "{}"
+            table_prefix = "staging_"
+
+            [prometheus_exporter]
+            bind_address = "127.0.0.1:0"
+            "#,
+            url
+        ))?;
+        let table_prefix = &config.get_main_storage_config().table_prefix;
+
+        let mut production: Box<dyn MainStorage> = clickhouse_storage::connect(&url).await?;
+        Migrations::new(&SCRIPTS_UP).up(production.as_mut()).await?;
+
+        let mut staging = connect_main_storage(config.get_main_storage_config()).await?;
+        Migrations::new(&SCRIPTS_UP)
+            .with_table_prefix(table_prefix)
+            .up(staging.as_mut())
+            .await?;
+
+        let tx_signature = Signature::new_unique();
+        let mut instruction = Instruction::new(&Pubkey::new_unique(), &tx_signature);
+        instruction.tx_status = TxStatus::Success;
+        staging.store_instructions_block(vec![instruction]).await?;
+
+        let page = Page {
+            limit: 10,
+            offset: 0,
+        };
+        let tx_signature = tx_signature.to_string();
+        assert_eq!(
+            staging
+                .fetch_instructions_by_signature(&tx_signature, page)
+                .await?
+                .len(),
+            1,
+            "{} client",
+            client
+        );
+        assert!(
+            production
+                .fetch_instructions_by_signature(&tx_signature, page)
+                .await?
+                .is_empty(),
+            "{} client",
+            client
+        );
+
+        assert_eq!(
+            count("staging_instructions").await?,
+            stored as u64 + 1,
+            "{} client",
+            client
+        );
+        assert_eq!(count("instructions").await?, 0, "{} client", client);
+    }
+
+    Ok(())
+}
+
+/// The highest stored watermark is read back whatever order the watermarks are stored in
+#[tokio::test]
+async fn indexing_watermark_is_read_back() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+
+    for (client, url) in [
+        ("tcp", clickhouse.tcp_url()),
+        ("http", clickhouse.http_url()),
+    ] {
+        let mut storage: Box<dyn MainStorage> = clickhouse_storage::connect(&url).await?;
+        Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+
+        assert_eq!(
+            storage.get_indexing_watermark().await?,
+            None,
+            "{} client",
+            client
+        );
+
+        for slot in [117946133, 117946140, 117946135] {
+            storage.store_indexing_watermark(slot).await?;
+        }
+        assert_eq!(
+            storage.get_indexing_watermark().await?,
+            Some(117946140),
+            "{} client",
+            client
+        );
+    }
+
+    Ok(())
+}
+
+/// The retention of the config is set as the TTL of the tables at the start, a second start
+/// finds it up to date
+#[tokio::test]
+async fn retention_is_set_as_table_ttl() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+
+    let config = Configuration::from_toml(&format!(
+        r#"
+        [queue_storage]
+        storage_type = "Memory"
+
+        [main_storage]
+        database_url = "{}"
+
+        [main_storage.retention]
+        instruction_arguments = 90
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:0"
+        "#,
+        clickhouse.tcp_url()
+    ))?;
+    let retention = config
+        .get_main_storage_config()
+        .retention
+        .clone()
+        .ok_or_else(|| anyhow!("No retention"))?;
+
+    for _ in 0..2 {
+        let mut storage = connect_main_storage(config.get_main_storage_config()).await?;
+        Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+        apply_retention(storage.as_mut(), &retained_tables(&retention), "").await?;
+    }
+
+    let mut ttls = Vec::new();
+    for table in ["instructions", "instruction_arguments"] {
+        let block = pool
+            .get_handle()
+            .await?
+            .query(format!(
+                "SELECT engine_full FROM system.tables \
+                WHERE database = currentDatabase() AND name = '{}'",
+                table
+            ))
+            .fetch_all()
+            .await?;
+        let engine_full: String = block
+            .rows()
+            .next()
+            .ok_or_else(|| anyhow!("No table {}", table))?
+            .get("engine_full")?;
+
+        ttls.push(clickhouse_storage::retention::live_ttl(&engine_full).map(str::to_string));
+    }
+
+    assert_eq!(
+        ttls,
+        vec![None, Some("inserted_at + toIntervalDay(90)".to_string())]
+    );
+
+    Ok(())
+}
+
+/// The API answers with the same JSON from both clients, the rows are seeded the way the
+/// collector stores them
+#[cfg(feature = "api-server")]
+#[tokio::test]
+async fn api_serves_the_stored_rows() -> Result<()> {
+    use crate::api_server::ApiServer;
+    use crate::configuration::ApiServerConfig;
+    use crate::storages::main_storage::{Balance, Delegation};
+    use hyper::{header::AUTHORIZATION, Body, Request, StatusCode};
+    use serde_json::{json, Value};
+    use std::str::FromStr;
+
+    const STAKE_ACC: &str = "Stake11111111111111111111111111111111111111";
+    const VOTE_ACC: &str = "Vote111111111111111111111111111111111111111";
+
+    let clickhouse = Clickhouse::start().await?;
+    let tx_signature = Signature::from_str(TRANSACTION_SIGNATURE)?;
+
+    let mut storage = connect_main_storage(
+        Configuration::from_toml(&format!(
+            r#"
+            [queue_storage]
+            storage_type = "Memory"
+
+            [main_storage]
+            database_url = "{}"
+
+            [prometheus_exporter]
+            bind_address = "127.0.0.1:0"
+            "#,
+            clickhouse.tcp_url()
+        ))?
+        .get_main_storage_config(),
+    )
+    .await?;
+    Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+
+    let mut instruction = Instruction::new(&Pubkey::from_str(VOTE_ACC)?, &tx_signature);
+    instruction.slot = 117946133;
+    instruction.tx_status = TxStatus::Success;
+    instruction.accounts = vec![STAKE_ACC.to_string()];
+    storage.store_instructions_block(vec![instruction]).await?;
+    storage
+        .store_delegations_block(vec![Delegation {
+            slot: 117946133,
+            block_time: 1643213404,
+            stake_acc: STAKE_ACC.to_string(),
+            vote_acc: Some(VOTE_ACC.to_string()),
+            tx_signature: TRANSACTION_SIGNATURE.to_string(),
+            amount: 1000,
+            raw_instruction_idx: 0,
+        }])
+        .await?;
+    storage
+        .store_balances_block(vec![Balance {
+            tx_signature: TRANSACTION_SIGNATURE.to_string(),
+            account: STAKE_ACC.to_string(),
+            pre_balance: Some(2000),
+            post_balance: Some(1000),
+            pre_token_balance_mint: None,
+            pre_token_balance_owner: None,
+            pre_token_balance_amount: None,
+            pre_token_balance_program_id: None,
+            post_token_balance_mint: None,
+            post_token_balance_owner: None,
+            post_token_balance_amount: None,
+            post_token_balance_program_id: None,
+            inner_instructions_set: None,
+            attributed_amount: None,
+        }])
+        .await?;
+
+    for url in [clickhouse.tcp_url(), clickhouse.http_url()] {
+        let api = ApiServer::new(
+            vec![clickhouse_storage::connect(&url).await?],
+            ApiServerConfig {
+                bind_address: "127.0.0.1:0".to_string(),
+                token: "secret".to_string(),
+                default_limit: 100,
+                max_limit: 1000,
+                connections: 1,
+            },
+        );
+        let (api, url) = (&api, &url);
+        let get = |uri: String| async move {
+            let request = Request::get(uri)
+                .header(AUTHORIZATION, "Bearer secret")
+                .body(Body::empty())?;
+            let response = api.handle(request).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", url);
+
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            Ok::<Value, anyhow::Error>(serde_json::from_slice(&body)?)
+        };
+
+        let instructions = get(format!("/instructions?tx={}", TRANSACTION_SIGNATURE)).await?;
+        assert_eq!(instructions.as_array().map(Vec::len), Some(1), "{}", url);
+        assert_eq!(instructions[0]["program"], VOTE_ACC, "{}", url);
+        assert_eq!(instructions[0]["slot"], 117946133, "{}", url);
+        assert_eq!(instructions[0]["tx_status"], 1, "{}", url);
+        assert_eq!(instructions[0]["account_0"], STAKE_ACC, "{}", url);
+        assert_eq!(instructions[0]["account_1"], Value::Null, "{}", url);
+
+        assert_eq!(
+            get(format!(
+                "/delegations?stake_acc={}&from_slot=117946133&to_slot=117946133",
+                STAKE_ACC
+            ))
+            .await?,
+            json!([{
+                "slot": 117946133,
+                "block_time": 1643213404,
+                "stake_acc": STAKE_ACC,
+                "vote_acc": VOTE_ACC,
+                "tx_signature": TRANSACTION_SIGNATURE,
+                "amount": 1000,
+                "raw_instruction_idx": 0
+            }]),
+            "{}",
+            url
+        );
+
+        let balances = get(format!("/balances?account={}", STAKE_ACC)).await?;
+        assert_eq!(balances.as_array().map(Vec::len), Some(1), "{}", url);
+        assert_eq!(balances[0]["pre_balance"], 2000, "{}", url);
+        assert_eq!(balances[0]["post_balance"], 1000, "{}", url);
+
+        // Past the last page
+        assert_eq!(
+            get(format!("/balances?account={}&offset=1", STAKE_ACC)).await?,
+            json!([]),
+            "{}",
+            url
+        );
+    }
+
+    Ok(())
+}
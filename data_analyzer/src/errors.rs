@@ -1,83 +1,7 @@
+use clickhouse_storage::StorageError;
+use indexer_errors::{classify_chain, impl_into_indexer_error, Classify, ErrorClass};
+pub use solana_instruction_parser::{ConvertingError, ParseInstructionError};
 use thiserror::Error;
-#[derive(Debug, Error)]
-pub enum ParseInstructionError {
-    #[error("Failed to convert to serde_json: {0}")]
-    SerdeError(#[from] serde_json::Error),
-
-    #[error("Failed to get sighash of instruction: {0}")]
-    SighashFromSliceError(#[from] std::array::TryFromSliceError),
-
-    #[error("Failed to deserialize instruction: {0}")]
-    DeserializeError(#[from] std::io::Error),
-
-    #[error("Failed to deserialize in {instruction}: {err}")]
-    DeserializeInInstructionError {
-        instruction: String,
-        err: std::io::Error,
-    },
-
-    #[error("Failed to limited_deserialize in {instruction}: {err}")]
-    LimDeserializeInInstructionError {
-        instruction: String,
-        err: solana_program::instruction::InstructionError,
-    },
-
-    #[error("Failed to deserialize instruction from base58")]
-    DeserializeFromBase58Error,
-
-    #[error("Failed to parse instruction: {0}")]
-    ParseError(String),
-
-    #[error("Invalid index in {site}: {index}, when length is {max_len}")]
-    InvalidIndex {
-        site: String,
-        index: usize,
-        max_len: usize,
-    },
-
-    #[error("{site} has invalid length: {len} instead of {expected_len}")]
-    InvalidLength {
-        site: String,
-        len: usize,
-        expected_len: usize,
-    },
-
-    #[error("Converting Error: {0}")]
-    ConvertingError(#[from] ConvertingError),
-
-    #[error("Cannot get instruction name")]
-    InvalidInstructionName,
-
-    #[error("Given hash doesn't match any sighash in {0}")]
-    SighashMatchError(String),
-
-    #[error("Address doesn't match any program")]
-    ProgramAddressMatchError,
-
-    #[error("{0} is unsupported")]
-    Unsupported(String),
-}
-
-impl From<rust_base58::base58::FromBase58Error> for ParseInstructionError {
-    fn from(_: rust_base58::base58::FromBase58Error) -> Self {
-        Self::DeserializeFromBase58Error
-    }
-}
-
-#[derive(Debug, Error)]
-pub enum ConvertingError {
-    #[error("Cannot get {0} field")]
-    EmptyField(String),
-
-    #[error("Types has different lengths")]
-    DifferentLengths,
-
-    #[error("{0} is unsupported")]
-    Unsupported(String),
-
-    #[error("Failed to deserialize: {0}")]
-    DeserializeError(#[from] serde_json::error::Error),
-}
 
 #[derive(Debug, Error, PartialEq)]
 #[error("Failed to connect to PostgreSQL {source}")]
@@ -86,13 +10,6 @@ pub struct PostgreSQLError {
     source: diesel::result::ConnectionError,
 }
 
-#[derive(Debug, Error)]
-#[error("Failed to connect to Main Storage")]
-pub struct MainStorageError {
-    #[from]
-    source: clickhouse_rs::errors::Error,
-}
-
 #[derive(Debug, Error)]
 pub enum QueueManagerError {
     #[error("Failed to get data from queue manager")]
@@ -102,8 +19,112 @@ pub enum QueueManagerError {
     CustomError(#[from] anyhow::Error),
 }
 
+/// Failure to reach an actor through its handle
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ActorError {
+    #[error("{0} is not running")]
+    ActorUnavailable(&'static str),
+
+    #[error("Mailbox of {0} is full")]
+    MailboxFull(&'static str),
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum RabbitMQError {
     #[error("Failed to connect to RabbitMQ: {0}")]
     ConnectionError(#[from] lapin::Error),
 }
+
+impl Classify for PostgreSQLError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::Transient
+    }
+}
+
+impl Classify for QueueManagerError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            QueueManagerError::RecvError(_) => ErrorClass::Transient,
+            QueueManagerError::CustomError(err) => classify(err),
+        }
+    }
+}
+
+impl Classify for ActorError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::Transient
+    }
+}
+
+impl Classify for RabbitMQError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::Transient
+    }
+}
+
+impl_into_indexer_error!(
+    PostgreSQLError,
+    QueueManagerError,
+    ActorError,
+    RabbitMQError
+);
+
+/// Class of an error returned by the storages and actors, which is the class of the first
+/// error of its chain known to the analyzer
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    classify_chain(err.as_ref(), |err| {
+        if let Some(err) = err.downcast_ref::<StorageError>() {
+            Some(err.classify())
+        } else if let Some(err) = err.downcast_ref::<ParseInstructionError>() {
+            Some(err.classify())
+        } else if let Some(err) = err.downcast_ref::<ConvertingError>() {
+            Some(err.classify())
+        } else if let Some(err) = err.downcast_ref::<QueueManagerError>() {
+            Some(err.classify())
+        } else if let Some(err) = err.downcast_ref::<ActorError>() {
+            Some(err.classify())
+        } else if let Some(err) = err.downcast_ref::<PostgreSQLError>() {
+            Some(err.classify())
+        } else {
+            err.downcast_ref::<RabbitMQError>()
+                .map(|err| err.classify())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indexer_errors::IndexerError;
+
+    use super::*;
+
+    #[test]
+    fn indexer_error_keeps_the_message() {
+        let err = IndexerError::from(ActorError::ActorUnavailable("QueueManager"));
+        assert_eq!(err.to_string(), "QueueManager is not running");
+        assert_eq!(err.classify(), ErrorClass::Transient);
+
+        let err = IndexerError::from(QueueManagerError::CustomError(anyhow::Error::from(
+            ParseInstructionError::InvalidInstructionName,
+        )));
+        assert_eq!(err.to_string(), "Custom error: Cannot get instruction name");
+        assert_eq!(err.classify(), ErrorClass::DataCorruption);
+
+        let err = IndexerError::from(ConvertingError::EmptyField("slot".to_string()));
+        assert_eq!(err.to_string(), "Cannot get slot field");
+        assert_eq!(err.classify(), ErrorClass::DataCorruption);
+    }
+
+    #[test]
+    fn errors_are_classified_by_their_chain() {
+        let err = anyhow::Error::from(StorageError::UnknownProtocol("ftp".to_string()))
+            .context("Failed to connect to the main storage");
+        assert_eq!(classify(&err), ErrorClass::Configuration);
+
+        let err = anyhow::Error::from(ActorError::MailboxFull("TransactionParser"));
+        assert_eq!(classify(&err), ErrorClass::Transient);
+
+        let err = anyhow::anyhow!("Block 10 has no time");
+        assert_eq!(classify(&err), ErrorClass::Transient);
+    }
+}
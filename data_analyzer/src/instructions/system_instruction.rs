@@ -1,175 +0,0 @@
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
-#[allow(deprecated)]
-use serde_derive::{Deserialize, Serialize};
-use solana_program::pubkey::Pubkey;
-
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-#[instr_args_parse(InstrRoot)]
-pub enum SystemInstruction {
-    /// Create a new account
-    ///
-    /// # Account references
-    ///   0. `[WRITE, SIGNER]` Funding account
-    ///   1. `[WRITE, SIGNER]` New account
-    CreateAccount {
-        /// Number of lamports to transfer to the new account
-        lamports: u64,
-
-        /// Number of bytes of memory to allocate
-        space: u64,
-
-        /// Address of program that will own the new account
-        owner: Pubkey,
-    },
-
-    /// Assign account to a program
-    ///
-    /// # Account references
-    ///   0. `[WRITE, SIGNER]` Assigned account public key
-    Assign {
-        /// Owner program account
-        owner: Pubkey,
-    },
-
-    /// Transfer lamports
-    ///
-    /// # Account references
-    ///   0. `[WRITE, SIGNER]` Funding account
-    ///   1. `[WRITE]` Recipient account
-    Transfer {
-        lamports: u64,
-    },
-
-    /// Create a new account at an address derived from a base pubkey and a seed
-    ///
-    /// # Account references
-    ///   0. `[WRITE, SIGNER]` Funding account
-    ///   1. `[WRITE]` Created account
-    ///   2. `[SIGNER]` (optional) Base account; the account matching the base Pubkey below must be
-    ///                          provided as a signer, but may be the same as the funding account
-    ///                          and provided as account 0
-    CreateAccountWithSeed {
-        /// Base public key
-        base: Pubkey,
-
-        /// String of ASCII chars, no longer than `Pubkey::MAX_SEED_LEN`
-        seed: String,
-
-        /// Number of lamports to transfer to the new account
-        lamports: u64,
-
-        /// Number of bytes of memory to allocate
-        space: u64,
-
-        /// Owner program account address
-        owner: Pubkey,
-    },
-
-    /// Consumes a stored nonce, replacing it with a successor
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Nonce account
-    ///   1. `[]` RecentBlockhashes sysvar
-    ///   2. `[SIGNER]` Nonce authority
-    AdvanceNonceAccount,
-
-    /// Withdraw funds from a nonce account
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Nonce account
-    ///   1. `[WRITE]` Recipient account
-    ///   2. `[]` RecentBlockhashes sysvar
-    ///   3. `[]` Rent sysvar
-    ///   4. `[SIGNER]` Nonce authority
-    ///
-    /// The `u64` parameter is the lamports to withdraw, which must leave the
-    /// account balance above the rent exempt reserve or at zero.
-    WithdrawNonceAccount(u64),
-
-    /// Drive state of Uninitialized nonce account to Initialized, setting the nonce value
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Nonce account
-    ///   1. `[]` RecentBlockhashes sysvar
-    ///   2. `[]` Rent sysvar
-    ///
-    /// The `Pubkey` parameter specifies the entity authorized to execute nonce
-    /// instruction on the account
-    ///
-    /// No signatures are required to execute this instruction, enabling derived
-    /// nonce account addresses
-    InitializeNonceAccount(Pubkey),
-
-    /// Change the entity authorized to execute nonce instructions on the account
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Nonce account
-    ///   1. `[SIGNER]` Nonce authority
-    ///
-    /// The `Pubkey` parameter identifies the entity to authorize
-    AuthorizeNonceAccount(Pubkey),
-
-    /// Allocate space in a (possibly new) account without funding
-    ///
-    /// # Account references
-    ///   0. `[WRITE, SIGNER]` New account
-    Allocate {
-        /// Number of bytes of memory to allocate
-        space: u64,
-    },
-
-    /// Allocate space for and assign an account at an address
-    ///    derived from a base public key and a seed
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Allocated account
-    ///   1. `[SIGNER]` Base account
-    AllocateWithSeed {
-        /// Base public key
-        base: Pubkey,
-
-        /// String of ASCII chars, no longer than `pubkey::MAX_SEED_LEN`
-        seed: String,
-
-        /// Number of bytes of memory to allocate
-        space: u64,
-
-        /// Owner program account
-        owner: Pubkey,
-    },
-
-    /// Assign account to a program based on a seed
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Assigned account
-    ///   1. `[SIGNER]` Base account
-    AssignWithSeed {
-        /// Base public key
-        base: Pubkey,
-
-        /// String of ASCII chars, no longer than `pubkey::MAX_SEED_LEN`
-        seed: String,
-
-        /// Owner program account
-        owner: Pubkey,
-    },
-
-    /// Transfer lamports from a derived address
-    ///
-    /// # Account references
-    ///   0. `[WRITE]` Funding account
-    ///   1. `[SIGNER]` Base for funding account
-    ///   2. `[WRITE]` Recipient account
-    TransferWithSeed {
-        /// Amount to transfer
-        lamports: u64,
-
-        /// Seed to use to derive the funding account address
-        from_seed: String,
-
-        /// Owner to use to derive the funding account address
-        from_owner: Pubkey,
-    },
-
-    UpgradeNonceAccount,
-}
@@ -1,391 +1,309 @@
 use convert_case::{Case, Casing};
-use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{spanned::Spanned, *};
+use syn::*;
 
 /// Defines style formating
-const INSTRUCTION_ARGUMENTS_CASE: Case = Case::Snake;
 const PROMETHEUS_CASE: Case = Case::Snake;
 
-/// This func parseed struct fields and returns lines which push `PathTree` struct for some field into `fields_vec` vector.
-///
-/// `fields_vec` vector must be defined before calling `TokenStream` which returns this func.
-fn parse_struct_fields(fields: &syn::Fields, type_name: &syn::Ident) -> TokenStream {
-    let mut from_code = Vec::new();
-
-    match fields {
-        syn::Fields::Named(syn::FieldsNamed {
-            named: named_fields,
-            ..
-        }) => {
-            named_fields
-                .into_iter()
-                .for_each(|named_field| {
-                    let field_ident = named_field.ident.as_ref().unwrap();
-
-                    from_code.push(quote!{
-                        fields_vec.push((stringify!(#field_ident).to_string(), Box::new(other_val.#field_ident.into())));
-                    });
-                });
-        }
-        syn::Fields::Unnamed(syn::FieldsUnnamed {
-            unnamed: unnamed_fields,
-            ..
-        }) => {
-            unnamed_fields.into_iter().enumerate().for_each(|(i, unnamed_field)| {
-                let index = syn::Index{index: i as u32, span: unnamed_field.span()};
-
-                from_code.push(quote!{
-                    fields_vec.push((stringify!(#index).to_string(), Box::new(other_val.#index.into())));
-                });
-            });
-        }
-        syn::Fields::Unit => {
-            let type_name_formatted = type_name.to_string().to_case(INSTRUCTION_ARGUMENTS_CASE);
-            from_code.push(quote! {
-                fields_vec.push((#type_name_formatted.to_string(), Box::new(PathTree::None)));
-            });
-        }
-    }
-
-    quote! {#(#from_code)*}
-}
-
-/// Parse Enum Variants and returns `match self` expression
-/// that returns string containing variant name and pushes `PathTree` struct for all fields, contained in
-/// this variant, into `fields_vec` vector.
-///
-/// `fields_vec` vector must be defined before calling `TokenStream` which returns this func.
-fn parse_enum_variants(
-    variants: &syn::punctuated::Punctuated<syn::Variant, syn::token::Comma>,
-    enum_ident: &syn::Ident,
-) -> TokenStream {
-    let mut from_code = Vec::new();
-
-    // Generating match arm
-    variants.into_iter().for_each(|variant| {
-        let mut math_arm_inner_code = Vec::new();
-        let variant_ident = &variant.ident;
-        let variant_name_formatted = variant_ident.to_string().to_case(INSTRUCTION_ARGUMENTS_CASE);
-        let variant_fields = &variant.fields;
-
-        // This var below represents list of idents, passed into match arm.
-        // # Example
-        //
-        // SomeEnum::Unit => {...}
-        // In this case args var would be quote!{ }
-
-        // SomeEnum::TupleVariant2(arg0, arg1, arg2) => {...}
-        // In this case args var would be quote!{ (arg0, arg1, arg2) }
-        //
-        // SomeEnum::StructLikeVariant2{field_in_struct_like_variant1, field2, some_other_field} => {...}
-        // In this case args would be quote!{ {field_in_struct_like_variant1, field2, some_other_field} }
-        let args = match variant_fields {
-            syn::Fields::Named(syn::FieldsNamed { named: named_fields, .. }) => {
-                let args_vec: Vec<&syn::Ident> = named_fields
-                .into_iter()
-                .map(|named_field| {
-                    let named_field_ident = named_field.ident.as_ref().unwrap();
-
-                    math_arm_inner_code.push(quote!{
-                        fields_vec.push((stringify!(#named_field_ident).to_string(), Box::new(#named_field_ident.into())));
-                    });
-
-                   named_field_ident
-                }).collect();
-                if args_vec.is_empty() { quote!{} } else { quote!{{#(#args_vec, )*}} }
-            }
-            syn::Fields::Unnamed(syn::FieldsUnnamed {
-                unnamed: unnamed_fields, ..
-            }) => {
-                let args_vec: Vec<syn::Ident> = unnamed_fields.into_iter().enumerate().map(|(i, unnamed_field)| {
-
-                    let index = syn::Index{index: i as u32, span: unnamed_field.span()};
-                    let arg = syn::Ident::new(format!("arg{}", i).as_str(), unnamed_field.span());
-
-                    math_arm_inner_code.push(quote!{
-                        fields_vec.push((stringify!(#index).to_string(), Box::new(#arg.into())));
-                    });
+#[proc_macro_derive(HandleInstance)]
+pub fn handle_instance(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as syn::DeriveInput);
+    let type_name = item.ident;
+    let type_name_formatted = type_name.to_string().to_case(PROMETHEUS_CASE);
 
-                    arg
-                }).collect();
+    quote! {
 
-                if args_vec.is_empty() { quote!{} } else { quote!{(#(#args_vec, )*)} }
-            }
-            syn::Fields::Unit => {
-                quote!{}
+        impl Drop for #type_name {
+            fn drop(&mut self) {
+                log::debug!("{} has been dropped", stringify!(#type_name));
+                crate::metrics_update!(dec total ACTIVE_HANDLE_INSTANCES_COUNT, &[#type_name_formatted]);
             }
-        };
-
-
-        from_code.push(quote! {
-                #enum_ident::#variant_ident #args => {
-                    #(#math_arm_inner_code)*
-                    #variant_name_formatted
-                },
-        });
-    });
-
-    quote! {
-        match other_val {
-            #(#from_code)*
         }
-    }
-}
 
-/// Generates impl From<T> for PathTree for additional types such as array and tuple.
-/// You can pass `Array(..)` or 'Tuple(..)' attribute in macros with inner parameters, which represents length of array of tuple.
-/// E.G: #[implement_path_tree(Array(1, 2), Tuple(2))] - it will generate 'impl From<T> for PathTree' code for array of length 1 and 2 and tuple of length 2,
-/// regardless of types.
-///
-/// Note: macro `implement_path_tree` is just a wrapper of this func.
-fn get_additional_impls(attr: &syn::AttributeArgs) -> TokenStream {
-    let mut additional_impls = Vec::new();
-    attr.iter().for_each(|meta| {
-        match meta {
-            syn::NestedMeta::Meta(meta) => {
-                if let syn::Meta::List(syn::MetaList{path, nested, ..}) = meta {
-                    if path.is_ident("Tuple") {
-                        nested.iter().for_each(|nested_meta| {
-                            match nested_meta {
-                                syn::NestedMeta::Lit(syn::Lit::Int(lit)) => {
-                                    let mut inner_code = Vec::new();
-                                    let mut templates = Vec::new();
-                                    let length = lit.base10_parse::<usize>().unwrap();
-
-                                    for i in 0..length {
-                                        let index = syn::Index::from(i);
-                                        inner_code.push(quote! {
-                                            tuple_fields.push((stringify!(#index).to_string(), Box::new(other_val.#index.into())));
-                                        });
-                                        let ident = syn::Ident::new(format!("T{}", i).as_str(), path.span());
-                                        templates.push(ident);
-                                    }
-
-                                    inner_code.push(quote!{
-                                        fields_vec.push(("".to_string(), Box::new(PathTree::Path(tuple_fields))));
-                                    });
-
-                                    additional_impls.push(quote! {
-                                        impl<#(#templates, )*> From<(#(#templates, )*)> for PathTree
-                                        where #(#templates: Into<PathTree> + Clone, )*
-                                        {
-                                            fn from(other_val: (#(#templates, )*)) -> Self {
-                                                let mut tuple_fields = Vec::new();
-                                                let mut fields_vec = Vec::new();
-                                                #(#inner_code)*
-
-                                                PathTree::Path(fields_vec)
-                                            }
-                                        }
-                                    });
-                                }
-                                _ => {
-                                    panic!("Tuple attribute can contain only length field");
-                                }
-                            }
-                        });
-                    } else if path.is_ident("Array") {
-                        nested.iter().for_each(|nested_meta| {
-                            let length  = if let syn::NestedMeta::Lit(syn::Lit::Int(lit)) = &nested_meta {
-                                lit.base10_parse::<usize>().unwrap()
-                            } else {
-                                panic!("Array attributes must be an usize value");
-                            };
-
-
-                            additional_impls.push(quote!{
-                                impl<T> From<[T; #length]> for PathTree
-                                where T: Into<PathTree> + Clone,
-                                {
-                                    fn from(slice: [T; #length]) -> Self {
-                                        let mut path_vec = Vec::new();
-                                        slice.into_iter().enumerate().for_each(|(i, val)| {
-                                            path_vec.push((i.to_string(), Box::new(val.clone().into())));
-                                        });
-
-                                        Self::Path(path_vec)
-                                    }
-                                }
-                            });
-                        });
-                    }
-                } else {
-                    unimplemented!("Only list is supported as attribute");
+        impl Clone for #type_name {
+            fn clone(&self) -> Self {
+                crate::metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &[#type_name_formatted]);
+                Self {
+                    sender: self.sender.clone(),
                 }
             }
-            _ => {
-                unimplemented!{"Unsupported attribute argument type"};
-            }
         }
-    });
 
-    quote!(#(#additional_impls)*)
+    }
+    .into()
 }
 
-/// Generates impl From<T> for PathTree for additional types such as array and tuple.
-/// You can pass `Array(..)` or 'Tuple(..)' attribute in macros with inner parameters, which represents length of array or tuple.
-/// E.G: #[implement_path_tree(Array(1, 2), Tuple(2))] - it will generate 'impl From<T> for PathTree' code for array of length 1 and 2 and tuple of length 2,
-/// regardless of types.
-///
-/// Note: This macro is just a wrapper of `get_additional_impls` func
-#[proc_macro_attribute]
-pub fn implement_path_tree(
-    attr: proc_macro::TokenStream,
-    item: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    let item = parse_macro_input!(item as syn::Item);
-    let attr = parse_macro_input!(attr as syn::AttributeArgs);
-
-    let additional_impls = get_additional_impls(&attr);
+#[proc_macro_derive(ActorInstance)]
+pub fn actor_instance(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as syn::DeriveInput);
+    let type_name = item.ident;
+    let type_name_formatted = type_name.to_string().to_case(PROMETHEUS_CASE);
 
     quote! {
-        #item
-        #additional_impls
+
+        impl Drop for #type_name {
+            fn drop(&mut self) {
+                log::debug!("{} has been dropped", stringify!(#type_name));
+                crate::metrics_update!(dec total ACTIVE_ACTOR_INSTANCES_COUNT, &[#type_name_formatted]);
+            }
+        }
+
     }
     .into()
 }
 
-/// Macros produces implementation for trait From<T> for PathTree struct and method
-/// `get_arguments(..)`, that returns Vec<InstructionArgument>
+/// Attribute of the table and of its columns, `#[clickhouse_table(name = "...", ...)]`
+const TABLE_ATTRIBUTE: &str = "clickhouse_table";
+
+/// ZooKeeper path and replica of the replicated tables of the `on_cluster` migrations
+const REPLICATION_ARGS: &str = "'/clickhouse/tables/01/{database}/{table}', '{replica}'";
+
+/// Generates `clickhouse_storage::ClickhouseTable` of a row: its columns and the `CREATE TABLE`
+/// scripts of its table. The table is described by the attribute of the struct:
 ///
-/// Attributes:
-/// * InstrRoot:  It indicates, that particular enum is "root" and won't generate it's
-/// variant field name (instruction name) in `arg_path` field.
-
-#[proc_macro_attribute]
-pub fn instr_args_parse(
-    attr: proc_macro::TokenStream,
-    item: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    let item = parse_macro_input!(item as syn::Item);
-    let attr = parse_macro_input!(attr as syn::AttributeArgs);
-
-    let trait_impl = match item {
-        syn::Item::Struct(strct) => {
-            let name = &strct.ident;
-            let inner_code = parse_struct_fields(&strct.fields, name);
-            quote! {
-                #strct
-
-                impl From<#name> for PathTree {
-                    fn from(other_val: #name) -> Self {
-                        let mut fields_vec: Vec<(String, Box<PathTree>)> = Vec::new();
-                        #inner_code
-
-                        PathTree::Path(fields_vec)
-                    }
-                }
+/// - `name`, `engine` (e.g. `MergeTree()`) and `order_by` are required;
+/// - `partition_by` and `settings` are added when set;
+/// - every `extra` is a line of the column list which isn't a field of the row, a column
+///   ClickHouse fills itself or an index.
+///
+/// The type of a column is derived from the type of its field, `Option<T>` being
+/// `Nullable(T)`. A field of another type sets it with `#[clickhouse_table(column_type = "...")]`
+/// and a column with a default sets it with `#[clickhouse_table(default = "...")]`
+#[proc_macro_derive(ClickhouseTable, attributes(clickhouse_table))]
+pub fn clickhouse_table(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as syn::DeriveInput);
+
+    clickhouse_table_impl(&item).unwrap_or_else(|err| err.to_compile_error().into())
+}
 
-                impl #name {
-                    pub fn get_arguments(self, tx_signature: &str, instruction_idx: u8, inner_instructions_set: Option<u8>, program: &str) -> Vec<InstructionArgument> {
-                        let path_tree: PathTree = self.into();
+#[derive(Default)]
+struct Table {
+    name: String,
+    engine: String,
+    partition_by: Option<String>,
+    order_by: String,
+    settings: Option<String>,
+    extra: Vec<String>,
+}
 
-                        let mut instruction_arguments = Vec::new();
+struct Column {
+    name: String,
+    column_type: String,
+    default: Option<String>,
+}
 
-                        let mut instruction_arguments_mock = InstructionArgument::new(tx_signature, instruction_idx, inner_instructions_set, program);
-                        path_tree.get_instruction_args_vec(&mut instruction_arguments, instruction_arguments_mock, &mut 0);
+fn clickhouse_table_impl(item: &DeriveInput) -> Result<proc_macro::TokenStream> {
+    let type_name = &item.ident;
+    let table = table(item)?;
 
-                        instruction_arguments
-                    }
-                }
-            }
+    let fields = match &item.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new_spanned(
+                item,
+                "ClickhouseTable is derived only for structs with named fields",
+            ))
+        }
+    };
+    let columns = fields.iter().map(column).collect::<Result<Vec<_>>>()?;
+
+    let table_name = &table.name;
+    let names = columns.iter().map(|column| &column.name);
+    let column_types = columns.iter().map(|column| &column.column_type);
+    let single = create_table(&table, &columns, false);
+    let on_cluster = create_table(&table, &columns, true);
+
+    Ok(quote! {
+
+        impl clickhouse_storage::ClickhouseTable for #type_name {
+            const TABLE: &'static str = #table_name;
+            const COLUMNS: &'static [&'static str] = &[#(#names),*];
+            const COLUMN_TYPES: &'static [&'static str] = &[#(#column_types),*];
+            const CREATE_TABLE: &'static str = #single;
+            const CREATE_TABLE_ON_CLUSTER: &'static str = #on_cluster;
         }
-        syn::Item::Enum(enm) => {
-            let name = &enm.ident;
-            let inner_code = parse_enum_variants(&enm.variants, name);
 
-            let mut return_val = quote! {
-                PathTree::Path(vec![(variant.to_string(), Box::new(PathTree::None)),
-                    (variant.to_string(), Box::new(PathTree::Path(fields_vec)))])
-            };
+    }
+    .into())
+}
 
-            if let Some(syn::NestedMeta::Meta(meta)) = attr.first() {
-                let path = meta.path();
+/// `name = "value"` pairs of the `#[clickhouse_table(...)]` attributes
+fn attribute_values(attrs: &[Attribute]) -> Result<Vec<(Ident, String)>> {
+    let mut values = Vec::new();
+
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(TABLE_ATTRIBUTE))
+    {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(Error::new_spanned(
+                attr,
+                "expected #[clickhouse_table(...)]",
+            ));
+        };
 
-                if path.is_ident("InstrRoot") {
-                    return_val = quote! {
-                        PathTree::Path(fields_vec)
-                    }
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(value),
+                    ..
+                })) if path.get_ident().is_some() => {
+                    values.push((path.get_ident().unwrap().clone(), value.value()));
                 }
+                nested => return Err(Error::new_spanned(nested, "expected `name = \"value\"`")),
             }
-            quote! {
-                #enm
+        }
+    }
 
-                impl From<#name> for PathTree {
-                    fn from(other_val: #name) -> Self {
-                        let mut fields_vec = Vec::new();
-                        let variant = #inner_code;
+    Ok(values)
+}
 
-                        #return_val
-                    }
-                }
+fn table(item: &DeriveInput) -> Result<Table> {
+    let mut table = Table::default();
+
+    for (name, value) in attribute_values(&item.attrs)? {
+        match name.to_string().as_str() {
+            "name" => table.name = value,
+            "engine" => table.engine = value,
+            "partition_by" => table.partition_by = Some(value),
+            "order_by" => table.order_by = value,
+            "settings" => table.settings = Some(value),
+            "extra" => table.extra.push(value),
+            _ => return Err(Error::new_spanned(name, "unknown table attribute")),
+        }
+    }
 
-                impl #name {
-                    pub fn get_arguments(self, tx_signature: &str, instruction_idx: u8, inner_instructions_set: Option<u8>, program: &str) -> Vec<InstructionArgument> {
-                        let path_tree: PathTree = self.into();
+    for (required, value) in [
+        ("name", &table.name),
+        ("engine", &table.engine),
+        ("order_by", &table.order_by),
+    ] {
+        if value.is_empty() {
+            return Err(Error::new_spanned(
+                &item.ident,
+                format!("#[clickhouse_table({} = \"...\")] is required", required),
+            ));
+        }
+    }
 
-                        let mut instruction_arguments = Vec::new();
+    Ok(table)
+}
 
-                        let mut instruction_arguments_mock = InstructionArgument::new(tx_signature, instruction_idx, inner_instructions_set, program);
-                        path_tree.get_instruction_args_vec(&mut instruction_arguments, instruction_arguments_mock, &mut 0);
+fn column(field: &Field) -> Result<Column> {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut column_type = None;
+    let mut default = None;
 
-                        instruction_arguments
-                    }
-                }
-            }
+    for (name, value) in attribute_values(&field.attrs)? {
+        match name.to_string().as_str() {
+            "column_type" => column_type = Some(value),
+            "default" => default = Some(value),
+            _ => return Err(Error::new_spanned(name, "unknown column attribute")),
         }
-        _ => {
-            unimplemented!("Only structs and enums are supported");
+    }
+
+    let column_type = match column_type.or_else(|| clickhouse_type(&field.ty)) {
+        Some(column_type) => column_type,
+        None => {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "no ClickHouse type of the field, set its `column_type`",
+            ))
         }
     };
 
-    trait_impl.into()
+    Ok(Column {
+        name: ident.to_string(),
+        column_type,
+        default,
+    })
 }
 
-#[proc_macro_derive(HandleInstance)]
-pub fn handle_instance(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let item = parse_macro_input!(item as syn::DeriveInput);
-    let type_name = item.ident;
-    let type_name_formatted = type_name.to_string().to_case(PROMETHEUS_CASE);
-
-    quote! {
-
-        impl Drop for #type_name {
-            fn drop(&mut self) {
-                log::debug!("{} has been dropped", stringify!(#type_name));
-                crate::metrics_update!(dec total ACTIVE_HANDLE_INSTANCES_COUNT, &[#type_name_formatted]);
-            }
-        }
+/// ClickHouse type a field of the `ty` is serialized as
+fn clickhouse_type(ty: &Type) -> Option<String> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
 
-        impl Clone for #type_name {
-            fn clone(&self) -> Self {
-                crate::metrics_update!(inc total ACTIVE_HANDLE_INSTANCES_COUNT, &[#type_name_formatted]);
-                Self {
-                    sender: self.sender.clone(),
-                }
-            }
+    let column_type = match segment.ident.to_string().as_str() {
+        "Option" => {
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            let Some(GenericArgument::Type(inner)) = args.args.first() else {
+                return None;
+            };
+            return clickhouse_type(inner).map(|inner| format!("Nullable({})", inner));
         }
+        "String" => "String",
+        "bool" => "Bool",
+        "u8" => "UInt8",
+        "u16" => "UInt16",
+        "u32" => "UInt32",
+        "u64" => "UInt64",
+        "i8" => "Int8",
+        "i16" => "Int16",
+        "i32" => "Int32",
+        "i64" => "Int64",
+        "f32" => "Float32",
+        "f64" => "Float64",
+        _ => return None,
+    };
 
-    }
-    .into()
+    Some(column_type.to_string())
 }
 
-#[proc_macro_derive(ActorInstance)]
-pub fn actor_instance(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let item = parse_macro_input!(item as syn::DeriveInput);
-    let type_name = item.ident;
-    let type_name_formatted = type_name.to_string().to_case(PROMETHEUS_CASE);
+/// `Replicated` variant of the `engine`, e.g. `ReplicatedReplacingMergeTree(<path>, <replica>,
+/// slot)` of `ReplacingMergeTree(slot)`
+fn replicated_engine(engine: &str) -> String {
+    let (name, args) = engine.split_once('(').unwrap_or((engine, ")"));
+    let args = args.trim_end().trim_end_matches(')').trim();
 
-    quote! {
+    if args.is_empty() {
+        format!("Replicated{}({})", name, REPLICATION_ARGS)
+    } else {
+        format!("Replicated{}({}, {})", name, REPLICATION_ARGS, args)
+    }
+}
 
-        impl Drop for #type_name {
-            fn drop(&mut self) {
-                log::debug!("{} has been dropped", stringify!(#type_name));
-                crate::metrics_update!(dec total ACTIVE_ACTOR_INSTANCES_COUNT, &[#type_name_formatted]);
-            }
-        }
+fn create_table(table: &Table, columns: &[Column], on_cluster: bool) -> String {
+    let mut lines: Vec<String> = columns
+        .iter()
+        .map(|column| match &column.default {
+            Some(default) => format!(
+                "    {} {} DEFAULT {}",
+                column.name, column.column_type, default
+            ),
+            None => format!("    {} {}", column.name, column.column_type),
+        })
+        .collect();
+    lines.extend(table.extra.iter().map(|extra| format!("    {}", extra)));
+
+    let (on_cluster, engine) = if on_cluster {
+        (" ON CLUSTER '{cluster}'", replicated_engine(&table.engine))
+    } else {
+        ("", table.engine.clone())
+    };
 
+    let mut ddl = format!(
+        "CREATE TABLE IF NOT EXISTS {{table_prefix}}{}{}\n(\n{}\n) ENGINE = {}\n",
+        table.name,
+        on_cluster,
+        lines.join(",\n"),
+        engine
+    );
+    if let Some(partition_by) = &table.partition_by {
+        ddl += &format!("PARTITION BY {}\n", partition_by);
     }
-    .into()
+    ddl += &format!("ORDER BY {}", table.order_by);
+    if let Some(settings) = &table.settings {
+        ddl += &format!("\nSETTINGS {}", settings);
+    }
+
+    ddl
 }
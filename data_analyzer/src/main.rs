@@ -4,53 +4,112 @@ extern crate clickhouse as clickhouse_http;
 extern crate dotenv;
 
 mod actors;
+#[cfg(feature = "api-server")]
+mod api_server;
+mod backpressure;
+mod block_time_resolver;
 mod configuration;
+#[cfg(all(test, feature = "integration-tests"))]
+mod end_to_end_tests;
 mod errors;
-mod instructions;
+mod health;
+mod metadata_parsing_ctx;
+mod parse_tx;
 mod register;
+mod signature_tracing;
 mod storages;
 mod transactions_parsing_ctx;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use configuration::*;
-use env_logger::Env;
 use register::*;
 
 use anyhow::Result;
-use log::info;
+use clickhouse_storage::{apply_retention, check_schema, Migrations};
+use log::{info, warn};
+use metadata_parsing_ctx::*;
+use parse_tx::ParseTxArgs;
 use tokio::signal;
 use tokio::signal::unix::{signal, SignalKind};
 use transactions_parsing_ctx::*;
 
 use crate::storages::main_storage::connect_main_storage;
-use crate::storages::main_storage::migrations::{Migrations, SCRIPTS_UP};
+use crate::storages::main_storage::migrations::{SCRIPTS_DOWN, SCRIPTS_UP};
+use crate::storages::main_storage::schema::{retained_tables, WRITTEN_TABLES};
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
     /// Config file
-    #[clap(short, long)]
-    config: String,
+    #[clap(short, long, required = true)]
+    config: Option<String>,
+
+    /// Print the migrations which would be run and exit without executing them
+    #[clap(long)]
+    validate_migrations: bool,
+
+    /// Revert the migrations applied after the given version and exit
+    #[clap(long, value_name = "VERSION")]
+    rollback_to: Option<String>,
+
+    /// Start without comparing the tables with the rows written to them
+    #[clap(long)]
+    skip_schema_check: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the rows which would be extracted from a single transaction as JSON, no storage is
+    /// connected
+    ParseTx(ParseTxArgs),
+    /// Serve the stored rows over HTTP with the `[api_server]` section of the config, nothing
+    /// is parsed
+    #[cfg(feature = "api-server")]
+    ServeApi,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
 
-    info!("Starting data_analyzer");
+    if let Some(Command::ParseTx(parse_tx_args)) = &args.command {
+        return parse_tx::run(parse_tx_args, &mut std::io::stdout().lock()).await;
+    }
+
+    let config = args.config.as_deref().expect("--config is required");
+    let config = match Configuration::new(config) {
+        Ok(config) => config,
+        Err(err) => indexer_errors::exit_on_configuration_error(err),
+    };
+    let register = Register::new(config);
 
-    let register = Register::new(Configuration::new(&Args::parse().config)?);
+    indexer_telemetry::init_logger(
+        "data_analyzer",
+        register.config.get_logging_config().format,
+        "info",
+    );
 
-    // Run migrations. The storage will be dropped right after that and connection will be closed.
-    {
-        let mut storage =
-            connect_main_storage(&register.config.get_main_storage_config().database_url).await?;
+    #[cfg(feature = "api-server")]
+    if let Some(Command::ServeApi) = &args.command {
+        info!("Starting the data_analyzer API server");
+        api_server::ApiServer::setup_and_run(&register).await?;
+        wait_termination().await;
 
-        let migrations = Migrations::new();
-        migrations.up(&mut storage, &SCRIPTS_UP).await?;
+        info!("Shutting down the data_analyzer API server");
+        return Ok(());
+    }
+
+    info!("Starting data_analyzer");
+
+    if !setup_main_storage(&args, &register).await? {
+        return Ok(());
     }
 
     TransactionsParsingCtx::setup_and_run(&register).await?;
+    MetadataParsingCtx::setup_and_run(&register).await?;
 
     wait_termination().await;
 
@@ -58,6 +117,66 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Runs, validates or rolls back the migrations and checks the schema, the storage is dropped
+/// right after that and the connection is closed. Returns false when the analyzer exits right
+/// after the migrations
+async fn setup_main_storage(args: &Args, register: &Register) -> Result<bool> {
+    let config = register.config.get_main_storage_config();
+    let mut storage = connect_main_storage(config).await?;
+
+    let migrations = Migrations::new(&SCRIPTS_UP)
+        .with_down(&SCRIPTS_DOWN)
+        .with_table_prefix(&config.table_prefix);
+    let validate_only =
+        args.validate_migrations || register.config.get_migrations_config().validate_only;
+
+    match (&args.rollback_to, validate_only) {
+        (Some(version), true) => {
+            let plan = migrations.rollback_plan(storage.as_mut(), version).await?;
+            print_migrations_plan("reverted", &plan);
+            return Ok(false);
+        }
+        (Some(version), false) => {
+            migrations.rollback_to(storage.as_mut(), version).await?;
+            info!("Migrations are rolled back to {}", version);
+            return Ok(false);
+        }
+        (None, true) => {
+            let plan = migrations.pending(storage.as_mut()).await?;
+            print_migrations_plan("run", &plan);
+            return Ok(false);
+        }
+        (None, false) => migrations.up(storage.as_mut()).await?,
+    }
+
+    if let Some(retention) = &config.retention {
+        apply_retention(
+            storage.as_mut(),
+            &retained_tables(retention),
+            &config.table_prefix,
+        )
+        .await?;
+    }
+
+    if args.skip_schema_check {
+        warn!("Schema check is skipped");
+    } else {
+        check_schema(storage.as_mut(), WRITTEN_TABLES, &config.table_prefix).await?;
+    }
+
+    Ok(true)
+}
+
+fn print_migrations_plan(action: &str, plan: &[(&str, &str)]) {
+    if plan.is_empty() {
+        info!("No migrations would be {}", action);
+    }
+
+    for (name, _) in plan {
+        info!("Migration {} would be {}", name, action);
+    }
+}
+
 async fn wait_termination() {
     let mut term = signal(SignalKind::terminate()).unwrap();
     let mut inter = signal(SignalKind::interrupt()).unwrap();
@@ -0,0 +1,90 @@
+use crate::actors::main_storage_manager::MainStorageManagerHandle;
+use crate::storages::main_storage::Block;
+use crate::{actors::queue_manager::QueueManagerHandle, register::Register};
+use crate::{metrics_update, repeat_until_ok};
+use anyhow::Result;
+use log::{error, info};
+use tokio::time::{sleep, Duration};
+
+pub struct MetadataParsingCtx;
+
+impl MetadataParsingCtx {
+    pub async fn setup_and_run(register: &Register) -> Result<Self> {
+        let metadata_queue_manager = QueueManagerHandle::new(register).await?;
+        let main_storage_manager = MainStorageManagerHandle::new(register).await?;
+
+        // Metadata thread
+        tokio::spawn(MetadataParsingCtx::metadata_worker(
+            metadata_queue_manager,
+            main_storage_manager,
+        ));
+
+        Ok(Self {})
+    }
+
+    async fn metadata_worker(
+        mut queue_manager: QueueManagerHandle,
+        mut main_storage_manager: MainStorageManagerHandle,
+    ) {
+        metrics_update!(inc total ACTIVE_WORKERS_COUNT, &["metadata"]);
+
+        let metadata_join_handle = tokio::spawn(async move {
+            loop {
+                let loop_timer = metrics_update!(timer LOOP_TIME, &["metadata"]);
+                let blocks_metadata = match queue_manager.get_blocks_metadata().await {
+                    Ok(blocks_metadata) => blocks_metadata,
+                    Err(err) => {
+                        error!("Failed to get blocks metadata: {}", err);
+                        sleep(Duration::from_millis(5000)).await;
+                        continue;
+                    }
+                };
+
+                if blocks_metadata.is_empty() {
+                    sleep(Duration::from_millis(5000)).await;
+                    continue;
+                }
+
+                let slots: Vec<u64> = blocks_metadata
+                    .iter()
+                    .map(|metadata| metadata.slot)
+                    .collect();
+
+                let blocks: Vec<Block> = blocks_metadata
+                    .into_iter()
+                    .filter_map(|metadata| {
+                        let slot = metadata.slot;
+                        Block::try_from_metadata(metadata)
+                            .map_err(|err| {
+                                error!("Failed to parse metadata of slot {}: {}", slot, err)
+                            })
+                            .ok()
+                    })
+                    .collect();
+
+                // The blocks table deduplicates by slot, so storing the same block twice is harmless
+                repeat_until_ok!(
+                    main_storage_manager
+                        .store_blocks_block(blocks.clone())
+                        .await,
+                    5
+                );
+
+                repeat_until_ok!(
+                    queue_manager
+                        .mark_blocks_metadata_as_parsed(slots.clone())
+                        .await,
+                    5
+                );
+
+                info!("Stored {} blocks", blocks.len());
+                metrics_update!(timer observe loop_timer);
+            }
+        });
+
+        if metadata_join_handle.await.is_err() {
+            metrics_update!(dec total ACTIVE_WORKERS_COUNT, &["metadata"]);
+            error!("Metadata worker has been killed");
+        }
+    }
+}
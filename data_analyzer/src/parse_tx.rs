@@ -0,0 +1,193 @@
+//! `parse-tx` subcommand: prints the rows the analyzer would extract from a single transaction
+//! without connecting to any storage
+
+use std::{collections::HashMap, io::Write, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use serde::Serialize;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_instruction_parser::{ParseOptions, TransactionParsingResult};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+
+use crate::actors::transaction_parser::{
+    collect_delegations, transaction_signature, TransactionParserHandle,
+};
+use crate::signature_tracing::SignatureTracer;
+use crate::storages::main_storage::https_client::{
+    BalancesRow, InstructionArgumentsRow, InstructionRow,
+};
+use crate::storages::main_storage::Delegation;
+
+#[derive(Args, Debug)]
+pub struct ParseTxArgs {
+    /// Signature of the transaction loaded with `getTransaction`
+    #[clap(long, requires = "rpc_url", conflicts_with = "file")]
+    signature: Option<String>,
+
+    /// Solana RPC the transaction is loaded from
+    #[clap(long)]
+    rpc_url: Option<String>,
+
+    /// File with the transaction in the JSON of `getTransaction`
+    #[clap(long, required_unless_present = "signature")]
+    file: Option<PathBuf>,
+}
+
+/// Rows of the transaction in the form they are inserted into the main storage
+#[derive(Serialize)]
+struct ParsedTransaction {
+    instructions: Vec<InstructionRow>,
+    instruction_arguments: Vec<InstructionArgumentsRow>,
+    balances: Vec<BalancesRow>,
+    delegations: Vec<Delegation>,
+    undelegations: Vec<Delegation>,
+}
+
+/// Loads and parses the transaction and writes its rows to `out` as pretty JSON. Fails with the
+/// parsing error if the analyzer would store the transaction as erroneous
+pub async fn run(args: &ParseTxArgs, out: &mut impl Write) -> Result<()> {
+    let transaction = load_transaction(args).await?;
+    let tx_signature = transaction_signature(&transaction);
+
+    let mut transaction_parser =
+        TransactionParserHandle::new(ParseOptions::default(), SignatureTracer::default()).await;
+    let TransactionParsingResult {
+        instructions,
+        balances,
+        instruction_arguments,
+        ..
+    } = transaction_parser
+        .parse_transaction(transaction)
+        .await?
+        .with_context(|| format!("Failed to parse the transaction {}", tx_signature))?;
+
+    let pre_balances: HashMap<String, u64> = balances
+        .iter()
+        .filter_map(|balance| {
+            balance
+                .pre_balance
+                .map(|pre_balance| (balance.account.clone(), pre_balance))
+        })
+        .collect();
+    let (delegations, undelegations) = collect_delegations(instructions.clone(), &pre_balances);
+
+    let parsed_transaction = ParsedTransaction {
+        instructions: instructions.into_iter().map(InstructionRow::from).collect(),
+        instruction_arguments: instruction_arguments
+            .into_iter()
+            .map(InstructionArgumentsRow::from)
+            .collect(),
+        balances: balances.into_iter().map(BalancesRow::from).collect(),
+        delegations,
+        undelegations,
+    };
+
+    serde_json::to_writer_pretty(&mut *out, &parsed_transaction)?;
+    writeln!(out)?;
+
+    Ok(())
+}
+
+async fn load_transaction(args: &ParseTxArgs) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    match (&args.file, &args.signature, &args.rpc_url) {
+        (Some(file), _, _) => {
+            let transaction = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+
+            Ok(serde_json::from_str(&transaction)?)
+        }
+        (None, Some(signature), Some(rpc_url)) => {
+            let client = RpcClient::new(rpc_url.clone());
+            let config = RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            };
+
+            Ok(client
+                .get_transaction_with_config(&Signature::from_str(signature)?, config)
+                .await?)
+        }
+        _ => Err(anyhow!("--file or --signature with --rpc-url is required")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    const TRANSACTION: &str = include_str!("../../tests/fixtures/transaction.json");
+    const TRANSACTION_SIGNATURE: &str =
+        "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU";
+
+    fn file_args(name: &str, transaction: &str) -> ParseTxArgs {
+        let file = std::env::temp_dir().join(name);
+        std::fs::write(&file, transaction).unwrap();
+
+        ParseTxArgs {
+            signature: None,
+            rpc_url: None,
+            file: Some(file),
+        }
+    }
+
+    #[tokio::test]
+    async fn rows_of_the_file_are_printed() {
+        let args = file_args("parse_tx_rows_of_the_file_are_printed.json", TRANSACTION);
+        let mut out = Vec::new();
+
+        run(&args, &mut out).await.unwrap();
+
+        let expected = solana_instruction_parser::parse_transaction(
+            serde_json::from_str(TRANSACTION).unwrap(),
+        )
+        .unwrap();
+        let printed: Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(
+            printed["instructions"].as_array().unwrap().len(),
+            expected.instructions.len()
+        );
+        assert_eq!(
+            printed["instruction_arguments"].as_array().unwrap().len(),
+            expected.instruction_arguments.len()
+        );
+        assert_eq!(
+            printed["balances"].as_array().unwrap().len(),
+            expected.balances.len()
+        );
+        assert_eq!(
+            printed["instructions"][0]["tx_signature"],
+            TRANSACTION_SIGNATURE
+        );
+        assert_eq!(printed["instructions"][0]["slot"], 117946133);
+        assert_eq!(printed["delegations"], Value::Array(Vec::new()));
+        assert_eq!(printed["undelegations"], Value::Array(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn parsing_error_is_returned() {
+        let mut transaction: Value = serde_json::from_str(TRANSACTION).unwrap();
+        transaction["transaction"]["message"]["instructions"][0]["programIdIndex"] = 99.into();
+        let args = file_args(
+            "parse_tx_parsing_error_is_returned.json",
+            &transaction.to_string(),
+        );
+        let mut out = Vec::new();
+
+        let err = run(&args, &mut out).await.unwrap_err();
+
+        assert!(out.is_empty());
+        assert_eq!(
+            err.to_string(),
+            format!("Failed to parse the transaction {}", TRANSACTION_SIGNATURE)
+        );
+        assert!(err
+            .chain()
+            .any(|cause| cause.is::<crate::errors::ParseInstructionError>()));
+    }
+}
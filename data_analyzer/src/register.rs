@@ -1,11 +1,30 @@
+use prometheus::Registry;
+
+use crate::actors::prometheus_exporter::REGISTRY;
+use crate::backpressure::InFlightBatches;
 use crate::configuration::*;
+use crate::health::SharedHealthState;
+use crate::signature_tracing::SignatureTracer;
 
 pub struct Register {
     pub config: Configuration,
+    pub health: SharedHealthState,
+    pub tracer: SignatureTracer,
+    pub in_flight_batches: InFlightBatches,
+    /// Registry of the metrics exported by the service
+    pub registry: Registry,
 }
 
 impl Register {
     pub fn new(config: Configuration) -> Self {
-        Self { config }
+        Self {
+            tracer: SignatureTracer::new(config.get_tracing_config()),
+            in_flight_batches: InFlightBatches::new(
+                config.get_queue_storage_config().max_unflushed_batches,
+            ),
+            config,
+            health: Default::default(),
+            registry: REGISTRY.clone(),
+        }
     }
 }
@@ -0,0 +1,93 @@
+use crate::configuration::TracingConfig;
+use log::info;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Prefix of the trace records, shared with data_loader so one grep follows a signature through
+/// both services
+pub const TRACE_PREFIX: &str = "[trace]";
+
+/// Steps of the parsing of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The transaction is claimed from the queue for parsing
+    FetchedByAnalyzer,
+    /// The transaction is parsed
+    Parsed,
+    /// The instructions of the transaction are buffered by the collector
+    InstructionsBuffered,
+    /// The buffered instructions of the transaction are stored in the main storage
+    Flushed,
+    /// The transaction is marked as parsed in the queue
+    MarkedParsed,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TraceEvent::FetchedByAnalyzer => "fetched-by-analyzer",
+            TraceEvent::Parsed => "parsed",
+            TraceEvent::InstructionsBuffered => "instructions-buffered",
+            TraceEvent::Flushed => "flushed",
+            TraceEvent::MarkedParsed => "marked-parsed",
+        })
+    }
+}
+
+/// Logs the steps of the signatures listed in the `[tracing]` section, the others are not traced
+#[derive(Debug, Clone, Default)]
+pub struct SignatureTracer {
+    signatures: Arc<HashSet<String>>,
+    /// Traced events in the order they are logged
+    #[cfg(test)]
+    events: Arc<Mutex<Vec<(String, TraceEvent)>>>,
+}
+
+impl SignatureTracer {
+    pub fn new(config: &TracingConfig) -> Self {
+        Self {
+            signatures: Arc::new(config.signatures.iter().cloned().collect()),
+            #[cfg(test)]
+            events: Default::default(),
+        }
+    }
+
+    pub fn is_traced(&self, signature: &str) -> bool {
+        self.signatures.contains(signature)
+    }
+
+    pub fn trace(&self, signature: &str, event: TraceEvent) {
+        if !self.is_traced(signature) {
+            return;
+        }
+
+        info!(
+            tx_signature = signature,
+            trace_event = event.to_string().as_str();
+            "{} {} {}",
+            TRACE_PREFIX,
+            signature,
+            event
+        );
+
+        #[cfg(test)]
+        self.events
+            .lock()
+            .unwrap()
+            .push((signature.to_string(), event));
+    }
+
+    #[cfg(test)]
+    pub fn events(&self, signature: &str) -> Vec<TraceEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(traced, _)| traced == signature)
+            .map(|(_, event)| *event)
+            .collect()
+    }
+}
@@ -0,0 +1,459 @@
+//! Newline-delimited JSON files selected with a `file://<directory>` database_url, so a slice of
+//! transactions can be analyzed without ClickHouse. Every table is written to its own directory
+//! with the columns of the ClickHouse table, e.g. DuckDB reads them with
+//! `read_json_auto('<directory>/instructions/*.ndjson')`
+
+use super::main_storage::{
+    https_client::{BalancesRow, ErroneousTransactionRow, InstructionArgumentsRow, InstructionRow},
+    AnchorEvent, Balance, BatchMarker, BidEvent, Block, CandyMint, ClaimEvent, CommissionChange,
+    Delegation, DelegationsDaily, EntanglerSwap, ErroneousRow, ErroneousTransaction,
+    IndexingWatermark, Instruction, InstructionArgument, MainStorage, NftEvent, PackEvent, Page,
+    QueueCursor, QueueCursorRow, SaleEvent, SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use clickhouse_storage::{ClickhouseStorage, StorageError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const FILE_EXTENSION: &str = "ndjson";
+
+/// Storages connected by one process, keeps the file names of the actors apart
+static CONNECTED_STORAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// File of a table the rows are currently appended to
+struct TableFile {
+    writer: BufWriter<File>,
+    rows: usize,
+}
+
+pub struct FileMainStorage {
+    directory: PathBuf,
+    /// Rows written to a file before the next one is started
+    max_file_rows: usize,
+    /// `<process id>-<storage number>`, the prefix of the file names of the storage
+    name: String,
+    files_started: usize,
+    files: HashMap<&'static str, TableFile>,
+}
+
+impl FileMainStorage {
+    pub fn connect(directory: &str, max_file_rows: usize) -> Result<Self> {
+        fs::create_dir_all(directory)?;
+
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            max_file_rows: max_file_rows.max(1),
+            name: format!(
+                "{}-{}",
+                std::process::id(),
+                CONNECTED_STORAGES.fetch_add(1, Ordering::Relaxed)
+            ),
+            files_started: 0,
+            files: HashMap::new(),
+        })
+    }
+
+    fn store<T: Serialize>(&mut self, table: &'static str, rows: Vec<T>) -> Result<()> {
+        for row in rows {
+            if self
+                .files
+                .get(table)
+                .is_none_or(|file| file.rows >= self.max_file_rows)
+            {
+                self.start_file(table)?;
+            }
+
+            let file = self.files.get_mut(table).unwrap();
+            serde_json::to_writer(&mut file.writer, &row)?;
+            file.writer.write_all(b"\n")?;
+            file.rows += 1;
+        }
+
+        // The rows of the stored block are readable right away
+        if let Some(file) = self.files.get_mut(table) {
+            file.writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn start_file(&mut self, table: &'static str) -> Result<()> {
+        if let Some(mut file) = self.files.remove(table) {
+            file.writer.flush()?;
+        }
+
+        let table_directory = self.directory.join(table);
+        fs::create_dir_all(&table_directory)?;
+
+        let path = table_directory.join(format!(
+            "{}-{:06}.{}",
+            self.name, self.files_started, FILE_EXTENSION
+        ));
+        self.files_started += 1;
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.files.insert(
+            table,
+            TableFile {
+                writer: BufWriter::new(file),
+                rows: 0,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// Reads the rows of all the files of the `table`, including the ones written by other storages
+pub fn read_rows<T: DeserializeOwned>(directory: &Path, table: &str) -> Result<Vec<T>> {
+    let table_directory = directory.join(table);
+    if !table_directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs::read_dir(table_directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == FILE_EXTENSION));
+    paths.sort();
+
+    let mut rows = Vec::new();
+
+    for path in paths {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.is_empty() {
+                rows.push(serde_json::from_str(&line)?);
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+#[async_trait]
+impl ClickhouseStorage for FileMainStorage {
+    async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+
+    /// The directories of the tables are created with their first rows
+    async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl MainStorage for FileMainStorage {
+    async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
+        let rows: Vec<_> = instructions.into_iter().map(InstructionRow::from).collect();
+        self.store("instructions", rows)
+    }
+
+    async fn store_instruction_arguments_block(
+        &mut self,
+        instruction_arguments: Vec<InstructionArgument>,
+    ) -> Result<()> {
+        let rows: Vec<_> = instruction_arguments
+            .into_iter()
+            .map(InstructionArgumentsRow::from)
+            .collect();
+        self.store("instruction_arguments", rows)
+    }
+
+    async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
+        let rows: Vec<_> = balances.into_iter().map(BalancesRow::from).collect();
+        self.store("balances", rows)
+    }
+
+    async fn store_erroneous_transaction_block(
+        &mut self,
+        erroneous_transactions: Vec<ErroneousTransaction>,
+    ) -> Result<()> {
+        let rows: Vec<_> = erroneous_transactions
+            .into_iter()
+            .map(ErroneousTransactionRow::from)
+            .collect();
+        self.store("erroneous_transactions", rows)
+    }
+
+    async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        self.store("erroneous_rows", erroneous_rows)
+    }
+
+    async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        self.store("batches", batches)
+    }
+
+    async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        self.store("delegations", delegations)
+    }
+
+    async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
+        self.store("undelegations", undelegations)
+    }
+
+    async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        self.store("delegations_daily", delegations_daily)
+    }
+
+    async fn store_blocks_block(&mut self, blocks: Vec<Block>) -> Result<()> {
+        self.store("blocks", blocks)
+    }
+
+    async fn store_token_transfers_block(
+        &mut self,
+        token_transfers: Vec<TokenTransfer>,
+    ) -> Result<()> {
+        self.store("token_transfers", token_transfers)
+    }
+
+    async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        self.store("nft_events", nft_events)
+    }
+
+    async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        self.store("commission_changes", commission_changes)
+    }
+
+    async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        self.store("claim_events", claim_events)
+    }
+
+    async fn store_entangler_swaps_block(
+        &mut self,
+        entangler_swaps: Vec<EntanglerSwap>,
+    ) -> Result<()> {
+        self.store("entangler_swaps", entangler_swaps)
+    }
+
+    async fn store_anchor_events_block(&mut self, anchor_events: Vec<AnchorEvent>) -> Result<()> {
+        self.store("anchor_events", anchor_events)
+    }
+
+    async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        self.store("nft_sales", sale_events)
+    }
+
+    async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        self.store("nft_pack_events", pack_events)
+    }
+
+    async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        self.store("candy_mints", candy_mints)
+    }
+
+    async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        self.store("stake_account_events", stake_account_events)
+    }
+
+    async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        self.store("bid_events", bid_events)
+    }
+
+    async fn store_sol_transfers_block(&mut self, sol_transfers: Vec<SolTransfer>) -> Result<()> {
+        self.store("sol_transfers", sol_transfers)
+    }
+
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        Ok(read_rows::<Block>(&self.directory, "blocks")?
+            .into_iter()
+            .find(|block| block.slot == slot && block.block_time.is_some())
+            .and_then(|block| block.block_time))
+    }
+
+    async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        let mut delegations: Vec<_> = read_rows::<Delegation>(&self.directory, "delegations")?
+            .into_iter()
+            .filter(|delegation| {
+                delegation.stake_acc == stake_acc
+                    && (from_slot..=to_slot).contains(&delegation.slot)
+            })
+            .collect();
+        delegations.sort_by_key(|delegation| (delegation.slot, delegation.raw_instruction_idx));
+
+        Ok(delegations)
+    }
+
+    async fn fetch_instructions_by_signature(
+        &mut self,
+        tx_signature: &str,
+        page: Page,
+    ) -> Result<Vec<InstructionRow>> {
+        let mut instructions: Vec<_> =
+            read_rows::<InstructionRow>(&self.directory, "instructions")?
+                .into_iter()
+                .filter(|instruction| instruction.tx_signature == tx_signature)
+                .collect();
+        instructions.sort_by_key(|instruction| {
+            (
+                instruction
+                    .transaction_instruction_idx
+                    .unwrap_or(instruction.instruction_idx),
+                instruction.inner_instructions_set.is_some(),
+                instruction.instruction_idx,
+            )
+        });
+
+        Ok(page.of(instructions))
+    }
+
+    async fn fetch_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+        page: Page,
+    ) -> Result<Vec<Delegation>> {
+        let delegations = self
+            .get_delegations_for_stake_acc(stake_acc, from_slot, to_slot)
+            .await?;
+
+        Ok(page.of(delegations))
+    }
+
+    async fn fetch_balances_by_account(
+        &mut self,
+        account: &str,
+        page: Page,
+    ) -> Result<Vec<BalancesRow>> {
+        let mut balances: Vec<_> = read_rows::<BalancesRow>(&self.directory, "balances")?
+            .into_iter()
+            .filter(|balance| balance.account == account)
+            .collect();
+        balances.sort_by(|a, b| {
+            (&a.tx_signature, a.inner_instructions_set)
+                .cmp(&(&b.tx_signature, b.inner_instructions_set))
+        });
+
+        Ok(page.of(balances))
+    }
+
+    async fn get_queue_cursor(&mut self, queue: &str) -> Result<Option<QueueCursor>> {
+        Ok(
+            read_rows::<QueueCursorRow>(&self.directory, "queue_cursors")?
+                .into_iter()
+                .filter(|row| row.queue == queue)
+                .map(|row| QueueCursor {
+                    slot: row.slot,
+                    signature: row.signature,
+                })
+                .max(),
+        )
+    }
+
+    async fn store_queue_cursor(&mut self, queue: &str, cursor: QueueCursor) -> Result<()> {
+        self.store(
+            "queue_cursors",
+            vec![QueueCursorRow {
+                queue: queue.to_string(),
+                slot: cursor.slot,
+                signature: cursor.signature,
+            }],
+        )
+    }
+
+    async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        Ok(
+            read_rows::<IndexingWatermark>(&self.directory, "indexing_watermark")?
+                .into_iter()
+                .map(|watermark| watermark.slot)
+                .max(),
+        )
+    }
+
+    async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        self.store("indexing_watermark", vec![IndexingWatermark { slot }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delegation(slot: u64) -> Delegation {
+        Delegation {
+            slot,
+            stake_acc: "stake".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn files_are_rotated() -> Result<()> {
+        let directory = std::env::temp_dir().join("data_analyzer_files_are_rotated");
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut storage = FileMainStorage::connect(directory.to_str().unwrap(), 2)?;
+        storage
+            .store_delegations_block(vec![delegation(3), delegation(1), delegation(2)])
+            .await?;
+        storage.store_delegations_block(vec![delegation(4)]).await?;
+
+        assert_eq!(fs::read_dir(directory.join("delegations"))?.count(), 2);
+        assert_eq!(
+            read_rows::<Delegation>(&directory, "delegations")?,
+            vec![delegation(3), delegation(1), delegation(2), delegation(4)]
+        );
+        assert_eq!(
+            storage.get_delegations_for_stake_acc("stake", 2, 3).await?,
+            vec![delegation(2), delegation(3)]
+        );
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn overflowing_argument_keeps_exact_value() -> Result<()> {
+        let directory = std::env::temp_dir().join("data_analyzer_overflowing_argument");
+        let _ = fs::remove_dir_all(&directory);
+
+        let exact_value = (u64::MAX as u128 * 3).to_string();
+        let mut storage = FileMainStorage::connect(directory.to_str().unwrap(), 10)?;
+        storage
+            .store_instruction_arguments_block(vec![InstructionArgument {
+                arg_path: "/amount".to_string(),
+                unsigned_value: Some(u64::MAX),
+                string_value: Some(exact_value.clone()),
+                overflow: true,
+                ..Default::default()
+            }])
+            .await?;
+
+        let rows = read_rows::<InstructionArgumentsRow>(&directory, "instruction_arguments")?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].unsigned_value, Some(u64::MAX));
+        assert_eq!(rows[0].string_value, Some(exact_value));
+        assert_eq!(rows[0].overflow, 1);
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+}
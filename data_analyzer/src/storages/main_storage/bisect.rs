@@ -0,0 +1,216 @@
+use super::{
+    AnchorEvent, Balance, BidEvent, CandyMint, ClaimEvent, CommissionChange, Delegation,
+    EntanglerSwap, ErroneousRow, Instruction, InstructionArgument, NftEvent, PackEvent, SaleEvent,
+    SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+use anyhow::Result;
+use std::fmt::Debug;
+use std::future::Future;
+use tokio::time::{Duration, Instant};
+
+/// Row of the transaction it was parsed from
+pub trait TxRow: Debug {
+    fn tx_signature(&self) -> &str;
+
+    /// The row as it is kept in `erroneous_rows` when the `table` rejects it
+    fn erroneous(&self, table: &str, cause: String) -> ErroneousRow {
+        ErroneousRow {
+            target_table: table.to_string(),
+            tx_signature: self.tx_signature().to_string(),
+            row: format!("{:?}", self),
+            cause,
+        }
+    }
+}
+
+macro_rules! impl_tx_row {
+    ( $( $row:ty ),* ) => {
+        $(
+            impl TxRow for $row {
+                fn tx_signature(&self) -> &str {
+                    &self.tx_signature
+                }
+            }
+        )*
+    };
+}
+
+impl_tx_row!(
+    Instruction,
+    InstructionArgument,
+    Balance,
+    Delegation,
+    TokenTransfer,
+    NftEvent,
+    CommissionChange,
+    ClaimEvent,
+    EntanglerSwap,
+    AnchorEvent,
+    SaleEvent,
+    PackEvent,
+    CandyMint,
+    StakeAccountEvent,
+    BidEvent,
+    SolTransfer
+);
+
+/// Bounds of the bisection of a single failed block
+#[derive(Debug, Clone, Copy)]
+pub struct BisectionLimits {
+    pub max_inserts: usize,
+    pub max_duration: Duration,
+}
+
+/// Rows of a failed block after its bisection
+#[derive(Debug)]
+pub struct Bisection<T> {
+    /// Rows stored by the inserts of the halves
+    pub stored: Vec<T>,
+    /// Rows whose own inserts failed while the other halves were stored, along with the errors
+    pub rejected: Vec<(T, String)>,
+    /// Rows which are not stored yet because the limits were reached or nothing could be stored
+    pub remaining: Vec<T>,
+}
+
+/// Inserts the halves of a failed block and the halves of the failed halves down to single rows,
+/// so a single bad row doesn't take the whole block down. A single row is rejected only when
+/// another insert of the bisection succeeds, otherwise the main storage is likely unavailable
+/// and every row is left to the next flush
+pub async fn bisect<T, F, Fut>(rows: Vec<T>, limits: BisectionLimits, mut store: F) -> Bisection<T>
+where
+    T: Clone,
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let deadline = Instant::now() + limits.max_duration;
+    let mut inserts = 0;
+
+    let mut stored = Vec::new();
+    let mut failed = Vec::new();
+    let mut remaining = Vec::new();
+
+    // The block itself has failed, the halves are inserted in the order of the rows
+    let mut chunks = halves(rows);
+    while let Some(chunk) = chunks.pop() {
+        if inserts >= limits.max_inserts || Instant::now() >= deadline {
+            remaining.extend(chunk);
+            continue;
+        }
+
+        inserts += 1;
+        match store(chunk.clone()).await {
+            Ok(()) => stored.extend(chunk),
+            Err(err) if chunk.len() == 1 => {
+                failed.extend(chunk.into_iter().map(|row| (row, format!("{:#}", err))))
+            }
+            Err(_) => chunks.extend(halves(chunk)),
+        }
+    }
+
+    if stored.is_empty() {
+        remaining.extend(failed.into_iter().map(|(row, _)| row));
+
+        return Bisection {
+            stored,
+            rejected: Vec::new(),
+            remaining,
+        };
+    }
+
+    Bisection {
+        stored,
+        rejected: failed,
+        remaining,
+    }
+}
+
+/// The halves of the rows, the first half is the last one to be popped
+fn halves<T>(mut rows: Vec<T>) -> Vec<Vec<T>> {
+    if rows.len() <= 1 {
+        return vec![rows];
+    }
+
+    let second = rows.split_off(rows.len() / 2);
+    vec![second, rows]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::{Arc, Mutex};
+
+    const LIMITS: BisectionLimits = BisectionLimits {
+        max_inserts: 64,
+        max_duration: Duration::from_secs(30),
+    };
+
+    /// Stores the blocks which have none of the `bad` rows, the others fail
+    fn storage(
+        bad: &'static [u32],
+    ) -> (
+        Arc<Mutex<Vec<u32>>>,
+        impl FnMut(Vec<u32>) -> std::future::Ready<Result<()>>,
+    ) {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let store = {
+            let stored = stored.clone();
+            move |rows: Vec<u32>| {
+                if rows.iter().any(|row| bad.contains(row)) {
+                    return std::future::ready(Err(anyhow!("Cannot parse input")));
+                }
+                stored.lock().unwrap().extend(rows);
+                std::future::ready(Ok(()))
+            }
+        };
+
+        (stored, store)
+    }
+
+    #[tokio::test]
+    async fn bad_rows_are_isolated() {
+        let (stored, store) = storage(&[3, 70]);
+
+        let bisection = bisect((0..100).collect(), LIMITS, store).await;
+
+        let expected: Vec<u32> = (0..100).filter(|row| *row != 3 && *row != 70).collect();
+        assert_eq!(bisection.stored, expected);
+        assert_eq!(*stored.lock().unwrap(), expected);
+        assert_eq!(
+            bisection.rejected,
+            [
+                (3, String::from("Cannot parse input")),
+                (70, String::from("Cannot parse input"))
+            ]
+        );
+        assert!(bisection.remaining.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rows_are_kept_when_nothing_is_stored() {
+        let (_, store) = storage(&[0, 1]);
+
+        let bisection = bisect(vec![0, 1], LIMITS, store).await;
+
+        assert!(bisection.stored.is_empty());
+        assert!(bisection.rejected.is_empty());
+        assert_eq!(bisection.remaining, [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn inserts_are_bounded() {
+        let (_, store) = storage(&[3]);
+        let limits = BisectionLimits {
+            max_inserts: 4,
+            ..LIMITS
+        };
+
+        let bisection = bisect((0..100).collect(), limits, store).await;
+
+        // 0..50 fails, 0..25 fails, 0..12 fails, 0..6 fails and the budget is spent
+        let mut remaining = bisection.remaining;
+        remaining.sort_unstable();
+        assert_eq!(remaining, (0..100).collect::<Vec<u32>>());
+        assert!(bisection.stored.is_empty());
+    }
+}
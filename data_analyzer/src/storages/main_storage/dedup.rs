@@ -0,0 +1,65 @@
+//! Tokens of `insert_deduplication_token`. ClickHouse drops an insert whose token is among the
+//! last inserts of the table, so a block re-sent after its acknowledgement has been lost, or
+//! again after a restart, is stored once. Replicated tables keep the last
+//! `replicated_deduplication_window` tokens, the others keep `non_replicated_deduplication_window`
+//! ones, which is 0 unless it is set for the server.
+//!
+//! `delegations_daily` is inserted without a token: the sums of two different blocks may be
+//! equal and the second one must not be dropped
+
+use solana_sdk::hash::Hasher;
+use std::fmt::Debug;
+
+/// Token of a block of rows inserted into `table`. It is derived from the rows only, so the
+/// same block gets the same token in another process. A row is hashed by its `Debug` output,
+/// which covers all of its fields
+pub fn deduplication_token<T: Debug>(table: &str, rows: &[T]) -> String {
+    let mut hasher = Hasher::default();
+    hasher.hash(table.as_bytes());
+
+    for row in rows {
+        let row = format!("{:?}", row);
+        hasher.hash(&(row.len() as u64).to_le_bytes());
+        hasher.hash(row.as_bytes());
+    }
+
+    format!("{}-{}", table, hasher.result())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storages::main_storage::Delegation;
+
+    fn delegation(tx_signature: &str, amount: u64) -> Delegation {
+        Delegation {
+            tx_signature: tx_signature.to_string(),
+            amount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_rows_have_same_token() {
+        let rows = vec![delegation("a", 1), delegation("b", 2)];
+
+        assert_eq!(
+            deduplication_token("delegations", &rows),
+            deduplication_token("delegations", &rows.clone())
+        );
+    }
+
+    #[test]
+    fn token_follows_table_and_rows() {
+        let rows = vec![delegation("a", 1), delegation("b", 2)];
+        let token = deduplication_token("delegations", &rows);
+
+        assert!(token.starts_with("delegations-"));
+        assert_ne!(token, deduplication_token("undelegations", &rows));
+        assert_ne!(
+            token,
+            deduplication_token("delegations", &[delegation("a", 1), delegation("b", 3)])
+        );
+        assert_ne!(token, deduplication_token("delegations", &rows[..1]));
+    }
+}
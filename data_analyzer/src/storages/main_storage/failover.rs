@@ -0,0 +1,613 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use clickhouse_storage::{schema::TableColumn, ClickhouseStorage, StorageError};
+use futures_lite::future::Boxed;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use tokio::time::sleep;
+
+use super::{
+    https_client::{BalancesRow, InstructionRow},
+    AnchorEvent, Balance, BatchMarker, BidEvent, Block, CandyMint, ClaimEvent, CommissionChange,
+    Delegation, DelegationsDaily, EntanglerSwap, ErroneousRow, ErroneousTransaction, Instruction,
+    InstructionArgument, MainStorage, NftEvent, PackEvent, Page, QueueCursor, SaleEvent,
+    SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+use crate::metrics_update;
+
+/// Pause before the first retry on the next replica, doubled with every failed one
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    /// Address of the replica the last request succeeded on per database url, so the storages
+    /// connected later don't start with a dead replica
+    static ref LAST_HEALTHY_REPLICAS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+/// Connects to the replica of the url
+pub type Connector = Arc<dyn Fn(String) -> Boxed<Result<Box<dyn MainStorage>>> + Send + Sync>;
+
+struct Replica {
+    address: String,
+    /// Url of the replica alone
+    url: String,
+    /// Dropped on a connection error and connected again when the replica is used next time
+    storage: Option<Box<dyn MainStorage>>,
+}
+
+/// Main storage of several replicas of a ClickHouse cluster listed in the `database_url`
+/// separated by commas. Requests go to the last healthy replica, on a connection-level error
+/// the request is repeated on the next ones until every replica has been tried once. Other
+/// errors are returned as they are
+pub struct FailoverStorage {
+    database_url: String,
+    replicas: Vec<Replica>,
+    current: usize,
+    backoff: Duration,
+    connector: Connector,
+}
+
+impl FailoverStorage {
+    /// Connects to the last healthy replica or to the first one which answers. `replicas` are
+    /// (address, url) of every replica
+    pub async fn connect(
+        database_url: &str,
+        replicas: Vec<(String, String)>,
+        connector: Connector,
+    ) -> Result<Self> {
+        let last_healthy = LAST_HEALTHY_REPLICAS
+            .lock()
+            .unwrap()
+            .get(database_url)
+            .cloned();
+        let current = replicas
+            .iter()
+            .position(|(address, _)| Some(address) == last_healthy.as_ref())
+            .unwrap_or(0);
+
+        let mut storage = Self {
+            database_url: database_url.to_string(),
+            replicas: replicas
+                .into_iter()
+                .map(|(address, url)| Replica {
+                    address,
+                    url,
+                    storage: None,
+                })
+                .collect(),
+            current,
+            backoff: MIN_BACKOFF,
+            connector,
+        };
+
+        storage
+            .with_failover(|_| Box::pin(async { Ok(()) }))
+            .await?;
+
+        Ok(storage)
+    }
+
+    pub fn current_address(&self) -> &str {
+        &self.replicas[self.current].address
+    }
+
+    async fn current_storage(&mut self) -> Result<&mut Box<dyn MainStorage>> {
+        let replica = &mut self.replicas[self.current];
+
+        if replica.storage.is_none() {
+            replica.storage = Some((self.connector)(replica.url.clone()).await?);
+            info!("Connected to main storage replica {}", replica.address);
+        }
+
+        Ok(replica.storage.as_mut().unwrap())
+    }
+
+    fn set_replica_health(&self, replica: usize, healthy: bool) {
+        metrics_update!(
+            set MAIN_STORAGE_REPLICA_HEALTHY,
+            &[&self.replicas[replica].address],
+            if healthy { 1.0 } else { 0.0 }
+        );
+    }
+
+    /// Runs the `request` on the current replica and on the next ones while it fails with
+    /// a connection error, the replica it succeeds on is remembered as the healthy one
+    async fn with_failover<T, F>(&mut self, mut request: F) -> Result<T>
+    where
+        F: for<'a> FnMut(
+                &'a mut Box<dyn MainStorage>,
+            ) -> Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>
+            + Send,
+        T: Send,
+    {
+        let mut attempts = 0;
+
+        loop {
+            // A replica which is not connected yet has failed to connect, that's always
+            // a connection error
+            let result = match self.current_storage().await {
+                Ok(storage) => request(storage).await.map_err(|err| {
+                    let connection_error = is_connection_error(&err);
+                    (err, connection_error)
+                }),
+                Err(err) => Err((err, true)),
+            };
+
+            match result {
+                Ok(response) => {
+                    self.backoff = MIN_BACKOFF;
+                    self.set_replica_health(self.current, true);
+                    LAST_HEALTHY_REPLICAS.lock().unwrap().insert(
+                        self.database_url.clone(),
+                        self.current_address().to_string(),
+                    );
+
+                    return Ok(response);
+                }
+                Err((err, true)) => {
+                    self.set_replica_health(self.current, false);
+                    self.replicas[self.current].storage = None;
+
+                    attempts += 1;
+                    if attempts >= self.replicas.len() {
+                        return Err(err);
+                    }
+
+                    let failed = self.current;
+                    self.current = (self.current + 1) % self.replicas.len();
+                    warn!(
+                        "Main storage replica {} failed: {}, retrying on {} in {:?}",
+                        self.replicas[failed].address,
+                        err,
+                        self.current_address(),
+                        self.backoff
+                    );
+
+                    sleep(self.backoff).await;
+                    self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                }
+                Err((err, false)) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Whether the request failed to reach the server rather than being rejected by it
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<std::io::Error>() {
+            return true;
+        }
+
+        if let Some(err) = cause.downcast_ref::<clickhouse_rs::errors::Error>() {
+            return matches!(
+                err,
+                clickhouse_rs::errors::Error::Io(_)
+                    | clickhouse_rs::errors::Error::Connection(_)
+                    | clickhouse_rs::errors::Error::Driver(_)
+            );
+        }
+
+        matches!(
+            cause.downcast_ref::<clickhouse::error::Error>(),
+            Some(clickhouse::error::Error::Network(_))
+        )
+    })
+}
+
+/// `ClickhouseStorage` reports the errors as `StorageError`, which are converted back after
+/// the failover
+fn into_storage_error(err: anyhow::Error) -> StorageError {
+    match err.downcast::<StorageError>() {
+        Ok(err) => err,
+        Err(err) => StorageError::UnknownProtocol(err.to_string()),
+    }
+}
+
+#[async_trait]
+impl ClickhouseStorage for FailoverStorage {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+        self.with_failover(|storage| {
+            let ddl = ddl.to_string();
+            Box::pin(async move { Ok(storage.execute(&ddl).await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        self.with_failover(|storage| {
+            Box::pin(async move { Ok(storage.migrations_table_exists().await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+        self.with_failover(|storage| {
+            let version = version.to_string();
+            Box::pin(async move { Ok(storage.migration_exists(&version).await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+
+    async fn migration_lock_holder(
+        &mut self,
+        owner: &str,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        self.with_failover(|storage| {
+            let owner = owner.to_string();
+            Box::pin(async move { Ok(storage.migration_lock_holder(&owner, ttl_secs).await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+
+    async fn describe_table(
+        &mut self,
+        table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        self.with_failover(|storage| {
+            let table = table.to_string();
+            Box::pin(async move { Ok(storage.describe_table(&table).await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+
+    async fn table_engine(&mut self, table: &str) -> Result<Option<String>, StorageError> {
+        self.with_failover(|storage| {
+            let table = table.to_string();
+            Box::pin(async move { Ok(storage.table_engine(&table).await?) })
+        })
+        .await
+        .map_err(into_storage_error)
+    }
+}
+
+/// Every attempt takes its own copy of the rows, the storages consume them
+#[async_trait]
+impl MainStorage for FailoverStorage {
+    async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
+        self.with_failover(|storage| storage.store_instructions_block(instructions.clone()))
+            .await
+    }
+
+    async fn store_instruction_arguments_block(
+        &mut self,
+        instruction_arguments: Vec<InstructionArgument>,
+    ) -> Result<()> {
+        self.with_failover(|storage| {
+            storage.store_instruction_arguments_block(instruction_arguments.clone())
+        })
+        .await
+    }
+
+    async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
+        self.with_failover(|storage| storage.store_balances_block(balances.clone()))
+            .await
+    }
+
+    async fn store_erroneous_transaction_block(
+        &mut self,
+        erroneous_transactions: Vec<ErroneousTransaction>,
+    ) -> Result<()> {
+        self.with_failover(|storage| {
+            storage.store_erroneous_transaction_block(erroneous_transactions.clone())
+        })
+        .await
+    }
+
+    async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        self.with_failover(|storage| storage.store_erroneous_rows_block(erroneous_rows.clone()))
+            .await
+    }
+
+    async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        self.with_failover(|storage| storage.store_batches_block(batches.clone()))
+            .await
+    }
+
+    async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        self.with_failover(|storage| storage.store_delegations_block(delegations.clone()))
+            .await
+    }
+
+    async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
+        self.with_failover(|storage| storage.store_undelegations_block(undelegations.clone()))
+            .await
+    }
+
+    async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        self.with_failover(|storage| {
+            storage.store_delegations_daily_block(delegations_daily.clone())
+        })
+        .await
+    }
+
+    async fn store_blocks_block(&mut self, blocks: Vec<Block>) -> Result<()> {
+        self.with_failover(|storage| storage.store_blocks_block(blocks.clone()))
+            .await
+    }
+
+    async fn store_token_transfers_block(
+        &mut self,
+        token_transfers: Vec<TokenTransfer>,
+    ) -> Result<()> {
+        self.with_failover(|storage| storage.store_token_transfers_block(token_transfers.clone()))
+            .await
+    }
+
+    async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_nft_events_block(nft_events.clone()))
+            .await
+    }
+
+    async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        self.with_failover(|storage| {
+            storage.store_commission_changes_block(commission_changes.clone())
+        })
+        .await
+    }
+
+    async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_claim_events_block(claim_events.clone()))
+            .await
+    }
+
+    async fn store_entangler_swaps_block(
+        &mut self,
+        entangler_swaps: Vec<EntanglerSwap>,
+    ) -> Result<()> {
+        self.with_failover(|storage| storage.store_entangler_swaps_block(entangler_swaps.clone()))
+            .await
+    }
+
+    async fn store_anchor_events_block(&mut self, anchor_events: Vec<AnchorEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_anchor_events_block(anchor_events.clone()))
+            .await
+    }
+
+    async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_sale_events_block(sale_events.clone()))
+            .await
+    }
+
+    async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_pack_events_block(pack_events.clone()))
+            .await
+    }
+
+    async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        self.with_failover(|storage| storage.store_candy_mints_block(candy_mints.clone()))
+            .await
+    }
+
+    async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        self.with_failover(|storage| {
+            storage.store_stake_account_events_block(stake_account_events.clone())
+        })
+        .await
+    }
+
+    async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        self.with_failover(|storage| storage.store_bid_events_block(bid_events.clone()))
+            .await
+    }
+
+    async fn store_sol_transfers_block(&mut self, sol_transfers: Vec<SolTransfer>) -> Result<()> {
+        self.with_failover(|storage| storage.store_sol_transfers_block(sol_transfers.clone()))
+            .await
+    }
+
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        self.with_failover(|storage| storage.get_block_time(slot))
+            .await
+    }
+
+    async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        self.with_failover(|storage| {
+            let stake_acc = stake_acc.to_string();
+            Box::pin(async move {
+                storage
+                    .get_delegations_for_stake_acc(&stake_acc, from_slot, to_slot)
+                    .await
+            })
+        })
+        .await
+    }
+
+    async fn fetch_instructions_by_signature(
+        &mut self,
+        tx_signature: &str,
+        page: Page,
+    ) -> Result<Vec<InstructionRow>> {
+        self.with_failover(|storage| {
+            let tx_signature = tx_signature.to_string();
+            Box::pin(async move {
+                storage
+                    .fetch_instructions_by_signature(&tx_signature, page)
+                    .await
+            })
+        })
+        .await
+    }
+
+    async fn fetch_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+        page: Page,
+    ) -> Result<Vec<Delegation>> {
+        self.with_failover(|storage| {
+            let stake_acc = stake_acc.to_string();
+            Box::pin(async move {
+                storage
+                    .fetch_delegations_for_stake_acc(&stake_acc, from_slot, to_slot, page)
+                    .await
+            })
+        })
+        .await
+    }
+
+    async fn fetch_balances_by_account(
+        &mut self,
+        account: &str,
+        page: Page,
+    ) -> Result<Vec<BalancesRow>> {
+        self.with_failover(|storage| {
+            let account = account.to_string();
+            Box::pin(async move { storage.fetch_balances_by_account(&account, page).await })
+        })
+        .await
+    }
+
+    async fn get_queue_cursor(&mut self, queue: &str) -> Result<Option<QueueCursor>> {
+        self.with_failover(|storage| {
+            let queue = queue.to_string();
+            Box::pin(async move { storage.get_queue_cursor(&queue).await })
+        })
+        .await
+    }
+
+    async fn store_queue_cursor(&mut self, queue: &str, cursor: QueueCursor) -> Result<()> {
+        self.with_failover(|storage| {
+            let queue = queue.to_string();
+            let cursor = cursor.clone();
+            Box::pin(async move { storage.store_queue_cursor(&queue, cursor).await })
+        })
+        .await
+    }
+
+    async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        self.with_failover(|storage| {
+            Box::pin(async move { storage.get_indexing_watermark().await })
+        })
+        .await
+    }
+
+    async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        self.with_failover(|storage| {
+            Box::pin(async move { storage.store_indexing_watermark(slot).await })
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::MainStorageConfig;
+    use crate::storages::main_storage::connect_main_storage;
+    use crate::storages::memory::MemoryMainStorage;
+    use futures_lite::FutureExt;
+
+    /// The first replica refuses the connections, the second one is kept in memory
+    fn connector(memory_url: &'static str) -> Connector {
+        Arc::new(move |url: String| {
+            async move {
+                if url.starts_with("memory://") {
+                    let storage: Box<dyn MainStorage> =
+                        Box::new(MemoryMainStorage::connect(memory_url));
+                    return Ok(storage);
+                }
+
+                connect_main_storage(&MainStorageConfig {
+                    database_url: url,
+                    storage_type: Default::default(),
+                    max_block_rows: 0,
+                    flush_interval_ms: 0,
+                    async_insert: false,
+                    max_file_rows: 0,
+                    retention: None,
+                })
+                .await
+            }
+            .boxed()
+        })
+    }
+
+    fn replicas() -> Vec<(String, String)> {
+        vec![
+            (
+                "127.0.0.1:1".to_string(),
+                "tcp://default@tcp(127.0.0.1:1)/default".to_string(),
+            ),
+            ("memory".to_string(), "memory://".to_string()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn unreachable_replica_is_rotated() {
+        let database_url = "failover_unreachable_replica_is_rotated";
+        let mut storage = FailoverStorage::connect(
+            database_url,
+            replicas(),
+            connector("failover_unreachable_replica_is_rotated"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(storage.current_address(), "memory");
+
+        storage
+            .store_blocks_block(vec![Block {
+                slot: 1,
+                ..Default::default()
+            }])
+            .await
+            .unwrap();
+
+        let memory = MemoryMainStorage::connect("failover_unreachable_replica_is_rotated");
+        assert_eq!(memory.main().blocks.len(), 1);
+
+        // The next storage starts with the healthy replica
+        let storage = FailoverStorage::connect(
+            database_url,
+            replicas(),
+            connector("failover_unreachable_replica_is_rotated"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(storage.current_address(), "memory");
+        assert_eq!(
+            LAST_HEALTHY_REPLICAS.lock().unwrap()[database_url],
+            "memory"
+        );
+    }
+
+    #[test]
+    fn connection_errors() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(is_connection_error(&anyhow::Error::from(refused)));
+
+        let rejected = anyhow::anyhow!("Code: 60. DB::Exception: Table doesn't exist");
+        assert!(!is_connection_error(&rejected));
+    }
+}
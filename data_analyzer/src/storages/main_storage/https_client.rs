@@ -1,120 +1,102 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use clickhouse_http::{Client, Row};
-use dsn::DSN;
+use clickhouse_http::{insert::Insert, query::RowCursor, Client, Row};
+use clickhouse_storage::{schema::TableColumn, storage::http, ClickhouseStorage, StorageError};
+use macros::ClickhouseTable;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::MainStorageError;
 use crate::storages::main_storage::{
-    Balance, ErroneousTransaction, Instruction, InstructionArgument, MainStorage, TxStatus,
+    Balance, ErroneousRow, ErroneousTransaction, Instruction, InstructionArgument, MainStorage,
+    TxStatus,
 };
 
-use super::Delegation;
+use super::{
+    dedup::deduplication_token, AnchorEvent, BatchMarker, BidEvent, Block, CandyMint, ClaimEvent,
+    CommissionChange, Delegation, DelegationsDaily, EntanglerSwap, IndexingWatermark, NftEvent,
+    PackEvent, Page, QueueCursor, QueueCursorRow, SaleEvent, SolTransfer, StakeAccountEvent,
+    TokenTransfer,
+};
 
 pub struct HttpsClient {
     client: Client,
+    table_prefix: String,
 }
 
 impl HttpsClient {
-    pub async fn new(db_creds: DSN) -> Result<Self, MainStorageError> {
-        let protocol = db_creds.driver;
-        let address = db_creds.address;
-
-        let mut client = if protocol == "https" {
-            Client::with_https_client().with_url(format!("{protocol}://{address}"))
-        } else {
-            Client::default().with_url(format!("{protocol}://{address}"))
-        };
-
-        if let Some(user_name) = db_creds.username {
-            client = client.with_user(user_name);
-        }
-        if let Some(password) = db_creds.password {
-            client = client.with_password(password);
-        }
-        if let Some(db) = db_creds.database {
-            client = client.with_database(db);
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            table_prefix: String::new(),
         }
+    }
 
-        Ok(Self { client })
+    /// Name of the `table` with the prefix of the storage
+    fn table(&self, table: &str) -> String {
+        format!("{}{}", self.table_prefix, table)
     }
+
+    /// Insert which ClickHouse drops when an insert of the same `token` is already stored
+    fn insert_deduplicated<T: Row>(&self, table: &str, token: String) -> Result<Insert<T>> {
+        Ok(self
+            .client
+            .clone()
+            .with_option("insert_deduplication_token", token)
+            .insert(&self.table(table))?)
+    }
+}
+
+/// Makes ClickHouse buffer the inserts and write them as larger parts. The insert still
+/// returns only after the data is flushed, so errors are not lost. The buffered inserts are
+/// deduplicated by their tokens as the direct ones
+pub fn with_async_insert(client: Client) -> Client {
+    client
+        .with_option("async_insert", "1")
+        .with_option("wait_for_async_insert", "1")
+        .with_option("async_insert_deduplicate", "1")
 }
 
 #[async_trait]
-impl MainStorage for HttpsClient {
-    async fn execute(&mut self, ddl: &str) -> Result<()> {
-        let query = self.client.query(ddl);
-        query.execute().await?;
-        Ok(())
+impl ClickhouseStorage for HttpsClient {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+        http::execute(&self.client, ddl).await
     }
 
-    async fn migration_exists(&mut self, version: &str) -> Result<bool> {
-        let mut cursor = self
-            .client
-            .query("SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = ?")
-            .bind(version)
-            .fetch::<u64>()?;
-
-        if let Some(count) = cursor.next().await? {
-            Ok(count > 0)
-        } else {
-            Ok(false)
-        }
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        http::migrations_table_exists(&self.client).await
+    }
+
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+        http::migration_exists(&self.client, version).await
     }
 
+    async fn migration_lock_holder(
+        &mut self,
+        _owner: &str,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        http::migration_lock_holder(&self.client, ttl_secs).await
+    }
+
+    async fn describe_table(
+        &mut self,
+        table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        Ok(Some(http::describe_table(&self.client, table).await?))
+    }
+
+    async fn table_engine(&mut self, table: &str) -> Result<Option<String>, StorageError> {
+        Ok(Some(http::table_engine(&self.client, table).await?))
+    }
+}
+
+#[async_trait]
+impl MainStorage for HttpsClient {
     async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
-        let mut insert = self.client.insert("instructions")?;
+        let token = deduplication_token("instructions", &instructions);
+        let mut insert = self.insert_deduplicated("instructions", token)?;
 
         for instruction in instructions {
-            insert
-                .write(&InstructionRow {
-                    program: instruction.program.clone(),
-                    tx_signature: instruction.tx_signature.clone(),
-                    tx_status: instruction.tx_status,
-                    slot: instruction.slot,
-                    block_time: instruction.block_time,
-                    instruction_idx: instruction.instruction_idx,
-                    inner_instructions_set: instruction.inner_instructions_set,
-                    transaction_instruction_idx: instruction.transaction_instruction_idx,
-                    instruction_name: instruction.instruction_name.clone(),
-                    account_0: instruction.accounts[0].clone(),
-                    account_1: instruction.accounts[1].clone(),
-                    account_2: instruction.accounts[2].clone(),
-                    account_3: instruction.accounts[3].clone(),
-                    account_4: instruction.accounts[4].clone(),
-                    account_5: instruction.accounts[5].clone(),
-                    account_6: instruction.accounts[6].clone(),
-                    account_7: instruction.accounts[7].clone(),
-                    account_8: instruction.accounts[8].clone(),
-                    account_9: instruction.accounts[9].clone(),
-                    account_10: instruction.accounts[10].clone(),
-                    account_11: instruction.accounts[11].clone(),
-                    account_12: instruction.accounts[12].clone(),
-                    account_13: instruction.accounts[13].clone(),
-                    account_14: instruction.accounts[14].clone(),
-                    account_15: instruction.accounts[15].clone(),
-                    account_16: instruction.accounts[16].clone(),
-                    account_17: instruction.accounts[17].clone(),
-                    account_18: instruction.accounts[18].clone(),
-                    account_19: instruction.accounts[19].clone(),
-                    account_20: instruction.accounts[20].clone(),
-                    account_21: instruction.accounts[21].clone(),
-                    account_22: instruction.accounts[22].clone(),
-                    account_23: instruction.accounts[23].clone(),
-                    account_24: instruction.accounts[24].clone(),
-                    account_25: instruction.accounts[25].clone(),
-                    account_26: instruction.accounts[26].clone(),
-                    account_27: instruction.accounts[27].clone(),
-                    account_28: instruction.accounts[28].clone(),
-                    account_29: instruction.accounts[29].clone(),
-                    account_30: instruction.accounts[30].clone(),
-                    account_31: instruction.accounts[31].clone(),
-                    account_32: instruction.accounts[32].clone(),
-                    account_33: instruction.accounts[33].clone(),
-                    account_34: instruction.accounts[34].clone(),
-                    data: instruction.data.clone(),
-                })
-                .await?;
+            insert.write(&InstructionRow::from(instruction)).await?;
         }
 
         insert.end().await?;
@@ -126,23 +108,12 @@ impl MainStorage for HttpsClient {
         &mut self,
         instruction_arguments: Vec<InstructionArgument>,
     ) -> Result<()> {
-        let mut insert = self.client.insert("instruction_arguments")?;
+        let token = deduplication_token("instruction_arguments", &instruction_arguments);
+        let mut insert = self.insert_deduplicated("instruction_arguments", token)?;
 
         for instruction_argument in instruction_arguments {
             insert
-                .write(&InstructionArgumentsRow {
-                    tx_signature: instruction_argument.tx_signature.clone(),
-                    instruction_idx: instruction_argument.instruction_idx,
-                    inner_instructions_set: instruction_argument.inner_instructions_set,
-                    program: instruction_argument.program,
-                    arg_idx: instruction_argument.arg_idx,
-                    arg_path: instruction_argument.arg_path,
-                    int_value: instruction_argument.int_value,
-                    unsigned_value: instruction_argument.unsigned_value,
-                    float_value: instruction_argument.float_value,
-                    string_value: instruction_argument.string_value,
-                    enum_value: None, // TODO: Why?
-                })
+                .write(&InstructionArgumentsRow::from(instruction_argument))
                 .await?;
         }
 
@@ -152,25 +123,11 @@ impl MainStorage for HttpsClient {
     }
 
     async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
-        let mut insert = self.client.insert("balances")?;
+        let token = deduplication_token("balances", &balances);
+        let mut insert = self.insert_deduplicated("balances", token)?;
 
         for balance in balances {
-            insert
-                .write(&BalancesRow {
-                    tx_signature: balance.tx_signature,
-                    account: balance.account,
-                    pre_balance: balance.pre_balance,
-                    post_balance: balance.post_balance,
-                    pre_token_balance_mint: balance.pre_token_balance_mint,
-                    pre_token_balance_owner: balance.pre_token_balance_owner,
-                    pre_token_balance_amount: balance.pre_token_balance_amount,
-                    pre_token_balance_program_id: balance.pre_token_balance_program_id,
-                    post_token_balance_mint: balance.post_token_balance_mint,
-                    post_token_balance_owner: balance.post_token_balance_owner,
-                    post_token_balance_amount: balance.post_token_balance_amount,
-                    post_token_balance_program_id: balance.post_token_balance_program_id,
-                })
-                .await?;
+            insert.write(&BalancesRow::from(balance)).await?;
         }
 
         insert.end().await?;
@@ -179,7 +136,8 @@ impl MainStorage for HttpsClient {
     }
 
     async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
-        let mut insert = self.client.insert("delegations")?;
+        let token = deduplication_token("delegations", &delegations);
+        let mut insert = self.insert_deduplicated("delegations", token)?;
 
         for delegation in delegations {
             insert.write(&delegation).await?;
@@ -191,7 +149,8 @@ impl MainStorage for HttpsClient {
     }
 
     async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
-        let mut insert = self.client.insert("undelegations")?;
+        let token = deduplication_token("undelegations", &undelegations);
+        let mut insert = self.insert_deduplicated("undelegations", token)?;
 
         for undelegation in undelegations {
             insert.write(&undelegation).await?;
@@ -202,20 +161,410 @@ impl MainStorage for HttpsClient {
         Ok(())
     }
 
+    async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        let mut insert = self.client.insert(&self.table("delegations_daily"))?;
+
+        for day in delegations_daily {
+            insert.write(&day).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_blocks_block(&mut self, blocks: Vec<Block>) -> Result<()> {
+        let token = deduplication_token("blocks", &blocks);
+        let mut insert = self.insert_deduplicated("blocks", token)?;
+
+        for block in blocks {
+            insert.write(&block).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_token_transfers_block(
+        &mut self,
+        token_transfers: Vec<TokenTransfer>,
+    ) -> Result<()> {
+        let token = deduplication_token("token_transfers", &token_transfers);
+        let mut insert = self.insert_deduplicated("token_transfers", token)?;
+
+        for token_transfer in token_transfers {
+            insert.write(&token_transfer).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        let token = deduplication_token("nft_events", &nft_events);
+        let mut insert = self.insert_deduplicated("nft_events", token)?;
+
+        for nft_event in nft_events {
+            insert.write(&nft_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        let token = deduplication_token("commission_changes", &commission_changes);
+        let mut insert = self.insert_deduplicated("commission_changes", token)?;
+
+        for commission_change in commission_changes {
+            insert.write(&commission_change).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        let token = deduplication_token("claim_events", &claim_events);
+        let mut insert = self.insert_deduplicated("claim_events", token)?;
+
+        for claim_event in claim_events {
+            insert.write(&claim_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_entangler_swaps_block(
+        &mut self,
+        entangler_swaps: Vec<EntanglerSwap>,
+    ) -> Result<()> {
+        let token = deduplication_token("entangler_swaps", &entangler_swaps);
+        let mut insert = self.insert_deduplicated("entangler_swaps", token)?;
+
+        for entangler_swap in entangler_swaps {
+            insert.write(&entangler_swap).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_anchor_events_block(&mut self, anchor_events: Vec<AnchorEvent>) -> Result<()> {
+        let token = deduplication_token("anchor_events", &anchor_events);
+        let mut insert = self.insert_deduplicated("anchor_events", token)?;
+
+        for anchor_event in anchor_events {
+            insert.write(&anchor_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        let token = deduplication_token("nft_sales", &sale_events);
+        let mut insert = self.insert_deduplicated("nft_sales", token)?;
+
+        for sale_event in sale_events {
+            insert.write(&sale_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        let token = deduplication_token("nft_pack_events", &pack_events);
+        let mut insert = self.insert_deduplicated("nft_pack_events", token)?;
+
+        for pack_event in pack_events {
+            insert.write(&pack_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        let token = deduplication_token("candy_mints", &candy_mints);
+        let mut insert = self.insert_deduplicated("candy_mints", token)?;
+
+        for candy_mint in candy_mints {
+            insert.write(&candy_mint).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        let token = deduplication_token("stake_account_events", &stake_account_events);
+        let mut insert = self.insert_deduplicated("stake_account_events", token)?;
+
+        for stake_account_event in stake_account_events {
+            insert.write(&stake_account_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        let token = deduplication_token("bid_events", &bid_events);
+        let mut insert = self.insert_deduplicated("bid_events", token)?;
+
+        for bid_event in bid_events {
+            insert.write(&bid_event).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_sol_transfers_block(&mut self, sol_transfers: Vec<SolTransfer>) -> Result<()> {
+        let token = deduplication_token("sol_transfers", &sol_transfers);
+        let mut insert = self.insert_deduplicated("sol_transfers", token)?;
+
+        for sol_transfer in sol_transfers {
+            insert.write(&sol_transfer).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "SELECT assumeNotNull(block_time) FROM {}
+                WHERE slot = ? AND block_time IS NOT NULL
+                LIMIT 1",
+                self.table("blocks")
+            ))
+            .bind(slot)
+            .fetch::<i64>()?;
+
+        Ok(cursor.next().await?)
+    }
+
+    async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "SELECT slot, block_time, stake_acc, vote_acc, tx_signature, amount, raw_instruction_idx
+                FROM {}
+                WHERE stake_acc = ? AND slot >= ? AND slot <= ?
+                ORDER BY slot, raw_instruction_idx",
+                self.table("delegations")
+            ))
+            .bind(stake_acc)
+            .bind(from_slot)
+            .bind(to_slot)
+            .fetch::<Delegation>()?;
+
+        let mut delegations = Vec::new();
+
+        while let Some(row) = cursor.next().await? {
+            delegations.push(row);
+        }
+
+        Ok(delegations)
+    }
+
+    async fn fetch_instructions_by_signature(
+        &mut self,
+        tx_signature: &str,
+        page: Page,
+    ) -> Result<Vec<InstructionRow>> {
+        let cursor = self
+            .client
+            .query(&format!(
+                "SELECT ?fields FROM {}
+                WHERE tx_signature = ?
+                ORDER BY ifNull(transaction_instruction_idx, instruction_idx),
+                    isNotNull(inner_instructions_set), instruction_idx
+                LIMIT ? OFFSET ?",
+                self.table("instructions")
+            ))
+            .bind(tx_signature)
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch::<InstructionRow>()?;
+
+        collect(cursor).await
+    }
+
+    async fn fetch_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+        page: Page,
+    ) -> Result<Vec<Delegation>> {
+        let cursor = self
+            .client
+            .query(&format!(
+                "SELECT ?fields FROM {}
+                WHERE stake_acc = ? AND slot >= ? AND slot <= ?
+                ORDER BY slot, raw_instruction_idx
+                LIMIT ? OFFSET ?",
+                self.table("delegations")
+            ))
+            .bind(stake_acc)
+            .bind(from_slot)
+            .bind(to_slot)
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch::<Delegation>()?;
+
+        collect(cursor).await
+    }
+
+    async fn fetch_balances_by_account(
+        &mut self,
+        account: &str,
+        page: Page,
+    ) -> Result<Vec<BalancesRow>> {
+        let cursor = self
+            .client
+            .query(&format!(
+                "SELECT ?fields FROM {}
+                WHERE account = ?
+                ORDER BY tx_signature, inner_instructions_set NULLS FIRST
+                LIMIT ? OFFSET ?",
+                self.table("balances")
+            ))
+            .bind(account)
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch::<BalancesRow>()?;
+
+        collect(cursor).await
+    }
+
+    async fn get_queue_cursor(&mut self, queue: &str) -> Result<Option<QueueCursor>> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "SELECT slot, signature FROM {}
+                WHERE queue = ?
+                ORDER BY slot DESC, signature DESC
+                LIMIT 1",
+                self.table("queue_cursors")
+            ))
+            .bind(queue)
+            .fetch::<QueueCursor>()?;
+
+        Ok(cursor.next().await?)
+    }
+
+    async fn store_queue_cursor(&mut self, queue: &str, cursor: QueueCursor) -> Result<()> {
+        let mut insert = self.client.insert(&self.table("queue_cursors"))?;
+
+        insert
+            .write(&QueueCursorRow {
+                queue: queue.to_string(),
+                slot: cursor.slot,
+                signature: cursor.signature,
+            })
+            .await?;
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "SELECT slot FROM {} ORDER BY slot DESC LIMIT 1",
+                self.table("indexing_watermark")
+            ))
+            .fetch::<IndexingWatermark>()?;
+
+        Ok(cursor.next().await?.map(|watermark| watermark.slot))
+    }
+
+    async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        let mut insert = self.client.insert(&self.table("indexing_watermark"))?;
+
+        insert.write(&IndexingWatermark { slot }).await?;
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        let mut insert = self.client.insert(&self.table("erroneous_rows"))?;
+
+        for erroneous_row in erroneous_rows {
+            insert.write(&erroneous_row).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        let mut insert = self.client.insert(&self.table("batches"))?;
+
+        for batch in batches {
+            insert.write(&batch).await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    fn set_table_prefix(&mut self, table_prefix: &str) {
+        self.table_prefix = table_prefix.to_string();
+    }
+
     async fn store_erroneous_transaction_block(
         &mut self,
         erroneous_transactions: Vec<ErroneousTransaction>,
     ) -> Result<()> {
-        let mut insert = self.client.insert("erroneous_transactions")?;
+        let token = deduplication_token("erroneous_transactions", &erroneous_transactions);
+        let mut insert = self.insert_deduplicated("erroneous_transactions", token)?;
 
         for erroneous_transaction in erroneous_transactions {
             insert
-                .write(&ErroneousTransactionRow {
-                    slot: erroneous_transaction.slot,
-                    transaction: erroneous_transaction.transaction,
-                    tx_signature: erroneous_transaction.tx_signature,
-                    cause: erroneous_transaction.cause,
-                })
+                .write(&ErroneousTransactionRow::from(erroneous_transaction))
                 .await?;
         }
 
@@ -225,16 +574,46 @@ impl MainStorage for HttpsClient {
     }
 }
 
-#[derive(Row, Serialize, Deserialize)]
+/// Rows the cursor of a query returns
+async fn collect<T: Row + for<'a> Deserialize<'a>>(mut cursor: RowCursor<T>) -> Result<Vec<T>> {
+    let mut rows = Vec::new();
+
+    while let Some(row) = cursor.next().await? {
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[derive(Row, Serialize, Deserialize, ClickhouseTable)]
+#[clickhouse_table(
+    name = "instructions",
+    engine = "MergeTree()",
+    partition_by = "intDiv(slot, 1000000)",
+    order_by = "(program, slot, tx_signature)",
+    settings = "index_granularity = 8192",
+    extra = "raw_instruction_idx UInt32 MATERIALIZED if(transaction_instruction_idx IS NULL, \
+        toUInt32(instruction_idx) * 512, \
+        toUInt32(transaction_instruction_idx) * 512 + instruction_idx + 1)",
+    extra = "inserted_at DateTime('UTC') DEFAULT now()",
+    extra = "INDEX slot_idx slot TYPE minmax GRANULARITY 8192",
+    extra = "INDEX instruction_name_idx instruction_name TYPE minmax GRANULARITY 8192",
+    extra = "INDEX account_1_idx account_1 TYPE minmax GRANULARITY 8192",
+    extra = "INDEX tx_signature_idx tx_signature TYPE minmax GRANULARITY 8192"
+)]
 pub struct InstructionRow {
     pub program: String,
     pub tx_signature: String,
+    #[clickhouse_table(column_type = "Enum('Failed' = 0, 'Success' = 1, 'NotExecuted' = 3)")]
     pub tx_status: TxStatus,
+    pub failed_instruction_idx: Option<u8>,
     pub slot: u64,
-    pub block_time: u64,
+    pub block_time: Option<u64>,
     pub instruction_idx: u8,
     pub inner_instructions_set: Option<u8>,
     pub transaction_instruction_idx: Option<u8>,
+    pub parent_program: Option<String>,
+    pub stack_height: Option<u8>,
     pub instruction_name: String,
     pub account_0: Option<String>,
     pub account_1: Option<String>,
@@ -271,6 +650,11 @@ pub struct InstructionRow {
     pub account_32: Option<String>,
     pub account_33: Option<String>,
     pub account_34: Option<String>,
+    #[clickhouse_table(default = "''")]
+    pub account_roles: String,
+    pub tx_version: Option<u8>,
+    #[clickhouse_table(default = "0")]
+    pub num_signatures: u8,
     pub data: String,
 }
 
@@ -283,7 +667,13 @@ pub struct MetadataRow {
     pub block_height: Option<u64>,
 }
 
-#[derive(Row, Serialize, Deserialize)]
+#[derive(Row, Serialize, Deserialize, ClickhouseTable)]
+#[clickhouse_table(
+    name = "balances",
+    engine = "MergeTree()",
+    order_by = "(tx_signature, account)",
+    settings = "index_granularity = 8192"
+)]
 pub struct BalancesRow {
     pub tx_signature: String,
     pub account: String,
@@ -297,9 +687,21 @@ pub struct BalancesRow {
     pub post_token_balance_owner: Option<String>,
     pub post_token_balance_amount: Option<f64>,
     pub post_token_balance_program_id: Option<String>,
+    pub inner_instructions_set: Option<u8>,
+    pub attributed_amount: Option<u64>,
 }
 
-#[derive(Row, Serialize, Deserialize)]
+#[derive(Row, Serialize, Deserialize, ClickhouseTable)]
+#[clickhouse_table(
+    name = "instruction_arguments",
+    engine = "MergeTree()",
+    order_by = "(tx_signature, program)",
+    settings = "index_granularity = 8192",
+    extra = "inserted_at DateTime('UTC') DEFAULT now()",
+    extra = "INDEX arg_path_idx arg_path TYPE minmax GRANULARITY 8192",
+    extra = "INDEX inner_instructions_set_idx inner_instructions_set TYPE minmax GRANULARITY 8192",
+    extra = "INDEX instruction_idx_idx instruction_idx TYPE minmax GRANULARITY 8192"
+)]
 pub struct InstructionArgumentsRow {
     pub tx_signature: String,
     pub instruction_idx: u8,
@@ -312,12 +714,132 @@ pub struct InstructionArgumentsRow {
     pub float_value: Option<f64>,
     pub string_value: Option<String>,
     pub enum_value: Option<String>,
+    #[clickhouse_table(default = "0")]
+    pub overflow: u8,
 }
 
-#[derive(Row, Serialize, Deserialize)]
+#[derive(Row, Serialize, Deserialize, ClickhouseTable)]
+#[clickhouse_table(
+    name = "erroneous_transactions",
+    engine = "MergeTree()",
+    order_by = "(tx_signature, slot)",
+    settings = "index_granularity = 8192"
+)]
 pub struct ErroneousTransactionRow {
     pub slot: u64,
     pub transaction: String,
+    #[clickhouse_table(default = "'json'")]
+    pub encoding: String,
     pub tx_signature: String,
     pub cause: String,
 }
+
+impl From<Instruction> for InstructionRow {
+    fn from(instruction: Instruction) -> Self {
+        Self {
+            program: instruction.program,
+            tx_signature: instruction.tx_signature,
+            tx_status: instruction.tx_status,
+            failed_instruction_idx: instruction.failed_instruction_idx,
+            slot: instruction.slot,
+            block_time: instruction.block_time,
+            instruction_idx: instruction.instruction_idx,
+            inner_instructions_set: instruction.inner_instructions_set,
+            transaction_instruction_idx: instruction.transaction_instruction_idx,
+            parent_program: instruction.parent_program,
+            stack_height: instruction.stack_height,
+            instruction_name: instruction.instruction_name,
+            account_0: instruction.account(0),
+            account_1: instruction.account(1),
+            account_2: instruction.account(2),
+            account_3: instruction.account(3),
+            account_4: instruction.account(4),
+            account_5: instruction.account(5),
+            account_6: instruction.account(6),
+            account_7: instruction.account(7),
+            account_8: instruction.account(8),
+            account_9: instruction.account(9),
+            account_10: instruction.account(10),
+            account_11: instruction.account(11),
+            account_12: instruction.account(12),
+            account_13: instruction.account(13),
+            account_14: instruction.account(14),
+            account_15: instruction.account(15),
+            account_16: instruction.account(16),
+            account_17: instruction.account(17),
+            account_18: instruction.account(18),
+            account_19: instruction.account(19),
+            account_20: instruction.account(20),
+            account_21: instruction.account(21),
+            account_22: instruction.account(22),
+            account_23: instruction.account(23),
+            account_24: instruction.account(24),
+            account_25: instruction.account(25),
+            account_26: instruction.account(26),
+            account_27: instruction.account(27),
+            account_28: instruction.account(28),
+            account_29: instruction.account(29),
+            account_30: instruction.account(30),
+            account_31: instruction.account(31),
+            account_32: instruction.account(32),
+            account_33: instruction.account(33),
+            account_34: instruction.account(34),
+            account_roles: instruction.account_roles,
+            tx_version: instruction.tx_version,
+            num_signatures: instruction.num_signatures,
+            data: instruction.data,
+        }
+    }
+}
+
+impl From<InstructionArgument> for InstructionArgumentsRow {
+    fn from(instruction_argument: InstructionArgument) -> Self {
+        Self {
+            tx_signature: instruction_argument.tx_signature,
+            instruction_idx: instruction_argument.instruction_idx,
+            inner_instructions_set: instruction_argument.inner_instructions_set,
+            program: instruction_argument.program,
+            arg_idx: instruction_argument.arg_idx,
+            arg_path: instruction_argument.arg_path,
+            int_value: instruction_argument.int_value,
+            unsigned_value: instruction_argument.unsigned_value,
+            float_value: instruction_argument.float_value,
+            string_value: instruction_argument.string_value,
+            enum_value: None, // TODO: Why?
+            overflow: instruction_argument.overflow.into(),
+        }
+    }
+}
+
+impl From<Balance> for BalancesRow {
+    fn from(balance: Balance) -> Self {
+        Self {
+            tx_signature: balance.tx_signature,
+            account: balance.account,
+            pre_balance: balance.pre_balance,
+            post_balance: balance.post_balance,
+            pre_token_balance_mint: balance.pre_token_balance_mint,
+            pre_token_balance_owner: balance.pre_token_balance_owner,
+            pre_token_balance_amount: balance.pre_token_balance_amount,
+            pre_token_balance_program_id: balance.pre_token_balance_program_id,
+            post_token_balance_mint: balance.post_token_balance_mint,
+            post_token_balance_owner: balance.post_token_balance_owner,
+            post_token_balance_amount: balance.post_token_balance_amount,
+            post_token_balance_program_id: balance.post_token_balance_program_id,
+            inner_instructions_set: balance.inner_instructions_set,
+            attributed_amount: balance.attributed_amount,
+        }
+    }
+}
+
+impl From<ErroneousTransaction> for ErroneousTransactionRow {
+    fn from(erroneous_transaction: ErroneousTransaction) -> Self {
+        Self {
+            slot: erroneous_transaction.slot,
+            transaction: erroneous_transaction.transaction,
+            encoding: erroneous_transaction.encoding,
+            tx_signature: erroneous_transaction.tx_signature,
+            cause: erroneous_transaction.cause,
+        }
+    }
+}
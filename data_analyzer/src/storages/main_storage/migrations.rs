@@ -1,26 +1,30 @@
-use anyhow::Result;
+use clickhouse_storage::ClickhouseTable;
 
-use super::MainStorage;
+use super::https_client::{
+    BalancesRow, ErroneousTransactionRow, InstructionArgumentsRow, InstructionRow,
+};
+use super::Delegation;
 
-pub struct Migrations {}
+// The tables of the rows deriving `ClickhouseTable` are created by their generated scripts, the
+// later `ALTER TABLE` migrations written for the older tables leave them as they are
 
 #[cfg(feature = "on_ch_cluster")]
-pub const SCRIPTS_UP: [(&str, &str); 7] = [
+pub const SCRIPTS_UP: [(&str, &str); 38] = [
     (
         "00000000000000_initial_setup",
-        include_str!("./migrations/on_cluster/00000000000000_initial_setup/up.sql"),
+        InstructionRow::CREATE_TABLE_ON_CLUSTER,
     ),
     (
         "00000000000001_initial_setup",
-        include_str!("./migrations/on_cluster/00000000000001_initial_setup/up.sql"),
+        BalancesRow::CREATE_TABLE_ON_CLUSTER,
     ),
     (
         "00000000000002_initial_setup",
-        include_str!("./migrations/on_cluster/00000000000002_initial_setup/up.sql"),
+        InstructionArgumentsRow::CREATE_TABLE_ON_CLUSTER,
     ),
     (
         "00000000000003_initial_setup",
-        include_str!("./migrations/on_cluster/00000000000003_initial_setup/up.sql"),
+        ErroneousTransactionRow::CREATE_TABLE_ON_CLUSTER,
     ),
     (
         "00000000000004_initial_setup",
@@ -28,122 +32,616 @@ pub const SCRIPTS_UP: [(&str, &str); 7] = [
     ),
     (
         "00000000000005_delegations_setup",
-        include_str!("./migrations/on_cluster/00000000000005_delegations_setup/up.sql"),
+        Delegation::CREATE_TABLE_ON_CLUSTER,
     ),
     (
         "00000000000006_undelegations_setup",
         include_str!("./migrations/on_cluster/00000000000006_undelegations_setup/up.sql"),
     ),
+    (
+        "00000000000007_blocks_setup",
+        include_str!("./migrations/on_cluster/00000000000007_blocks_setup/up.sql"),
+    ),
+    (
+        "00000000000008_token_transfers_setup",
+        include_str!("./migrations/on_cluster/00000000000008_token_transfers_setup/up.sql"),
+    ),
+    (
+        "00000000000009_failed_instruction_setup",
+        include_str!("./migrations/on_cluster/00000000000009_failed_instruction_setup/up.sql"),
+    ),
+    (
+        "00000000000010_cpi_setup",
+        include_str!("./migrations/on_cluster/00000000000010_cpi_setup/up.sql"),
+    ),
+    (
+        "00000000000011_erroneous_encoding_setup",
+        include_str!("./migrations/on_cluster/00000000000011_erroneous_encoding_setup/up.sql"),
+    ),
+    (
+        "00000000000012_nft_events_setup",
+        include_str!("./migrations/on_cluster/00000000000012_nft_events_setup/up.sql"),
+    ),
+    (
+        "00000000000013_commission_changes_setup",
+        include_str!("./migrations/on_cluster/00000000000013_commission_changes_setup/up.sql"),
+    ),
+    (
+        "00000000000014_argument_overflow_setup",
+        include_str!("./migrations/on_cluster/00000000000014_argument_overflow_setup/up.sql"),
+    ),
+    (
+        "00000000000015_account_roles_setup",
+        include_str!("./migrations/on_cluster/00000000000015_account_roles_setup/up.sql"),
+    ),
+    (
+        "00000000000016_claim_events_setup",
+        include_str!("./migrations/on_cluster/00000000000016_claim_events_setup/up.sql"),
+    ),
+    (
+        "00000000000017_transaction_version_setup",
+        include_str!("./migrations/on_cluster/00000000000017_transaction_version_setup/up.sql"),
+    ),
+    (
+        "00000000000018_delegations_daily_setup",
+        include_str!("./migrations/on_cluster/00000000000018_delegations_daily_setup/up.sql"),
+    ),
+    (
+        "00000000000019_entangler_swaps_setup",
+        include_str!("./migrations/on_cluster/00000000000019_entangler_swaps_setup/up.sql"),
+    ),
+    (
+        "00000000000020_anchor_events_setup",
+        include_str!("./migrations/on_cluster/00000000000020_anchor_events_setup/up.sql"),
+    ),
+    (
+        "00000000000021_nft_sales_setup",
+        include_str!("./migrations/on_cluster/00000000000021_nft_sales_setup/up.sql"),
+    ),
+    (
+        "00000000000022_instructions_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000022_instructions_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000023_delegations_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000023_delegations_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000024_undelegations_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000024_undelegations_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000025_nft_pack_events_setup",
+        include_str!("./migrations/on_cluster/00000000000025_nft_pack_events_setup/up.sql"),
+    ),
+    (
+        "00000000000026_instructions_block_time_setup",
+        include_str!("./migrations/on_cluster/00000000000026_instructions_block_time_setup/up.sql"),
+    ),
+    (
+        "00000000000027_candy_mints_setup",
+        include_str!("./migrations/on_cluster/00000000000027_candy_mints_setup/up.sql"),
+    ),
+    (
+        "00000000000028_balances_attribution_setup",
+        include_str!("./migrations/on_cluster/00000000000028_balances_attribution_setup/up.sql"),
+    ),
+    (
+        "00000000000029_instructions_inserted_at_setup",
+        include_str!(
+            "./migrations/on_cluster/00000000000029_instructions_inserted_at_setup/up.sql"
+        ),
+    ),
+    (
+        "00000000000030_instruction_arguments_inserted_at_setup",
+        include_str!(
+            "./migrations/on_cluster/00000000000030_instruction_arguments_inserted_at_setup/up.sql"
+        ),
+    ),
+    (
+        "00000000000031_queue_cursors_setup",
+        include_str!("./migrations/on_cluster/00000000000031_queue_cursors_setup/up.sql"),
+    ),
+    (
+        "00000000000032_erroneous_rows_setup",
+        include_str!("./migrations/on_cluster/00000000000032_erroneous_rows_setup/up.sql"),
+    ),
+    (
+        "00000000000033_stake_account_events_setup",
+        include_str!("./migrations/on_cluster/00000000000033_stake_account_events_setup/up.sql"),
+    ),
+    (
+        "00000000000034_batches_setup",
+        include_str!("./migrations/on_cluster/00000000000034_batches_setup/up.sql"),
+    ),
+    (
+        "00000000000035_bid_events_setup",
+        include_str!("./migrations/on_cluster/00000000000035_bid_events_setup/up.sql"),
+    ),
+    (
+        "00000000000036_sol_transfers_setup",
+        include_str!("./migrations/on_cluster/00000000000036_sol_transfers_setup/up.sql"),
+    ),
+    (
+        "00000000000037_indexing_watermark_setup",
+        include_str!("./migrations/on_cluster/00000000000037_indexing_watermark_setup/up.sql"),
+    ),
 ];
 
 #[cfg(not(feature = "on_ch_cluster"))]
-pub const SCRIPTS_UP: [(&str, &str); 7] = [
+pub const SCRIPTS_UP: [(&str, &str); 38] = [
+    ("00000000000000_initial_setup", InstructionRow::CREATE_TABLE),
+    ("00000000000001_initial_setup", BalancesRow::CREATE_TABLE),
+    (
+        "00000000000002_initial_setup",
+        InstructionArgumentsRow::CREATE_TABLE,
+    ),
+    (
+        "00000000000003_initial_setup",
+        ErroneousTransactionRow::CREATE_TABLE,
+    ),
+    (
+        "00000000000004_initial_setup",
+        include_str!("./migrations/single/00000000000004_initial_setup/up.sql"),
+    ),
+    ("00000000000005_delegations_setup", Delegation::CREATE_TABLE),
+    (
+        "00000000000006_undelegations_setup",
+        include_str!("./migrations/single/00000000000006_undelegations_setup/up.sql"),
+    ),
+    (
+        "00000000000007_blocks_setup",
+        include_str!("./migrations/single/00000000000007_blocks_setup/up.sql"),
+    ),
+    (
+        "00000000000008_token_transfers_setup",
+        include_str!("./migrations/single/00000000000008_token_transfers_setup/up.sql"),
+    ),
+    (
+        "00000000000009_failed_instruction_setup",
+        include_str!("./migrations/single/00000000000009_failed_instruction_setup/up.sql"),
+    ),
+    (
+        "00000000000010_cpi_setup",
+        include_str!("./migrations/single/00000000000010_cpi_setup/up.sql"),
+    ),
+    (
+        "00000000000011_erroneous_encoding_setup",
+        include_str!("./migrations/single/00000000000011_erroneous_encoding_setup/up.sql"),
+    ),
+    (
+        "00000000000012_nft_events_setup",
+        include_str!("./migrations/single/00000000000012_nft_events_setup/up.sql"),
+    ),
+    (
+        "00000000000013_commission_changes_setup",
+        include_str!("./migrations/single/00000000000013_commission_changes_setup/up.sql"),
+    ),
+    (
+        "00000000000014_argument_overflow_setup",
+        include_str!("./migrations/single/00000000000014_argument_overflow_setup/up.sql"),
+    ),
+    (
+        "00000000000015_account_roles_setup",
+        include_str!("./migrations/single/00000000000015_account_roles_setup/up.sql"),
+    ),
+    (
+        "00000000000016_claim_events_setup",
+        include_str!("./migrations/single/00000000000016_claim_events_setup/up.sql"),
+    ),
+    (
+        "00000000000017_transaction_version_setup",
+        include_str!("./migrations/single/00000000000017_transaction_version_setup/up.sql"),
+    ),
+    (
+        "00000000000018_delegations_daily_setup",
+        include_str!("./migrations/single/00000000000018_delegations_daily_setup/up.sql"),
+    ),
+    (
+        "00000000000019_entangler_swaps_setup",
+        include_str!("./migrations/single/00000000000019_entangler_swaps_setup/up.sql"),
+    ),
+    (
+        "00000000000020_anchor_events_setup",
+        include_str!("./migrations/single/00000000000020_anchor_events_setup/up.sql"),
+    ),
+    (
+        "00000000000021_nft_sales_setup",
+        include_str!("./migrations/single/00000000000021_nft_sales_setup/up.sql"),
+    ),
+    (
+        "00000000000022_instructions_raw_idx_setup",
+        include_str!("./migrations/single/00000000000022_instructions_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000023_delegations_raw_idx_setup",
+        include_str!("./migrations/single/00000000000023_delegations_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000024_undelegations_raw_idx_setup",
+        include_str!("./migrations/single/00000000000024_undelegations_raw_idx_setup/up.sql"),
+    ),
+    (
+        "00000000000025_nft_pack_events_setup",
+        include_str!("./migrations/single/00000000000025_nft_pack_events_setup/up.sql"),
+    ),
+    (
+        "00000000000026_instructions_block_time_setup",
+        include_str!("./migrations/single/00000000000026_instructions_block_time_setup/up.sql"),
+    ),
+    (
+        "00000000000027_candy_mints_setup",
+        include_str!("./migrations/single/00000000000027_candy_mints_setup/up.sql"),
+    ),
+    (
+        "00000000000028_balances_attribution_setup",
+        include_str!("./migrations/single/00000000000028_balances_attribution_setup/up.sql"),
+    ),
+    (
+        "00000000000029_instructions_inserted_at_setup",
+        include_str!("./migrations/single/00000000000029_instructions_inserted_at_setup/up.sql"),
+    ),
+    (
+        "00000000000030_instruction_arguments_inserted_at_setup",
+        include_str!(
+            "./migrations/single/00000000000030_instruction_arguments_inserted_at_setup/up.sql"
+        ),
+    ),
+    (
+        "00000000000031_queue_cursors_setup",
+        include_str!("./migrations/single/00000000000031_queue_cursors_setup/up.sql"),
+    ),
+    (
+        "00000000000032_erroneous_rows_setup",
+        include_str!("./migrations/single/00000000000032_erroneous_rows_setup/up.sql"),
+    ),
+    (
+        "00000000000033_stake_account_events_setup",
+        include_str!("./migrations/single/00000000000033_stake_account_events_setup/up.sql"),
+    ),
+    (
+        "00000000000034_batches_setup",
+        include_str!("./migrations/single/00000000000034_batches_setup/up.sql"),
+    ),
+    (
+        "00000000000035_bid_events_setup",
+        include_str!("./migrations/single/00000000000035_bid_events_setup/up.sql"),
+    ),
+    (
+        "00000000000036_sol_transfers_setup",
+        include_str!("./migrations/single/00000000000036_sol_transfers_setup/up.sql"),
+    ),
+    (
+        "00000000000037_indexing_watermark_setup",
+        include_str!("./migrations/single/00000000000037_indexing_watermark_setup/up.sql"),
+    ),
+];
+
+#[cfg(feature = "on_ch_cluster")]
+pub const SCRIPTS_DOWN: [(&str, &str); 38] = [
     (
         "00000000000000_initial_setup",
-        include_str!("./migrations/single/00000000000000_initial_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000000_initial_setup/down.sql"),
     ),
     (
         "00000000000001_initial_setup",
-        include_str!("./migrations/single/00000000000001_initial_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000001_initial_setup/down.sql"),
     ),
     (
         "00000000000002_initial_setup",
-        include_str!("./migrations/single/00000000000002_initial_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000002_initial_setup/down.sql"),
     ),
     (
         "00000000000003_initial_setup",
-        include_str!("./migrations/single/00000000000003_initial_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000003_initial_setup/down.sql"),
     ),
     (
         "00000000000004_initial_setup",
-        include_str!("./migrations/single/00000000000004_initial_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000004_initial_setup/down.sql"),
     ),
     (
         "00000000000005_delegations_setup",
-        include_str!("./migrations/single/00000000000005_delegations_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000005_delegations_setup/down.sql"),
     ),
     (
         "00000000000006_undelegations_setup",
-        include_str!("./migrations/single/00000000000006_undelegations_setup/up.sql"),
+        include_str!("./migrations/on_cluster/00000000000006_undelegations_setup/down.sql"),
+    ),
+    (
+        "00000000000007_blocks_setup",
+        include_str!("./migrations/on_cluster/00000000000007_blocks_setup/down.sql"),
+    ),
+    (
+        "00000000000008_token_transfers_setup",
+        include_str!("./migrations/on_cluster/00000000000008_token_transfers_setup/down.sql"),
+    ),
+    (
+        "00000000000009_failed_instruction_setup",
+        include_str!("./migrations/on_cluster/00000000000009_failed_instruction_setup/down.sql"),
+    ),
+    (
+        "00000000000010_cpi_setup",
+        include_str!("./migrations/on_cluster/00000000000010_cpi_setup/down.sql"),
+    ),
+    (
+        "00000000000011_erroneous_encoding_setup",
+        include_str!("./migrations/on_cluster/00000000000011_erroneous_encoding_setup/down.sql"),
+    ),
+    (
+        "00000000000012_nft_events_setup",
+        include_str!("./migrations/on_cluster/00000000000012_nft_events_setup/down.sql"),
+    ),
+    (
+        "00000000000013_commission_changes_setup",
+        include_str!("./migrations/on_cluster/00000000000013_commission_changes_setup/down.sql"),
+    ),
+    (
+        "00000000000014_argument_overflow_setup",
+        include_str!("./migrations/on_cluster/00000000000014_argument_overflow_setup/down.sql"),
+    ),
+    (
+        "00000000000015_account_roles_setup",
+        include_str!("./migrations/on_cluster/00000000000015_account_roles_setup/down.sql"),
+    ),
+    (
+        "00000000000016_claim_events_setup",
+        include_str!("./migrations/on_cluster/00000000000016_claim_events_setup/down.sql"),
+    ),
+    (
+        "00000000000017_transaction_version_setup",
+        include_str!("./migrations/on_cluster/00000000000017_transaction_version_setup/down.sql"),
+    ),
+    (
+        "00000000000018_delegations_daily_setup",
+        include_str!("./migrations/on_cluster/00000000000018_delegations_daily_setup/down.sql"),
+    ),
+    (
+        "00000000000019_entangler_swaps_setup",
+        include_str!("./migrations/on_cluster/00000000000019_entangler_swaps_setup/down.sql"),
+    ),
+    (
+        "00000000000020_anchor_events_setup",
+        include_str!("./migrations/on_cluster/00000000000020_anchor_events_setup/down.sql"),
+    ),
+    (
+        "00000000000021_nft_sales_setup",
+        include_str!("./migrations/on_cluster/00000000000021_nft_sales_setup/down.sql"),
+    ),
+    (
+        "00000000000022_instructions_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000022_instructions_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000023_delegations_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000023_delegations_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000024_undelegations_raw_idx_setup",
+        include_str!("./migrations/on_cluster/00000000000024_undelegations_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000025_nft_pack_events_setup",
+        include_str!("./migrations/on_cluster/00000000000025_nft_pack_events_setup/down.sql"),
+    ),
+    (
+        "00000000000026_instructions_block_time_setup",
+        include_str!(
+            "./migrations/on_cluster/00000000000026_instructions_block_time_setup/down.sql"
+        ),
+    ),
+    (
+        "00000000000027_candy_mints_setup",
+        include_str!("./migrations/on_cluster/00000000000027_candy_mints_setup/down.sql"),
+    ),
+    (
+        "00000000000028_balances_attribution_setup",
+        include_str!("./migrations/on_cluster/00000000000028_balances_attribution_setup/down.sql"),
+    ),
+    (
+        "00000000000029_instructions_inserted_at_setup",
+        include_str!("./migrations/on_cluster/00000000000029_instructions_inserted_at_setup/down.sql"),
+    ),
+    (
+        "00000000000030_instruction_arguments_inserted_at_setup",
+        include_str!("./migrations/on_cluster/00000000000030_instruction_arguments_inserted_at_setup/down.sql"),
+    ),
+    (
+        "00000000000031_queue_cursors_setup",
+        include_str!("./migrations/on_cluster/00000000000031_queue_cursors_setup/down.sql"),
+    ),
+    (
+        "00000000000032_erroneous_rows_setup",
+        include_str!("./migrations/on_cluster/00000000000032_erroneous_rows_setup/down.sql"),
+    ),
+    (
+        "00000000000033_stake_account_events_setup",
+        include_str!("./migrations/on_cluster/00000000000033_stake_account_events_setup/down.sql"),
+    ),
+    (
+        "00000000000034_batches_setup",
+        include_str!("./migrations/on_cluster/00000000000034_batches_setup/down.sql"),
+    ),
+    (
+        "00000000000035_bid_events_setup",
+        include_str!("./migrations/on_cluster/00000000000035_bid_events_setup/down.sql"),
+    ),
+    (
+        "00000000000036_sol_transfers_setup",
+        include_str!("./migrations/on_cluster/00000000000036_sol_transfers_setup/down.sql"),
+    ),
+    (
+        "00000000000037_indexing_watermark_setup",
+        include_str!("./migrations/on_cluster/00000000000037_indexing_watermark_setup/down.sql"),
     ),
 ];
 
-impl Migrations {
-    pub fn new() -> Self {
-        Self {}
-    }
-
-    async fn create_table(&self, storage: &mut Box<dyn MainStorage>) -> Result<()> {
-        log::debug!("creating migration table __diesel_schema_migrations",);
-
-        #[cfg(feature = "on_ch_cluster")]
-        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations ON CLUSTER '{cluster}'
-            (
-                version String,
-                run_on DateTime('UTC')
-            ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
-            ORDER BY (version)
-            SETTINGS index_granularity = 8192"#;
-
-        #[cfg(not(feature = "on_ch_cluster"))]
-        let query = r#"CREATE TABLE IF NOT EXISTS __schema_migrations
-            (
-                version String,
-                run_on DateTime('UTC')
-            ) ENGINE = MergeTree()
-            ORDER BY (version)
-            SETTINGS index_granularity = 8192"#;
-
-        storage.execute(query).await
-    }
-
-    async fn exists(&self, storage: &mut Box<dyn MainStorage>, version: &str) -> Result<bool> {
-        log::trace!("check if migration {} exists", version);
-        storage.migration_exists(version).await
-    }
-
-    async fn execute(&self, storage: &mut Box<dyn MainStorage>, script: &str) -> Result<()> {
-        storage.execute(script).await?;
-        Ok(())
-    }
+#[cfg(not(feature = "on_ch_cluster"))]
+pub const SCRIPTS_DOWN: [(&str, &str); 38] = [
+    (
+        "00000000000000_initial_setup",
+        include_str!("./migrations/single/00000000000000_initial_setup/down.sql"),
+    ),
+    (
+        "00000000000001_initial_setup",
+        include_str!("./migrations/single/00000000000001_initial_setup/down.sql"),
+    ),
+    (
+        "00000000000002_initial_setup",
+        include_str!("./migrations/single/00000000000002_initial_setup/down.sql"),
+    ),
+    (
+        "00000000000003_initial_setup",
+        include_str!("./migrations/single/00000000000003_initial_setup/down.sql"),
+    ),
+    (
+        "00000000000004_initial_setup",
+        include_str!("./migrations/single/00000000000004_initial_setup/down.sql"),
+    ),
+    (
+        "00000000000005_delegations_setup",
+        include_str!("./migrations/single/00000000000005_delegations_setup/down.sql"),
+    ),
+    (
+        "00000000000006_undelegations_setup",
+        include_str!("./migrations/single/00000000000006_undelegations_setup/down.sql"),
+    ),
+    (
+        "00000000000007_blocks_setup",
+        include_str!("./migrations/single/00000000000007_blocks_setup/down.sql"),
+    ),
+    (
+        "00000000000008_token_transfers_setup",
+        include_str!("./migrations/single/00000000000008_token_transfers_setup/down.sql"),
+    ),
+    (
+        "00000000000009_failed_instruction_setup",
+        include_str!("./migrations/single/00000000000009_failed_instruction_setup/down.sql"),
+    ),
+    (
+        "00000000000010_cpi_setup",
+        include_str!("./migrations/single/00000000000010_cpi_setup/down.sql"),
+    ),
+    (
+        "00000000000011_erroneous_encoding_setup",
+        include_str!("./migrations/single/00000000000011_erroneous_encoding_setup/down.sql"),
+    ),
+    (
+        "00000000000012_nft_events_setup",
+        include_str!("./migrations/single/00000000000012_nft_events_setup/down.sql"),
+    ),
+    (
+        "00000000000013_commission_changes_setup",
+        include_str!("./migrations/single/00000000000013_commission_changes_setup/down.sql"),
+    ),
+    (
+        "00000000000014_argument_overflow_setup",
+        include_str!("./migrations/single/00000000000014_argument_overflow_setup/down.sql"),
+    ),
+    (
+        "00000000000015_account_roles_setup",
+        include_str!("./migrations/single/00000000000015_account_roles_setup/down.sql"),
+    ),
+    (
+        "00000000000016_claim_events_setup",
+        include_str!("./migrations/single/00000000000016_claim_events_setup/down.sql"),
+    ),
+    (
+        "00000000000017_transaction_version_setup",
+        include_str!("./migrations/single/00000000000017_transaction_version_setup/down.sql"),
+    ),
+    (
+        "00000000000018_delegations_daily_setup",
+        include_str!("./migrations/single/00000000000018_delegations_daily_setup/down.sql"),
+    ),
+    (
+        "00000000000019_entangler_swaps_setup",
+        include_str!("./migrations/single/00000000000019_entangler_swaps_setup/down.sql"),
+    ),
+    (
+        "00000000000020_anchor_events_setup",
+        include_str!("./migrations/single/00000000000020_anchor_events_setup/down.sql"),
+    ),
+    (
+        "00000000000021_nft_sales_setup",
+        include_str!("./migrations/single/00000000000021_nft_sales_setup/down.sql"),
+    ),
+    (
+        "00000000000022_instructions_raw_idx_setup",
+        include_str!("./migrations/single/00000000000022_instructions_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000023_delegations_raw_idx_setup",
+        include_str!("./migrations/single/00000000000023_delegations_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000024_undelegations_raw_idx_setup",
+        include_str!("./migrations/single/00000000000024_undelegations_raw_idx_setup/down.sql"),
+    ),
+    (
+        "00000000000025_nft_pack_events_setup",
+        include_str!("./migrations/single/00000000000025_nft_pack_events_setup/down.sql"),
+    ),
+    (
+        "00000000000026_instructions_block_time_setup",
+        include_str!("./migrations/single/00000000000026_instructions_block_time_setup/down.sql"),
+    ),
+    (
+        "00000000000027_candy_mints_setup",
+        include_str!("./migrations/single/00000000000027_candy_mints_setup/down.sql"),
+    ),
+    (
+        "00000000000028_balances_attribution_setup",
+        include_str!("./migrations/single/00000000000028_balances_attribution_setup/down.sql"),
+    ),
+    (
+        "00000000000029_instructions_inserted_at_setup",
+        include_str!("./migrations/single/00000000000029_instructions_inserted_at_setup/down.sql"),
+    ),
+    (
+        "00000000000030_instruction_arguments_inserted_at_setup",
+        include_str!(
+            "./migrations/single/00000000000030_instruction_arguments_inserted_at_setup/down.sql"
+        ),
+    ),
+    (
+        "00000000000031_queue_cursors_setup",
+        include_str!("./migrations/single/00000000000031_queue_cursors_setup/down.sql"),
+    ),
+    (
+        "00000000000032_erroneous_rows_setup",
+        include_str!("./migrations/single/00000000000032_erroneous_rows_setup/down.sql"),
+    ),
+    (
+        "00000000000033_stake_account_events_setup",
+        include_str!("./migrations/single/00000000000033_stake_account_events_setup/down.sql"),
+    ),
+    (
+        "00000000000034_batches_setup",
+        include_str!("./migrations/single/00000000000034_batches_setup/down.sql"),
+    ),
+    (
+        "00000000000035_bid_events_setup",
+        include_str!("./migrations/single/00000000000035_bid_events_setup/down.sql"),
+    ),
+    (
+        "00000000000036_sol_transfers_setup",
+        include_str!("./migrations/single/00000000000036_sol_transfers_setup/down.sql"),
+    ),
+    (
+        "00000000000037_indexing_watermark_setup",
+        include_str!("./migrations/single/00000000000037_indexing_watermark_setup/down.sql"),
+    ),
+];
 
-    async fn insert_migration(
-        &self,
-        storage: &mut Box<dyn MainStorage>,
-        version: &str,
-    ) -> Result<()> {
-        let ddl = &format!(
-            "INSERT INTO __schema_migrations (version, run_on) VALUES ('{}', now())",
-            version
-        );
-        storage.execute(ddl).await?;
-        Ok(())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn parse_name(&self, name: &str) -> String {
-        let v: Vec<&str> = name.split('_').collect();
-        if !v.is_empty() {
-            v[0].replace('-', "")
-        } else {
-            "".to_string()
-        }
-    }
+    #[test]
+    fn every_migration_has_down_script() {
+        let up: Vec<&str> = SCRIPTS_UP.iter().map(|(name, _)| *name).collect();
+        let down: Vec<&str> = SCRIPTS_DOWN.iter().map(|(name, _)| *name).collect();
 
-    pub async fn up(
-        &self,
-        storage: &mut Box<dyn MainStorage>,
-        scripts: &[(&str, &str)],
-    ) -> Result<()> {
-        log::info!("migrating up to __schema_migrations");
-        self.create_table(storage).await?;
-        for (name, script) in scripts {
-            let version = &self.parse_name(name);
-            if !self.exists(storage, version).await? {
-                log::debug!("run migration {}", &name);
-                self.execute(storage, script).await?;
-                self.insert_migration(storage, version).await?;
-            }
-        }
-        Ok(())
+        assert_eq!(up, down);
     }
 }
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}erroneous_transactions ON CLUSTER '{cluster}';
@@ -0,0 +1 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}' DROP COLUMN IF EXISTS raw_instruction_idx
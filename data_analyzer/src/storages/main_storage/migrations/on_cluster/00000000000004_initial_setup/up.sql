@@ -1,4 +1,4 @@
-ALTER TABLE instructions ON CLUSTER '{cluster}' ADD COLUMN IF NOT EXISTS
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}' ADD COLUMN IF NOT EXISTS
 raw_instruction_idx UInt16 MATERIALIZED 
 if(
     transaction_instruction_idx IS NULL, 
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}blocks ON CLUSTER '{cluster}'
+(
+    slot UInt64,
+    blockhash String,
+    block_time Nullable(Int64),
+    block_height Nullable(UInt64),
+    leader Nullable(String),
+    reward_lamports Int64
+) ENGINE = ReplicatedReplacingMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY slot
+SETTINGS index_granularity = 8192;
\ No newline at end of file
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}token_transfers ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    block_time UInt64,
+    mint String,
+    source_owner Nullable(String),
+    destination_owner Nullable(String),
+    amount UInt64,
+    decimals UInt8,
+    program_id Nullable(String)
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (mint, slot, tx_signature)
+SETTINGS index_granularity = 8192;
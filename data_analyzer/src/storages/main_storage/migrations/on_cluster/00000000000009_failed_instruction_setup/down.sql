@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS failed_instruction_idx,
+MODIFY COLUMN tx_status Enum('Failed' = 0, 'Success' = 1)
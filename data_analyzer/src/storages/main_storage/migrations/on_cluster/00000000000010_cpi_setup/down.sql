@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS parent_program,
+DROP COLUMN IF EXISTS stack_height
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}erroneous_transactions ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS encoding
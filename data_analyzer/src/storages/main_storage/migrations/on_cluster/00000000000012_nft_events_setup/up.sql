@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}nft_events ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    mint Nullable(String),
+    collection_key Nullable(String),
+    token_standard Nullable(String),
+    event_type String,
+    name Nullable(String),
+    uri Nullable(String),
+    INDEX mint_idx mint TYPE bloom_filter GRANULARITY 4,
+    INDEX collection_key_idx collection_key TYPE bloom_filter GRANULARITY 4
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (event_type, slot, tx_signature)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}commission_changes ON CLUSTER '{cluster}';
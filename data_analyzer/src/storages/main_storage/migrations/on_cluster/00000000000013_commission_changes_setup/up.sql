@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}commission_changes ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    vote_account String,
+    authority Nullable(String),
+    old_commission Nullable(UInt8),
+    new_commission UInt8
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (vote_account, slot, tx_signature)
+SETTINGS index_granularity = 8192;
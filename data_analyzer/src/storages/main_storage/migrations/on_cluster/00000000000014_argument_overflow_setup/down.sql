@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instruction_arguments ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS overflow
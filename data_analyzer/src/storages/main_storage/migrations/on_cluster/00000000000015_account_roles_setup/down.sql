@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS account_roles
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+ADD COLUMN IF NOT EXISTS account_roles String DEFAULT '' AFTER account_34
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}delegations_daily ON CLUSTER '{cluster}'
+(
+    date Date,
+    vote_acc String,
+    delegated_lamports UInt64,
+    undelegated_lamports UInt64,
+    net Int64
+) ENGINE = ReplicatedSummingMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}', (delegated_lamports, undelegated_lamports, net))
+PARTITION BY toYYYYMM(date)
+ORDER BY (date, vote_acc)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}entangler_swaps ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    payer String,
+    mint_a String,
+    mint_b String,
+    direction String,
+    price Nullable(UInt64),
+    INDEX mint_b_idx mint_b TYPE bloom_filter GRANULARITY 4,
+    INDEX payer_idx payer TYPE bloom_filter GRANULARITY 4
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (mint_a, slot, tx_signature)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}nft_sales ON CLUSTER '{cluster}';
@@ -0,0 +1,7 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+MODIFY COLUMN raw_instruction_idx UInt32 MATERIALIZED
+if(
+    transaction_instruction_idx IS NULL,
+    toUInt32(instruction_idx) * 512,
+    toUInt32(transaction_instruction_idx) * 512 + instruction_idx + 1
+)
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}delegations ON CLUSTER '{cluster}'
+MODIFY COLUMN raw_instruction_idx UInt32
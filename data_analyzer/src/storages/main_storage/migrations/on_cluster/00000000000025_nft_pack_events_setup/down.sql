@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}nft_pack_events ON CLUSTER '{cluster}';
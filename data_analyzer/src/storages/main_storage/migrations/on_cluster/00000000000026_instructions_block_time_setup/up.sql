@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions ON CLUSTER '{cluster}'
+MODIFY COLUMN block_time Nullable(UInt64)
@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}balances ON CLUSTER '{cluster}'
+DROP COLUMN IF EXISTS inner_instructions_set,
+DROP COLUMN IF EXISTS attributed_amount
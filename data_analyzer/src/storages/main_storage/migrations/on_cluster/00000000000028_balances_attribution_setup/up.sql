@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}balances ON CLUSTER '{cluster}'
+ADD COLUMN IF NOT EXISTS inner_instructions_set Nullable(UInt8),
+ADD COLUMN IF NOT EXISTS attributed_amount Nullable(UInt64)
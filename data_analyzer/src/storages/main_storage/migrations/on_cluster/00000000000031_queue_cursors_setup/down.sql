@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}queue_cursors ON CLUSTER '{cluster}';
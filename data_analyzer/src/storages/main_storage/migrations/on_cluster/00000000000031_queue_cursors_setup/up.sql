@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}queue_cursors ON CLUSTER '{cluster}'
+(
+    queue String,
+    slot UInt64,
+    signature String,
+    stored_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplicatedReplacingMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}', stored_at)
+ORDER BY queue;
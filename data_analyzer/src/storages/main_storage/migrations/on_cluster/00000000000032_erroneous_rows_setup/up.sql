@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}erroneous_rows ON CLUSTER '{cluster}'
+(
+    target_table String,
+    tx_signature String,
+    row String,
+    cause String,
+    stored_at DateTime DEFAULT now()
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (target_table, tx_signature);
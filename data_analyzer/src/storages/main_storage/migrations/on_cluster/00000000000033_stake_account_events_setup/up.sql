@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}stake_account_events ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    stake_acc String,
+    event_type String,
+    authority_type Nullable(String),
+    old_authority Nullable(String),
+    new_authority Nullable(String),
+    lockup_expiration Nullable(Int64),
+    INDEX new_authority_idx new_authority TYPE bloom_filter GRANULARITY 4
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (stake_acc, slot, tx_signature)
+SETTINGS index_granularity = 8192;
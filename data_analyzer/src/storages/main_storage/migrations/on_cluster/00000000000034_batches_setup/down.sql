@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}batches ON CLUSTER '{cluster}';
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}batches ON CLUSTER '{cluster}'
+(
+    batch_id UInt64,
+    min_slot UInt64,
+    max_slot UInt64,
+    complete UInt8,
+    stored_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplicatedReplacingMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}', stored_at)
+ORDER BY batch_id;
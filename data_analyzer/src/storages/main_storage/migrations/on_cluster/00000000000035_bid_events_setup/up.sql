@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}bid_events ON CLUSTER '{cluster}'
+(
+    tx_signature String,
+    slot UInt64,
+    instruction_idx UInt8,
+    inner_instructions_set Nullable(UInt8),
+    auction String,
+    bidder String,
+    amount Nullable(UInt64),
+    event_type String
+) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+ORDER BY (auction, bidder, slot, tx_signature)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS {table_prefix}sol_transfers ON CLUSTER '{cluster}';
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}indexing_watermark ON CLUSTER '{cluster}'
+(
+    slot UInt64,
+    stored_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplicatedReplacingMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}', slot)
+ORDER BY tuple();
@@ -0,0 +1 @@
+ALTER TABLE {table_prefix}instructions DROP COLUMN IF EXISTS raw_instruction_idx
@@ -1,4 +1,4 @@
-ALTER TABLE instructions ADD COLUMN IF NOT EXISTS
+ALTER TABLE {table_prefix}instructions ADD COLUMN IF NOT EXISTS
 raw_instruction_idx UInt16 MATERIALIZED 
 if(
     transaction_instruction_idx IS NULL, 
@@ -1,4 +1,4 @@
-CREATE TABLE IF NOT EXISTS undelegations
+CREATE TABLE IF NOT EXISTS {table_prefix}undelegations
 (
     slot UInt64,
     block_time UInt64,
@@ -8,5 +8,6 @@ CREATE TABLE IF NOT EXISTS undelegations
     amount UInt64,
     raw_instruction_idx UInt16
 ) ENGINE = MergeTree()
+PARTITION BY intDiv(slot, 1000000)
 ORDER BY (stake_acc, slot)
 SETTINGS index_granularity = 8192;
\ No newline at end of file
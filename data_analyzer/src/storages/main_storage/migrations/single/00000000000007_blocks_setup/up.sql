@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}blocks
+(
+    slot UInt64,
+    blockhash String,
+    block_time Nullable(Int64),
+    block_height Nullable(UInt64),
+    leader Nullable(String),
+    reward_lamports Int64
+) ENGINE = ReplacingMergeTree()
+ORDER BY slot
+SETTINGS index_granularity = 8192;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions
+ADD COLUMN IF NOT EXISTS failed_instruction_idx Nullable(UInt8) AFTER tx_status,
+MODIFY COLUMN tx_status Enum('Failed' = 0, 'Success' = 1, 'NotExecuted' = 3)
@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions
+ADD COLUMN IF NOT EXISTS parent_program Nullable(String) AFTER transaction_instruction_idx,
+ADD COLUMN IF NOT EXISTS stack_height Nullable(UInt8) AFTER parent_program
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}erroneous_transactions
+DROP COLUMN IF EXISTS encoding
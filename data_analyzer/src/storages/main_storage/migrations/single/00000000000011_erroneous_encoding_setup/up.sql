@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}erroneous_transactions
+ADD COLUMN IF NOT EXISTS encoding String DEFAULT 'json' AFTER transaction
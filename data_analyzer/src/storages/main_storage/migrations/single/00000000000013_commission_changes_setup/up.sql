@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}commission_changes
+(
+    tx_signature String,
+    slot UInt64,
+    vote_account String,
+    authority Nullable(String),
+    old_commission Nullable(UInt8),
+    new_commission UInt8
+) ENGINE = MergeTree()
+ORDER BY (vote_account, slot, tx_signature)
+SETTINGS index_granularity = 8192;
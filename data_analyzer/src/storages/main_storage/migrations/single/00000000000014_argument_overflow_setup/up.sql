@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instruction_arguments
+ADD COLUMN IF NOT EXISTS overflow UInt8 DEFAULT 0 AFTER string_value
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions
+ADD COLUMN IF NOT EXISTS account_roles String DEFAULT '' AFTER account_34
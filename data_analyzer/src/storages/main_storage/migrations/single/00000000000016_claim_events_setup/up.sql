@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}claim_events
+(
+    tx_signature String,
+    slot UInt64,
+    distributor String,
+    claimant String,
+    amount UInt64,
+    mint Nullable(String),
+    INDEX claimant_idx claimant TYPE bloom_filter GRANULARITY 4
+) ENGINE = MergeTree()
+ORDER BY (distributor, slot, tx_signature)
+SETTINGS index_granularity = 8192;
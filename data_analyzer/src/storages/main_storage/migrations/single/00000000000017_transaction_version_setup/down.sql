@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions
+DROP COLUMN IF EXISTS tx_version,
+DROP COLUMN IF EXISTS num_signatures
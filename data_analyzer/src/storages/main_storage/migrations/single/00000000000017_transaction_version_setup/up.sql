@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}instructions
+ADD COLUMN IF NOT EXISTS tx_version Nullable(UInt8) AFTER account_roles,
+ADD COLUMN IF NOT EXISTS num_signatures UInt8 DEFAULT 0 AFTER tx_version
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}anchor_events
+(
+    tx_signature String,
+    slot UInt64,
+    program String,
+    source String,
+    event_idx UInt16,
+    instruction_idx Nullable(UInt8),
+    stack_height Nullable(UInt8),
+    discriminator String,
+    data String,
+    INDEX discriminator_idx discriminator TYPE bloom_filter GRANULARITY 4,
+    INDEX tx_signature_idx tx_signature TYPE bloom_filter GRANULARITY 4
+) ENGINE = MergeTree()
+ORDER BY (program, slot, tx_signature, event_idx)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}nft_sales
+(
+    tx_signature String,
+    slot UInt64,
+    block_time UInt64,
+    marketplace_program String,
+    instruction_name String,
+    instruction_idx UInt8,
+    inner_instructions_set Nullable(UInt8),
+    mint String,
+    seller Nullable(String),
+    buyer String,
+    price_lamports Nullable(UInt64),
+    token_size UInt64,
+    currency_mint Nullable(String),
+    INDEX mint_idx mint TYPE bloom_filter GRANULARITY 4,
+    INDEX tx_signature_idx tx_signature TYPE bloom_filter GRANULARITY 4
+) ENGINE = MergeTree()
+ORDER BY (marketplace_program, slot, tx_signature, instruction_idx)
+SETTINGS index_granularity = 8192;
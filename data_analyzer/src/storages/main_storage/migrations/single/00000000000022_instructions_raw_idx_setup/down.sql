@@ -0,0 +1,7 @@
+ALTER TABLE {table_prefix}instructions
+MODIFY COLUMN raw_instruction_idx UInt16 MATERIALIZED
+if(
+    transaction_instruction_idx IS NULL,
+    instruction_idx * 256,
+    transaction_instruction_idx * 256 + instruction_idx + 1
+)
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}delegations
+MODIFY COLUMN raw_instruction_idx UInt16
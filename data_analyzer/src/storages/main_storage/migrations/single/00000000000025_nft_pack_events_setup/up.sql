@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}nft_pack_events
+(
+    tx_signature String,
+    slot UInt64,
+    pack_set String,
+    pack_card Nullable(String),
+    claimer Nullable(String),
+    new_mint Nullable(String),
+    event_type String,
+    INDEX claimer_idx claimer TYPE bloom_filter GRANULARITY 4,
+    INDEX new_mint_idx new_mint TYPE bloom_filter GRANULARITY 4
+) ENGINE = MergeTree()
+ORDER BY (pack_set, slot, tx_signature)
+SETTINGS index_granularity = 8192;
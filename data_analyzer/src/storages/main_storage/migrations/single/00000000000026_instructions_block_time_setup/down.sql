@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions
+MODIFY COLUMN block_time UInt64
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}candy_mints
+(
+    tx_signature String,
+    slot UInt64,
+    instruction_idx UInt8,
+    inner_instructions_set Nullable(UInt8),
+    candy_machine String,
+    minter String,
+    mint Nullable(String),
+    price_lamports Nullable(UInt64),
+    currency_mint Nullable(String),
+    collection Nullable(String),
+    INDEX minter_idx minter TYPE bloom_filter GRANULARITY 4,
+    INDEX mint_idx mint TYPE bloom_filter GRANULARITY 4,
+    INDEX collection_idx collection TYPE bloom_filter GRANULARITY 4
+) ENGINE = MergeTree()
+ORDER BY (candy_machine, slot, tx_signature, instruction_idx)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions
+DROP COLUMN IF EXISTS inserted_at
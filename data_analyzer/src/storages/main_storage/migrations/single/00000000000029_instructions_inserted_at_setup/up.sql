@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instructions
+ADD COLUMN IF NOT EXISTS inserted_at DateTime('UTC') DEFAULT now()
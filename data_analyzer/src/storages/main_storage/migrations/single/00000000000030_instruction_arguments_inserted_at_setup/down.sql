@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}instruction_arguments
+DROP COLUMN IF EXISTS inserted_at
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}queue_cursors
+(
+    queue String,
+    slot UInt64,
+    signature String,
+    stored_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(stored_at)
+ORDER BY queue;
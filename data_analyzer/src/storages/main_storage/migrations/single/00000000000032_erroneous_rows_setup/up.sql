@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}erroneous_rows
+(
+    target_table String,
+    tx_signature String,
+    row String,
+    cause String,
+    stored_at DateTime DEFAULT now()
+) ENGINE = MergeTree()
+ORDER BY (target_table, tx_signature);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}batches
+(
+    batch_id UInt64,
+    min_slot UInt64,
+    max_slot UInt64,
+    complete UInt8,
+    stored_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(stored_at)
+ORDER BY batch_id;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}bid_events
+(
+    tx_signature String,
+    slot UInt64,
+    instruction_idx UInt8,
+    inner_instructions_set Nullable(UInt8),
+    auction String,
+    bidder String,
+    amount Nullable(UInt64),
+    event_type String
+) ENGINE = MergeTree()
+ORDER BY (auction, bidder, slot, tx_signature)
+SETTINGS index_granularity = 8192;
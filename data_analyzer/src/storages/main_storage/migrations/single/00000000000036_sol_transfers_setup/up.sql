@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}sol_transfers
+(
+    tx_signature String,
+    slot UInt64,
+    instruction_idx UInt8,
+    inner_instructions_set Nullable(UInt8),
+    source String,
+    destination String,
+    lamports UInt64,
+    transfer_kind String
+) ENGINE = MergeTree()
+ORDER BY (source, destination, slot, tx_signature)
+SETTINGS index_granularity = 8192;
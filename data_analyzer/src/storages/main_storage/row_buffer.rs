@@ -0,0 +1,289 @@
+use super::{
+    AnchorEvent, Balance, BidEvent, CandyMint, ClaimEvent, CommissionChange, Delegation,
+    EntanglerSwap, ErroneousTransaction, Instruction, InstructionArgument, NftEvent, PackEvent,
+    SaleEvent, SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+use std::mem::size_of;
+
+/// Approximate number of bytes a row takes in memory: the row itself and its strings
+pub trait RowSize {
+    fn row_size(&self) -> usize;
+}
+
+fn option_len(value: &Option<String>) -> usize {
+    value.as_ref().map_or(0, String::len)
+}
+
+impl RowSize for Instruction {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.program.len()
+            + self.tx_signature.len()
+            + option_len(&self.parent_program)
+            + self.instruction_name.len()
+            + self.accounts.capacity() * size_of::<String>()
+            + self.accounts.iter().map(String::len).sum::<usize>()
+            + self.data.len()
+    }
+}
+
+impl RowSize for InstructionArgument {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.program.len()
+            + self.arg_path.len()
+            + option_len(&self.string_value)
+    }
+}
+
+impl RowSize for Balance {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.account.len()
+            + option_len(&self.pre_token_balance_mint)
+            + option_len(&self.pre_token_balance_owner)
+            + option_len(&self.pre_token_balance_program_id)
+            + option_len(&self.post_token_balance_mint)
+            + option_len(&self.post_token_balance_owner)
+            + option_len(&self.post_token_balance_program_id)
+    }
+}
+
+impl RowSize for TokenTransfer {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.mint.len()
+            + option_len(&self.source_owner)
+            + option_len(&self.destination_owner)
+            + option_len(&self.program_id)
+    }
+}
+
+impl RowSize for NftEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + option_len(&self.mint)
+            + option_len(&self.collection_key)
+            + option_len(&self.token_standard)
+            + self.event_type.len()
+            + option_len(&self.name)
+            + option_len(&self.uri)
+    }
+}
+
+impl RowSize for CommissionChange {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.vote_account.len()
+            + option_len(&self.authority)
+    }
+}
+
+impl RowSize for ClaimEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.distributor.len()
+            + self.claimant.len()
+            + option_len(&self.mint)
+    }
+}
+
+impl RowSize for EntanglerSwap {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.payer.len()
+            + self.mint_a.len()
+            + self.mint_b.len()
+            + self.direction.len()
+    }
+}
+
+impl RowSize for AnchorEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.program.len()
+            + self.source.len()
+            + self.discriminator.len()
+            + self.data.len()
+    }
+}
+
+impl RowSize for SaleEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.marketplace_program.len()
+            + self.instruction_name.len()
+            + self.mint.len()
+            + self.seller.as_ref().map_or(0, String::len)
+            + self.buyer.len()
+            + self.currency_mint.as_ref().map_or(0, String::len)
+    }
+}
+
+impl RowSize for PackEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.pack_set.len()
+            + self.pack_card.as_ref().map_or(0, String::len)
+            + self.claimer.as_ref().map_or(0, String::len)
+            + self.new_mint.as_ref().map_or(0, String::len)
+            + self.event_type.len()
+    }
+}
+
+impl RowSize for CandyMint {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.candy_machine.len()
+            + self.minter.len()
+            + self.mint.as_ref().map_or(0, String::len)
+            + self.currency_mint.as_ref().map_or(0, String::len)
+            + self.collection.as_ref().map_or(0, String::len)
+    }
+}
+
+impl RowSize for StakeAccountEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.stake_acc.len()
+            + self.event_type.len()
+            + option_len(&self.authority_type)
+            + option_len(&self.old_authority)
+            + option_len(&self.new_authority)
+    }
+}
+
+impl RowSize for BidEvent {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.auction.len()
+            + self.bidder.len()
+            + self.event_type.len()
+    }
+}
+
+impl RowSize for SolTransfer {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.tx_signature.len()
+            + self.source.len()
+            + self.destination.len()
+            + self.transfer_kind.len()
+    }
+}
+
+impl RowSize for Delegation {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.stake_acc.len()
+            + option_len(&self.vote_acc)
+            + self.tx_signature.len()
+    }
+}
+
+impl RowSize for ErroneousTransaction {
+    fn row_size(&self) -> usize {
+        size_of::<Self>()
+            + self.transaction.len()
+            + self.encoding.len()
+            + self.tx_signature.len()
+            + self.cause.len()
+    }
+}
+
+/// Rows of a table waiting to be inserted along with their approximate size
+pub struct RowBuffer<T> {
+    rows: Vec<T>,
+    bytes: usize,
+}
+
+impl<T: RowSize> RowBuffer<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            rows: Vec::with_capacity(capacity),
+            bytes: 0,
+        }
+    }
+
+    pub fn push(&mut self, row: T) {
+        self.bytes += row.row_size();
+        self.rows.push(row);
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_follow_rows() {
+        let mut buffer = RowBuffer::with_capacity(2);
+        let mut argument = InstructionArgument::new("signature", 0, None, "program");
+        argument.string_value = Some("x".repeat(1000));
+        let argument_size = argument.row_size();
+
+        assert!(argument_size > 1000);
+
+        buffer.push(argument.clone());
+        buffer.push(argument);
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.bytes(), 2 * argument_size);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.bytes(), 0);
+    }
+
+    #[test]
+    fn instruction_takes_only_its_accounts() {
+        let mut instruction = Instruction::new(&Default::default(), &Default::default());
+        let empty_size = instruction.row_size();
+
+        instruction.accounts = vec!["x".repeat(44); 3];
+        instruction.accounts.shrink_to_fit();
+
+        assert_eq!(
+            instruction.row_size(),
+            empty_size + 3 * (size_of::<String>() + 44)
+        );
+        // Far below the 256 account slots every instruction used to carry
+        assert!(
+            instruction.row_size()
+                < solana_instruction_parser::ACCOUNTS_ARRAY_SIZE * size_of::<Option<String>>()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use clickhouse::Row;
+use clickhouse_storage::schema::same_columns;
+use clickhouse_storage::{ClickhouseTable, TableRetention, WrittenTable};
+
+use crate::configuration::RetentionConfig;
+
+use super::https_client::{
+    BalancesRow, ErroneousTransactionRow, InstructionArgumentsRow, InstructionRow,
+};
+use super::{
+    AnchorEvent, BatchMarker, BidEvent, Block, CandyMint, ClaimEvent, CommissionChange, Delegation,
+    DelegationsDaily, EntanglerSwap, ErroneousRow, IndexingWatermark, NftEvent, PackEvent,
+    QueueCursorRow, SaleEvent, SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+
+/// Tables the analyzer inserts into with the columns of the rows of the HTTP client. The TCP
+/// client writes the same columns, so a new field of a row is checked once it's added here
+pub const WRITTEN_TABLES: &[WrittenTable] = &[
+    WrittenTable::of::<InstructionRow>(),
+    WrittenTable::of::<InstructionArgumentsRow>(),
+    WrittenTable::of::<BalancesRow>(),
+    WrittenTable::of::<Delegation>(),
+    WrittenTable {
+        table: "undelegations",
+        columns: Delegation::COLUMNS,
+    },
+    WrittenTable {
+        table: "delegations_daily",
+        columns: DelegationsDaily::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "blocks",
+        columns: Block::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "token_transfers",
+        columns: TokenTransfer::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "nft_events",
+        columns: NftEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "commission_changes",
+        columns: CommissionChange::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "claim_events",
+        columns: ClaimEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "entangler_swaps",
+        columns: EntanglerSwap::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "anchor_events",
+        columns: AnchorEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "nft_sales",
+        columns: SaleEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "nft_pack_events",
+        columns: PackEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "candy_mints",
+        columns: CandyMint::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "stake_account_events",
+        columns: StakeAccountEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "bid_events",
+        columns: BidEvent::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "sol_transfers",
+        columns: SolTransfer::COLUMN_NAMES,
+    },
+    WrittenTable::of::<ErroneousTransactionRow>(),
+    WrittenTable {
+        table: "queue_cursors",
+        columns: QueueCursorRow::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "erroneous_rows",
+        columns: ErroneousRow::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "batches",
+        columns: BatchMarker::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "indexing_watermark",
+        columns: IndexingWatermark::COLUMN_NAMES,
+    },
+];
+
+// The generated columns are the names the HTTP client serializes the rows with, a field renamed
+// or skipped by serde fails the build
+const _: () = assert!(same_columns(
+    InstructionRow::COLUMNS,
+    InstructionRow::COLUMN_NAMES
+));
+const _: () = assert!(same_columns(
+    InstructionArgumentsRow::COLUMNS,
+    InstructionArgumentsRow::COLUMN_NAMES
+));
+const _: () = assert!(same_columns(
+    BalancesRow::COLUMNS,
+    BalancesRow::COLUMN_NAMES
+));
+const _: () = assert!(same_columns(Delegation::COLUMNS, Delegation::COLUMN_NAMES));
+const _: () = assert!(same_columns(
+    ErroneousTransactionRow::COLUMNS,
+    ErroneousTransactionRow::COLUMN_NAMES
+));
+
+/// Tables with a configurable retention, the age of a row is counted from `inserted_at`, the time
+/// it was inserted at
+pub fn retained_tables(retention: &RetentionConfig) -> Vec<TableRetention> {
+    vec![
+        TableRetention {
+            table: "instructions",
+            column: "inserted_at",
+            days: retention.instructions,
+        },
+        TableRetention {
+            table: "instruction_arguments",
+            column: "inserted_at",
+            days: retention.instruction_arguments,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storages::main_storage::tcp_client::STORED_ACCOUNTS;
+
+    /// The scripts generated from the rows are kept in `tables`, a changed row changes its script
+    /// there as well and the migration of the change is written after the difference
+    #[test]
+    fn generated_tables_are_not_changed() {
+        let tables = [
+            (
+                InstructionRow::CREATE_TABLE,
+                include_str!("tables/single/instructions.sql"),
+            ),
+            (
+                InstructionArgumentsRow::CREATE_TABLE,
+                include_str!("tables/single/instruction_arguments.sql"),
+            ),
+            (
+                BalancesRow::CREATE_TABLE,
+                include_str!("tables/single/balances.sql"),
+            ),
+            (
+                Delegation::CREATE_TABLE,
+                include_str!("tables/single/delegations.sql"),
+            ),
+            (
+                ErroneousTransactionRow::CREATE_TABLE,
+                include_str!("tables/single/erroneous_transactions.sql"),
+            ),
+            (
+                InstructionRow::CREATE_TABLE_ON_CLUSTER,
+                include_str!("tables/on_cluster/instructions.sql"),
+            ),
+            (
+                InstructionArgumentsRow::CREATE_TABLE_ON_CLUSTER,
+                include_str!("tables/on_cluster/instruction_arguments.sql"),
+            ),
+            (
+                BalancesRow::CREATE_TABLE_ON_CLUSTER,
+                include_str!("tables/on_cluster/balances.sql"),
+            ),
+            (
+                Delegation::CREATE_TABLE_ON_CLUSTER,
+                include_str!("tables/on_cluster/delegations.sql"),
+            ),
+            (
+                ErroneousTransactionRow::CREATE_TABLE_ON_CLUSTER,
+                include_str!("tables/on_cluster/erroneous_transactions.sql"),
+            ),
+        ];
+
+        for (generated, snapshot) in tables {
+            assert_eq!(generated, snapshot.trim_end());
+        }
+    }
+
+    /// The parser keeps up to `ACCOUNTS_ARRAY_SIZE` accounts of an instruction, only the first
+    /// `STORED_ACCOUNTS` of them have a column
+    #[test]
+    fn instructions_have_a_column_per_stored_account() {
+        let accounts: Vec<&str> = InstructionRow::COLUMNS
+            .iter()
+            .copied()
+            .filter(|column| {
+                column
+                    .strip_prefix("account_")
+                    .map_or(false, |idx| idx.parse::<usize>().is_ok())
+            })
+            .collect();
+        let expected: Vec<String> = (0..STORED_ACCOUNTS)
+            .map(|idx| format!("account_{}", idx))
+            .collect();
+
+        assert_eq!(accounts, expected);
+    }
+
+    #[test]
+    fn generated_column_types() {
+        let types: Vec<(&str, &str)> = Delegation::COLUMNS
+            .iter()
+            .copied()
+            .zip(Delegation::COLUMN_TYPES.iter().copied())
+            .collect();
+
+        assert_eq!(
+            types,
+            vec![
+                ("slot", "UInt64"),
+                ("block_time", "UInt64"),
+                ("stake_acc", "String"),
+                ("vote_acc", "Nullable(String)"),
+                ("tx_signature", "String"),
+                ("amount", "UInt64"),
+                ("raw_instruction_idx", "UInt32"),
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-CREATE TABLE IF NOT EXISTS delegations ON CLUSTER '{cluster}'
+CREATE TABLE IF NOT EXISTS {table_prefix}delegations ON CLUSTER '{cluster}'
 (
     slot UInt64,
     block_time UInt64,
@@ -6,7 +6,8 @@ CREATE TABLE IF NOT EXISTS delegations ON CLUSTER '{cluster}'
     vote_acc Nullable(String),
     tx_signature String,
     amount UInt64,
-    raw_instruction_idx UInt16
+    raw_instruction_idx UInt32
 ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
+PARTITION BY intDiv(slot, 1000000)
 ORDER BY (stake_acc, slot)
-SETTINGS index_granularity = 8192;
\ No newline at end of file
+SETTINGS index_granularity = 8192
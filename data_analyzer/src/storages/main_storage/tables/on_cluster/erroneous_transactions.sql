@@ -1,9 +1,10 @@
-CREATE TABLE IF NOT EXISTS erroneous_transactions ON CLUSTER '{cluster}'
+CREATE TABLE IF NOT EXISTS {table_prefix}erroneous_transactions ON CLUSTER '{cluster}'
 (
     slot UInt64,
     transaction String,
+    encoding String DEFAULT 'json',
     tx_signature String,
     cause String
 ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
 ORDER BY (tx_signature, slot)
-SETTINGS index_granularity = 8192;
+SETTINGS index_granularity = 8192
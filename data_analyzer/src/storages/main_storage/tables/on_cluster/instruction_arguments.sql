@@ -1,4 +1,4 @@
-CREATE TABLE IF NOT EXISTS instruction_arguments ON CLUSTER '{cluster}'
+CREATE TABLE IF NOT EXISTS {table_prefix}instruction_arguments ON CLUSTER '{cluster}'
 (
     tx_signature String,
     instruction_idx UInt8,
@@ -11,9 +11,11 @@ CREATE TABLE IF NOT EXISTS instruction_arguments ON CLUSTER '{cluster}'
     float_value Nullable(Float64),
     string_value Nullable(String),
     enum_value Nullable(String),
+    overflow UInt8 DEFAULT 0,
+    inserted_at DateTime('UTC') DEFAULT now(),
     INDEX arg_path_idx arg_path TYPE minmax GRANULARITY 8192,
     INDEX inner_instructions_set_idx inner_instructions_set TYPE minmax GRANULARITY 8192,
     INDEX instruction_idx_idx instruction_idx TYPE minmax GRANULARITY 8192
 ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
 ORDER BY (tx_signature, program)
-SETTINGS index_granularity = 8192;
+SETTINGS index_granularity = 8192
@@ -1,13 +1,16 @@
-CREATE TABLE IF NOT EXISTS instructions ON CLUSTER '{cluster}'
+CREATE TABLE IF NOT EXISTS {table_prefix}instructions ON CLUSTER '{cluster}'
 (
     program String,
     tx_signature String,
-    tx_status Enum('Failed' = 0, 'Success' = 1),
+    tx_status Enum('Failed' = 0, 'Success' = 1, 'NotExecuted' = 3),
+    failed_instruction_idx Nullable(UInt8),
     slot UInt64,
-    block_time UInt64,
+    block_time Nullable(UInt64),
     instruction_idx UInt8,
     inner_instructions_set Nullable(UInt8),
     transaction_instruction_idx Nullable(UInt8),
+    parent_program Nullable(String),
+    stack_height Nullable(UInt8),
     instruction_name String,
     account_0 Nullable(String),
     account_1 Nullable(String),
@@ -44,11 +47,17 @@ CREATE TABLE IF NOT EXISTS instructions ON CLUSTER '{cluster}'
     account_32 Nullable(String),
     account_33 Nullable(String),
     account_34 Nullable(String),
+    account_roles String DEFAULT '',
+    tx_version Nullable(UInt8),
+    num_signatures UInt8 DEFAULT 0,
     data String,
+    raw_instruction_idx UInt32 MATERIALIZED if(transaction_instruction_idx IS NULL, toUInt32(instruction_idx) * 512, toUInt32(transaction_instruction_idx) * 512 + instruction_idx + 1),
+    inserted_at DateTime('UTC') DEFAULT now(),
     INDEX slot_idx slot TYPE minmax GRANULARITY 8192,
     INDEX instruction_name_idx instruction_name TYPE minmax GRANULARITY 8192,
     INDEX account_1_idx account_1 TYPE minmax GRANULARITY 8192,
     INDEX tx_signature_idx tx_signature TYPE minmax GRANULARITY 8192
 ) ENGINE = ReplicatedMergeTree('/clickhouse/tables/01/{database}/{table}', '{replica}')
-ORDER BY (program, instruction_name)
-SETTINGS index_granularity = 8192;
+PARTITION BY intDiv(slot, 1000000)
+ORDER BY (program, slot, tx_signature)
+SETTINGS index_granularity = 8192
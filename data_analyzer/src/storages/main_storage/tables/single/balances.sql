@@ -1,6 +1,6 @@
-CREATE TABLE IF NOT EXISTS balances 
+CREATE TABLE IF NOT EXISTS {table_prefix}balances
 (
-    tx_signature String, 
+    tx_signature String,
     account String,
     pre_balance Nullable(UInt64),
     post_balance Nullable(UInt64),
@@ -11,7 +11,9 @@ CREATE TABLE IF NOT EXISTS balances
     post_token_balance_mint Nullable(String),
     post_token_balance_owner Nullable(String),
     post_token_balance_amount Nullable(Float64),
-    post_token_balance_program_id Nullable(String)
+    post_token_balance_program_id Nullable(String),
+    inner_instructions_set Nullable(UInt8),
+    attributed_amount Nullable(UInt64)
 ) ENGINE = MergeTree()
 ORDER BY (tx_signature, account)
-SETTINGS index_granularity = 8192;
+SETTINGS index_granularity = 8192
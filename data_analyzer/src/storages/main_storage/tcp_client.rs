@@ -1,44 +1,304 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono_tz::Tz;
 use clickhouse_rs::{
     row,
-    types::{Block, Enum8},
+    types::{Block, Enum8, Value},
     ClientHandle, Pool,
 };
-use dsn::DSN;
+use clickhouse_storage::{
+    schema::TableColumn, storage::tcp, ClickhouseStorage, ClickhouseTable, StorageError,
+};
 
-use crate::errors::MainStorageError;
 use crate::storages::main_storage::{
-    Balance, ErroneousTransaction, Instruction, InstructionArgument, MainStorage,
+    Balance, ErroneousRow, ErroneousTransaction, Instruction, InstructionArgument, MainStorage,
 };
 
-use super::Delegation;
+use super::{
+    dedup::deduplication_token,
+    https_client::{BalancesRow, ErroneousTransactionRow, InstructionArgumentsRow, InstructionRow},
+    AnchorEvent, BatchMarker, BidEvent, CandyMint, ClaimEvent, CommissionChange, Delegation,
+    DelegationsDaily, EntanglerSwap, NftEvent, PackEvent, Page, QueueCursor, SaleEvent,
+    SolTransfer, StakeAccountEvent, TokenTransfer, TxStatus,
+};
 
-pub struct TcpClient {
-    client: ClientHandle,
+/// Accounts of an instruction stored in the `account_N` columns of `instructions`
+pub(super) const STORED_ACCOUNTS: usize = 35;
+
+/// The `value` as a ClickHouse string literal. clickhouse-rs has no bound parameters, so the
+/// strings coming from the callers are escaped as the HTTP client escapes its bound ones
+fn quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
 }
 
-impl TcpClient {
-    pub async fn new(db_creds: DSN) -> Result<Self, MainStorageError> {
-        let mut database_url = format!("{}://", db_creds.driver);
+/// Values of a row in the order of the `ClickhouseTable::COLUMNS` of its table. The rows are
+/// destructured without `..`, so a field added to a row doesn't compile until it's inserted
+trait TcpRow: ClickhouseTable {
+    fn values(self) -> Vec<Value>;
+}
 
-        if let Some(user_name) = db_creds.username {
-            database_url = format!("{database_url}{user_name}");
-        }
-        if let Some(password) = db_creds.password {
-            database_url = format!("{database_url}:{password}");
-        }
+/// Block of the rows, its columns are named by the ones generated for their table
+fn rows_block<T: TcpRow>(rows: impl ExactSizeIterator<Item = T>) -> Result<Block> {
+    let mut block = Block::with_capacity(rows.len());
 
-        let address = db_creds.address;
-        database_url = format!("{database_url}@{address}");
+    for row in rows {
+        block.push(
+            T::COLUMNS
+                .iter()
+                .map(|column| column.to_string())
+                .zip(row.values())
+                .collect::<Vec<(String, Value)>>(),
+        )?;
+    }
 
-        if let Some(db) = db_creds.database {
-            database_url = format!("{database_url}/{db}");
-        }
+    Ok(block)
+}
+
+impl TcpRow for InstructionRow {
+    fn values(self) -> Vec<Value> {
+        let InstructionRow {
+            program,
+            tx_signature,
+            tx_status,
+            failed_instruction_idx,
+            slot,
+            block_time,
+            instruction_idx,
+            inner_instructions_set,
+            transaction_instruction_idx,
+            parent_program,
+            stack_height,
+            instruction_name,
+            account_0,
+            account_1,
+            account_2,
+            account_3,
+            account_4,
+            account_5,
+            account_6,
+            account_7,
+            account_8,
+            account_9,
+            account_10,
+            account_11,
+            account_12,
+            account_13,
+            account_14,
+            account_15,
+            account_16,
+            account_17,
+            account_18,
+            account_19,
+            account_20,
+            account_21,
+            account_22,
+            account_23,
+            account_24,
+            account_25,
+            account_26,
+            account_27,
+            account_28,
+            account_29,
+            account_30,
+            account_31,
+            account_32,
+            account_33,
+            account_34,
+            account_roles,
+            tx_version,
+            num_signatures,
+            data,
+        } = self;
+
+        vec![
+            program.into(),
+            tx_signature.into(),
+            Enum8::of(tx_status.into()).into(),
+            failed_instruction_idx.into(),
+            slot.into(),
+            block_time.into(),
+            instruction_idx.into(),
+            inner_instructions_set.into(),
+            transaction_instruction_idx.into(),
+            parent_program.into(),
+            stack_height.into(),
+            instruction_name.into(),
+            account_0.into(),
+            account_1.into(),
+            account_2.into(),
+            account_3.into(),
+            account_4.into(),
+            account_5.into(),
+            account_6.into(),
+            account_7.into(),
+            account_8.into(),
+            account_9.into(),
+            account_10.into(),
+            account_11.into(),
+            account_12.into(),
+            account_13.into(),
+            account_14.into(),
+            account_15.into(),
+            account_16.into(),
+            account_17.into(),
+            account_18.into(),
+            account_19.into(),
+            account_20.into(),
+            account_21.into(),
+            account_22.into(),
+            account_23.into(),
+            account_24.into(),
+            account_25.into(),
+            account_26.into(),
+            account_27.into(),
+            account_28.into(),
+            account_29.into(),
+            account_30.into(),
+            account_31.into(),
+            account_32.into(),
+            account_33.into(),
+            account_34.into(),
+            account_roles.into(),
+            tx_version.into(),
+            num_signatures.into(),
+            data.into(),
+        ]
+    }
+}
+
+impl TcpRow for InstructionArgumentsRow {
+    fn values(self) -> Vec<Value> {
+        let InstructionArgumentsRow {
+            tx_signature,
+            instruction_idx,
+            inner_instructions_set,
+            program,
+            arg_idx,
+            arg_path,
+            int_value,
+            unsigned_value,
+            float_value,
+            string_value,
+            enum_value,
+            overflow,
+        } = self;
+
+        vec![
+            tx_signature.into(),
+            instruction_idx.into(),
+            inner_instructions_set.into(),
+            program.into(),
+            arg_idx.into(),
+            arg_path.into(),
+            int_value.into(),
+            unsigned_value.into(),
+            float_value.into(),
+            string_value.into(),
+            enum_value.into(),
+            overflow.into(),
+        ]
+    }
+}
+
+impl TcpRow for BalancesRow {
+    fn values(self) -> Vec<Value> {
+        let BalancesRow {
+            tx_signature,
+            account,
+            pre_balance,
+            post_balance,
+            pre_token_balance_mint,
+            pre_token_balance_owner,
+            pre_token_balance_amount,
+            pre_token_balance_program_id,
+            post_token_balance_mint,
+            post_token_balance_owner,
+            post_token_balance_amount,
+            post_token_balance_program_id,
+            inner_instructions_set,
+            attributed_amount,
+        } = self;
+
+        vec![
+            tx_signature.into(),
+            account.into(),
+            pre_balance.into(),
+            post_balance.into(),
+            pre_token_balance_mint.into(),
+            pre_token_balance_owner.into(),
+            pre_token_balance_amount.into(),
+            pre_token_balance_program_id.into(),
+            post_token_balance_mint.into(),
+            post_token_balance_owner.into(),
+            post_token_balance_amount.into(),
+            post_token_balance_program_id.into(),
+            inner_instructions_set.into(),
+            attributed_amount.into(),
+        ]
+    }
+}
+
+impl TcpRow for Delegation {
+    fn values(self) -> Vec<Value> {
+        let Delegation {
+            slot,
+            block_time,
+            stake_acc,
+            vote_acc,
+            tx_signature,
+            amount,
+            raw_instruction_idx,
+        } = self;
+
+        vec![
+            slot.into(),
+            block_time.into(),
+            stake_acc.into(),
+            vote_acc.into(),
+            tx_signature.into(),
+            amount.into(),
+            raw_instruction_idx.into(),
+        ]
+    }
+}
+
+impl TcpRow for ErroneousTransactionRow {
+    fn values(self) -> Vec<Value> {
+        let ErroneousTransactionRow {
+            slot,
+            transaction,
+            encoding,
+            tx_signature,
+            cause,
+        } = self;
 
-        let pool = Pool::new(database_url);
+        vec![
+            slot.into(),
+            transaction.into(),
+            encoding.into(),
+            tx_signature.into(),
+            cause.into(),
+        ]
+    }
+}
+
+pub struct TcpClient {
+    client: ClientHandle,
+    table_prefix: String,
+}
+
+impl TcpClient {
+    pub async fn new(pool: Pool) -> Result<Self, StorageError> {
         let client = pool.get_handle().await?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            table_prefix: String::new(),
+        })
+    }
+
+    /// Name of the `table` with the prefix of the storage
+    fn table(&self, table: &str) -> String {
+        format!("{}{}", self.table_prefix, table)
     }
 
     #[allow(unused)]
@@ -50,214 +310,823 @@ impl TcpClient {
     pub fn get_handle(&mut self) -> &mut ClientHandle {
         &mut self.client
     }
+
+    /// Insert which ClickHouse drops when an insert of the same `token` is already stored.
+    /// clickhouse-rs has no settings per query, so the token is set for the session of the
+    /// connection for the time of the insert
+    async fn insert_deduplicated(
+        &mut self,
+        table: &str,
+        block: Block,
+        token: String,
+    ) -> Result<()> {
+        let table = self.table(table);
+        let client = self.get_handle();
+        client
+            .execute(format!("SET insert_deduplication_token = '{}'", token))
+            .await?;
+        let result = client.insert(table, block).await;
+        client
+            .execute("SET insert_deduplication_token = ''")
+            .await?;
+
+        Ok(result?)
+    }
+}
+
+#[async_trait]
+impl ClickhouseStorage for TcpClient {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+        tcp::execute(self.get_handle(), ddl).await
+    }
+
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        tcp::migrations_table_exists(self.get_handle()).await
+    }
+
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+        tcp::migration_exists(self.get_handle(), version).await
+    }
+
+    async fn migration_lock_holder(
+        &mut self,
+        _owner: &str,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        tcp::migration_lock_holder(self.get_handle(), ttl_secs).await
+    }
+
+    async fn describe_table(
+        &mut self,
+        table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        Ok(Some(tcp::describe_table(self.get_handle(), table).await?))
+    }
+
+    async fn table_engine(&mut self, table: &str) -> Result<Option<String>, StorageError> {
+        Ok(Some(tcp::table_engine(self.get_handle(), table).await?))
+    }
 }
 
 #[allow(unused)]
 #[async_trait]
 impl MainStorage for TcpClient {
-    async fn execute(&mut self, ddl: &str) -> Result<()> {
-        let client = self.get_handle();
-        client.execute(ddl).await?;
+    async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
+        let token = deduplication_token("instructions", &instructions);
+        let block = rows_block(instructions.into_iter().map(InstructionRow::from))?;
+
+        self.insert_deduplicated("instructions", block, token)
+            .await?;
+
         Ok(())
     }
 
-    async fn migration_exists(&mut self, version: &str) -> Result<bool> {
-        let client = self.get_handle();
-        let query = &format!(
-            "SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = '{}'",
-            version
-        );
+    async fn store_instruction_arguments_block(
+        &mut self,
+        instruction_arguments: Vec<InstructionArgument>,
+    ) -> Result<()> {
+        let token = deduplication_token("instruction_arguments", &instruction_arguments);
+        let block = rows_block(
+            instruction_arguments
+                .into_iter()
+                .map(InstructionArgumentsRow::from),
+        )?;
 
-        let block = client.query(query).fetch_all().await?;
+        self.insert_deduplicated("instruction_arguments", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
+        let token = deduplication_token("balances", &balances);
+        let block = rows_block(balances.into_iter().map(BalancesRow::from))?;
 
-        return if let Some(row) = block.rows().next() {
-            let count: u64 = row.get("count")?;
-            Ok(count > 0)
-        } else {
-            Ok(false)
-        };
+        self.insert_deduplicated("balances", block, token).await?;
+        Ok(())
     }
 
-    async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
-        let block_size = instructions.len();
+    async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        let token = deduplication_token("delegations", &delegations);
+        let block = rows_block(delegations.into_iter())?;
+
+        self.insert_deduplicated("delegations", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
+        let token = deduplication_token("undelegations", &undelegations);
+        // Created alike the delegations, so the same columns
+        let block = rows_block(undelegations.into_iter())?;
+
+        self.insert_deduplicated("undelegations", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        let block_size = delegations_daily.len();
 
         let mut block = Block::with_capacity(block_size);
 
-        for instruction in instructions {
-            block.push(row! {program: *instruction.program,
-                tx_signature: *instruction.tx_signature,
-                tx_status: Enum8::of(instruction.tx_status.into()),
-                slot: instruction.slot,
-                block_time: instruction.block_time,
-                instruction_idx: instruction.instruction_idx,
-                inner_instructions_set: instruction.inner_instructions_set,
-                transaction_instruction_idx: instruction.transaction_instruction_idx,
-                instruction_name: *instruction.instruction_name,
-                account_0: instruction.accounts[0].clone(),
-                account_1: instruction.accounts[1].clone(),
-                account_2: instruction.accounts[2].clone(),
-                account_3: instruction.accounts[3].clone(),
-                account_4: instruction.accounts[4].clone(),
-                account_5: instruction.accounts[5].clone(),
-                account_6: instruction.accounts[6].clone(),
-                account_7: instruction.accounts[7].clone(),
-                account_8: instruction.accounts[8].clone(),
-                account_9: instruction.accounts[9].clone(),
-                account_10: instruction.accounts[10].clone(),
-                account_11: instruction.accounts[11].clone(),
-                account_12: instruction.accounts[12].clone(),
-                account_13: instruction.accounts[13].clone(),
-                account_14: instruction.accounts[14].clone(),
-                account_15: instruction.accounts[15].clone(),
-                account_16: instruction.accounts[16].clone(),
-                account_17: instruction.accounts[17].clone(),
-                account_18: instruction.accounts[18].clone(),
-                account_19: instruction.accounts[19].clone(),
-                account_20: instruction.accounts[20].clone(),
-                account_21: instruction.accounts[21].clone(),
-                account_22: instruction.accounts[22].clone(),
-                account_23: instruction.accounts[23].clone(),
-                account_24: instruction.accounts[24].clone(),
-                account_25: instruction.accounts[25].clone(),
-                account_26: instruction.accounts[26].clone(),
-                account_27: instruction.accounts[27].clone(),
-                account_28: instruction.accounts[28].clone(),
-                account_29: instruction.accounts[29].clone(),
-                account_30: instruction.accounts[30].clone(),
-                account_31: instruction.accounts[31].clone(),
-                account_32: instruction.accounts[32].clone(),
-                account_33: instruction.accounts[33].clone(),
-                account_34: instruction.accounts[34].clone(),
-                data: *instruction.data,
+        for day in delegations_daily {
+            block.push(row! {
+                date: Value::Date(day.date, Tz::UTC),
+                vote_acc: day.vote_acc,
+                delegated_lamports: day.delegated_lamports,
+                undelegated_lamports: day.undelegated_lamports,
+                net: day.net,
             })?;
         }
 
+        let table = self.table("delegations_daily");
         let client = self.get_handle();
-        client.insert("instructions", block).await?;
+        client.insert(table, block).await?;
+        Ok(())
+    }
+
+    async fn store_blocks_block(&mut self, blocks: Vec<super::Block>) -> Result<()> {
+        let block_size = blocks.len();
+        let token = deduplication_token("blocks", &blocks);
 
+        let mut clickhouse_block = Block::with_capacity(block_size);
+
+        for block in blocks {
+            clickhouse_block.push(row! {
+                slot: block.slot,
+                blockhash: block.blockhash,
+                block_time: block.block_time,
+                block_height: block.block_height,
+                leader: block.leader,
+                reward_lamports: block.reward_lamports,
+            })?;
+        }
+
+        self.insert_deduplicated("blocks", clickhouse_block, token)
+            .await?;
         Ok(())
     }
 
-    async fn store_instruction_arguments_block(
+    async fn store_token_transfers_block(
         &mut self,
-        instruction_arguments: Vec<InstructionArgument>,
+        token_transfers: Vec<TokenTransfer>,
     ) -> Result<()> {
-        let block_size = instruction_arguments.len();
+        let block_size = token_transfers.len();
+        let token = deduplication_token("token_transfers", &token_transfers);
 
         let mut block = Block::with_capacity(block_size);
 
-        for instruction_argument in instruction_arguments {
+        for token_transfer in token_transfers {
             block.push(row! {
-                tx_signature: *instruction_argument.tx_signature,
-                instruction_idx: instruction_argument.instruction_idx,
-                inner_instructions_set: instruction_argument.inner_instructions_set,
-                program: instruction_argument.program,
-                arg_idx: instruction_argument.arg_idx,
-                arg_path: instruction_argument.arg_path,
-                int_value: instruction_argument.int_value,
-                unsigned_value: instruction_argument.unsigned_value,
-                float_value: instruction_argument.float_value,
-                string_value: instruction_argument.string_value,
+                tx_signature: token_transfer.tx_signature,
+                slot: token_transfer.slot,
+                block_time: token_transfer.block_time,
+                mint: token_transfer.mint,
+                source_owner: token_transfer.source_owner,
+                destination_owner: token_transfer.destination_owner,
+                amount: token_transfer.amount,
+                decimals: token_transfer.decimals,
+                program_id: token_transfer.program_id,
             })?;
         }
 
-        let client = self.get_handle();
-        client.insert("instruction_arguments", block).await?;
+        self.insert_deduplicated("token_transfers", block, token)
+            .await?;
         Ok(())
     }
 
-    async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
-        let block_size = balances.len();
+    async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        let block_size = nft_events.len();
+        let token = deduplication_token("nft_events", &nft_events);
 
         let mut block = Block::with_capacity(block_size);
 
-        for balance in balances {
+        for nft_event in nft_events {
             block.push(row! {
-                tx_signature: balance.tx_signature,
-                account: balance.account,
-                pre_balance: balance.pre_balance,
-                post_balance: balance.post_balance,
-                pre_token_balance_mint: balance.pre_token_balance_mint,
-                pre_token_balance_owner: balance.pre_token_balance_owner,
-                pre_token_balance_amount: balance.pre_token_balance_amount,
-                pre_token_balance_program_id: balance.pre_token_balance_program_id,
-                post_token_balance_mint: balance.post_token_balance_mint,
-                post_token_balance_owner: balance.post_token_balance_owner,
-                post_token_balance_amount: balance.post_token_balance_amount,
-                post_token_balance_program_id: balance.post_token_balance_program_id,
+                tx_signature: nft_event.tx_signature,
+                slot: nft_event.slot,
+                mint: nft_event.mint,
+                collection_key: nft_event.collection_key,
+                token_standard: nft_event.token_standard,
+                event_type: nft_event.event_type,
+                name: nft_event.name,
+                uri: nft_event.uri,
             })?;
         }
 
-        let client = self.get_handle();
-        client.insert("balances", block).await?;
+        self.insert_deduplicated("nft_events", block, token).await?;
         Ok(())
     }
 
-    async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
-        let block_size = delegations.len();
+    async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        let block_size = commission_changes.len();
+        let token = deduplication_token("commission_changes", &commission_changes);
 
         let mut block = Block::with_capacity(block_size);
 
-        for delegation in delegations {
+        for commission_change in commission_changes {
             block.push(row! {
-                slot: delegation.slot,
-                block_time: delegation.block_time,
-                stake_acc: delegation.stake_acc,
-                vote_acc: delegation.vote_acc,
-                tx_signature: delegation.tx_signature,
-                amount: delegation.amount,
-                raw_instruction_idx: delegation.raw_instruction_idx,
+                tx_signature: commission_change.tx_signature,
+                slot: commission_change.slot,
+                vote_account: commission_change.vote_account,
+                authority: commission_change.authority,
+                old_commission: commission_change.old_commission,
+                new_commission: commission_change.new_commission,
             })?;
         }
 
-        let client = self.get_handle();
-        client.insert("delegations", block).await?;
+        self.insert_deduplicated("commission_changes", block, token)
+            .await?;
         Ok(())
     }
 
-    async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
-        let block_size = undelegations.len();
+    async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        let block_size = claim_events.len();
+        let token = deduplication_token("claim_events", &claim_events);
 
         let mut block = Block::with_capacity(block_size);
 
-        for undelegation in undelegations {
+        for claim_event in claim_events {
             block.push(row! {
-                slot: undelegation.slot,
-                block_time: undelegation.block_time,
-                stake_acc: undelegation.stake_acc,
-                vote_acc: undelegation.vote_acc,
-                tx_signature: undelegation.tx_signature,
-                amount: undelegation.amount,
-                raw_instruction_idx: undelegation.raw_instruction_idx,
+                tx_signature: claim_event.tx_signature,
+                slot: claim_event.slot,
+                distributor: claim_event.distributor,
+                claimant: claim_event.claimant,
+                amount: claim_event.amount,
+                mint: claim_event.mint,
             })?;
         }
 
-        let client = self.get_handle();
-        client.insert("undelegations", block).await?;
+        self.insert_deduplicated("claim_events", block, token)
+            .await?;
         Ok(())
     }
 
-    async fn store_erroneous_transaction_block(
+    async fn store_entangler_swaps_block(
         &mut self,
-        erroneous_transactions: Vec<ErroneousTransaction>,
+        entangler_swaps: Vec<EntanglerSwap>,
     ) -> Result<()> {
-        let block_size = erroneous_transactions.len();
+        let block_size = entangler_swaps.len();
+        let token = deduplication_token("entangler_swaps", &entangler_swaps);
 
         let mut block = Block::with_capacity(block_size);
 
-        for erroneous_transactions in erroneous_transactions {
+        for entangler_swap in entangler_swaps {
             block.push(row! {
-               slot: erroneous_transactions.slot,
-               transaction: erroneous_transactions.transaction,
-               tx_signature: erroneous_transactions.tx_signature,
-               cause: erroneous_transactions.cause
+                tx_signature: entangler_swap.tx_signature,
+                slot: entangler_swap.slot,
+                payer: entangler_swap.payer,
+                mint_a: entangler_swap.mint_a,
+                mint_b: entangler_swap.mint_b,
+                direction: entangler_swap.direction,
+                price: entangler_swap.price,
             })?;
         }
 
+        self.insert_deduplicated("entangler_swaps", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_anchor_events_block(&mut self, anchor_events: Vec<AnchorEvent>) -> Result<()> {
+        let block_size = anchor_events.len();
+        let token = deduplication_token("anchor_events", &anchor_events);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for anchor_event in anchor_events {
+            block.push(row! {
+                tx_signature: anchor_event.tx_signature,
+                slot: anchor_event.slot,
+                program: anchor_event.program,
+                source: anchor_event.source,
+                event_idx: anchor_event.event_idx,
+                instruction_idx: anchor_event.instruction_idx,
+                stack_height: anchor_event.stack_height,
+                discriminator: anchor_event.discriminator,
+                data: anchor_event.data,
+            })?;
+        }
+
+        self.insert_deduplicated("anchor_events", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        let block_size = sale_events.len();
+        let token = deduplication_token("nft_sales", &sale_events);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for sale_event in sale_events {
+            block.push(row! {
+                tx_signature: sale_event.tx_signature,
+                slot: sale_event.slot,
+                block_time: sale_event.block_time,
+                marketplace_program: sale_event.marketplace_program,
+                instruction_name: sale_event.instruction_name,
+                instruction_idx: sale_event.instruction_idx,
+                inner_instructions_set: sale_event.inner_instructions_set,
+                mint: sale_event.mint,
+                seller: sale_event.seller,
+                buyer: sale_event.buyer,
+                price_lamports: sale_event.price_lamports,
+                token_size: sale_event.token_size,
+                currency_mint: sale_event.currency_mint,
+            })?;
+        }
+
+        self.insert_deduplicated("nft_sales", block, token).await?;
+        Ok(())
+    }
+
+    async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        let block_size = pack_events.len();
+        let token = deduplication_token("nft_pack_events", &pack_events);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for pack_event in pack_events {
+            block.push(row! {
+                tx_signature: pack_event.tx_signature,
+                slot: pack_event.slot,
+                pack_set: pack_event.pack_set,
+                pack_card: pack_event.pack_card,
+                claimer: pack_event.claimer,
+                new_mint: pack_event.new_mint,
+                event_type: pack_event.event_type,
+            })?;
+        }
+
+        self.insert_deduplicated("nft_pack_events", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        let block_size = candy_mints.len();
+        let token = deduplication_token("candy_mints", &candy_mints);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for candy_mint in candy_mints {
+            block.push(row! {
+                tx_signature: candy_mint.tx_signature,
+                slot: candy_mint.slot,
+                instruction_idx: candy_mint.instruction_idx,
+                inner_instructions_set: candy_mint.inner_instructions_set,
+                candy_machine: candy_mint.candy_machine,
+                minter: candy_mint.minter,
+                mint: candy_mint.mint,
+                price_lamports: candy_mint.price_lamports,
+                currency_mint: candy_mint.currency_mint,
+                collection: candy_mint.collection,
+            })?;
+        }
+
+        self.insert_deduplicated("candy_mints", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        let block_size = stake_account_events.len();
+        let token = deduplication_token("stake_account_events", &stake_account_events);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for stake_account_event in stake_account_events {
+            block.push(row! {
+                tx_signature: stake_account_event.tx_signature,
+                slot: stake_account_event.slot,
+                stake_acc: stake_account_event.stake_acc,
+                event_type: stake_account_event.event_type,
+                authority_type: stake_account_event.authority_type,
+                old_authority: stake_account_event.old_authority,
+                new_authority: stake_account_event.new_authority,
+                lockup_expiration: stake_account_event.lockup_expiration,
+            })?;
+        }
+
+        self.insert_deduplicated("stake_account_events", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        let block_size = bid_events.len();
+        let token = deduplication_token("bid_events", &bid_events);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for bid_event in bid_events {
+            block.push(row! {
+                tx_signature: bid_event.tx_signature,
+                slot: bid_event.slot,
+                instruction_idx: bid_event.instruction_idx,
+                inner_instructions_set: bid_event.inner_instructions_set,
+                auction: bid_event.auction,
+                bidder: bid_event.bidder,
+                amount: bid_event.amount,
+                event_type: bid_event.event_type,
+            })?;
+        }
+
+        self.insert_deduplicated("bid_events", block, token).await?;
+        Ok(())
+    }
+
+    async fn store_sol_transfers_block(&mut self, sol_transfers: Vec<SolTransfer>) -> Result<()> {
+        let block_size = sol_transfers.len();
+        let token = deduplication_token("sol_transfers", &sol_transfers);
+
+        let mut block = Block::with_capacity(block_size);
+
+        for sol_transfer in sol_transfers {
+            block.push(row! {
+                tx_signature: sol_transfer.tx_signature,
+                slot: sol_transfer.slot,
+                instruction_idx: sol_transfer.instruction_idx,
+                inner_instructions_set: sol_transfer.inner_instructions_set,
+                source: sol_transfer.source,
+                destination: sol_transfer.destination,
+                lamports: sol_transfer.lamports,
+                transfer_kind: sol_transfer.transfer_kind,
+            })?;
+        }
+
+        self.insert_deduplicated("sol_transfers", block, token)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        let query = format!(
+            "SELECT block_time FROM {} WHERE slot = {} AND block_time IS NOT NULL LIMIT 1",
+            self.table("blocks"),
+            slot
+        );
+
         let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        match block.rows().next() {
+            Some(row) => Ok(row.get("block_time")?),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        let query = format!(
+            "SELECT slot, block_time, stake_acc, vote_acc, tx_signature, amount, raw_instruction_idx
+            FROM {}
+            WHERE stake_acc = {} AND slot >= {} AND slot <= {}
+            ORDER BY slot, raw_instruction_idx",
+            self.table("delegations"),
+            quoted(stake_acc), from_slot, to_slot
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
 
-        client.insert("erroneous_transactions", block).await?;
+        let mut delegations = Vec::with_capacity(block.row_count());
+
+        for row in block.rows() {
+            delegations.push(Delegation {
+                slot: row.get("slot")?,
+                block_time: row.get("block_time")?,
+                stake_acc: row.get("stake_acc")?,
+                vote_acc: row.get("vote_acc")?,
+                tx_signature: row.get("tx_signature")?,
+                amount: row.get("amount")?,
+                raw_instruction_idx: row.get("raw_instruction_idx")?,
+            });
+        }
+
+        Ok(delegations)
+    }
+
+    async fn fetch_instructions_by_signature(
+        &mut self,
+        tx_signature: &str,
+        page: Page,
+    ) -> Result<Vec<InstructionRow>> {
+        let query = format!(
+            "SELECT *, toInt8(tx_status) AS tx_status_value FROM {}
+            WHERE tx_signature = {}
+            ORDER BY ifNull(transaction_instruction_idx, instruction_idx),
+                isNotNull(inner_instructions_set), instruction_idx
+            LIMIT {} OFFSET {}",
+            self.table("instructions"),
+            quoted(tx_signature),
+            page.limit,
+            page.offset
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        let mut instructions = Vec::with_capacity(block.row_count());
+
+        for row in block.rows() {
+            let mut instruction = Instruction {
+                program: row.get("program")?,
+                tx_signature: row.get("tx_signature")?,
+                tx_status: TxStatus::from(row.get::<i8, _>("tx_status_value")?),
+                failed_instruction_idx: row.get("failed_instruction_idx")?,
+                slot: row.get("slot")?,
+                block_time: row.get("block_time")?,
+                instruction_idx: row.get("instruction_idx")?,
+                inner_instructions_set: row.get("inner_instructions_set")?,
+                transaction_instruction_idx: row.get("transaction_instruction_idx")?,
+                parent_program: row.get("parent_program")?,
+                stack_height: row.get("stack_height")?,
+                instruction_name: row.get("instruction_name")?,
+                account_roles: row.get("account_roles")?,
+                tx_version: row.get("tx_version")?,
+                num_signatures: row.get("num_signatures")?,
+                data: row.get("data")?,
+                ..Instruction::new(&Default::default(), &Default::default())
+            };
+            // The accounts are padded with None after the last one
+            for idx in 0..STORED_ACCOUNTS {
+                match row.get::<Option<String>, _>(format!("account_{}", idx).as_str())? {
+                    Some(account) => instruction.accounts.push(account),
+                    None => break,
+                }
+            }
+
+            instructions.push(InstructionRow::from(instruction));
+        }
+
+        Ok(instructions)
+    }
+
+    async fn fetch_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+        page: Page,
+    ) -> Result<Vec<Delegation>> {
+        let query = format!(
+            "SELECT slot, block_time, stake_acc, vote_acc, tx_signature, amount, raw_instruction_idx
+            FROM {}
+            WHERE stake_acc = {} AND slot >= {} AND slot <= {}
+            ORDER BY slot, raw_instruction_idx
+            LIMIT {} OFFSET {}",
+            self.table("delegations"),
+            quoted(stake_acc), from_slot, to_slot, page.limit, page.offset
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        let mut delegations = Vec::with_capacity(block.row_count());
+
+        for row in block.rows() {
+            delegations.push(Delegation {
+                slot: row.get("slot")?,
+                block_time: row.get("block_time")?,
+                stake_acc: row.get("stake_acc")?,
+                vote_acc: row.get("vote_acc")?,
+                tx_signature: row.get("tx_signature")?,
+                amount: row.get("amount")?,
+                raw_instruction_idx: row.get("raw_instruction_idx")?,
+            });
+        }
+
+        Ok(delegations)
+    }
 
+    async fn fetch_balances_by_account(
+        &mut self,
+        account: &str,
+        page: Page,
+    ) -> Result<Vec<BalancesRow>> {
+        let query = format!(
+            "SELECT * FROM {}
+            WHERE account = {}
+            ORDER BY tx_signature, inner_instructions_set NULLS FIRST
+            LIMIT {} OFFSET {}",
+            self.table("balances"),
+            quoted(account),
+            page.limit,
+            page.offset
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        let mut balances = Vec::with_capacity(block.row_count());
+
+        for row in block.rows() {
+            balances.push(BalancesRow {
+                tx_signature: row.get("tx_signature")?,
+                account: row.get("account")?,
+                pre_balance: row.get("pre_balance")?,
+                post_balance: row.get("post_balance")?,
+                pre_token_balance_mint: row.get("pre_token_balance_mint")?,
+                pre_token_balance_owner: row.get("pre_token_balance_owner")?,
+                pre_token_balance_amount: row.get("pre_token_balance_amount")?,
+                pre_token_balance_program_id: row.get("pre_token_balance_program_id")?,
+                post_token_balance_mint: row.get("post_token_balance_mint")?,
+                post_token_balance_owner: row.get("post_token_balance_owner")?,
+                post_token_balance_amount: row.get("post_token_balance_amount")?,
+                post_token_balance_program_id: row.get("post_token_balance_program_id")?,
+                inner_instructions_set: row.get("inner_instructions_set")?,
+                attributed_amount: row.get("attributed_amount")?,
+            });
+        }
+
+        Ok(balances)
+    }
+
+    async fn get_queue_cursor(&mut self, queue: &str) -> Result<Option<QueueCursor>> {
+        let query = format!(
+            "SELECT slot, signature FROM {}
+            WHERE queue = {}
+            ORDER BY slot DESC, signature DESC
+            LIMIT 1",
+            self.table("queue_cursors"),
+            quoted(queue)
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        match block.rows().next() {
+            Some(row) => Ok(Some(QueueCursor {
+                slot: row.get("slot")?,
+                signature: row.get("signature")?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn store_queue_cursor(&mut self, queue: &str, cursor: QueueCursor) -> Result<()> {
+        let mut block = Block::with_capacity(1);
+        block.push(row! {
+            queue: queue.to_string(),
+            slot: cursor.slot,
+            signature: cursor.signature,
+        })?;
+
+        let table = self.table("queue_cursors");
+        let client = self.get_handle();
+        client.insert(table, block).await?;
+        Ok(())
+    }
+
+    async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        let query = format!(
+            "SELECT slot FROM {} ORDER BY slot DESC LIMIT 1",
+            self.table("indexing_watermark")
+        );
+
+        let client = self.get_handle();
+        let block = client.query(query).fetch_all().await?;
+
+        match block.rows().next() {
+            Some(row) => Ok(Some(row.get("slot")?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        let mut block = Block::with_capacity(1);
+        block.push(row! { slot: slot })?;
+
+        let table = self.table("indexing_watermark");
+        let client = self.get_handle();
+        client.insert(table, block).await?;
+        Ok(())
+    }
+
+    async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        let mut block = Block::with_capacity(erroneous_rows.len());
+
+        for erroneous_row in erroneous_rows {
+            block.push(row! {
+                target_table: erroneous_row.target_table,
+                tx_signature: erroneous_row.tx_signature,
+                row: erroneous_row.row,
+                cause: erroneous_row.cause,
+            })?;
+        }
+
+        let table = self.table("erroneous_rows");
+        let client = self.get_handle();
+        client.insert(table, block).await?;
         Ok(())
     }
+
+    async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        let mut block = Block::with_capacity(batches.len());
+
+        for batch in batches {
+            block.push(row! {
+                batch_id: batch.batch_id,
+                min_slot: batch.min_slot,
+                max_slot: batch.max_slot,
+                complete: u8::from(batch.complete),
+            })?;
+        }
+
+        let table = self.table("batches");
+        let client = self.get_handle();
+        client.insert(table, block).await?;
+        Ok(())
+    }
+
+    fn set_table_prefix(&mut self, table_prefix: &str) {
+        self.table_prefix = table_prefix.to_string();
+    }
+
+    async fn store_erroneous_transaction_block(
+        &mut self,
+        erroneous_transactions: Vec<ErroneousTransaction>,
+    ) -> Result<()> {
+        let token = deduplication_token("erroneous_transactions", &erroneous_transactions);
+        let block = rows_block(
+            erroneous_transactions
+                .into_iter()
+                .map(ErroneousTransactionRow::from),
+        )?;
+
+        self.insert_deduplicated("erroneous_transactions", block, token)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_quoted() {
+        assert_eq!(quoted("stake"), "'stake'");
+        assert_eq!(quoted("stake' OR '1' = '1"), r"'stake\' OR \'1\' = \'1'");
+        assert_eq!(quoted(r"stake\"), r"'stake\\'");
+    }
+
+    fn assert_generated_columns<T: TcpRow>(row: T) {
+        let block = rows_block(std::iter::once(row)).unwrap();
+        let columns: Vec<_> = block.columns().iter().map(|column| column.name()).collect();
+
+        assert_eq!(columns, T::COLUMNS);
+    }
+
+    #[test]
+    fn blocks_have_the_generated_columns() {
+        assert_generated_columns(InstructionRow::from(Instruction::new(
+            &Default::default(),
+            &Default::default(),
+        )));
+        assert_generated_columns(InstructionArgumentsRow::from(InstructionArgument::default()));
+        assert_generated_columns(BalancesRow {
+            tx_signature: "tx_signature".to_string(),
+            account: "account".to_string(),
+            pre_balance: Some(1000),
+            post_balance: Some(995),
+            pre_token_balance_mint: None,
+            pre_token_balance_owner: None,
+            pre_token_balance_amount: None,
+            pre_token_balance_program_id: None,
+            post_token_balance_mint: None,
+            post_token_balance_owner: None,
+            post_token_balance_amount: None,
+            post_token_balance_program_id: None,
+            inner_instructions_set: None,
+            attributed_amount: None,
+        });
+        assert_generated_columns(Delegation::default());
+        assert_generated_columns(ErroneousTransactionRow {
+            slot: 1,
+            transaction: "{}".to_string(),
+            encoding: "json".to_string(),
+            tx_signature: "tx_signature".to_string(),
+            cause: "cause".to_string(),
+        });
+    }
 }
@@ -0,0 +1,664 @@
+//! Vec and HashMap backed storages selected with the `Memory` storage type. Storages connected
+//! with the same url share their data, so the rows stored by the actors can be inspected
+//! without running PostgreSQL or ClickHouse
+
+use super::main_storage::{
+    https_client::{BalancesRow, InstructionRow},
+    AnchorEvent, Balance, BatchMarker, BidEvent, Block, CandyMint, ClaimEvent, CommissionChange,
+    Delegation, DelegationsDaily, EntanglerSwap, ErroneousRow, ErroneousTransaction, Instruction,
+    InstructionArgument, MainStorage, Metadata, NftEvent, PackEvent, Page, QueueCursor, SaleEvent,
+    SolTransfer, StakeAccountEvent, TokenTransfer,
+};
+use super::postgre_storage::{models, ERRONEOUS_TRANSACTION};
+use super::read_only::QueueReader;
+use super::{QueueProgress, QueueStats, QueueStorage};
+use anyhow::Result;
+use async_trait::async_trait;
+use clickhouse_storage::{ClickhouseStorage, StorageError};
+use lazy_static::lazy_static;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Transactions claimed by one `get_transactions` call, the same as for PostgreSQL
+const TRANSACTIONS_BATCH_SIZE: usize = 1000;
+
+lazy_static! {
+    static ref QUEUES: Mutex<HashMap<String, Arc<Mutex<MemoryQueue>>>> = Mutex::new(HashMap::new());
+    static ref MAIN_STORAGES: Mutex<HashMap<String, Arc<Mutex<MemoryMain>>>> =
+        Mutex::new(HashMap::new());
+}
+
+fn shared<T: Default>(
+    storages: &Mutex<HashMap<String, Arc<Mutex<T>>>>,
+    url: &str,
+) -> Arc<Mutex<T>> {
+    storages
+        .lock()
+        .unwrap()
+        .entry(url.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Transaction of the queue with its signature and parsing status: 0 is pending,
+/// 2 is in progress, 1 is parsed and `ERRONEOUS_TRANSACTION` failed to parse
+pub struct QueuedTransaction {
+    pub signature: String,
+    pub transaction: EncodedConfirmedTransactionWithStatusMeta,
+    pub parsing_status: i32,
+}
+
+#[derive(Default)]
+pub struct MemoryQueue {
+    pub transactions: Vec<QueuedTransaction>,
+    pub delegations: HashMap<String, models::Delegation>,
+    /// Blocks metadata with its parsing status
+    pub blocks_metadata: Vec<(Metadata, bool)>,
+}
+
+#[derive(Clone)]
+pub struct MemoryQueueStorage {
+    queue: Arc<Mutex<MemoryQueue>>,
+}
+
+impl MemoryQueueStorage {
+    pub fn connect(url: &str) -> Self {
+        Self {
+            queue: shared(&QUEUES, url),
+        }
+    }
+
+    pub fn queue(&self) -> MutexGuard<'_, MemoryQueue> {
+        self.queue.lock().unwrap()
+    }
+
+    /// Adds a pending transaction as the data_loader does
+    #[cfg(test)]
+    pub fn push_transaction(&self, transaction: EncodedConfirmedTransactionWithStatusMeta) {
+        use solana_transaction_status::EncodedTransaction;
+
+        let signature = match &transaction.transaction.transaction {
+            EncodedTransaction::Json(ui_transaction) => ui_transaction
+                .signatures
+                .first()
+                .cloned()
+                .unwrap_or_default(),
+            encoded_transaction => encoded_transaction
+                .decode()
+                .and_then(|transaction| transaction.signatures.first().map(ToString::to_string))
+                .unwrap_or_default(),
+        };
+
+        self.queue().transactions.push(QueuedTransaction {
+            signature,
+            transaction,
+            parsing_status: 0,
+        });
+    }
+}
+
+#[async_trait]
+impl QueueStorage for MemoryQueueStorage {
+    async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+        let mut queue = self.queue();
+
+        let mut claimed: Vec<_> = queue
+            .transactions
+            .iter_mut()
+            .filter(|queued| queued.parsing_status == 0)
+            .collect();
+        claimed.sort_by_key(|queued| queued.transaction.slot);
+
+        claimed
+            .into_iter()
+            .take(TRANSACTIONS_BATCH_SIZE)
+            .map(|queued| {
+                queued.parsing_status = 2;
+
+                // EncodedConfirmedTransactionWithStatusMeta doesn't implement Clone trait
+                EncodedConfirmedTransactionWithStatusMeta {
+                    slot: queued.transaction.slot,
+                    transaction: queued.transaction.transaction.clone(),
+                    block_time: queued.transaction.block_time,
+                }
+            })
+            .collect()
+    }
+
+    async fn get_delegations(
+        &mut self,
+        stake_accs: Vec<String>,
+    ) -> Result<Vec<models::Delegation>> {
+        let queue = self.queue();
+
+        Ok(stake_accs
+            .into_iter()
+            .filter_map(|stake_acc| queue.delegations.get(&stake_acc).cloned())
+            .collect())
+    }
+
+    async fn save_delegations(&mut self, delegations: Vec<models::Delegation>) -> Result<()> {
+        let mut queue = self.queue();
+
+        for delegation in delegations {
+            queue
+                .delegations
+                .insert(delegation.stake_acc.clone(), delegation);
+        }
+
+        Ok(())
+    }
+
+    async fn mark_transaction_as_parsed(&mut self, transaction: String) -> Result<()> {
+        for queued in self
+            .queue()
+            .transactions
+            .iter_mut()
+            .filter(|queued| queued.signature == transaction)
+        {
+            queued.parsing_status = 1;
+        }
+
+        Ok(())
+    }
+
+    async fn mark_transaction_as_erroneous(&mut self, transaction: String) -> Result<()> {
+        for queued in self
+            .queue()
+            .transactions
+            .iter_mut()
+            .filter(|queued| queued.signature == transaction)
+        {
+            queued.parsing_status = ERRONEOUS_TRANSACTION;
+        }
+
+        Ok(())
+    }
+
+    /// Nothing is claimed by a dead analyzer within a single process
+    async fn reclaim_stuck_transactions(&mut self, _timeout: u64) -> Result<usize> {
+        Ok(0)
+    }
+
+    async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+        let mut blocks_metadata: Vec<_> = self
+            .queue()
+            .blocks_metadata
+            .iter()
+            .filter(|(_, parsed)| !parsed)
+            .map(|(metadata, _)| metadata.clone())
+            .collect();
+        blocks_metadata.sort_by_key(|metadata| metadata.slot);
+
+        Ok(blocks_metadata)
+    }
+
+    async fn mark_blocks_metadata_as_parsed(&mut self, slots: Vec<u64>) -> Result<()> {
+        for (metadata, parsed) in self.queue().blocks_metadata.iter_mut() {
+            if slots.contains(&metadata.slot) {
+                *parsed = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn queue_stats(&mut self) -> Result<QueueStats> {
+        Ok(queue_stats(
+            self.queue()
+                .transactions
+                .iter()
+                .filter(|queued| queued.parsing_status == 0),
+        ))
+    }
+
+    /// The memory queue has no signatures, only its transactions are unfinished until parsed or
+    /// found erroneous
+    async fn queue_progress(&mut self) -> Result<QueueProgress> {
+        let queue = self.queue();
+
+        Ok(QueueProgress {
+            lowest_unfinished_slot: queue
+                .transactions
+                .iter()
+                .filter(|queued| ![1, ERRONEOUS_TRANSACTION].contains(&queued.parsing_status))
+                .map(|queued| queued.transaction.slot)
+                .min(),
+            highest_slot: queue
+                .transactions
+                .iter()
+                .map(|queued| queued.transaction.slot)
+                .max(),
+        })
+    }
+}
+
+fn queue_stats<'a>(pending: impl Iterator<Item = &'a QueuedTransaction>) -> QueueStats {
+    pending.fold(QueueStats::default(), |stats, queued| {
+        stats.merge(QueueStats {
+            pending: 1,
+            oldest_pending_slot: Some(queued.transaction.slot),
+            oldest_pending_block_time: queued.transaction.block_time,
+        })
+    })
+}
+
+/// Reads the queue without changing the parsing statuses
+#[async_trait]
+impl QueueReader for MemoryQueueStorage {
+    async fn read_transactions(
+        &mut self,
+        after: &QueueCursor,
+        limit: usize,
+    ) -> Result<Vec<(QueueCursor, EncodedConfirmedTransactionWithStatusMeta)>> {
+        let queue = self.queue();
+
+        let mut transactions: Vec<_> = queue
+            .transactions
+            .iter()
+            .map(|queued| {
+                let cursor = QueueCursor {
+                    slot: queued.transaction.slot,
+                    signature: queued.signature.clone(),
+                };
+                (cursor, queued)
+            })
+            .filter(|(cursor, _)| cursor > after)
+            .collect();
+        transactions.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        Ok(transactions
+            .into_iter()
+            .take(limit)
+            .map(|(cursor, queued)| {
+                let transaction = EncodedConfirmedTransactionWithStatusMeta {
+                    slot: queued.transaction.slot,
+                    transaction: queued.transaction.transaction.clone(),
+                    block_time: queued.transaction.block_time,
+                };
+                (cursor, transaction)
+            })
+            .collect())
+    }
+
+    async fn read_blocks_metadata(
+        &mut self,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<Metadata>> {
+        let mut blocks_metadata: Vec<_> = self
+            .queue()
+            .blocks_metadata
+            .iter()
+            .map(|(metadata, _)| metadata.clone())
+            .filter(|metadata| Some(metadata.slot) > after)
+            .collect();
+        blocks_metadata.sort_by_key(|metadata| metadata.slot);
+        blocks_metadata.truncate(limit);
+
+        Ok(blocks_metadata)
+    }
+
+    async fn read_delegations(
+        &mut self,
+        stake_accs: Vec<String>,
+    ) -> Result<Vec<models::Delegation>> {
+        self.get_delegations(stake_accs).await
+    }
+
+    async fn read_queue_stats(&mut self, after: &QueueCursor) -> Result<QueueStats> {
+        Ok(queue_stats(self.queue().transactions.iter().filter(
+            |queued| {
+                QueueCursor {
+                    slot: queued.transaction.slot,
+                    signature: queued.signature.clone(),
+                } > *after
+            },
+        )))
+    }
+}
+
+/// Stored rows of every table along with the number of rows of every insert
+#[derive(Default)]
+pub struct MemoryMain {
+    pub instructions: Vec<Instruction>,
+    pub instruction_arguments: Vec<InstructionArgument>,
+    pub balances: Vec<Balance>,
+    pub erroneous_transactions: Vec<ErroneousTransaction>,
+    pub erroneous_rows: Vec<ErroneousRow>,
+    pub batches: Vec<BatchMarker>,
+    pub delegations: Vec<Delegation>,
+    pub undelegations: Vec<Delegation>,
+    pub delegations_daily: Vec<DelegationsDaily>,
+    pub blocks: Vec<Block>,
+    pub token_transfers: Vec<TokenTransfer>,
+    pub nft_events: Vec<NftEvent>,
+    pub commission_changes: Vec<CommissionChange>,
+    pub claim_events: Vec<ClaimEvent>,
+    pub entangler_swaps: Vec<EntanglerSwap>,
+    pub anchor_events: Vec<AnchorEvent>,
+    pub sale_events: Vec<SaleEvent>,
+    pub pack_events: Vec<PackEvent>,
+    pub candy_mints: Vec<CandyMint>,
+    pub stake_account_events: Vec<StakeAccountEvent>,
+    pub bid_events: Vec<BidEvent>,
+    pub sol_transfers: Vec<SolTransfer>,
+    /// Latest cursor of every read-only queue
+    pub queue_cursors: HashMap<String, QueueCursor>,
+    /// Every stored indexing watermark in the order of the stores
+    pub indexing_watermarks: Vec<u64>,
+    pub inserts: Vec<(&'static str, usize)>,
+}
+
+#[derive(Clone)]
+pub struct MemoryMainStorage {
+    main: Arc<Mutex<MemoryMain>>,
+}
+
+impl MemoryMainStorage {
+    pub fn connect(url: &str) -> Self {
+        Self {
+            main: shared(&MAIN_STORAGES, url),
+        }
+    }
+
+    pub fn main(&self) -> MutexGuard<'_, MemoryMain> {
+        self.main.lock().unwrap()
+    }
+
+    fn store<T>(
+        &self,
+        table: &'static str,
+        rows: Vec<T>,
+        select: fn(&mut MemoryMain) -> &mut Vec<T>,
+    ) {
+        let mut main = self.main();
+
+        main.inserts.push((table, rows.len()));
+        select(&mut main).extend(rows);
+    }
+}
+
+#[async_trait]
+impl ClickhouseStorage for MemoryMainStorage {
+    async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+
+    /// Tables of the memory storage don't have to be created
+    async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl MainStorage for MemoryMainStorage {
+    async fn store_instructions_block(&mut self, instructions: Vec<Instruction>) -> Result<()> {
+        self.store("instructions", instructions, |main| &mut main.instructions);
+        Ok(())
+    }
+
+    async fn store_instruction_arguments_block(
+        &mut self,
+        instruction_arguments: Vec<InstructionArgument>,
+    ) -> Result<()> {
+        self.store("instruction_arguments", instruction_arguments, |main| {
+            &mut main.instruction_arguments
+        });
+        Ok(())
+    }
+
+    async fn store_balances_block(&mut self, balances: Vec<Balance>) -> Result<()> {
+        self.store("balances", balances, |main| &mut main.balances);
+        Ok(())
+    }
+
+    async fn store_erroneous_transaction_block(
+        &mut self,
+        erroneous_transactions: Vec<ErroneousTransaction>,
+    ) -> Result<()> {
+        self.store("erroneous_transactions", erroneous_transactions, |main| {
+            &mut main.erroneous_transactions
+        });
+        Ok(())
+    }
+
+    async fn store_erroneous_rows_block(
+        &mut self,
+        erroneous_rows: Vec<ErroneousRow>,
+    ) -> Result<()> {
+        self.store("erroneous_rows", erroneous_rows, |main| {
+            &mut main.erroneous_rows
+        });
+        Ok(())
+    }
+
+    async fn store_batches_block(&mut self, batches: Vec<BatchMarker>) -> Result<()> {
+        self.store("batches", batches, |main| &mut main.batches);
+        Ok(())
+    }
+
+    async fn store_delegations_block(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        self.store("delegations", delegations, |main| &mut main.delegations);
+        Ok(())
+    }
+
+    async fn store_undelegations_block(&mut self, undelegations: Vec<Delegation>) -> Result<()> {
+        self.store("undelegations", undelegations, |main| {
+            &mut main.undelegations
+        });
+        Ok(())
+    }
+
+    async fn store_delegations_daily_block(
+        &mut self,
+        delegations_daily: Vec<DelegationsDaily>,
+    ) -> Result<()> {
+        self.store("delegations_daily", delegations_daily, |main| {
+            &mut main.delegations_daily
+        });
+        Ok(())
+    }
+
+    async fn store_blocks_block(&mut self, blocks: Vec<Block>) -> Result<()> {
+        self.store("blocks", blocks, |main| &mut main.blocks);
+        Ok(())
+    }
+
+    async fn store_token_transfers_block(
+        &mut self,
+        token_transfers: Vec<TokenTransfer>,
+    ) -> Result<()> {
+        self.store("token_transfers", token_transfers, |main| {
+            &mut main.token_transfers
+        });
+        Ok(())
+    }
+
+    async fn store_nft_events_block(&mut self, nft_events: Vec<NftEvent>) -> Result<()> {
+        self.store("nft_events", nft_events, |main| &mut main.nft_events);
+        Ok(())
+    }
+
+    async fn store_commission_changes_block(
+        &mut self,
+        commission_changes: Vec<CommissionChange>,
+    ) -> Result<()> {
+        self.store("commission_changes", commission_changes, |main| {
+            &mut main.commission_changes
+        });
+        Ok(())
+    }
+
+    async fn store_claim_events_block(&mut self, claim_events: Vec<ClaimEvent>) -> Result<()> {
+        self.store("claim_events", claim_events, |main| &mut main.claim_events);
+        Ok(())
+    }
+
+    async fn store_entangler_swaps_block(
+        &mut self,
+        entangler_swaps: Vec<EntanglerSwap>,
+    ) -> Result<()> {
+        self.store("entangler_swaps", entangler_swaps, |main| {
+            &mut main.entangler_swaps
+        });
+        Ok(())
+    }
+
+    async fn store_anchor_events_block(&mut self, anchor_events: Vec<AnchorEvent>) -> Result<()> {
+        self.store("anchor_events", anchor_events, |main| {
+            &mut main.anchor_events
+        });
+        Ok(())
+    }
+
+    async fn store_sale_events_block(&mut self, sale_events: Vec<SaleEvent>) -> Result<()> {
+        self.store("nft_sales", sale_events, |main| &mut main.sale_events);
+        Ok(())
+    }
+
+    async fn store_pack_events_block(&mut self, pack_events: Vec<PackEvent>) -> Result<()> {
+        self.store("nft_pack_events", pack_events, |main| &mut main.pack_events);
+        Ok(())
+    }
+
+    async fn store_candy_mints_block(&mut self, candy_mints: Vec<CandyMint>) -> Result<()> {
+        self.store("candy_mints", candy_mints, |main| &mut main.candy_mints);
+        Ok(())
+    }
+
+    async fn store_stake_account_events_block(
+        &mut self,
+        stake_account_events: Vec<StakeAccountEvent>,
+    ) -> Result<()> {
+        self.store("stake_account_events", stake_account_events, |main| {
+            &mut main.stake_account_events
+        });
+        Ok(())
+    }
+
+    async fn store_bid_events_block(&mut self, bid_events: Vec<BidEvent>) -> Result<()> {
+        self.store("bid_events", bid_events, |main| &mut main.bid_events);
+        Ok(())
+    }
+
+    async fn store_sol_transfers_block(&mut self, sol_transfers: Vec<SolTransfer>) -> Result<()> {
+        self.store("sol_transfers", sol_transfers, |main| {
+            &mut main.sol_transfers
+        });
+        Ok(())
+    }
+
+    async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+        Ok(self
+            .main()
+            .blocks
+            .iter()
+            .find(|block| block.slot == slot)
+            .and_then(|block| block.block_time))
+    }
+
+    async fn get_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<Delegation>> {
+        let mut delegations: Vec<_> = self
+            .main()
+            .delegations
+            .iter()
+            .filter(|delegation| {
+                delegation.stake_acc == stake_acc
+                    && (from_slot..=to_slot).contains(&delegation.slot)
+            })
+            .cloned()
+            .collect();
+        delegations.sort_by_key(|delegation| (delegation.slot, delegation.raw_instruction_idx));
+
+        Ok(delegations)
+    }
+
+    async fn fetch_instructions_by_signature(
+        &mut self,
+        tx_signature: &str,
+        page: Page,
+    ) -> Result<Vec<InstructionRow>> {
+        let mut instructions: Vec<_> = self
+            .main()
+            .instructions
+            .iter()
+            .filter(|instruction| instruction.tx_signature == tx_signature)
+            .cloned()
+            .collect();
+        instructions.sort();
+
+        Ok(page.of(instructions.into_iter().map(InstructionRow::from)))
+    }
+
+    async fn fetch_delegations_for_stake_acc(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+        page: Page,
+    ) -> Result<Vec<Delegation>> {
+        let delegations = self
+            .get_delegations_for_stake_acc(stake_acc, from_slot, to_slot)
+            .await?;
+
+        Ok(page.of(delegations))
+    }
+
+    async fn fetch_balances_by_account(
+        &mut self,
+        account: &str,
+        page: Page,
+    ) -> Result<Vec<BalancesRow>> {
+        let mut balances: Vec<_> = self
+            .main()
+            .balances
+            .iter()
+            .filter(|balance| balance.account == account)
+            .cloned()
+            .collect();
+        balances.sort_by(|a, b| {
+            (&a.tx_signature, a.inner_instructions_set)
+                .cmp(&(&b.tx_signature, b.inner_instructions_set))
+        });
+
+        Ok(page.of(balances.into_iter().map(BalancesRow::from)))
+    }
+
+    async fn get_queue_cursor(&mut self, queue: &str) -> Result<Option<QueueCursor>> {
+        Ok(self.main().queue_cursors.get(queue).cloned())
+    }
+
+    async fn store_queue_cursor(&mut self, queue: &str, cursor: QueueCursor) -> Result<()> {
+        let mut main = self.main();
+
+        main.inserts.push(("queue_cursors", 1));
+        let stored = main.queue_cursors.entry(queue.to_string()).or_default();
+        *stored = cursor.max(stored.clone());
+
+        Ok(())
+    }
+
+    async fn get_indexing_watermark(&mut self) -> Result<Option<u64>> {
+        Ok(self.main().indexing_watermarks.iter().copied().max())
+    }
+
+    async fn store_indexing_watermark(&mut self, slot: u64) -> Result<()> {
+        let mut main = self.main();
+
+        main.inserts.push(("indexing_watermark", 1));
+        main.indexing_watermarks.push(slot);
+
+        Ok(())
+    }
+}
@@ -1,29 +1,127 @@
+pub mod file;
 pub mod main_storage;
+pub mod memory;
 pub mod postgre_storage;
+pub mod read_only;
+pub mod sharded;
 // pub mod rabbit_storage;
 
+use self::main_storage::Metadata;
 use self::postgre_storage::models::Delegation;
 use anyhow::Result;
 use async_trait::async_trait;
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 
+/// Transactions of the queue waiting to be parsed, exported to tell whether the analyzer keeps up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    pub pending: u64,
+    pub oldest_pending_slot: Option<u64>,
+    /// Block time of the transaction of `oldest_pending_slot`
+    pub oldest_pending_block_time: Option<i64>,
+}
+
+impl QueueStats {
+    /// Stats of the shards of a queue together, the oldest pending transaction is the one with
+    /// the lowest slot
+    pub fn merge(self, other: QueueStats) -> QueueStats {
+        let oldest = match (self.oldest_pending_slot, other.oldest_pending_slot) {
+            (Some(slot), Some(other_slot)) if other_slot < slot => &other,
+            (None, Some(_)) => &other,
+            _ => &self,
+        };
+
+        QueueStats {
+            pending: self.pending + other.pending,
+            oldest_pending_slot: oldest.oldest_pending_slot,
+            oldest_pending_block_time: oldest.oldest_pending_block_time,
+        }
+    }
+
+    /// Seconds since the block time of the oldest pending transaction, 0 when nothing is pending
+    /// or its block time is not known
+    pub fn oldest_pending_age(&self, now: i64) -> i64 {
+        self.oldest_pending_block_time
+            .map_or(0, |block_time| (now - block_time).max(0))
+    }
+}
+
+/// Slots of the queue the indexing watermark is computed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueProgress {
+    /// Lowest slot of a signature which is neither loaded nor skipped or of a transaction which
+    /// is not parsed
+    pub lowest_unfinished_slot: Option<u64>,
+    /// Highest slot of the transactions of the queue
+    pub highest_slot: Option<u64>,
+}
+
+impl QueueProgress {
+    /// Progress of the shards of a queue together
+    pub fn merge(self, other: QueueProgress) -> QueueProgress {
+        let lowest_unfinished_slot =
+            match (self.lowest_unfinished_slot, other.lowest_unfinished_slot) {
+                (Some(slot), Some(other_slot)) => Some(slot.min(other_slot)),
+                (slot, other_slot) => slot.or(other_slot),
+            };
+
+        QueueProgress {
+            lowest_unfinished_slot,
+            highest_slot: self.highest_slot.max(other.highest_slot),
+        }
+    }
+
+    /// Highest slot up to which every signature of the queue is parsed or skipped: the slot
+    /// before the lowest unfinished one, the highest slot when nothing is unfinished. None for
+    /// an empty queue or an unfinished slot 0
+    pub fn watermark(&self) -> Option<u64> {
+        match self.lowest_unfinished_slot {
+            Some(slot) => slot.checked_sub(1),
+            None => self.highest_slot,
+        }
+    }
+}
+
 #[async_trait]
 pub trait QueueStorage: Send {
     async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta>;
     async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>>;
     async fn save_delegations(&mut self, delegations: Vec<Delegation>) -> Result<()>;
     async fn mark_transaction_as_parsed(&mut self, transactions: String) -> Result<()>;
+    /// The transaction failed to parse and is handed to the erroneous transactions collector, it
+    /// is neither claimed nor reclaimed again
+    async fn mark_transaction_as_erroneous(&mut self, transaction: String) -> Result<()>;
+    async fn reclaim_stuck_transactions(&mut self, timeout: u64) -> Result<usize>;
+    async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>>;
+    async fn mark_blocks_metadata_as_parsed(&mut self, slots: Vec<u64>) -> Result<()>;
+    /// Counted for the metrics on their own interval, never by the parsing loop
+    async fn queue_stats(&mut self) -> Result<QueueStats>;
+    /// Read for the indexing watermark on its own interval as well
+    async fn queue_progress(&mut self) -> Result<QueueProgress>;
 }
 
+/// Limit of the sleep of `repeat_until_ok!` between the attempts
+pub const MAX_REPEAT_SLEEP_SECS: u64 = 60;
+
+/// Repeats the call until it succeeds, the sleep between the attempts starts at `$sleep_time`
+/// seconds and doubles after every failure up to `MAX_REPEAT_SLEEP_SECS`
 #[macro_export]
 macro_rules! repeat_until_ok {
     ( $func:expr, $sleep_time:expr ) => {{
+        let mut sleep_time: u64 = $sleep_time;
         loop {
             match $func {
                 Ok(result) => break result,
                 Err(err) => {
+                    let err = anyhow::Error::from(err);
                     log::error!("Error in func {}: {}", stringify!($func), err);
-                    tokio::time::sleep(std::time::Duration::from_secs($sleep_time)).await;
+                    $crate::metrics_update!(
+                        inc ERRORS_COUNT,
+                        &[$crate::errors::classify(&err).as_str()]
+                    );
+                    tokio::time::sleep(std::time::Duration::from_secs(sleep_time)).await;
+                    sleep_time =
+                        (sleep_time * 2).clamp(1, $crate::storages::MAX_REPEAT_SLEEP_SECS);
                 }
             }
         }
@@ -1,9 +1,15 @@
 #[allow(clippy::extra_unused_lifetimes)]
 pub mod models;
+pub mod reconnecting;
 pub mod schema;
 
-use self::models::{Delegation, Transaction};
-use super::QueueStorage;
+use self::models::{BlockMetadata, Delegation, QueueProgressSlots, Transaction};
+use self::reconnecting::{PgConnectionFactory, ReconnectingConnection};
+use super::{
+    main_storage::{Metadata, QueueCursor},
+    read_only::QueueReader,
+    QueueProgress, QueueStats, QueueStorage,
+};
 
 use crate::errors::PostgreSQLError;
 use anyhow::Result;
@@ -17,13 +23,22 @@ use log::{error, info};
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 use url::Url;
 
+/// Parsing status of the transactions whose JSON can't be read, e.g. truncated by a crash of the
+/// data_loader mid-insert. Their signatures are queued for loading again and the data_loader
+/// replaces the rows
+pub const CORRUPT_TRANSACTION: i32 = 98;
+/// Parsing status of the transactions which failed to parse and are stored in
+/// `erroneous_transactions`. They are left alone until a fixed analyzer is to parse them again,
+/// which is done by setting their status back to 0
+pub const ERRONEOUS_TRANSACTION: i32 = 97;
+
 pub struct PostgreStorage {
-    connection: PgConnection,
+    connection: ReconnectingConnection,
 }
 
 impl PostgreStorage {
     pub async fn new(database_url: &str) -> Result<Self> {
-        let connection = establish_connection(database_url)?;
+        let connection = ReconnectingConnection::new(PgConnectionFactory::new(database_url))?;
         let parsed_url = Url::parse(database_url)?;
         info!(
             "PostgreSQL connection established: {}://******:******@{}{}",
@@ -33,12 +48,100 @@ impl PostgreStorage {
         );
         Ok(PostgreStorage { connection })
     }
+
+    /// Connects and runs a trivial query, for the readiness probe
+    pub fn ping(database_url: &str) -> Result<()> {
+        let connection = establish_connection(database_url)?;
+        diesel::sql_query("SELECT 1").execute(&connection)?;
+        Ok(())
+    }
 }
 
 fn establish_connection(database_url: &str) -> Result<PgConnection, PostgreSQLError> {
     Ok(PgConnection::establish(database_url)?)
 }
 
+fn metadata(block_metadata: BlockMetadata) -> Metadata {
+    Metadata {
+        slot: block_metadata.slot as u64,
+        blockhash: block_metadata.blockhash,
+        rewards: block_metadata.rewards,
+        block_time: block_metadata.block_time,
+        block_height: block_metadata.block_height.map(|height| height as u64),
+    }
+}
+
+/// Stats of the transactions of the `pending` query. The oldest of them is read by the slot
+/// index, the count of the pending rows by the partial index of the data_loader's migrations
+fn queue_stats(
+    conn: &PgConnection,
+    pending: impl Fn() -> schema::transactions::BoxedQuery<'static, diesel::pg::Pg>,
+) -> Result<QueueStats> {
+    use schema::transactions;
+
+    let count = pending().count().get_result::<i64>(conn)?;
+    let oldest = pending()
+        .select((transactions::slot, transactions::block_time))
+        .order(transactions::slot)
+        .first::<(Option<i32>, Option<i32>)>(conn)
+        .optional()?;
+
+    Ok(QueueStats {
+        pending: count as u64,
+        oldest_pending_slot: oldest.and_then(|(slot, _)| slot).map(|slot| slot as u64),
+        oldest_pending_block_time: oldest
+            .and_then(|(_, block_time)| block_time)
+            .map(Into::into),
+    })
+}
+
+/// Lowest slot of the signatures which are pending, in progress or given up on by the data_loader
+/// (`loading_status` 0, 1 and 99) and of the transactions which are pending, in progress or
+/// corrupt, read from the indexes of the statuses, and the highest slot of the transactions from
+/// the slot index. The loaded and the skipped signatures (2 and 3) are finished, as are the parsed
+/// and the erroneous transactions, which wait for a fixed analyzer
+fn queue_progress(conn: &PgConnection) -> Result<QueueProgress> {
+    let slots = diesel::sql_query(
+        "SELECT LEAST( \
+            (SELECT min(slot) FROM transactions WHERE parsing_status = 0), \
+            (SELECT min(slot) FROM transactions WHERE parsing_status IN (2, $1)), \
+            (SELECT min(slot) FROM signatures WHERE loading_status IN (0, 1, 99)) \
+         ) AS lowest_unfinished_slot, \
+         (SELECT max(slot) FROM transactions) AS highest_slot",
+    )
+    .bind::<diesel::sql_types::Integer, _>(CORRUPT_TRANSACTION)
+    .get_result::<QueueProgressSlots>(conn)?;
+
+    Ok(QueueProgress {
+        lowest_unfinished_slot: slots.lowest_unfinished_slot.map(|slot| slot as u64),
+        highest_slot: slots.highest_slot.map(|slot| slot as u64),
+    })
+}
+
+/// Flags the transactions as corrupt and queues their signatures for loading again
+fn mark_corrupt_transactions(conn: &PgConnection, signatures: &[String]) -> Result<()> {
+    use schema::{signatures as signatures_table, transactions};
+
+    conn.transaction::<_, Error, _>(|| {
+        diesel::update(transactions::table.filter(transactions::signature.eq_any(signatures)))
+            .set((
+                transactions::parsing_status.eq(CORRUPT_TRANSACTION),
+                transactions::parsing_started_at.eq(None::<std::time::SystemTime>),
+            ))
+            .execute(conn)?;
+
+        diesel::update(
+            signatures_table::table.filter(signatures_table::signature.eq_any(signatures)),
+        )
+        .set(signatures_table::loading_status.eq(0))
+        .execute(conn)?;
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
 fn _format_or_empty<T: std::fmt::Debug>(val: Option<T>) -> String {
     if val.is_some() {
         format!("{:?}", val.unwrap())
@@ -50,34 +153,61 @@ fn _format_or_empty<T: std::fmt::Debug>(val: Option<T>) -> String {
 #[async_trait]
 impl QueueStorage for PostgreStorage {
     async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
-        use schema::transactions::dsl::*;
-        let conn = &self.connection;
-
-        let query_result = transactions
-            .filter(parsing_status.eq(0))
-            .order(slot)
-            .limit(1000)
-            .load::<Transaction>(conn);
+        // Claim a batch by moving it into the in-progress state, rows locked by
+        // another analyzer are skipped so concurrent instances get disjoint batches
+        let query_result = self.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE transactions SET parsing_status = 2, parsing_started_at = now() \
+                 WHERE signature IN ( \
+                    SELECT signature FROM transactions WHERE parsing_status = 0 \
+                    ORDER BY slot LIMIT 1000 FOR UPDATE SKIP LOCKED \
+                 ) \
+                 RETURNING slot, transaction, block_time, parsing_status, signature",
+            )
+            .load::<Transaction>(conn)?)
+        });
 
         match query_result {
-            Ok(query_result) => {
-                let mut sgntrs = Vec::with_capacity(query_result.len());
+            Ok(mut query_result) => {
+                // RETURNING doesn't keep the order of the subquery
+                query_result.sort_by_key(|tx| tx.slot);
+
+                let mut corrupt = Vec::new();
                 let encoded_confirmed_transactions: Vec<_> = query_result
                     .into_iter()
-                    .map(|tx| {
-                        sgntrs.push(tx.signature.clone());
-                        EncodedConfirmedTransactionWithStatusMeta {
-                            slot: tx.slot.unwrap_or_default() as u64,
-                            transaction: serde_json::from_str(&tx.transaction.unwrap()).unwrap(),
-                            block_time: Some(tx.block_time.unwrap_or_default().into()),
+                    .filter_map(|tx| {
+                        match serde_json::from_str(tx.transaction.as_deref().unwrap_or_default()) {
+                            Ok(transaction) => Some(EncodedConfirmedTransactionWithStatusMeta {
+                                slot: tx.slot.unwrap_or_default() as u64,
+                                transaction,
+                                block_time: tx.block_time(),
+                            }),
+                            Err(err) => {
+                                error!(
+                                    "Transaction {} is corrupt and is loaded again: {}",
+                                    tx.signature, err
+                                );
+                                corrupt.push(tx.signature);
+                                None
+                            }
                         }
                     })
                     .collect();
 
+                // Left claimed if this fails, they are flagged again once they are reclaimed
+                if !corrupt.is_empty() {
+                    if let Err(err) = self
+                        .connection
+                        .run(|conn| mark_corrupt_transactions(conn, &corrupt))
+                    {
+                        error!("Corrupt transactions were not flagged: {:#?}", err);
+                    }
+                }
+
                 encoded_confirmed_transactions
             }
-            Err(err) => match err {
-                Error::NotFound => {
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::NotFound) => {
                     info!("get_transaction: NotFound");
                     vec![]
                 }
@@ -91,35 +221,609 @@ impl QueueStorage for PostgreStorage {
 
     async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
         use schema::delegations::dsl::*;
-        let conn = &self.connection;
 
-        Ok(delegations
-            .filter(stake_acc.eq_any(stake_accs))
-            .load::<Delegation>(conn)?)
+        self.connection.run(|conn| {
+            Ok(delegations
+                .filter(stake_acc.eq_any(&stake_accs))
+                .load::<Delegation>(conn)?)
+        })
     }
 
     async fn save_delegations(&mut self, delegations_vec: Vec<Delegation>) -> Result<()> {
         use schema::delegations;
-        let conn = &self.connection;
 
-        diesel::insert_into(delegations::table)
-            .values(delegations_vec)
-            .on_conflict(delegations::stake_acc)
-            .do_update()
-            .set(delegations::vote_acc.eq(excluded(delegations::vote_acc)))
-            .execute(conn)?;
+        self.connection.run(|conn| {
+            diesel::insert_into(delegations::table)
+                .values(&delegations_vec)
+                .on_conflict(delegations::stake_acc)
+                .do_update()
+                .set((
+                    delegations::vote_acc.eq(excluded(delegations::vote_acc)),
+                    delegations::delegated_amount.eq(excluded(delegations::delegated_amount)),
+                ))
+                .execute(conn)?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     async fn mark_transaction_as_parsed(&mut self, transaction: String) -> Result<()> {
         use schema::transactions;
-        let conn = &self.connection;
 
-        diesel::update(transactions::table)
-            .filter(transactions::signature.eq(transaction))
-            .set(transactions::parsing_status.eq(1))
+        self.connection.run(|conn| {
+            diesel::update(transactions::table)
+                .filter(transactions::signature.eq(&transaction))
+                .set(transactions::parsing_status.eq(1))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    async fn mark_transaction_as_erroneous(&mut self, transaction: String) -> Result<()> {
+        use schema::transactions;
+
+        self.connection.run(|conn| {
+            diesel::update(transactions::table)
+                .filter(transactions::signature.eq(&transaction))
+                .set((
+                    transactions::parsing_status.eq(ERRONEOUS_TRANSACTION),
+                    transactions::parsing_started_at.eq(None::<std::time::SystemTime>),
+                ))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    async fn reclaim_stuck_transactions(&mut self, timeout: u64) -> Result<usize> {
+        self.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE transactions SET parsing_status = 0, parsing_started_at = NULL \
+                 WHERE parsing_status = 2 AND parsing_started_at < now() - $1 * interval '1 second'",
+            )
+            .bind::<diesel::sql_types::BigInt, _>(timeout as i64)
+            .execute(conn)?)
+        })
+    }
+
+    async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+        use schema::blocks_metadata;
+
+        let blocks_metadata = self.connection.run(|conn| {
+            Ok(blocks_metadata::table
+                .filter(blocks_metadata::parsing_status.eq(0))
+                .order(blocks_metadata::slot)
+                .limit(1000)
+                .load::<BlockMetadata>(conn)?)
+        })?;
+
+        Ok(blocks_metadata.into_iter().map(metadata).collect())
+    }
+
+    async fn mark_blocks_metadata_as_parsed(&mut self, slots: Vec<u64>) -> Result<()> {
+        use schema::blocks_metadata;
+        let slots: Vec<i64> = slots.into_iter().map(|slot| slot as i64).collect();
+
+        self.connection.run(|conn| {
+            diesel::update(blocks_metadata::table)
+                .filter(blocks_metadata::slot.eq_any(&slots))
+                .set(blocks_metadata::parsing_status.eq(1))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    async fn queue_stats(&mut self) -> Result<QueueStats> {
+        use schema::transactions;
+
+        self.connection.run(|conn| {
+            queue_stats(conn, || {
+                transactions::table
+                    .filter(transactions::parsing_status.eq(0))
+                    .into_boxed()
+            })
+        })
+    }
+
+    async fn queue_progress(&mut self) -> Result<QueueProgress> {
+        self.connection.run(queue_progress)
+    }
+}
+
+/// Only SELECTs, so a read replica of the data_loader's database can be read
+#[async_trait]
+impl QueueReader for PostgreStorage {
+    /// The rows without their JSON, truncated by the retention of the data_loader, and the
+    /// corrupt ones are skipped, as well as the ones whose JSON can't be read, so a bad row never
+    /// stops the reader
+    async fn read_transactions(
+        &mut self,
+        after: &QueueCursor,
+        limit: usize,
+    ) -> Result<Vec<(QueueCursor, EncodedConfirmedTransactionWithStatusMeta)>> {
+        let transactions = self.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "SELECT slot, transaction, block_time, parsing_status, signature FROM transactions \
+                 WHERE (slot, signature) > ($1, $2) \
+                   AND transaction IS NOT NULL AND parsing_status IS DISTINCT FROM $4 \
+                 ORDER BY slot, signature LIMIT $3",
+            )
+            .bind::<diesel::sql_types::Integer, _>(after.slot as i32)
+            .bind::<diesel::sql_types::Text, _>(&after.signature)
+            .bind::<diesel::sql_types::BigInt, _>(limit as i64)
+            .bind::<diesel::sql_types::Integer, _>(CORRUPT_TRANSACTION)
+            .load::<Transaction>(conn)?)
+        })?;
+
+        Ok(transactions
+            .into_iter()
+            .filter_map(|tx| {
+                let slot = tx.slot.unwrap_or_default() as u64;
+                let block_time = tx.block_time();
+
+                match serde_json::from_str(tx.transaction.as_deref().unwrap_or_default()) {
+                    Ok(transaction) => Some((
+                        QueueCursor {
+                            slot,
+                            signature: tx.signature,
+                        },
+                        EncodedConfirmedTransactionWithStatusMeta {
+                            slot,
+                            transaction,
+                            block_time,
+                        },
+                    )),
+                    Err(err) => {
+                        error!(
+                            "Transaction {} is corrupt and is skipped: {}",
+                            tx.signature, err
+                        );
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    async fn read_blocks_metadata(
+        &mut self,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<Metadata>> {
+        use schema::blocks_metadata;
+
+        let blocks_metadata = self.connection.run(|conn| {
+            let mut query = blocks_metadata::table
+                .order(blocks_metadata::slot)
+                .limit(limit as i64)
+                .into_boxed();
+            if let Some(after) = after {
+                query = query.filter(blocks_metadata::slot.gt(after as i64));
+            }
+
+            Ok(query.load::<BlockMetadata>(conn)?)
+        })?;
+
+        Ok(blocks_metadata.into_iter().map(metadata).collect())
+    }
+
+    async fn read_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+        self.get_delegations(stake_accs).await
+    }
+
+    async fn read_queue_stats(&mut self, after: &QueueCursor) -> Result<QueueStats> {
+        use schema::transactions;
+
+        let slot = after.slot as i32;
+        let signature = after.signature.clone();
+
+        self.connection.run(|conn| {
+            queue_stats(conn, || {
+                transactions::table
+                    .filter(
+                        transactions::slot.gt(slot).or(transactions::slot
+                            .eq(slot)
+                            .and(transactions::signature.gt(signature.clone()))),
+                    )
+                    .into_boxed()
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod postgre_server_tests {
+    use super::*;
+    use crate::block_time_resolver::{BlockTimeResolver, BlockTimeSource};
+    use solana_transaction_status::EncodedTransaction;
+    use std::collections::HashSet;
+
+    const DATABASE_URL: &str = "postgresql://postgres@badaddr/postgres";
+
+    fn setup_transactions(storage: &PostgreStorage, count: usize) -> Result<()> {
+        storage
+            .connection
+            .run(|conn| insert_transactions(conn, count))
+    }
+
+    fn insert_transactions(conn: &PgConnection, count: usize) -> Result<()> {
+        diesel::sql_query(
+            "CREATE TABLE IF NOT EXISTS transactions (
+                slot INTEGER,
+                transaction TEXT,
+                block_time INTEGER,
+                parsing_status INTEGER,
+                signature VARCHAR(88) PRIMARY KEY NOT NULL DEFAULT '',
+                parsing_started_at TIMESTAMP DEFAULT NULL
+            )",
+        )
+        .execute(conn)?;
+        diesel::sql_query("DELETE FROM transactions").execute(conn)?;
+
+        for i in 0..count {
+            let sig = format!("signature_{}", i);
+            diesel::sql_query(
+                "INSERT INTO transactions (slot, transaction, block_time, parsing_status, signature) \
+                 VALUES ($1, $2, 0, 0, $3)",
+            )
+            .bind::<diesel::sql_types::Integer, _>(i as i32)
+            .bind::<diesel::sql_types::Text, _>(format!(r#"{{"transaction":"{}","meta":null}}"#, sig))
+            .bind::<diesel::sql_types::Text, _>(sig)
+            .execute(conn)?;
+        }
+
+        Ok(())
+    }
+
+    fn setup_signatures(storage: &PostgreStorage) -> Result<()> {
+        storage.connection.run(|conn| {
+            diesel::sql_query(
+                "CREATE TABLE IF NOT EXISTS signatures (
+                    signature VARCHAR(88),
+                    slot INTEGER,
+                    err TEXT,
+                    memo TEXT,
+                    block_time INTEGER,
+                    confirmation_status VARCHAR(20),
+                    loading_status INTEGER,
+                    program VARCHAR(44) NOT NULL DEFAULT '',
+                    potential_gap_start BOOLEAN
+                )",
+            )
+            .execute(conn)?;
+            diesel::sql_query("DELETE FROM signatures").execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    fn claimed_signatures(
+        transactions: Vec<EncodedConfirmedTransactionWithStatusMeta>,
+    ) -> Vec<String> {
+        transactions
+            .into_iter()
+            .map(|tx| match tx.transaction.transaction {
+                EncodedTransaction::LegacyBinary(sig) => sig,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn claim_and_reclaim_transactions() -> Result<()> {
+        let mut first_storage = PostgreStorage::new(DATABASE_URL).await?;
+        let mut second_storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&first_storage, 2500)?;
+
+        let mut claimed = Vec::new();
+        loop {
+            let first = claimed_signatures(first_storage.get_transactions().await);
+            let second = claimed_signatures(second_storage.get_transactions().await);
+
+            if first.is_empty() && second.is_empty() {
+                break;
+            }
+
+            claimed.extend(first);
+            claimed.extend(second);
+        }
+
+        let unique: HashSet<_> = claimed.iter().collect();
+        assert_eq!(claimed.len(), 2500);
+        assert_eq!(unique.len(), 2500);
+
+        // Transactions claimed by a dead analyzer go back to the queue after the timeout
+        let mut storage = first_storage;
+        setup_transactions(&storage, 10)?;
+
+        let claimed = storage.get_transactions().await;
+        assert_eq!(claimed.len(), 10);
+        assert!(storage.get_transactions().await.is_empty());
+
+        assert_eq!(storage.reclaim_stuck_transactions(3600).await?, 0);
+
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE transactions SET parsing_started_at = now() - interval '2 hours' \
+                 WHERE parsing_status = 2",
+            )
+            .execute(conn)?)
+        })?;
+
+        assert_eq!(storage.reclaim_stuck_transactions(3600).await?, 10);
+        assert_eq!(storage.get_transactions().await.len(), 10);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn erroneous_transaction_is_not_reclaimed() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&storage, 2)?;
+
+        let claimed = claimed_signatures(storage.get_transactions().await);
+        assert_eq!(claimed, vec!["signature_0", "signature_1"]);
+
+        // The first one fails to parse, the second one is claimed by a dead analyzer
+        storage
+            .mark_transaction_as_erroneous("signature_0".to_string())
+            .await?;
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE transactions SET parsing_started_at = now() - interval '2 hours' \
+                 WHERE parsing_status = 2",
+            )
+            .execute(conn)?)
+        })?;
+
+        assert_eq!(storage.reclaim_stuck_transactions(3600).await?, 1);
+        assert_eq!(
+            claimed_signatures(storage.get_transactions().await),
+            vec!["signature_1"]
+        );
+
+        let parsing_status = storage.connection.run(|conn| {
+            Ok(schema::transactions::table
+                .select(schema::transactions::parsing_status)
+                .filter(schema::transactions::signature.eq("signature_0"))
+                .first::<Option<i32>>(conn)?)
+        })?;
+        assert_eq!(parsing_status, Some(ERRONEOUS_TRANSACTION));
+
+        Ok(())
+    }
+
+    /// Knows the block time of every slot, as the stored blocks do
+    struct SlotBlockTimes;
+
+    #[async_trait]
+    impl BlockTimeSource for SlotBlockTimes {
+        async fn get_block_time(&mut self, slot: u64) -> Result<Option<i64>> {
+            Ok(Some(1643213000 + slot as i64))
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_block_time_is_resolved() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&storage, 3)?;
+
+        // Stored by the data_loader without a block time, its older versions stored 0 instead
+        storage.connection.run(|conn| {
+            diesel::sql_query(
+                "UPDATE transactions SET block_time = NULL WHERE signature = 'signature_0'",
+            )
+            .execute(conn)?;
+            diesel::sql_query(
+                "UPDATE transactions SET block_time = 1643213402 WHERE signature = 'signature_2'",
+            )
+            .execute(conn)?;
+
+            Ok(())
+        })?;
+
+        let mut batch = storage.get_transactions().await;
+        let block_times = |batch: &[EncodedConfirmedTransactionWithStatusMeta]| {
+            batch.iter().map(|tx| tx.block_time).collect::<Vec<_>>()
+        };
+        assert_eq!(block_times(&batch), [None, None, Some(1643213402)]);
+
+        let mut resolver = BlockTimeResolver::with_sources(16, Box::new(SlotBlockTimes), None);
+        resolver.resolve_batch(&mut batch).await;
+        assert_eq!(
+            block_times(&batch),
+            [Some(1643213000), Some(1643213001), Some(1643213402)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupt_transaction_is_flagged_for_reload() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&storage, 3)?;
+
+        setup_signatures(&storage)?;
+
+        storage.connection.run(|conn| {
+            diesel::sql_query(
+                "INSERT INTO signatures (signature, loading_status) VALUES ('signature_1', 2)",
+            )
+            .execute(conn)?;
+
+            // Truncated by a crash of the data_loader mid-insert
+            diesel::sql_query(
+                "UPDATE transactions SET transaction = '{\"transaction\":\"sig' \
+                 WHERE signature = 'signature_1'",
+            )
+            .execute(conn)?;
+
+            Ok(())
+        })?;
+
+        let claimed = claimed_signatures(storage.get_transactions().await);
+        assert_eq!(claimed, vec!["signature_0", "signature_2"]);
+        assert!(storage.get_transactions().await.is_empty());
+
+        let (parsing_status, loading_status) = storage.connection.run(|conn| {
+            let parsing_status = schema::transactions::table
+                .select(schema::transactions::parsing_status)
+                .filter(schema::transactions::signature.eq("signature_1"))
+                .first::<Option<i32>>(conn)?;
+            let loading_status = schema::signatures::table
+                .select(schema::signatures::loading_status)
+                .filter(schema::signatures::signature.eq("signature_1"))
+                .first::<Option<i32>>(conn)?;
+
+            Ok((parsing_status, loading_status))
+        })?;
+        assert_eq!(parsing_status, Some(CORRUPT_TRANSACTION));
+        assert_eq!(loading_status, Some(0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bad_rows_are_skipped_by_the_reader() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&storage, 5)?;
+
+        storage.connection.run(|conn| {
+            // Truncated by the retention of the data_loader
+            diesel::sql_query(
+                "UPDATE transactions SET transaction = NULL WHERE signature = 'signature_1'",
+            )
+            .execute(conn)?;
+            diesel::sql_query(
+                "UPDATE transactions SET parsing_status = $1 WHERE signature = 'signature_2'",
+            )
+            .bind::<diesel::sql_types::Integer, _>(CORRUPT_TRANSACTION)
             .execute(conn)?;
+            // Not flagged yet
+            diesel::sql_query(
+                "UPDATE transactions SET transaction = '{\"transaction\":\"sig' \
+                 WHERE signature = 'signature_3'",
+            )
+            .execute(conn)?;
+
+            Ok(())
+        })?;
+
+        let read = storage
+            .read_transactions(&QueueCursor::default(), 3)
+            .await?;
+        let cursors: Vec<_> = read.iter().map(|(cursor, _)| cursor.clone()).collect();
+        assert_eq!(
+            cursors,
+            vec![
+                QueueCursor {
+                    slot: 0,
+                    signature: "signature_0".to_string(),
+                },
+                QueueCursor {
+                    slot: 4,
+                    signature: "signature_4".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            claimed_signatures(read.into_iter().map(|(_, tx)| tx).collect()),
+            vec!["signature_0", "signature_4"]
+        );
+
+        assert!(storage.read_transactions(&cursors[1], 3).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queue_progress_is_read_from_the_statuses() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_transactions(&storage, 0)?;
+        setup_signatures(&storage)?;
+
+        assert_eq!(storage.queue_progress().await?.watermark(), None);
+
+        // Slots 0, 1 and 2 with the middle one pending
+        setup_transactions(&storage, 3)?;
+        for signature in ["signature_0", "signature_2"] {
+            storage
+                .mark_transaction_as_parsed(signature.to_string())
+                .await?;
+        }
+        assert_eq!(
+            storage.queue_progress().await?,
+            QueueProgress {
+                lowest_unfinished_slot: Some(1),
+                highest_slot: Some(2),
+            }
+        );
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(0));
+
+        storage
+            .mark_transaction_as_parsed("signature_1".to_string())
+            .await?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(2));
+
+        // A signature whose transaction is not loaded yet, then skipped by the data_loader
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "INSERT INTO signatures (signature, slot, loading_status) \
+                 VALUES ('signature_3', 1, 0)",
+            )
+            .execute(conn)?)
+        })?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(0));
+
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE signatures SET loading_status = 3 WHERE signature = 'signature_3'",
+            )
+            .execute(conn)?)
+        })?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(2));
+
+        // A loaded signature is finished, one being loaded is not
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "INSERT INTO signatures (signature, slot, loading_status) \
+                 VALUES ('signature_4', 1, 2), ('signature_5', 2, 1)",
+            )
+            .execute(conn)?)
+        })?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(1));
+
+        storage.connection.run(|conn| {
+            Ok(diesel::sql_query(
+                "UPDATE signatures SET loading_status = 2 WHERE signature = 'signature_5'",
+            )
+            .execute(conn)?)
+        })?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn erroneous_transaction_does_not_stall_the_watermark() -> Result<()> {
+        let mut storage = PostgreStorage::new(DATABASE_URL).await?;
+        setup_signatures(&storage)?;
+        setup_transactions(&storage, 3)?;
+
+        assert_eq!(storage.get_transactions().await.len(), 3);
+        for signature in ["signature_0", "signature_2"] {
+            storage
+                .mark_transaction_as_parsed(signature.to_string())
+                .await?;
+        }
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(0));
+
+        storage
+            .mark_transaction_as_erroneous("signature_1".to_string())
+            .await?;
+        assert_eq!(storage.queue_progress().await?.watermark(), Some(2));
 
         Ok(())
     }
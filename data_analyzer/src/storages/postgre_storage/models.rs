@@ -59,9 +59,41 @@ pub struct Transaction {
     pub signature: String,
 }
 
-#[derive(Insertable, QueryableByName, Queryable, Debug, PartialEq, Eq)]
+impl Transaction {
+    /// None when the data_loader didn't get the block time, stored as NULL or as 0 by its older
+    /// versions, so the block time resolver looks it up
+    pub fn block_time(&self) -> Option<i64> {
+        self.block_time
+            .filter(|block_time| *block_time != 0)
+            .map(Into::into)
+    }
+}
+
+#[derive(Insertable, QueryableByName, Queryable, Clone, Debug, PartialEq, Eq)]
 #[table_name = "delegations"]
 pub struct Delegation {
     pub stake_acc: String,
     pub vote_acc: Option<String>,
+    /// Lamports of the stake account delegated to `vote_acc`, unknown for the rows saved
+    /// before it was tracked
+    pub delegated_amount: Option<i64>,
+}
+
+#[derive(Queryable, Debug, PartialEq, Eq)]
+pub struct BlockMetadata {
+    pub slot: i64,
+    pub blockhash: String,
+    pub rewards: String,
+    pub block_time: Option<i64>,
+    pub block_height: Option<i64>,
+    pub parsing_status: i32,
+}
+
+/// Slots of the query of `queue_progress`
+#[derive(QueryableByName, Debug, PartialEq, Eq)]
+pub struct QueueProgressSlots {
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Integer>"]
+    pub lowest_unfinished_slot: Option<i32>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Integer>"]
+    pub highest_slot: Option<i32>,
 }
@@ -0,0 +1,242 @@
+use std::{cell::RefCell, thread, time::Duration};
+
+use anyhow::Result;
+use diesel::{
+    pg::PgConnection,
+    result::{ConnectionError, DatabaseErrorKind, Error},
+    Connection,
+};
+use log::warn;
+
+use crate::errors::PostgreSQLError;
+
+/// Attempts to establish a lost connection before the operation fails
+const RECONNECT_ATTEMPTS: u32 = 5;
+/// Delay after the first failed attempt, it doubles after every next one
+const RECONNECT_DELAY: Duration = Duration::from_millis(200);
+
+/// Messages of the errors libpq reports for a connection the server has closed, diesel 1.4
+/// doesn't expose their SQLSTATE
+const CONNECTION_ERROR_MESSAGES: &[&str] = &[
+    "server closed the connection",
+    "terminating connection",
+    "no connection to the server",
+    "could not receive data from server",
+    "could not send data to server",
+    "connection has been closed",
+];
+
+/// Opens the connections of a `ReconnectingConnection`
+pub trait ConnectionFactory {
+    type Connection;
+
+    fn connect(&self) -> Result<Self::Connection, ConnectionError>;
+}
+
+pub struct PgConnectionFactory {
+    database_url: String,
+}
+
+impl PgConnectionFactory {
+    pub fn new(database_url: &str) -> Self {
+        Self {
+            database_url: database_url.to_string(),
+        }
+    }
+}
+
+impl ConnectionFactory for PgConnectionFactory {
+    type Connection = PgConnection;
+
+    fn connect(&self) -> Result<PgConnection, ConnectionError> {
+        PgConnection::establish(&self.database_url)
+    }
+}
+
+/// Connection which is established again when it's lost, e.g. after PostgreSQL restarts. An
+/// operation failed with a connection-level error is run once more on the new connection
+pub struct ReconnectingConnection<F: ConnectionFactory = PgConnectionFactory> {
+    factory: F,
+    /// None once the connection is lost and couldn't be established again
+    connection: RefCell<Option<F::Connection>>,
+    reconnect_delay: Duration,
+}
+
+impl<F: ConnectionFactory> ReconnectingConnection<F> {
+    /// Connects right away, a database which can't be reached fails the start
+    pub fn new(factory: F) -> Result<Self> {
+        let connection = factory.connect().map_err(PostgreSQLError::from)?;
+
+        Ok(Self {
+            factory,
+            connection: RefCell::new(Some(connection)),
+            reconnect_delay: RECONNECT_DELAY,
+        })
+    }
+
+    /// Runs the operation, after a connection-level error it's run once more on a new connection
+    /// and the error of the second attempt is returned
+    pub fn run<T>(&self, operation: impl Fn(&F::Connection) -> Result<T>) -> Result<T> {
+        let mut connection = self.connection.borrow_mut();
+
+        if connection.is_none() {
+            *connection = Some(self.reconnect()?);
+        }
+
+        match operation(connection.as_ref().unwrap()) {
+            Err(err) if is_connection_error(&err) => {
+                warn!("Lost the PostgreSQL connection, reconnecting: {}", err);
+                *connection = None;
+                *connection = Some(self.reconnect()?);
+
+                operation(connection.as_ref().unwrap())
+            }
+            result => result,
+        }
+    }
+
+    /// Establishes a new connection, the failed attempts are repeated with a growing delay
+    fn reconnect(&self) -> Result<F::Connection> {
+        let mut delay = self.reconnect_delay;
+        let mut attempt = 1;
+
+        loop {
+            match self.factory.connect() {
+                Ok(connection) => return Ok(connection),
+                Err(err) if attempt < RECONNECT_ATTEMPTS => {
+                    warn!(
+                        "PostgreSQL connection attempt {} failed, next in {:?}: {}",
+                        attempt, delay, err
+                    );
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(err) => return Err(PostgreSQLError::from(err).into()),
+            }
+        }
+    }
+}
+
+/// Whether the connection the error was returned by is lost
+fn is_connection_error(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<Error>() {
+        Some(Error::DatabaseError(DatabaseErrorKind::UnableToSendCommand, _)) => true,
+        Some(Error::DatabaseError(_, info)) => CONNECTION_ERROR_MESSAGES
+            .iter()
+            .any(|message| info.message().contains(message)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Hands out the numbers of the connection attempts, the ones in `failing` fail
+    struct MockFactory {
+        attempts: Cell<u32>,
+        failing: Vec<u32>,
+    }
+
+    impl ConnectionFactory for MockFactory {
+        type Connection = u32;
+
+        fn connect(&self) -> Result<u32, ConnectionError> {
+            let attempt = self.attempts.get() + 1;
+            self.attempts.set(attempt);
+
+            if self.failing.contains(&attempt) {
+                return Err(ConnectionError::BadConnection("refused".to_string()));
+            }
+
+            Ok(attempt)
+        }
+    }
+
+    fn connection(failing: Vec<u32>) -> ReconnectingConnection<MockFactory> {
+        let mut connection = ReconnectingConnection::new(MockFactory {
+            attempts: Cell::new(0),
+            failing,
+        })
+        .unwrap();
+        connection.reconnect_delay = Duration::ZERO;
+
+        connection
+    }
+
+    fn lost_connection() -> anyhow::Error {
+        Error::DatabaseError(
+            DatabaseErrorKind::__Unknown,
+            Box::new("server closed the connection unexpectedly".to_string()),
+        )
+        .into()
+    }
+
+    #[test]
+    fn operation_is_retried_on_a_new_connection() {
+        // The first reconnection attempt fails as well
+        let connection = connection(vec![2]);
+
+        let result = connection.run(|connection| match connection {
+            1 => Err(lost_connection()),
+            connection => Ok(*connection),
+        });
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(connection.factory.attempts.get(), 3);
+        // The new connection is kept
+        assert_eq!(connection.run(|connection| Ok(*connection)).unwrap(), 3);
+    }
+
+    #[test]
+    fn error_of_the_retry_is_returned() {
+        let connection = connection(vec![]);
+        let runs = Cell::new(0);
+
+        let result = connection.run(|_| -> Result<()> {
+            runs.set(runs.get() + 1);
+            Err(Error::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand,
+                Box::new("no connection to the server".to_string()),
+            )
+            .into())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let connection = connection(vec![]);
+        let runs = Cell::new(0);
+
+        let result = connection.run(|_| -> Result<()> {
+            runs.set(runs.get() + 1);
+            Err(Error::NotFound.into())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(runs.get(), 1);
+        assert_eq!(connection.factory.attempts.get(), 1);
+    }
+
+    #[test]
+    fn failed_reconnection_is_repeated_by_the_next_operation() {
+        let connection = connection((2..=RECONNECT_ATTEMPTS + 1).collect());
+
+        let result = connection.run(|connection| match connection {
+            1 => Err(lost_connection()),
+            connection => Ok(*connection),
+        });
+        assert!(result.is_err());
+
+        assert_eq!(
+            connection.run(|connection| Ok(*connection)).unwrap(),
+            RECONNECT_ATTEMPTS + 2
+        );
+    }
+}
@@ -1,7 +1,19 @@
+table! {
+    blocks_metadata (slot) {
+        slot -> Int8,
+        blockhash -> Text,
+        rewards -> Text,
+        block_time -> Nullable<Int8>,
+        block_height -> Nullable<Int8>,
+        parsing_status -> Int4,
+    }
+}
+
 table! {
     delegations (stake_acc) {
         stake_acc -> Text,
         vote_acc -> Nullable<Text>,
+        delegated_amount -> Nullable<Int8>,
     }
 }
 
@@ -34,6 +46,7 @@ table! {
         block_time -> Nullable<Int4>,
         parsing_status -> Nullable<Int4>,
         signature -> Varchar,
+        parsing_started_at -> Nullable<Timestamp>,
     }
 }
 
@@ -1,8 +1,8 @@
-use super::{main_storage::Metadata, QueueStorage};
+use super::{main_storage::Metadata, QueueProgress, QueueStats, QueueStorage};
 use anyhow::Result;
 use async_trait::async_trait;
 use futures_lite::stream::StreamExt;
-use lapin::{options::*, types::FieldTable, Connection, ConnectionProperties, Consumer};
+use lapin::{options::*, types::FieldTable, Channel, Connection, ConnectionProperties, Consumer};
 use log::{error, info};
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 
@@ -11,7 +11,10 @@ use serialization::{deserialize_metadata, deserialize_transaction};
 mod serialization;
 
 pub struct RabbitStorage {
+    channel: Channel,
     consumer: Consumer,
+    /// Name of the consumed queue
+    queue: &'static str,
 }
 
 impl RabbitStorage {
@@ -24,6 +27,11 @@ impl RabbitStorage {
 
         info!("Channel created");
 
+        let queue = match queue_type {
+            QueueType::Transaction => "Transactions",
+            QueueType::Metadata => "Metadata",
+        };
+
         let consumer = match queue_type {
             QueueType::Transaction => {
                 let transaction_consumer = channel
@@ -54,7 +62,11 @@ impl RabbitStorage {
             }
         };
 
-        Ok(Self { consumer })
+        Ok(Self {
+            channel,
+            consumer,
+            queue,
+        })
     }
 }
 
@@ -129,4 +141,31 @@ impl QueueStorage for RabbitStorage {
             None
         }
     }
+
+    /// Messages ready to be delivered, the slots of the messages are not known before they are
+    /// consumed
+    async fn queue_stats(&mut self) -> Result<QueueStats> {
+        let queue = self
+            .channel
+            .queue_declare(
+                self.queue,
+                QueueDeclareOptions {
+                    passive: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        Ok(QueueStats {
+            pending: queue.message_count() as u64,
+            ..QueueStats::default()
+        })
+    }
+
+    /// The slots of the messages are not known before they are consumed, so there is no
+    /// watermark
+    async fn queue_progress(&mut self) -> Result<QueueProgress> {
+        Ok(QueueProgress::default())
+    }
 }
@@ -3,14 +3,20 @@ use crate::errors::RabbitMQError;
 use anyhow::Result;
 use metadata_generated::metadata::*;
 use solana_program::message::MessageHeader;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{TransactionError, TransactionVersion};
+use solana_sdk::transaction_context::TransactionReturnData;
 use solana_transaction_status::{
     option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
     EncodedTransaction, EncodedTransactionWithStatusMeta, Reward, RewardType, UiAddressTableLookup,
-    UiCompiledInstruction, UiInnerInstructions, UiInstruction, UiMessage, UiRawMessage,
-    UiTransaction, UiTransactionStatusMeta, UiTransactionTokenBalance,
+    UiCompiledInstruction, UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiMessage,
+    UiRawMessage, UiTransaction, UiTransactionReturnData, UiTransactionStatusMeta,
+    UiTransactionTokenBalance,
 };
 use transaction_info_generated::transaction_info::{
-    root_as_transaction_info, RewardType as TransactionInfoRewardType, SanitizedMessage,
+    root_as_transaction_info, Pubkey as TransactionInfoPubkey,
+    RewardType as TransactionInfoRewardType, SanitizedMessage, SanitizedTransaction,
+    TransactionReturnData as TransactionInfoReturnData,
 };
 
 use rust_base58::ToBase58;
@@ -28,7 +34,11 @@ pub fn deserialize_metadata(data: &[u8]) -> Result<NativeMetadata> {
         slot: metadata.slot(),
         blockhash: metadata.blockhash().unwrap().to_string(),
         rewards: metadata.rewards().unwrap().to_string(),
-        block_time: metadata.block_time(),
+        block_time: if metadata.block_time() == 0 {
+            None
+        } else {
+            Some(metadata.block_time())
+        },
         block_height: if metadata.block_height() == 0 {
             None
         } else {
@@ -37,15 +47,68 @@ pub fn deserialize_metadata(data: &[u8]) -> Result<NativeMetadata> {
     })
 }
 
+/// The transaction error is passed as JSON, the same way RPC returns it in `meta.err`
+fn deserialize_transaction_error(err: Option<&str>) -> Result<Option<TransactionError>> {
+    Ok(err.map(serde_json::from_str).transpose()?)
+}
+
+/// Addresses loaded from the lookup tables of a v0 message, the accounts with the indexes after
+/// `account_keys`: the writable ones first, then the readonly ones
+fn deserialize_loaded_addresses(
+    sanitized_transaction: &SanitizedTransaction,
+) -> OptionSerializer<UiLoadedAddresses> {
+    let loaded_addresses = match sanitized_transaction
+        .message_as_v0()
+        .and_then(|v0_message| v0_message.loaded_addresses())
+    {
+        Some(loaded_addresses) => loaded_addresses,
+        None => return OptionSerializer::None,
+    };
+
+    OptionSerializer::Some(UiLoadedAddresses {
+        writable: pubkeys_to_base58(loaded_addresses.writable()),
+        readonly: pubkeys_to_base58(loaded_addresses.readonly()),
+    })
+}
+
+/// Data the last program set with `sol_set_return_data`, base64 encoded the way RPC returns it
+fn deserialize_return_data(
+    return_data: Option<TransactionInfoReturnData>,
+) -> OptionSerializer<UiTransactionReturnData> {
+    return_data
+        .and_then(|return_data| {
+            let program_id = Pubkey::try_from(return_data.program_id()?.key()?).ok()?;
+
+            Some(UiTransactionReturnData::from(TransactionReturnData {
+                program_id,
+                data: return_data.data().unwrap_or_default().to_vec(),
+            }))
+        })
+        .into()
+}
+
+fn pubkeys_to_base58(
+    keys: Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<TransactionInfoPubkey>>>,
+) -> Vec<String> {
+    keys.map(|keys| {
+        keys.iter()
+            .map(|key| key.key().unwrap().to_base58())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
 pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
     let transaction_info = root_as_transaction_info(data)?;
 
     let slot: u64 = transaction_info.slot();
     let transaction = {
+        let sanitized_transaction = transaction_info.transaction().unwrap();
         let meta_info = transaction_info.transaction_meta().unwrap();
+        let err = deserialize_transaction_error(meta_info.err())?;
         let meta = Some(UiTransactionStatusMeta {
-            err: None,
-            status: Ok(()),
+            err: err.clone(),
+            status: err.map_or(Ok(()), Err),
             fee: meta_info.fee(),
             pre_balances: meta_info.pre_balances().unwrap().safe_slice().into(),
             post_balances: meta_info.post_balances().unwrap().safe_slice().into(),
@@ -154,18 +217,17 @@ pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactio
                     .collect(),
             )
             .into(),
-            loaded_addresses: OptionSerializer::None,
-            return_data: OptionSerializer::None,
-            compute_units_consumed: OptionSerializer::None,
+            loaded_addresses: deserialize_loaded_addresses(&sanitized_transaction),
+            return_data: deserialize_return_data(meta_info.return_data()),
+            compute_units_consumed: meta_info.compute_units_consumed().into(),
         });
 
-        let sanitized_transaction = transaction_info.transaction().unwrap();
         let message_type = sanitized_transaction.message_type();
-        let message = match message_type {
+        let (version, message) = match message_type {
             SanitizedMessage::Legacy => {
                 let legacy_message = sanitized_transaction.message_as_legacy().unwrap();
 
-                UiMessage::Raw(UiRawMessage {
+                let message = UiMessage::Raw(UiRawMessage {
                     header: MessageHeader {
                         num_readonly_signed_accounts: legacy_message
                             .header()
@@ -198,13 +260,15 @@ pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactio
                         })
                         .collect(),
                     address_table_lookups: None,
-                })
+                });
+
+                (TransactionVersion::LEGACY, message)
             }
             SanitizedMessage::V0 => {
                 let v0_message = sanitized_transaction.message_as_v0().unwrap();
                 let message = v0_message.message().unwrap();
 
-                UiMessage::Raw(UiRawMessage {
+                let message = UiMessage::Raw(UiRawMessage {
                     header: MessageHeader {
                         num_readonly_signed_accounts: message
                             .header()
@@ -253,7 +317,9 @@ pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactio
                             })
                             .collect(),
                     ),
-                })
+                });
+
+                (TransactionVersion::Number(0), message)
             }
             _ => {
                 return Err(anyhow::anyhow!(RabbitMQError::DeserializationError(
@@ -275,7 +341,7 @@ pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactio
         EncodedTransactionWithStatusMeta {
             transaction,
             meta,
-            version: None,
+            version: Some(version),
         }
     };
 
@@ -285,3 +351,520 @@ pub fn deserialize_transaction(data: &[u8]) -> Result<EncodedConfirmedTransactio
         block_time: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flatbuffers::{FlatBufferBuilder, WIPOffset};
+    use solana_sdk::instruction::InstructionError;
+    use solana_transaction_status::UiReturnDataEncoding;
+    use transaction_info_generated::transaction_info as fb;
+
+    fn pubkey<'a>(fbb: &mut FlatBufferBuilder<'a>, key: &Pubkey) -> WIPOffset<fb::Pubkey<'a>> {
+        let key = fbb.create_vector(key.as_ref());
+        fb::Pubkey::create(fbb, &fb::PubkeyArgs { key: Some(key) })
+    }
+
+    /// A v0 transaction of a single instruction of the program loaded from a lookup table
+    fn v0_transaction(
+        account_key: &Pubkey,
+        loaded_writable: &[Pubkey],
+        err: Option<&str>,
+        compute_units_consumed: Option<u64>,
+        return_data: Option<(&Pubkey, &[u8])>,
+    ) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+
+        let header = fb::MessageHeader::create(
+            &mut fbb,
+            &fb::MessageHeaderArgs {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+        );
+        let account_keys = vec![pubkey(&mut fbb, account_key)];
+        let account_keys = fbb.create_vector(&account_keys);
+        let recent_blockhash = fbb.create_vector(&[0u8; 32]);
+        let instruction_accounts = fbb.create_vector(&[0u8, 1]);
+        let instruction_data = fbb.create_vector(&[1u8, 2, 3]);
+        let instruction = fb::CompiledInstruction::create(
+            &mut fbb,
+            &fb::CompiledInstructionArgs {
+                program_id_index: 2,
+                accounts: Some(instruction_accounts),
+                data: Some(instruction_data),
+            },
+        );
+        let instructions = fbb.create_vector(&[instruction]);
+        let lookup_table = pubkey(&mut fbb, &Pubkey::new_unique());
+        let writable_indexes: Vec<u8> = (0..loaded_writable.len() as u8).collect();
+        let writable_indexes = fbb.create_vector(&writable_indexes);
+        let readonly_indexes = fbb.create_vector(&[0u8]);
+        let lookup = fb::MessageAddressTableLookup::create(
+            &mut fbb,
+            &fb::MessageAddressTableLookupArgs {
+                account_key: Some(lookup_table),
+                writable_indexes: Some(writable_indexes),
+                readonly_indexes: Some(readonly_indexes),
+            },
+        );
+        let address_table_lookups = fbb.create_vector(&[lookup]);
+        let message = fb::MessageV0::create(
+            &mut fbb,
+            &fb::MessageV0Args {
+                header: Some(header),
+                account_keys: Some(account_keys),
+                recent_blockhash: Some(recent_blockhash),
+                instructions: Some(instructions),
+                address_table_lookups: Some(address_table_lookups),
+            },
+        );
+
+        let writable: Vec<_> = loaded_writable
+            .iter()
+            .map(|key| pubkey(&mut fbb, key))
+            .collect();
+        let writable = fbb.create_vector(&writable);
+        let readonly = vec![pubkey(&mut fbb, &Pubkey::default())];
+        let readonly = fbb.create_vector(&readonly);
+        let loaded_addresses = fb::LoadedAddresses::create(
+            &mut fbb,
+            &fb::LoadedAddressesArgs {
+                writable: Some(writable),
+                readonly: Some(readonly),
+            },
+        );
+        let message = fb::LoadedMessageV0::create(
+            &mut fbb,
+            &fb::LoadedMessageV0Args {
+                message: Some(message),
+                loaded_addresses: Some(loaded_addresses),
+            },
+        );
+
+        let signature_key = fbb.create_vector(&[1u8; 64]);
+        let signature = fb::Signature::create(
+            &mut fbb,
+            &fb::SignatureArgs {
+                key: Some(signature_key),
+            },
+        );
+        let signatures = fbb.create_vector(&[signature]);
+        let transaction = fb::SanitizedTransaction::create(
+            &mut fbb,
+            &fb::SanitizedTransactionArgs {
+                message_type: fb::SanitizedMessage::V0,
+                message: Some(message.as_union_value()),
+                signatures: Some(signatures),
+                ..Default::default()
+            },
+        );
+
+        let balances = fbb.create_vector(&[1u64, 2, 3, 4]);
+        let inner_instructions = fbb.create_vector::<WIPOffset<fb::InnerInstructions>>(&[]);
+        let log_messages = fbb.create_vector::<WIPOffset<&str>>(&[]);
+        let token_balances = fbb.create_vector::<WIPOffset<fb::TransactionTokenBalance>>(&[]);
+        let rewards = fbb.create_vector::<WIPOffset<fb::Reward>>(&[]);
+        let err = err.map(|err| fbb.create_string(err));
+        let return_data = return_data.map(|(program_id, data)| {
+            let program_id = pubkey(&mut fbb, program_id);
+            let data = fbb.create_vector(data);
+            fb::TransactionReturnData::create(
+                &mut fbb,
+                &fb::TransactionReturnDataArgs {
+                    program_id: Some(program_id),
+                    data: Some(data),
+                },
+            )
+        });
+        let transaction_meta = fb::TransactionStatusMeta::create(
+            &mut fbb,
+            &fb::TransactionStatusMetaArgs {
+                status: err.is_none(),
+                fee: 5000,
+                pre_balances: Some(balances),
+                post_balances: Some(balances),
+                inner_instructions: Some(inner_instructions),
+                log_messages: Some(log_messages),
+                pre_token_balances: Some(token_balances),
+                post_token_balances: Some(token_balances),
+                rewards: Some(rewards),
+                err,
+                compute_units_consumed,
+                return_data,
+            },
+        );
+
+        let transaction_info = fb::TransactionInfo::create(
+            &mut fbb,
+            &fb::TransactionInfoArgs {
+                signature: Some(signature),
+                is_vote: false,
+                slot: 100,
+                transaction: Some(transaction),
+                transaction_meta: Some(transaction_meta),
+            },
+        );
+        fbb.finish(transaction_info, None);
+
+        fbb.finished_data().to_vec()
+    }
+
+    /// Instruction of the program at the index 2 with the data of a single byte
+    fn compiled_instruction<'a>(
+        fbb: &mut FlatBufferBuilder<'a>,
+        data: u8,
+    ) -> WIPOffset<fb::CompiledInstruction<'a>> {
+        let accounts = fbb.create_vector(&[0u8, 1]);
+        let data = fbb.create_vector(&[data]);
+        fb::CompiledInstruction::create(
+            fbb,
+            &fb::CompiledInstructionArgs {
+                program_id_index: 2,
+                accounts: Some(accounts),
+                data: Some(data),
+            },
+        )
+    }
+
+    /// Inner instructions of the top-level instructions of `inner_order_transaction` as
+    /// (index, data of the inner instructions), in the order RPC returns them
+    const INNER_SETS: [(u8, &[u8]); 3] = [(0, &[10, 11]), (1, &[20]), (3, &[40, 41, 42])];
+
+    /// A legacy transaction of four top-level instructions of `program` and its inner sets in the
+    /// order of the positions of `INNER_SETS` given by `inner_order`
+    fn inner_order_transaction(program: &Pubkey, inner_order: &[usize]) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+
+        let header = fb::MessageHeader::create(
+            &mut fbb,
+            &fb::MessageHeaderArgs {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+        );
+        let account_keys = vec![
+            pubkey(&mut fbb, &Pubkey::new_from_array([1; 32])),
+            pubkey(&mut fbb, &Pubkey::new_from_array([2; 32])),
+            pubkey(&mut fbb, program),
+        ];
+        let account_keys = fbb.create_vector(&account_keys);
+        let recent_blockhash = fbb.create_vector(&[0u8; 32]);
+        let instructions: Vec<_> = (0..4)
+            .map(|data| compiled_instruction(&mut fbb, data))
+            .collect();
+        let instructions = fbb.create_vector(&instructions);
+        let message = fb::LegacyMessage::create(
+            &mut fbb,
+            &fb::LegacyMessageArgs {
+                header: Some(header),
+                account_keys: Some(account_keys),
+                recent_blockhash: Some(recent_blockhash),
+                instructions: Some(instructions),
+            },
+        );
+
+        let signature_key = fbb.create_vector(&[1u8; 64]);
+        let signature = fb::Signature::create(
+            &mut fbb,
+            &fb::SignatureArgs {
+                key: Some(signature_key),
+            },
+        );
+        let signatures = fbb.create_vector(&[signature]);
+        let transaction = fb::SanitizedTransaction::create(
+            &mut fbb,
+            &fb::SanitizedTransactionArgs {
+                message_type: fb::SanitizedMessage::Legacy,
+                message: Some(message.as_union_value()),
+                signatures: Some(signatures),
+                ..Default::default()
+            },
+        );
+
+        let inner_instructions: Vec<_> = inner_order
+            .iter()
+            .map(|position| {
+                let (index, data) = INNER_SETS[*position];
+                let instructions: Vec<_> = data
+                    .iter()
+                    .map(|data| compiled_instruction(&mut fbb, *data))
+                    .collect();
+                let instructions = fbb.create_vector(&instructions);
+
+                fb::InnerInstructions::create(
+                    &mut fbb,
+                    &fb::InnerInstructionsArgs {
+                        index,
+                        instructions: Some(instructions),
+                    },
+                )
+            })
+            .collect();
+        let inner_instructions = fbb.create_vector(&inner_instructions);
+        let balances = fbb.create_vector::<u64>(&[]);
+        let log_messages = fbb.create_vector::<WIPOffset<&str>>(&[]);
+        let token_balances = fbb.create_vector::<WIPOffset<fb::TransactionTokenBalance>>(&[]);
+        let rewards = fbb.create_vector::<WIPOffset<fb::Reward>>(&[]);
+        let transaction_meta = fb::TransactionStatusMeta::create(
+            &mut fbb,
+            &fb::TransactionStatusMetaArgs {
+                status: true,
+                fee: 5000,
+                pre_balances: Some(balances),
+                post_balances: Some(balances),
+                inner_instructions: Some(inner_instructions),
+                log_messages: Some(log_messages),
+                pre_token_balances: Some(token_balances),
+                post_token_balances: Some(token_balances),
+                rewards: Some(rewards),
+                ..Default::default()
+            },
+        );
+
+        let transaction_info = fb::TransactionInfo::create(
+            &mut fbb,
+            &fb::TransactionInfoArgs {
+                signature: Some(signature),
+                is_vote: false,
+                slot: 100,
+                transaction: Some(transaction),
+                transaction_meta: Some(transaction_meta),
+            },
+        );
+        fbb.finish(transaction_info, None);
+
+        fbb.finished_data().to_vec()
+    }
+
+    /// `inner_order_transaction` as RPC returns it
+    fn inner_order_rpc_transaction(program: &Pubkey) -> EncodedConfirmedTransactionWithStatusMeta {
+        let instruction = |data: u8| {
+            serde_json::json!({
+                "programIdIndex": 2,
+                "accounts": [0, 1],
+                "data": [data].to_base58()
+            })
+        };
+        let inner_instructions: Vec<_> = INNER_SETS
+            .iter()
+            .map(|(index, data)| {
+                serde_json::json!({
+                    "index": index,
+                    "instructions": data.iter().copied().map(instruction).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [[1u8; 64].to_base58()],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        Pubkey::new_from_array([1; 32]).to_string(),
+                        Pubkey::new_from_array([2; 32]).to_string(),
+                        program.to_string()
+                    ],
+                    "recentBlockhash": [0u8; 32].to_base58(),
+                    "instructions": (0..4).map(instruction).collect::<Vec<_>>()
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": inner_instructions,
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            },
+            "version": "legacy"
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: None,
+        }
+    }
+
+    /// Position, data and parent of the parsed instructions in the order they are returned
+    fn instruction_positions(
+        transaction: EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Vec<(Option<u8>, Option<u8>, u8, String, Option<String>)> {
+        let instructions = solana_instruction_parser::parse_transaction(transaction)
+            .unwrap()
+            .instructions;
+
+        instructions
+            .into_iter()
+            .map(|instruction| {
+                (
+                    instruction.transaction_instruction_idx,
+                    instruction.inner_instructions_set,
+                    instruction.instruction_idx,
+                    instruction.data,
+                    instruction.parent_program,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inner_sets_are_ordered_the_same_as_from_rpc() {
+        let program = Pubkey::new_unique();
+        let rpc_positions = instruction_positions(inner_order_rpc_transaction(&program));
+
+        // Top-level instructions 0, 1 and 3 with their inner sets in the execution order, the
+        // third top-level instruction has none
+        let data = |data: u8| [data].to_base58();
+        let parent = Some(program.to_string());
+        assert_eq!(
+            rpc_positions,
+            vec![
+                (None, None, 0, data(0), None),
+                (Some(0), Some(0), 0, data(10), parent.clone()),
+                (Some(0), Some(0), 1, data(11), parent.clone()),
+                (None, None, 1, data(1), None),
+                (Some(1), Some(1), 0, data(20), parent.clone()),
+                (None, None, 2, data(2), None),
+                (None, None, 3, data(3), None),
+                (Some(3), Some(2), 0, data(40), parent.clone()),
+                (Some(3), Some(2), 1, data(41), parent.clone()),
+                (Some(3), Some(2), 2, data(42), parent),
+            ]
+        );
+
+        // Every order of the inner sets in the Geyser payload
+        for inner_order in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            let transaction =
+                deserialize_transaction(&inner_order_transaction(&program, &inner_order)).unwrap();
+
+            assert_eq!(
+                instruction_positions(transaction),
+                rpc_positions,
+                "Inner sets in the order {:?}",
+                inner_order
+            );
+        }
+    }
+
+    #[test]
+    fn loaded_addresses_are_deserialized() {
+        let account_key = Pubkey::new_unique();
+        let loaded_writable = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let transaction = deserialize_transaction(&v0_transaction(
+            &account_key,
+            &loaded_writable,
+            None,
+            Some(1234),
+            None,
+        ))
+        .unwrap();
+        let meta = transaction.transaction.meta.unwrap();
+
+        assert_eq!(
+            meta.loaded_addresses,
+            OptionSerializer::Some(UiLoadedAddresses {
+                writable: loaded_writable.iter().map(|key| key.to_string()).collect(),
+                readonly: vec![Pubkey::default().to_string()],
+            })
+        );
+        assert_eq!(meta.compute_units_consumed, OptionSerializer::Some(1234));
+        assert_eq!(meta.return_data, OptionSerializer::None);
+        assert_eq!(meta.err, None);
+        assert_eq!(
+            transaction.transaction.version,
+            Some(TransactionVersion::Number(0))
+        );
+
+        match transaction.transaction.transaction {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys, vec![account_key.to_string()]);
+                assert_eq!(
+                    message.address_table_lookups.unwrap()[0].writable_indexes,
+                    vec![0, 1]
+                );
+            }
+            _ => panic!("A raw JSON message is expected"),
+        }
+    }
+
+    #[test]
+    fn failed_transaction_without_compute_units_is_deserialized() {
+        let transaction = deserialize_transaction(&v0_transaction(
+            &Pubkey::new_unique(),
+            &[Pubkey::new_unique(), Pubkey::new_unique()],
+            Some("{\"InstructionError\":[0,{\"Custom\":1}]}"),
+            None,
+            None,
+        ))
+        .unwrap();
+        let meta = transaction.transaction.meta.unwrap();
+
+        let err = TransactionError::InstructionError(0, InstructionError::Custom(1));
+        assert_eq!(meta.err, Some(err.clone()));
+        assert_eq!(meta.status, Err(err));
+        assert_eq!(meta.compute_units_consumed, OptionSerializer::None);
+    }
+
+    #[test]
+    fn return_data_is_deserialized() {
+        let program_id = Pubkey::new_unique();
+
+        let transaction = deserialize_transaction(&v0_transaction(
+            &Pubkey::new_unique(),
+            &[Pubkey::new_unique()],
+            None,
+            None,
+            Some((&program_id, &[1, 2, 3])),
+        ))
+        .unwrap();
+        let meta = transaction.transaction.meta.unwrap();
+
+        assert_eq!(
+            meta.return_data,
+            OptionSerializer::Some(UiTransactionReturnData {
+                program_id: program_id.to_string(),
+                data: ("AQID".to_string(), UiReturnDataEncoding::Base64),
+            })
+        );
+    }
+
+    #[test]
+    fn transaction_error() {
+        assert_eq!(deserialize_transaction_error(None).unwrap(), None);
+
+        assert_eq!(
+            deserialize_transaction_error(Some("{\"InstructionError\":[1,{\"Custom\":6000}]}"))
+                .unwrap(),
+            Some(TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(6000)
+            ))
+        );
+
+        assert!(deserialize_transaction_error(Some("not a json")).is_err());
+    }
+}
@@ -2203,6 +2203,9 @@ pub mod transaction_info {
         pub const VT_PRE_TOKEN_BALANCES: flatbuffers::VOffsetT = 16;
         pub const VT_POST_TOKEN_BALANCES: flatbuffers::VOffsetT = 18;
         pub const VT_REWARDS: flatbuffers::VOffsetT = 20;
+        pub const VT_ERR: flatbuffers::VOffsetT = 22;
+        pub const VT_COMPUTE_UNITS_CONSUMED: flatbuffers::VOffsetT = 24;
+        pub const VT_RETURN_DATA: flatbuffers::VOffsetT = 26;
 
         #[inline]
         pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -2214,7 +2217,16 @@ pub mod transaction_info {
             args: &'args TransactionStatusMetaArgs<'args>,
         ) -> flatbuffers::WIPOffset<TransactionStatusMeta<'bldr>> {
             let mut builder = TransactionStatusMetaBuilder::new(_fbb);
+            if let Some(x) = args.compute_units_consumed {
+                builder.add_compute_units_consumed(x);
+            }
             builder.add_fee(args.fee);
+            if let Some(x) = args.return_data {
+                builder.add_return_data(x);
+            }
+            if let Some(x) = args.err {
+                builder.add_err(x);
+            }
             if let Some(x) = args.rewards {
                 builder.add_rewards(x);
             }
@@ -2313,6 +2325,24 @@ pub mod transaction_info {
                 flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Reward>>,
             >>(TransactionStatusMeta::VT_REWARDS, None)
         }
+        #[inline]
+        pub fn err(&self) -> Option<&'a str> {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<&str>>(TransactionStatusMeta::VT_ERR, None)
+        }
+        #[inline]
+        pub fn compute_units_consumed(&self) -> Option<u64> {
+            self._tab
+                .get::<u64>(TransactionStatusMeta::VT_COMPUTE_UNITS_CONSUMED, None)
+        }
+        #[inline]
+        pub fn return_data(&self) -> Option<TransactionReturnData<'a>> {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<TransactionReturnData>>(
+                    TransactionStatusMeta::VT_RETURN_DATA,
+                    None,
+                )
+        }
     }
 
     impl flatbuffers::Verifiable for TransactionStatusMeta<'_> {
@@ -2350,6 +2380,17 @@ pub mod transaction_info {
                 .visit_field::<flatbuffers::ForwardsUOffset<
                     flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Reward>>,
                 >>("rewards", Self::VT_REWARDS, false)?
+                .visit_field::<flatbuffers::ForwardsUOffset<&str>>("err", Self::VT_ERR, false)?
+                .visit_field::<u64>(
+                    "compute_units_consumed",
+                    Self::VT_COMPUTE_UNITS_CONSUMED,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<TransactionReturnData>>(
+                    "return_data",
+                    Self::VT_RETURN_DATA,
+                    false,
+                )?
                 .finish();
             Ok(())
         }
@@ -2382,6 +2423,9 @@ pub mod transaction_info {
                 flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Reward<'a>>>,
             >,
         >,
+        pub err: Option<flatbuffers::WIPOffset<&'a str>>,
+        pub compute_units_consumed: Option<u64>,
+        pub return_data: Option<flatbuffers::WIPOffset<TransactionReturnData<'a>>>,
     }
     impl<'a> Default for TransactionStatusMetaArgs<'a> {
         #[inline]
@@ -2396,6 +2440,9 @@ pub mod transaction_info {
                 pre_token_balances: None,
                 post_token_balances: None,
                 rewards: None,
+                err: None,
+                compute_units_consumed: None,
+                return_data: None,
             }
         }
     }
@@ -2496,6 +2543,29 @@ pub mod transaction_info {
             );
         }
         #[inline]
+        pub fn add_err(&mut self, err: flatbuffers::WIPOffset<&'b str>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<_>>(TransactionStatusMeta::VT_ERR, err);
+        }
+        #[inline]
+        pub fn add_compute_units_consumed(&mut self, compute_units_consumed: u64) {
+            self.fbb_.push_slot_always::<u64>(
+                TransactionStatusMeta::VT_COMPUTE_UNITS_CONSUMED,
+                compute_units_consumed,
+            );
+        }
+        #[inline]
+        pub fn add_return_data(
+            &mut self,
+            return_data: flatbuffers::WIPOffset<TransactionReturnData<'b>>,
+        ) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<TransactionReturnData>>(
+                    TransactionStatusMeta::VT_RETURN_DATA,
+                    return_data,
+                );
+        }
+        #[inline]
         pub fn new(
             _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
         ) -> TransactionStatusMetaBuilder<'a, 'b> {
@@ -2524,6 +2594,148 @@ pub mod transaction_info {
             ds.field("pre_token_balances", &self.pre_token_balances());
             ds.field("post_token_balances", &self.post_token_balances());
             ds.field("rewards", &self.rewards());
+            ds.field("err", &self.err());
+            ds.field("compute_units_consumed", &self.compute_units_consumed());
+            ds.field("return_data", &self.return_data());
+            ds.finish()
+        }
+    }
+    pub enum TransactionReturnDataOffset {}
+    #[derive(Copy, Clone, PartialEq)]
+
+    pub struct TransactionReturnData<'a> {
+        pub _tab: flatbuffers::Table<'a>,
+    }
+
+    impl<'a> flatbuffers::Follow<'a> for TransactionReturnData<'a> {
+        type Inner = TransactionReturnData<'a>;
+        #[inline]
+        fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+            Self {
+                _tab: flatbuffers::Table { buf, loc },
+            }
+        }
+    }
+
+    impl<'a> TransactionReturnData<'a> {
+        pub const VT_PROGRAM_ID: flatbuffers::VOffsetT = 4;
+        pub const VT_DATA: flatbuffers::VOffsetT = 6;
+
+        #[inline]
+        pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+            TransactionReturnData { _tab: table }
+        }
+        #[allow(unused_mut)]
+        pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+            _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+            args: &'args TransactionReturnDataArgs<'args>,
+        ) -> flatbuffers::WIPOffset<TransactionReturnData<'bldr>> {
+            let mut builder = TransactionReturnDataBuilder::new(_fbb);
+            if let Some(x) = args.data {
+                builder.add_data(x);
+            }
+            if let Some(x) = args.program_id {
+                builder.add_program_id(x);
+            }
+            builder.finish()
+        }
+
+        #[inline]
+        pub fn program_id(&self) -> Option<Pubkey<'a>> {
+            self._tab.get::<flatbuffers::ForwardsUOffset<Pubkey>>(
+                TransactionReturnData::VT_PROGRAM_ID,
+                None,
+            )
+        }
+        #[inline]
+        pub fn data(&self) -> Option<&'a [u8]> {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(
+                    TransactionReturnData::VT_DATA,
+                    None,
+                )
+                .map(|v| v.safe_slice())
+        }
+    }
+
+    impl flatbuffers::Verifiable for TransactionReturnData<'_> {
+        #[inline]
+        fn run_verifier(
+            v: &mut flatbuffers::Verifier,
+            pos: usize,
+        ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+            use self::flatbuffers::Verifiable;
+            v.visit_table(pos)?
+                .visit_field::<flatbuffers::ForwardsUOffset<Pubkey>>(
+                    "program_id",
+                    Self::VT_PROGRAM_ID,
+                    false,
+                )?
+                .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u8>>>(
+                    "data",
+                    Self::VT_DATA,
+                    false,
+                )?
+                .finish();
+            Ok(())
+        }
+    }
+    pub struct TransactionReturnDataArgs<'a> {
+        pub program_id: Option<flatbuffers::WIPOffset<Pubkey<'a>>>,
+        pub data: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+    }
+    impl<'a> Default for TransactionReturnDataArgs<'a> {
+        #[inline]
+        fn default() -> Self {
+            TransactionReturnDataArgs {
+                program_id: None,
+                data: None,
+            }
+        }
+    }
+
+    pub struct TransactionReturnDataBuilder<'a: 'b, 'b> {
+        fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+    }
+    impl<'a: 'b, 'b> TransactionReturnDataBuilder<'a, 'b> {
+        #[inline]
+        pub fn add_program_id(&mut self, program_id: flatbuffers::WIPOffset<Pubkey<'b>>) {
+            self.fbb_
+                .push_slot_always::<flatbuffers::WIPOffset<Pubkey>>(
+                    TransactionReturnData::VT_PROGRAM_ID,
+                    program_id,
+                );
+        }
+        #[inline]
+        pub fn add_data(&mut self, data: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u8>>) {
+            self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+                TransactionReturnData::VT_DATA,
+                data,
+            );
+        }
+        #[inline]
+        pub fn new(
+            _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+        ) -> TransactionReturnDataBuilder<'a, 'b> {
+            let start = _fbb.start_table();
+            TransactionReturnDataBuilder {
+                fbb_: _fbb,
+                start_: start,
+            }
+        }
+        #[inline]
+        pub fn finish(self) -> flatbuffers::WIPOffset<TransactionReturnData<'a>> {
+            let o = self.fbb_.end_table(self.start_);
+            flatbuffers::WIPOffset::new(o.value())
+        }
+    }
+
+    impl std::fmt::Debug for TransactionReturnData<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let mut ds = f.debug_struct("TransactionReturnData");
+            ds.field("program_id", &self.program_id());
+            ds.field("data", &self.data());
             ds.finish()
         }
     }
@@ -0,0 +1,479 @@
+//! Queue storage of an analyzer pointed at a read replica of the data_loader's database, e.g. a
+//! second analyzer trying new decoders on the production queue. The replica rejects the updates
+//! of `parsing_status`, so instead of claiming and marking the rows the transactions are read in
+//! (slot, signature) order after a cursor kept in the `queue_cursors` table of the analyzer's own
+//! main storage.
+//!
+//! Marking a transaction as parsed only advances the cursor locally, it's stored before the next
+//! batch is read. The transactions are marked once the collector has flushed the rows of their
+//! batch and the ones before, so a restart resumes strictly after the last transaction whose rows
+//! are stored. The erroneous transactions are marked the same way once they are handed to the
+//! erroneous transactions collector, so they are not analyzed again after a restart either
+
+use super::main_storage::{MainStorage, Metadata, QueueCursor};
+use super::postgre_storage::models::Delegation;
+use super::{QueueProgress, QueueStats, QueueStorage};
+use anyhow::Result;
+use async_trait::async_trait;
+use log::{error, info};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::collections::{HashMap, VecDeque};
+
+/// Transactions read by one `get_transactions` call, the same as claimed from PostgreSQL
+const TRANSACTIONS_BATCH_SIZE: usize = 1000;
+/// Blocks metadata read by one `get_blocks_metadata` call
+const BLOCKS_METADATA_BATCH_SIZE: usize = 1000;
+
+/// Reads of the queue which leave it untouched
+#[async_trait]
+pub trait QueueReader: Send {
+    /// Up to `limit` transactions strictly after the cursor in (slot, signature) order along with
+    /// their cursors, whatever their parsing status is, except the ones which can't be read
+    async fn read_transactions(
+        &mut self,
+        after: &QueueCursor,
+        limit: usize,
+    ) -> Result<Vec<(QueueCursor, EncodedConfirmedTransactionWithStatusMeta)>>;
+    /// Up to `limit` blocks metadata after the slot ordered by slot, whatever their parsing
+    /// status is
+    async fn read_blocks_metadata(
+        &mut self,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<Metadata>>;
+    async fn read_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>>;
+    /// Stats of the transactions strictly after the cursor, the ones a reader has yet to read
+    async fn read_queue_stats(&mut self, after: &QueueCursor) -> Result<QueueStats>;
+}
+
+pub struct ReadOnlyQueueStorage {
+    reader: Box<dyn QueueReader>,
+    cursors: Box<dyn MainStorage>,
+    /// Names of the cursors of the transactions and the blocks metadata in `queue_cursors`
+    transactions_queue: String,
+    blocks_metadata_queue: String,
+    /// Last transaction of the last read batch, the next batch is read after it
+    read: QueueCursor,
+    /// Last transaction marked as parsed
+    parsed: QueueCursor,
+    /// Cursor of the transactions stored in the main storage
+    stored: QueueCursor,
+    /// Slots of the transactions of the read batches by their signatures, oldest batch first. A
+    /// batch is kept until its transactions or the ones of a later batch are marked, as the
+    /// batches are marked long after the next ones are read
+    batches_slots: VecDeque<HashMap<String, u64>>,
+    /// Slot of the last stored blocks metadata
+    blocks_metadata_slot: Option<u64>,
+}
+
+impl ReadOnlyQueueStorage {
+    /// Resumes after the cursors stored for the shard in the main storage
+    pub async fn new(
+        reader: Box<dyn QueueReader>,
+        mut cursors: Box<dyn MainStorage>,
+        shard: usize,
+    ) -> Result<Self> {
+        let transactions_queue = format!("transactions_{}", shard);
+        let blocks_metadata_queue = format!("blocks_metadata_{}", shard);
+
+        let stored = cursors
+            .get_queue_cursor(&transactions_queue)
+            .await?
+            .unwrap_or_default();
+        let blocks_metadata_slot = cursors
+            .get_queue_cursor(&blocks_metadata_queue)
+            .await?
+            .map(|cursor| cursor.slot);
+
+        info!(
+            "Read-only queue {} resumes after slot {} signature '{}'",
+            transactions_queue, stored.slot, stored.signature
+        );
+
+        Ok(Self {
+            reader,
+            cursors,
+            transactions_queue,
+            blocks_metadata_queue,
+            read: stored.clone(),
+            parsed: stored.clone(),
+            stored,
+            batches_slots: VecDeque::new(),
+            blocks_metadata_slot,
+        })
+    }
+
+    /// Moves the cursor of the marked transactions up to the transaction, if it's of a batch
+    /// still kept
+    fn advance_parsed(&mut self, transaction: String) {
+        let marked = self
+            .batches_slots
+            .iter_mut()
+            .enumerate()
+            .find_map(|(batch, slots)| slots.remove(&transaction).map(|slot| (batch, slot)));
+
+        if let Some((batch, slot)) = marked {
+            // The batches read before are flushed as well, the transactions of them marked later
+            // are behind the cursor anyway
+            self.batches_slots.drain(..batch);
+            while self
+                .batches_slots
+                .front()
+                .map_or(false, |slots| slots.is_empty())
+            {
+                self.batches_slots.pop_front();
+            }
+
+            let cursor = QueueCursor {
+                slot,
+                signature: transaction,
+            };
+            if cursor > self.parsed {
+                self.parsed = cursor;
+            }
+        }
+    }
+
+    /// Stores the cursor of the last parsed transaction, a failed store is retried before the
+    /// next batch is read
+    async fn store_parsed_cursor(&mut self) {
+        if self.parsed <= self.stored {
+            return;
+        }
+
+        match self
+            .cursors
+            .store_queue_cursor(&self.transactions_queue, self.parsed.clone())
+            .await
+        {
+            Ok(()) => self.stored = self.parsed.clone(),
+            Err(err) => error!(
+                "Failed to store the cursor of {}: {:#?}",
+                self.transactions_queue, err
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl QueueStorage for ReadOnlyQueueStorage {
+    async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+        self.store_parsed_cursor().await;
+
+        match self
+            .reader
+            .read_transactions(&self.read, TRANSACTIONS_BATCH_SIZE)
+            .await
+        {
+            Ok(transactions) => {
+                if let Some((last, _)) = transactions.last() {
+                    self.read = last.clone();
+                    self.batches_slots.push_back(HashMap::new());
+                }
+
+                transactions
+                    .into_iter()
+                    .map(|(cursor, transaction)| {
+                        if let Some(slots) = self.batches_slots.back_mut() {
+                            slots.insert(cursor.signature, cursor.slot);
+                        }
+                        transaction
+                    })
+                    .collect()
+            }
+            Err(err) => {
+                error!("Read-only queue cannot read transactions: {:#?}", err);
+                vec![]
+            }
+        }
+    }
+
+    async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+        self.reader.read_delegations(stake_accs).await
+    }
+
+    /// The replica can't be written, the delegations stay in the cache of the queue manager
+    async fn save_delegations(&mut self, _delegations: Vec<Delegation>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn mark_transaction_as_parsed(&mut self, transaction: String) -> Result<()> {
+        self.advance_parsed(transaction);
+        Ok(())
+    }
+
+    async fn mark_transaction_as_erroneous(&mut self, transaction: String) -> Result<()> {
+        self.advance_parsed(transaction);
+        Ok(())
+    }
+
+    /// Nothing is claimed in the replica
+    async fn reclaim_stuck_transactions(&mut self, _timeout: u64) -> Result<usize> {
+        Ok(0)
+    }
+
+    async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+        self.reader
+            .read_blocks_metadata(self.blocks_metadata_slot, BLOCKS_METADATA_BATCH_SIZE)
+            .await
+    }
+
+    /// The blocks are stored before their metadata is marked, so the cursor is stored at once
+    async fn mark_blocks_metadata_as_parsed(&mut self, slots: Vec<u64>) -> Result<()> {
+        let slot = match slots.into_iter().max() {
+            Some(slot) if Some(slot) > self.blocks_metadata_slot => slot,
+            _ => return Ok(()),
+        };
+
+        self.cursors
+            .store_queue_cursor(
+                &self.blocks_metadata_queue,
+                QueueCursor {
+                    slot,
+                    signature: String::new(),
+                },
+            )
+            .await?;
+        self.blocks_metadata_slot = Some(slot);
+
+        Ok(())
+    }
+
+    /// The transactions after the last read batch are pending
+    async fn queue_stats(&mut self) -> Result<QueueStats> {
+        self.reader.read_queue_stats(&self.read).await
+    }
+
+    /// The transactions up to the stored cursor are finished, the ones after it are not. The
+    /// signatures of the replica are not read, a reader only sees the loaded transactions
+    async fn queue_progress(&mut self) -> Result<QueueProgress> {
+        let unfinished = self.reader.read_queue_stats(&self.stored).await?;
+
+        Ok(QueueProgress {
+            lowest_unfinished_slot: unfinished.oldest_pending_slot,
+            highest_slot: (self.stored != QueueCursor::default()).then_some(self.stored.slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::transaction_parser::transaction_signature;
+    use crate::storages::memory::{MemoryMainStorage, MemoryQueueStorage};
+
+    fn transaction(slot: u64) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [format!("signature_{}", slot)],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": "" }]
+                }
+            },
+            "meta": null
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        }
+    }
+
+    fn metadata(slot: u64) -> Metadata {
+        Metadata {
+            slot,
+            blockhash: String::new(),
+            rewards: "[]".to_string(),
+            block_time: None,
+            block_height: None,
+        }
+    }
+
+    fn queue(url: &str, transactions: u64) -> MemoryQueueStorage {
+        let queue = MemoryQueueStorage::connect(url);
+
+        for slot in 0..transactions {
+            queue.push_transaction(transaction(slot));
+        }
+        queue.queue().blocks_metadata = (0..3).map(|slot| (metadata(slot), false)).collect();
+        queue.queue().delegations.insert(
+            String::from("stake"),
+            Delegation {
+                stake_acc: String::from("stake"),
+                vote_acc: Some(String::from("vote")),
+                delegated_amount: None,
+            },
+        );
+
+        queue
+    }
+
+    async fn read_only(
+        queue: &MemoryQueueStorage,
+        main: &MemoryMainStorage,
+    ) -> ReadOnlyQueueStorage {
+        ReadOnlyQueueStorage::new(Box::new(queue.clone()), Box::new(main.clone()), 0)
+            .await
+            .unwrap()
+    }
+
+    fn signatures(transactions: &[EncodedConfirmedTransactionWithStatusMeta]) -> Vec<String> {
+        transactions.iter().map(transaction_signature).collect()
+    }
+
+    #[tokio::test]
+    async fn queue_is_not_updated() -> Result<()> {
+        let queue = queue("memory://read_only_queue_is_not_updated", 10);
+        let main = MemoryMainStorage::connect("memory://read_only_queue_is_not_updated");
+        let mut storage = read_only(&queue, &main).await;
+
+        let transactions = storage.get_transactions().await;
+        assert_eq!(transactions.len(), 10);
+        for signature in signatures(&transactions) {
+            storage.mark_transaction_as_parsed(signature).await?;
+        }
+        assert!(storage.get_transactions().await.is_empty());
+
+        let blocks_metadata = storage.get_blocks_metadata().await?;
+        assert_eq!(blocks_metadata.len(), 3);
+        storage.mark_blocks_metadata_as_parsed(vec![0, 1]).await?;
+        assert_eq!(storage.get_blocks_metadata().await?, vec![metadata(2)]);
+
+        storage
+            .save_delegations(vec![Delegation {
+                stake_acc: String::from("stake"),
+                vote_acc: None,
+                delegated_amount: None,
+            }])
+            .await?;
+        assert_eq!(
+            storage
+                .get_delegations(vec![String::from("stake")])
+                .await?
+                .first()
+                .and_then(|delegation| delegation.vote_acc.clone()),
+            Some(String::from("vote"))
+        );
+
+        let queue = queue.queue();
+        assert!(queue
+            .transactions
+            .iter()
+            .all(|queued| queued.parsing_status == 0));
+        assert!(queue.blocks_metadata.iter().all(|(_, parsed)| !parsed));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restart_resumes_after_the_flushed_batch() -> Result<()> {
+        let queue = queue("memory://read_only_restart_resumes", 2500);
+        let main = MemoryMainStorage::connect("memory://read_only_restart_resumes");
+
+        let mut analyzed = Vec::new();
+
+        // The first batch is flushed and marked, the second one is lost along with the process
+        // before it's flushed. The cursor of the first batch is stored when the third batch is
+        // read
+        let mut storage = read_only(&queue, &main).await;
+        let flushed = signatures(&storage.get_transactions().await);
+        assert_eq!(flushed.len(), 1000);
+        assert_eq!(storage.get_transactions().await.len(), 1000);
+        for signature in &flushed {
+            storage
+                .mark_transaction_as_parsed(signature.clone())
+                .await?;
+        }
+        analyzed.extend(flushed);
+        storage.get_transactions().await;
+        drop(storage);
+
+        let mut storage = read_only(&queue, &main).await;
+        loop {
+            let transactions = signatures(&storage.get_transactions().await);
+            if transactions.is_empty() {
+                break;
+            }
+
+            for signature in &transactions {
+                storage
+                    .mark_transaction_as_parsed(signature.clone())
+                    .await?;
+            }
+            analyzed.extend(transactions);
+        }
+
+        let expected: Vec<_> = (0..2500)
+            .map(|slot| format!("signature_{}", slot))
+            .collect();
+        assert_eq!(analyzed, expected);
+        assert_eq!(
+            main.main().queue_cursors.get("transactions_0"),
+            Some(&QueueCursor {
+                slot: 2499,
+                signature: String::from("signature_2499"),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batches_marked_after_the_next_read_advance_the_cursor() -> Result<()> {
+        let queue = queue("memory://read_only_late_marks", 2500);
+        let main = MemoryMainStorage::connect("memory://read_only_late_marks");
+        let mut storage = read_only(&queue, &main).await;
+        let stored =
+            |main: &MemoryMainStorage| main.main().queue_cursors.get("transactions_0").cloned();
+
+        // As the transaction worker does: the batch is ended and the next one is read before the
+        // collector has flushed the first one and its transactions are marked
+        let first = signatures(&storage.get_transactions().await);
+        let second = signatures(&storage.get_transactions().await);
+        assert_eq!(stored(&main), None);
+
+        for signature in first.iter().take(999) {
+            storage
+                .mark_transaction_as_parsed(signature.clone())
+                .await?;
+        }
+        let third = signatures(&storage.get_transactions().await);
+        assert_eq!(
+            stored(&main),
+            Some(QueueCursor {
+                slot: 998,
+                signature: String::from("signature_998"),
+            })
+        );
+
+        // The last transaction of the first batch is never marked, e.g. the erroneous transactions
+        // collector failed to take it, the cursor moves past it with the marks of the second batch
+        for signature in second.iter().chain(&third) {
+            storage
+                .mark_transaction_as_parsed(signature.clone())
+                .await?;
+        }
+        assert!(storage.get_transactions().await.is_empty());
+        assert_eq!(
+            stored(&main),
+            Some(QueueCursor {
+                slot: 2499,
+                signature: String::from("signature_2499"),
+            })
+        );
+        assert!(storage.batches_slots.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+//! Queue storage spread over several databases. The data_loader stores a signature and its
+//! transaction in the shard `shard_of` routes it to, so the analyzer claims it and marks it as
+//! parsed in the same one. Both route by the `queue_shards` crate
+
+use super::main_storage::Metadata;
+use super::postgre_storage::models::Delegation;
+use super::{QueueProgress, QueueStats, QueueStorage};
+use crate::metrics_update;
+use anyhow::Result;
+use async_trait::async_trait;
+use queue_shards::{group_by_shard, shard_of};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+/// Queue storage of the shards in the order they are configured. The transactions are claimed
+/// from one shard at a time, a transaction is marked as parsed in the shard of its signature and
+/// the delegations are kept in the shard of their stake account. Reclaiming and the blocks
+/// metadata are run on every shard
+pub struct ShardedQueueStorage {
+    shards: Vec<Box<dyn QueueStorage>>,
+    /// The shard the next batch is claimed from first
+    next_shard: usize,
+}
+
+impl ShardedQueueStorage {
+    pub fn new(shards: Vec<Box<dyn QueueStorage>>) -> Self {
+        Self {
+            shards,
+            next_shard: 0,
+        }
+    }
+}
+
+fn count_shard_operation(shard: usize, operation: &str) {
+    metrics_update!(inc QUEUE_SHARD_OPERATIONS_COUNT, &[&shard.to_string(), operation]);
+}
+
+#[async_trait]
+impl QueueStorage for ShardedQueueStorage {
+    /// Batch of a single shard, the shards are claimed from in turn and the next one is asked
+    /// when a shard has no pending transactions
+    async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+        for offset in 0..self.shards.len() {
+            let shard = (self.next_shard + offset) % self.shards.len();
+            count_shard_operation(shard, "get_transactions");
+
+            let transactions = self.shards[shard].get_transactions().await;
+
+            if !transactions.is_empty() {
+                self.next_shard = (shard + 1) % self.shards.len();
+                return transactions;
+            }
+        }
+
+        Vec::new()
+    }
+
+    async fn get_delegations(&mut self, stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+        let shard_stake_accs = group_by_shard(stake_accs, self.shards.len(), |stake_acc| {
+            stake_acc.as_str()
+        });
+        let mut delegations = Vec::new();
+
+        for (shard, stake_accs) in shard_stake_accs.into_iter().enumerate() {
+            if stake_accs.is_empty() {
+                continue;
+            }
+
+            count_shard_operation(shard, "get_delegations");
+            delegations.extend(self.shards[shard].get_delegations(stake_accs).await?);
+        }
+
+        Ok(delegations)
+    }
+
+    async fn save_delegations(&mut self, delegations: Vec<Delegation>) -> Result<()> {
+        let shard_delegations = group_by_shard(delegations, self.shards.len(), |delegation| {
+            delegation.stake_acc.as_str()
+        });
+
+        for (shard, delegations) in shard_delegations.into_iter().enumerate() {
+            if delegations.is_empty() {
+                continue;
+            }
+
+            count_shard_operation(shard, "save_delegations");
+            self.shards[shard].save_delegations(delegations).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn mark_transaction_as_parsed(&mut self, transaction: String) -> Result<()> {
+        let shard = shard_of(&transaction, self.shards.len());
+        count_shard_operation(shard, "mark_transaction_as_parsed");
+
+        self.shards[shard]
+            .mark_transaction_as_parsed(transaction)
+            .await
+    }
+
+    async fn mark_transaction_as_erroneous(&mut self, transaction: String) -> Result<()> {
+        let shard = shard_of(&transaction, self.shards.len());
+        count_shard_operation(shard, "mark_transaction_as_erroneous");
+
+        self.shards[shard]
+            .mark_transaction_as_erroneous(transaction)
+            .await
+    }
+
+    async fn reclaim_stuck_transactions(&mut self, timeout: u64) -> Result<usize> {
+        let mut reclaimed = 0;
+
+        for (shard, storage) in self.shards.iter_mut().enumerate() {
+            count_shard_operation(shard, "reclaim_stuck_transactions");
+            reclaimed += storage.reclaim_stuck_transactions(timeout).await?;
+        }
+
+        Ok(reclaimed)
+    }
+
+    async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+        let mut blocks_metadata = Vec::new();
+
+        for (shard, storage) in self.shards.iter_mut().enumerate() {
+            count_shard_operation(shard, "get_blocks_metadata");
+            blocks_metadata.extend(storage.get_blocks_metadata().await?);
+        }
+
+        blocks_metadata.sort_by_key(|metadata| metadata.slot);
+
+        Ok(blocks_metadata)
+    }
+
+    /// Marked in every shard, the slots a shard doesn't have are not updated there
+    async fn mark_blocks_metadata_as_parsed(&mut self, slots: Vec<u64>) -> Result<()> {
+        for (shard, storage) in self.shards.iter_mut().enumerate() {
+            count_shard_operation(shard, "mark_blocks_metadata_as_parsed");
+            storage
+                .mark_blocks_metadata_as_parsed(slots.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn queue_stats(&mut self) -> Result<QueueStats> {
+        let mut stats = QueueStats::default();
+
+        for (shard, storage) in self.shards.iter_mut().enumerate() {
+            count_shard_operation(shard, "queue_stats");
+            stats = stats.merge(storage.queue_stats().await?);
+        }
+
+        Ok(stats)
+    }
+
+    async fn queue_progress(&mut self) -> Result<QueueProgress> {
+        let mut progress = QueueProgress::default();
+
+        for (shard, storage) in self.shards.iter_mut().enumerate() {
+            count_shard_operation(shard, "queue_progress");
+            progress = progress.merge(storage.queue_progress().await?);
+        }
+
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actors::prometheus_exporter::QUEUE_SHARD_OPERATIONS_COUNT;
+    use crate::actors::transaction_parser::transaction_signature;
+    use crate::storages::memory::MemoryQueueStorage;
+
+    /// Memory shards with their own queues, the url is unique to the test
+    fn memory_shards(test: &str) -> Vec<MemoryQueueStorage> {
+        (0..2)
+            .map(|shard| MemoryQueueStorage::connect(&format!("{}_{}", test, shard)))
+            .collect()
+    }
+
+    fn sharded(shards: &[MemoryQueueStorage]) -> ShardedQueueStorage {
+        ShardedQueueStorage::new(
+            shards
+                .iter()
+                .map(|shard| Box::new(shard.clone()) as Box<dyn QueueStorage>)
+                .collect(),
+        )
+    }
+
+    fn transaction(signature: &str, slot: u64) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": "" }]
+                }
+            },
+            "meta": null
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        }
+    }
+
+    fn metadata(slot: u64) -> Metadata {
+        Metadata {
+            slot,
+            blockhash: String::new(),
+            rewards: "[]".to_string(),
+            block_time: None,
+            block_height: None,
+        }
+    }
+
+    /// Shard with `stuck` transactions claimed by a dead analyzer
+    struct StuckShard {
+        stuck: usize,
+    }
+
+    #[async_trait]
+    impl QueueStorage for StuckShard {
+        async fn get_transactions(&mut self) -> Vec<EncodedConfirmedTransactionWithStatusMeta> {
+            Vec::new()
+        }
+
+        async fn get_delegations(&mut self, _stake_accs: Vec<String>) -> Result<Vec<Delegation>> {
+            Ok(Vec::new())
+        }
+
+        async fn save_delegations(&mut self, _delegations: Vec<Delegation>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn mark_transaction_as_parsed(&mut self, _transaction: String) -> Result<()> {
+            Ok(())
+        }
+
+        async fn mark_transaction_as_erroneous(&mut self, _transaction: String) -> Result<()> {
+            Ok(())
+        }
+
+        async fn reclaim_stuck_transactions(&mut self, _timeout: u64) -> Result<usize> {
+            Ok(self.stuck)
+        }
+
+        async fn get_blocks_metadata(&mut self) -> Result<Vec<Metadata>> {
+            Ok(Vec::new())
+        }
+
+        async fn mark_blocks_metadata_as_parsed(&mut self, _slots: Vec<u64>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn queue_stats(&mut self) -> Result<QueueStats> {
+            Ok(QueueStats::default())
+        }
+
+        async fn queue_progress(&mut self) -> Result<QueueProgress> {
+            Ok(QueueProgress::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn transactions_are_claimed_and_marked_in_their_shards() {
+        let shards = memory_shards("transactions_are_claimed_and_marked_in_their_shards");
+        let signatures = ["signature_0", "signature_1", "signature_2", "signature_3"];
+        for (slot, signature) in signatures.iter().enumerate() {
+            shards[shard_of(signature, shards.len())]
+                .push_transaction(transaction(signature, slot as u64));
+        }
+        let mut storage = sharded(&shards);
+        let mark_operations = |shard: &str| {
+            QUEUE_SHARD_OPERATIONS_COUNT
+                .with_label_values(&[shard, "mark_transaction_as_parsed"])
+                .get()
+        };
+        let marked_before = (mark_operations("0"), mark_operations("1"));
+
+        let first_batch: Vec<_> = storage
+            .get_transactions()
+            .await
+            .iter()
+            .map(transaction_signature)
+            .collect();
+        let second_batch: Vec<_> = storage
+            .get_transactions()
+            .await
+            .iter()
+            .map(transaction_signature)
+            .collect();
+
+        assert_eq!(first_batch, vec!["signature_1", "signature_2"]);
+        assert_eq!(second_batch, vec!["signature_0", "signature_3"]);
+        assert!(storage.get_transactions().await.is_empty());
+
+        for signature in first_batch.into_iter().chain(second_batch) {
+            storage.mark_transaction_as_parsed(signature).await.unwrap();
+        }
+
+        for shard in &shards {
+            assert_eq!(shard.queue().transactions.len(), 2);
+            assert!(shard
+                .queue()
+                .transactions
+                .iter()
+                .all(|queued| queued.parsing_status == 1));
+        }
+        assert_eq!(mark_operations("0"), marked_before.0 + 2);
+        assert_eq!(mark_operations("1"), marked_before.1 + 2);
+    }
+
+    #[tokio::test]
+    async fn delegations_are_kept_in_the_shard_of_the_stake_account() {
+        let shards = memory_shards("delegations_are_kept_in_the_shard_of_the_stake_account");
+        let mut storage = sharded(&shards);
+        let stake_accs = ["signature_0", "signature_1"].map(String::from);
+
+        storage
+            .save_delegations(
+                stake_accs
+                    .iter()
+                    .map(|stake_acc| Delegation {
+                        stake_acc: stake_acc.clone(),
+                        vote_acc: Some(format!("vote_of_{}", stake_acc)),
+                        delegated_amount: None,
+                    })
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        assert!(shards[1].queue().delegations.contains_key("signature_0"));
+        assert!(shards[0].queue().delegations.contains_key("signature_1"));
+        assert_eq!(shards[0].queue().delegations.len(), 1);
+
+        let mut delegations = storage.get_delegations(stake_accs.to_vec()).await.unwrap();
+        delegations.sort_by(|a, b| a.stake_acc.cmp(&b.stake_acc));
+        assert_eq!(
+            delegations,
+            vec![
+                Delegation {
+                    stake_acc: "signature_0".to_string(),
+                    vote_acc: Some("vote_of_signature_0".to_string()),
+                    delegated_amount: None,
+                },
+                Delegation {
+                    stake_acc: "signature_1".to_string(),
+                    vote_acc: Some("vote_of_signature_1".to_string()),
+                    delegated_amount: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reclaiming_and_blocks_metadata_fan_out_to_every_shard() {
+        let shards = memory_shards("reclaiming_and_blocks_metadata_fan_out_to_every_shard");
+        shards[0]
+            .queue()
+            .blocks_metadata
+            .push((metadata(20), false));
+        shards[1]
+            .queue()
+            .blocks_metadata
+            .push((metadata(10), false));
+        shards[1]
+            .queue()
+            .blocks_metadata
+            .push((metadata(30), false));
+        let mut storage = sharded(&shards);
+
+        let slots: Vec<_> = storage
+            .get_blocks_metadata()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|metadata| metadata.slot)
+            .collect();
+        assert_eq!(slots, vec![10, 20, 30]);
+
+        storage.mark_blocks_metadata_as_parsed(slots).await.unwrap();
+        assert!(storage.get_blocks_metadata().await.unwrap().is_empty());
+
+        let mut storage = ShardedQueueStorage::new(vec![
+            Box::new(StuckShard { stuck: 2 }),
+            Box::new(StuckShard { stuck: 3 }),
+        ]);
+        assert_eq!(storage.reclaim_stuck_transactions(600).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn queue_stats_of_the_shards_are_merged() {
+        let shards = memory_shards("queue_stats_of_the_shards_are_merged");
+        shards[0].push_transaction(transaction("signature_0", 20));
+        shards[1].push_transaction(transaction("signature_1", 10));
+        shards[1].push_transaction(transaction("signature_2", 30));
+        shards[1].queue().transactions[0].transaction.block_time = Some(1643213300);
+        let mut storage = sharded(&shards);
+
+        assert_eq!(
+            storage.queue_stats().await.unwrap(),
+            QueueStats {
+                pending: 3,
+                oldest_pending_slot: Some(10),
+                oldest_pending_block_time: Some(1643213300),
+            }
+        );
+
+        // The claimed transactions are not pending anymore
+        storage.get_transactions().await;
+        assert_eq!(
+            storage.queue_stats().await.unwrap(),
+            QueueStats {
+                pending: 2,
+                oldest_pending_slot: Some(10),
+                oldest_pending_block_time: Some(1643213300),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn queue_progress_of_the_shards_is_merged() {
+        let shards = memory_shards("queue_progress_of_the_shards_is_merged");
+        shards[0].push_transaction(transaction("signature_0", 20));
+        shards[1].push_transaction(transaction("signature_1", 10));
+        shards[1].push_transaction(transaction("signature_2", 30));
+        shards[1].queue().transactions[0].parsing_status = 1;
+        let mut storage = sharded(&shards);
+
+        assert_eq!(
+            storage.queue_progress().await.unwrap(),
+            QueueProgress {
+                lowest_unfinished_slot: Some(20),
+                highest_slot: Some(30),
+            }
+        );
+        assert_eq!(
+            storage.queue_progress().await.unwrap().watermark(),
+            Some(19)
+        );
+    }
+}
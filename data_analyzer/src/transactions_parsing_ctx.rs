@@ -1,12 +1,20 @@
 use crate::actors::collector::CollectorHandle;
 use crate::actors::erroneous_transactions_collector::ErroneousTransactionsCollectorHandle;
+use crate::actors::main_storage_manager::MainStorageManagerHandle;
 use crate::actors::prometheus_exporter::PrometheusExporterHandle;
-use crate::actors::transaction_parser::TransactionParserHandle;
+use crate::actors::transaction_parser::{
+    retain_changed_balances, transaction_signature, TransactionParserHandle,
+};
+use crate::block_time_resolver::BlockTimeResolver;
+use crate::health::SharedHealthState;
+use crate::storages::QueueStats;
 use crate::{actors::queue_manager::QueueManagerHandle, register::Register};
 use crate::{metrics_update, repeat_until_ok};
 use anyhow::Result;
-use log::error;
+use log::{debug, error, info};
+use solana_instruction_parser::TransactionParsingResult;
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{sleep, Duration};
 
 pub struct TransactionsParsingCtx;
@@ -15,35 +23,221 @@ impl TransactionsParsingCtx {
     pub async fn setup_and_run(register: &Register) -> Result<Self> {
         let transaction_queue_manager = QueueManagerHandle::new(register).await?;
         let collector = CollectorHandle::new(register).await?;
+        let main_storage_manager = MainStorageManagerHandle::new(register).await?;
         let erroneous_transactions_collector =
             ErroneousTransactionsCollectorHandle::new(register).await?;
         PrometheusExporterHandle::new(register).await?;
 
-        let transaction_parser = TransactionParserHandle::new().await;
+        let transaction_parser = TransactionParserHandle::new(
+            register
+                .config
+                .get_transactions_parsing_config()
+                .parse_options(),
+            register.tracer.clone(),
+        )
+        .await;
+
+        // Reclaim thread
+        tokio::spawn(TransactionsParsingCtx::reclaim_worker(
+            transaction_queue_manager.clone(),
+            register.config.get_parsing_claim_timeout(),
+        ));
+
+        // Queue stats thread, the counts are not run by the transaction thread
+        let queue_stats_interval = register.config.get_queue_stats_interval();
+        if queue_stats_interval > 0 {
+            tokio::spawn(TransactionsParsingCtx::queue_stats_worker(
+                transaction_queue_manager.clone(),
+                queue_stats_interval,
+            ));
+        }
+
+        // Watermark thread, the queue is read on its own interval as for the stats
+        let watermark_interval = register.config.get_watermark_interval();
+        if watermark_interval > 0 {
+            tokio::spawn(TransactionsParsingCtx::watermark_worker(
+                transaction_queue_manager.clone(),
+                main_storage_manager.clone(),
+                register.health.clone(),
+                watermark_interval,
+            ));
+        }
+
+        let block_time_resolver = BlockTimeResolver::new(
+            register.config.get_block_time_config(),
+            main_storage_manager.clone(),
+        );
 
         // Transaction thread
         tokio::spawn(TransactionsParsingCtx::transaction_worker(
             transaction_queue_manager,
             transaction_parser,
+            block_time_resolver,
+            main_storage_manager,
             collector,
             erroneous_transactions_collector,
+            register
+                .config
+                .get_analysis_config()
+                .store_unchanged_balances,
+            register.health.clone(),
         ));
 
         Ok(Self {})
     }
 
+    async fn reclaim_worker(mut queue_manager: QueueManagerHandle, timeout: u64) {
+        metrics_update!(inc total ACTIVE_WORKERS_COUNT, &["reclaim"]);
+
+        let reclaim_join_handle = tokio::spawn(async move {
+            loop {
+                match queue_manager.reclaim_stuck_transactions(timeout).await {
+                    Ok(0) => {}
+                    Ok(count) => {
+                        info!(
+                            "{} stuck transactions have been returned to the queue",
+                            count
+                        )
+                    }
+                    Err(err) => error!("Failed to reclaim stuck transactions: {}", err),
+                }
+
+                sleep(Duration::from_secs(timeout.max(1))).await;
+            }
+        });
+
+        if reclaim_join_handle.await.is_err() {
+            metrics_update!(dec total ACTIVE_WORKERS_COUNT, &["reclaim"]);
+            error!("Reclaim worker has been killed");
+        }
+    }
+
+    async fn queue_stats_worker(mut queue_manager: QueueManagerHandle, interval: u64) {
+        metrics_update!(inc total ACTIVE_WORKERS_COUNT, &["queue_stats"]);
+
+        let queue_stats_join_handle = tokio::spawn(async move {
+            loop {
+                // The first count waits for the interval as well, so it doesn't delay the
+                // first batch of a restarted analyzer
+                sleep(Duration::from_secs(interval)).await;
+
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since_epoch| since_epoch.as_secs() as i64);
+
+                if let Err(err) =
+                    TransactionsParsingCtx::export_queue_stats(&mut queue_manager, now).await
+                {
+                    error!("Failed to get the queue stats: {}", err);
+                }
+            }
+        });
+
+        if queue_stats_join_handle.await.is_err() {
+            metrics_update!(dec total ACTIVE_WORKERS_COUNT, &["queue_stats"]);
+            error!("Queue stats worker has been killed");
+        }
+    }
+
+    /// Sets the queue gauges, the age of the oldest pending transaction is counted up to `now`
+    async fn export_queue_stats(
+        queue_manager: &mut QueueManagerHandle,
+        now: i64,
+    ) -> Result<QueueStats> {
+        let stats = queue_manager.get_queue_stats().await?;
+
+        metrics_update!(set QUEUE_PENDING_COUNT, stats.pending as f64);
+        metrics_update!(
+            set QUEUE_OLDEST_PENDING_AGE,
+            stats.oldest_pending_age(now) as f64
+        );
+
+        Ok(stats)
+    }
+
+    async fn watermark_worker(
+        mut queue_manager: QueueManagerHandle,
+        mut main_storage_manager: MainStorageManagerHandle,
+        health: SharedHealthState,
+        interval: u64,
+    ) {
+        metrics_update!(inc total ACTIVE_WORKERS_COUNT, &["watermark"]);
+
+        let watermark_join_handle = tokio::spawn(async move {
+            // The watermark stored before a restart is exported at once and never moved back
+            let mut watermark =
+                repeat_until_ok!(main_storage_manager.get_indexing_watermark().await, 5);
+            TransactionsParsingCtx::export_watermark(watermark, &health);
+
+            loop {
+                sleep(Duration::from_secs(interval)).await;
+
+                match TransactionsParsingCtx::advance_watermark(
+                    &mut queue_manager,
+                    &mut main_storage_manager,
+                    &health,
+                    watermark,
+                )
+                .await
+                {
+                    Ok(advanced) => watermark = advanced,
+                    Err(err) => error!("Failed to advance the indexing watermark: {}", err),
+                }
+            }
+        });
+
+        if watermark_join_handle.await.is_err() {
+            metrics_update!(dec total ACTIVE_WORKERS_COUNT, &["watermark"]);
+            error!("Watermark worker has been killed");
+        }
+    }
+
+    /// Stores the watermark of the queue if it's above the `stored` one and exports the higher
+    /// of them, so the watermark only moves forward
+    async fn advance_watermark(
+        queue_manager: &mut QueueManagerHandle,
+        main_storage_manager: &mut MainStorageManagerHandle,
+        health: &SharedHealthState,
+        stored: Option<u64>,
+    ) -> Result<Option<u64>> {
+        let watermark = match queue_manager.get_queue_progress().await?.watermark() {
+            Some(slot) if Some(slot) > stored => {
+                main_storage_manager.store_indexing_watermark(slot).await?;
+                debug!("Indexing watermark has advanced to slot {}", slot);
+                Some(slot)
+            }
+            _ => stored,
+        };
+
+        TransactionsParsingCtx::export_watermark(watermark, health);
+
+        Ok(watermark)
+    }
+
+    /// Sets the gauge and the watermark of the health endpoints once the watermark is known
+    fn export_watermark(watermark: Option<u64>, health: &SharedHealthState) {
+        if let Some(slot) = watermark {
+            metrics_update!(set INDEXING_WATERMARK, slot as f64);
+            health.write().unwrap().set_watermark(slot);
+        }
+    }
+
     async fn transaction_worker(
         mut queue_manager: QueueManagerHandle,
         mut transaction_parser: TransactionParserHandle,
+        mut block_time_resolver: BlockTimeResolver,
+        mut main_storage_manager: MainStorageManagerHandle,
         mut collector: CollectorHandle,
         mut erroneous_transactions_collector: ErroneousTransactionsCollectorHandle,
+        store_unchanged_balances: bool,
+        health: SharedHealthState,
     ) {
         metrics_update!(inc total ACTIVE_WORKERS_COUNT, &["transaction"]);
 
         let transaction_join_handle = tokio::spawn(async move {
             loop {
                 let loop_timer = metrics_update!(timer LOOP_TIME, &["transaction"]);
-                let encoded_transaction_res = queue_manager
+                let mut encoded_transaction_res = queue_manager
                     .get_transactions()
                     .await
                     .unwrap_or_else(|err| {
@@ -51,13 +245,34 @@ impl TransactionsParsingCtx {
                     });
 
                 if encoded_transaction_res.is_empty() {
+                    // Nothing to parse, the queue still responds
+                    health.write().unwrap().batch_parsed();
                     sleep(Duration::from_millis(5000)).await;
                     continue;
                 }
 
-                for encoded_transaction in encoded_transaction_res {
-                    // ToDo: mark transaction as parsed (2) after instructions and balances will be stored
+                let batch_timer = metrics_update!(timer BATCH_PARSING_TIME);
+
+                block_time_resolver
+                    .resolve_batch(&mut encoded_transaction_res)
+                    .await;
 
+                let slots = encoded_transaction_res
+                    .iter()
+                    .map(|encoded_transaction| encoded_transaction.slot);
+                let batch = repeat_until_ok!(
+                    collector
+                        .begin_batch(
+                            slots.clone().min().unwrap_or_default(),
+                            slots.clone().max().unwrap_or_default()
+                        )
+                        .await,
+                    5
+                );
+                let mut parsed_signatures = Vec::new();
+                let mut erroneous_signatures = Vec::new();
+
+                for encoded_transaction in encoded_transaction_res {
                     // EncodedConfirmedTransactionWithStatusMeta doesn't implement Copy trait
                     let cloned_encoded_transaction = EncodedConfirmedTransactionWithStatusMeta {
                         slot: encoded_transaction.slot,
@@ -68,25 +283,45 @@ impl TransactionsParsingCtx {
                     let parsing_timer = metrics_update!(timer TRANSACTION_PARSING_TIME);
                     let parsing_result = transaction_parser
                         .parse_transaction(cloned_encoded_transaction)
-                        .await;
+                        .await
+                        .unwrap_or_else(|err| {
+                            panic!("Transaction parser has been killed: {:#?}", err);
+                        });
                     metrics_update!(timer observe parsing_timer);
 
                     match parsing_result {
                         Ok(parsing_result) => {
-                            let (instructions, balances, instruction_arguments) = parsing_result;
+                            let TransactionParsingResult {
+                                instructions,
+                                mut balances,
+                                instruction_arguments,
+                                token_transfers,
+                                nft_events,
+                                commission_changes,
+                                claim_events,
+                                entangler_swaps,
+                                anchor_events,
+                                sale_events,
+                                pack_events,
+                                candy_mints,
+                                stake_account_events,
+                                bid_events,
+                                sol_transfers,
+                                ..
+                            } = parsing_result;
 
                             let (delegations, undelegations) = repeat_until_ok!(
                                 transaction_parser
                                     .parse_delegations(
                                         queue_manager.clone(),
+                                        main_storage_manager.clone(),
                                         instructions.clone(),
                                         balances
                                             .iter()
-                                            .map(|balance| {
-                                                (
-                                                    balance.account.clone(),
-                                                    balance.pre_balance.unwrap(),
-                                                )
+                                            .filter_map(|balance| {
+                                                balance.pre_balance.map(|pre_balance| {
+                                                    (balance.account.clone(), pre_balance)
+                                                })
                                             })
                                             .collect(),
                                     )
@@ -94,6 +329,12 @@ impl TransactionsParsingCtx {
                                 5
                             );
 
+                            // Delegations need the pre-balances of all the accounts, so the
+                            // untouched ones are dropped only after they are parsed
+                            if !store_unchanged_balances {
+                                retain_changed_balances(&mut balances);
+                            }
+
                             let tx_signature = instructions[0].tx_signature.clone();
 
                             for instruction in instructions {
@@ -110,6 +351,56 @@ impl TransactionsParsingCtx {
                                 collector.save_balance(balance).await;
                             }
 
+                            for token_transfer in token_transfers {
+                                collector.save_token_transfer(token_transfer).await;
+                            }
+
+                            for nft_event in nft_events {
+                                collector.save_nft_event(nft_event).await;
+                            }
+
+                            for commission_change in commission_changes {
+                                collector.save_commission_change(commission_change).await;
+                            }
+
+                            for claim_event in claim_events {
+                                collector.save_claim_event(claim_event).await;
+                            }
+
+                            for entangler_swap in entangler_swaps {
+                                collector.save_entangler_swap(entangler_swap).await;
+                            }
+
+                            for anchor_event in anchor_events {
+                                collector.save_anchor_event(anchor_event).await;
+                            }
+
+                            for sale_event in sale_events {
+                                collector.save_sale_event(sale_event).await;
+                            }
+
+                            for pack_event in pack_events {
+                                collector.save_pack_event(pack_event).await;
+                            }
+
+                            for candy_mint in candy_mints {
+                                collector.save_candy_mint(candy_mint).await;
+                            }
+
+                            for stake_account_event in stake_account_events {
+                                collector
+                                    .save_stake_account_event(stake_account_event)
+                                    .await;
+                            }
+
+                            for bid_event in bid_events {
+                                collector.save_bid_event(bid_event).await;
+                            }
+
+                            for sol_transfer in sol_transfers {
+                                collector.save_sol_transfer(sol_transfer).await;
+                            }
+
                             for delegation in delegations {
                                 collector.save_delegation(delegation).await;
                             }
@@ -118,29 +409,81 @@ impl TransactionsParsingCtx {
                                 collector.save_undelegation(undelegation).await;
                             }
 
-                            repeat_until_ok!(
-                                queue_manager
-                                    .mark_transaction_as_parsed(tx_signature.clone())
-                                    .await,
-                                5
+                            metrics_update!(inc TRANSACTIONS_PARSED_COUNT);
+
+                            debug!(
+                                actor = "transaction_worker",
+                                tx_signature = tx_signature.as_str();
+                                "Transaction has been parsed"
                             );
+
+                            parsed_signatures.push(tx_signature);
                         }
                         Err(parsing_err) => {
+                            metrics_update!(inc PARSE_ERRORS_COUNT, &[parsing_err.kind()]);
+
+                            let tx_signature = transaction_signature(&encoded_transaction);
+
+                            debug!(
+                                actor = "transaction_worker",
+                                tx_signature = tx_signature.as_str();
+                                "Failed to parse transaction: {}",
+                                parsing_err
+                            );
+
                             if let Err(err) = erroneous_transactions_collector
                                 .handle_error(encoded_transaction, parsing_err)
                                 .await
                             {
-                                log::error!(
+                                error!(
+                                    actor = "transaction_worker",
+                                    tx_signature = tx_signature.as_str();
                                     "Problem occurred when processing erroneous transaction: {:#?}",
                                     err
                                 );
                             } else {
                                 metrics_update!(inc ERRONEOUS_TRANSACTIONS_COUNT);
+                                // The ones the collector failed to take stay claimed and are
+                                // parsed again once they are reclaimed
+                                erroneous_signatures.push(tx_signature);
                             }
                         }
                     }
                 }
+
+                // The transactions are marked only once all the rows of the batch are stored, so
+                // a crash in between leaves them to be parsed again. The collector flushes them
+                // by its triggers while the next batches are parsed
+                let completed = collector.end_batch(batch).await;
+                let mut batch_queue_manager = queue_manager.clone();
+                tokio::spawn(async move {
+                    if completed.await.is_err() {
+                        error!("Collector has been killed before the batch was stored");
+                        return;
+                    }
+
+                    for tx_signature in parsed_signatures {
+                        repeat_until_ok!(
+                            batch_queue_manager
+                                .mark_transaction_as_parsed(tx_signature.clone())
+                                .await,
+                            5
+                        );
+                    }
+
+                    for tx_signature in erroneous_signatures {
+                        repeat_until_ok!(
+                            batch_queue_manager
+                                .mark_transaction_as_erroneous(tx_signature.clone())
+                                .await,
+                            5
+                        );
+                    }
+                });
+
+                metrics_update!(timer observe batch_timer);
                 metrics_update!(timer observe loop_timer);
+                health.write().unwrap().batch_parsed();
             }
         });
 
@@ -150,3 +493,593 @@ impl TransactionsParsingCtx {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::Configuration;
+    use crate::signature_tracing::TraceEvent;
+    use crate::storages::file::read_rows;
+    use crate::storages::main_storage::https_client::InstructionRow;
+    use crate::storages::memory::{MemoryMainStorage, MemoryQueueStorage};
+    use crate::storages::postgre_storage::ERRONEOUS_TRANSACTION;
+    use crate::storages::QueueStorage;
+
+    const TX_SIGNATURE: &str =
+        "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU";
+
+    fn transaction() -> EncodedConfirmedTransactionWithStatusMeta {
+        let compiled_instruction = serde_json::json!({
+            "programIdIndex": 2,
+            "accounts": [0, 1],
+            "data": "3Bxs4h24hBtQy9rw"
+        });
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [TX_SIGNATURE],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [compiled_instruction, compiled_instruction]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1000000, 0, 1],
+                "postBalances": [995000, 0, 1],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        }
+    }
+
+    #[tokio::test]
+    async fn transaction_is_stored_in_main_storage() {
+        let register = Register::new(
+            Configuration::from_toml(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "transactions_parsing_ctx"
+
+                [main_storage]
+                storage_type = "Memory"
+                database_url = "transactions_parsing_ctx"
+                flush_interval_ms = 100
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let queue_storage = MemoryQueueStorage::connect("transactions_parsing_ctx");
+        let main_storage = MemoryMainStorage::connect("transactions_parsing_ctx");
+        queue_storage.push_transaction(transaction());
+
+        TransactionsParsingCtx::setup_and_run(&register)
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if queue_storage.queue().transactions[0].parsing_status == 1 {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        let main = main_storage.main();
+
+        // The batch is marked before and after its rows, the transaction after the batch. A
+        // tick may flush the instructions before the end of the batch in more than one block
+        let mut tables: Vec<_> = main.inserts.iter().map(|(table, _)| *table).collect();
+        tables.dedup();
+        assert_eq!(tables, ["batches", "instructions", "balances", "batches"]);
+        assert_eq!(
+            main.batches
+                .iter()
+                .map(|batch| (batch.min_slot, batch.max_slot, batch.complete))
+                .collect::<Vec<_>>(),
+            [(117946133, 117946133, false), (117946133, 117946133, true)]
+        );
+        assert_eq!(main.batches[0].batch_id, main.batches[1].batch_id);
+
+        let instructions: Vec<_> = main
+            .instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.tx_signature.as_str(),
+                    instruction.program.as_str(),
+                    instruction.instruction_idx,
+                    instruction.slot,
+                    instruction.block_time,
+                )
+            })
+            .collect();
+        assert_eq!(
+            instructions,
+            [0, 1].map(|instruction_idx| (
+                TX_SIGNATURE,
+                "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs",
+                instruction_idx,
+                117946133,
+                Some(1643213404),
+            ))
+        );
+
+        let mut balances: Vec<_> = main
+            .balances
+            .iter()
+            .map(|balance| {
+                (
+                    balance.account.as_str(),
+                    balance.pre_balance,
+                    balance.post_balance,
+                )
+            })
+            .collect();
+        balances.sort();
+        assert_eq!(
+            balances,
+            [
+                (
+                    "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs",
+                    Some(1),
+                    Some(1)
+                ),
+                (
+                    "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                    Some(0),
+                    Some(0)
+                ),
+                (
+                    "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                    Some(1000000),
+                    Some(995000)
+                ),
+            ]
+        );
+        drop(main);
+
+        // The transaction is not claimed again
+        assert_eq!(queue_storage.queue().transactions[0].parsing_status, 1);
+    }
+
+    #[tokio::test]
+    async fn erroneous_transaction_is_not_claimed_again() {
+        let register = Register::new(
+            Configuration::from_toml(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "erroneous_transaction_is_not_claimed_again"
+
+                [main_storage]
+                storage_type = "Memory"
+                database_url = "erroneous_transaction_is_not_claimed_again"
+                flush_interval_ms = 100
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+            )
+            .unwrap(),
+        );
+
+        // Token balance of an account the transaction doesn't have
+        let mut erroneous = transaction();
+        let mut json = serde_json::to_value(&erroneous.transaction).unwrap();
+        json["meta"]["postTokenBalances"] = serde_json::json!([{
+            "accountIndex": 9,
+            "mint": "BNFSDxJuDPM6EYKKZGs5pcR9HYu8t2UjSe18ZUTaBkgM",
+            "uiTokenAmount": {
+                "uiAmount": 1.0,
+                "decimals": 0,
+                "amount": "1",
+                "uiAmountString": "1"
+            }
+        }]);
+        erroneous.transaction = serde_json::from_value(json).unwrap();
+
+        let mut queue_storage =
+            MemoryQueueStorage::connect("erroneous_transaction_is_not_claimed_again");
+        queue_storage.push_transaction(erroneous);
+
+        TransactionsParsingCtx::setup_and_run(&register)
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if queue_storage.queue().transactions[0].parsing_status == ERRONEOUS_TRANSACTION {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(
+            queue_storage.queue().transactions[0].parsing_status,
+            ERRONEOUS_TRANSACTION
+        );
+
+        // Neither reclaimed nor claimed again
+        assert_eq!(
+            queue_storage.reclaim_stuck_transactions(0).await.unwrap(),
+            0
+        );
+        assert!(queue_storage.get_transactions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn traced_transaction_steps_are_logged() {
+        // Instructions are flushed by the threshold of the block rows, so they are stored before
+        // the transaction is marked as parsed
+        let register = Register::new(
+            Configuration::from_toml(&format!(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "traced_transaction_steps_are_logged"
+
+                [main_storage]
+                storage_type = "Memory"
+                database_url = "traced_transaction_steps_are_logged"
+                max_block_rows = 2
+                flush_interval_ms = 60000
+
+                [tracing]
+                signatures = ["{}"]
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+                TX_SIGNATURE
+            ))
+            .unwrap(),
+        );
+
+        let queue_storage = MemoryQueueStorage::connect("traced_transaction_steps_are_logged");
+        queue_storage.push_transaction(transaction());
+
+        TransactionsParsingCtx::setup_and_run(&register)
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if register
+                .tracer
+                .events(TX_SIGNATURE)
+                .contains(&TraceEvent::MarkedParsed)
+            {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        assert_eq!(
+            register.tracer.events(TX_SIGNATURE),
+            [
+                TraceEvent::FetchedByAnalyzer,
+                TraceEvent::Parsed,
+                TraceEvent::InstructionsBuffered,
+                TraceEvent::InstructionsBuffered,
+                TraceEvent::Flushed,
+                TraceEvent::MarkedParsed,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_is_exported_to_files() {
+        let directory = std::env::temp_dir().join("data_analyzer_transaction_is_exported");
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let register = Register::new(
+            Configuration::from_toml(&format!(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "transaction_is_exported_to_files"
+
+                [main_storage]
+                database_url = "file://{}"
+                flush_interval_ms = 100
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+                directory.display()
+            ))
+            .unwrap(),
+        );
+
+        let queue_storage = MemoryQueueStorage::connect("transaction_is_exported_to_files");
+        queue_storage.push_transaction(transaction());
+
+        TransactionsParsingCtx::setup_and_run(&register)
+            .await
+            .unwrap();
+
+        let mut instructions = Vec::new();
+        for _ in 0..50 {
+            instructions = read_rows::<InstructionRow>(&directory, "instructions").unwrap();
+            if !instructions.is_empty() {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        let instructions: Vec<_> = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.tx_signature.as_str(),
+                    instruction.program.as_str(),
+                    instruction.instruction_idx,
+                    instruction.slot,
+                    instruction.block_time,
+                    instruction.account_0.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            instructions,
+            [0, 1].map(|instruction_idx| (
+                TX_SIGNATURE,
+                "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs",
+                instruction_idx,
+                117946133,
+                Some(1643213404),
+                Some("GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm"),
+            ))
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn queue_stats_are_exported() {
+        use crate::actors::prometheus_exporter::{QUEUE_OLDEST_PENDING_AGE, QUEUE_PENDING_COUNT};
+
+        let register = Register::new(
+            Configuration::from_toml(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "queue_stats_are_exported"
+
+                [main_storage]
+                storage_type = "Memory"
+                database_url = "queue_stats_are_exported"
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+            )
+            .unwrap(),
+        );
+        let queue_storage = MemoryQueueStorage::connect("queue_stats_are_exported");
+        let mut parsed = transaction();
+        parsed.slot -= 10;
+        parsed.block_time = Some(1643213300);
+        queue_storage.push_transaction(parsed);
+        queue_storage.queue().transactions[0].parsing_status = 1;
+        queue_storage.push_transaction(transaction());
+        queue_storage.push_transaction(transaction());
+
+        let mut queue_manager = QueueManagerHandle::new(&register).await.unwrap();
+        let stats = TransactionsParsingCtx::export_queue_stats(&mut queue_manager, 1643213464)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stats,
+            QueueStats {
+                pending: 2,
+                oldest_pending_slot: Some(117946133),
+                oldest_pending_block_time: Some(1643213404),
+            }
+        );
+        assert_eq!(QUEUE_PENDING_COUNT.get(), 2.0);
+        assert_eq!(QUEUE_OLDEST_PENDING_AGE.get(), 60.0);
+
+        // Nothing pending
+        for queued in queue_storage.queue().transactions.iter_mut() {
+            queued.parsing_status = 1;
+        }
+        TransactionsParsingCtx::export_queue_stats(&mut queue_manager, 1643213464)
+            .await
+            .unwrap();
+
+        assert_eq!(QUEUE_PENDING_COUNT.get(), 0.0);
+        assert_eq!(QUEUE_OLDEST_PENDING_AGE.get(), 0.0);
+    }
+
+    fn watermark_register(url: &str) -> Register {
+        Register::new(
+            Configuration::from_toml(&format!(
+                r#"
+                [queue_storage]
+                storage_type = "Memory"
+                storage_url = "{url}"
+
+                [main_storage]
+                storage_type = "Memory"
+                database_url = "{url}"
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+                url = url
+            ))
+            .unwrap(),
+        )
+    }
+
+    /// Queues the transaction at every slot with its parsing status
+    fn queue_transactions(queue_storage: &MemoryQueueStorage, slots: &[(u64, i32)]) {
+        for (slot, parsing_status) in slots {
+            let mut queued = transaction();
+            queued.slot = *slot;
+            queue_storage.push_transaction(queued);
+            queue_storage
+                .queue()
+                .transactions
+                .last_mut()
+                .unwrap()
+                .parsing_status = *parsing_status;
+        }
+    }
+
+    #[tokio::test]
+    async fn watermark_stops_before_the_first_unfinished_slot() {
+        use crate::actors::prometheus_exporter::INDEXING_WATERMARK;
+
+        let register = watermark_register("watermark_stops_before_the_first_unfinished_slot");
+        let queue_storage =
+            MemoryQueueStorage::connect("watermark_stops_before_the_first_unfinished_slot");
+        let main_storage =
+            MemoryMainStorage::connect("watermark_stops_before_the_first_unfinished_slot");
+        let mut queue_manager = QueueManagerHandle::new(&register).await.unwrap();
+        let mut main_storage_manager = MainStorageManagerHandle::new(&register).await.unwrap();
+
+        // Nothing is queued yet
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, None);
+        assert_eq!(register.health.read().unwrap().watermark(), None);
+        assert!(main_storage.main().indexing_watermarks.is_empty());
+
+        // A pending transaction between the parsed ones
+        queue_transactions(&queue_storage, &[(10, 1), (20, 0), (30, 1)]);
+
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            watermark,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, Some(19));
+        assert_eq!(main_storage.main().indexing_watermarks, [19]);
+        assert_eq!(INDEXING_WATERMARK.get(), 19.0);
+        assert_eq!(register.health.read().unwrap().watermark(), Some(19));
+
+        // All of them are parsed
+        queue_storage.queue().transactions[1].parsing_status = 1;
+
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            watermark,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, Some(30));
+        assert_eq!(main_storage.main().indexing_watermarks, [19, 30]);
+    }
+
+    #[tokio::test]
+    async fn watermark_is_not_moved_back_after_restart() {
+        let register = watermark_register("watermark_is_not_moved_back_after_restart");
+        let queue_storage =
+            MemoryQueueStorage::connect("watermark_is_not_moved_back_after_restart");
+        let main_storage = MemoryMainStorage::connect("watermark_is_not_moved_back_after_restart");
+
+        // Stored by the analyzer before the restart, a transaction below it has been reclaimed
+        main_storage.main().indexing_watermarks.push(25);
+        queue_transactions(&queue_storage, &[(10, 1), (20, 0), (30, 1)]);
+
+        let mut queue_manager = QueueManagerHandle::new(&register).await.unwrap();
+        let mut main_storage_manager = MainStorageManagerHandle::new(&register).await.unwrap();
+        let stored = main_storage_manager.get_indexing_watermark().await.unwrap();
+
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            stored,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, Some(25));
+        assert_eq!(main_storage.main().indexing_watermarks, [25]);
+        assert_eq!(register.health.read().unwrap().watermark(), Some(25));
+
+        queue_storage.queue().transactions[1].parsing_status = 1;
+
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            watermark,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, Some(30));
+        assert_eq!(main_storage.main().indexing_watermarks, [25, 30]);
+    }
+
+    #[tokio::test]
+    async fn erroneous_transaction_does_not_stall_the_watermark() {
+        let register = watermark_register("erroneous_transaction_does_not_stall_the_watermark");
+        let queue_storage =
+            MemoryQueueStorage::connect("erroneous_transaction_does_not_stall_the_watermark");
+        let main_storage =
+            MemoryMainStorage::connect("erroneous_transaction_does_not_stall_the_watermark");
+        let mut queue_manager = QueueManagerHandle::new(&register).await.unwrap();
+        let mut main_storage_manager = MainStorageManagerHandle::new(&register).await.unwrap();
+
+        // Stored in erroneous_transactions between the parsed ones
+        queue_transactions(
+            &queue_storage,
+            &[(10, 1), (20, ERRONEOUS_TRANSACTION), (30, 1)],
+        );
+
+        let watermark = TransactionsParsingCtx::advance_watermark(
+            &mut queue_manager,
+            &mut main_storage_manager,
+            &register.health,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watermark, Some(30));
+        assert_eq!(main_storage.main().indexing_watermarks, [30]);
+    }
+}
@@ -2,15 +2,28 @@ use anyhow::Result;
 use log::info;
 use tokio::sync::mpsc;
 
-use crate::{register::Register, storages::queue_storage::QueueStorage};
+use crate::{
+    configuration::LoadingStatusCheckConfig,
+    contract_keys::SharedContractKeys,
+    prometheus_ctx::{PENDING_SIGNATURES, PENDING_SIGNATURES_ESTIMATED, SKIPPED_SIGNATURES_COUNT},
+    register::Register,
+    storages::queue_storage::QueueStorage,
+};
 
 struct LoadingStatusChecker {
     receiver: mpsc::Receiver<LoadingStatusCheckerMessage>,
     queue_storage: QueueStorage,
+    /// Skip the signatures with errors, they're never loaded with
+    /// `load_only_successful_transactions`
+    skip_errored: bool,
+    /// Programs whose pending signatures are counted
+    contract_keys: SharedContractKeys,
+    config: LoadingStatusCheckConfig,
 }
 
 enum LoadingStatusCheckerMessage {
     ResetLoadingStatus,
+    CountPendingSignatures,
 }
 
 impl LoadingStatusChecker {
@@ -19,10 +32,15 @@ impl LoadingStatusChecker {
         receiver: mpsc::Receiver<LoadingStatusCheckerMessage>,
     ) -> Result<Self> {
         let queue_storage =
-            QueueStorage::new(&register.config.get_queue_storage_config().database_url).await?;
+            QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
         Ok(LoadingStatusChecker {
             receiver,
             queue_storage,
+            skip_errored: register
+                .config
+                .get_load_only_successful_transactions_status(),
+            contract_keys: register.contract_keys.clone(),
+            config: register.config.get_loading_status_check_config().clone(),
         })
     }
 
@@ -31,6 +49,9 @@ impl LoadingStatusChecker {
             LoadingStatusCheckerMessage::ResetLoadingStatus => {
                 self.reset_loading_status()?;
             }
+            LoadingStatusCheckerMessage::CountPendingSignatures => {
+                self.count_pending_signatures()?;
+            }
         }
 
         Ok(())
@@ -44,8 +65,51 @@ impl LoadingStatusChecker {
         info!("Loading status checker stopped");
     }
 
+    /// Gives the failed loadings another chance and skips the signatures which won't be loaded,
+    /// so the pending signatures gauge counts only the ones the loaders are going to claim
     fn reset_loading_status(&self) -> Result<()> {
-        self.queue_storage.reset_loading_status()?;
+        self.in_batches(|limit| self.queue_storage.reset_loading_status(limit))?;
+
+        if self.skip_errored {
+            let skipped =
+                self.in_batches(|limit| self.queue_storage.skip_errored_signatures(limit))?;
+            if skipped > 0 {
+                info!("Skipped {} signatures of failed transactions", skipped);
+                SKIPPED_SIGNATURES_COUNT.inc_by(skipped as u64);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs the update until a batch is not full, every batch is a separate statement, so the
+    /// rows are not locked for long. Returns the number of the updated rows
+    fn in_batches(&self, update: impl Fn(i64) -> Result<usize>) -> Result<usize> {
+        let mut updated = 0;
+
+        loop {
+            let batch = update(self.config.batch_size)?;
+            updated += batch;
+
+            if (batch as i64) < self.config.batch_size {
+                return Ok(updated);
+            }
+        }
+    }
+
+    fn count_pending_signatures(&self) -> Result<()> {
+        for program in &self.contract_keys.keys() {
+            let pending = self
+                .queue_storage
+                .count_pending_signatures(program, self.config.exact_count_limit)?;
+
+            PENDING_SIGNATURES
+                .with_label_values(&[program.as_str()])
+                .set(pending.count);
+            PENDING_SIGNATURES_ESTIMATED
+                .with_label_values(&[program.as_str()])
+                .set(i64::from(!pending.exact));
+        }
 
         Ok(())
     }
@@ -70,4 +134,10 @@ impl LoadingStatusCheckerHandle {
 
         let _ = self.sender.send(msg).await;
     }
+
+    pub async fn count_pending_signatures(&self) {
+        let msg = LoadingStatusCheckerMessage::CountPendingSignatures;
+
+        let _ = self.sender.send(msg).await;
+    }
 }
@@ -1,10 +1,20 @@
-use crate::{register::Register, storages::queue_storage::*};
+use crate::{
+    configuration::LoadingFairness,
+    contract_keys::SharedContractKeys,
+    register::Register,
+    signature_tracing::{SignatureTracer, TraceEvent},
+    storages::queue_storage::*,
+};
 use anyhow::Result;
 use tokio::sync::{mpsc, oneshot};
 
 struct QueueManager {
     receiver: mpsc::Receiver<QueueManagerMessage>,
     queue_storage: QueueStorage,
+    fairness: LoadingFairness,
+    /// Programs with their weights, the ones weighted above the lowest weight are claimed first
+    contract_keys: SharedContractKeys,
+    tracer: SignatureTracer,
 }
 
 enum QueueManagerMessage {
@@ -28,21 +38,58 @@ impl QueueManager {
         Ok(QueueManager {
             receiver,
             queue_storage: QueueStorage::new(
-                &register.config.get_queue_storage_config().database_url,
+                &register.config.get_queue_storage_config().database_urls(),
             )
             .await?,
+            fairness: register.config.get_loading_fairness(),
+            contract_keys: register.contract_keys.clone(),
+            tracer: register.tracer.clone(),
         })
     }
 
+    fn get_signature(&mut self, load_only_successful_transactions: bool) -> Option<String> {
+        // The keys may be reloaded between the claims
+        let weighted_programs = self.contract_keys.weighted_keys();
+        let programs: Vec<String> = weighted_programs
+            .iter()
+            .map(|(program, _)| program.clone())
+            .collect();
+
+        for programs in &preferred_programs(weighted_programs) {
+            let signature = match self.fairness {
+                LoadingFairness::Global => self
+                    .queue_storage
+                    .get_signature_from_queue(load_only_successful_transactions, Some(programs)),
+                LoadingFairness::PerProgramRoundRobin => self
+                    .queue_storage
+                    .get_program_signature_round_robin(load_only_successful_transactions, programs),
+            };
+
+            if signature.is_some() {
+                return signature;
+            }
+        }
+
+        match self.fairness {
+            LoadingFairness::Global => self
+                .queue_storage
+                .get_signature_from_queue(load_only_successful_transactions, None),
+            LoadingFairness::PerProgramRoundRobin => self
+                .queue_storage
+                .get_signature_round_robin(load_only_successful_transactions, &programs),
+        }
+    }
+
     fn handle_message(&mut self, msg: QueueManagerMessage) -> Result<()> {
         match msg {
             QueueManagerMessage::GetSignature {
                 respond_to,
                 load_only_successful_transactions,
             } => {
-                let signature = self
-                    .queue_storage
-                    .get_signature_from_queue(load_only_successful_transactions);
+                let signature = self.get_signature(load_only_successful_transactions);
+                if let Some(signature) = &signature {
+                    self.tracer.trace(signature, TraceEvent::Claimed);
+                }
                 let _ = respond_to.send(signature);
             }
             QueueManagerMessage::MarkSignatureAsLoaded { signature } => {
@@ -70,6 +117,29 @@ impl QueueManager {
     }
 }
 
+/// Groups the programs weighted above the lowest weight by weight, the heaviest first. Nothing is
+/// preferred when all the weights are equal
+fn preferred_programs(weighted_programs: Vec<(String, u32)>) -> Vec<Vec<String>> {
+    let mut weights: Vec<u32> = weighted_programs
+        .iter()
+        .map(|(_, weight)| *weight)
+        .collect();
+    weights.sort_unstable_by(|a, b| b.cmp(a));
+    weights.dedup();
+    weights.pop();
+
+    weights
+        .into_iter()
+        .map(|group_weight| {
+            weighted_programs
+                .iter()
+                .filter(|(_, weight)| *weight == group_weight)
+                .map(|(program, _)| program.clone())
+                .collect()
+        })
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct QueueManagerHandle {
     sender: mpsc::Sender<QueueManagerMessage>,
@@ -108,3 +178,39 @@ impl QueueManagerHandle {
         let _ = self.sender.send(msg).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weighted(programs: &[(&str, u32)]) -> Vec<(String, u32)> {
+        programs
+            .iter()
+            .map(|(program, weight)| (program.to_string(), *weight))
+            .collect()
+    }
+
+    #[test]
+    fn equal_weights_prefer_nothing() {
+        assert!(preferred_programs(weighted(&[("a", 1), ("b", 1)])).is_empty());
+        assert!(preferred_programs(weighted(&[("a", 5)])).is_empty());
+        assert!(preferred_programs(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn heavier_programs_are_preferred_first() {
+        assert_eq!(
+            preferred_programs(weighted(&[
+                ("a", 1),
+                ("b", 10),
+                ("c", 5),
+                ("d", 10),
+                ("e", 1)
+            ])),
+            vec![
+                vec!["b".to_string(), "d".to_string()],
+                vec!["c".to_string()]
+            ]
+        );
+    }
+}
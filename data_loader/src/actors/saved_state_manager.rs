@@ -1,7 +1,11 @@
-use crate::{register::Register, storages::queue_storage::*};
+use crate::{
+    register::Register, solana_client::TRANSACTIONS_BATCH_LEN, storages::queue_storage::*,
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
 use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
 use tokio::sync::{mpsc, oneshot};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -11,6 +15,40 @@ pub struct SavedState {
     pub until: Option<Signature>,
 }
 
+impl SavedState {
+    /// Moves the state past a non-empty batch of signatures, newest first, loaded with
+    /// `before` and `until` of this state. Returns true when the pass is finished: a batch
+    /// shorter than TRANSACTIONS_BATCH_LEN means that nothing older is left to load, either
+    /// `until` or the first transaction of the program is reached. The newest signature of
+    /// the pass becomes `until` of the next one then, so a restarted loader only walks
+    /// the signatures which appeared since.
+    pub fn advance(&mut self, signatures: &[RpcConfirmedTransactionStatusWithSignature]) -> bool {
+        if self.newest_transaction.is_none() {
+            self.newest_transaction = signatures
+                .first()
+                .and_then(|s| Signature::from_str(&s.signature).ok());
+        }
+
+        if signatures.len() < TRANSACTIONS_BATCH_LEN {
+            if self.newest_transaction.is_some() {
+                self.until = self.newest_transaction;
+            }
+
+            self.before = None;
+            self.newest_transaction = None;
+
+            return true;
+        }
+
+        // The next batch starts with the last signature of this one, so the signatures
+        // are stored without a gap between the batches
+        let before_idx = signatures.len().saturating_sub(2);
+        self.before = Signature::from_str(&signatures[before_idx].signature).ok();
+
+        false
+    }
+}
+
 struct SavedStateManager {
     receiver: mpsc::Receiver<SavedStateManagerMessage>,
     queue_storage: QueueStorage,
@@ -29,7 +67,7 @@ impl SavedStateManager {
         receiver: mpsc::Receiver<SavedStateManagerMessage>,
     ) -> Result<SavedStateManager> {
         let queue_storage =
-            QueueStorage::new(&register.config.get_queue_storage_config().database_url).await?;
+            QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
         Ok(SavedStateManager {
             receiver,
             queue_storage,
@@ -1,6 +1,6 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
-use crate::solana_client::*;
+use crate::{configuration::SolanaClientConfig, solana_client::*};
 use log::{error, info};
 use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
 use solana_sdk::pubkey::Pubkey;
@@ -23,14 +23,16 @@ enum SignaturesRpcLoaderMessage {
 
 impl SignaturesRpcLoader {
     async fn new(
-        client_type: &ClientType,
+        solana_client_config: &SolanaClientConfig,
         receiver: mpsc::Receiver<SignaturesRpcLoaderMessage>,
         url: &str,
         account_key: &str,
+        rate_limiter: Option<Arc<RateLimiter>>,
     ) -> Self {
         SignaturesRpcLoader {
             receiver,
-            rpc_client: crate::solana_client::new_with_url(client_type, url).await,
+            rpc_client: crate::solana_client::new_with_url(solana_client_config, url, rate_limiter)
+                .await,
             account_key: account_key.to_string(),
         }
     }
@@ -65,7 +67,7 @@ impl SignaturesRpcLoader {
             .load_signatures_batch(
                 &Pubkey::from_str(&self.account_key).unwrap(),
                 saved_state.before,
-                None,
+                saved_state.until,
             )
             .await;
 
@@ -87,10 +89,21 @@ pub struct SignaturesRpcLoaderHandle {
 }
 
 impl SignaturesRpcLoaderHandle {
-    pub async fn new(client_type: &ClientType, url: &str, account_key: &str) -> Self {
+    pub async fn new(
+        solana_client_config: &SolanaClientConfig,
+        url: &str,
+        account_key: &str,
+        rate_limiter: Option<Arc<RateLimiter>>,
+    ) -> Self {
         let (sender, receiver) = mpsc::channel(16);
-        let mut signatures_rpc_loader =
-            SignaturesRpcLoader::new(client_type, receiver, url, account_key).await;
+        let mut signatures_rpc_loader = SignaturesRpcLoader::new(
+            solana_client_config,
+            receiver,
+            url,
+            account_key,
+            rate_limiter,
+        )
+        .await;
         tokio::spawn(async move { signatures_rpc_loader.run().await });
 
         Self { sender }
@@ -112,3 +125,155 @@ impl SignaturesRpcLoaderHandle {
             .expect("SignaturesRpcLoader task has been killed")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_client::client_error::ClientError;
+    use solana_sdk::signature::Signature;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Signatures of a program, newest first, served the way getSignaturesForAddress does
+    struct FakeClient {
+        history: Vec<RpcConfirmedTransactionStatusWithSignature>,
+        batches: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl SolanaClient for FakeClient {
+        async fn load_signatures_batch(
+            &self,
+            _account_key: &Pubkey,
+            before: Option<Signature>,
+            until: Option<Signature>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+
+            let position = |signature: Option<Signature>| {
+                signature.and_then(|signature| {
+                    self.history
+                        .iter()
+                        .position(|s| s.signature == signature.to_string())
+                })
+            };
+            let start = position(before).map_or(0, |idx| idx + 1);
+            let end = position(until).unwrap_or(self.history.len()).max(start);
+
+            Ok(self.history[start..end]
+                .iter()
+                .take(TRANSACTIONS_BATCH_LEN)
+                .cloned()
+                .collect())
+        }
+
+        async fn load_transaction_info(
+            &self,
+            _signature: &str,
+        ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+            unimplemented!()
+        }
+    }
+
+    fn loader(history_len: usize) -> (SignaturesRpcLoader, Vec<String>, Arc<AtomicUsize>) {
+        let history: Vec<_> = (0..history_len)
+            .map(|idx| RpcConfirmedTransactionStatusWithSignature {
+                signature: Signature::new_unique().to_string(),
+                slot: (history_len - idx) as u64,
+                err: None,
+                memo: None,
+                block_time: None,
+                confirmation_status: None,
+            })
+            .collect();
+        let signatures = history.iter().map(|s| s.signature.clone()).collect();
+        let batches = Arc::new(AtomicUsize::new(0));
+
+        let (_, receiver) = mpsc::channel(1);
+        let loader = SignaturesRpcLoader {
+            receiver,
+            rpc_client: Box::new(FakeClient {
+                history,
+                batches: batches.clone(),
+            }),
+            account_key: Pubkey::new_unique().to_string(),
+        };
+
+        (loader, signatures, batches)
+    }
+
+    /// Loads batches the way SignaturesLoadingCtx does until the pass is finished
+    async fn load_pass(loader: &SignaturesRpcLoader, saved_state: &mut SavedState) {
+        loop {
+            let signatures = loader.process_load_signatures(*saved_state).await;
+
+            if signatures.is_empty() || saved_state.advance(&signatures) {
+                break;
+            }
+        }
+    }
+
+    fn signature(signature: &str) -> Option<Signature> {
+        Some(Signature::from_str(signature).unwrap())
+    }
+
+    #[tokio::test]
+    async fn full_history_without_until() {
+        let (loader, signatures, batches) = loader(1200);
+        let mut saved_state = SavedState {
+            newest_transaction: None,
+            before: None,
+            until: None,
+        };
+
+        load_pass(&loader, &mut saved_state).await;
+
+        // 500 + 500 + 202, the batches overlap by a signature
+        assert_eq!(batches.load(Ordering::SeqCst), 3);
+        assert_eq!(saved_state.until, signature(&signatures[0]));
+        assert_eq!(saved_state.before, None);
+    }
+
+    #[tokio::test]
+    async fn restart_stops_at_until() {
+        let (loader, signatures, batches) = loader(10_000);
+        // 700 signatures appeared since the previous pass
+        let mut saved_state = SavedState {
+            newest_transaction: None,
+            before: None,
+            until: signature(&signatures[700]),
+        };
+
+        load_pass(&loader, &mut saved_state).await;
+
+        assert_eq!(batches.load(Ordering::SeqCst), 2);
+        assert_eq!(saved_state.until, signature(&signatures[0]));
+
+        // Nothing new, a single request
+        load_pass(&loader, &mut saved_state).await;
+
+        assert_eq!(batches.load(Ordering::SeqCst), 3);
+        assert_eq!(saved_state.until, signature(&signatures[0]));
+    }
+
+    #[tokio::test]
+    async fn restart_in_the_middle_of_a_pass() {
+        let (loader, signatures, batches) = loader(10_000);
+        // The loader was stopped after the first batch of a pass over 1200 new signatures
+        let mut saved_state = SavedState {
+            newest_transaction: signature(&signatures[0]),
+            before: signature(&signatures[498]),
+            until: signature(&signatures[1200]),
+        };
+
+        load_pass(&loader, &mut saved_state).await;
+
+        assert_eq!(batches.load(Ordering::SeqCst), 2);
+        assert_eq!(saved_state.until, signature(&signatures[0]));
+        assert_eq!(saved_state.newest_transaction, None);
+    }
+}
@@ -18,6 +18,7 @@ enum SignaturesSaverMessage {
         signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
         program_address: Pubkey,
         saved_state: Box<SavedState>,
+        pass_finished: bool,
         respond_to: oneshot::Sender<usize>,
     },
 }
@@ -28,7 +29,7 @@ impl SignaturesSaver {
         receiver: mpsc::Receiver<SignaturesSaverMessage>,
     ) -> Result<Self> {
         let queue_storage =
-            QueueStorage::new(&register.config.get_queue_storage_config().database_url).await?;
+            QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
         Ok(SignaturesSaver {
             receiver,
             queue_storage,
@@ -41,10 +42,15 @@ impl SignaturesSaver {
                 signatures,
                 program_address,
                 saved_state,
+                pass_finished,
                 respond_to,
             } => {
-                let signatures_stored =
-                    self.save_signatures_and_state(signatures, program_address, *saved_state)?;
+                let signatures_stored = self.save_signatures_and_state(
+                    signatures,
+                    program_address,
+                    *saved_state,
+                    pass_finished,
+                )?;
                 let _ = respond_to.send(signatures_stored);
             }
         }
@@ -65,11 +71,13 @@ impl SignaturesSaver {
         signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
         program_address: Pubkey,
         saved_state: SavedState,
+        pass_finished: bool,
     ) -> Result<usize> {
         let signatures_stored = self.queue_storage.store_signatures_and_state(
             &signatures,
             &program_address.to_string(),
             &serde_json::to_string(&saved_state)?,
+            pass_finished,
         )?;
 
         Ok(signatures_stored)
@@ -95,12 +103,14 @@ impl SignaturesSaverHandle {
         signatures: Vec<RpcConfirmedTransactionStatusWithSignature>,
         program_address: Pubkey,
         saved_state: SavedState,
+        pass_finished: bool,
     ) -> usize {
         let (sender, receiver) = oneshot::channel();
         let msg = SignaturesSaverMessage::SaveSignaturesAndState {
             signatures,
             program_address,
             saved_state: Box::new(saved_state),
+            pass_finished,
             respond_to: sender,
         };
 
@@ -1,4 +1,7 @@
-use crate::{repeat_until_ok, solana_client::*};
+use std::sync::Arc;
+
+use crate::{configuration::SolanaClientConfig, repeat_until_ok, solana_client::*};
+use indexer_errors::ErrorClass;
 use log::info;
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 use tokio::sync::{mpsc, oneshot};
@@ -17,13 +20,15 @@ enum TransactionsRpcLoaderMessage {
 
 impl TransactionsRpcLoader {
     async fn new(
-        client_type: &ClientType,
+        solana_client_config: &SolanaClientConfig,
         receiver: mpsc::Receiver<TransactionsRpcLoaderMessage>,
         url: &str,
+        rate_limiter: Option<Arc<RateLimiter>>,
     ) -> Self {
         TransactionsRpcLoader {
             receiver,
-            rpc_client: crate::solana_client::new_with_url(client_type, url).await,
+            rpc_client: crate::solana_client::new_with_url(solana_client_config, url, rate_limiter)
+                .await,
         }
     }
 
@@ -50,7 +55,11 @@ impl TransactionsRpcLoader {
         &self,
         signature: &str,
     ) -> EncodedConfirmedTransactionWithStatusMeta {
-        repeat_until_ok!(self.rpc_client.load_transaction_info(signature).await, 5)
+        repeat_until_ok!(
+            self.rpc_client.load_transaction_info(signature).await,
+            5,
+            ErrorClass::External
+        )
     }
 }
 
@@ -60,10 +69,14 @@ pub struct TransactionsRpcLoaderHandle {
 }
 
 impl TransactionsRpcLoaderHandle {
-    pub async fn new(client_type: &ClientType, url: &str) -> Self {
+    pub async fn new(
+        solana_client_config: &SolanaClientConfig,
+        url: &str,
+        rate_limiter: Option<Arc<RateLimiter>>,
+    ) -> Self {
         let (sender, receiver) = mpsc::channel(3);
         let mut transactions_rpc_loader =
-            TransactionsRpcLoader::new(client_type, receiver, url).await;
+            TransactionsRpcLoader::new(solana_client_config, receiver, url, rate_limiter).await;
         tokio::spawn(async move { transactions_rpc_loader.run().await });
 
         Self { sender }
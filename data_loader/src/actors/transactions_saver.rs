@@ -1,13 +1,18 @@
 use anyhow::Result;
-use log::info;
+use log::{error, info};
 use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 use tokio::sync::{mpsc, oneshot};
 
-use crate::{register::Register, storages::queue_storage::QueueStorage};
+use crate::{
+    register::Register,
+    signature_tracing::{SignatureTracer, TraceEvent},
+    storages::queue_storage::QueueStorage,
+};
 
 struct TransactionsSaver {
     receiver: mpsc::Receiver<TransactionsSaverMessage>,
     queue_storage: QueueStorage,
+    tracer: SignatureTracer,
 }
 
 enum TransactionsSaverMessage {
@@ -24,11 +29,12 @@ impl TransactionsSaver {
         receiver: mpsc::Receiver<TransactionsSaverMessage>,
     ) -> Result<Self> {
         let queue_storage =
-            QueueStorage::new(&register.config.get_queue_storage_config().database_url).await?;
+            QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
 
         Ok(TransactionsSaver {
             receiver,
             queue_storage,
+            tracer: register.tracer.clone(),
         })
     }
 
@@ -60,8 +66,19 @@ impl TransactionsSaver {
         signature: String,
         transaction: EncodedConfirmedTransactionWithStatusMeta,
     ) -> Result<()> {
-        self.queue_storage
-            .store_transaction(&signature, transaction)?;
+        match self
+            .queue_storage
+            .store_transaction(&signature, transaction)
+        {
+            Ok(()) => self.tracer.trace(&signature, TraceEvent::StoredToQueue),
+            // A rejected transaction is loaded again after the loading faults are reset
+            Err(err) if err.is::<serde_json::Error>() => {
+                error!("Transaction {} is not stored: {}", signature, err);
+                self.queue_storage.mark_signature_loading_fault(signature)?;
+            }
+            Err(err) => return Err(err),
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use crate::{
+    register::Register,
+    solana_client::{new_with_url, SolanaClient},
+    storages::queue_storage::{models::NewBlockMetadata, QueueStorage},
+};
+use anyhow::Result;
+use log::{error, info};
+use solana_sdk::clock::Slot;
+use solana_transaction_status::UiConfirmedBlock;
+use tokio::time::sleep;
+
+/// Slots of a shard whose blocks are loaded by one pass
+const BLOCKS_BATCH_LEN: i64 = 100;
+/// Pause after a pass which stored nothing, every pending slot has its metadata or the client
+/// failed
+const IDLE_SLEEP: Duration = Duration::from_secs(5);
+
+/// Loads the blocks of the slots of the pending transactions and stores their metadata in
+/// `blocks_metadata`, where the analyzer reads it from
+pub struct BlocksLoadingCtx {}
+
+impl BlocksLoadingCtx {
+    pub async fn setup_and_run(register: &Register) -> Result<Self> {
+        let mut queue_storage =
+            QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
+        let client = new_with_url(
+            register.config.get_solana_client_config(),
+            &register.config.get_endpoint_url(),
+            register.rate_limiter.clone(),
+        )
+        .await;
+
+        tokio::spawn(async move {
+            loop {
+                match Self::load_blocks(&mut queue_storage, client.as_ref()).await {
+                    Ok(0) => sleep(IDLE_SLEEP).await,
+                    Ok(stored) => info!("Stored the metadata of {} blocks", stored),
+                    Err(err) => {
+                        error!("Failed to load the blocks metadata: {}", err);
+                        sleep(IDLE_SLEEP).await;
+                    }
+                }
+            }
+        });
+
+        info!("Blocks metadata loader spawned");
+
+        Ok(Self {})
+    }
+
+    /// Loads the blocks of a batch of the slots without metadata, returns the number of the
+    /// stored rows
+    async fn load_blocks(
+        queue_storage: &mut QueueStorage,
+        client: &dyn SolanaClient,
+    ) -> Result<usize> {
+        let mut blocks = Vec::new();
+
+        for slot in queue_storage.slots_without_blocks_metadata(BLOCKS_BATCH_LEN)? {
+            for (slot, block) in client.load_confirmed_blocks(slot..slot + 1).await? {
+                blocks.push(block_metadata(slot, &block)?);
+            }
+        }
+
+        if blocks.is_empty() {
+            return Ok(0);
+        }
+
+        queue_storage.store_blocks_metadata(&blocks)
+    }
+}
+
+/// Row of the block in `blocks_metadata`, the rewards are stored as the JSON of `Rewards`
+fn block_metadata(slot: Slot, block: &UiConfirmedBlock) -> Result<NewBlockMetadata> {
+    Ok(NewBlockMetadata {
+        slot: slot as i64,
+        blockhash: block.blockhash.clone(),
+        rewards: serde_json::to_string(block.rewards.as_deref().unwrap_or_default())?,
+        block_time: block.block_time,
+        block_height: block.block_height.map(|height| height as i64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::{Reward, RewardType, Rewards};
+
+    #[test]
+    fn block_metadata_keeps_the_rewards() {
+        let reward = Reward {
+            pubkey: "leader".to_string(),
+            lamports: 5000,
+            post_balance: 1_000_005_000,
+            reward_type: Some(RewardType::Fee),
+            commission: None,
+        };
+        let block = UiConfirmedBlock {
+            previous_blockhash: "previous_blockhash".to_string(),
+            blockhash: "blockhash".to_string(),
+            parent_slot: 99,
+            transactions: None,
+            signatures: None,
+            rewards: Some(vec![reward.clone()]),
+            block_time: Some(1_650_000_000),
+            block_height: Some(90),
+        };
+
+        let metadata = block_metadata(100, &block).unwrap();
+
+        assert_eq!(
+            metadata,
+            NewBlockMetadata {
+                slot: 100,
+                blockhash: "blockhash".to_string(),
+                rewards: metadata.rewards.clone(),
+                block_time: Some(1_650_000_000),
+                block_height: Some(90),
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<Rewards>(&metadata.rewards).unwrap(),
+            vec![reward]
+        );
+    }
+
+    #[test]
+    fn block_without_rewards_has_empty_rewards() {
+        let block = UiConfirmedBlock {
+            previous_blockhash: String::new(),
+            blockhash: "blockhash".to_string(),
+            parent_slot: 0,
+            transactions: None,
+            signatures: None,
+            rewards: None,
+            block_time: None,
+            block_height: None,
+        };
+
+        assert_eq!(block_metadata(1, &block).unwrap().rewards, "[]");
+    }
+}
@@ -1,16 +1,94 @@
-use crate::solana_client::ClientType;
+use crate::solana_client::{ClientType, ReplayMode};
 use anyhow::Result;
 use config::{Config, Environment};
+use indexer_errors::ConfigurationErrors;
+use indexer_telemetry::LogFormat;
 use serde::Deserialize;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct QueueStorageConfig {
+    /// Database of the queue when it's not sharded
+    #[serde(default)]
     pub database_url: String,
+    /// Databases the signatures are spread over by their hashes, `database_url` is not used
+    /// when they're set. The analyzer has to be configured with the same shards in the same order
+    #[serde(default)]
+    pub shards: Vec<String>,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+impl QueueStorageConfig {
+    /// Databases of the shards, the single database is the only shard
+    pub fn database_urls(&self) -> Vec<String> {
+        if self.shards.is_empty() {
+            vec![self.database_url.clone()]
+        } else {
+            self.shards.clone()
+        }
+    }
+}
+
+/// Truncation of the JSON of parsed transactions, the rows themselves are kept so the loaded
+/// signatures are not inserted again. Off unless enabled in the config
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default = "default_retention_enabled")]
+    pub enabled: bool,
+    /// Seconds between retention passes
+    #[serde(default = "default_retention_period")]
+    pub period: u64,
+    /// Parsed transactions with a block_time older than that are truncated
+    #[serde(default = "default_retention_min_age_days")]
+    pub min_age_days: u32,
+    /// Rows truncated by one statement
+    #[serde(default = "default_retention_batch_size")]
+    pub batch_size: i64,
+}
+
+fn default_retention_enabled() -> bool {
+    false
+}
+
+fn default_retention_period() -> u64 {
+    3600
+}
+
+fn default_retention_min_age_days() -> u32 {
+    30
+}
+
+fn default_retention_batch_size() -> i64 {
+    10000
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_retention_enabled(),
+            period: default_retention_period(),
+            min_age_days: default_retention_min_age_days(),
+            batch_size: default_retention_batch_size(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ContractKeys {
     pub keys: Vec<String>,
+    /// Weights of the `keys` in the same order, 1 for the missing ones. A program is polled
+    /// for new signatures proportionally often and its signatures are loaded first
+    #[serde(default)]
+    pub weights: Vec<u32>,
+    /// Seconds between the reloads of the keys from the configuration file, 0 disables them
+    #[serde(default = "default_reload_period")]
+    pub reload_period: u64,
+}
+
+fn default_reload_period() -> u64 {
+    60
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -18,25 +96,161 @@ pub struct EndPoint {
     url: String,
 }
 
+/// The signatures of a program are polled slower while many of them wait for their
+/// transactions, and not at all above `backlog_hard_cap`, so the RPC quota is not spent on a
+/// backlog the transactions loaders can't keep up with
 #[derive(Debug, Clone, Deserialize)]
 pub struct SignaturesLoading {
-    reset_status_period: u64,
+    pub reset_status_period: u64,
+    /// Pending signatures of a program above which its polls are delayed, 0 never delays them
+    #[serde(default = "default_backlog_threshold")]
+    pub backlog_threshold: i64,
+    /// Pending signatures of a program at which its polling stops until they drain
+    #[serde(default = "default_backlog_hard_cap")]
+    pub backlog_hard_cap: i64,
+    /// Seconds of the delay just below the hard cap, the delay grows linearly from 0 at the
+    /// threshold. The backlog above the hard cap is checked again after that long
+    #[serde(default = "default_max_poll_interval")]
+    pub max_poll_interval: u64,
+}
+
+fn default_backlog_threshold() -> i64 {
+    100000
+}
+
+fn default_backlog_hard_cap() -> i64 {
+    1000000
+}
+
+fn default_max_poll_interval() -> u64 {
+    60
+}
+
+/// Passes of the loading status checker over the signatures. The statuses are reset every
+/// `reset_status_period` of `[signatures_loading]`, the pending signatures are counted every
+/// `count_period`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadingStatusCheckConfig {
+    /// Seconds between the counts of the pending signatures of every program
+    #[serde(default = "default_count_period")]
+    pub count_period: u64,
+    /// Pending signatures of a program counted exactly, the planner's estimate is exported for
+    /// a program with more of them. 0 exports the estimates only
+    #[serde(default = "default_exact_count_limit")]
+    pub exact_count_limit: i64,
+    /// Signatures a statement of the status reset updates
+    #[serde(default = "default_status_batch_size")]
+    pub batch_size: i64,
+}
+
+fn default_count_period() -> u64 {
+    60
+}
+
+fn default_exact_count_limit() -> i64 {
+    100000
+}
+
+fn default_status_batch_size() -> i64 {
+    10000
+}
+
+impl Default for LoadingStatusCheckConfig {
+    fn default() -> Self {
+        Self {
+            count_period: default_count_period(),
+            exact_count_limit: default_exact_count_limit(),
+            batch_size: default_status_batch_size(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct TransactionsLoading {
     number_of_threads: usize,
     load_only_successful_transactions: bool,
+    #[serde(default)]
+    fairness: LoadingFairness,
+}
+
+/// The order the transactions loaders claim the pending signatures in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadingFairness {
+    /// The newest signatures of all programs first
+    #[default]
+    Global,
+    /// The newest signature of each program in turn
+    PerProgramRoundRobin,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SolanaClientConfig {
+    pub client_type: ClientType,
+    /// Required by the `Replay` client type
+    pub replay: Option<ReplayConfig>,
+    /// Ledger directory of the `LedgerDir` client type
+    pub ledger_path: Option<String>,
+    /// RPC url of the `BigTableWithRpcFallback` client type, the data missing in BigTable is
+    /// requested from it
+    pub fallback_url: Option<String>,
+    /// Limit of the requests of all the loaders, not limited without it
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// Requests per second
+    pub rps: f64,
+    /// Requests which may be sent at once after an idle period
+    pub burst: u32,
+    /// Share of the rate and the burst reserved for each of the signatures and the
+    /// transactions requests, the rest is taken by whichever comes first
+    #[serde(default = "default_reserved_share")]
+    pub reserved_share: f64,
+}
+
+fn default_reserved_share() -> f64 {
+    0.1
 }
 
 #[derive(Debug, Clone, Deserialize)]
-pub struct SolanaClient {
-    client_type: ClientType,
+pub struct ReplayConfig {
+    pub mode: ReplayMode,
+    /// Directory of the recorded responses
+    pub directory: String,
+    /// The client whose responses are recorded
+    #[serde(default = "default_recorded_client_type")]
+    pub client_type: ClientType,
+}
+
+fn default_recorded_client_type() -> ClientType {
+    ClientType::Rpc
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct PrometheusExporter {
     bind_address: String,
+    /// Seconds without a loaded batch after which `/readyz` reports the loading as stalled
+    #[serde(default = "default_readiness_staleness")]
+    readiness_staleness: u64,
+}
+
+fn default_readiness_staleness() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+/// Signatures whose loading steps are logged, see `SignatureTracer`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub signatures: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -45,25 +259,173 @@ pub struct Configuration {
     contracts: ContractKeys,
     endpoint: EndPoint,
     signatures_loading: SignaturesLoading,
+    #[serde(default)]
+    loading_status_check: LoadingStatusCheckConfig,
     transactions_loading: TransactionsLoading,
-    solana_client: SolanaClient,
+    solana_client: SolanaClientConfig,
     prometheus_exporter: PrometheusExporter,
+    #[serde(default)]
+    logging: LoggingConfig,
+    #[serde(default)]
+    tracing: TracingConfig,
 }
 
 impl Configuration {
     pub fn new(filename: &str) -> Result<Self> {
-        Ok(Config::builder()
+        let config = Config::builder()
             .add_source(config::File::with_name(filename))
             .add_source(
                 Environment::with_prefix("dl")
                     .prefix_separator("__")
                     .separator("__")
+                    .with_list_parse_key("queue_storage.shards")
                     .with_list_parse_key("contracts.keys")
+                    .with_list_parse_key("contracts.weights")
+                    .with_list_parse_key("tracing.signatures")
                     .list_separator(",")
                     .try_parsing(true),
             )
             .build()?
-            .try_deserialize::<Configuration>()?)
+            .try_deserialize::<Configuration>()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads the configuration from a TOML string without the environment variables
+    #[cfg(test)]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let config = Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize::<Configuration>()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks the values serde can't, every problem is reported rather than the first one
+    fn validate(&self) -> Result<(), ConfigurationErrors> {
+        let mut errors = ConfigurationErrors::new();
+
+        errors.check(
+            !self.queue_storage.database_url.is_empty() || !self.queue_storage.shards.is_empty(),
+            || "Either database_url or shards of the queue storage must be set".to_string(),
+        );
+        for (idx, url) in self.queue_storage.database_urls().iter().enumerate() {
+            if !url.is_empty() {
+                errors.check_url(
+                    &format!("queue_storage database {}", idx),
+                    url,
+                    &["postgres", "postgresql"],
+                );
+            }
+        }
+        errors.check(self.queue_storage.retention.batch_size > 0, || {
+            "queue_storage.retention.batch_size must be positive".to_string()
+        });
+
+        for key in &self.contracts.keys {
+            errors.check(Pubkey::from_str(key).is_ok(), || {
+                format!("Contract key {:?} is not a base58 public key", key)
+            });
+        }
+        errors.check(
+            self.contracts.weights.len() <= self.contracts.keys.len(),
+            || {
+                format!(
+                    "{} contract weights are set for {} contract keys",
+                    self.contracts.weights.len(),
+                    self.contracts.keys.len()
+                )
+            },
+        );
+        errors.check(!self.contracts.weights.contains(&0), || {
+            "Contract weights must be positive".to_string()
+        });
+
+        errors.check_url("endpoint.url", &self.endpoint.url, &["http", "https"]);
+
+        let signatures_loading = &self.signatures_loading;
+        if signatures_loading.backlog_threshold > 0 {
+            errors.check(
+                signatures_loading.backlog_hard_cap > signatures_loading.backlog_threshold,
+                || {
+                    format!(
+                        "signatures_loading.backlog_hard_cap {} must be above backlog_threshold {}",
+                        signatures_loading.backlog_hard_cap, signatures_loading.backlog_threshold
+                    )
+                },
+            );
+            errors.check(signatures_loading.max_poll_interval > 0, || {
+                "signatures_loading.max_poll_interval must be positive".to_string()
+            });
+        }
+
+        errors.check(self.transactions_loading.number_of_threads > 0, || {
+            "transactions_loading.number_of_threads must be positive".to_string()
+        });
+        errors.check(self.loading_status_check.batch_size > 0, || {
+            "Loading status check batch_size must be positive".to_string()
+        });
+
+        self.validate_solana_client(&mut errors);
+
+        errors.check_bind_address(
+            "prometheus_exporter.bind_address",
+            &self.prometheus_exporter.bind_address,
+        );
+
+        for signature in &self.tracing.signatures {
+            errors.check(Signature::from_str(signature).is_ok(), || {
+                format!("Traced signature {:?} is not a base58 signature", signature)
+            });
+        }
+
+        errors.into_result()
+    }
+
+    /// The sections and the fields the client type requires
+    fn validate_solana_client(&self, errors: &mut ConfigurationErrors) {
+        let solana_client = &self.solana_client;
+
+        match solana_client.client_type {
+            ClientType::Replay => errors.check(solana_client.replay.is_some(), || {
+                "The Replay client requires the [solana_client.replay] section".to_string()
+            }),
+            ClientType::LedgerDir => errors.check(
+                solana_client
+                    .ledger_path
+                    .as_ref()
+                    .map_or(false, |path| !path.is_empty()),
+                || "The LedgerDir client requires solana_client.ledger_path".to_string(),
+            ),
+            ClientType::BigTableWithRpcFallback => match &solana_client.fallback_url {
+                Some(fallback_url) => errors.check_url(
+                    "solana_client.fallback_url",
+                    fallback_url,
+                    &["http", "https"],
+                ),
+                None => errors
+                    .push("The BigTableWithRpcFallback client requires solana_client.fallback_url"),
+            },
+            _ => {}
+        }
+
+        if let Some(replay) = &solana_client.replay {
+            errors.check(!replay.directory.is_empty(), || {
+                "solana_client.replay.directory must be set".to_string()
+            });
+        }
+
+        if let Some(rate_limit) = &solana_client.rate_limit {
+            errors.check(rate_limit.rps > 0.0 && rate_limit.burst > 0, || {
+                "Rate limit rps and burst must be positive".to_string()
+            });
+            errors.check((0.0..0.5).contains(&rate_limit.reserved_share), || {
+                "Rate limit reserved_share must be at least 0 and below 0.5".to_string()
+            });
+        }
     }
 
     pub fn get_queue_storage_config(&self) -> &QueueStorageConfig {
@@ -74,6 +436,25 @@ impl Configuration {
         self.contracts.keys.clone()
     }
 
+    /// Contract keys with their weights, 1 if the weight is not set
+    pub fn get_weighted_account_keys(&self) -> Vec<(String, u32)> {
+        self.contracts
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(idx, account_key)| {
+                (
+                    account_key.clone(),
+                    self.contracts.weights.get(idx).copied().unwrap_or(1),
+                )
+            })
+            .collect()
+    }
+
+    pub fn get_contract_keys_reload_period(&self) -> u64 {
+        self.contracts.reload_period
+    }
+
     pub fn get_endpoint_url(&self) -> String {
         self.endpoint.url.clone()
     }
@@ -86,15 +467,184 @@ impl Configuration {
         self.transactions_loading.load_only_successful_transactions
     }
 
-    pub fn get_solana_client_type(&self) -> &ClientType {
-        &self.solana_client.client_type
+    pub fn get_loading_fairness(&self) -> LoadingFairness {
+        self.transactions_loading.fairness
+    }
+
+    pub fn get_solana_client_config(&self) -> &SolanaClientConfig {
+        &self.solana_client
     }
 
     pub fn get_reset_status_period(&self) -> u64 {
         self.signatures_loading.reset_status_period
     }
 
+    pub fn get_signatures_loading_config(&self) -> &SignaturesLoading {
+        &self.signatures_loading
+    }
+
+    pub fn get_loading_status_check_config(&self) -> &LoadingStatusCheckConfig {
+        &self.loading_status_check
+    }
+
     pub fn get_prometheus_exporter_bind_address(&self) -> String {
         self.prometheus_exporter.bind_address.clone()
     }
+
+    pub fn get_readiness_staleness(&self) -> u64 {
+        self.prometheus_exporter.readiness_staleness
+    }
+
+    pub fn get_logging_config(&self) -> &LoggingConfig {
+        &self.logging
+    }
+
+    pub fn get_tracing_config(&self) -> &TracingConfig {
+        &self.tracing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+        [queue_storage]
+        database_url = "postgres://localhost/queue"
+
+        [contracts]
+        keys = ["Stake11111111111111111111111111111111111111"]
+
+        [endpoint]
+        url = "http://localhost:8899"
+
+        [signatures_loading]
+        reset_status_period = 300
+
+        [transactions_loading]
+        number_of_threads = 1
+        load_only_successful_transactions = false
+
+        [solana_client]
+        client_type = "Rpc"
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:0"
+    "#;
+
+    /// Problems of the valid configuration with the `replacements` applied
+    fn problems(replacements: &[(&str, &str)]) -> Vec<String> {
+        let toml = replacements
+            .iter()
+            .fold(VALID.to_string(), |toml, (from, to)| toml.replace(from, to));
+
+        Config::builder()
+            .add_source(config::File::from_str(&toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize::<Configuration>()
+            .unwrap()
+            .validate()
+            .map_or_else(|errors| errors.problems().to_vec(), |_| Vec::new())
+    }
+
+    #[test]
+    fn valid_configuration_has_no_problems() {
+        assert!(problems(&[]).is_empty());
+        assert!(Configuration::from_toml(VALID).is_ok());
+    }
+
+    #[test]
+    fn endpoint_url_must_be_http() {
+        let problems = problems(&[("http://localhost:8899", "localhost:8899")]);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("endpoint.url"));
+    }
+
+    #[test]
+    fn contract_keys_must_be_public_keys() {
+        let problems = problems(&[(
+            r#"keys = ["Stake11111111111111111111111111111111111111"]"#,
+            r#"keys = ["Stake11111111111111111111111111111111111111", "0OIl", "metaplex"]"#,
+        )]);
+
+        assert_eq!(
+            problems,
+            [
+                r#"Contract key "0OIl" is not a base58 public key"#,
+                r#"Contract key "metaplex" is not a base58 public key"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn number_of_threads_must_be_positive() {
+        assert_eq!(
+            problems(&[("number_of_threads = 1", "number_of_threads = 0")]),
+            ["transactions_loading.number_of_threads must be positive"]
+        );
+    }
+
+    #[test]
+    fn backlog_hard_cap_must_be_above_the_threshold() {
+        assert_eq!(
+            problems(&[(
+                "reset_status_period = 300",
+                "reset_status_period = 300\nbacklog_threshold = 5000\nbacklog_hard_cap = 5000",
+            )]),
+            ["signatures_loading.backlog_hard_cap 5000 must be above backlog_threshold 5000"]
+        );
+        // Without the backpressure the cap is not used
+        assert!(problems(&[(
+            "reset_status_period = 300",
+            "reset_status_period = 300\nbacklog_threshold = 0\nbacklog_hard_cap = 0",
+        )])
+        .is_empty());
+    }
+
+    #[test]
+    fn bind_address_must_be_a_socket_address() {
+        let problems = problems(&[("127.0.0.1:0", "localhost")]);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("prometheus_exporter.bind_address"));
+    }
+
+    #[test]
+    fn client_type_requires_its_section() {
+        assert_eq!(
+            problems(&[(r#"client_type = "Rpc""#, r#"client_type = "Replay""#)]),
+            ["The Replay client requires the [solana_client.replay] section"]
+        );
+        assert_eq!(
+            problems(&[(
+                r#"client_type = "Rpc""#,
+                r#"client_type = "BigTableWithRpcFallback""#
+            )]),
+            ["The BigTableWithRpcFallback client requires solana_client.fallback_url"]
+        );
+    }
+
+    #[test]
+    fn unknown_client_type_is_rejected() {
+        let toml = VALID.replace(r#"client_type = "Rpc""#, r#"client_type = "Grpc""#);
+
+        assert!(Configuration::from_toml(&toml).is_err());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let problems = problems(&[
+            ("postgres://localhost/queue", "mysql://localhost/queue"),
+            ("number_of_threads = 1", "number_of_threads = 0"),
+            ("127.0.0.1:0", "127.0.0.1"),
+            (
+                "[solana_client]",
+                "[loading_status_check]\nbatch_size = 0\n[solana_client]",
+            ),
+        ]);
+
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+    }
 }
@@ -0,0 +1,256 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use log::{error, info};
+
+use crate::{configuration::Configuration, prometheus_ctx::ACTIVE_CONTRACT_KEYS};
+
+/// Contract keys with their weights in the order of the configuration. The keys reloaded from
+/// the configuration file replace them while the loader runs, so the actors read them on every
+/// use instead of keeping a copy
+#[derive(Debug, Clone)]
+pub struct SharedContractKeys(Arc<RwLock<Vec<(String, u32)>>>);
+
+impl SharedContractKeys {
+    pub fn new(weighted_keys: Vec<(String, u32)>) -> Self {
+        ACTIVE_CONTRACT_KEYS.set(weighted_keys.len() as i64);
+
+        Self(Arc::new(RwLock::new(weighted_keys)))
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn weighted_keys(&self) -> Vec<(String, u32)> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Adds the key or updates its weight
+    fn insert(&self, key: String, weight: u32) {
+        let mut weighted_keys = self.0.write().unwrap();
+
+        match weighted_keys.iter_mut().find(|(known, _)| *known == key) {
+            Some((_, known_weight)) => *known_weight = weight,
+            None => weighted_keys.push((key, weight)),
+        }
+
+        ACTIVE_CONTRACT_KEYS.set(weighted_keys.len() as i64);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut weighted_keys = self.0.write().unwrap();
+        weighted_keys.retain(|(known, _)| known != key);
+
+        ACTIVE_CONTRACT_KEYS.set(weighted_keys.len() as i64);
+    }
+}
+
+/// Signatures loading of the programs started and stopped by the reloads of the keys
+#[async_trait]
+pub trait ProgramLoaders: Send {
+    async fn start(&mut self, key: &str, weight: u32) -> Result<()>;
+    /// Stops polling the signatures of the program, the signatures and the transactions already
+    /// stored are kept and the claimed ones are still loaded
+    fn stop(&mut self, key: &str);
+}
+
+/// Applies the changes of `contracts.keys` of the configuration file to the running loaders
+pub struct ContractKeysReloader<L> {
+    config_file: String,
+    contract_keys: SharedContractKeys,
+    loaders: L,
+}
+
+impl<L: ProgramLoaders> ContractKeysReloader<L> {
+    pub fn new(config_file: &str, contract_keys: SharedContractKeys, loaders: L) -> Self {
+        Self {
+            config_file: config_file.to_string(),
+            contract_keys,
+            loaders,
+        }
+    }
+
+    /// Re-reads the configuration file, stops the loading of the removed keys and starts the one
+    /// of the added keys. An invalid file keeps the current keys, a key whose loading failed to
+    /// start is not added, so the next reload starts it again. The new weights of the kept keys
+    /// change the order the transactions are claimed in, not the polling of their signatures
+    pub async fn reload(&mut self) -> Result<()> {
+        let weighted_keys = Configuration::new(&self.config_file)?.get_weighted_account_keys();
+        let current_keys = self.contract_keys.keys();
+
+        for key in &current_keys {
+            if !weighted_keys.iter().any(|(new_key, _)| new_key == key) {
+                info!(program = key.as_str(); "{}: Removed from the contract keys", key);
+                self.loaders.stop(key);
+                self.contract_keys.remove(key);
+            }
+        }
+
+        for (key, weight) in weighted_keys {
+            if !current_keys.contains(&key) {
+                info!(program = key.as_str(); "{}: Added to the contract keys", key);
+
+                if let Err(err) = self.loaders.start(&key, weight).await {
+                    error!(
+                        program = key.as_str();
+                        "{}: Signatures loading not started: {:#}", key, err
+                    );
+                    continue;
+                }
+            }
+
+            self.contract_keys.insert(key, weight);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[derive(Default)]
+    struct MockLoaders {
+        started: Vec<(String, u32)>,
+        stopped: Vec<String>,
+        failing: Vec<String>,
+    }
+
+    #[async_trait]
+    impl ProgramLoaders for MockLoaders {
+        async fn start(&mut self, key: &str, weight: u32) -> Result<()> {
+            if self.failing.iter().any(|failing| failing == key) {
+                return Err(anyhow!("Connection refused"));
+            }
+
+            self.started.push((key.to_string(), weight));
+            Ok(())
+        }
+
+        fn stop(&mut self, key: &str) {
+            self.stopped.push(key.to_string());
+        }
+    }
+
+    /// Public keys named by the tests `a`, `b` and `c`
+    const A: &str = "Stake11111111111111111111111111111111111111";
+    const B: &str = "Vote111111111111111111111111111111111111111";
+    const C: &str = "Config1111111111111111111111111111111111111";
+
+    fn write_config(file: &std::path::Path, keys: &[&str], weights: &[u32]) {
+        std::fs::write(
+            file,
+            format!(
+                r#"
+                [queue_storage]
+                database_url = "postgres://localhost/queue"
+
+                [contracts]
+                keys = {:?}
+                weights = {:?}
+
+                [endpoint]
+                url = "http://localhost:8899"
+
+                [signatures_loading]
+                reset_status_period = 300
+
+                [transactions_loading]
+                number_of_threads = 1
+                load_only_successful_transactions = false
+
+                [solana_client]
+                client_type = "Rpc"
+
+                [prometheus_exporter]
+                bind_address = "127.0.0.1:0"
+                "#,
+                keys, weights
+            ),
+        )
+        .unwrap();
+    }
+
+    fn weighted(keys: &[(&str, u32)]) -> Vec<(String, u32)> {
+        keys.iter()
+            .map(|(key, weight)| (key.to_string(), *weight))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn added_keys_are_started_and_removed_ones_stopped() {
+        let file = std::env::temp_dir().join("contract_keys_added_and_removed.toml");
+        let contract_keys = SharedContractKeys::new(weighted(&[(A, 1), (B, 1)]));
+        let mut reloader = ContractKeysReloader::new(
+            file.to_str().unwrap(),
+            contract_keys.clone(),
+            MockLoaders::default(),
+        );
+
+        write_config(&file, &[B, C], &[5, 2]);
+        reloader.reload().await.unwrap();
+
+        assert_eq!(reloader.loaders.started, weighted(&[(C, 2)]));
+        assert_eq!(reloader.loaders.stopped, [A]);
+        assert_eq!(contract_keys.weighted_keys(), weighted(&[(B, 5), (C, 2)]));
+
+        // Nothing changes on a reload of the same file
+        reloader.reload().await.unwrap();
+
+        assert_eq!(reloader.loaders.started.len(), 1);
+        assert_eq!(reloader.loaders.stopped.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_start_is_retried_by_the_next_reload() {
+        let file = std::env::temp_dir().join("contract_keys_failed_start.toml");
+        let contract_keys = SharedContractKeys::new(weighted(&[(A, 1)]));
+        let mut reloader = ContractKeysReloader::new(
+            file.to_str().unwrap(),
+            contract_keys.clone(),
+            MockLoaders {
+                failing: vec![B.to_string()],
+                ..Default::default()
+            },
+        );
+
+        write_config(&file, &[A, B], &[]);
+        reloader.reload().await.unwrap();
+
+        assert!(reloader.loaders.started.is_empty());
+        assert_eq!(contract_keys.keys(), [A]);
+
+        reloader.loaders.failing.clear();
+        reloader.reload().await.unwrap();
+
+        assert_eq!(reloader.loaders.started, weighted(&[(B, 1)]));
+        assert_eq!(contract_keys.keys(), [A, B]);
+    }
+
+    #[tokio::test]
+    async fn invalid_config_keeps_the_keys() {
+        let file = std::env::temp_dir().join("contract_keys_invalid_config.toml");
+        let contract_keys = SharedContractKeys::new(weighted(&[(A, 1)]));
+        let mut reloader = ContractKeysReloader::new(
+            file.to_str().unwrap(),
+            contract_keys.clone(),
+            MockLoaders::default(),
+        );
+
+        // More weights than keys
+        write_config(&file, &[B], &[1, 2]);
+
+        assert!(reloader.reload().await.is_err());
+        assert!(reloader.loaders.stopped.is_empty());
+        assert_eq!(contract_keys.keys(), [A]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Loading of the queued signatures into PostgreSQL started in Docker, the transactions are
+//! served by the Replay client from a directory instead of the RPC. The tests need a running
+//! Docker, so they are ignored unless run with `--ignored`
+
+use crate::configuration::Configuration;
+use crate::register::Register;
+use crate::storages::queue_storage::{schema, QueueStorage};
+use crate::transactions_loading_ctx::TransactionsLoadingCtx;
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+use integration_tests::{retry, Postgres, TRANSACTION, TRANSACTION_SIGNATURE};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta,
+};
+
+const PROGRAM: &str = "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs";
+
+/// Directory of the Replay client with the fixture transaction
+fn replay_directory() -> Result<String> {
+    let directory = std::env::temp_dir().join("data_loader_end_to_end_tests");
+    let _ = std::fs::remove_dir_all(&directory);
+
+    std::fs::create_dir_all(directory.join("transactions"))?;
+    std::fs::write(
+        directory
+            .join("transactions")
+            .join(format!("{}.json", TRANSACTION_SIGNATURE)),
+        TRANSACTION,
+    )?;
+
+    Ok(directory.to_str().unwrap().to_string())
+}
+
+#[tokio::test]
+#[ignore]
+async fn queued_signature_is_loaded() -> Result<()> {
+    let postgres = Postgres::start().await?;
+    let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_str(TRANSACTION)?;
+
+    let storage = QueueStorage::new(&[postgres.url.clone()]).await?;
+    storage.store_signatures_and_state(
+        &[RpcConfirmedTransactionStatusWithSignature {
+            signature: TRANSACTION_SIGNATURE.to_string(),
+            slot: transaction.slot,
+            err: None,
+            memo: None,
+            block_time: transaction.block_time,
+            confirmation_status: None,
+        }],
+        PROGRAM,
+        "{}",
+        true,
+    )?;
+
+    let register = Register::new(Configuration::from_toml(&format!(
+        r#"
+        [queue_storage]
+        database_url = "{}"
+
+        [endpoint]
+        url = "http://127.0.0.1:8899"
+
+        [contracts]
+        keys = ["{}"]
+
+        [signatures_loading]
+        reset_status_period = 300
+
+        [transactions_loading]
+        number_of_threads = 1
+        load_only_successful_transactions = true
+
+        [solana_client]
+        client_type = "Replay"
+
+        [solana_client.replay]
+        mode = "replay"
+        directory = "{}"
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:0"
+        "#,
+        postgres.url,
+        PROGRAM,
+        replay_directory()?
+    ))?);
+
+    TransactionsLoadingCtx::setup_and_run(&register).await?;
+
+    let connection = postgres.connection()?;
+    let (slot, block_time, stored) = retry(|| async {
+        schema::transactions::table
+            .filter(schema::transactions::signature.eq(TRANSACTION_SIGNATURE))
+            .select((
+                schema::transactions::slot,
+                schema::transactions::block_time,
+                schema::transactions::transaction,
+            ))
+            .first::<(Option<i32>, Option<i32>, Option<String>)>(&connection)
+    })
+    .await?;
+
+    assert_eq!(slot, Some(117946133));
+    assert_eq!(block_time, Some(1643213404));
+
+    let stored: EncodedTransactionWithStatusMeta =
+        serde_json::from_str(&stored.ok_or_else(|| anyhow!("The transaction is truncated"))?)?;
+    assert_eq!(stored, transaction.transaction);
+
+    let loading_status = schema::signatures::table
+        .filter(schema::signatures::signature.eq(TRANSACTION_SIGNATURE))
+        .select(schema::signatures::loading_status)
+        .first::<Option<i32>>(&connection)?;
+    assert_eq!(loading_status, Some(2));
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::register::Register;
+use crate::solana_client::SolanaClient;
+use crate::storages::queue_storage::{FetchedTransactionRow, QueueStorage};
+
+/// Printed by `fetch-tx` as JSON
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct FetchSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// "success" or the error of the transaction, "unknown" without the status meta
+    pub status: String,
+    pub transaction_row: Option<FetchedTransactionRow>,
+}
+
+/// Loads the transaction with the configured client, stores it in the queue and marks the
+/// signature as loaded, see `QueueStorage::store_fetched_transaction`
+pub async fn fetch_tx(register: &Register, signature: &str, force: bool) -> Result<FetchSummary> {
+    let client = crate::solana_client::new_with_url(
+        register.config.get_solana_client_config(),
+        &register.config.get_endpoint_url(),
+        register.rate_limiter.clone(),
+    )
+    .await;
+    let (transaction, mut summary) = fetch(client.as_ref(), signature).await?;
+
+    let queue_storage =
+        QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
+    summary.transaction_row =
+        Some(queue_storage.store_fetched_transaction(signature, transaction, force)?);
+
+    Ok(summary)
+}
+
+/// Loads the transaction once, a failed request is not repeated
+async fn fetch(
+    client: &dyn SolanaClient,
+    signature: &str,
+) -> Result<(EncodedConfirmedTransactionWithStatusMeta, FetchSummary)> {
+    Signature::from_str(signature).with_context(|| format!("Invalid signature {}", signature))?;
+
+    let transaction = client
+        .load_transaction_info(signature)
+        .await
+        .with_context(|| format!("Failed to fetch transaction {}", signature))?;
+
+    let status = match &transaction.transaction.meta {
+        Some(meta) => meta
+            .err
+            .as_ref()
+            .map_or("success".to_string(), |err| err.to_string()),
+        None => "unknown".to_string(),
+    };
+
+    let summary = FetchSummary {
+        signature: signature.to_string(),
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        status,
+        transaction_row: None,
+    };
+
+    Ok((transaction, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        rpc_response::RpcConfirmedTransactionStatusWithSignature,
+    };
+    use solana_sdk::pubkey::Pubkey;
+
+    /// Serves the transaction for any signature, fails if there is none
+    struct FakeClient {
+        transaction: Option<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl SolanaClient for FakeClient {
+        async fn load_signatures_batch(
+            &self,
+            _account_key: &Pubkey,
+            _before: Option<Signature>,
+            _until: Option<Signature>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+            unimplemented!()
+        }
+
+        async fn load_transaction_info(
+            &self,
+            _signature: &str,
+        ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+            match &self.transaction {
+                Some(transaction) => Ok(serde_json::from_value(transaction.clone())?),
+                None => Err(ClientError {
+                    request: None,
+                    kind: ClientErrorKind::Custom("Connection refused".to_string()),
+                }),
+            }
+        }
+    }
+
+    fn transaction(err: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "slot": 117946133,
+            "blockTime": 1643213404,
+            "transaction": {
+                "signatures": [],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0
+                    },
+                    "accountKeys": [],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": []
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn summary_of_fetched_transaction() {
+        let signature = Signature::new_unique().to_string();
+
+        for (err, status) in [
+            (serde_json::Value::Null, "success"),
+            (
+                serde_json::json!({ "InstructionError": [0, "InvalidAccountData"] }),
+                "Error processing Instruction 0: invalid account data for instruction",
+            ),
+        ] {
+            let client = FakeClient {
+                transaction: Some(transaction(err)),
+            };
+
+            let (transaction, summary) = fetch(&client, &signature).await.unwrap();
+
+            assert_eq!(transaction.slot, 117946133);
+            assert_eq!(
+                summary,
+                FetchSummary {
+                    signature: signature.clone(),
+                    slot: 117946133,
+                    block_time: Some(1643213404),
+                    status: status.to_string(),
+                    transaction_row: None,
+                }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_is_an_error() {
+        let client = FakeClient { transaction: None };
+
+        let err = fetch(&client, &Signature::new_unique().to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Failed to fetch transaction"));
+
+        let client = FakeClient {
+            transaction: Some(transaction(serde_json::Value::Null)),
+        };
+
+        let err = fetch(&client, "not a signature").await.unwrap_err();
+        assert!(err.to_string().starts_with("Invalid signature"));
+    }
+}
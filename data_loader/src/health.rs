@@ -0,0 +1,136 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use hyper::{header::CONTENT_TYPE, Body, Response};
+use serde::Serialize;
+
+/// Progress of the loading published by the loading contexts for the readiness probe
+#[derive(Debug)]
+pub struct HealthState {
+    /// Time of the last successfully loaded batch, the start time until the first one
+    last_batch_at: Instant,
+}
+
+impl Default for HealthState {
+    fn default() -> Self {
+        Self {
+            last_batch_at: Instant::now(),
+        }
+    }
+}
+
+impl HealthState {
+    pub fn batch_loaded(&mut self) {
+        self.last_batch_at = Instant::now();
+    }
+
+    /// Fails if no batch has been loaded within `staleness`
+    pub fn check_staleness(&self, staleness: Duration) -> Result<(), String> {
+        let elapsed = self.last_batch_at.elapsed();
+
+        if elapsed > staleness {
+            return Err(format!(
+                "No batch has been loaded for {} seconds",
+                elapsed.as_secs()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub type SharedHealthState = Arc<RwLock<HealthState>>;
+
+#[derive(Serialize)]
+struct FailingCheck<'a> {
+    check: &'a str,
+    error: &'a str,
+}
+
+#[derive(Serialize)]
+struct Readiness<'a> {
+    status: &'a str,
+    failing_checks: Vec<FailingCheck<'a>>,
+}
+
+/// 200 if all the checks pass, 503 with the failing ones otherwise
+pub fn readiness_response(checks: &[(&str, Result<(), String>)]) -> Response<Body> {
+    let failing_checks: Vec<_> = checks
+        .iter()
+        .filter_map(|(check, result)| {
+            result
+                .as_ref()
+                .err()
+                .map(|error| FailingCheck { check, error })
+        })
+        .collect();
+
+    let (status_code, status) = if failing_checks.is_empty() {
+        (200, "ok")
+    } else {
+        (503, "unavailable")
+    };
+
+    Response::builder()
+        .status(status_code)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::to_vec(&Readiness {
+                status,
+                failing_checks,
+            })
+            .unwrap(),
+        ))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(response: Response<Body>) -> serde_json::Value {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stale_pipeline_is_not_ready() {
+        let state = HealthState {
+            last_batch_at: Instant::now() - Duration::from_secs(120),
+        };
+
+        let response = readiness_response(&[
+            ("queue_storage", Ok(())),
+            ("last_batch", state.check_staleness(Duration::from_secs(60))),
+        ]);
+
+        assert_eq!(response.status(), 503);
+        assert_eq!(
+            body(response).await,
+            serde_json::json!({
+                "status": "unavailable",
+                "failing_checks": [{
+                    "check": "last_batch",
+                    "error": "No batch has been loaded for 120 seconds",
+                }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn fresh_pipeline_is_ready() {
+        let mut state = HealthState::default();
+        state.batch_loaded();
+
+        let response = readiness_response(&[
+            ("queue_storage", Ok(())),
+            ("last_batch", state.check_staleness(Duration::from_secs(60))),
+        ]);
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            body(response).await,
+            serde_json::json!({ "status": "ok", "failing_checks": [] })
+        );
+    }
+}
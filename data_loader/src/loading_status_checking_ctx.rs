@@ -11,12 +11,24 @@ impl LoadingStatusCheckingCtx {
     pub async fn setup_and_run(register: &Register) -> Result<Self> {
         let loading_status_checker = LoadingStatusCheckerHandle::new(register).await?;
 
-        let duration = register.config.get_reset_status_period();
+        let reset_period = register.config.get_reset_status_period();
+        let count_period = register
+            .config
+            .get_loading_status_check_config()
+            .count_period;
 
+        let reset_checker = loading_status_checker.clone();
         tokio::spawn(async move {
             loop {
-                loading_status_checker.reset_loading_status().await;
-                sleep(Duration::from_secs(duration)).await;
+                reset_checker.reset_loading_status().await;
+                sleep(Duration::from_secs(reset_period)).await;
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                loading_status_checker.count_pending_signatures().await;
+                sleep(Duration::from_secs(count_period)).await;
             }
         });
 
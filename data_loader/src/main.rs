@@ -4,38 +4,47 @@ extern crate diesel;
 extern crate diesel_migrations;
 
 mod actors;
+mod blocks_loading_ctx;
 mod configuration;
+mod contract_keys;
+#[cfg(test)]
+mod end_to_end_tests;
+mod fetch_tx;
+mod health;
 #[macro_use]
 mod loader_version;
 mod loading_status_checking_ctx;
 mod prometheus_ctx;
 mod register;
+mod retention_ctx;
+mod signature_tracing;
 mod signatures_loading_ctx;
 mod solana_client;
 mod storages;
 mod transactions_loading_ctx;
 
-use clap::{crate_name, App, Arg, ArgAction};
+use clap::{crate_name, App, Arg, ArgAction, ArgMatches};
 use configuration::*;
-use env_logger::Env;
 use register::*;
 use signatures_loading_ctx::*;
 use transactions_loading_ctx::*;
 
+use std::sync::Arc;
 use tokio::signal;
 use tokio::signal::unix::{signal, SignalKind};
 
 use anyhow::Result;
-use log::info;
+use log::{info, warn};
 
+use crate::blocks_loading_ctx::BlocksLoadingCtx;
 use crate::loader_version::Version;
 use crate::loading_status_checking_ctx::LoadingStatusCheckingCtx;
 use crate::prometheus_ctx::PrometheusExporter;
+use crate::retention_ctx::RetentionCtx;
+use crate::storages::queue_storage::QueueStorage;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
-
     let version = version!();
 
     let matches = App::new(crate_name!())
@@ -45,30 +54,122 @@ async fn main() -> Result<()> {
                 .short('c')
                 .long("config-file")
                 .takes_value(true)
+                .global(true)
                 .default_value("./Config.toml")
                 .help("The name of the configuration file"),
         )
-        .arg(
-            Arg::with_name("dont-load-signatures")
-                .long("dont-load-signatures")
-                .action(ArgAction::SetTrue)
-                .help("Whether to load signatures"),
+        .subcommand(
+            App::new("run")
+                .about("Load signatures and transactions, the default if no subcommand is given")
+                .arg(
+                    Arg::with_name("dont-load-signatures")
+                        .long("dont-load-signatures")
+                        .action(ArgAction::SetTrue)
+                        .help("Whether to load signatures"),
+                )
+                .arg(
+                    Arg::with_name("no-blocks-metadata")
+                        .long("no-blocks-metadata")
+                        .action(ArgAction::SetTrue)
+                        .help(
+                            "Don't load the blocks of the queued transactions, for the clients \
+                            which don't serve blocks",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("no-retention")
+                        .long("no-retention")
+                        .action(ArgAction::SetTrue)
+                        .help(
+                            "Keep the JSON of parsed transactions regardless of the retention \
+                            config",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("reload-errored")
+                        .long("reload-errored")
+                        .action(ArgAction::SetTrue)
+                        .help(
+                            "Queue the skipped signatures of failed transactions again, unless \
+                            only the successful transactions are loaded",
+                        ),
+                ),
+        )
+        .subcommand(App::new("status").about("Print the loading progress of every program"))
+        .subcommand(
+            App::new("fetch-tx")
+                .about("Load one transaction, store it in the queue and print a JSON summary")
+                .arg(
+                    Arg::with_name("signature")
+                        .required(true)
+                        .help("Signature of the transaction"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Overwrite the transaction if it is already stored"),
+                ),
         )
         .get_matches();
 
-    let register = Register::new(Configuration::new(
-        matches.value_of("config-file").unwrap_or_default(),
-    )?);
+    let config_file = matches.value_of("config-file").unwrap_or_default();
+    let config = match Configuration::new(config_file) {
+        Ok(config) => config,
+        Err(err) => indexer_errors::exit_on_configuration_error(err),
+    };
+    let register = Arc::new(Register::new(config));
+
+    indexer_telemetry::init_logger(
+        "data_loader",
+        register.config.get_logging_config().format,
+        "info",
+    );
+
+    match matches.subcommand() {
+        Some(("status", _)) => print_progress(&register).await,
+        Some(("fetch-tx", fetch_tx_matches)) => {
+            let summary = fetch_tx::fetch_tx(
+                &register,
+                fetch_tx_matches.value_of("signature").unwrap_or_default(),
+                fetch_tx_matches.get_flag("force"),
+            )
+            .await?;
+            println!("{}", serde_json::to_string(&summary)?);
+
+            Ok(())
+        }
+        Some(("run", run_matches)) => run(&register, config_file, run_matches).await,
+        _ => run(&register, config_file, &ArgMatches::default()).await,
+    }
+}
+
+async fn run(register: &Arc<Register>, config_file: &str, matches: &ArgMatches) -> Result<()> {
+    let flag = |name| matches!(matches.try_get_one::<bool>(name), Ok(Some(true)));
 
     info!("Starting data_loader");
 
-    if !matches.get_flag("dont-load-signatures") {
+    if flag("reload-errored") {
+        reload_errored(register).await?;
+    }
+
+    if !flag("dont-load-signatures") {
         info!("Signatures loading enabled");
-        SignaturesLoadingCtx::setup_and_run(&register).await?;
+        SignaturesLoadingCtx::setup_and_run(register.clone(), config_file).await?;
     }
-    TransactionsLoadingCtx::setup_and_run(&register).await?;
-    LoadingStatusCheckingCtx::setup_and_run(&register).await?;
-    PrometheusExporter::setup_and_run(&register).await?;
+    TransactionsLoadingCtx::setup_and_run(register).await?;
+    LoadingStatusCheckingCtx::setup_and_run(register).await?;
+    if flag("no-blocks-metadata") {
+        info!("Loading of the blocks metadata disabled by --no-blocks-metadata");
+    } else {
+        BlocksLoadingCtx::setup_and_run(register).await?;
+    }
+    if flag("no-retention") {
+        info!("Retention of parsed transactions disabled by --no-retention");
+    } else {
+        RetentionCtx::setup_and_run(register).await?;
+    }
+    PrometheusExporter::setup_and_run(register).await?;
 
     wait_termination().await;
 
@@ -76,6 +177,61 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn reload_errored(register: &Register) -> Result<()> {
+    if register
+        .config
+        .get_load_only_successful_transactions_status()
+    {
+        warn!("--reload-errored is ignored while load_only_successful_transactions is set");
+        return Ok(());
+    }
+
+    let queue_storage =
+        QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
+    let reloaded = queue_storage.reload_errored_signatures()?;
+    info!(
+        "{} skipped signatures of failed transactions are queued again",
+        reloaded
+    );
+
+    Ok(())
+}
+
+async fn print_progress(register: &Register) -> Result<()> {
+    let queue_storage =
+        QueueStorage::new(&register.config.get_queue_storage_config().database_urls()).await?;
+
+    println!(
+        "{:<44}  {:>12}  {:>12}  {:>8}  {:<19}",
+        "program", "oldest_slot", "newest_slot", "backfill", "last_batch_at"
+    );
+
+    for key in register.config.get_account_keys() {
+        let progress = queue_storage.get_progress(&key)?;
+        let oldest_slot = progress
+            .as_ref()
+            .and_then(|progress| progress.oldest_loaded_slot);
+        let newest_slot = progress
+            .as_ref()
+            .and_then(|progress| progress.newest_loaded_slot);
+
+        println!(
+            "{:<44}  {:>12}  {:>12}  {:>8}  {:<19}",
+            key,
+            oldest_slot.map_or("-".to_string(), |slot| slot.to_string()),
+            newest_slot.map_or("-".to_string(), |slot| slot.to_string()),
+            progress
+                .as_ref()
+                .map_or(false, |progress| progress.backfill_complete),
+            progress
+                .and_then(|progress| progress.last_batch_at)
+                .unwrap_or_else(|| "-".to_string()),
+        );
+    }
+
+    Ok(())
+}
+
 async fn wait_termination() {
     let mut term = signal(SignalKind::terminate()).unwrap();
     let mut inter = signal(SignalKind::interrupt()).unwrap();
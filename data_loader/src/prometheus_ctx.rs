@@ -1,16 +1,153 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use hyper::{
     header::CONTENT_TYPE,
     service::{make_service_fn, service_fn},
-    Body, Response, Server,
+    Body, Request, Response, Server,
 };
+use lazy_static::lazy_static;
 use log::{error, info};
-use prometheus::{Encoder, TextEncoder};
+use prometheus::{
+    core::Collector, register_gauge_vec_with_registry, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, register_int_counter_with_registry,
+    register_int_gauge_vec_with_registry, register_int_gauge_with_registry, Encoder, GaugeVec,
+    HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Registry, TextEncoder,
+};
+
+use crate::{
+    health::{readiness_response, SharedHealthState},
+    register::Register,
+    storages::queue_storage::QueueStorage,
+};
 
-use crate::register::Register;
+lazy_static! {
+    /// Registry of the metrics of the service
+    pub static ref REGISTRY: Registry = indexer_telemetry::service_registry("data_loader");
+    pub static ref BUILD_INFO: IntGaugeVec =
+        indexer_telemetry::register_build_info("dl", version!(), &REGISTRY).unwrap();
+    pub static ref PURGED_TRANSACTIONS_COUNT: IntCounter = register_int_counter_with_registry!(
+        "dl_purged_transactions_total",
+        "Parsed transactions which JSON has been removed by the retention task",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref BACKFILL_COMPLETE: IntGaugeVec = register_int_gauge_vec_with_registry!(
+        "dl_backfill_complete",
+        "Whether the signatures of the program have been loaded down to its first transaction",
+        &["program"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref PENDING_SIGNATURES: IntGaugeVec = register_int_gauge_vec_with_registry!(
+        "dl_pending_signatures",
+        "Signatures of the program waiting for their transactions to be loaded, the skipped ones \
+         are not pending",
+        &["program"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref PENDING_SIGNATURES_ESTIMATED: IntGaugeVec =
+        register_int_gauge_vec_with_registry!(
+            "dl_pending_signatures_estimated",
+            "Whether the pending signatures of the program are the planner's estimate, they are \
+             above the exact count limit",
+            &["program"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref SKIPPED_SIGNATURES_COUNT: IntCounter = register_int_counter_with_registry!(
+        "dl_skipped_signatures_total",
+        "Signatures of failed transactions skipped while only the successful ones are loaded",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref ERRORS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "dl_errors_total",
+        "Failed requests which are retried per error class",
+        &["class"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref ACTIVE_CONTRACT_KEYS: IntGauge = register_int_gauge_with_registry!(
+        "dl_active_contract_keys",
+        "Programs of the contract keys whose signatures are loaded, updated by the reloads of \
+         the configuration file",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref SIGNATURES_POLLS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "dl_signatures_polls_total",
+        "Requests of the signatures of the program, proportional to its weight while it's idle",
+        &["program"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref SIGNATURES_POLL_INTERVAL: GaugeVec = register_gauge_vec_with_registry!(
+        "dl_signatures_poll_interval_seconds",
+        "Delay of the polls of the program added by its backlog of pending signatures, the max \
+         poll interval while the polls are skipped above the hard cap",
+        &["program"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref QUEUE_SHARD_OPERATIONS_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "dl_queue_shard_operations_total",
+            "Operations of the queue storage run on the shard, a single database is shard 0",
+            &["shard", "operation"],
+            REGISTRY
+        )
+        .unwrap();
+    pub static ref RATE_LIMIT_WAIT_SECONDS: HistogramVec = register_histogram_vec_with_registry!(
+        "dl_rate_limit_wait_seconds",
+        "Time the Solana client requests wait for a permit of the rate limiter",
+        &["request"],
+        vec![0.001, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref SOLANA_CLIENT_FALLBACKS_COUNT: IntCounterVec =
+        register_int_counter_vec_with_registry!(
+            "dl_solana_client_fallbacks_total",
+            "Requests of the BigTableWithRpcFallback client whose data is missing in BigTable and \
+             is requested from the RPC",
+            &["request"],
+            REGISTRY
+        )
+        .unwrap();
+}
+
+/// Every metric of the service, so they are exported from the start instead of their first use
+pub fn metrics() -> Vec<&'static dyn Collector> {
+    vec![
+        &*BUILD_INFO,
+        &*PURGED_TRANSACTIONS_COUNT,
+        &*BACKFILL_COMPLETE,
+        &*PENDING_SIGNATURES,
+        &*PENDING_SIGNATURES_ESTIMATED,
+        &*SKIPPED_SIGNATURES_COUNT,
+        &*ERRORS_COUNT,
+        &*ACTIVE_CONTRACT_KEYS,
+        &*SIGNATURES_POLLS_COUNT,
+        &*SIGNATURES_POLL_INTERVAL,
+        &*QUEUE_SHARD_OPERATIONS_COUNT,
+        &*RATE_LIMIT_WAIT_SECONDS,
+        &*SOLANA_CLIENT_FALLBACKS_COUNT,
+    ]
+}
 
 pub struct PrometheusExporter {}
 
+/// What the readiness probe checks
+#[derive(Clone)]
+struct Readiness {
+    registry: Registry,
+    database_urls: Vec<String>,
+    health: SharedHealthState,
+    staleness: Duration,
+}
+
 impl PrometheusExporter {
     pub async fn setup_and_run(register: &Register) -> Result<()> {
         let addr = register
@@ -18,27 +155,27 @@ impl PrometheusExporter {
             .get_prometheus_exporter_bind_address()
             .parse()
             .unwrap();
+        metrics();
+
+        let readiness = Readiness {
+            registry: register.registry.clone(),
+            database_urls: register.config.get_queue_storage_config().database_urls(),
+            health: register.health.clone(),
+            staleness: Duration::from_secs(register.config.get_readiness_staleness()),
+        };
 
         tokio::spawn(async move {
             info!("Prometheus exporter started on http://{}", &addr);
 
             if let Err(err) = Server::bind(&addr)
-                .serve(make_service_fn(|_| async {
-                    Ok::<_, hyper::Error>(service_fn(|_| async {
-                        let encoder = TextEncoder::new();
-                        let metric_families = prometheus::gather();
-                        let mut buffer = Vec::new();
-
-                        encoder.encode(&metric_families, &mut buffer).unwrap();
-
-                        let response = Response::builder()
-                            .status(200)
-                            .header(CONTENT_TYPE, encoder.format_type())
-                            .body(Body::from(buffer))
-                            .unwrap();
-
-                        Ok::<_, hyper::Error>(response)
-                    }))
+                .serve(make_service_fn(move |_| {
+                    let readiness = readiness.clone();
+
+                    async move {
+                        Ok::<_, hyper::Error>(service_fn(move |request| {
+                            Self::respond(request, readiness.clone())
+                        }))
+                    }
                 }))
                 .await
             {
@@ -48,4 +185,117 @@ impl PrometheusExporter {
 
         Ok(())
     }
+
+    async fn respond(
+        request: Request<Body>,
+        readiness: Readiness,
+    ) -> Result<Response<Body>, hyper::Error> {
+        let response = match request.uri().path() {
+            "/healthz" => readiness_response(&[]),
+            "/readyz" => {
+                let database_urls = readiness.database_urls;
+                let queue_storage =
+                    tokio::task::spawn_blocking(move || QueueStorage::ping(&database_urls))
+                        .await
+                        .map_err(|err| err.to_string())
+                        .and_then(|ping| ping.map_err(|err| err.to_string()));
+                let last_batch = readiness
+                    .health
+                    .read()
+                    .unwrap()
+                    .check_staleness(readiness.staleness);
+
+                readiness_response(&[("queue_storage", queue_storage), ("last_batch", last_batch)])
+            }
+            _ => {
+                let encoder = TextEncoder::new();
+                let metric_families = readiness.registry.gather();
+                let mut buffer = Vec::new();
+
+                encoder.encode(&metric_families, &mut buffer).unwrap();
+
+                Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, encoder.format_type())
+                    .body(Body::from(buffer))
+                    .unwrap()
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::proto::MetricType;
+
+    /// `# HELP` and `# TYPE` lines of the metrics in the /metrics output, where the metrics with
+    /// labels appear after their first samples
+    fn descriptions() -> String {
+        let mut families: Vec<_> = metrics()
+            .iter()
+            .flat_map(|metric| metric.collect())
+            .collect();
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        families
+            .iter()
+            .map(|family| {
+                let metric_type = match family.get_field_type() {
+                    MetricType::COUNTER => "counter",
+                    MetricType::GAUGE => "gauge",
+                    MetricType::HISTOGRAM => "histogram",
+                    MetricType::SUMMARY => "summary",
+                    MetricType::UNTYPED => "untyped",
+                };
+
+                format!(
+                    "# HELP {name} {}\n# TYPE {name} {}\n",
+                    family.get_help(),
+                    metric_type,
+                    name = family.get_name()
+                )
+            })
+            .collect()
+    }
+
+    /// The dashboards and the alerts refer to the metrics by their names, a renamed metric has
+    /// to be renamed in `metrics.golden` as well
+    #[test]
+    fn metrics_are_not_renamed() {
+        assert_eq!(descriptions(), include_str!("metrics.golden"));
+    }
+
+    /// Labels of every sample of the metric as the registry exports them
+    fn sample_labels(name: &str) -> Vec<Vec<(String, String)>> {
+        REGISTRY
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == name)
+            .flat_map(|family| family.get_metric())
+            .map(|metric| {
+                let mut labels: Vec<_> = metric
+                    .get_label()
+                    .iter()
+                    .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+                    .collect();
+                labels.sort();
+                labels
+            })
+            .collect()
+    }
+
+    /// The dashboards select the backfill state of a program by its address
+    #[test]
+    fn backfill_complete_is_labelled_by_program() {
+        let program = "backfill_complete_is_labelled_by_program";
+        BACKFILL_COMPLETE.with_label_values(&[program]).set(1);
+
+        assert!(sample_labels("dl_backfill_complete").contains(&vec![
+            ("program".to_string(), program.to_string()),
+            ("service".to_string(), "data_loader".to_string()),
+        ]));
+    }
 }
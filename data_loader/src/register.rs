@@ -1,12 +1,40 @@
+use std::sync::Arc;
+
+use prometheus::Registry;
+
 use crate::configuration::*;
+use crate::contract_keys::SharedContractKeys;
+use crate::health::SharedHealthState;
+use crate::prometheus_ctx::REGISTRY;
+use crate::signature_tracing::SignatureTracer;
+use crate::solana_client::RateLimiter;
 
 #[derive(Debug)]
 pub struct Register {
     pub config: Configuration,
+    /// `contracts.keys` of the configuration, replaced by its reloads
+    pub contract_keys: SharedContractKeys,
+    pub health: SharedHealthState,
+    pub tracer: SignatureTracer,
+    /// Shared by the Solana clients of all the loaders
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Registry of the metrics exported by the service
+    pub registry: Registry,
 }
 
 impl Register {
     pub fn new(config: Configuration) -> Self {
-        Self { config }
+        Self {
+            tracer: SignatureTracer::new(config.get_tracing_config()),
+            rate_limiter: config
+                .get_solana_client_config()
+                .rate_limit
+                .as_ref()
+                .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit))),
+            contract_keys: SharedContractKeys::new(config.get_weighted_account_keys()),
+            config,
+            health: Default::default(),
+            registry: REGISTRY.clone(),
+        }
     }
 }
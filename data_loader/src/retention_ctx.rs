@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use crate::{
+    prometheus_ctx::PURGED_TRANSACTIONS_COUNT, register::Register,
+    storages::queue_storage::QueueStorage,
+};
+use anyhow::Result;
+use log::{error, info};
+use tokio::time::sleep;
+
+pub struct RetentionCtx {}
+
+impl RetentionCtx {
+    pub async fn setup_and_run(register: &Register) -> Result<Self> {
+        let queue_storage_config = register.config.get_queue_storage_config();
+        let retention = queue_storage_config.retention.clone();
+
+        if !retention.enabled {
+            info!("Retention of parsed transactions is disabled");
+            return Ok(Self {});
+        }
+
+        let queue_storage = QueueStorage::new(&queue_storage_config.database_urls()).await?;
+
+        tokio::spawn(async move {
+            loop {
+                // Every batch is a separate statement, so the rows are not locked for long
+                loop {
+                    match queue_storage
+                        .purge_parsed_transactions(retention.min_age_days, retention.batch_size)
+                    {
+                        Ok(purged) => {
+                            PURGED_TRANSACTIONS_COUNT.inc_by(purged as u64);
+
+                            if (purged as i64) < retention.batch_size {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            error!("Failed to purge parsed transactions: {}", err);
+                            break;
+                        }
+                    }
+                }
+
+                sleep(Duration::from_secs(retention.period)).await;
+            }
+        });
+
+        info!(
+            "Retention of parsed transactions older than {} days spawned",
+            retention.min_age_days
+        );
+
+        Ok(Self {})
+    }
+}
@@ -0,0 +1,77 @@
+use crate::configuration::TracingConfig;
+use log::info;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// Prefix of the trace records, shared with data_analyzer so one grep follows a signature through
+/// both services
+pub const TRACE_PREFIX: &str = "[trace]";
+
+/// Steps of the loading of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The signature is claimed from the queue for loading
+    Claimed,
+    /// The transaction is fetched from the RPC node
+    Fetched,
+    /// The transaction is stored to the queue for data_analyzer
+    StoredToQueue,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TraceEvent::Claimed => "claimed",
+            TraceEvent::Fetched => "fetched",
+            TraceEvent::StoredToQueue => "stored-to-queue",
+        })
+    }
+}
+
+/// Logs the steps of the signatures listed in the `[tracing]` section, the others are not traced
+#[derive(Debug, Clone, Default)]
+pub struct SignatureTracer {
+    signatures: Arc<HashSet<String>>,
+}
+
+impl SignatureTracer {
+    pub fn new(config: &TracingConfig) -> Self {
+        Self {
+            signatures: Arc::new(config.signatures.iter().cloned().collect()),
+        }
+    }
+
+    pub fn is_traced(&self, signature: &str) -> bool {
+        self.signatures.contains(signature)
+    }
+
+    pub fn trace(&self, signature: &str, event: TraceEvent) {
+        if self.is_traced(signature) {
+            info!(
+                tx_signature = signature,
+                trace_event = event.to_string().as_str();
+                "{} {} {}",
+                TRACE_PREFIX,
+                signature,
+                event
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_listed_signatures_are_traced() {
+        let tracer = SignatureTracer::new(&TracingConfig {
+            signatures: vec!["traced".to_string()],
+        });
+
+        assert!(tracer.is_traced("traced"));
+        assert!(!tracer.is_traced("other"));
+        assert!(!SignatureTracer::default().is_traced("traced"));
+    }
+}
@@ -1,139 +1,574 @@
-use std::{str::FromStr, time::Duration};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use anyhow::Result;
-use log::info;
-use solana_sdk::{pubkey::Pubkey, signature::Signature};
-use tokio::time::sleep;
+use async_trait::async_trait;
+use log::{error, info, warn};
+use solana_sdk::pubkey::Pubkey;
+use tokio::{task::JoinHandle, time::sleep};
 
 use crate::{
     actors::{
         saved_state_manager::SavedStateManagerHandle, signatures_rpc_loader::*,
         signatures_saver::SignaturesSaverHandle,
     },
+    configuration::SignaturesLoading,
+    contract_keys::{ContractKeysReloader, ProgramLoaders},
+    prometheus_ctx::{BACKFILL_COMPLETE, SIGNATURES_POLLS_COUNT, SIGNATURES_POLL_INTERVAL},
     register::Register,
+    storages::queue_storage::QueueStorage,
 };
 
+/// Growth and limit of the sleep between the polls of a program without new signatures
+const IDLE_SLEEP_STEP_MS: u64 = 1000;
+const IDLE_SLEEP_MAX_MS: u64 = 5000;
+
 pub struct SignaturesLoadingCtx;
 
 impl SignaturesLoadingCtx {
-    pub async fn setup_and_run(register: &Register) -> Result<Self> {
-        for key in register.config.get_account_keys() {
-            let contract_address = key.clone();
-            let contract_address_for_logging = key.clone();
-            let rpc_loader = SignaturesRpcLoaderHandle::new(
-                register.config.get_solana_client_type(),
-                &register.config.get_endpoint_url(),
-                &key,
-            )
-            .await;
+    /// Spawns the signatures loader of every contract key and, unless `reload_period` of
+    /// `[contracts]` is 0, the periodic reload of the keys from `config_file`
+    pub async fn setup_and_run(register: Arc<Register>, config_file: &str) -> Result<Self> {
+        let weighted_keys = register.contract_keys.weighted_keys();
+        let max_weight = weighted_keys
+            .iter()
+            .map(|(_, weight)| *weight)
+            .max()
+            .unwrap_or(1);
 
-            let signatures_saver = SignaturesSaverHandle::new(register).await?;
+        let mut loaders = SignaturesLoaders {
+            register: register.clone(),
+            max_weight,
+            tasks: LoaderTasks::default(),
+        };
 
-            let saved_state_manager = SavedStateManagerHandle::new(register).await?;
-
-            let mut saved_state = saved_state_manager
-                .load_state(Pubkey::from_str(&key).unwrap())
-                .await;
-
-            info!(
-                "{}: Saved state loaded: {:?}",
-                &contract_address_for_logging, &saved_state
-            );
+        for (key, weight) in weighted_keys {
+            loaders.start(&key, weight).await?;
+        }
 
-            let mut sleep_time = 0;
+        let reload_period = register.config.get_contract_keys_reload_period();
+        if reload_period > 0 {
+            let mut reloader =
+                ContractKeysReloader::new(config_file, register.contract_keys.clone(), loaders);
 
             tokio::spawn(async move {
                 loop {
-                    let signatures = rpc_loader.signatures_rpc_load(saved_state).await;
+                    sleep(Duration::from_secs(reload_period)).await;
 
-                    info!(
-                        "{}: {} signatures loaded",
-                        &contract_address_for_logging,
-                        signatures.len()
-                    );
-
-                    if saved_state.newest_transaction.is_none() && !signatures.is_empty() {
-                        saved_state.newest_transaction = Some(
-                            Signature::from_str(&signatures.get(0).unwrap().signature).unwrap(),
+                    if let Err(err) = reloader.reload().await {
+                        error!(
+                            actor = "signatures_loader";
+                            "Contract keys were not reloaded: {:#}", err
                         );
                     }
+                }
+            });
+        }
 
-                    if signatures.is_empty() {
-                        if sleep_time < 5000 {
-                            sleep_time += 1000;
-                        }
+        Ok(Self {})
+    }
+}
 
-                        sleep(Duration::from_millis(sleep_time)).await;
-                        continue;
-                    } else {
-                        sleep_time = 0;
+/// Pending signatures of a program, counted exactly up to `exact_count_limit`
+pub trait PendingBacklog: Send {
+    fn pending_signatures(&self, program_key: &str, exact_count_limit: i64) -> Result<i64>;
+}
 
-                        let before_idx = signatures.len().saturating_sub(2);
+impl PendingBacklog for QueueStorage {
+    fn pending_signatures(&self, program_key: &str, exact_count_limit: i64) -> Result<i64> {
+        Ok(self
+            .count_pending_signatures(program_key, exact_count_limit)?
+            .count)
+    }
+}
 
-                        info!(
-                            "{}: first in a batch: {}",
-                            &contract_address_for_logging,
-                            &signatures.get(0).unwrap().signature
-                        );
-                        info!(
-                            "{}: new before: {}",
-                            &contract_address_for_logging,
-                            &signatures.get(before_idx).unwrap().signature
-                        );
+/// What the loader of a program does before its next poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backpressure {
+    /// Polls after the delay
+    Poll(Duration),
+    /// Checks the backlog again after the delay without polling
+    Skip(Duration),
+}
 
-                        saved_state.before = Some(
-                            Signature::from_str(&signatures.get(before_idx).unwrap().signature)
-                                .unwrap(),
-                        );
-                    };
+/// Delays the polls of a program by its backlog of pending signatures, see `SignaturesLoading`
+struct BacklogThrottle<B> {
+    backlog: B,
+    config: SignaturesLoading,
+}
+
+impl<B: PendingBacklog> BacklogThrottle<B> {
+    fn new(backlog: B, config: SignaturesLoading) -> Self {
+        Self { backlog, config }
+    }
+
+    /// The backlog is counted exactly up to the threshold, the planner's estimate above it is
+    /// precise enough for the delay. A failed count doesn't delay the poll
+    fn check(&self, program_key: &str) -> Backpressure {
+        let threshold = self.config.backlog_threshold;
+        if threshold <= 0 {
+            return Backpressure::Poll(Duration::ZERO);
+        }
 
-                    let until = saved_state.until.unwrap_or_default().to_string();
+        let backpressure = match self.backlog.pending_signatures(program_key, threshold) {
+            Ok(pending) => self.backpressure(pending),
+            Err(err) => {
+                warn!(
+                    actor = "signatures_loader",
+                    program = program_key;
+                    "{}: Pending signatures not counted: {:#}", program_key, err
+                );
+                Backpressure::Poll(Duration::ZERO)
+            }
+        };
 
-                    if signatures.iter().any(|s| s.signature == until) {
-                        // We have loaded all retrospective transactions signatures.
-                        // Move the the head to the current top and the end of a tail to the prev one.
-                        if saved_state.newest_transaction.is_some() {
-                            saved_state.until = saved_state.newest_transaction;
-                        }
+        let (Backpressure::Poll(delay) | Backpressure::Skip(delay)) = backpressure;
+        SIGNATURES_POLL_INTERVAL
+            .with_label_values(&[program_key])
+            .set(delay.as_secs_f64());
 
+        backpressure
+    }
+
+    fn backpressure(&self, pending: i64) -> Backpressure {
+        let threshold = self.config.backlog_threshold;
+        let hard_cap = self.config.backlog_hard_cap;
+        let max_poll_interval = Duration::from_secs(self.config.max_poll_interval);
+
+        if pending >= hard_cap {
+            Backpressure::Skip(max_poll_interval)
+        } else if pending > threshold {
+            Backpressure::Poll(
+                max_poll_interval
+                    .mul_f64((pending - threshold) as f64 / (hard_cap - threshold) as f64),
+            )
+        } else {
+            Backpressure::Poll(Duration::ZERO)
+        }
+    }
+}
+
+/// Signatures loaders of the programs, each one is a task polling the signatures of its program
+struct SignaturesLoaders {
+    register: Arc<Register>,
+    /// Weight of the heaviest program at the start, the polling of the others is relative to it
+    max_weight: u32,
+    tasks: LoaderTasks,
+}
+
+/// Tasks of the signatures loaders. A stopped loader finishes its current poll, storing its
+/// state, before it checks its flag, so it is kept until the loader of the same key is started
+/// again from that state
+#[derive(Default)]
+struct LoaderTasks {
+    /// Flags the running loaders check before every poll
+    running: HashMap<String, (Arc<AtomicBool>, JoinHandle<()>)>,
+    stopping: HashMap<String, JoinHandle<()>>,
+}
+
+impl LoaderTasks {
+    fn insert(&mut self, key: String, stopped: Arc<AtomicBool>, task: JoinHandle<()>) {
+        self.running.insert(key, (stopped, task));
+    }
+
+    fn stop(&mut self, key: &str) {
+        self.stopping.retain(|_, task| !task.is_finished());
+
+        if let Some((stopped, task)) = self.running.remove(key) {
+            stopped.store(true, Ordering::Relaxed);
+            self.stopping.insert(key.to_string(), task);
+        }
+    }
+
+    /// Waits until the stopped loader of the key exits
+    async fn wait_stopped(&mut self, key: &str) {
+        if let Some(task) = self.stopping.remove(key) {
+            if let Err(err) = task.await {
+                error!(
+                    actor = "signatures_loader",
+                    program = key;
+                    "{}: Stopped signature loader failed: {}", key, err
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProgramLoaders for SignaturesLoaders {
+    async fn start(&mut self, key: &str, weight: u32) -> Result<()> {
+        // A key removed and added back is loaded from the state its previous loader stores
+        self.tasks.wait_stopped(key).await;
+
+        let key = key.to_string();
+        let contract_address = key.clone();
+        let contract_address_for_logging = key.clone();
+        let rpc_loader = SignaturesRpcLoaderHandle::new(
+            self.register.config.get_solana_client_config(),
+            &self.register.config.get_endpoint_url(),
+            &key,
+            self.register.rate_limiter.clone(),
+        )
+        .await;
+
+        let signatures_saver = SignaturesSaverHandle::new(&self.register).await?;
+
+        let queue_storage = QueueStorage::new(
+            &self
+                .register
+                .config
+                .get_queue_storage_config()
+                .database_urls(),
+        )
+        .await?;
+        let throttle = BacklogThrottle::new(
+            queue_storage,
+            self.register.config.get_signatures_loading_config().clone(),
+        );
+
+        let saved_state_manager = SavedStateManagerHandle::new(&self.register).await?;
+
+        let mut saved_state = saved_state_manager
+            .load_state(Pubkey::from_str(&key).unwrap())
+            .await;
+
+        info!(
+            actor = "signatures_loader",
+            program = contract_address_for_logging.as_str();
+            "{}: Saved state loaded: {:?}",
+            &contract_address_for_logging, &saved_state
+        );
+
+        // `until` is set once the first pass reaches the first transaction of the program
+        BACKFILL_COMPLETE
+            .with_label_values(&[&key])
+            .set(saved_state.until.is_some() as i64);
+
+        // The heaviest programs are polled the most often, a program of weight 1 is polled
+        // `max_weight` times rarer
+        let max_weight = self.max_weight.max(weight) as u64;
+        let idle_sleep_step = IDLE_SLEEP_STEP_MS * max_weight / weight as u64;
+        let idle_sleep_max = IDLE_SLEEP_MAX_MS * max_weight / weight as u64;
+        let mut sleep_time = 0;
+        let health = self.register.health.clone();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let loader_stopped = stopped.clone();
+
+        let task = tokio::spawn(async move {
+            loop {
+                if loader_stopped.load(Ordering::Relaxed) {
+                    info!(
+                        actor = "signatures_loader",
+                        program = contract_address_for_logging.as_str();
+                        "{}: Signature loader stopped",
+                        &contract_address_for_logging
+                    );
+                    break;
+                }
+
+                match throttle.check(&key) {
+                    Backpressure::Poll(delay) => {
+                        if !delay.is_zero() {
+                            sleep(delay).await;
+                        }
+                    }
+                    Backpressure::Skip(delay) => {
                         info!(
-                            "{}: until updated: {:?}",
-                            &contract_address_for_logging, saved_state.until
+                            actor = "signatures_loader",
+                            program = contract_address_for_logging.as_str();
+                            "{}: Poll skipped, the pending signatures are above the hard cap",
+                            &contract_address_for_logging
                         );
+                        sleep(delay).await;
+                        continue;
+                    }
+                }
+
+                SIGNATURES_POLLS_COUNT.with_label_values(&[&key]).inc();
+                let signatures = rpc_loader.signatures_rpc_load(saved_state).await;
+                // An empty batch still means the RPC responds, the loading is not stalled
+                health.write().unwrap().batch_loaded();
+
+                info!(
+                    actor = "signatures_loader",
+                    program = contract_address_for_logging.as_str();
+                    "{}: {} signatures loaded",
+                    &contract_address_for_logging,
+                    signatures.len()
+                );
 
-                        saved_state.before = None;
-                        saved_state.newest_transaction = None;
+                if signatures.is_empty() {
+                    if sleep_time < idle_sleep_max {
+                        sleep_time += idle_sleep_step;
                     }
 
-                    let signatures_to_store = signatures.len();
-                    info!(
-                        "{}: {} signatures sent to storage ",
-                        &contract_address_for_logging, &signatures_to_store
-                    );
+                    sleep(Duration::from_millis(sleep_time)).await;
+                    continue;
+                }
+
+                sleep_time = 0;
 
-                    let signatures_stored = signatures_saver
-                        .store_signatures_and_state(
-                            signatures,
-                            Pubkey::from_str(&key).unwrap(),
-                            saved_state,
-                        )
-                        .await;
+                info!(
+                    actor = "signatures_loader",
+                    program = contract_address_for_logging.as_str();
+                    "{}: first in a batch: {}",
+                    &contract_address_for_logging,
+                    &signatures[0].signature
+                );
 
+                let pass_finished = saved_state.advance(&signatures);
+
+                if pass_finished {
+                    BACKFILL_COMPLETE.with_label_values(&[&key]).set(1);
+
+                    // We have loaded all retrospective transactions signatures.
+                    // Move the the head to the current top and the end of a tail to the prev one.
+                    info!(
+                        actor = "signatures_loader",
+                        program = contract_address_for_logging.as_str();
+                        "{}: until updated: {:?}",
+                        &contract_address_for_logging, saved_state.until
+                    );
+                } else {
                     info!(
-                        "{}: {} signatures stored ",
-                        &contract_address_for_logging, &signatures_stored
+                        actor = "signatures_loader",
+                        program = contract_address_for_logging.as_str();
+                        "{}: new before: {:?}",
+                        &contract_address_for_logging, saved_state.before
                     );
+                }
 
-                    if signatures_to_store > 0 && signatures_stored == 0 {
-                        saved_state.before = None;
-                        saved_state.newest_transaction = None;
-                        sleep(Duration::from_millis(5000)).await;
-                    }
+                let signatures_to_store = signatures.len();
+                info!(
+                    actor = "signatures_loader",
+                    program = contract_address_for_logging.as_str();
+                    "{}: {} signatures sent to storage ",
+                    &contract_address_for_logging, &signatures_to_store
+                );
+
+                let signatures_stored = signatures_saver
+                    .store_signatures_and_state(
+                        signatures,
+                        Pubkey::from_str(&key).unwrap(),
+                        saved_state,
+                        pass_finished,
+                    )
+                    .await;
+
+                info!(
+                    actor = "signatures_loader",
+                    program = contract_address_for_logging.as_str();
+                    "{}: {} signatures stored ",
+                    &contract_address_for_logging, &signatures_stored
+                );
+
+                if signatures_to_store > 0 && signatures_stored == 0 {
+                    saved_state.before = None;
+                    saved_state.newest_transaction = None;
+                    sleep(Duration::from_millis(5000)).await;
                 }
-            });
-            info!("{}: Signature loader spawned", contract_address);
+            }
+        });
+        self.tasks.insert(contract_address.clone(), stopped, task);
+        info!(
+            actor = "signatures_loader",
+            program = contract_address.as_str();
+            "{}: Signature loader spawned",
+            contract_address
+        );
+
+        Ok(())
+    }
+
+    fn stop(&mut self, key: &str) {
+        self.tasks.stop(key);
+
+        let _ = BACKFILL_COMPLETE.remove_label_values(&[key]);
+        let _ = SIGNATURES_POLL_INTERVAL.remove_label_values(&[key]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::Mutex;
+
+    /// Reports the pending signatures set by the test and records the exact count limits
+    struct MockBacklog {
+        pending: Arc<Mutex<Result<i64, String>>>,
+        limits: Arc<Mutex<Vec<i64>>>,
+    }
+
+    impl PendingBacklog for MockBacklog {
+        fn pending_signatures(&self, _program_key: &str, exact_count_limit: i64) -> Result<i64> {
+            self.limits.lock().unwrap().push(exact_count_limit);
+
+            self.pending
+                .lock()
+                .unwrap()
+                .clone()
+                .map_err(|err| anyhow!(err))
         }
+    }
 
-        Ok(Self {})
+    fn config(backlog_threshold: i64) -> SignaturesLoading {
+        SignaturesLoading {
+            reset_status_period: 300,
+            backlog_threshold,
+            backlog_hard_cap: 3000,
+            max_poll_interval: 60,
+        }
+    }
+
+    fn throttle(
+        backlog_threshold: i64,
+    ) -> (
+        BacklogThrottle<MockBacklog>,
+        Arc<Mutex<Result<i64, String>>>,
+        Arc<Mutex<Vec<i64>>>,
+    ) {
+        let backlog = MockBacklog {
+            pending: Arc::new(Mutex::new(Ok(0))),
+            limits: Arc::new(Mutex::new(Vec::new())),
+        };
+        let (pending, limits) = (backlog.pending.clone(), backlog.limits.clone());
+
+        (
+            BacklogThrottle::new(backlog, config(backlog_threshold)),
+            pending,
+            limits,
+        )
+    }
+
+    #[test]
+    fn polls_slow_down_with_the_backlog_and_resume_as_it_drains() {
+        let program = "backlog_drains";
+        let (throttle, pending, limits) = throttle(1000);
+        let check = |backlog| {
+            *pending.lock().unwrap() = Ok(backlog);
+            throttle.check(program)
+        };
+
+        assert_eq!(check(0), Backpressure::Poll(Duration::ZERO));
+        assert_eq!(check(1000), Backpressure::Poll(Duration::ZERO));
+        assert_eq!(check(1500), Backpressure::Poll(Duration::from_secs(15)));
+        assert_eq!(check(2000), Backpressure::Poll(Duration::from_secs(30)));
+        assert_eq!(
+            SIGNATURES_POLL_INTERVAL.with_label_values(&[program]).get(),
+            30.0
+        );
+
+        // Drained
+        assert_eq!(check(500), Backpressure::Poll(Duration::ZERO));
+        assert_eq!(
+            SIGNATURES_POLL_INTERVAL.with_label_values(&[program]).get(),
+            0.0
+        );
+        // Counted exactly up to the threshold
+        assert!(limits.lock().unwrap().iter().all(|limit| *limit == 1000));
+    }
+
+    #[test]
+    fn polls_are_skipped_above_the_hard_cap() {
+        let program = "backlog_above_hard_cap";
+        let (throttle, pending, _) = throttle(1000);
+
+        *pending.lock().unwrap() = Ok(3000);
+        assert_eq!(
+            throttle.check(program),
+            Backpressure::Skip(Duration::from_secs(60))
+        );
+        *pending.lock().unwrap() = Ok(5_000_000);
+        assert_eq!(
+            throttle.check(program),
+            Backpressure::Skip(Duration::from_secs(60))
+        );
+        assert_eq!(
+            SIGNATURES_POLL_INTERVAL.with_label_values(&[program]).get(),
+            60.0
+        );
+
+        *pending.lock().unwrap() = Ok(2999);
+        assert!(matches!(throttle.check(program), Backpressure::Poll(_)));
+    }
+
+    #[test]
+    fn backlog_is_not_counted_without_a_threshold() {
+        let (throttle, pending, limits) = throttle(0);
+        *pending.lock().unwrap() = Ok(5_000_000);
+
+        assert_eq!(
+            throttle.check("backlog_ignored"),
+            Backpressure::Poll(Duration::ZERO)
+        );
+        assert!(limits.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_count_does_not_delay_the_poll() {
+        let (throttle, pending, _) = throttle(1000);
+        *pending.lock().unwrap() = Err("Connection refused".to_string());
+
+        assert_eq!(
+            throttle.check("backlog_not_counted"),
+            Backpressure::Poll(Duration::ZERO)
+        );
+    }
+
+    /// Loader of `key` which exits `exit_delay` after it is stopped, as one finishing its poll
+    fn loader(tasks: &mut LoaderTasks, key: &str, exit_delay: Duration) -> Arc<AtomicBool> {
+        let (stopped, exited) = (
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let (loader_stopped, loader_exited) = (stopped.clone(), exited.clone());
+
+        let task = tokio::spawn(async move {
+            while !loader_stopped.load(Ordering::Relaxed) {
+                sleep(Duration::from_millis(1)).await;
+            }
+            sleep(exit_delay).await;
+            loader_exited.store(true, Ordering::Relaxed);
+        });
+        tasks.insert(key.to_string(), stopped, task);
+
+        exited
+    }
+
+    #[tokio::test]
+    async fn restarted_key_waits_for_its_stopped_loader() {
+        let mut tasks = LoaderTasks::default();
+        let exited = loader(&mut tasks, "a", Duration::from_millis(50));
+
+        tasks.stop("a");
+        assert!(!exited.load(Ordering::Relaxed));
+
+        tasks.wait_stopped("a").await;
+        assert!(exited.load(Ordering::Relaxed));
+        assert!(tasks.stopping.is_empty());
+    }
+
+    #[tokio::test]
+    async fn finished_stopped_loaders_are_dropped() {
+        let mut tasks = LoaderTasks::default();
+        loader(&mut tasks, "a", Duration::ZERO);
+        loader(&mut tasks, "b", Duration::from_secs(60));
+
+        tasks.stop("a");
+        while !tasks.stopping["a"].is_finished() {
+            sleep(Duration::from_millis(1)).await;
+        }
+        tasks.stop("b");
+
+        assert_eq!(tasks.stopping.keys().collect::<Vec<_>>(), ["b"]);
+        assert!(tasks.running.is_empty());
     }
 }
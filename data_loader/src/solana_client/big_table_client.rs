@@ -1,14 +1,18 @@
-use std::str::FromStr;
+use std::{ops::Range, str::FromStr};
 
-use crate::solana_client::{SolanaClient, TRANSACTIONS_BATCH_LEN};
+use crate::solana_client::{not_found_error, SolanaClient, TRANSACTIONS_BATCH_LEN};
 use async_trait::async_trait;
 use solana_client::{
     client_error::{ClientError, ClientErrorKind},
+    rpc_request::RpcRequest,
     rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
-use solana_sdk::{pubkey::Pubkey, signature::Signature};
-use solana_storage_bigtable::LedgerStorage;
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_storage_bigtable::{Error as BigTableError, LedgerStorage};
+use solana_transaction_status::{
+    BlockEncodingOptions, EncodedConfirmedTransactionWithStatusMeta, TransactionDetails,
+    UiConfirmedBlock, UiTransactionEncoding,
+};
 
 pub struct SolanaBigTableClient {
     pub(crate) rpc_client: LedgerStorage,
@@ -61,8 +65,65 @@ impl SolanaClient for SolanaBigTableClient {
                 request: None,
                 kind: ClientErrorKind::Custom(String::from("BigTableError")),
             })?
-            .unwrap();
+            // Not uploaded to BigTable yet or unknown, as the RPC reports it
+            .ok_or_else(|| {
+                not_found_error::<EncodedConfirmedTransactionWithStatusMeta>(
+                    RpcRequest::GetTransaction,
+                )
+            })?;
 
         Ok(tx.encode(UiTransactionEncoding::Json, None).unwrap())
     }
+
+    async fn load_confirmed_blocks(
+        &self,
+        slots: Range<Slot>,
+    ) -> Result<Vec<(Slot, UiConfirmedBlock)>, ClientError> {
+        if slots.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let confirmed_slots = self
+            .rpc_client
+            .get_confirmed_blocks(slots.start, (slots.end - slots.start) as usize)
+            .await
+            .map_err(|_| ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom(String::from("BigTableError")),
+            })?;
+
+        let mut blocks = Vec::new();
+        for slot in confirmed_slots
+            .into_iter()
+            .filter(|slot| slots.contains(slot))
+        {
+            let block = match self.rpc_client.get_confirmed_block(slot).await {
+                Ok(block) => block,
+                Err(BigTableError::BlockNotFound(_)) => {
+                    return Err(not_found_error::<UiConfirmedBlock>(RpcRequest::GetBlock))
+                }
+                Err(_) => {
+                    return Err(ClientError {
+                        request: None,
+                        kind: ClientErrorKind::Custom(String::from("BigTableError")),
+                    })
+                }
+            };
+
+            let block = block
+                .encode_with_options(
+                    UiTransactionEncoding::Json,
+                    BlockEncodingOptions {
+                        transaction_details: TransactionDetails::Full,
+                        show_rewards: true,
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?;
+
+            blocks.push((slot, block));
+        }
+
+        Ok(blocks)
+    }
 }
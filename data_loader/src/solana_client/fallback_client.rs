@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use async_trait::async_trait;
+use solana_client::{
+    client_error::ClientError, rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
+
+use crate::{
+    prometheus_ctx::SOLANA_CLIENT_FALLBACKS_COUNT,
+    solana_client::{is_not_found, SolanaClient},
+};
+
+/// Client of the `BigTableWithRpcFallback` client type. The requests are sent to the primary
+/// client and only those whose data it doesn't have are sent to the fallback one, the other
+/// errors of the primary client are returned as they are
+pub struct FallbackClient {
+    primary: Box<dyn SolanaClient>,
+    fallback: Box<dyn SolanaClient>,
+}
+
+impl FallbackClient {
+    pub fn new(primary: Box<dyn SolanaClient>, fallback: Box<dyn SolanaClient>) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+#[async_trait]
+impl SolanaClient for FallbackClient {
+    async fn load_signatures_batch(
+        &self,
+        account_key: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+        match self
+            .primary
+            .load_signatures_batch(account_key, before, until)
+            .await
+        {
+            Err(err) if is_not_found(&err) => {
+                SOLANA_CLIENT_FALLBACKS_COUNT
+                    .with_label_values(&["signatures"])
+                    .inc();
+
+                self.fallback
+                    .load_signatures_batch(account_key, before, until)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn load_transaction_info(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+        match self.primary.load_transaction_info(signature).await {
+            Err(err) if is_not_found(&err) => {
+                SOLANA_CLIENT_FALLBACKS_COUNT
+                    .with_label_values(&["transactions"])
+                    .inc();
+
+                self.fallback.load_transaction_info(signature).await
+            }
+            result => result,
+        }
+    }
+
+    async fn load_confirmed_blocks(
+        &self,
+        slots: Range<Slot>,
+    ) -> Result<Vec<(Slot, UiConfirmedBlock)>, ClientError> {
+        match self.primary.load_confirmed_blocks(slots.clone()).await {
+            Err(err) if is_not_found(&err) => {
+                SOLANA_CLIENT_FALLBACKS_COUNT
+                    .with_label_values(&["blocks"])
+                    .inc();
+
+                self.fallback.load_confirmed_blocks(slots).await
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::not_found_error;
+    use solana_client::{client_error::ClientErrorKind, rpc_request::RpcRequest};
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta};
+    use std::sync::{Arc, Mutex};
+
+    /// Answers the transactions of `known` at `slot` and fails the others with `missing`
+    struct MockClient {
+        name: &'static str,
+        slot: Slot,
+        known: Vec<String>,
+        missing: fn() -> ClientError,
+        requests: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl SolanaClient for MockClient {
+        async fn load_signatures_batch(
+            &self,
+            _account_key: &Pubkey,
+            _before: Option<Signature>,
+            _until: Option<Signature>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+            self.requests.lock().unwrap().push(self.name);
+
+            Ok(Vec::new())
+        }
+
+        async fn load_transaction_info(
+            &self,
+            signature: &str,
+        ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+            self.requests.lock().unwrap().push(self.name);
+
+            if !self.known.iter().any(|known| known == signature) {
+                return Err((self.missing)());
+            }
+
+            Ok(EncodedConfirmedTransactionWithStatusMeta {
+                slot: self.slot,
+                transaction: EncodedTransactionWithStatusMeta {
+                    transaction: EncodedTransaction::LegacyBinary(String::new()),
+                    meta: None,
+                    version: None,
+                },
+                block_time: None,
+            })
+        }
+    }
+
+    fn not_found() -> ClientError {
+        not_found_error::<EncodedConfirmedTransactionWithStatusMeta>(RpcRequest::GetTransaction)
+    }
+
+    fn big_table_error() -> ClientError {
+        ClientError::from(ClientErrorKind::Custom(String::from("BigTableError")))
+    }
+
+    /// BigTable with the `old` transaction and the RPC with both of them
+    fn fallback_client(
+        missing: fn() -> ClientError,
+    ) -> (FallbackClient, Arc<Mutex<Vec<&'static str>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let client = FallbackClient::new(
+            Box::new(MockClient {
+                name: "big_table",
+                slot: 1,
+                known: vec![String::from("old")],
+                missing,
+                requests: requests.clone(),
+            }),
+            Box::new(MockClient {
+                name: "rpc",
+                slot: 2,
+                known: vec![String::from("old"), String::from("recent")],
+                missing: not_found,
+                requests: requests.clone(),
+            }),
+        );
+
+        (client, requests)
+    }
+
+    #[tokio::test]
+    async fn big_table_is_requested_first() {
+        let (client, requests) = fallback_client(not_found);
+
+        let tx = client.load_transaction_info("old").await.unwrap();
+
+        assert_eq!(tx.slot, 1);
+        assert_eq!(*requests.lock().unwrap(), ["big_table"]);
+    }
+
+    #[tokio::test]
+    async fn missing_transaction_is_requested_from_the_rpc() {
+        let (client, requests) = fallback_client(not_found);
+        let fallbacks = SOLANA_CLIENT_FALLBACKS_COUNT.with_label_values(&["transactions"]);
+        let fallbacks_before = fallbacks.get();
+
+        let tx = client.load_transaction_info("recent").await.unwrap();
+
+        assert_eq!(tx.slot, 2);
+        assert_eq!(*requests.lock().unwrap(), ["big_table", "rpc"]);
+        assert!(fallbacks.get() > fallbacks_before);
+
+        // Missing in both of them
+        let err = client.load_transaction_info("unknown").await.unwrap_err();
+        assert!(is_not_found(&err));
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_requested_from_the_rpc() {
+        let (client, requests) = fallback_client(big_table_error);
+
+        let err = client.load_transaction_info("recent").await.unwrap_err();
+
+        assert!(!is_not_found(&err));
+        assert_eq!(err.to_string(), big_table_error().to_string());
+        assert_eq!(*requests.lock().unwrap(), ["big_table"]);
+    }
+}
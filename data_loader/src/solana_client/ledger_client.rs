@@ -0,0 +1,336 @@
+use std::{path::Path, str::FromStr, sync::Arc};
+
+use crate::solana_client::{not_found_error, SolanaClient, TRANSACTIONS_BATCH_LEN};
+use async_trait::async_trait;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::RpcRequest,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_ledger::{
+    blockstore::Blockstore,
+    blockstore_db::BlockstoreError,
+    blockstore_options::{AccessType, BlockstoreOptions},
+};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    ConfirmedTransactionStatusWithSignature, ConfirmedTransactionWithStatusMeta,
+    EncodedConfirmedTransactionWithStatusMeta, TransactionConfirmationStatus,
+    UiTransactionEncoding,
+};
+
+/// Rooted data of a ledger the `LedgerDir` client is served from, the `Blockstore` of the ledger
+/// directory in the service
+pub trait LedgerSource: Send + Sync + 'static {
+    fn max_root(&self) -> Slot;
+
+    fn rooted_transaction(
+        &self,
+        signature: Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>, BlockstoreError>;
+
+    /// Signatures of the transactions of the address up to `highest_slot`, newest first
+    fn signatures_for_address(
+        &self,
+        address: Pubkey,
+        highest_slot: Slot,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> Result<Vec<ConfirmedTransactionStatusWithSignature>, BlockstoreError>;
+}
+
+impl LedgerSource for Blockstore {
+    fn max_root(&self) -> Slot {
+        Blockstore::max_root(self)
+    }
+
+    fn rooted_transaction(
+        &self,
+        signature: Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>, BlockstoreError> {
+        self.get_rooted_transaction(signature)
+    }
+
+    fn signatures_for_address(
+        &self,
+        address: Pubkey,
+        highest_slot: Slot,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> Result<Vec<ConfirmedTransactionStatusWithSignature>, BlockstoreError> {
+        self.get_confirmed_signatures_for_address2(address, highest_slot, before, until, limit)
+            .map(|signatures| signatures.infos)
+    }
+}
+
+/// Serves the rooted transactions of a local ledger directory (a validator's rocksdb or a
+/// snapshot of it), so a history is loaded without the RPC. The responses are built the way the
+/// RPC builds them
+pub struct SolanaLedgerClient<L: LedgerSource = Blockstore> {
+    ledger: Arc<L>,
+}
+
+impl SolanaLedgerClient {
+    /// Opens the ledger as a secondary, so a validator may keep writing to it
+    pub fn open(ledger_path: &str) -> Self {
+        let blockstore = Blockstore::open_with_options(
+            Path::new(ledger_path),
+            BlockstoreOptions {
+                access_type: AccessType::Secondary,
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap_or_else(|err| panic!("Failed to open the ledger {}: {}", ledger_path, err));
+
+        Self::new(blockstore)
+    }
+}
+
+impl<L: LedgerSource> SolanaLedgerClient<L> {
+    pub fn new(ledger: L) -> Self {
+        Self {
+            ledger: Arc::new(ledger),
+        }
+    }
+
+    /// Runs a blocking read of the rocksdb off the runtime threads
+    async fn read<T, F>(&self, read: F) -> Result<T, ClientError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&L) -> Result<T, BlockstoreError> + Send + 'static,
+    {
+        let ledger = Arc::clone(&self.ledger);
+
+        tokio::task::spawn_blocking(move || read(&ledger))
+            .await
+            .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))?
+            .map_err(|err| {
+                ClientError::from(ClientErrorKind::Custom(format!("BlockstoreError: {}", err)))
+            })
+    }
+}
+
+#[async_trait]
+impl<L: LedgerSource> SolanaClient for SolanaLedgerClient<L> {
+    async fn load_signatures_batch(
+        &self,
+        account_key: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+        let account_key = *account_key;
+
+        let (highest_slot, signatures) = self
+            .read(move |ledger| {
+                let highest_slot = ledger.max_root();
+                let signatures = ledger.signatures_for_address(
+                    account_key,
+                    highest_slot,
+                    before,
+                    until,
+                    TRANSACTIONS_BATCH_LEN,
+                )?;
+
+                Ok((highest_slot, signatures))
+            })
+            .await?;
+
+        Ok(signatures
+            .into_iter()
+            .map(|signature| to_rpc_signature(signature, highest_slot))
+            .collect())
+    }
+
+    async fn load_transaction_info(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+        let signature = Signature::from_str(signature).unwrap();
+
+        let tx = self
+            .read(move |ledger| ledger.rooted_transaction(signature))
+            .await?;
+
+        encode_transaction(tx)
+    }
+}
+
+/// Signatures up to the highest root are finalized, as the RPC reports them
+fn to_rpc_signature(
+    signature: ConfirmedTransactionStatusWithSignature,
+    highest_slot: Slot,
+) -> RpcConfirmedTransactionStatusWithSignature {
+    let mut signature = RpcConfirmedTransactionStatusWithSignature::from(signature);
+    if signature.slot <= highest_slot {
+        signature.confirmation_status = Some(TransactionConfirmationStatus::Finalized);
+    }
+
+    signature
+}
+
+/// Encodes the transaction as the RPC `getTransaction` does with the JSON encoding and version 0
+/// supported. For a missing transaction the RPC answers null and its client fails to deserialize
+/// it, the same error is returned here
+fn encode_transaction(
+    tx: Option<ConfirmedTransactionWithStatusMeta>,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+    let Some(tx) = tx else {
+        return Err(
+            not_found_error::<EncodedConfirmedTransactionWithStatusMeta>(
+                RpcRequest::GetTransaction,
+            ),
+        );
+    };
+
+    tx.encode(UiTransactionEncoding::Json, Some(0))
+        .map_err(|err| ClientError::from(ClientErrorKind::Custom(err.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        message::Message,
+        transaction::{Transaction, TransactionVersion, VersionedTransaction},
+    };
+    use solana_transaction_status::{
+        EncodedTransaction, TransactionStatusMeta, TransactionWithStatusMeta,
+        VersionedTransactionWithStatusMeta,
+    };
+    use std::collections::HashMap;
+
+    const MAX_ROOT: Slot = 200;
+
+    /// Ledger of the given transactions, each one of them is a transaction of `address`
+    struct FakeLedger {
+        address: Pubkey,
+        transactions: HashMap<Signature, ConfirmedTransactionWithStatusMeta>,
+    }
+
+    impl LedgerSource for FakeLedger {
+        fn max_root(&self) -> Slot {
+            MAX_ROOT
+        }
+
+        fn rooted_transaction(
+            &self,
+            signature: Signature,
+        ) -> Result<Option<ConfirmedTransactionWithStatusMeta>, BlockstoreError> {
+            Ok(self.transactions.get(&signature).cloned())
+        }
+
+        fn signatures_for_address(
+            &self,
+            address: Pubkey,
+            highest_slot: Slot,
+            _before: Option<Signature>,
+            _until: Option<Signature>,
+            limit: usize,
+        ) -> Result<Vec<ConfirmedTransactionStatusWithSignature>, BlockstoreError> {
+            assert_eq!(highest_slot, MAX_ROOT);
+            assert_eq!(limit, TRANSACTIONS_BATCH_LEN);
+
+            if address != self.address {
+                return Ok(vec![]);
+            }
+
+            Ok(self
+                .transactions
+                .iter()
+                .map(|(signature, tx)| ConfirmedTransactionStatusWithSignature {
+                    signature: *signature,
+                    slot: tx.slot,
+                    err: None,
+                    memo: None,
+                    block_time: tx.block_time,
+                })
+                .collect())
+        }
+    }
+
+    fn transaction(payer: &Pubkey, slot: Slot) -> (Signature, ConfirmedTransactionWithStatusMeta) {
+        let signature = Signature::new_unique();
+        let mut transaction = Transaction::new_unsigned(Message::new(&[], Some(payer)));
+        transaction.signatures = vec![signature];
+
+        let tx = ConfirmedTransactionWithStatusMeta {
+            slot,
+            tx_with_meta: TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+                transaction: VersionedTransaction::from(transaction),
+                meta: TransactionStatusMeta::default(),
+            }),
+            block_time: Some(1700000000),
+        };
+
+        (signature, tx)
+    }
+
+    fn ledger_client(address: Pubkey) -> (SolanaLedgerClient<FakeLedger>, Signature) {
+        let (signature, tx) = transaction(&address, 100);
+
+        let client = SolanaLedgerClient::new(FakeLedger {
+            address,
+            transactions: HashMap::from([(signature, tx)]),
+        });
+
+        (client, signature)
+    }
+
+    #[tokio::test]
+    async fn transaction_is_encoded_as_by_the_rpc() {
+        let (client, signature) = ledger_client(Pubkey::new_unique());
+
+        let tx = client
+            .load_transaction_info(&signature.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(tx.slot, 100);
+        assert_eq!(tx.block_time, Some(1700000000));
+        assert_eq!(tx.transaction.version, Some(TransactionVersion::LEGACY));
+        assert!(tx.transaction.meta.is_some());
+        let EncodedTransaction::Json(ui_transaction) = tx.transaction.transaction else {
+            panic!("The transaction isn't JSON encoded");
+        };
+        assert_eq!(ui_transaction.signatures, vec![signature.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn missing_transaction_fails_as_in_the_rpc() {
+        let (client, _) = ledger_client(Pubkey::new_unique());
+
+        let err = client
+            .load_transaction_info(&Signature::new_unique().to_string())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.kind(), ClientErrorKind::SerdeJson(_)));
+        assert!(matches!(err.request(), Some(RpcRequest::GetTransaction)));
+    }
+
+    #[tokio::test]
+    async fn signatures_are_finalized() {
+        let address = Pubkey::new_unique();
+        let (client, signature) = ledger_client(address);
+
+        let signatures = client
+            .load_signatures_batch(&address, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].signature, signature.to_string());
+        assert_eq!(signatures[0].slot, 100);
+        assert_eq!(
+            signatures[0].confirmation_status,
+            Some(TransactionConfirmationStatus::Finalized)
+        );
+        assert!(client
+            .load_signatures_batch(&Pubkey::new_unique(), None, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
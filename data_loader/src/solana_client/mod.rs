@@ -1,18 +1,32 @@
 mod big_table_client;
+mod fallback_client;
+mod ledger_client;
+mod rate_limiter;
+mod replay_client;
 mod rpc_client;
 
 pub use big_table_client::*;
+pub use fallback_client::*;
+pub use ledger_client::*;
+pub use rate_limiter::*;
+pub use replay_client::*;
 pub use rpc_client::*;
 
+use crate::configuration::SolanaClientConfig;
+
+use std::{ops::Range, sync::Arc};
+
 use async_trait::async_trait;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use solana_client::{
-    client_error::ClientError, nonblocking::rpc_client::RpcClient,
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcRequest,
     rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
-use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
 use solana_storage_bigtable::LedgerStorage;
-use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
 
 // Attention! TRANSACTIONS_BATCH_LEN should not be less than 2
 pub const TRANSACTIONS_BATCH_LEN: usize = 500;
@@ -21,6 +35,12 @@ pub const TRANSACTIONS_BATCH_LEN: usize = 500;
 pub enum ClientType {
     Rpc,
     BigTable,
+    /// Records or replays the responses of another client, see `[solana_client.replay]`
+    Replay,
+    /// Reads the rooted transactions of a local ledger directory, see `ledger_path`
+    LedgerDir,
+    /// Reads from BigTable and the data BigTable doesn't have yet from the RPC of `fallback_url`
+    BigTableWithRpcFallback,
 }
 
 #[async_trait]
@@ -36,10 +56,87 @@ pub trait SolanaClient: Sync + Send {
         &self,
         signature: &str,
     ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError>;
+
+    /// Confirmed blocks of the slots of the range along with their slots, the skipped slots have
+    /// no blocks
+    async fn load_confirmed_blocks(
+        &self,
+        _slots: Range<Slot>,
+    ) -> Result<Vec<(Slot, UiConfirmedBlock)>, ClientError> {
+        Err(ClientError::from(ClientErrorKind::Custom(String::from(
+            "Confirmed blocks are not loaded by the client",
+        ))))
+    }
+}
+
+/// The error of a request whose data the RPC doesn't have: it answers null and its client fails
+/// to deserialize it
+pub fn not_found_error<T: DeserializeOwned>(request: RpcRequest) -> ClientError {
+    let err = serde_json::from_value::<T>(serde_json::Value::Null)
+        .err()
+        .expect("null is deserialized");
+
+    ClientError::new_with_request(ClientErrorKind::SerdeJson(err), request)
 }
 
-pub async fn new_with_url(client_type: &ClientType, url: &str) -> Box<dyn SolanaClient> {
-    match client_type {
+/// Whether the data of the request is missing, as opposed to a failure of the request
+pub fn is_not_found(err: &ClientError) -> bool {
+    matches!(err.kind(), ClientErrorKind::SerdeJson(_))
+        && matches!(
+            err.request(),
+            Some(RpcRequest::GetTransaction | RpcRequest::GetBlock)
+        )
+}
+
+/// The requests of the network clients wait for the permits of `rate_limiter`, the one shared
+/// by all the clients of the service
+pub async fn new_with_url(
+    config: &SolanaClientConfig,
+    url: &str,
+    rate_limiter: Option<Arc<RateLimiter>>,
+) -> Box<dyn SolanaClient> {
+    match config.client_type {
+        ClientType::Replay => {
+            let replay = config
+                .replay
+                .as_ref()
+                .expect("The Replay client requires the [solana_client.replay] section");
+
+            match replay.mode {
+                ReplayMode::Record => Box::new(SolanaReplayClient::record(
+                    &replay.directory,
+                    new_network_client(&replay.client_type, url, rate_limiter).await,
+                )),
+                ReplayMode::Replay => Box::new(SolanaReplayClient::replay(&replay.directory)),
+            }
+        }
+        ClientType::LedgerDir => Box::new(SolanaLedgerClient::open(
+            config
+                .ledger_path
+                .as_ref()
+                .expect("The LedgerDir client requires solana_client.ledger_path"),
+        )),
+        ClientType::BigTableWithRpcFallback => {
+            let fallback_url = config
+                .fallback_url
+                .as_ref()
+                .expect("The BigTableWithRpcFallback client requires solana_client.fallback_url");
+
+            Box::new(FallbackClient::new(
+                new_network_client(&ClientType::BigTable, url, rate_limiter.clone()).await,
+                new_network_client(&ClientType::Rpc, fallback_url, rate_limiter).await,
+            ))
+        }
+        ref client_type => new_network_client(client_type, url, rate_limiter).await,
+    }
+}
+
+async fn new_network_client(
+    client_type: &ClientType,
+    url: &str,
+    rate_limiter: Option<Arc<RateLimiter>>,
+) -> Box<dyn SolanaClient> {
+    let client: Box<dyn SolanaClient> = match client_type {
         ClientType::Rpc => Box::new(SolanaRpcClient {
             rpc_client: RpcClient::new(url.to_string()),
         }),
@@ -48,5 +145,15 @@ pub async fn new_with_url(client_type: &ClientType, url: &str) -> Box<dyn Solana
                 .await
                 .unwrap(),
         }),
+        ClientType::Replay => panic!("The Replay client can't record another Replay client"),
+        ClientType::LedgerDir => panic!("The Replay client can't record the LedgerDir client"),
+        ClientType::BigTableWithRpcFallback => {
+            panic!("The Replay client can't record the BigTableWithRpcFallback client")
+        }
+    };
+
+    match rate_limiter {
+        Some(rate_limiter) => Box::new(RateLimitedClient::new(client, rate_limiter)),
+        None => client,
     }
 }
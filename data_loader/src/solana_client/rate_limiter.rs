@@ -0,0 +1,272 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use solana_client::{
+    client_error::ClientError, rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiConfirmedBlock};
+
+use crate::{
+    configuration::RateLimitConfig, prometheus_ctx::RATE_LIMIT_WAIT_SECONDS,
+    solana_client::SolanaClient,
+};
+
+/// Requests sharing the rate limit, each class gets a reserved share of it, so the bulk
+/// transaction fetches don't starve the signature polling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestClass {
+    Signatures,
+    Transactions,
+}
+
+impl RequestClass {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RequestClass::Signatures => "signatures",
+            RequestClass::Transactions => "transactions",
+        }
+    }
+
+    fn idx(&self) -> usize {
+        match self {
+            RequestClass::Signatures => 0,
+            RequestClass::Transactions => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    /// Tokens added per second
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket of a part of the rate and the burst, which holds at least one token unless
+    /// it gets no rate
+    fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        let capacity = if rate > 0.0 { burst.max(1.0) } else { 0.0 };
+
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    /// Takes a token or returns the time until there is one
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        if self.rate <= 0.0 {
+            return Err(Duration::MAX);
+        }
+
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    /// Reserved buckets of the request classes, by `RequestClass::idx`
+    reserved: [TokenBucket; 2],
+    /// The rest of the rate, the requests of any class take from it
+    shared: TokenBucket,
+}
+
+/// Token bucket limiting the requests of all the loaders of the service. A request takes a token
+/// of the reserved bucket of its class first and of the shared bucket when the reserved one is
+/// empty, so every class gets at least its reserved share of the rate
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self::new_at(config, Instant::now())
+    }
+
+    fn new_at(config: &RateLimitConfig, now: Instant) -> Self {
+        let rps = config.rps;
+        let burst = f64::from(config.burst);
+        let share = config.reserved_share;
+        let shared_share = 1.0 - 2.0 * share;
+
+        let reserved = || TokenBucket::new(rps * share, burst * share, now);
+
+        Self {
+            buckets: Mutex::new(Buckets {
+                reserved: [reserved(), reserved()],
+                shared: TokenBucket::new(rps * shared_share, burst * shared_share, now),
+            }),
+        }
+    }
+
+    /// Takes a token or returns the time after which another attempt may succeed
+    fn try_acquire(&self, class: RequestClass, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let reserved_wait = match buckets.reserved[class.idx()].try_take(now) {
+            Ok(()) => return Ok(()),
+            Err(wait) => wait,
+        };
+
+        buckets
+            .shared
+            .try_take(now)
+            .map_err(|shared_wait| reserved_wait.min(shared_wait))
+    }
+
+    /// Waits for a permit of the request, the time spent waiting is observed by
+    /// `data_loader_rate_limit_wait_seconds`
+    pub async fn acquire(&self, class: RequestClass) {
+        let started = Instant::now();
+
+        while let Err(wait) = self.try_acquire(class, Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+
+        RATE_LIMIT_WAIT_SECONDS
+            .with_label_values(&[class.as_str()])
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+/// Client whose requests wait for the permits of the rate limiter shared by all the clients of
+/// the service
+pub struct RateLimitedClient {
+    client: Box<dyn SolanaClient>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedClient {
+    pub fn new(client: Box<dyn SolanaClient>, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            client,
+            rate_limiter,
+        }
+    }
+}
+
+#[async_trait]
+impl SolanaClient for RateLimitedClient {
+    async fn load_signatures_batch(
+        &self,
+        account_key: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+        self.rate_limiter.acquire(RequestClass::Signatures).await;
+
+        self.client
+            .load_signatures_batch(account_key, before, until)
+            .await
+    }
+
+    async fn load_transaction_info(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+        self.rate_limiter.acquire(RequestClass::Transactions).await;
+
+        self.client.load_transaction_info(signature).await
+    }
+
+    async fn load_confirmed_blocks(
+        &self,
+        slots: Range<Slot>,
+    ) -> Result<Vec<(Slot, UiConfirmedBlock)>, ClientError> {
+        self.rate_limiter.acquire(RequestClass::Transactions).await;
+
+        self.client.load_confirmed_blocks(slots).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate_limiter(now: Instant) -> RateLimiter {
+        RateLimiter::new_at(
+            &RateLimitConfig {
+                rps: 100.0,
+                burst: 100,
+                reserved_share: 0.1,
+            },
+            now,
+        )
+    }
+
+    /// Permits the class gets at `now`, until the buckets are empty
+    fn drain(rate_limiter: &RateLimiter, class: RequestClass, now: Instant) -> usize {
+        let mut permits = 0;
+        while rate_limiter.try_acquire(class, now).is_ok() {
+            permits += 1;
+        }
+
+        permits
+    }
+
+    #[test]
+    fn burst_is_shared_by_the_classes() {
+        let now = Instant::now();
+        let rate_limiter = rate_limiter(now);
+
+        // The reserved 10 tokens and the shared 80 ones
+        assert_eq!(drain(&rate_limiter, RequestClass::Transactions, now), 90);
+        // Only the reserved tokens are left for the signatures
+        assert_eq!(drain(&rate_limiter, RequestClass::Signatures, now), 10);
+    }
+
+    #[test]
+    fn signatures_are_not_starved() {
+        let now = Instant::now();
+        let rate_limiter = rate_limiter(now);
+        drain(&rate_limiter, RequestClass::Transactions, now);
+        drain(&rate_limiter, RequestClass::Signatures, now);
+
+        // The transactions take all the shared tokens first, the signatures still get their
+        // 10 rps
+        let mut signatures = 0;
+        for step in 1..=10 {
+            let now = now + Duration::from_millis(step * 100);
+            drain(&rate_limiter, RequestClass::Transactions, now);
+            signatures += drain(&rate_limiter, RequestClass::Signatures, now);
+        }
+
+        assert_eq!(signatures, 10);
+    }
+
+    #[test]
+    fn wait_until_the_next_token() {
+        let now = Instant::now();
+        let rate_limiter = rate_limiter(now);
+        drain(&rate_limiter, RequestClass::Signatures, now);
+
+        // A reserved token is added every 100ms, a shared one every 12.5ms
+        let wait = rate_limiter
+            .try_acquire(RequestClass::Signatures, now)
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_secs_f64(1.0 / 80.0));
+        assert!(rate_limiter
+            .try_acquire(RequestClass::Signatures, now + wait)
+            .is_ok());
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+
+use crate::solana_client::SolanaClient;
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayMode {
+    /// Responses of the wrapped client are written to the directory
+    Record,
+    /// Responses are served from the directory only, the network is not used
+    Replay,
+}
+
+/// Records the responses of another client as JSON files named after the request parameters,
+/// or serves them back, so a loader issue can be reproduced without the RPC
+pub struct SolanaReplayClient {
+    directory: PathBuf,
+    /// The wrapped client in the record mode, None in the replay mode
+    recorded_client: Option<Box<dyn SolanaClient>>,
+}
+
+impl SolanaReplayClient {
+    pub fn record(directory: &str, recorded_client: Box<dyn SolanaClient>) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+            recorded_client: Some(recorded_client),
+        }
+    }
+
+    pub fn replay(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+            recorded_client: None,
+        }
+    }
+
+    fn signatures_batch_path(
+        &self,
+        account_key: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> PathBuf {
+        let signature_or_none =
+            |signature: Option<Signature>| signature.map_or("none".to_string(), |s| s.to_string());
+
+        self.directory.join("signatures").join(format!(
+            "{}-{}-{}.json",
+            account_key,
+            signature_or_none(before),
+            signature_or_none(until)
+        ))
+    }
+
+    fn transaction_path(&self, signature: &str) -> PathBuf {
+        self.directory
+            .join("transactions")
+            .join(format!("{}.json", signature))
+    }
+}
+
+async fn write_response<T: Serialize>(path: &Path, response: &T) -> Result<(), ClientError> {
+    if let Some(directory) = path.parent() {
+        tokio::fs::create_dir_all(directory).await?;
+    }
+
+    tokio::fs::write(path, serde_json::to_vec(response)?).await?;
+
+    Ok(())
+}
+
+async fn read_response<T: DeserializeOwned>(path: &Path) -> Result<T, ClientError> {
+    let response = tokio::fs::read(path).await.map_err(|err| ClientError {
+        request: None,
+        kind: ClientErrorKind::Custom(format!("No recorded response {}: {}", path.display(), err)),
+    })?;
+
+    Ok(serde_json::from_slice(&response)?)
+}
+
+#[async_trait]
+impl SolanaClient for SolanaReplayClient {
+    async fn load_signatures_batch(
+        &self,
+        account_key: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+        let path = self.signatures_batch_path(account_key, before, until);
+
+        match &self.recorded_client {
+            Some(recorded_client) => {
+                let signatures = recorded_client
+                    .load_signatures_batch(account_key, before, until)
+                    .await?;
+                write_response(&path, &signatures).await?;
+
+                Ok(signatures)
+            }
+            None => read_response(&path).await,
+        }
+    }
+
+    async fn load_transaction_info(
+        &self,
+        signature: &str,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+        let path = self.transaction_path(signature);
+
+        match &self.recorded_client {
+            Some(recorded_client) => {
+                let transaction = recorded_client.load_transaction_info(signature).await?;
+                write_response(&path, &transaction).await?;
+
+                Ok(transaction)
+            }
+            None => read_response(&path).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Serves one signature per batch and an empty transaction for any signature
+    struct FakeClient {
+        requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl SolanaClient for FakeClient {
+        async fn load_signatures_batch(
+            &self,
+            _account_key: &Pubkey,
+            before: Option<Signature>,
+            _until: Option<Signature>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, ClientError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+
+            Ok(vec![RpcConfirmedTransactionStatusWithSignature {
+                signature: Signature::new_unique().to_string(),
+                slot: if before.is_some() { 1 } else { 2 },
+                err: None,
+                memo: None,
+                block_time: None,
+                confirmation_status: None,
+            }])
+        }
+
+        async fn load_transaction_info(
+            &self,
+            _signature: &str,
+        ) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+
+            Ok(EncodedConfirmedTransactionWithStatusMeta {
+                slot: 2,
+                transaction: EncodedTransactionWithStatusMeta {
+                    transaction: EncodedTransaction::LegacyBinary(String::new()),
+                    meta: None,
+                    version: None,
+                },
+                block_time: Some(2),
+            })
+        }
+    }
+
+    fn directory(name: &str) -> String {
+        let directory = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        directory.to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed() -> anyhow::Result<()> {
+        let directory = directory("data_loader_recorded_responses_are_replayed");
+        let requests = Arc::new(AtomicUsize::new(0));
+        let account_key = Pubkey::new_unique();
+        let before = Signature::new_unique();
+
+        let recorder = SolanaReplayClient::record(
+            &directory,
+            Box::new(FakeClient {
+                requests: requests.clone(),
+            }),
+        );
+        let newest = recorder
+            .load_signatures_batch(&account_key, None, None)
+            .await?;
+        let older = recorder
+            .load_signatures_batch(&account_key, Some(before), None)
+            .await?;
+        let transaction = recorder.load_transaction_info(&newest[0].signature).await?;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let replayer = SolanaReplayClient::replay(&directory);
+        assert_eq!(
+            replayer
+                .load_signatures_batch(&account_key, None, None)
+                .await?,
+            newest
+        );
+        assert_eq!(
+            replayer
+                .load_signatures_batch(&account_key, Some(before), None)
+                .await?,
+            older
+        );
+        assert_eq!(
+            serde_json::to_value(replayer.load_transaction_info(&newest[0].signature).await?)?,
+            serde_json::to_value(transaction)?
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        std::fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay_fails_on_missing_response() {
+        let directory = directory("data_loader_replay_fails_on_missing_response");
+        let replayer = SolanaReplayClient::replay(&directory);
+
+        let err = replayer
+            .load_signatures_batch(&Pubkey::new_unique(), None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No recorded response"));
+
+        let err = replayer
+            .load_transaction_info(&Signature::new_unique().to_string())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No recorded response"));
+    }
+}
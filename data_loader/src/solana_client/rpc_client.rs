@@ -1,14 +1,21 @@
-use std::str::FromStr;
+use std::{ops::Range, str::FromStr};
 
 use crate::solana_client::{SolanaClient, TRANSACTIONS_BATCH_LEN};
 use async_trait::async_trait;
 use solana_client::{
-    client_error::ClientError, nonblocking::rpc_client::RpcClient,
-    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcBlockConfig, RpcTransactionConfig},
     rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_sdk::{
+    clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionDetails, UiConfirmedBlock,
+    UiTransactionEncoding,
+};
 
 pub struct SolanaRpcClient {
     pub(crate) rpc_client: RpcClient,
@@ -49,4 +56,36 @@ impl SolanaClient for SolanaRpcClient {
             .get_transaction_with_config(&signature, config)
             .await
     }
+
+    /// The blocks are loaded without their transactions, only the metadata and the rewards
+    async fn load_confirmed_blocks(
+        &self,
+        slots: Range<Slot>,
+    ) -> Result<Vec<(Slot, UiConfirmedBlock)>, ClientError> {
+        if slots.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            transaction_details: Some(TransactionDetails::None),
+            rewards: Some(true),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let mut blocks = Vec::new();
+        for slot in self
+            .rpc_client
+            .get_blocks(slots.start, Some(slots.end - 1))
+            .await?
+        {
+            blocks.push((
+                slot,
+                self.rpc_client.get_block_with_config(slot, config).await?,
+            ));
+        }
+
+        Ok(blocks)
+    }
 }
@@ -1,12 +1,24 @@
+/// Limit of the sleep of `repeat_until_ok!` between the attempts
+pub const MAX_REPEAT_SLEEP_SECS: u64 = 60;
+
+/// Repeats the call until it succeeds. The sleep between the attempts starts at `$sleep_time`
+/// seconds and doubles after every failure up to `MAX_REPEAT_SLEEP_SECS`, so a call which keeps
+/// failing, e.g. on a database which is down, isn't repeated in a hot loop
 #[macro_export]
 macro_rules! repeat_until_ok {
-    ( $func:expr, $sleep_time:expr ) => {{
+    ( $func:expr, $sleep_time:expr, $class:expr ) => {{
+        let mut sleep_time: u64 = $sleep_time;
         loop {
             match $func {
                 Ok(result) => break result,
                 Err(err) => {
                     log::error!("Error in func {}: {}", stringify!($func), err);
-                    tokio::time::sleep(std::time::Duration::from_secs($sleep_time)).await;
+                    $crate::prometheus_ctx::ERRORS_COUNT
+                        .with_label_values(&[$class.as_str()])
+                        .inc();
+                    tokio::time::sleep(std::time::Duration::from_secs(sleep_time)).await;
+                    sleep_time =
+                        (sleep_time * 2).clamp(1, $crate::storages::macros::MAX_REPEAT_SLEEP_SECS);
                 }
             }
         }
@@ -1,2 +1,3 @@
-pub mod queue_storage;
 pub mod macros;
+pub mod queue_storage;
+pub mod reconnecting;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS transactions_parsing_started_at;
+ALTER TABLE transactions DROP COLUMN IF EXISTS parsing_started_at;
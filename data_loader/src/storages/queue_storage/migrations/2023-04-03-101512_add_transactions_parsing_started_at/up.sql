@@ -0,0 +1,2 @@
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS parsing_started_at TIMESTAMP DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS transactions_parsing_started_at ON public.transactions USING btree (parsing_started_at);
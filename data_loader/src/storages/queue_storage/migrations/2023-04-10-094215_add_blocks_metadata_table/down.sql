@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS blocks_metadata_parsing_status;
+DROP TABLE IF EXISTS blocks_metadata;
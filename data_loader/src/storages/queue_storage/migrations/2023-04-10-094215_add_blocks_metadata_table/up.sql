@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS blocks_metadata (
+	slot BIGINT PRIMARY KEY,
+	blockhash TEXT NOT NULL,
+	rewards TEXT NOT NULL DEFAULT '[]',
+	block_time BIGINT,
+	block_height BIGINT,
+	parsing_status INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS blocks_metadata_parsing_status ON public.blocks_metadata USING btree (parsing_status);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS transactions_retention ON public.transactions USING btree (block_time) WHERE parsing_status = 1 AND transaction IS NOT NULL;
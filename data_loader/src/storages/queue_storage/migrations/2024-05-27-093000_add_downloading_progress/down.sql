@@ -0,0 +1,5 @@
+ALTER TABLE downloading_statuses
+    DROP COLUMN IF EXISTS oldest_loaded_slot,
+    DROP COLUMN IF EXISTS newest_loaded_slot,
+    DROP COLUMN IF EXISTS backfill_complete,
+    DROP COLUMN IF EXISTS last_batch_at;
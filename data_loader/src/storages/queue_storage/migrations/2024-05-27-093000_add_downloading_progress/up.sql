@@ -0,0 +1,20 @@
+ALTER TABLE downloading_statuses
+    ADD COLUMN IF NOT EXISTS oldest_loaded_slot BIGINT DEFAULT NULL,
+    ADD COLUMN IF NOT EXISTS newest_loaded_slot BIGINT DEFAULT NULL,
+    ADD COLUMN IF NOT EXISTS backfill_complete BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS last_batch_at TIMESTAMP DEFAULT NULL;
+
+UPDATE downloading_statuses
+SET oldest_loaded_slot = loaded.oldest_slot,
+    newest_loaded_slot = loaded.newest_slot
+FROM (
+    SELECT program, MIN(slot) AS oldest_slot, MAX(slot) AS newest_slot
+    FROM signatures
+    GROUP BY program
+) AS loaded
+WHERE loaded.program = downloading_statuses.key;
+
+-- A pass has been finished when the state has `until`
+UPDATE downloading_statuses
+SET backfill_complete = TRUE
+WHERE downloading_status::jsonb ->> 'until' IS NOT NULL;
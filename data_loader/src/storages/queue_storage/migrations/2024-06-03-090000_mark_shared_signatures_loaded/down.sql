@@ -0,0 +1,2 @@
+-- Loading statuses can't be restored
+SELECT 1;
@@ -0,0 +1,5 @@
+-- transactions is unique on signature since 2022-05-30-125526, so signatures queued for
+-- several programs only have to be marked loaded when the transaction is already stored
+UPDATE signatures SET loading_status = 2
+WHERE loading_status = 0
+  AND EXISTS (SELECT 1 FROM transactions WHERE transactions.signature = signatures.signature);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS signatures_program_loading_status_slot;
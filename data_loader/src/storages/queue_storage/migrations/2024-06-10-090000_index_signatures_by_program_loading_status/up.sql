@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS signatures_program_loading_status_slot ON public.signatures USING btree (program, loading_status, slot);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS signatures_errored_loading_status;
+-- The skipped status is unknown to the earlier loaders
+UPDATE signatures SET loading_status = 0 WHERE loading_status = 3;
@@ -0,0 +1,3 @@
+-- Signatures with errors are skipped in bulk (loading_status = 3) by the loading status checker
+-- while only the successful transactions are loaded, its first pass skips the existing rows
+CREATE INDEX IF NOT EXISTS signatures_errored_loading_status ON public.signatures USING btree (loading_status) WHERE err <> '';
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS signatures_pending_program;
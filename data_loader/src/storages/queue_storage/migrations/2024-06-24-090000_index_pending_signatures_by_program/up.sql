@@ -0,0 +1,3 @@
+-- The pending signatures of every program are counted by the loading status checker, the index
+-- holds only the pending rows, so a count doesn't read the loaded ones
+CREATE INDEX IF NOT EXISTS signatures_pending_program ON public.signatures USING btree (program) WHERE loading_status = 0;
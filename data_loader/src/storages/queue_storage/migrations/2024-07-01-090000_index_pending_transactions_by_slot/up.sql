@@ -0,0 +1,3 @@
+-- The pending transactions are counted and the oldest of them is looked up for the queue stats
+-- of the analyzer, the index holds only the pending rows, so neither reads the parsed ones
+CREATE INDEX IF NOT EXISTS transactions_pending_slot ON public.transactions USING btree (slot) WHERE parsing_status = 0;
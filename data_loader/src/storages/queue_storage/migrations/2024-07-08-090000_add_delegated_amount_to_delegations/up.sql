@@ -0,0 +1,3 @@
+-- The lamports still delegated by the stake account, the analyzer bounds the undelegated
+-- withdrawals by them. NULL for the rows saved before, which are bounded by the balance
+ALTER TABLE delegations ADD COLUMN IF NOT EXISTS delegated_amount BIGINT;
@@ -2,35 +2,168 @@
 pub mod models;
 pub mod schema;
 
-use self::models::{NewDownloadingStatus, NewSignature, NewTransaction};
+use self::models::{
+    Count, DownloadingProgress, NewBlockMetadata, NewDownloadingStatus, NewSignature,
+    NewTransaction, QueryPlan, SlotRow,
+};
 use self::schema::{
     downloading_statuses::columns::key, downloading_statuses::dsl::*, signatures::dsl::*,
     transactions::dsl::*,
 };
-use anyhow::Result;
+use super::reconnecting::{PgConnectionFactory, ReconnectingConnection};
+use crate::prometheus_ctx::QUEUE_SHARD_OPERATIONS_COUNT;
+use anyhow::{anyhow, Result};
 
-use diesel::{pg::PgConnection, prelude::*};
+use diesel::{pg::upsert::excluded, pg::PgConnection, prelude::*};
+use log::error;
+use queue_shards::{group_by_shard, shard_of};
+use serde::Serialize;
 use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
-use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransactionWithStatusMeta,
+};
+use std::cell::Cell;
+
+/// Program of the signature rows created for the transactions loaded by `fetch-tx`
+pub const FETCHED_PROGRAM: &str = "fetch-tx";
+
+/// Loading status of the signatures with errors when only the successful transactions are
+/// loaded. It's terminal like the loaded status until `reload_errored_signatures`
+pub const SKIPPED_DUE_TO_ERROR: i32 = 3;
+
+/// Parsing status the analyzer gives a transaction whose JSON it can't read, e.g. truncated by a
+/// crash mid-insert. The analyzer queues its signature for loading again and the row is replaced
+/// by the next `store_transaction`
+pub const CORRUPT_TRANSACTION: i32 = 98;
+
+/// Pending signatures of a program, `exact` is false for the planner's estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingSignatures {
+    pub count: i64,
+    pub exact: bool,
+}
+
+/// What `store_fetched_transaction` did with the transactions row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchedTransactionRow {
+    Inserted,
+    /// Replaced by the fetched transaction and queued for parsing again
+    Overwritten,
+    /// Already stored and left as is
+    Kept,
+}
 
+/// Queue of the signatures and their transactions, spread over the databases of the shards. A
+/// signature is stored in the shard `shard_of` routes it to, the operations on all the queued
+/// signatures are run on every shard. The downloading statuses of the programs are kept in the
+/// first shard
 pub struct QueueStorage {
-    connection: PgConnection,
+    shards: Vec<ReconnectingConnection>,
+    /// The program `get_signature_round_robin` claimed the last signature of
+    last_program: Option<String>,
+    /// The shard the next signature is claimed from first
+    next_shard: Cell<usize>,
 }
 
 embed_migrations!("./src/storages/queue_storage/migrations");
 
 impl QueueStorage {
-    pub async fn new(database_url: &str) -> Result<Self> {
-        let connection = establish_connection(database_url)?;
-        embedded_migrations::run(&connection)?;
-        Ok(QueueStorage { connection })
+    /// Connects to the databases of the shards and runs the migrations in every one of them, a
+    /// single database is the only shard
+    pub async fn new(database_urls: &[String]) -> Result<Self> {
+        if database_urls.is_empty() {
+            return Err(anyhow!("No database of the queue storage is set"));
+        }
+
+        let mut shards = Vec::with_capacity(database_urls.len());
+
+        for database_url in database_urls {
+            let connection = ReconnectingConnection::new(PgConnectionFactory::new(database_url))?;
+            connection.run(|conn| Ok(embedded_migrations::run(conn)?))?;
+            shards.push(connection);
+        }
+
+        Ok(QueueStorage {
+            shards,
+            last_program: None,
+            next_shard: Cell::new(0),
+        })
     }
 }
 
+impl QueueStorage {
+    /// Connects to every shard without running the migrations, for the readiness probe
+    pub fn ping(database_urls: &[String]) -> Result<()> {
+        for database_url in database_urls {
+            let connection = establish_connection(database_url)?;
+            diesel::sql_query("SELECT 1").execute(&connection)?;
+        }
+
+        Ok(())
+    }
+
+    /// Connection of the shard storing the signature
+    fn shard(&self, sign: &str, operation: &str) -> &ReconnectingConnection {
+        let shard = shard_of(sign, self.shards.len());
+        count_shard_operation(shard, operation);
+
+        &self.shards[shard]
+    }
+
+    /// Connection of the shard keeping the downloading statuses
+    fn status_shard(&self, operation: &str) -> &ReconnectingConnection {
+        count_shard_operation(0, operation);
+
+        &self.shards[0]
+    }
+
+    /// Runs the operation on every shard and returns their results in the order of the shards,
+    /// the first failure stops it
+    fn fan_out<T>(
+        &self,
+        operation: &str,
+        run: impl Fn(&PgConnection) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(shard, connection)| {
+                count_shard_operation(shard, operation);
+                connection.run(&run)
+            })
+            .collect()
+    }
+
+    /// Runs the operation on every shard and adds up the numbers of the updated rows
+    fn fan_out_sum(
+        &self,
+        operation: &str,
+        run: impl Fn(&PgConnection) -> Result<usize>,
+    ) -> Result<usize> {
+        Ok(self.fan_out(operation, run)?.into_iter().sum())
+    }
+}
+
+fn count_shard_operation(shard: usize, operation: &str) {
+    QUEUE_SHARD_OPERATIONS_COUNT
+        .with_label_values(&[&shard.to_string(), operation])
+        .inc();
+}
+
 fn establish_connection(database_url: &str) -> Result<PgConnection> {
     Ok(PgConnection::establish(database_url)?)
 }
 
+/// JSON of the transaction as it's stored, rejected with the `serde_json::Error` if it doesn't
+/// read back the way the analyzer reads it
+fn transaction_json(tx: &EncodedTransactionWithStatusMeta) -> serde_json::Result<String> {
+    let json = serde_json::to_string(tx)?;
+    serde_json::from_str::<EncodedTransactionWithStatusMeta>(&json)?;
+
+    Ok(json)
+}
+
 fn format_or_empty<T: std::fmt::Debug>(val: Option<T>) -> String {
     if val.is_some() {
         format!("{:?}", val.unwrap())
@@ -41,75 +174,171 @@ fn format_or_empty<T: std::fmt::Debug>(val: Option<T>) -> String {
 
 impl QueueStorage {
     pub fn load_downloading_status(&self, account_key: &str) -> Option<String> {
-        let conn = &self.connection;
-
-        if let Ok(result) = downloading_statuses
-            .select(downloading_status)
-            .filter(key.eq(account_key))
-            .first::<Option<String>>(conn)
-        {
-            result
-        } else {
-            None
-        }
+        self.status_shard("load_downloading_status")
+            .run(|conn| {
+                Ok(downloading_statuses
+                    .select(downloading_status)
+                    .filter(key.eq(account_key))
+                    .first::<Option<String>>(conn)?)
+            })
+            .ok()
+            .flatten()
     }
 
+    /// Claims the newest pending signature of a shard, of the `program_keys` programs if
+    /// they're set. The shards are claimed from in turn, the next one is asked when a shard has
+    /// no pending signatures
     pub fn get_signature_from_queue(
         &self,
         load_only_successful_transactions: bool,
+        program_keys: Option<&[String]>,
     ) -> Option<String> {
-        let conn = &self.connection;
+        let first_shard = self.next_shard.get();
 
-        let result = if load_only_successful_transactions {
-            signatures
-                .select(schema::signatures::dsl::signature)
-                .filter(loading_status.eq(0))
-                .filter(err.eq(""))
-                .order(schema::signatures::dsl::slot.desc())
-                .first::<String>(conn)
-        } else {
-            signatures
+        for offset in 0..self.shards.len() {
+            let shard = (first_shard + offset) % self.shards.len();
+            count_shard_operation(shard, "claim_signature");
+
+            let claimed = self.shards[shard]
+                .run(|conn| {
+                    Self::claim_signature(conn, load_only_successful_transactions, program_keys)
+                })
+                .unwrap_or_else(|err| {
+                    error!("Failed to claim a signature from shard {}: {}", shard, err);
+                    None
+                });
+
+            if claimed.is_some() {
+                self.next_shard.set((shard + 1) % self.shards.len());
+                return claimed;
+            }
+        }
+
+        None
+    }
+
+    fn claim_signature(
+        conn: &PgConnection,
+        load_only_successful_transactions: bool,
+        program_keys: Option<&[String]>,
+    ) -> Result<Option<String>> {
+        loop {
+            let mut query = signatures
                 .select(schema::signatures::dsl::signature)
                 .filter(loading_status.eq(0))
+                .into_boxed();
+
+            if load_only_successful_transactions {
+                query = query.filter(err.eq(""));
+            }
+
+            if let Some(program_keys) = program_keys {
+                query = query.filter(program.eq_any(program_keys));
+            }
+
+            let result = query
                 .order(schema::signatures::dsl::slot.desc())
-                .first::<String>(conn)
-        };
+                .first::<String>(conn);
+
+            let sign = match result {
+                Ok(sign) => sign,
+                Err(diesel::result::Error::NotFound) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
 
-        match result {
-            Ok(result) => {
-                let sign = result.clone();
-                let target = signatures.filter(schema::signatures::dsl::signature.eq(sign));
+            // A transaction touching several programs is queued once per program,
+            // it's loaded only for the first one
+            let loaded_for_another_program = diesel::select(diesel::dsl::exists(
+                signatures
+                    .filter(schema::signatures::dsl::signature.eq(&sign))
+                    .filter(loading_status.eq(2)),
+            ))
+            .get_result::<bool>(conn)?;
 
+            let target = signatures.filter(schema::signatures::dsl::signature.eq(&sign));
+
+            if loaded_for_another_program {
                 diesel::update(target)
-                    .set(loading_status.eq(1))
-                    .execute(conn)
-                    .unwrap();
-                Some(result)
+                    .set(loading_status.eq(2))
+                    .execute(conn)?;
+                continue;
+            }
+
+            diesel::update(target)
+                .set(loading_status.eq(1))
+                .execute(conn)?;
+            return Ok(Some(sign));
+        }
+    }
+
+    /// Claims the newest pending signature of the `programs` in turn, so a program with lots
+    /// of newer signatures doesn't starve the others. Falls back to the global ordering when
+    /// none of them has pending signatures
+    pub fn get_signature_round_robin(
+        &mut self,
+        load_only_successful_transactions: bool,
+        programs: &[String],
+    ) -> Option<String> {
+        self.get_program_signature_round_robin(load_only_successful_transactions, programs)
+            .or_else(|| self.get_signature_from_queue(load_only_successful_transactions, None))
+    }
+
+    /// Claims the newest pending signature of the `programs` in turn, starting after the
+    /// program of the last claimed one, `None` when none of them has pending signatures
+    pub fn get_program_signature_round_robin(
+        &mut self,
+        load_only_successful_transactions: bool,
+        programs: &[String],
+    ) -> Option<String> {
+        let next_program = self
+            .last_program
+            .as_ref()
+            .and_then(|last| programs.iter().position(|program_key| program_key == last))
+            .map_or(0, |last_idx| last_idx + 1);
+
+        for offset in 0..programs.len() {
+            let program_idx = (next_program + offset) % programs.len();
+
+            if let Some(sign) = self.get_signature_from_queue(
+                load_only_successful_transactions,
+                Some(std::slice::from_ref(&programs[program_idx])),
+            ) {
+                self.last_program = Some(programs[program_idx].clone());
+                return Some(sign);
             }
-            Err(_) => None,
         }
+
+        None
     }
 
     pub fn mark_signature_as_loaded(&self, sign: String) -> Result<()> {
-        let target = signatures.filter(schema::signatures::dsl::signature.eq(sign));
+        self.shard(&sign, "mark_signature_as_loaded").run(|conn| {
+            let target = signatures.filter(schema::signatures::dsl::signature.eq(&sign));
 
-        diesel::update(target)
-            .set(loading_status.eq(2))
-            .execute(&self.connection)?;
+            Ok(diesel::update(target)
+                .set(loading_status.eq(2))
+                .execute(conn)?)
+        })?;
 
         Ok(())
     }
 
     pub fn mark_signature_loading_fault(&self, sign: String) -> Result<()> {
-        let target = signatures.filter(schema::signatures::dsl::signature.eq(sign));
+        self.shard(&sign, "mark_signature_loading_fault")
+            .run(|conn| {
+                let target = signatures.filter(schema::signatures::dsl::signature.eq(&sign));
 
-        diesel::update(target)
-            .set(loading_status.eq(99))
-            .execute(&self.connection)?;
+                Ok(diesel::update(target)
+                    .set(loading_status.eq(99))
+                    .execute(conn)?)
+            })?;
 
         Ok(())
     }
 
+    /// Stores the transaction and marks its signature as loaded. A transaction whose JSON doesn't
+    /// read back is rejected with the `serde_json::Error`, a stored one marked as corrupt by the
+    /// analyzer is replaced
     pub fn store_transaction(
         &self,
         sign: &str,
@@ -117,30 +346,133 @@ impl QueueStorage {
     ) -> Result<()> {
         let new_transaction = NewTransaction {
             slot: tx.slot as i32,
-            transaction: &serde_json::to_string(&tx.transaction).unwrap(),
-            block_time: tx.block_time.unwrap_or_default() as i32,
+            transaction: &transaction_json(&tx.transaction)?,
+            block_time: tx.block_time.map(|block_time| block_time as i32),
             parsing_status: 0_i32,
             signature: sign,
         };
 
-        let conn = &self.connection;
-
-        conn.build_transaction()
-            .run::<(), diesel::result::Error, _>(|| {
-                diesel::insert_into(transactions)
-                    .values(&new_transaction)
-                    .on_conflict_do_nothing()
+        self.shard(sign, "store_transaction").run(|conn| {
+            conn.build_transaction()
+                .run::<(), diesel::result::Error, _>(|| {
+                    diesel::delete(
+                        transactions
+                            .filter(schema::transactions::dsl::signature.eq(sign))
+                            .filter(
+                                schema::transactions::dsl::parsing_status.eq(CORRUPT_TRANSACTION),
+                            ),
+                    )
                     .execute(conn)?;
 
-                let target = signatures.filter(schema::signatures::dsl::signature.eq(sign));
+                    diesel::insert_into(transactions)
+                        .values(&new_transaction)
+                        .on_conflict(schema::transactions::dsl::signature)
+                        .do_update()
+                        .set((
+                            schema::transactions::dsl::slot
+                                .eq(excluded(schema::transactions::dsl::slot)),
+                            schema::transactions::dsl::block_time
+                                .eq(excluded(schema::transactions::dsl::block_time)),
+                        ))
+                        .execute(conn)?;
 
-                diesel::update(target)
+                    let target = signatures.filter(schema::signatures::dsl::signature.eq(sign));
+
+                    diesel::update(target)
+                        .set(loading_status.eq(2))
+                        .execute(conn)?;
+
+                    Ok(())
+                })?;
+            Ok(())
+        })
+    }
+
+    /// Stores a transaction fetched by hand and marks its signature as loaded, a row is created
+    /// for the signature if no program has queued it. An already stored transaction is replaced
+    /// only if `force` is set
+    pub fn store_fetched_transaction(
+        &self,
+        sign: &str,
+        tx: EncodedConfirmedTransactionWithStatusMeta,
+        force: bool,
+    ) -> Result<FetchedTransactionRow> {
+        let tx_err = tx
+            .transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.err.as_ref());
+        let new_signature = NewSignature {
+            signature: sign,
+            slot: tx.slot as i32,
+            err: format_or_empty(tx_err),
+            memo: String::new(),
+            block_time: tx.block_time.map(|block_time| block_time as i32),
+            confirmation_status: String::new(),
+            loading_status: 2_i32,
+            program: FETCHED_PROGRAM,
+            potential_gap_start: false,
+        };
+        let new_transaction = NewTransaction {
+            slot: tx.slot as i32,
+            transaction: &transaction_json(&tx.transaction)?,
+            block_time: tx.block_time.map(|block_time| block_time as i32),
+            parsing_status: 0_i32,
+            signature: sign,
+        };
+
+        self.shard(sign, "store_fetched_transaction").run(|conn| {
+            let row = conn
+                .build_transaction()
+                .run::<FetchedTransactionRow, diesel::result::Error, _>(|| {
+                    let stored = diesel::select(diesel::dsl::exists(
+                        transactions.filter(schema::transactions::dsl::signature.eq(sign)),
+                    ))
+                    .get_result::<bool>(conn)?;
+
+                    let row = match (stored, force) {
+                        (false, _) => {
+                            diesel::insert_into(transactions)
+                                .values(&new_transaction)
+                                .execute(conn)?;
+                            FetchedTransactionRow::Inserted
+                        }
+                        (true, true) => {
+                            diesel::update(
+                                transactions.filter(schema::transactions::dsl::signature.eq(sign)),
+                            )
+                            .set((
+                                schema::transactions::dsl::slot.eq(new_transaction.slot),
+                                schema::transactions::dsl::transaction
+                                    .eq(new_transaction.transaction),
+                                schema::transactions::dsl::block_time
+                                    .eq(new_transaction.block_time),
+                                schema::transactions::dsl::parsing_status.eq(0),
+                                schema::transactions::dsl::parsing_started_at
+                                    .eq(None::<std::time::SystemTime>),
+                            ))
+                            .execute(conn)?;
+                            FetchedTransactionRow::Overwritten
+                        }
+                        (true, false) => FetchedTransactionRow::Kept,
+                    };
+
+                    let updated = diesel::update(
+                        signatures.filter(schema::signatures::dsl::signature.eq(sign)),
+                    )
                     .set(loading_status.eq(2))
                     .execute(conn)?;
 
-                Ok(())
-            })?;
-        Ok(())
+                    if updated == 0 {
+                        diesel::insert_into(signatures)
+                            .values(&new_signature)
+                            .execute(conn)?;
+                    }
+
+                    Ok(row)
+                })?;
+            Ok(row)
+        })
     }
 
     pub fn store_signatures_and_state(
@@ -148,9 +480,8 @@ impl QueueStorage {
         transaction_statuses: &[RpcConfirmedTransactionStatusWithSignature],
         account_key: &str,
         status: &str,
+        pass_finished: bool,
     ) -> Result<usize> {
-        let conn = &self.connection;
-
         let mut new_signatures = Vec::new();
 
         for transaction_status in transaction_statuses {
@@ -159,7 +490,9 @@ impl QueueStorage {
                 slot: transaction_status.slot as i32,
                 err: format_or_empty(transaction_status.err.as_ref()),
                 memo: format_or_empty(transaction_status.memo.as_ref()),
-                block_time: transaction_status.block_time.unwrap_or_default() as i32,
+                block_time: transaction_status
+                    .block_time
+                    .map(|block_time| block_time as i32),
                 confirmation_status: format_or_empty(
                     transaction_status.confirmation_status.as_ref(),
                 ),
@@ -171,7 +504,9 @@ impl QueueStorage {
             new_signatures.push(new_signature);
         }
 
-        if !new_signatures.is_empty() {
+        // The oldest signature of the batch is followed by the already stored ones only
+        // when the pass is finished, otherwise the next batch has to close the gap
+        if !new_signatures.is_empty() && !pass_finished {
             new_signatures
                 .iter_mut()
                 .last()
@@ -179,70 +514,857 @@ impl QueueStorage {
                 .potential_gap_start = true
         }
 
+        let oldest_slot = transaction_statuses
+            .iter()
+            .map(|transaction_status| transaction_status.slot as i64)
+            .min();
+        let newest_slot = transaction_statuses
+            .iter()
+            .map(|transaction_status| transaction_status.slot as i64)
+            .max();
+
         let new_downloading_status = NewDownloadingStatus {
             key: account_key,
             downloading_status: status,
         };
 
-        let ret_result = conn
-            .build_transaction()
-            .run::<usize, diesel::result::Error, _>(|| {
-                let mut rows_inserted = 0;
+        let first_in_batch = new_signatures
+            .first()
+            .map(|new_signature| new_signature.signature);
+        let shard_signatures = group_by_shard(new_signatures, self.shards.len(), |new_signature| {
+            new_signature.signature
+        });
+        let mut rows_inserted = 0;
 
-                if !new_signatures.is_empty() {
-                    let first_in_batch = new_signatures.get(0).unwrap().signature;
+        // The first shard keeps the downloading status, it's stored last so that a batch stored
+        // in part is requested again
+        for (shard, new_signatures) in shard_signatures.iter().enumerate().rev() {
+            if new_signatures.is_empty() && shard != 0 {
+                continue;
+            }
 
-                    diesel::update(
-                        signatures
-                            .filter(schema::signatures::dsl::signature.eq(first_in_batch))
-                            .filter(program.eq(account_key)),
-                    )
-                    .set(potential_gap_start.eq(false))
-                    .execute(conn)?;
+            count_shard_operation(shard, "store_signatures_and_state");
 
-                    rows_inserted = diesel::insert_into(signatures)
-                        .values(&new_signatures)
-                        .on_conflict_do_nothing()
-                        .execute(conn)?;
-                }
+            rows_inserted += self.shards[shard].run(|conn| {
+                let ret_result = conn
+                    .build_transaction()
+                    .run::<usize, diesel::result::Error, _>(|| {
+                        let mut rows_inserted = 0;
+
+                        if !new_signatures.is_empty() {
+                            let first_in_batch = first_in_batch.unwrap();
+
+                            if shard_of(first_in_batch, self.shards.len()) == shard {
+                                diesel::update(
+                                    signatures
+                                        .filter(
+                                            schema::signatures::dsl::signature.eq(first_in_batch),
+                                        )
+                                        .filter(program.eq(account_key)),
+                                )
+                                .set(potential_gap_start.eq(false))
+                                .execute(conn)?;
+                            }
 
-                let result = diesel::update(downloading_statuses.filter(key.eq(account_key)))
-                    .set(downloading_status.eq(status))
-                    .execute(conn);
+                            rows_inserted = diesel::insert_into(signatures)
+                                .values(new_signatures)
+                                .on_conflict_do_nothing()
+                                .execute(conn)?;
+                        }
 
-                if result.is_err() || (result.is_ok() && result? < 1) {
-                    diesel::insert_into(downloading_statuses)
-                        .values(&new_downloading_status)
-                        .on_conflict_do_nothing()
+                        if shard != 0 {
+                            return Ok(rows_inserted);
+                        }
+
+                        let result =
+                            diesel::update(downloading_statuses.filter(key.eq(account_key)))
+                                .set(downloading_status.eq(status))
+                                .execute(conn);
+
+                        if result.is_err() || (result.is_ok() && result? < 1) {
+                            diesel::insert_into(downloading_statuses)
+                                .values(&new_downloading_status)
+                                .on_conflict_do_nothing()
+                                .execute(conn)?;
+                        }
+
+                        // NULL slots of an empty batch are ignored by LEAST and GREATEST
+                        diesel::sql_query(
+                            "UPDATE downloading_statuses \
+                             SET oldest_loaded_slot = LEAST(oldest_loaded_slot, $2), \
+                                 newest_loaded_slot = GREATEST(newest_loaded_slot, $3), \
+                                 backfill_complete = backfill_complete OR $4, \
+                                 last_batch_at = now() \
+                             WHERE key = $1",
+                        )
+                        .bind::<diesel::sql_types::Text, _>(account_key)
+                        .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(
+                            oldest_slot,
+                        )
+                        .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(
+                            newest_slot,
+                        )
+                        .bind::<diesel::sql_types::Bool, _>(pass_finished)
                         .execute(conn)?;
-                }
 
-                Ok(rows_inserted)
+                        Ok(rows_inserted)
+                    })?;
+                Ok(ret_result)
             })?;
-        Ok(ret_result)
+        }
+
+        Ok(rows_inserted)
     }
 
-    pub fn reset_loading_status(&self) -> Result<()> {
-        let conn = &self.connection;
+    /// Queues again up to `limit` signatures of every shard whose loading failed, the number of
+    /// the updated rows is returned. The status is a literal, so the planner picks the loading
+    /// status index for any plan of the statement
+    pub fn reset_loading_status(&self, limit: i64) -> Result<usize> {
+        self.fan_out_sum("reset_loading_status", |conn| {
+            Ok(diesel::sql_query(
+                "UPDATE signatures SET loading_status = 0 \
+                 WHERE signature IN ( \
+                    SELECT signature FROM signatures WHERE loading_status = 99 LIMIT $1 \
+                 ) AND loading_status = 99",
+            )
+            .bind::<diesel::sql_types::BigInt, _>(limit)
+            .execute(conn)?)
+        })
+    }
 
-        let target = signatures.filter(schema::signatures::dsl::loading_status.eq(99));
-        diesel::update(target)
-            .set(loading_status.eq(0))
-            .execute(conn)
-            .unwrap();
+    pub fn reset_status_loading_in_progress(&self) -> Result<()> {
+        self.fan_out("reset_status_loading_in_progress", |conn| {
+            let target = signatures.filter(schema::signatures::dsl::loading_status.eq(1));
+
+            Ok(diesel::update(target)
+                .set(loading_status.eq(0))
+                .execute(conn)?)
+        })?;
 
         Ok(())
     }
 
-    pub fn reset_status_loading_in_progress(&self) -> Result<()> {
-        let conn = &self.connection;
+    /// Marks up to `limit` pending signatures of failed transactions of every shard as skipped,
+    /// they're never claimed while only the successful transactions are loaded. The rows are
+    /// found through the partial `signatures_errored_loading_status` index
+    pub fn skip_errored_signatures(&self, limit: i64) -> Result<usize> {
+        self.fan_out_sum("skip_errored_signatures", |conn| {
+            Ok(diesel::sql_query(
+                "UPDATE signatures SET loading_status = $1 \
+                 WHERE signature IN ( \
+                    SELECT signature FROM signatures WHERE loading_status = 0 AND err <> '' LIMIT $2 \
+                 ) AND loading_status = 0 AND err <> ''",
+            )
+            .bind::<diesel::sql_types::Integer, _>(SKIPPED_DUE_TO_ERROR)
+            .bind::<diesel::sql_types::BigInt, _>(limit)
+            .execute(conn)?)
+        })
+    }
 
-        let target = signatures.filter(schema::signatures::dsl::loading_status.eq(1));
-        diesel::update(target)
-            .set(loading_status.eq(0))
-            .execute(conn)
-            .unwrap();
+    /// Queues the skipped signatures of failed transactions again
+    pub fn reload_errored_signatures(&self) -> Result<usize> {
+        self.fan_out_sum("reload_errored_signatures", |conn| {
+            let target = signatures.filter(loading_status.eq(SKIPPED_DUE_TO_ERROR));
 
-        Ok(())
+            Ok(diesel::update(target)
+                .set(loading_status.eq(0))
+                .execute(conn)?)
+        })
+    }
+
+    /// Signatures of the program waiting to be claimed by the transactions loaders. Up to
+    /// `exact_count_limit` of them are counted through the partial `signatures_pending_program`
+    /// index; beyond it the count is the planner's estimate, `reltuples` of `pg_class` scaled by
+    /// the statistics of the columns, so a backfilling program doesn't make every pass read
+    /// millions of index entries. The counts of the shards are added up, the sum is exact only
+    /// if all of them are
+    pub fn count_pending_signatures(
+        &self,
+        program_key: &str,
+        exact_count_limit: i64,
+    ) -> Result<PendingSignatures> {
+        let shard_counts = self.fan_out("count_pending_signatures", |conn| {
+            if exact_count_limit > 0 {
+                let count = diesel::sql_query(
+                    "SELECT count(*) AS count FROM ( \
+                        SELECT 1 FROM signatures WHERE program = $1 AND loading_status = 0 LIMIT $2 \
+                     ) pending",
+                )
+                .bind::<diesel::sql_types::Text, _>(program_key)
+                .bind::<diesel::sql_types::BigInt, _>(exact_count_limit)
+                .get_result::<Count>(conn)?
+                .count;
+
+                if count < exact_count_limit {
+                    return Ok(PendingSignatures { count, exact: true });
+                }
+            }
+
+            let plan = diesel::sql_query(
+                "EXPLAIN (FORMAT JSON) \
+                 SELECT 1 FROM signatures WHERE program = $1 AND loading_status = 0",
+            )
+            .bind::<diesel::sql_types::Text, _>(program_key)
+            .get_result::<QueryPlan>(conn)?;
+            let estimate = plan
+                .rows()
+                .ok_or_else(|| anyhow!("No row estimate in the plan {}", plan.plan))?;
+
+            // The statistics may lag behind, there are at least as many as were counted
+            Ok(PendingSignatures {
+                count: (estimate as i64).max(exact_count_limit),
+                exact: false,
+            })
+        })?;
+
+        Ok(PendingSignatures {
+            count: shard_counts.iter().map(|pending| pending.count).sum(),
+            exact: shard_counts.iter().all(|pending| pending.exact),
+        })
+    }
+
+    /// Truncates the JSON of up to `limit` parsed transactions of every shard with a block_time
+    /// older than `min_age_days`. Pending and in-progress transactions are never touched, nor
+    /// the ones stored without a block_time (NULL, or 0 as the older versions stored it)
+    pub fn purge_parsed_transactions(&self, min_age_days: u32, limit: i64) -> Result<usize> {
+        self.fan_out_sum("purge_parsed_transactions", |conn| {
+            let purged = diesel::sql_query(
+                "UPDATE transactions SET transaction = NULL \
+                 WHERE signature IN ( \
+                    SELECT signature FROM transactions \
+                    WHERE parsing_status = 1 AND transaction IS NOT NULL \
+                      AND block_time > 0 \
+                      AND block_time < extract(epoch from now()) - $1 * 86400 \
+                    LIMIT $2 \
+                 ) AND parsing_status = 1",
+            )
+            .bind::<diesel::sql_types::Integer, _>(min_age_days as i32)
+            .bind::<diesel::sql_types::BigInt, _>(limit)
+            .execute(conn)?;
+
+            Ok(purged)
+        })
+    }
+
+    /// Slots of the pending transactions of every shard without their blocks metadata, up to
+    /// `limit` of a shard, the oldest first
+    pub fn slots_without_blocks_metadata(&self, limit: i64) -> Result<Vec<u64>> {
+        let shard_slots = self.fan_out("slots_without_blocks_metadata", |conn| {
+            Ok(diesel::sql_query(
+                "SELECT DISTINCT t.slot::BIGINT AS slot FROM transactions t \
+                 WHERE t.parsing_status = 0 AND t.slot IS NOT NULL \
+                   AND NOT EXISTS (SELECT 1 FROM blocks_metadata b WHERE b.slot = t.slot) \
+                 ORDER BY slot LIMIT $1",
+            )
+            .bind::<diesel::sql_types::BigInt, _>(limit)
+            .load::<SlotRow>(conn)?)
+        })?;
+
+        let mut slots: Vec<u64> = shard_slots
+            .into_iter()
+            .flatten()
+            .map(|row| row.slot as u64)
+            .collect();
+        slots.sort_unstable();
+        slots.dedup();
+
+        Ok(slots)
+    }
+
+    /// Stores the metadata of the blocks in the shards having transactions of their slots, the
+    /// metadata stored before is kept. Returns the number of the inserted rows
+    pub fn store_blocks_metadata(&self, blocks: &[NewBlockMetadata]) -> Result<usize> {
+        let block_slots: Vec<i32> = blocks.iter().map(|block| block.slot as i32).collect();
+
+        self.fan_out_sum("store_blocks_metadata", |conn| {
+            let shard_slots: Vec<Option<i32>> = transactions
+                .select(schema::transactions::dsl::slot)
+                .filter(schema::transactions::dsl::slot.eq_any(&block_slots))
+                .distinct()
+                .load(conn)?;
+            let shard_blocks: Vec<NewBlockMetadata> = blocks
+                .iter()
+                .filter(|block| shard_slots.contains(&Some(block.slot as i32)))
+                .cloned()
+                .collect();
+
+            if shard_blocks.is_empty() {
+                return Ok(0);
+            }
+
+            Ok(diesel::insert_into(schema::blocks_metadata::table)
+                .values(&shard_blocks)
+                .on_conflict_do_nothing()
+                .execute(conn)?)
+        })
+    }
+
+    /// Returns the loading progress of the program, None if nothing has been loaded yet
+    pub fn get_progress(&self, account_key: &str) -> Result<Option<DownloadingProgress>> {
+        self.status_shard("get_progress").run(|conn| {
+            let progress = diesel::sql_query(
+                "SELECT oldest_loaded_slot, newest_loaded_slot, backfill_complete, \
+                        to_char(last_batch_at, 'YYYY-MM-DD HH24:MI:SS') AS last_batch_at \
+                 FROM downloading_statuses WHERE key = $1",
+            )
+            .bind::<diesel::sql_types::Text, _>(account_key)
+            .load::<DownloadingProgress>(conn)?;
+
+            Ok(progress.into_iter().next())
+        })
+    }
+}
+
+#[cfg(test)]
+mod queue_storage_server_tests {
+    use super::*;
+    use solana_transaction_status::EncodedTransaction;
+
+    const DATABASE_URL: &str = "postgresql://postgres@badaddr/postgres";
+
+    fn signature_status(sign: &str, sign_slot: u64) -> RpcConfirmedTransactionStatusWithSignature {
+        RpcConfirmedTransactionStatusWithSignature {
+            signature: sign.to_string(),
+            slot: sign_slot,
+            err: None,
+            memo: None,
+            block_time: Some(sign_slot as i64),
+            confirmation_status: None,
+        }
+    }
+
+    fn transaction(tx_slot: u64, tx_block_time: i64) -> EncodedConfirmedTransactionWithStatusMeta {
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: tx_slot,
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: EncodedTransaction::LegacyBinary(String::new()),
+                meta: None,
+                version: None,
+            },
+            block_time: Some(tx_block_time),
+        }
+    }
+
+    fn cleanup(storage: &QueueStorage, signs: &[&str], programs: &[&str]) -> Result<()> {
+        storage.shards[0].run(|conn| {
+            diesel::delete(signatures.filter(schema::signatures::dsl::signature.eq_any(signs)))
+                .execute(conn)?;
+            diesel::delete(transactions.filter(schema::transactions::dsl::signature.eq_any(signs)))
+                .execute(conn)?;
+            diesel::delete(downloading_statuses.filter(key.eq_any(programs))).execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    /// Claims and stores signatures the way the transactions loaders do, returns the
+    /// claimed ones, i.e. the ones loaded with RPC
+    fn load_queue(storage: &QueueStorage) -> Result<Vec<String>> {
+        let mut loaded = Vec::new();
+
+        while let Some(sign) = storage.get_signature_from_queue(false, None) {
+            storage.store_transaction(&sign, transaction(1, 1))?;
+            storage.mark_signature_as_loaded(sign.clone())?;
+            loaded.push(sign);
+        }
+
+        Ok(loaded)
+    }
+
+    #[tokio::test]
+    async fn shared_signature_is_loaded_once() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["shared_signature", "second_program_signature"];
+        let programs = ["first_program", "second_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        storage.store_signatures_and_state(
+            &[signature_status(signs[0], 10)],
+            programs[0],
+            "{}",
+            true,
+        )?;
+        assert_eq!(load_queue(&storage)?, vec![signs[0]]);
+
+        // The second program meets the transaction after it's loaded for the first one
+        storage.store_signatures_and_state(
+            &[
+                signature_status(signs[1], 20),
+                signature_status(signs[0], 10),
+            ],
+            programs[1],
+            "{}",
+            true,
+        )?;
+        assert_eq!(load_queue(&storage)?, vec![signs[1]]);
+
+        let stored: i64 = storage.shards[0].run(|conn| {
+            Ok(transactions
+                .filter(schema::transactions::dsl::signature.eq(signs[0]))
+                .count()
+                .get_result(conn)?)
+        })?;
+        assert_eq!(stored, 1);
+
+        let statuses: Vec<Option<i32>> = storage.shards[0].run(|conn| {
+            Ok(signatures
+                .select(loading_status)
+                .filter(schema::signatures::dsl::signature.eq(signs[0]))
+                .load(conn)?)
+        })?;
+        assert_eq!(statuses, vec![Some(2), Some(2)]);
+
+        cleanup(&storage, &signs, &programs)
+    }
+
+    #[tokio::test]
+    async fn round_robin_alternates_programs() -> Result<()> {
+        let mut storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = [
+            "busy_program_signature_1",
+            "busy_program_signature_2",
+            "busy_program_signature_3",
+            "busy_program_signature_4",
+            "quiet_program_signature_1",
+            "quiet_program_signature_2",
+        ];
+        let programs = ["busy_program", "quiet_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        // All the signatures of the busy program are newer
+        storage.store_signatures_and_state(
+            &[
+                signature_status(signs[0], 40),
+                signature_status(signs[1], 30),
+                signature_status(signs[2], 20),
+                signature_status(signs[3], 10),
+            ],
+            programs[0],
+            "{}",
+            true,
+        )?;
+        storage.store_signatures_and_state(
+            &[signature_status(signs[4], 2), signature_status(signs[5], 1)],
+            programs[1],
+            "{}",
+            true,
+        )?;
+
+        let programs = programs.map(String::from);
+        let mut claimed = Vec::new();
+        while let Some(sign) = storage.get_signature_round_robin(false, &programs) {
+            claimed.push(sign);
+        }
+
+        assert_eq!(
+            claimed,
+            vec![signs[0], signs[4], signs[1], signs[5], signs[2], signs[3]]
+        );
+
+        cleanup(&storage, &signs, &["busy_program", "quiet_program"])
+    }
+
+    #[tokio::test]
+    async fn preferred_programs_are_claimed_first() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["backfill_program_signature", "alerted_program_signature"];
+        let programs = ["backfill_program", "alerted_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        storage.store_signatures_and_state(
+            &[signature_status(signs[0], 20)],
+            programs[0],
+            "{}",
+            true,
+        )?;
+        storage.store_signatures_and_state(
+            &[signature_status(signs[1], 10)],
+            programs[1],
+            "{}",
+            true,
+        )?;
+
+        // The older signature of the preferred program goes first
+        let preferred = [programs[1].to_string()];
+        assert_eq!(
+            storage.get_signature_from_queue(false, Some(&preferred)),
+            Some(signs[1].to_string())
+        );
+        assert_eq!(
+            storage.get_signature_from_queue(false, Some(&preferred)),
+            None
+        );
+        assert_eq!(
+            storage.get_signature_from_queue(false, None),
+            Some(signs[0].to_string())
+        );
+
+        cleanup(&storage, &signs, &programs)
+    }
+
+    #[tokio::test]
+    async fn errored_signatures_are_skipped_and_reloaded() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["successful_signature", "errored_signature"];
+        let programs = ["erroring_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        let mut errored = signature_status(signs[1], 20);
+        errored.err = Some(solana_sdk::transaction::TransactionError::AccountInUse);
+        storage.store_signatures_and_state(
+            &[errored, signature_status(signs[0], 10)],
+            programs[0],
+            "{}",
+            true,
+        )?;
+
+        let status = |sign: &str| -> Result<Option<i32>> {
+            storage.shards[0].run(|conn| {
+                Ok(signatures
+                    .select(loading_status)
+                    .filter(schema::signatures::dsl::signature.eq(sign))
+                    .first(conn)?)
+            })
+        };
+
+        assert_eq!(storage.skip_errored_signatures(100)?, 1);
+        assert_eq!(status(signs[1])?, Some(SKIPPED_DUE_TO_ERROR));
+        assert_eq!(status(signs[0])?, Some(0));
+
+        // Skipped signatures are neither pending nor claimed
+        assert_eq!(
+            storage.get_signature_from_queue(false, Some(&[programs[0].to_string()])),
+            Some(signs[0].to_string())
+        );
+        assert_eq!(
+            storage.get_signature_from_queue(false, Some(&[programs[0].to_string()])),
+            None
+        );
+
+        assert_eq!(storage.reload_errored_signatures()?, 1);
+        assert_eq!(status(signs[1])?, Some(0));
+
+        cleanup(&storage, &signs, &programs)
+    }
+
+    #[tokio::test]
+    async fn pending_signatures_are_counted_up_to_the_limit() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = [
+            "pending_signature_1",
+            "pending_signature_2",
+            "pending_signature_3",
+        ];
+        let programs = ["counted_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        storage.store_signatures_and_state(
+            &signs
+                .iter()
+                .enumerate()
+                .map(|(idx, sign)| signature_status(sign, idx as u64 + 1))
+                .collect::<Vec<_>>(),
+            programs[0],
+            "{}",
+            true,
+        )?;
+        storage.mark_signature_as_loaded(signs[0].to_string())?;
+
+        assert_eq!(
+            storage.count_pending_signatures(programs[0], 10)?,
+            PendingSignatures {
+                count: 2,
+                exact: true
+            }
+        );
+
+        let estimate = storage.count_pending_signatures(programs[0], 2)?;
+        assert!(!estimate.exact);
+        assert!(estimate.count >= 2);
+
+        cleanup(&storage, &signs, &programs)
+    }
+
+    #[tokio::test]
+    async fn stored_again_transaction_updates_slot_and_block_time() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["restored_signature"];
+        cleanup(&storage, &signs, &[])?;
+
+        storage.store_transaction(signs[0], transaction(1, 100))?;
+        storage.store_transaction(signs[0], transaction(2, 200))?;
+
+        let stored: Vec<(Option<i32>, Option<i32>)> = storage.shards[0].run(|conn| {
+            Ok(transactions
+                .select((
+                    schema::transactions::dsl::slot,
+                    schema::transactions::dsl::block_time,
+                ))
+                .filter(schema::transactions::dsl::signature.eq(signs[0]))
+                .load(conn)?)
+        })?;
+        assert_eq!(stored, vec![(Some(2), Some(200))]);
+
+        cleanup(&storage, &signs, &[])
+    }
+
+    #[tokio::test]
+    async fn transaction_without_block_time_is_stored_with_null() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["undated_signature"];
+        cleanup(&storage, &signs, &[])?;
+
+        // The analyzer resolves a NULL block time, a 0 would be stored as is
+        let mut tx = transaction(1, 100);
+        tx.block_time = None;
+        storage.store_transaction(signs[0], tx)?;
+
+        let stored: Vec<Option<i32>> = storage.shards[0].run(|conn| {
+            Ok(transactions
+                .select(schema::transactions::dsl::block_time)
+                .filter(schema::transactions::dsl::signature.eq(signs[0]))
+                .load(conn)?)
+        })?;
+        assert_eq!(stored, vec![None]);
+
+        cleanup(&storage, &signs, &[])
+    }
+
+    #[tokio::test]
+    async fn blocks_metadata_is_stored_for_pending_slots() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["block_metadata_signature"];
+        let block_slot = 2_000_000_279;
+        cleanup(&storage, &signs, &[])?;
+        storage.shards[0].run(|conn| {
+            diesel::delete(
+                schema::blocks_metadata::table
+                    .filter(schema::blocks_metadata::slot.eq(block_slot as i64)),
+            )
+            .execute(conn)?;
+
+            Ok(())
+        })?;
+
+        storage.store_transaction(signs[0], transaction(block_slot, 100))?;
+        assert!(storage
+            .slots_without_blocks_metadata(i64::MAX)?
+            .contains(&block_slot));
+
+        let block = NewBlockMetadata {
+            slot: block_slot as i64,
+            blockhash: "blockhash".to_string(),
+            rewards: "[]".to_string(),
+            block_time: Some(100),
+            block_height: Some(90),
+        };
+        // The slot without transactions is not stored
+        let other_block = NewBlockMetadata {
+            slot: block_slot as i64 + 1,
+            ..block.clone()
+        };
+        assert_eq!(storage.store_blocks_metadata(&[block, other_block])?, 1);
+        assert!(!storage
+            .slots_without_blocks_metadata(i64::MAX)?
+            .contains(&block_slot));
+
+        let stored: Vec<(i64, String, i32)> = storage.shards[0].run(|conn| {
+            Ok(schema::blocks_metadata::table
+                .select((
+                    schema::blocks_metadata::slot,
+                    schema::blocks_metadata::blockhash,
+                    schema::blocks_metadata::parsing_status,
+                ))
+                .filter(schema::blocks_metadata::slot.ge(block_slot as i64))
+                .load(conn)?)
+        })?;
+        assert_eq!(
+            stored,
+            vec![(block_slot as i64, "blockhash".to_string(), 0)]
+        );
+
+        cleanup(&storage, &signs, &[])
+    }
+
+    #[tokio::test]
+    async fn only_dated_parsed_transactions_are_purged() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = [
+            "purged_parsed_signature",
+            "undated_parsed_signature",
+            "old_pending_signature",
+            "old_in_progress_signature",
+        ];
+        cleanup(&storage, &signs, &[])?;
+
+        storage.shards[0].run(|conn| {
+            for (sign, tx_block_time, status) in [
+                (signs[0], Some(100), 1),
+                (signs[1], None, 1),
+                (signs[2], Some(100), 0),
+                (signs[3], Some(100), 2),
+            ] {
+                diesel::insert_into(transactions)
+                    .values(&NewTransaction {
+                        slot: 1,
+                        transaction: "{}",
+                        block_time: tx_block_time,
+                        parsing_status: status,
+                        signature: sign,
+                    })
+                    .execute(conn)?;
+            }
+            Ok(())
+        })?;
+
+        while storage.purge_parsed_transactions(30, 10000)? > 0 {}
+
+        let mut stored: Vec<(String, Option<String>)> = storage.shards[0].run(|conn| {
+            Ok(transactions
+                .select((
+                    schema::transactions::dsl::signature,
+                    schema::transactions::dsl::transaction,
+                ))
+                .filter(schema::transactions::dsl::signature.eq_any(&signs[..]))
+                .load(conn)?)
+        })?;
+        stored.sort();
+        assert_eq!(
+            stored,
+            vec![
+                (signs[3].to_string(), Some("{}".to_string())),
+                (signs[2].to_string(), Some("{}".to_string())),
+                (signs[0].to_string(), None),
+                (signs[1].to_string(), Some("{}".to_string())),
+            ]
+        );
+
+        cleanup(&storage, &signs, &[])
+    }
+
+    #[test]
+    fn stored_json_reads_back() {
+        let tx = transaction(1, 100).transaction;
+        let json = transaction_json(&tx).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<EncodedTransactionWithStatusMeta>(&json).unwrap(),
+            tx
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupt_transaction_is_replaced() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["corrupt_signature"];
+        cleanup(&storage, &signs, &[])?;
+
+        // Truncated by a crash mid-insert and flagged by the analyzer
+        storage.shards[0].run(|conn| {
+            diesel::insert_into(transactions)
+                .values(&NewTransaction {
+                    slot: 1,
+                    transaction: "{\"transaction\":{\"signatures\":[",
+                    block_time: Some(100),
+                    parsing_status: CORRUPT_TRANSACTION,
+                    signature: signs[0],
+                })
+                .execute(conn)?;
+            Ok(())
+        })?;
+
+        storage.store_transaction(signs[0], transaction(2, 200))?;
+
+        let stored: Vec<(Option<String>, Option<i32>)> = storage.shards[0].run(|conn| {
+            Ok(transactions
+                .select((
+                    schema::transactions::dsl::transaction,
+                    schema::transactions::dsl::parsing_status,
+                ))
+                .filter(schema::transactions::dsl::signature.eq(signs[0]))
+                .load(conn)?)
+        })?;
+        assert_eq!(
+            stored,
+            vec![(
+                Some(transaction_json(&transaction(2, 200).transaction)?),
+                Some(0)
+            )]
+        );
+
+        cleanup(&storage, &signs, &[])
+    }
+
+    #[tokio::test]
+    async fn fetched_transaction_is_overwritten_with_force() -> Result<()> {
+        let storage = QueueStorage::new(&[DATABASE_URL.to_string()]).await?;
+        let signs = ["fetched_signature", "skipped_signature"];
+        let programs = ["skipping_program"];
+        cleanup(&storage, &signs, &programs)?;
+
+        // The signature of a program has been given up on
+        storage.store_signatures_and_state(
+            &[signature_status(signs[1], 10)],
+            programs[0],
+            "{}",
+            true,
+        )?;
+        storage.mark_signature_loading_fault(signs[1].to_string())?;
+
+        assert_eq!(
+            storage.store_fetched_transaction(signs[0], transaction(1, 100), false)?,
+            FetchedTransactionRow::Inserted
+        );
+        assert_eq!(
+            storage.store_fetched_transaction(signs[0], transaction(2, 200), false)?,
+            FetchedTransactionRow::Kept
+        );
+        assert_eq!(
+            storage.store_fetched_transaction(signs[1], transaction(10, 10), false)?,
+            FetchedTransactionRow::Inserted
+        );
+
+        storage.shards[0].run(|conn| {
+            Ok(diesel::update(
+                transactions.filter(schema::transactions::dsl::signature.eq(signs[0])),
+            )
+            .set(schema::transactions::dsl::parsing_status.eq(1))
+            .execute(conn)?)
+        })?;
+        assert_eq!(
+            storage.store_fetched_transaction(signs[0], transaction(3, 300), true)?,
+            FetchedTransactionRow::Overwritten
+        );
+
+        let stored: Vec<(Option<i32>, Option<i32>, Option<i32>)> =
+            storage.shards[0].run(|conn| {
+                Ok(transactions
+                    .select((
+                        schema::transactions::dsl::slot,
+                        schema::transactions::dsl::block_time,
+                        schema::transactions::dsl::parsing_status,
+                    ))
+                    .filter(schema::transactions::dsl::signature.eq(signs[0]))
+                    .load(conn)?)
+            })?;
+        assert_eq!(stored, vec![(Some(3), Some(300), Some(0))]);
+
+        // A row is created for the signature no program has queued, the queued one is reset
+        let statuses: Vec<(String, String, Option<i32>)> = storage.shards[0].run(|conn| {
+            Ok(signatures
+                .select((schema::signatures::dsl::signature, program, loading_status))
+                .filter(schema::signatures::dsl::signature.eq_any(signs))
+                .order(schema::signatures::dsl::signature)
+                .load(conn)?)
+        })?;
+        assert_eq!(
+            statuses,
+            vec![
+                (signs[0].to_string(), FETCHED_PROGRAM.to_string(), Some(2)),
+                (signs[1].to_string(), programs[0].to_string(), Some(2)),
+            ]
+        );
+
+        cleanup(&storage, &signs, &programs)
     }
 }
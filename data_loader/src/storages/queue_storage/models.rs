@@ -1,4 +1,5 @@
-use super::schema::{downloading_statuses, signatures, transactions};
+use super::schema::{blocks_metadata, downloading_statuses, signatures, transactions};
+use diesel::{deserialize, pg::Pg, row::NamedRow, QueryableByName};
 
 #[derive(Insertable, Debug)]
 #[table_name = "signatures"]
@@ -7,7 +8,7 @@ pub struct NewSignature<'a> {
     pub slot: i32,
     pub err: String,
     pub memo: String,
-    pub block_time: i32,
+    pub block_time: Option<i32>,
     pub confirmation_status: String,
     pub loading_status: i32,
     pub program: &'a str,
@@ -20,7 +21,7 @@ pub struct Signature {
     pub slot: i32,
     pub err: String,
     pub memo: String,
-    pub block_time: i32,
+    pub block_time: Option<i32>,
     pub confirmation_status: String,
     pub loading_status: i32,
     pub program: String,
@@ -41,12 +42,73 @@ pub struct DownloadingStatus {
     pub downloading_status: String,
 }
 
+/// Loading progress of a program, `last_batch_at` is formatted by PostgreSQL
+#[derive(QueryableByName, Debug)]
+pub struct DownloadingProgress {
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::BigInt>"]
+    pub oldest_loaded_slot: Option<i64>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::BigInt>"]
+    pub newest_loaded_slot: Option<i64>,
+    #[sql_type = "diesel::sql_types::Bool"]
+    pub backfill_complete: bool,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Text>"]
+    pub last_batch_at: Option<String>,
+}
+
+/// Metadata of a block for the analyzer, `rewards` is the JSON of the block rewards
+#[derive(Insertable, Clone, Debug, PartialEq, Eq)]
+#[table_name = "blocks_metadata"]
+pub struct NewBlockMetadata {
+    pub slot: i64,
+    pub blockhash: String,
+    pub rewards: String,
+    pub block_time: Option<i64>,
+    pub block_height: Option<i64>,
+}
+
+/// Result of a `SELECT ... AS slot` query
+#[derive(QueryableByName, Debug)]
+pub struct SlotRow {
+    #[sql_type = "diesel::sql_types::BigInt"]
+    pub slot: i64,
+}
+
+/// Result of a `SELECT count(*) AS count` query
+#[derive(QueryableByName, Debug)]
+pub struct Count {
+    #[sql_type = "diesel::sql_types::BigInt"]
+    pub count: i64,
+}
+
+/// Output of `EXPLAIN (FORMAT JSON)`, its only column is named `QUERY PLAN`
+#[derive(Debug)]
+pub struct QueryPlan {
+    pub plan: String,
+}
+
+impl QueryableByName<Pg> for QueryPlan {
+    fn build<R: NamedRow<Pg>>(row: &R) -> deserialize::Result<Self> {
+        Ok(Self {
+            plan: row.get::<diesel::sql_types::Text, _>("QUERY PLAN")?,
+        })
+    }
+}
+
+impl QueryPlan {
+    /// Rows the planner expects the top node of the plan to return
+    pub fn rows(&self) -> Option<f64> {
+        let plan: serde_json::Value = serde_json::from_str(&self.plan).ok()?;
+        plan.get(0)?.get("Plan")?.get("Plan Rows")?.as_f64()
+    }
+}
+
 #[derive(Insertable)]
 #[table_name = "transactions"]
 pub struct NewTransaction<'a> {
     pub slot: i32,
     pub transaction: &'a str,
-    pub block_time: i32,
+    /// NULL when the node doesn't know it, the analyzer resolves it then
+    pub block_time: Option<i32>,
     pub parsing_status: i32,
     pub signature: &'a str,
 }
@@ -54,7 +116,7 @@ pub struct NewTransaction<'a> {
 #[derive(Queryable)]
 pub struct Transaction {
     pub transaction: String,
-    pub block_time: i32,
+    pub block_time: Option<i32>,
     pub parsing_status: i32,
     pub signature: String,
 }
@@ -1,7 +1,19 @@
+table! {
+    blocks_metadata (slot) {
+        slot -> Int8,
+        blockhash -> Text,
+        rewards -> Text,
+        block_time -> Nullable<Int8>,
+        block_height -> Nullable<Int8>,
+        parsing_status -> Int4,
+    }
+}
+
 table! {
     delegations (stake_acc) {
         stake_acc -> Text,
         vote_acc -> Nullable<Text>,
+        delegated_amount -> Nullable<Int8>,
     }
 }
 
@@ -10,6 +22,10 @@ table! {
         id -> Int4,
         key -> Nullable<Varchar>,
         downloading_status -> Nullable<Varchar>,
+        oldest_loaded_slot -> Nullable<Int8>,
+        newest_loaded_slot -> Nullable<Int8>,
+        backfill_complete -> Bool,
+        last_batch_at -> Nullable<Timestamp>,
     }
 }
 
@@ -50,10 +66,12 @@ table! {
         block_time -> Nullable<Int4>,
         parsing_status -> Nullable<Int4>,
         signature -> Varchar,
+        parsing_started_at -> Nullable<Timestamp>,
     }
 }
 
 allow_tables_to_appear_in_same_query!(
+    blocks_metadata,
     delegations,
     downloading_statuses,
     epochs,
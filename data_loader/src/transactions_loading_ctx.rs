@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use log::info;
 
@@ -7,8 +9,12 @@ use crate::{
         transactions_saver::TransactionsSaverHandle,
     },
     register::Register,
+    signature_tracing::TraceEvent,
 };
 
+/// Pause of a loader which got no signature, the queue is empty or the storage is unavailable
+const IDLE_SLEEP: Duration = Duration::from_millis(500);
+
 pub struct TransactionsLoadingCtx;
 
 impl TransactionsLoadingCtx {
@@ -18,14 +24,17 @@ impl TransactionsLoadingCtx {
         for tx_loader_idx in 0..register.config.get_tx_loaders_num() {
             let queue_manager = primary_queue_manager.clone();
             let rpc_loader = TransactionsRpcLoaderHandle::new(
-                register.config.get_solana_client_type(),
+                register.config.get_solana_client_config(),
                 &register.config.get_endpoint_url(),
+                register.rate_limiter.clone(),
             )
             .await;
             let transaction_saver = TransactionsSaverHandle::new(register).await?;
             let load_only_successful_transactions = register
                 .config
                 .get_load_only_successful_transactions_status();
+            let health = register.health.clone();
+            let tracer = register.tracer.clone();
 
             tokio::spawn(async move {
                 loop {
@@ -33,17 +42,33 @@ impl TransactionsLoadingCtx {
                         .get_signature_from_queue(load_only_successful_transactions)
                         .await
                     {
-                        info!("TxLoader {} scheduled {:?}", &tx_loader_idx, &signature);
+                        info!(
+                            actor = "transactions_loader",
+                            tx_signature = signature.as_str();
+                            "TxLoader {} scheduled {:?}",
+                            &tx_loader_idx,
+                            &signature
+                        );
 
                         let sign = signature.clone();
 
                         let transaction = rpc_loader.transaction_rpc_load(signature.clone()).await;
+                        tracer.trace(&sign, TraceEvent::Fetched);
 
-                        info!("TxLoader {} success - {:?}", &tx_loader_idx, &sign);
+                        info!(
+                            actor = "transactions_loader",
+                            tx_signature = sign.as_str();
+                            "TxLoader {} success - {:?}",
+                            &tx_loader_idx,
+                            &sign
+                        );
                         transaction_saver.save_transaction(sign, transaction).await;
+                        health.write().unwrap().batch_loaded();
                         queue_manager
                             .mark_signature_as_loaded(signature.clone())
                             .await;
+                    } else {
+                        tokio::time::sleep(IDLE_SLEEP).await;
                     }
                 }
             });
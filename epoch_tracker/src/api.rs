@@ -0,0 +1,281 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{error, info};
+use serde::Serialize;
+use serde_json::json;
+use solana_sdk::clock::Epoch;
+
+use crate::{
+    errors::EpochStorageError,
+    register::Register,
+    storage::epoch_storage::{EpochBoundaries, EpochStorage},
+};
+
+/// Read access to the epochs the API serves
+#[async_trait]
+pub trait EpochSource: Send + Sync + 'static {
+    async fn current_epoch(&self) -> Result<Option<EpochBoundaries>, EpochStorageError>;
+    async fn epoch(&self, epoch: Epoch) -> Result<Option<EpochBoundaries>, EpochStorageError>;
+    async fn epochs(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<EpochBoundaries>, EpochStorageError>;
+}
+
+#[async_trait]
+impl EpochSource for EpochStorage {
+    async fn current_epoch(&self) -> Result<Option<EpochBoundaries>, EpochStorageError> {
+        EpochStorage::get_current_epoch().await
+    }
+
+    async fn epoch(&self, epoch: Epoch) -> Result<Option<EpochBoundaries>, EpochStorageError> {
+        EpochStorage::get_epoch(epoch).await
+    }
+
+    async fn epochs(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<EpochBoundaries>, EpochStorageError> {
+        EpochStorage::get_epochs(from, to).await
+    }
+}
+
+/// HTTP/JSON API exposing the epoch boundaries:
+/// `GET /epochs/current`, `GET /epochs/{number}` and `GET /epochs?from=&to=`
+pub struct ApiServer {}
+
+impl ApiServer {
+    pub async fn run() -> Result<()> {
+        let addr: SocketAddr = Register::current()
+            .configuration
+            .api_bind_address()
+            .parse()?;
+
+        let (addr, server) = Self::bind(&addr, EpochStorage {})?;
+        info!("API server started on http://{}", addr);
+
+        tokio::spawn(server);
+
+        Ok(())
+    }
+
+    /// Binds the server to the `addr` and returns the actual address along with the future
+    /// serving the requests
+    fn bind<S: EpochSource>(
+        addr: &SocketAddr,
+        source: S,
+    ) -> Result<(SocketAddr, impl std::future::Future<Output = ()>)> {
+        let source = Arc::new(source);
+
+        let server = Server::try_bind(addr)?.serve(make_service_fn(move |_| {
+            let source = source.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let source = source.clone();
+                    async move { Ok::<_, Infallible>(Self::handle(source.as_ref(), request).await) }
+                }))
+            }
+        }));
+        let addr = server.local_addr();
+
+        Ok((addr, async move {
+            if let Err(err) = server.await {
+                error!("API server error: {}", err);
+            }
+        }))
+    }
+
+    async fn handle<S: EpochSource>(source: &S, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::GET {
+            return Self::error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+        }
+
+        let path: Vec<&str> = request
+            .uri()
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        let result = match path.as_slice() {
+            ["epochs"] => match Self::parse_range(request.uri().query().unwrap_or_default()) {
+                Some((from, to)) => source
+                    .epochs(from, to)
+                    .await
+                    .map(|epochs| Self::json(&epochs)),
+                None => return Self::error(StatusCode::BAD_REQUEST, "Invalid epochs range"),
+            },
+            ["epochs", "current"] => source.current_epoch().await.map(Self::json_or_not_found),
+            ["epochs", epoch] => match epoch.parse() {
+                Ok(epoch) => source.epoch(epoch).await.map(Self::json_or_not_found),
+                Err(_) => return Self::error(StatusCode::BAD_REQUEST, "Invalid epoch number"),
+            },
+            _ => return Self::error(StatusCode::NOT_FOUND, "Not found"),
+        };
+
+        result.unwrap_or_else(|err| {
+            error!("Failed to serve {}: {}", request.uri(), err);
+            Self::error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+        })
+    }
+
+    /// Parses `from` and `to` query parameters. The range is open from the side of a missing one
+    fn parse_range(query: &str) -> Option<(Epoch, Epoch)> {
+        let mut from = Epoch::MIN;
+        let mut to = Epoch::MAX;
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("from", value)) if !value.is_empty() => from = value.parse().ok()?,
+                Some(("to", value)) if !value.is_empty() => to = value.parse().ok()?,
+                _ => {}
+            }
+        }
+
+        (from <= to).then_some((from, to))
+    }
+
+    fn json_or_not_found(epoch: Option<EpochBoundaries>) -> Response<Body> {
+        match epoch {
+            Some(epoch) => Self::json(&epoch),
+            None => Self::error(StatusCode::NOT_FOUND, "Epoch not found"),
+        }
+    }
+
+    fn json<T: Serialize>(value: &T) -> Response<Body> {
+        Self::response(StatusCode::OK, serde_json::to_vec(value).unwrap())
+    }
+
+    fn error(status: StatusCode, message: &str) -> Response<Body> {
+        Self::response(status, json!({ "error": message }).to_string().into_bytes())
+    }
+
+    fn response(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{body::to_bytes, Client};
+    use serde_json::Value;
+
+    use super::*;
+
+    struct SeededStorage {
+        epochs: Vec<EpochBoundaries>,
+    }
+
+    #[async_trait]
+    impl EpochSource for SeededStorage {
+        async fn current_epoch(&self) -> Result<Option<EpochBoundaries>, EpochStorageError> {
+            Ok(self.epochs.iter().max_by_key(|epoch| epoch.epoch).cloned())
+        }
+
+        async fn epoch(&self, epoch: Epoch) -> Result<Option<EpochBoundaries>, EpochStorageError> {
+            Ok(self.epochs.iter().find(|e| e.epoch == epoch).cloned())
+        }
+
+        async fn epochs(
+            &self,
+            from: Epoch,
+            to: Epoch,
+        ) -> Result<Vec<EpochBoundaries>, EpochStorageError> {
+            Ok(self
+                .epochs
+                .iter()
+                .filter(|epoch| from <= epoch.epoch && epoch.epoch <= to)
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn epoch(epoch: Epoch) -> EpochBoundaries {
+        EpochBoundaries {
+            epoch,
+            first_slot: Some(epoch * 432000),
+            last_slot: Some((epoch + 1) * 432000 - 1),
+            first_block_time: (epoch < 402).then_some(1_670_000_000 + epoch as i64),
+        }
+    }
+
+    async fn get(addr: &SocketAddr, path: &str) -> (StatusCode, Value) {
+        let response = Client::new()
+            .get(format!("http://{}{}", addr, path).parse().unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn epochs_endpoints() {
+        let storage = SeededStorage {
+            epochs: vec![epoch(400), epoch(401), epoch(402)],
+        };
+        let (addr, server) = ApiServer::bind(&"127.0.0.1:0".parse().unwrap(), storage).unwrap();
+        tokio::spawn(server);
+
+        let (status, body) = get(&addr, "/epochs/current").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "epoch": 402,
+                "first_slot": 173664000,
+                "last_slot": 174095999,
+                "first_block_time": null
+            })
+        );
+
+        let (status, body) = get(&addr, "/epochs/400").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "epoch": 400,
+                "first_slot": 172800000,
+                "last_slot": 173231999,
+                "first_block_time": 1670000400
+            })
+        );
+
+        let (status, _) = get(&addr, "/epochs/399").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = get(&addr, "/epochs/abc").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = get(&addr, "/epochs?from=401&to=402").await;
+        assert_eq!(status, StatusCode::OK);
+        let epochs: Vec<u64> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|epoch| epoch["epoch"].as_u64().unwrap())
+            .collect();
+        assert_eq!(epochs, vec![401, 402]);
+
+        let (status, body) = get(&addr, "/epochs?to=400").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let (status, _) = get(&addr, "/epochs?from=402&to=401").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
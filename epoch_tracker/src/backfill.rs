@@ -0,0 +1,365 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use indexer_errors::Classify;
+use log::{error, info, warn};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcBlockConfig,
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+    },
+    rpc_request::RpcError,
+};
+use solana_sdk::{
+    clock::{Epoch, Slot},
+    commitment_config::CommitmentConfig,
+    epoch_schedule::EpochSchedule,
+};
+use solana_transaction_status::{EncodedConfirmedBlock, TransactionDetails, UiTransactionEncoding};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+use crate::{
+    errors::{EpochStorageError, EpochTrackerError},
+    prometheus::ERRORS_COUNT,
+    storage::epoch_storage::EpochStorage,
+};
+
+/// Attempts to get a block when the request fails not because of a skipped slot
+const MAX_ATTEMPTS: usize = 5;
+
+/// RPC requests the backfill makes
+#[async_trait]
+pub trait BlockSource: Send + Sync {
+    async fn epoch_schedule(&self) -> Result<EpochSchedule, EpochTrackerError>;
+
+    /// Returns None if the slot was skipped
+    async fn block(&self, slot: Slot) -> Result<Option<EncodedConfirmedBlock>, EpochTrackerError>;
+}
+
+#[async_trait]
+impl BlockSource for RpcClient {
+    async fn epoch_schedule(&self) -> Result<EpochSchedule, EpochTrackerError> {
+        Ok(self.get_epoch_schedule().await?)
+    }
+
+    async fn block(&self, slot: Slot) -> Result<Option<EncodedConfirmedBlock>, EpochTrackerError> {
+        let block = self
+            .get_block_with_config(
+                slot,
+                RpcBlockConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    transaction_details: Some(TransactionDetails::Full),
+                    rewards: Some(true),
+                    commitment: Some(CommitmentConfig::finalized()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await;
+
+        match block {
+            Ok(block) => Ok(Some(block.into())),
+            Err(err) if is_slot_skipped(&err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn is_slot_skipped(err: &ClientError) -> bool {
+    matches!(
+        err.kind(),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if *code == JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+                || *code == JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED
+    )
+}
+
+/// Storage of the backfilled epochs
+#[async_trait]
+pub trait BackfillStorage: Send + Sync {
+    /// Epochs of the `from..=to` range which already have the first block
+    async fn backfilled_epochs(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<Epoch>, EpochStorageError>;
+
+    async fn store_epoch_with_first_block(
+        &self,
+        epoch: Epoch,
+        first_slot: Slot,
+        last_slot: Slot,
+        first_block: Slot,
+        first_block_raw: &EncodedConfirmedBlock,
+    ) -> Result<(), EpochStorageError>;
+}
+
+#[async_trait]
+impl BackfillStorage for EpochStorage {
+    async fn backfilled_epochs(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<Epoch>, EpochStorageError> {
+        EpochStorage::get_epochs_with_first_block(from, to).await
+    }
+
+    async fn store_epoch_with_first_block(
+        &self,
+        epoch: Epoch,
+        first_slot: Slot,
+        last_slot: Slot,
+        first_block: Slot,
+        first_block_raw: &EncodedConfirmedBlock,
+    ) -> Result<(), EpochStorageError> {
+        EpochStorage::store_epoch_with_first_block(
+            epoch,
+            first_slot,
+            last_slot,
+            first_block,
+            first_block_raw,
+        )
+        .await
+    }
+}
+
+/// Loads the historical epochs along with their first blocks. Each RPC request waits for
+/// the rate limiter, so the backfill doesn't exhaust the RPC limits shared with the tracking
+pub struct Backfill<S, T> {
+    source: S,
+    storage: T,
+    rate_limiter: Interval,
+}
+
+impl<S: BlockSource, T: BackfillStorage> Backfill<S, T> {
+    pub fn new(source: S, storage: T, requests_per_second: u32) -> Self {
+        let mut rate_limiter = interval(Duration::from_secs(1) / requests_per_second.max(1));
+        rate_limiter.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Self {
+            source,
+            storage,
+            rate_limiter,
+        }
+    }
+
+    /// Backfills the `from..=to` epochs. The epochs which already have the first block are
+    /// skipped, so an interrupted backfill continues from where it stopped
+    pub async fn run(&mut self, from: Epoch, to: Epoch) -> Result<(), EpochTrackerError> {
+        if from > to {
+            return Ok(());
+        }
+
+        self.rate_limiter.tick().await;
+        let epoch_schedule = self.source.epoch_schedule().await?;
+        let backfilled_epochs = self.storage.backfilled_epochs(from, to).await?;
+
+        info!(
+            actor = "backfill";
+            "Backfilling epochs from {} to {}, {} of them are already loaded",
+            from,
+            to,
+            backfilled_epochs.len()
+        );
+
+        for epoch in (from..=to).filter(|epoch| !backfilled_epochs.contains(epoch)) {
+            let first_slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+            let last_slot = epoch_schedule.get_last_slot_in_epoch(epoch);
+
+            match self.find_first_block(first_slot, last_slot).await? {
+                Some((first_block, first_block_raw)) => {
+                    self.storage
+                        .store_epoch_with_first_block(
+                            epoch,
+                            first_slot,
+                            last_slot,
+                            first_block,
+                            &first_block_raw,
+                        )
+                        .await?;
+
+                    info!(
+                        actor = "backfill",
+                        epoch = epoch;
+                        "First block of {} epoch is {}",
+                        epoch,
+                        first_block
+                    );
+                }
+                None => warn!(
+                    actor = "backfill",
+                    epoch = epoch;
+                    "No blocks were produced in {} epoch",
+                    epoch
+                ),
+            }
+        }
+
+        info!(actor = "backfill"; "Epochs from {} to {} are backfilled", from, to);
+
+        Ok(())
+    }
+
+    /// The leaders of the first slots of an epoch may skip them, so the slots are probed one
+    /// by one until a produced block is found
+    async fn find_first_block(
+        &mut self,
+        first_slot: Slot,
+        last_slot: Slot,
+    ) -> Result<Option<(Slot, EncodedConfirmedBlock)>, EpochTrackerError> {
+        for slot in first_slot..=last_slot {
+            if let Some(block) = self.get_block(slot).await? {
+                return Ok(Some((slot, block)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn get_block(
+        &mut self,
+        slot: Slot,
+    ) -> Result<Option<EncodedConfirmedBlock>, EpochTrackerError> {
+        let mut attempt = 1;
+
+        loop {
+            self.rate_limiter.tick().await;
+
+            match self.source.block(slot).await {
+                Err(err) if attempt < MAX_ATTEMPTS => {
+                    error!("Failed to get block of {} slot: {}", slot, err);
+                    ERRORS_COUNT
+                        .with_label_values(&[err.classify().as_str()])
+                        .inc();
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    const SLOTS_PER_EPOCH: u64 = 32;
+
+    /// Blocks of every slot except the first `skipped_slots` slots of each epoch
+    struct MockedRpc {
+        skipped_slots: u64,
+        requested_slots: Mutex<Vec<Slot>>,
+    }
+
+    #[async_trait]
+    impl BlockSource for MockedRpc {
+        async fn epoch_schedule(&self) -> Result<EpochSchedule, EpochTrackerError> {
+            Ok(EpochSchedule::custom(
+                SLOTS_PER_EPOCH,
+                SLOTS_PER_EPOCH,
+                false,
+            ))
+        }
+
+        async fn block(
+            &self,
+            slot: Slot,
+        ) -> Result<Option<EncodedConfirmedBlock>, EpochTrackerError> {
+            self.requested_slots.lock().unwrap().push(slot);
+
+            if slot % SLOTS_PER_EPOCH < self.skipped_slots {
+                return Ok(None);
+            }
+
+            Ok(Some(EncodedConfirmedBlock {
+                previous_blockhash: Default::default(),
+                blockhash: Default::default(),
+                parent_slot: slot.saturating_sub(self.skipped_slots + 1),
+                transactions: Vec::new(),
+                rewards: Vec::new(),
+                block_time: Some(1_670_000_000 + slot as i64),
+                block_height: None,
+            }))
+        }
+    }
+
+    #[derive(Default)]
+    struct MockedStorage {
+        backfilled_epochs: Vec<Epoch>,
+        stored: Mutex<Vec<(Epoch, Slot, Slot, Slot)>>,
+    }
+
+    #[async_trait]
+    impl BackfillStorage for MockedStorage {
+        async fn backfilled_epochs(
+            &self,
+            from: Epoch,
+            to: Epoch,
+        ) -> Result<Vec<Epoch>, EpochStorageError> {
+            Ok(self
+                .backfilled_epochs
+                .iter()
+                .copied()
+                .filter(|epoch| from <= *epoch && *epoch <= to)
+                .collect())
+        }
+
+        async fn store_epoch_with_first_block(
+            &self,
+            epoch: Epoch,
+            first_slot: Slot,
+            last_slot: Slot,
+            first_block: Slot,
+            _first_block_raw: &EncodedConfirmedBlock,
+        ) -> Result<(), EpochStorageError> {
+            self.stored
+                .lock()
+                .unwrap()
+                .push((epoch, first_slot, last_slot, first_block));
+
+            Ok(())
+        }
+    }
+
+    fn mocked_rpc(skipped_slots: u64) -> MockedRpc {
+        MockedRpc {
+            skipped_slots,
+            requested_slots: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn first_block_after_skipped_slots() {
+        let mut backfill = Backfill::new(mocked_rpc(3), MockedStorage::default(), 1000);
+
+        backfill.run(2, 2).await.unwrap();
+
+        assert_eq!(
+            *backfill.storage.stored.lock().unwrap(),
+            vec![(2, 64, 95, 67)]
+        );
+        assert_eq!(
+            *backfill.source.requested_slots.lock().unwrap(),
+            vec![64, 65, 66, 67]
+        );
+    }
+
+    #[tokio::test]
+    async fn backfilled_epochs_are_skipped() {
+        let storage = MockedStorage {
+            backfilled_epochs: vec![1, 3],
+            ..Default::default()
+        };
+        let mut backfill = Backfill::new(mocked_rpc(0), storage, 1000);
+
+        backfill.run(1, 4).await.unwrap();
+
+        assert_eq!(
+            *backfill.storage.stored.lock().unwrap(),
+            vec![(2, 64, 95, 64), (4, 128, 159, 128)]
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::Result;
 use clap::{crate_description, crate_name, crate_version, App, Arg, ArgMatches};
 use config::{Config, Environment};
+use indexer_errors::ConfigurationErrors;
+use indexer_telemetry::LogFormat;
 use serde::Deserialize;
+use solana_sdk::{clock::Epoch, pubkey::Pubkey};
+use std::str::FromStr;
 
 #[derive(Deserialize, Default, Debug)]
 struct EndPoint {
@@ -24,17 +28,55 @@ pub struct PrometheusExporter {
     bind_address: String,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct Api {
+    bind_address: String,
+}
+
+#[derive(Deserialize, Default, Debug)]
+struct Logging {
+    #[serde(default)]
+    format: LogFormat,
+}
+
+#[derive(Deserialize, Debug)]
+struct Epochs {
+    /// The first epoch to backfill, the historical epochs aren't loaded if it's not set
+    #[serde(default)]
+    backfill_from: Option<Epoch>,
+    #[serde(default = "default_backfill_requests_per_second")]
+    backfill_requests_per_second: u32,
+}
+
+impl Default for Epochs {
+    fn default() -> Self {
+        Self {
+            backfill_from: None,
+            backfill_requests_per_second: default_backfill_requests_per_second(),
+        }
+    }
+}
+
+fn default_backfill_requests_per_second() -> u32 {
+    10
+}
+
 #[derive(Deserialize, Default, Debug)]
 pub struct Configuration {
     endpoint: EndPoint,
     storage: Storage,
     validator: Validator,
     prometheus_exporter: PrometheusExporter,
+    api: Api,
+    #[serde(default)]
+    logging: Logging,
+    #[serde(default)]
+    epochs: Epochs,
 }
 
 impl Configuration {
     pub fn new() -> Result<Self> {
-        Ok(Config::builder()
+        let configuration = Config::builder()
             .add_source(config::File::with_name(
                 get_matches().value_of("config-file").unwrap_or_default(),
             ))
@@ -45,7 +87,56 @@ impl Configuration {
                     .try_parsing(true),
             )
             .build()?
-            .try_deserialize::<Configuration>()?)
+            .try_deserialize::<Configuration>()?;
+        configuration.validate()?;
+
+        Ok(configuration)
+    }
+
+    /// Reads the configuration from a TOML string without the environment variables
+    #[cfg(test)]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let configuration = Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize::<Configuration>()?;
+        configuration.validate()?;
+
+        Ok(configuration)
+    }
+
+    /// Checks the values serde can't, every problem is reported rather than the first one
+    fn validate(&self) -> Result<(), ConfigurationErrors> {
+        let mut errors = ConfigurationErrors::new();
+
+        errors.check_url("endpoint.url", &self.endpoint.url, &["http", "https"]);
+        if let Err(err) = tokio_postgres::Config::from_str(&self.storage.url) {
+            errors.push(format!(
+                "storage.url is not a PostgreSQL connection string: {}",
+                err
+            ));
+        }
+        errors.check(
+            Pubkey::from_str(&self.validator.vote_account).is_ok(),
+            || {
+                format!(
+                    "validator.vote_account {:?} is not a base58 public key",
+                    self.validator.vote_account
+                )
+            },
+        );
+
+        errors.check_bind_address(
+            "prometheus_exporter.bind_address",
+            &self.prometheus_exporter.bind_address,
+        );
+        errors.check_bind_address("api.bind_address", &self.api.bind_address);
+
+        errors.check(self.epochs.backfill_requests_per_second > 0, || {
+            "epochs.backfill_requests_per_second must be positive".to_string()
+        });
+
+        errors.into_result()
     }
 
     pub fn endpoint(&self) -> &str {
@@ -63,6 +154,22 @@ impl Configuration {
     pub fn prometheus_exporter_bind_address(&self) -> String {
         self.prometheus_exporter.bind_address.clone()
     }
+
+    pub fn api_bind_address(&self) -> String {
+        self.api.bind_address.clone()
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.logging.format
+    }
+
+    pub fn backfill_from(&self) -> Option<Epoch> {
+        self.epochs.backfill_from
+    }
+
+    pub fn backfill_requests_per_second(&self) -> u32 {
+        self.epochs.backfill_requests_per_second
+    }
 }
 
 pub fn get_matches() -> ArgMatches {
@@ -86,3 +193,94 @@ pub fn get_matches() -> ArgMatches {
         )
         .get_matches()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+        [endpoint]
+        url = "https://api.mainnet-beta.solana.com/"
+
+        [storage]
+        url = "host=localhost user=solana_staking password=solana_staking dbname=solana_staking"
+
+        [validator]
+        vote_account = "9QU2QSxhb24FUX3Tu2FpczXjpK3VYrvRudywSZaM29mF"
+
+        [prometheus_exporter]
+        bind_address = "127.0.0.1:9899"
+
+        [api]
+        bind_address = "127.0.0.1:9898"
+    "#;
+
+    /// Problems of the valid configuration with the `replacements` applied
+    fn problems(replacements: &[(&str, &str)]) -> Vec<String> {
+        let toml = replacements
+            .iter()
+            .fold(VALID.to_string(), |toml, (from, to)| toml.replace(from, to));
+
+        Config::builder()
+            .add_source(config::File::from_str(&toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize::<Configuration>()
+            .unwrap()
+            .validate()
+            .map_or_else(|errors| errors.problems().to_vec(), |_| Vec::new())
+    }
+
+    #[test]
+    fn valid_configuration_has_no_problems() {
+        assert!(problems(&[]).is_empty());
+        assert!(Configuration::from_toml(VALID).is_ok());
+    }
+
+    #[test]
+    fn endpoint_url_must_be_http() {
+        let problems = problems(&[("https://api", "api")]);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("endpoint.url"));
+    }
+
+    #[test]
+    fn storage_url_must_be_a_connection_string() {
+        let problems = problems(&[("host=localhost", "host localhost")]);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("storage.url"));
+    }
+
+    #[test]
+    fn vote_account_must_be_a_public_key() {
+        assert_eq!(
+            problems(&[("9QU2QSxhb24FUX3Tu2FpczXjpK3VYrvRudywSZaM29mF", "")]),
+            [r#"validator.vote_account "" is not a base58 public key"#]
+        );
+    }
+
+    #[test]
+    fn bind_addresses_must_be_socket_addresses() {
+        let problems = problems(&[
+            ("127.0.0.1:9899", "9899"),
+            ("127.0.0.1:9898", "localhost:9898"),
+        ]);
+
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("prometheus_exporter.bind_address"));
+        assert!(problems[1].starts_with("api.bind_address"));
+    }
+
+    #[test]
+    fn backfill_requests_per_second_must_be_positive() {
+        let toml = format!("{}\n[epochs]\nbackfill_requests_per_second = 0", VALID);
+
+        assert!(Configuration::from_toml(&toml).is_err());
+        assert_eq!(
+            problems(&[("[api]", "[epochs]\nbackfill_requests_per_second = 0\n[api]")]),
+            ["epochs.backfill_requests_per_second must be positive"]
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::{
 
 use clap::error;
 use futures::executor;
+use indexer_errors::{Classify, ErrorClass};
 use log::{debug, error, info};
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
@@ -24,8 +25,8 @@ use solana_transaction_status::EncodedConfirmedBlock;
 use tokio::time::sleep;
 
 use crate::{
-    configuration::get_matches, errors::EpochTrackerError, register::Register,
-    storage::epoch_storage::EpochStorage,
+    backfill::Backfill, configuration::get_matches, errors::EpochTrackerError,
+    prometheus::ERRORS_COUNT, register::Register, storage::epoch_storage::EpochStorage,
 };
 
 struct CurrentEpoch {
@@ -58,6 +59,28 @@ impl EpochTracker {
             sleep(Duration::from_secs(1)).await;
         }
 
+        if let Some(backfill_from) = Register::current().configuration.backfill_from() {
+            let rpc_client = RpcClient::new(url.clone());
+            let requests_per_second = Register::current()
+                .configuration
+                .backfill_requests_per_second();
+            // The current epoch is loaded by the tracking
+            let backfill_to = current_epoch.lock().unwrap().epoch.saturating_sub(1);
+
+            // Backfill historical epochs
+            tokio::spawn(async move {
+                if let Err(err) = Backfill::new(rpc_client, EpochStorage {}, requests_per_second)
+                    .run(backfill_from, backfill_to)
+                    .await
+                {
+                    error!("Failed to backfill epochs: {}", err);
+                    ERRORS_COUNT
+                        .with_label_values(&[err.classify().as_str()])
+                        .inc();
+                }
+            });
+        }
+
         let c_current_epoch = current_epoch.clone();
 
         let mut epochs_setup_completed = false;
@@ -199,7 +222,12 @@ impl EpochTracker {
                     lock.epoch
                 };
 
-                info!("Current epoch: {}", current_epoch);
+                info!(
+                    actor = "epoch_tracker",
+                    epoch = current_epoch;
+                    "Current epoch: {}",
+                    current_epoch
+                );
 
                 sleep(Duration::from_secs(3600)).await;
             }
@@ -254,6 +282,9 @@ impl EpochTracker {
                 }
                 Err(e) => {
                     error!("Error while trying to get epoch: {:?}", e);
+                    ERRORS_COUNT
+                        .with_label_values(&[ErrorClass::External.as_str()])
+                        .inc();
                 }
             }
 
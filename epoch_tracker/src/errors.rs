@@ -1,3 +1,4 @@
+use indexer_errors::{impl_into_indexer_error, Classify, ErrorClass};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,4 +18,60 @@ pub enum EpochTrackerError {
 pub enum EpochStorageError {
     #[error("Failed to connect to PostgreSQL Server: {0} ")]
     PostgresConnection(#[from] tokio_postgres::Error),
+
+    #[error("Failed to run migrations: {0}")]
+    Migrations(#[from] clickhouse_storage::StorageError),
+}
+
+impl Classify for EpochTrackerError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            EpochTrackerError::EpochStorage(err) => err.classify(),
+            EpochTrackerError::SolanaClient(_) => ErrorClass::External,
+            EpochTrackerError::SerdeJsonEncoding(_) => ErrorClass::DataCorruption,
+        }
+    }
+}
+
+impl Classify for EpochStorageError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            EpochStorageError::PostgresConnection(_) => ErrorClass::Transient,
+            EpochStorageError::Migrations(err) => err.classify(),
+        }
+    }
+}
+
+impl_into_indexer_error!(EpochTrackerError, EpochStorageError);
+
+#[cfg(test)]
+mod tests {
+    use clickhouse_storage::StorageError;
+    use indexer_errors::IndexerError;
+
+    use super::*;
+
+    #[test]
+    fn indexer_error_keeps_the_message() {
+        let err = IndexerError::from(EpochTrackerError::EpochStorage(
+            EpochStorageError::Migrations(StorageError::UnknownMigration(
+                "20221218013028".to_string(),
+            )),
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Failed to retrieve first and last slots of an epoch: Failed to run migrations: \
+            Unknown migration version: 20221218013028"
+        );
+        assert_eq!(err.classify(), ErrorClass::Configuration);
+
+        let err = IndexerError::from(EpochTrackerError::SerdeJsonEncoding(
+            serde_json::from_str::<u64>("epoch").unwrap_err(),
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Failed JSON encode: expected value at line 1 column 1"
+        );
+        assert_eq!(err.classify(), ErrorClass::DataCorruption);
+    }
 }
@@ -1,3 +1,5 @@
+mod api;
+mod backfill;
 mod configuration;
 mod epoch_tracker;
 mod errors;
@@ -6,7 +8,6 @@ mod register;
 mod storage;
 
 use anyhow::Result;
-use env_logger::Env;
 use log::info;
 use tokio::signal::{
     self,
@@ -14,18 +15,28 @@ use tokio::signal::{
 };
 
 use crate::{
-    epoch_tracker::EpochTracker, prometheus::PrometheusExporter,
-    storage::epoch_storage::EpochStorage,
+    api::ApiServer, configuration::Configuration, epoch_tracker::EpochTracker,
+    prometheus::PrometheusExporter, register::Register, storage::epoch_storage::EpochStorage,
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    // The register reads the configuration on its first use, so its problems are reported here
+    if let Err(err) = Configuration::new() {
+        indexer_errors::exit_on_configuration_error(err);
+    }
+
+    indexer_telemetry::init_logger(
+        "epoch_tracker",
+        Register::current().configuration.log_format(),
+        "info",
+    );
     info!("Starting epoch_rewards_tracker");
 
     EpochStorage::run_migrations().await?;
     EpochTracker::run().await?;
     PrometheusExporter::run().await?;
+    ApiServer::run().await?;
 
     wait_termination().await;
 
@@ -1,14 +1,38 @@
 use anyhow::Result;
+use clap::crate_version;
 use hyper::{
     header::CONTENT_TYPE,
     service::{make_service_fn, service_fn},
     Body, Response, Server,
 };
+use lazy_static::lazy_static;
 use log::{error, info};
-use prometheus::{Encoder, TextEncoder};
+use prometheus::{
+    core::Collector, register_int_counter_vec_with_registry, Encoder, IntCounterVec, IntGaugeVec,
+    Registry, TextEncoder,
+};
 
 use crate::register::Register;
 
+lazy_static! {
+    /// Registry of the metrics of the service
+    pub static ref REGISTRY: Registry = indexer_telemetry::service_registry("epoch_tracker");
+    pub static ref BUILD_INFO: IntGaugeVec =
+        indexer_telemetry::register_build_info("et", crate_version!(), &REGISTRY).unwrap();
+    pub static ref ERRORS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "et_errors_total",
+        "Failed RPC requests and backfills per error class",
+        &["class"],
+        REGISTRY
+    )
+    .unwrap();
+}
+
+/// Every metric of the service, so they are exported from the start instead of their first use
+pub fn metrics() -> Vec<&'static dyn Collector> {
+    vec![&*BUILD_INFO, &*ERRORS_COUNT]
+}
+
 pub struct PrometheusExporter {}
 
 impl PrometheusExporter {
@@ -19,6 +43,8 @@ impl PrometheusExporter {
             .parse()
             .unwrap();
 
+        metrics();
+
         tokio::spawn(async move {
             info!("Prometheus exporter started on http://{}", &addr);
 
@@ -26,7 +52,7 @@ impl PrometheusExporter {
                 .serve(make_service_fn(|_| async {
                     Ok::<_, hyper::Error>(service_fn(|_| async {
                         let encoder = TextEncoder::new();
-                        let metric_families = prometheus::gather();
+                        let metric_families = Register::current().registry.gather();
                         let mut buffer = Vec::new();
 
                         encoder.encode(&metric_families, &mut buffer).unwrap();
@@ -49,3 +75,46 @@ impl PrometheusExporter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::proto::MetricType;
+
+    /// `# HELP` and `# TYPE` lines of the metrics in the /metrics output, where the metrics with
+    /// labels appear after their first samples
+    fn descriptions() -> String {
+        let mut families: Vec<_> = metrics()
+            .iter()
+            .flat_map(|metric| metric.collect())
+            .collect();
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        families
+            .iter()
+            .map(|family| {
+                let metric_type = match family.get_field_type() {
+                    MetricType::COUNTER => "counter",
+                    MetricType::GAUGE => "gauge",
+                    MetricType::HISTOGRAM => "histogram",
+                    MetricType::SUMMARY => "summary",
+                    MetricType::UNTYPED => "untyped",
+                };
+
+                format!(
+                    "# HELP {name} {}\n# TYPE {name} {}\n",
+                    family.get_help(),
+                    metric_type,
+                    name = family.get_name()
+                )
+            })
+            .collect()
+    }
+
+    /// The dashboards and the alerts refer to the metrics by their names, a renamed metric has
+    /// to be renamed in `metrics.golden` as well
+    #[test]
+    fn metrics_are_not_renamed() {
+        assert_eq!(descriptions(), include_str!("metrics.golden"));
+    }
+}
@@ -1,9 +1,13 @@
 use crate::configuration::*;
+use crate::prometheus::REGISTRY;
+use prometheus::Registry;
 use std::sync::{Arc, RwLock};
 
 #[derive(Default)]
 pub struct Register {
     pub configuration: Configuration,
+    /// Registry of the metrics exported by the service
+    pub registry: Registry,
 }
 impl Register {
     pub fn current() -> Arc<Register> {
@@ -12,5 +16,5 @@ impl Register {
 }
 
 thread_local! {
-    static CURRENT_REGISTER:RwLock<Arc<Register>> = RwLock::new(Arc::new(Register { configuration: Configuration::new().unwrap() }))
+    static CURRENT_REGISTER:RwLock<Arc<Register>> = RwLock::new(Arc::new(Register { configuration: Configuration::new().unwrap(), registry: REGISTRY.clone() }))
 }
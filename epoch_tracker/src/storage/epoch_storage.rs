@@ -1,4 +1,6 @@
+use clickhouse_storage::PostgresMigrations;
 use log::{debug, info};
+use serde::Serialize;
 use serde_json::json;
 use solana_sdk::{
     account::Account,
@@ -9,12 +11,10 @@ use solana_sdk::{
     stake::state::StakeState,
 };
 use solana_transaction_status::EncodedConfirmedBlock;
-use tokio_postgres::{Client, NoTls};
+use tokio_postgres::{Client, NoTls, Row};
 
 use crate::{errors::EpochStorageError, register::Register};
 
-use super::migrations::Migration;
-
 const SCRIPTS_UP: [(&str, &str); 2] = [
     (
         "00000000000000_initial_setup",
@@ -26,13 +26,39 @@ const SCRIPTS_UP: [(&str, &str); 2] = [
     ),
 ];
 
+const EPOCH_BOUNDARIES_COLUMNS: &str =
+    "epoch, first_slot, last_slot, (first_block_json->>'blockTime')::BIGINT";
+
+/// Slot range of an epoch as it is exposed by the API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpochBoundaries {
+    pub epoch: Epoch,
+    pub first_slot: Option<Slot>,
+    pub last_slot: Option<Slot>,
+    pub first_block_time: Option<i64>,
+}
+
+impl From<&Row> for EpochBoundaries {
+    fn from(row: &Row) -> Self {
+        let epoch: i32 = row.get(0);
+        let first_slot: Option<i32> = row.get(1);
+        let last_slot: Option<i32> = row.get(2);
+
+        Self {
+            epoch: epoch as Epoch,
+            first_slot: first_slot.map(|first_slot| first_slot as Slot),
+            last_slot: last_slot.map(|last_slot| last_slot as Slot),
+            first_block_time: row.get(3),
+        }
+    }
+}
+
 pub struct EpochStorage {}
 
 impl EpochStorage {
     pub async fn run_migrations() -> Result<(), EpochStorageError> {
-        let migration = Migration::new();
         let mut client = Self::connect().await?;
-        migration.up(&mut client, &SCRIPTS_UP).await?;
+        PostgresMigrations::new(&SCRIPTS_UP).up(&mut client).await?;
         Ok(())
     }
 
@@ -91,6 +117,58 @@ impl EpochStorage {
         }
     }
 
+    /// Returns the latest epoch known to the tracker
+    pub async fn get_current_epoch() -> Result<Option<EpochBoundaries>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(&format!(
+                "SELECT {} FROM epochs ORDER BY epoch DESC LIMIT 1",
+                EPOCH_BOUNDARIES_COLUMNS
+            ))
+            .await?;
+
+        let response = client.query_opt(&stmt, &[]).await?;
+
+        Ok(response.as_ref().map(EpochBoundaries::from))
+    }
+
+    pub async fn get_epoch(epoch: Epoch) -> Result<Option<EpochBoundaries>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(&format!(
+                "SELECT {} FROM epochs WHERE epoch = $1",
+                EPOCH_BOUNDARIES_COLUMNS
+            ))
+            .await?;
+
+        let response = client.query_opt(&stmt, &[&(epoch as i32)]).await?;
+
+        Ok(response.as_ref().map(EpochBoundaries::from))
+    }
+
+    /// Returns epochs from the `from..=to` range ordered by epoch number
+    pub async fn get_epochs(
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<EpochBoundaries>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(&format!(
+                "SELECT {} FROM epochs WHERE epoch >= $1 AND epoch <= $2 ORDER BY epoch",
+                EPOCH_BOUNDARIES_COLUMNS
+            ))
+            .await?;
+
+        let from = from.min(i32::MAX as Epoch) as i32;
+        let to = to.min(i32::MAX as Epoch) as i32;
+        let response = client.query(&stmt, &[&from, &to]).await?;
+
+        Ok(response.iter().map(EpochBoundaries::from).collect())
+    }
+
     pub async fn get_epoch_with_empty_first_block() -> Result<Vec<Epoch>, EpochStorageError> {
         debug!("Trying to retrieve the list of the Epoch with empty first_block field");
 
@@ -117,7 +195,12 @@ impl EpochStorage {
         first_block: Slot,
         first_block_raw: &Option<EncodedConfirmedBlock>,
     ) -> Result<(), EpochStorageError> {
-        info!("Trying to update first_block field for {:?} epoch", epoch);
+        info!(
+            actor = "epoch_storage",
+            epoch = epoch;
+            "Trying to update first_block field for {:?} epoch",
+            epoch
+        );
 
         let client = Self::connect().await?;
 
@@ -146,6 +229,66 @@ impl EpochStorage {
         Ok(())
     }
 
+    /// Returns epochs from the `from..=to` range which already have the first block
+    pub async fn get_epochs_with_first_block(
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<Vec<Epoch>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare("SELECT epoch FROM epochs WHERE first_block IS NOT NULL AND epoch >= $1 AND epoch <= $2")
+            .await?;
+
+        let from = from.min(i32::MAX as Epoch) as i32;
+        let to = to.min(i32::MAX as Epoch) as i32;
+        let response = client.query(&stmt, &[&from, &to]).await?;
+
+        Ok(response
+            .iter()
+            .map(|row| row.get::<_, i32>(0) as Epoch)
+            .collect())
+    }
+
+    /// Stores a backfilled epoch, the first block of an already known epoch is overwritten
+    pub async fn store_epoch_with_first_block(
+        epoch: Epoch,
+        first_slot: Slot,
+        last_slot: Slot,
+        first_block: Slot,
+        first_block_raw: &EncodedConfirmedBlock,
+    ) -> Result<(), EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let first_block_json = json!(first_block_raw);
+        let first_block_raw = serde_json::to_string(first_block_raw).unwrap();
+
+        let stmt = client
+            .prepare(
+                "INSERT INTO epochs (epoch, first_slot, last_slot, first_block, first_block_raw, first_block_json) \
+                VALUES ($1, $2, $3, $4, $5, $6) \
+                ON CONFLICT (epoch) DO UPDATE SET first_block = EXCLUDED.first_block, \
+                first_block_raw = EXCLUDED.first_block_raw, first_block_json = EXCLUDED.first_block_json",
+            )
+            .await?;
+
+        let _ = client
+            .execute(
+                &stmt,
+                &[
+                    &(epoch as i32),
+                    &(first_slot as i32),
+                    &(last_slot as i32),
+                    &(first_block as i32),
+                    &first_block_raw,
+                    &first_block_json,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_epoch_with_empty_last_block() -> Result<Vec<Epoch>, EpochStorageError> {
         debug!("Trying to retrieve the list of the Epoch with empty last_block field");
 
@@ -172,7 +315,12 @@ impl EpochStorage {
         last_block: Slot,
         last_block_raw: &Option<EncodedConfirmedBlock>,
     ) -> Result<(), EpochStorageError> {
-        info!("Trying to update last_block field for {:?} epoch", epoch);
+        info!(
+            actor = "epoch_storage",
+            epoch = epoch;
+            "Trying to update last_block field for {:?} epoch",
+            epoch
+        );
 
         let client = Self::connect().await?;
 
@@ -217,7 +365,12 @@ impl EpochStorage {
         epoch: Epoch,
         all_stake_accounts: &Vec<(Pubkey, Account)>,
     ) -> Result<(), EpochStorageError> {
-        info!("Trying to update stakes field for {:?} epoch", epoch);
+        info!(
+            actor = "epoch_storage",
+            epoch = epoch;
+            "Trying to update stakes field for {:?} epoch",
+            epoch
+        );
 
         let mut stakes = Vec::new();
 
@@ -1,2 +1 @@
 pub(crate) mod epoch_storage;
-pub(crate) mod migrations;
@@ -0,0 +1,301 @@
+//! Error classes shared by the services, so the errors of any of them are alerted on the same
+//! way.
+//!
+//! A service error implements `Classify` and is converted into `IndexerError` with its message
+//! kept as is.
+
+use std::fmt;
+use std::net::SocketAddr;
+
+use thiserror::Error;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// What an error means for the operator, the alerts are routed by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Lost connection or unavailable actor, retrying may succeed
+    Transient,
+    /// Stored or received data which can't be decoded
+    DataCorruption,
+    /// Fixed only by changing the configuration
+    Configuration,
+    /// Failure of the RPC node or another external service
+    External,
+}
+
+impl ErrorClass {
+    /// Value of the `class` label of the error metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Transient => "transient",
+            ErrorClass::DataCorruption => "data_corruption",
+            ErrorClass::Configuration => "configuration",
+            ErrorClass::External => "external",
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub trait Classify {
+    fn classify(&self) -> ErrorClass;
+}
+
+/// Error of any service. The shared kinds are variants of their own, the errors of a service
+/// are wrapped by `Service` with their class and keep their messages
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("Failed to connect to the storage: {0}")]
+    StorageConnection(#[source] BoxError),
+
+    #[error("Failed to deserialize: {0}")]
+    Deserialization(#[source] BoxError),
+
+    #[error("Unknown protocol: {0}")]
+    UnknownProtocol(String),
+
+    #[error("{source}")]
+    Service {
+        class: ErrorClass,
+        #[source]
+        source: BoxError,
+    },
+}
+
+impl IndexerError {
+    pub fn new(class: ErrorClass, source: impl Into<BoxError>) -> Self {
+        IndexerError::Service {
+            class,
+            source: source.into(),
+        }
+    }
+}
+
+impl Classify for IndexerError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            IndexerError::StorageConnection(_) => ErrorClass::Transient,
+            IndexerError::Deserialization(_) => ErrorClass::DataCorruption,
+            IndexerError::UnknownProtocol(_) => ErrorClass::Configuration,
+            IndexerError::Service { class, .. } => *class,
+        }
+    }
+}
+
+/// Class of the first error of the `source` chain which is known to `classify`, the errors
+/// which are not are `Transient` since the services retry them
+pub fn classify_chain(
+    err: &(dyn std::error::Error + 'static),
+    classify: impl Fn(&(dyn std::error::Error + 'static)) -> Option<ErrorClass>,
+) -> ErrorClass {
+    let mut next = Some(err);
+
+    while let Some(err) = next {
+        if let Some(err) = err.downcast_ref::<IndexerError>() {
+            return err.classify();
+        }
+        if let Some(class) = classify(err) {
+            return class;
+        }
+        next = err.source();
+    }
+
+    ErrorClass::Transient
+}
+
+/// Converts the errors implementing `Classify` into `IndexerError`
+#[macro_export]
+macro_rules! impl_into_indexer_error {
+    ( $( $error:ty ),+ $(,)? ) => {
+        $(
+            impl From<$error> for $crate::IndexerError {
+                fn from(err: $error) -> Self {
+                    let class = $crate::Classify::classify(&err);
+                    $crate::IndexerError::new(class, err)
+                }
+            }
+        )+
+    };
+}
+
+/// Exit code of a service started with an invalid configuration
+pub const CONFIGURATION_EXIT_CODE: i32 = 2;
+
+/// Problems of a configuration found by the validation of a service. All of them are collected,
+/// so a deployment is fixed with one change rather than a restart per problem
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigurationErrors(Vec<String>);
+
+impl ConfigurationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, problem: impl Into<String>) {
+        self.0.push(problem.into());
+    }
+
+    /// Adds the `problem` unless the check is `ok`
+    pub fn check(&mut self, ok: bool, problem: impl FnOnce() -> String) {
+        if !ok {
+            self.push(problem());
+        }
+    }
+
+    /// Checks that the `url` of the `field` has one of the `schemes` and something after it
+    pub fn check_url(&mut self, field: &str, url: &str, schemes: &[&str]) {
+        match url.split_once("://") {
+            Some((scheme, rest)) if schemes.contains(&scheme) && !rest.is_empty() => {}
+            Some((scheme, _)) if !schemes.contains(&scheme) => self.push(format!(
+                "{} has the {} scheme, expected one of {}: {}",
+                field,
+                scheme,
+                schemes.join(", "),
+                url
+            )),
+            _ => self.push(format!(
+                "{} is not a url of the form {}://...: {:?}",
+                field,
+                schemes.join("|"),
+                url
+            )),
+        }
+    }
+
+    /// Checks that the `address` of the `field` is an `ip:port` address to bind to
+    pub fn check_bind_address(&mut self, field: &str, address: &str) {
+        if let Err(err) = address.parse::<SocketAddr>() {
+            self.push(format!(
+                "{} is not an ip:port address: {:?} ({})",
+                field, address, err
+            ));
+        }
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ConfigurationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration, {} problem(s):", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigurationErrors {}
+
+impl Classify for ConfigurationErrors {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::Configuration
+    }
+}
+
+/// Prints the error of loading the configuration and exits with `CONFIGURATION_EXIT_CODE`, the
+/// services call it before anything is started
+pub fn exit_on_configuration_error(err: impl fmt::Display) -> ! {
+    eprintln!("{:#}", err);
+    std::process::exit(CONFIGURATION_EXIT_CODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Error)]
+    enum ServiceError {
+        #[error("RPC returned an error: {0}")]
+        Response(String),
+
+        #[error("Inner error {0}")]
+        Inner(#[from] IndexerError),
+    }
+
+    impl Classify for ServiceError {
+        fn classify(&self) -> ErrorClass {
+            match self {
+                ServiceError::Response(_) => ErrorClass::External,
+                ServiceError::Inner(err) => err.classify(),
+            }
+        }
+    }
+
+    impl_into_indexer_error!(ServiceError);
+
+    #[test]
+    fn service_error_keeps_its_message() {
+        let err = IndexerError::from(ServiceError::Response("slot skipped".to_string()));
+
+        assert_eq!(err.to_string(), "RPC returned an error: slot skipped");
+        assert_eq!(err.classify(), ErrorClass::External);
+    }
+
+    #[test]
+    fn wrapped_error_has_class_of_the_inner_one() {
+        let err = IndexerError::from(ServiceError::Inner(IndexerError::UnknownProtocol(
+            "ftp".to_string(),
+        )));
+
+        assert_eq!(err.to_string(), "Inner error Unknown protocol: ftp");
+        assert_eq!(err.classify(), ErrorClass::Configuration);
+    }
+
+    #[test]
+    fn chain_is_classified_by_the_first_known_error() {
+        let err = ServiceError::Inner(IndexerError::Deserialization("bad json".into()));
+
+        assert_eq!(classify_chain(&err, |_| None), ErrorClass::DataCorruption);
+        assert_eq!(
+            classify_chain(&err, |err| err
+                .downcast_ref::<ServiceError>()
+                .map(|_| ErrorClass::External)),
+            ErrorClass::External
+        );
+        assert_eq!(
+            classify_chain(&ServiceError::Response(String::new()), |_| None),
+            ErrorClass::Transient
+        );
+    }
+
+    #[test]
+    fn every_configuration_problem_is_listed() {
+        let mut errors = ConfigurationErrors::new();
+        errors.check_url(
+            "endpoint.url",
+            "https://api.devnet.solana.com",
+            &["http", "https"],
+        );
+        errors.check_bind_address("prometheus_exporter.bind_address", "127.0.0.1:9898");
+        assert_eq!(errors.clone().into_result(), Ok(()));
+
+        errors.check_url("endpoint.url", "ftp://localhost", &["http", "https"]);
+        errors.check_url("endpoint.url", "localhost:8899", &["http", "https"]);
+        errors.check_bind_address("prometheus_exporter.bind_address", "localhost");
+        errors.check(false, || "number_of_threads must be positive".to_string());
+
+        assert_eq!(errors.problems().len(), 4);
+        assert!(errors.problems()[0].contains("ftp scheme"));
+        assert_eq!(errors.classify(), ErrorClass::Configuration);
+        assert!(errors
+            .to_string()
+            .starts_with("Invalid configuration, 4 problem(s):\n  - endpoint.url"));
+    }
+}
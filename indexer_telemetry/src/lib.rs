@@ -0,0 +1,8 @@
+//! Logging and metrics setup shared by the services, so the records and the metrics of all of
+//! them are written and collected the same way.
+
+pub mod logging;
+pub mod metrics;
+
+pub use logging::{init_logger, LogFormat};
+pub use metrics::{register_build_info, service_registry, GIT_SHA};
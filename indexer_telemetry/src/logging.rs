@@ -0,0 +1,131 @@
+use env_logger::Env;
+use log::kv::{self, Key, Value, VisitSource};
+use log::Record;
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use std::io::Write;
+
+/// `format` of the `[logging]` section of the service configs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Text records are the plain env_logger ones. JSON records are written one per line with the
+/// `service` field, the key-values passed to the log macros (actor, program, tx_signature,
+/// epoch) become top-level fields, so a single transaction or epoch can be traced by them.
+/// `default_filter` applies when `RUST_LOG` is not set
+pub fn init_logger(service: &'static str, format: LogFormat, default_filter: &str) {
+    let mut builder =
+        env_logger::Builder::from_env(Env::default().default_filter_or(default_filter));
+
+    if format == LogFormat::Json {
+        builder.format(move |buf, record| {
+            let mut fields = json_fields(service, record);
+            fields.insert(
+                "timestamp".to_string(),
+                buf.timestamp_millis().to_string().into(),
+            );
+
+            writeln!(buf, "{}", JsonValue::Object(fields))
+        });
+    }
+
+    builder.init();
+}
+
+fn json_fields(service: &str, record: &Record) -> Map<String, JsonValue> {
+    let mut fields = Map::new();
+    fields.insert("level".to_string(), record.level().as_str().into());
+    fields.insert("service".to_string(), service.into());
+    fields.insert("target".to_string(), record.target().into());
+    fields.insert("message".to_string(), record.args().to_string().into());
+
+    // JsonFields never fails
+    let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+
+    fields
+}
+
+struct JsonFields<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = match value.to_u64() {
+            Some(number) => number.into(),
+            None => value.to_string().into(),
+        };
+        self.0.insert(key.to_string(), value);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn json_record() {
+        let key_values = [
+            ("actor", "transaction_parser"),
+            ("tx_signature", "3gDkTVue"),
+        ];
+
+        let fields = json_fields(
+            "data_analyzer",
+            &Record::builder()
+                .args(format_args!("Transaction parsed"))
+                .level(Level::Info)
+                .target("instructions_data_analyzer")
+                .key_values(&key_values)
+                .build(),
+        );
+
+        assert_eq!(
+            JsonValue::Object(fields),
+            serde_json::json!({
+                "level": "INFO",
+                "service": "data_analyzer",
+                "target": "instructions_data_analyzer",
+                "message": "Transaction parsed",
+                "actor": "transaction_parser",
+                "tx_signature": "3gDkTVue"
+            })
+        );
+    }
+
+    #[test]
+    fn numeric_values_are_json_numbers() {
+        let key_values = [
+            ("actor", Value::from("rewards_worker")),
+            ("epoch", Value::from(420u64)),
+        ];
+
+        let fields = json_fields(
+            "rewards_analyzer",
+            &Record::builder()
+                .args(format_args!("Epoch analyzed"))
+                .level(Level::Info)
+                .target("rewards_analyzer")
+                .key_values(&key_values)
+                .build(),
+        );
+
+        assert_eq!(
+            JsonValue::Object(fields),
+            serde_json::json!({
+                "level": "INFO",
+                "service": "rewards_analyzer",
+                "target": "rewards_analyzer",
+                "message": "Epoch analyzed",
+                "actor": "rewards_worker",
+                "epoch": 420
+            })
+        );
+    }
+}
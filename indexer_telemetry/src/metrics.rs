@@ -0,0 +1,84 @@
+use prometheus::{IntGaugeVec, Opts, Registry};
+use std::collections::HashMap;
+
+/// Commit the service is built from, the `GIT_SHA` build argument of the Docker image
+pub const GIT_SHA: &str = match option_env!("GIT_SHA") {
+    Some(git_sha) => git_sha,
+    None => "unknown",
+};
+
+/// Registry of the metrics of a service, the samples are labelled with the service so the
+/// metrics of all the services can be scraped into one Prometheus. The process metrics are
+/// exported on Linux
+pub fn service_registry(service: &str) -> Registry {
+    let registry = Registry::new_custom(
+        None,
+        Some(HashMap::from([(
+            "service".to_string(),
+            service.to_string(),
+        )])),
+    )
+    .unwrap();
+
+    #[cfg(target_os = "linux")]
+    registry
+        .register(Box::new(
+            prometheus::process_collector::ProcessCollector::for_self(),
+        ))
+        .unwrap();
+
+    registry
+}
+
+/// `<prefix>_build_info` of the service with its only sample, labelled with the `version` of the
+/// service and `GIT_SHA`
+pub fn register_build_info(
+    prefix: &str,
+    version: &str,
+    registry: &Registry,
+) -> prometheus::Result<IntGaugeVec> {
+    let build_info = IntGaugeVec::new(
+        Opts::new(
+            format!("{}_build_info", prefix),
+            "Version and git commit the service is built from, always 1",
+        ),
+        &["version", "git_sha"],
+    )?;
+    registry.register(Box::new(build_info.clone()))?;
+    build_info.with_label_values(&[version, GIT_SHA]).set(1);
+
+    Ok(build_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_info_is_labelled_with_the_service() {
+        let registry = service_registry("data_loader");
+        register_build_info("dl", "0.1.0", &registry).unwrap();
+
+        let families = registry.gather();
+        let build_info = families
+            .iter()
+            .find(|family| family.get_name() == "dl_build_info")
+            .unwrap();
+        let mut labels: Vec<_> = build_info.get_metric()[0]
+            .get_label()
+            .iter()
+            .map(|label| (label.get_name(), label.get_value()))
+            .collect();
+        labels.sort();
+
+        assert_eq!(
+            labels,
+            [
+                ("git_sha", GIT_SHA),
+                ("service", "data_loader"),
+                ("version", "0.1.0")
+            ]
+        );
+        assert_eq!(build_info.get_metric()[0].get_gauge().get_value(), 1.0);
+    }
+}
@@ -0,0 +1,78 @@
+//! Routing of the signatures over the databases of a sharded queue storage. A signature, the
+//! rows of every program it's queued for and its transaction are kept in one shard, so the
+//! data_loader claims and stores it and the data_analyzer claims and marks it in the same
+//! database. Both route by this crate, so they can't route differently.
+
+use sha2::{Digest, Sha256};
+
+/// Shard of the signature among `shards` ones: the first 8 bytes of the SHA-256 of the signature
+/// read as a little endian number, modulo the number of the shards
+pub fn shard_of(signature: &str, shards: usize) -> usize {
+    if shards <= 1 {
+        return 0;
+    }
+
+    let digest = Sha256::digest(signature.as_bytes());
+    let prefix = u64::from_le_bytes(digest[..8].try_into().unwrap());
+
+    (prefix % shards as u64) as usize
+}
+
+/// Splits the items by the shards of their keys, the order of the items is kept within a shard
+pub fn group_by_shard<T>(
+    items: impl IntoIterator<Item = T>,
+    shards: usize,
+    key: impl Fn(&T) -> &str,
+) -> Vec<Vec<T>> {
+    let mut groups: Vec<Vec<T>> = (0..shards.max(1)).map(|_| Vec::new()).collect();
+
+    for item in items {
+        let shard = shard_of(key(&item), shards);
+        groups[shard].push(item);
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The shards of the deployed queues were filled by these routes, changing them strands the
+    /// queued signatures in the wrong shards
+    #[test]
+    fn signatures_are_routed_as_before() {
+        assert_eq!(shard_of("signature_0", 2), 1);
+        assert_eq!(shard_of("signature_1", 2), 0);
+        assert_eq!(shard_of("signature_3", 2), 1);
+        assert_eq!(shard_of("signature_0", 3), 2);
+        assert_eq!(shard_of("signature_3", 3), 0);
+    }
+
+    #[test]
+    fn single_database_is_the_only_shard() {
+        assert_eq!(shard_of("signature_0", 1), 0);
+        assert_eq!(shard_of("signature_0", 0), 0);
+    }
+
+    #[test]
+    fn signatures_are_grouped_by_their_shards() {
+        let signatures = ["signature_0", "signature_1", "signature_2", "signature_3"];
+
+        let groups = group_by_shard(signatures, 2, |signature| *signature);
+
+        assert_eq!(
+            groups,
+            vec![
+                vec!["signature_1", "signature_2"],
+                vec!["signature_0", "signature_3"]
+            ]
+        );
+
+        for (shard, group) in groups.iter().enumerate() {
+            for signature in group {
+                assert_eq!(shard_of(signature, 2), shard);
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Rewards and commissions of an epoch stored into ClickHouse started in Docker and migrated the
+//! way the service does it
+
+use crate::storage::main_storage::{migrations::SCRIPTS_UP, MainStorage};
+use anyhow::{anyhow, Result};
+use clickhouse_storage::Migrations;
+use integration_tests::{retry, Clickhouse};
+use solana_transaction_status::{Reward, RewardType};
+
+#[tokio::test]
+async fn voting_rewards_and_commissions_are_stored() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+
+    let mut storage: Box<dyn MainStorage> =
+        clickhouse_storage::connect(&clickhouse.tcp_url()).await?;
+    Migrations::new(&SCRIPTS_UP).up(storage.as_mut()).await?;
+
+    let reward = Reward {
+        pubkey: String::from("vote_1"),
+        lamports: 100,
+        post_balance: 1000,
+        reward_type: Some(RewardType::Voting),
+        commission: Some(7),
+    };
+
+    storage
+        .store_rewards_block(
+            vec![(String::new(), 301, Some(300), reward, 1643213404, None)],
+            false,
+        )
+        .await?;
+    storage
+        .store_commissions_block(vec![(String::from("vote_1"), 301, Some(7))])
+        .await?;
+
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+    let (rewards, commissions) = retry(|| async {
+        let mut client = pool.get_handle().await?;
+        let rewards = client
+            .query("SELECT epoch, pubkey, reward_type FROM rewards")
+            .fetch_all()
+            .await?;
+        let commissions = client
+            .query("SELECT epoch, vote_account, commission FROM vote_account_commissions")
+            .fetch_all()
+            .await?;
+
+        if rewards.row_count() == 0 || commissions.row_count() == 0 {
+            return Err(anyhow!("The rows are not stored yet"));
+        }
+
+        let rewards = rewards
+            .rows()
+            .map(|row| {
+                Ok((
+                    row.get::<u64, _>("epoch")?,
+                    row.get::<String, _>("pubkey")?,
+                    row.get::<Option<String>, _>("reward_type")?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let commissions = commissions
+            .rows()
+            .map(|row| {
+                Ok((
+                    row.get::<u64, _>("epoch")?,
+                    row.get::<String, _>("vote_account")?,
+                    row.get::<Option<u8>, _>("commission")?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((rewards, commissions))
+    })
+    .await?;
+
+    // Rewards are paid in the first block of the next epoch
+    assert_eq!(
+        rewards,
+        [(300, String::from("vote_1"), Some(String::from("voting")))]
+    );
+    assert_eq!(commissions, [(300, String::from("vote_1"), Some(7))]);
+
+    Ok(())
+}
+
+/// A storage with a table prefix writes and reads its own tables, the bare ones migrated next
+/// to them stay empty
+#[tokio::test]
+async fn prefixed_tables_are_isolated() -> Result<()> {
+    let clickhouse = Clickhouse::start().await?;
+
+    let mut production: Box<dyn MainStorage> =
+        clickhouse_storage::connect(&clickhouse.tcp_url()).await?;
+    Migrations::new(&SCRIPTS_UP).up(production.as_mut()).await?;
+
+    let mut staging: Box<dyn MainStorage> =
+        clickhouse_storage::connect(&clickhouse.http_url()).await?;
+    staging.set_table_prefix("staging_");
+    Migrations::new(&SCRIPTS_UP)
+        .with_table_prefix("staging_")
+        .up(staging.as_mut())
+        .await?;
+
+    let reward = Reward {
+        pubkey: String::from("stake_1"),
+        lamports: 100,
+        post_balance: 1000,
+        reward_type: Some(RewardType::Staking),
+        commission: None,
+    };
+    staging
+        .store_rewards_block(
+            vec![(String::new(), 301, Some(300), reward, 1643213404, None)],
+            false,
+        )
+        .await?;
+
+    // The HTTP insert returns after the rows are written
+    let staged = staging.get_rewards_with_empty_vote_acc(0, "", 10).await?;
+    assert_eq!(staged.len(), 1);
+    assert_eq!(staged[0].pubkey, "stake_1");
+
+    assert!(production
+        .get_rewards_with_empty_vote_acc(0, "", 10)
+        .await?
+        .is_empty());
+
+    let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+    let block = pool
+        .get_handle()
+        .await?
+        .query("SELECT count() AS count FROM staging_rewards")
+        .fetch_all()
+        .await?;
+    let count: u64 = block
+        .rows()
+        .next()
+        .ok_or_else(|| anyhow!("No count"))?
+        .get("count")?;
+    assert_eq!(count, 1);
+
+    Ok(())
+}
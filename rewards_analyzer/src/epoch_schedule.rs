@@ -0,0 +1,97 @@
+use serde::Deserialize;
+
+use crate::storage::epoch_storage::Epoch;
+
+/// Length of the first epoch of a cluster with warmup, the following ones double up to
+/// `slots_per_epoch`
+const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+/// Epoch schedule of the cluster as `getEpochSchedule` returns it, the boundaries are computed
+/// the same way as by `solana_sdk::epoch_schedule::EpochSchedule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochSchedule {
+    pub slots_per_epoch: u64,
+    /// The first epoch of `slots_per_epoch` slots, 0 for a cluster without warmup
+    pub first_normal_epoch: Epoch,
+    pub first_normal_slot: u64,
+}
+
+impl EpochSchedule {
+    /// Epoch of the slot
+    pub fn epoch_of(&self, slot: u64) -> Epoch {
+        if slot < self.first_normal_slot {
+            let epoch = (slot + MINIMUM_SLOTS_PER_EPOCH + 1)
+                .next_power_of_two()
+                .trailing_zeros()
+                - MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()
+                - 1;
+
+            epoch as Epoch
+        } else {
+            self.first_normal_epoch + (slot - self.first_normal_slot) / self.slots_per_epoch
+        }
+    }
+
+    pub fn first_slot(&self, epoch: Epoch) -> u64 {
+        if epoch <= self.first_normal_epoch {
+            (2_u64.pow(epoch as u32) - 1) * MINIMUM_SLOTS_PER_EPOCH
+        } else {
+            (epoch - self.first_normal_epoch) * self.slots_per_epoch + self.first_normal_slot
+        }
+    }
+
+    pub fn last_slot(&self, epoch: Epoch) -> u64 {
+        self.first_slot(epoch + 1) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET: EpochSchedule = EpochSchedule {
+        slots_per_epoch: 432000,
+        first_normal_epoch: 0,
+        first_normal_slot: 0,
+    };
+
+    /// 8192 slots per epoch with warmup
+    const DEVNET: EpochSchedule = EpochSchedule {
+        slots_per_epoch: 8192,
+        first_normal_epoch: 8,
+        first_normal_slot: 8160,
+    };
+
+    #[test]
+    fn epoch_of_slot() {
+        assert_eq!(MAINNET.epoch_of(0), 0);
+        assert_eq!(MAINNET.epoch_of(249_695_999), 577);
+        assert_eq!(MAINNET.epoch_of(249_696_000), 578);
+
+        assert_eq!(DEVNET.epoch_of(31), 0);
+        assert_eq!(DEVNET.epoch_of(32), 1);
+        assert_eq!(DEVNET.epoch_of(95), 1);
+        assert_eq!(DEVNET.epoch_of(96), 2);
+        assert_eq!(DEVNET.epoch_of(8159), 7);
+        assert_eq!(DEVNET.epoch_of(8160), 8);
+        assert_eq!(DEVNET.epoch_of(8160 + 8192), 9);
+    }
+
+    #[test]
+    fn slots_of_epoch() {
+        assert_eq!(MAINNET.first_slot(578), 249_696_000);
+        assert_eq!(MAINNET.last_slot(578), 250_127_999);
+
+        assert_eq!(DEVNET.first_slot(1), 32);
+        assert_eq!(DEVNET.last_slot(1), 95);
+        assert_eq!(DEVNET.first_slot(8), 8160);
+        assert_eq!(DEVNET.last_slot(7), 8159);
+        assert_eq!(DEVNET.first_slot(9), 8160 + 8192);
+
+        for epoch in 0..20 {
+            assert_eq!(DEVNET.epoch_of(DEVNET.first_slot(epoch)), epoch);
+            assert_eq!(DEVNET.epoch_of(DEVNET.last_slot(epoch)), epoch);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use indexer_errors::{impl_into_indexer_error, Classify, ErrorClass};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,9 +8,6 @@ pub enum RewardsAnalyzerError {
 
     #[error("MainStorage error {0}")]
     MainStorage(#[from] MainStorageError),
-
-    #[error("RewardsCollector error {0}")]
-    RewardsCollector(#[from] RewardsCollectorError),
 }
 
 #[derive(Error, Debug)]
@@ -29,8 +27,8 @@ pub enum EpochStorageError {
 
 #[derive(Debug, Error)]
 pub enum MainStorageError {
-    #[error("Unknown protocol")]
-    UnknownProtocol,
+    #[error("Storage error: {0}")]
+    Storage(#[from] clickhouse_storage::StorageError),
     #[error("Failed to connect to Main Storage: {0} ")]
     ClickhouseError(#[from] clickhouse_rs::errors::Error),
     #[error("Clickhouse HTTP error: {0} ")]
@@ -38,19 +36,142 @@ pub enum MainStorageError {
 }
 
 #[derive(Debug, Error)]
-pub enum RewardsCollectorError {
+pub enum DelegationsCollectorError {
     #[error("MainStorage error {0}")]
     MainStorage(#[from] MainStorageError),
 }
 
-#[derive(Debug, Error)]
-pub enum DelegationsCollectorError {
+#[derive(Error, Debug)]
+pub enum VoteAccountResolverError {
     #[error("MainStorage error {0}")]
     MainStorage(#[from] MainStorageError),
 }
 
 #[derive(Error, Debug)]
-pub enum VoteAccountResolverError {
+pub enum RpcError {
+    #[error("Failed to send RPC request: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("RPC returned an error: {0}")]
+    Response(String),
+
+    #[error("Failed to parse RPC result: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ProvisionalEpochsError {
+    #[error("RPC error {0}")]
+    Rpc(#[from] RpcError),
+
+    #[error("Failed to retrieve an epoch: {0}")]
+    EpochStorage(#[from] EpochStorageError),
+
     #[error("MainStorage error {0}")]
     MainStorage(#[from] MainStorageError),
 }
+
+impl Classify for RewardsAnalyzerError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            RewardsAnalyzerError::EpochStorage(err) => err.classify(),
+            RewardsAnalyzerError::MainStorage(err) => err.classify(),
+        }
+    }
+}
+
+impl Classify for DelegationsAnalyzerError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            DelegationsAnalyzerError::MainStorage(err) => err.classify(),
+            DelegationsAnalyzerError::DelegationsCollector(err) => err.classify(),
+        }
+    }
+}
+
+impl Classify for EpochStorageError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::Transient
+    }
+}
+
+impl Classify for MainStorageError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            MainStorageError::Storage(err) => err.classify(),
+            MainStorageError::ClickhouseError(_) | MainStorageError::ClickhouseHttp(_) => {
+                ErrorClass::Transient
+            }
+        }
+    }
+}
+
+impl Classify for DelegationsCollectorError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            DelegationsCollectorError::MainStorage(err) => err.classify(),
+        }
+    }
+}
+
+impl Classify for VoteAccountResolverError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            VoteAccountResolverError::MainStorage(err) => err.classify(),
+        }
+    }
+}
+
+impl Classify for ProvisionalEpochsError {
+    fn classify(&self) -> ErrorClass {
+        match self {
+            ProvisionalEpochsError::Rpc(err) => err.classify(),
+            ProvisionalEpochsError::EpochStorage(err) => err.classify(),
+            ProvisionalEpochsError::MainStorage(err) => err.classify(),
+        }
+    }
+}
+
+impl Classify for RpcError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::External
+    }
+}
+
+impl_into_indexer_error!(
+    RewardsAnalyzerError,
+    DelegationsAnalyzerError,
+    EpochStorageError,
+    MainStorageError,
+    DelegationsCollectorError,
+    VoteAccountResolverError,
+    ProvisionalEpochsError,
+    RpcError,
+);
+
+#[cfg(test)]
+mod tests {
+    use clickhouse_storage::StorageError;
+    use indexer_errors::IndexerError;
+
+    use super::*;
+
+    #[test]
+    fn indexer_error_keeps_the_message() {
+        let err = IndexerError::from(RewardsAnalyzerError::MainStorage(
+            MainStorageError::Storage(StorageError::UnknownProtocol("ftp".to_string())),
+        ));
+        assert_eq!(
+            err.to_string(),
+            "MainStorage error Storage error: Unknown protocol: ftp"
+        );
+        assert_eq!(err.classify(), ErrorClass::Configuration);
+
+        let err = IndexerError::from(RpcError::Response("Slot 10 was skipped".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "RPC returned an error: Slot 10 was skipped"
+        );
+        assert_eq!(err.classify(), ErrorClass::External);
+    }
+}
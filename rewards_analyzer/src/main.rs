@@ -2,49 +2,74 @@
 extern crate clickhouse as clickhouse_http;
 
 mod configuration;
+#[cfg(all(test, feature = "integration-tests"))]
+mod end_to_end_tests;
+mod epoch_schedule;
 mod errors;
+mod health;
 mod prometheus;
+mod provisional_epochs;
 mod register;
 mod rewards_analyzer;
-mod rewards_collector;
+mod rpc_client;
 mod storage;
 mod vote_accounts_resolver;
 
 use anyhow::Result;
-use env_logger::Env;
-use log::{error, info};
+use clickhouse_storage::{check_schema, Migrations};
+use log::{error, info, warn};
 use tokio::signal::{
     self,
     unix::{signal, SignalKind},
 };
 
 use crate::{
+    configuration::{get_matches, Configuration},
     prometheus::PrometheusExporter,
+    provisional_epochs::ProvisionalEpochs,
+    register::Register,
     rewards_analyzer::RewardsAnalyzer,
-    storage::main_storage::{
-        connect_main_storage,
-        migrations::{Migrations, SCRIPTS_UP},
-    },
+    storage::main_storage::{connect_main_storage, migrations::SCRIPTS_UP, schema::WRITTEN_TABLES},
+    vote_accounts_resolver::VoteAccountResolver,
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("error")).init();
+    // The register reads the configuration on its first use, so its problems are reported here
+    if let Err(err) = Configuration::new() {
+        indexer_errors::exit_on_configuration_error(err);
+    }
+
+    indexer_telemetry::init_logger(
+        "rewards_analyzer",
+        Register::current().configuration.log_format(),
+        "error",
+    );
     info!("Starting");
 
     // Run migrations. The storage will be dropped right after that and connection will be closed.
     {
         let mut storage = connect_main_storage().await?;
 
-        let migrations = Migrations::new();
-        migrations.up(&mut storage, &SCRIPTS_UP).await?;
+        let table_prefix = Register::current()
+            .configuration
+            .main_storage_table_prefix()
+            .to_string();
+
+        let migrations = Migrations::new(&SCRIPTS_UP).with_table_prefix(&table_prefix);
+        migrations.up(storage.as_mut()).await?;
+
+        if get_matches().is_present("skip-schema-check") {
+            warn!("Schema check is skipped");
+        } else {
+            check_schema(storage.as_mut(), WRITTEN_TABLES, &table_prefix).await?;
+        }
     }
 
     RewardsAnalyzer::run().await?;
     PrometheusExporter::run().await?;
-
-    // Uncomment to resolve vote accounts in rewards
-    // vote_accounts_resolver::VoteAccountResolver::run().await?;
+    VoteAccountResolver::run().await?;
+    ProvisionalEpochs::run().await?;
 
     wait_termination().await;
     info!("Shutting down");
@@ -1,13 +1,76 @@
+use std::time::Duration;
+
 use anyhow::Result;
+use clap::crate_version;
 use hyper::{
     header::CONTENT_TYPE,
     service::{make_service_fn, service_fn},
-    Body, Response, Server,
+    Body, Request, Response, Server,
 };
+use lazy_static::lazy_static;
 use log::{error, info};
-use prometheus::{Encoder, TextEncoder};
+use prometheus::{
+    core::Collector, register_int_counter_vec_with_registry, register_int_counter_with_registry,
+    register_int_gauge_vec_with_registry, register_int_gauge_with_registry, Encoder, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec, Registry, TextEncoder,
+};
+
+use crate::{
+    health::readiness_response,
+    register::Register,
+    storage::{epoch_storage::EpochStorage, main_storage::connect_main_storage},
+};
 
-use crate::register::Register;
+lazy_static! {
+    /// Registry of the metrics of the service
+    pub static ref REGISTRY: Registry = indexer_telemetry::service_registry("rewards_analyzer");
+    pub static ref BUILD_INFO: IntGaugeVec =
+        indexer_telemetry::register_build_info("ra", crate_version!(), &REGISTRY).unwrap();
+    pub static ref CURRENT_EPOCH: IntGaugeVec = register_int_gauge_vec_with_registry!(
+        "ra_current_epoch",
+        "Epoch which rewards are being analyzed by the worker",
+        &["worker"],
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref RESOLVED_VOTE_ACCOUNTS_COUNT: IntCounter = register_int_counter_with_registry!(
+        "ra_resolved_vote_accounts_total",
+        "Rewards which vote account has been resolved",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref UNRESOLVED_VOTE_ACCOUNTS: IntGauge = register_int_gauge_with_registry!(
+        "ra_unresolved_vote_accounts",
+        "Rewards which vote account is still unknown after the last resolver pass",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref PROVISIONAL_EPOCHS: IntGauge = register_int_gauge_with_registry!(
+        "ra_provisional_epochs",
+        "Epochs whose rewards were analyzed from the RPC and not confirmed by the epoch storage yet",
+        REGISTRY
+    )
+    .unwrap();
+    pub static ref ERRORS_COUNT: IntCounterVec = register_int_counter_vec_with_registry!(
+        "ra_errors_total",
+        "Failed requests to the epoch storage and the main storage which are retried per error class",
+        &["class"],
+        REGISTRY
+    )
+    .unwrap();
+}
+
+/// Every metric of the service, so they are exported from the start instead of their first use
+pub fn metrics() -> Vec<&'static dyn Collector> {
+    vec![
+        &*BUILD_INFO,
+        &*CURRENT_EPOCH,
+        &*RESOLVED_VOTE_ACCOUNTS_COUNT,
+        &*UNRESOLVED_VOTE_ACCOUNTS,
+        &*PROVISIONAL_EPOCHS,
+        &*ERRORS_COUNT,
+    ]
+}
 
 pub struct PrometheusExporter {}
 
@@ -19,26 +82,14 @@ impl PrometheusExporter {
             .parse()
             .unwrap();
 
+        metrics();
+
         tokio::spawn(async move {
             info!("Prometheus exporter started on http://{}", &addr);
 
             if let Err(err) = Server::bind(&addr)
                 .serve(make_service_fn(|_| async {
-                    Ok::<_, hyper::Error>(service_fn(|_| async {
-                        let encoder = TextEncoder::new();
-                        let metric_families = prometheus::gather();
-                        let mut buffer = Vec::new();
-
-                        encoder.encode(&metric_families, &mut buffer).unwrap();
-
-                        let response = Response::builder()
-                            .status(200)
-                            .header(CONTENT_TYPE, encoder.format_type())
-                            .body(Body::from(buffer))
-                            .unwrap();
-
-                        Ok::<_, hyper::Error>(response)
-                    }))
+                    Ok::<_, hyper::Error>(service_fn(Self::respond))
                 }))
                 .await
             {
@@ -48,4 +99,95 @@ impl PrometheusExporter {
 
         Ok(())
     }
+
+    async fn respond(request: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        let response = match request.uri().path() {
+            "/healthz" => readiness_response(&[]),
+            "/readyz" => Self::readiness().await,
+            _ => {
+                let encoder = TextEncoder::new();
+                let metric_families = Register::current().registry.gather();
+                let mut buffer = Vec::new();
+
+                encoder.encode(&metric_families, &mut buffer).unwrap();
+
+                Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, encoder.format_type())
+                    .body(Body::from(buffer))
+                    .unwrap()
+            }
+        };
+
+        Ok(response)
+    }
+
+    async fn readiness() -> Response<Body> {
+        let register = Register::current();
+        let staleness = Duration::from_secs(register.configuration.readiness_staleness());
+        let last_batch = register.health.read().unwrap().check_staleness(staleness);
+
+        readiness_response(&[
+            (
+                "epoch_storage",
+                EpochStorage::ping().await.map_err(|err| err.to_string()),
+            ),
+            ("main_storage", Self::check_main_storage().await),
+            ("last_batch", last_batch),
+        ])
+    }
+
+    async fn check_main_storage() -> Result<(), String> {
+        let mut storage = connect_main_storage()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        storage
+            .execute("SELECT 1")
+            .await
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::proto::MetricType;
+
+    /// `# HELP` and `# TYPE` lines of the metrics in the /metrics output, where the metrics with
+    /// labels appear after their first samples
+    fn descriptions() -> String {
+        let mut families: Vec<_> = metrics()
+            .iter()
+            .flat_map(|metric| metric.collect())
+            .collect();
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        families
+            .iter()
+            .map(|family| {
+                let metric_type = match family.get_field_type() {
+                    MetricType::COUNTER => "counter",
+                    MetricType::GAUGE => "gauge",
+                    MetricType::HISTOGRAM => "histogram",
+                    MetricType::SUMMARY => "summary",
+                    MetricType::UNTYPED => "untyped",
+                };
+
+                format!(
+                    "# HELP {name} {}\n# TYPE {name} {}\n",
+                    family.get_help(),
+                    metric_type,
+                    name = family.get_name()
+                )
+            })
+            .collect()
+    }
+
+    /// The dashboards and the alerts refer to the metrics by their names, a renamed metric has
+    /// to be renamed in `metrics.golden` as well
+    #[test]
+    fn metrics_are_not_renamed() {
+        assert_eq!(descriptions(), include_str!("metrics.golden"));
+    }
 }
@@ -0,0 +1,575 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use indexer_errors::Classify;
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use crate::{
+    epoch_schedule::EpochSchedule,
+    errors::{EpochStorageError, ProvisionalEpochsError, RpcError},
+    prometheus::{ERRORS_COUNT, PROVISIONAL_EPOCHS},
+    register::Register,
+    rewards_analyzer::RewardsAnalyzer,
+    rpc_client::{FirstBlock, RpcClient},
+    storage::{
+        epoch_storage::{Epoch, EpochStorage},
+        main_storage::{connect_main_storage, MainStorage},
+    },
+};
+
+/// Source of the epoch boundaries and the first blocks when the epoch storage lags behind
+#[async_trait]
+pub(crate) trait Cluster: Sync {
+    async fn epoch_schedule(&self) -> Result<EpochSchedule, RpcError>;
+    /// The latest finalized slot
+    async fn slot(&self) -> Result<u64, RpcError>;
+    async fn first_block(
+        &self,
+        first_slot: u64,
+        last_slot: u64,
+    ) -> Result<Option<FirstBlock>, RpcError>;
+}
+
+#[async_trait]
+impl Cluster for RpcClient {
+    async fn epoch_schedule(&self) -> Result<EpochSchedule, RpcError> {
+        self.get_epoch_schedule().await
+    }
+
+    async fn slot(&self) -> Result<u64, RpcError> {
+        self.get_slot().await
+    }
+
+    async fn first_block(
+        &self,
+        first_slot: u64,
+        last_slot: u64,
+    ) -> Result<Option<FirstBlock>, RpcError> {
+        self.get_first_block(first_slot, last_slot).await
+    }
+}
+
+/// Epochs loaded by the epoch tracker
+#[async_trait]
+pub(crate) trait TrackedEpochs: Sync {
+    async fn first_blocks(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<HashMap<Epoch, u64>, EpochStorageError>;
+    async fn confirm_provisional(
+        &self,
+        epoch: Epoch,
+        first_block: u64,
+    ) -> Result<bool, EpochStorageError>;
+}
+
+#[async_trait]
+impl TrackedEpochs for EpochStorage {
+    async fn first_blocks(
+        &self,
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<HashMap<Epoch, u64>, EpochStorageError> {
+        EpochStorage::get_first_blocks(from, to).await
+    }
+
+    async fn confirm_provisional(
+        &self,
+        epoch: Epoch,
+        first_block: u64,
+    ) -> Result<bool, EpochStorageError> {
+        EpochStorage::confirm_provisional(epoch, first_block).await
+    }
+}
+
+/// Analyzes the rewards of the latest epochs which the epoch tracker hasn't loaded yet. Their
+/// boundaries are computed from the epoch schedule of the RPC and the rewards are stored as
+/// provisional until the tracker loads the same first block. An epoch whose first block turns
+/// out to be another one is left to the workers, which replace its rows
+pub(crate) struct ProvisionalEpochs {}
+
+impl ProvisionalEpochs {
+    pub async fn run() -> Result<(), ProvisionalEpochsError> {
+        let configuration = &Register::current().configuration;
+
+        let rpc_url = match configuration.provisional_epochs_rpc_url() {
+            Some(rpc_url) => rpc_url,
+            None => {
+                info!("provisional_epochs is disabled, rpc_url is not set");
+                return Ok(());
+            }
+        };
+
+        info!("Starting provisional_epochs");
+        let interval = Duration::from_secs(configuration.provisional_epochs_interval());
+        let lookback_epochs = configuration.provisional_epochs_lookback();
+        let epochs_per_year = configuration.rewards_epochs_per_year();
+        let rpc_client = RpcClient::new(rpc_url);
+
+        let mut main_storage = connect_main_storage().await?;
+
+        tokio::spawn(async move {
+            loop {
+                match Self::pass(
+                    main_storage.as_mut(),
+                    &rpc_client,
+                    &EpochStorage {},
+                    lookback_epochs,
+                    epochs_per_year,
+                )
+                .await
+                {
+                    Ok(provisional) => {
+                        PROVISIONAL_EPOCHS.set(provisional as i64);
+                        info!(
+                            actor = "provisional_epochs";
+                            "provisional_epochs pass is finished, {} epochs are provisional",
+                            provisional
+                        );
+                    }
+                    Err(err) => {
+                        error!("provisional_epochs pass failed: {}", err);
+                        ERRORS_COUNT
+                            .with_label_values(&[err.classify().as_str()])
+                            .inc();
+                    }
+                }
+
+                sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Confirms the provisional epochs which the tracker has loaded with the same first block
+    /// and analyzes the epochs of the latest `lookback_epochs` it hasn't loaded yet. Returns the
+    /// number of the epochs which are still provisional
+    pub(crate) async fn pass<C: Cluster, T: TrackedEpochs>(
+        main_storage: &mut dyn MainStorage,
+        cluster: &C,
+        tracked: &T,
+        lookback_epochs: u64,
+        epochs_per_year: f64,
+    ) -> Result<usize, ProvisionalEpochsError> {
+        let epoch_schedule = cluster.epoch_schedule().await?;
+        let current = epoch_schedule.epoch_of(cluster.slot().await?);
+        // Rewards of the epoch are paid for the previous one, the epoch 0 has none
+        let from = current.saturating_sub(lookback_epochs - 1).max(1);
+
+        let provisional_epochs = main_storage.get_provisional_epochs().await?;
+        let tracked_from = provisional_epochs
+            .iter()
+            .map(|(epoch, _)| *epoch)
+            .fold(from, Epoch::min);
+        let first_blocks = tracked.first_blocks(tracked_from, current).await?;
+
+        let mut provisional: HashSet<Epoch> = HashSet::new();
+
+        for (epoch, first_block_slot) in provisional_epochs {
+            provisional.insert(epoch);
+
+            match first_blocks.get(&epoch) {
+                Some(&first_block) if Some(first_block) == first_block_slot => {
+                    if tracked.confirm_provisional(epoch, first_block).await? {
+                        main_storage.confirm_provisional_epoch(epoch).await?;
+                        provisional.remove(&epoch);
+                        info!(
+                            actor = "provisional_epochs",
+                            epoch = epoch;
+                            "Provisional rewards of {} epoch are confirmed",
+                            epoch
+                        );
+                    }
+                }
+                Some(&first_block) => warn!(
+                    actor = "provisional_epochs",
+                    epoch = epoch;
+                    "First block of {} epoch is {} in the epoch storage, not {:?}, \
+                    its provisional rewards will be replaced",
+                    epoch, first_block, first_block_slot
+                ),
+                None => {}
+            }
+        }
+
+        for epoch in from..=current {
+            if first_blocks.contains_key(&epoch) || provisional.contains(&epoch) {
+                continue;
+            }
+
+            let first_block = match cluster
+                .first_block(
+                    epoch_schedule.first_slot(epoch),
+                    epoch_schedule.last_slot(epoch),
+                )
+                .await?
+            {
+                Some(first_block) => first_block,
+                None => continue,
+            };
+
+            info!(
+                actor = "provisional_epochs",
+                epoch = epoch;
+                "Epoch storage has no {} epoch yet, its rewards are analyzed from the block {}",
+                epoch, first_block.slot
+            );
+            RewardsAnalyzer::process_epoch(
+                main_storage,
+                epoch,
+                Some(first_block.slot),
+                first_block.block_time,
+                first_block.rewards,
+                epochs_per_year,
+                true,
+            )
+            .await;
+            provisional.insert(epoch);
+        }
+
+        Ok(provisional.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use clickhouse_storage::{ClickhouseStorage, StorageError};
+    use solana_transaction_status::{Reward, RewardType, Rewards};
+
+    use super::*;
+    use crate::{
+        errors::MainStorageError,
+        storage::main_storage::{RewardRecResult, RewardYield},
+    };
+
+    const SLOTS_PER_EPOCH: u64 = 432000;
+
+    /// Rows of the analyzed epochs: first block slot, provisional and the rewarded accounts
+    #[derive(Default)]
+    struct MockMainStorage {
+        epochs: HashMap<Epoch, (Option<u64>, bool, Vec<String>)>,
+    }
+
+    #[async_trait]
+    impl ClickhouseStorage for MockMainStorage {
+        async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl MainStorage for MockMainStorage {
+        async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+            self.epochs.remove(&epoch);
+            Ok(())
+        }
+
+        async fn lookup_vote_acc(
+            &mut self,
+            _slot: u64,
+            _stake_acc: &str,
+        ) -> Result<Option<String>, MainStorageError> {
+            Ok(None)
+        }
+
+        async fn lookup_vote_accs(
+            &mut self,
+            _slot: u64,
+            stake_accs: &[String],
+        ) -> Result<HashMap<String, Option<String>>, MainStorageError> {
+            Ok(stake_accs
+                .iter()
+                .map(|stake_acc| (stake_acc.clone(), None))
+                .collect())
+        }
+
+        async fn store_rewards_block(
+            &mut self,
+            rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+            provisional: bool,
+        ) -> Result<(), MainStorageError> {
+            for (_, epoch, first_block_slot, reward, _, _) in rewards {
+                self.epochs
+                    .entry(epoch)
+                    .or_insert_with(|| (first_block_slot, provisional, Vec::new()))
+                    .2
+                    .push(reward.pubkey);
+            }
+            Ok(())
+        }
+
+        async fn get_delegated_stakes(
+            &mut self,
+            _slot: u64,
+        ) -> Result<HashMap<String, u64>, MainStorageError> {
+            Ok(HashMap::new())
+        }
+
+        async fn store_commissions_block(
+            &mut self,
+            _commissions: Vec<(String, Epoch, Option<u8>)>,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn get_rewards_with_empty_vote_acc(
+            &mut self,
+            _after_epoch: Epoch,
+            _after_pubkey: &str,
+            _limit: u64,
+        ) -> Result<Vec<RewardRecResult>, MainStorageError> {
+            Ok(Vec::new())
+        }
+
+        async fn update_reward(
+            &mut self,
+            _vote_acc: &str,
+            _epoch: Epoch,
+            _pubkey: &str,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn get_provisional_epochs(
+            &mut self,
+        ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError> {
+            let mut epochs: Vec<_> = self
+                .epochs
+                .iter()
+                .filter(|(_, (_, provisional, _))| *provisional)
+                .map(|(epoch, (first_block_slot, _, _))| (*epoch, *first_block_slot))
+                .collect();
+            epochs.sort();
+            Ok(epochs)
+        }
+
+        async fn confirm_provisional_epoch(
+            &mut self,
+            epoch: Epoch,
+        ) -> Result<(), MainStorageError> {
+            if let Some((_, provisional, _)) = self.epochs.get_mut(&epoch) {
+                *provisional = false;
+            }
+            Ok(())
+        }
+    }
+
+    /// The cluster at `slot` whose first blocks are skipped for `skipped` slots of an epoch
+    struct MockCluster {
+        slot: u64,
+        skipped: HashMap<Epoch, u64>,
+    }
+
+    #[async_trait]
+    impl Cluster for MockCluster {
+        async fn epoch_schedule(&self) -> Result<EpochSchedule, RpcError> {
+            Ok(EpochSchedule {
+                slots_per_epoch: SLOTS_PER_EPOCH,
+                first_normal_epoch: 0,
+                first_normal_slot: 0,
+            })
+        }
+
+        async fn slot(&self) -> Result<u64, RpcError> {
+            Ok(self.slot)
+        }
+
+        async fn first_block(
+            &self,
+            first_slot: u64,
+            last_slot: u64,
+        ) -> Result<Option<FirstBlock>, RpcError> {
+            let epoch = first_slot / SLOTS_PER_EPOCH;
+            let slot = first_slot + self.skipped.get(&epoch).copied().unwrap_or_default();
+
+            if slot > last_slot.min(self.slot) {
+                return Ok(None);
+            }
+
+            Ok(Some(FirstBlock {
+                slot,
+                block_time: 1700000000,
+                rewards: rewards(epoch),
+            }))
+        }
+    }
+
+    /// Epochs of the tracker: first block and rewards parsing status
+    #[derive(Default)]
+    struct MockTrackedEpochs {
+        epochs: Mutex<HashMap<Epoch, (u64, u8)>>,
+    }
+
+    impl MockTrackedEpochs {
+        fn track(&self, epoch: Epoch, first_block: u64, status: u8) {
+            self.epochs
+                .lock()
+                .unwrap()
+                .insert(epoch, (first_block, status));
+        }
+
+        fn status(&self, epoch: Epoch) -> Option<u8> {
+            self.epochs
+                .lock()
+                .unwrap()
+                .get(&epoch)
+                .map(|(_, status)| *status)
+        }
+    }
+
+    #[async_trait]
+    impl TrackedEpochs for MockTrackedEpochs {
+        async fn first_blocks(
+            &self,
+            from: Epoch,
+            to: Epoch,
+        ) -> Result<HashMap<Epoch, u64>, EpochStorageError> {
+            Ok(self
+                .epochs
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(epoch, _)| (from..=to).contains(*epoch))
+                .map(|(epoch, (first_block, _))| (*epoch, *first_block))
+                .collect())
+        }
+
+        async fn confirm_provisional(
+            &self,
+            epoch: Epoch,
+            first_block: u64,
+        ) -> Result<bool, EpochStorageError> {
+            match self.epochs.lock().unwrap().get_mut(&epoch) {
+                Some((tracked, status)) if *tracked == first_block && *status != 2 => {
+                    *status = 1;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+
+    fn rewards(epoch: Epoch) -> Rewards {
+        vec![
+            Reward {
+                pubkey: format!("stake_{}", epoch),
+                lamports: 1000,
+                post_balance: 0,
+                reward_type: Some(RewardType::Staking),
+                commission: None,
+            },
+            Reward {
+                pubkey: format!("vote_{}", epoch),
+                lamports: 100,
+                post_balance: 0,
+                reward_type: Some(RewardType::Voting),
+                commission: Some(7),
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn lagging_tracker_epochs_are_provisional_until_reconciled() {
+        let mut main_storage = MockMainStorage::default();
+        let tracked = MockTrackedEpochs::default();
+        // The tracker has loaded 299 and 300 while the cluster is in 302
+        tracked.track(299, 299 * SLOTS_PER_EPOCH, 1);
+        tracked.track(300, 300 * SLOTS_PER_EPOCH, 1);
+        let cluster = MockCluster {
+            slot: 302 * SLOTS_PER_EPOCH + 1000,
+            skipped: HashMap::new(),
+        };
+
+        let provisional = ProvisionalEpochs::pass(&mut main_storage, &cluster, &tracked, 4, 182.5)
+            .await
+            .unwrap();
+
+        // Rewards still flow for the epochs the tracker doesn't know
+        assert_eq!(provisional, 2);
+        assert_eq!(
+            main_storage.get_provisional_epochs().await.unwrap(),
+            vec![
+                (301, Some(301 * SLOTS_PER_EPOCH)),
+                (302, Some(302 * SLOTS_PER_EPOCH))
+            ]
+        );
+        assert_eq!(
+            main_storage.epochs[&302].2,
+            vec![String::from("stake_302"), String::from("vote_302")]
+        );
+        assert!(!main_storage.epochs.contains_key(&300));
+
+        // The tracker catches up, the first slot of 302 turns out to be skipped
+        tracked.track(301, 301 * SLOTS_PER_EPOCH, 0);
+        tracked.track(302, 302 * SLOTS_PER_EPOCH + 1, 0);
+
+        let provisional = ProvisionalEpochs::pass(&mut main_storage, &cluster, &tracked, 4, 182.5)
+            .await
+            .unwrap();
+
+        assert_eq!(provisional, 1);
+        assert_eq!(
+            main_storage.get_provisional_epochs().await.unwrap(),
+            vec![(302, Some(302 * SLOTS_PER_EPOCH))]
+        );
+        // The confirmed epoch is not analyzed again, the corrected one is left to the workers
+        assert_eq!(tracked.status(301), Some(1));
+        assert_eq!(tracked.status(302), Some(0));
+
+        // A worker analyzes 302 from the first block of the tracker
+        RewardsAnalyzer::process_epoch(
+            &mut main_storage,
+            302,
+            Some(302 * SLOTS_PER_EPOCH + 1),
+            1700000000,
+            rewards(302),
+            182.5,
+            false,
+        )
+        .await;
+        tracked.track(302, 302 * SLOTS_PER_EPOCH + 1, 1);
+
+        let provisional = ProvisionalEpochs::pass(&mut main_storage, &cluster, &tracked, 4, 182.5)
+            .await
+            .unwrap();
+
+        assert_eq!(provisional, 0);
+        assert_eq!(main_storage.epochs[&302].0, Some(302 * SLOTS_PER_EPOCH + 1));
+        assert!(!main_storage.epochs[&301].1);
+    }
+
+    #[tokio::test]
+    async fn epoch_without_finalized_first_block_is_skipped() {
+        let mut main_storage = MockMainStorage::default();
+        let tracked = MockTrackedEpochs::default();
+        tracked.track(301, 301 * SLOTS_PER_EPOCH, 1);
+        // The first block of 302 is not finalized yet
+        let cluster = MockCluster {
+            slot: 302 * SLOTS_PER_EPOCH + 10,
+            skipped: HashMap::from([(302, 20)]),
+        };
+
+        let provisional = ProvisionalEpochs::pass(&mut main_storage, &cluster, &tracked, 2, 182.5)
+            .await
+            .unwrap();
+
+        assert_eq!(provisional, 0);
+        assert!(main_storage.epochs.is_empty());
+    }
+}
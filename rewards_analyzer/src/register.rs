@@ -1,9 +1,15 @@
 use crate::configuration::*;
+use crate::health::{SharedHealthState, HEALTH};
+use crate::prometheus::REGISTRY;
+use prometheus::Registry;
 use std::sync::{Arc, RwLock};
 
 #[derive(Default)]
 pub struct Register {
     pub configuration: Configuration,
+    pub health: SharedHealthState,
+    /// Registry of the metrics exported by the service
+    pub registry: Registry,
 }
 impl Register {
     pub fn current() -> Arc<Register> {
@@ -12,5 +18,5 @@ impl Register {
 }
 
 thread_local! {
-    static CURRENT_REGISTER:RwLock<Arc<Register>> = RwLock::new(Arc::new(Register { configuration: Configuration::new().unwrap() }))
+    static CURRENT_REGISTER:RwLock<Arc<Register>> = RwLock::new(Arc::new(Register { configuration: Configuration::new().unwrap(), health: HEALTH.clone(), registry: REGISTRY.clone() }))
 }
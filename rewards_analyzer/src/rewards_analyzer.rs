@@ -1,82 +1,637 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use log::{error, info};
-use solana_transaction_status::RewardType;
+use log::info;
+use solana_transaction_status::{RewardType, Rewards};
 use tokio::time::sleep;
 
 use crate::{
     errors::RewardsAnalyzerError,
+    prometheus::CURRENT_EPOCH,
+    register::Register,
     repeat_until_ok,
-    rewards_collector::RewardsCollectorHandle,
-    storage::{epoch_storage::EpochStorage, main_storage::connect_main_storage},
+    storage::{
+        epoch_storage::{Epoch, EpochStorage},
+        main_storage::{connect_main_storage, MainStorage, RewardYield},
+    },
 };
 
+const BUFFER_SIZE: usize = 10000;
+/// Stake accounts whose vote accounts are looked up with one query
+const LOOKUP_VOTE_ACCS_CHUNK: usize = 1000;
+
 pub struct RewardsAnalyzer {}
 
 impl RewardsAnalyzer {
     pub async fn run() -> Result<Self, RewardsAnalyzerError> {
         info!("Starting rewards_analyzer");
 
-        let mut main_storage = connect_main_storage().await?;
-        let mut rewards_collector = RewardsCollectorHandle::new().await?;
-
-        tokio::spawn(async move {
-            loop {
-                if let (Some(epoch), first_block_slot) =
-                    repeat_until_ok!(EpochStorage::get_not_parsed_first_block_epoch().await, 5)
-                {
-                    info!("Start analyze the rewards of {} epoch", epoch);
-                    let (block_time, rewards) =
-                        repeat_until_ok!(EpochStorage::get_rewards_records(epoch).await, 5);
-                    info!("The number of rewards is: {}", rewards.len());
-
-                    info!("Call prepare_clean_unfinished");
-                    repeat_until_ok!(main_storage.clean_unfinished(epoch).await, 5);
-
-                    for reward in rewards {
-                        match reward.reward_type {
-                            Some(RewardType::Staking) => {
-                                let vote_acc = repeat_until_ok!(
-                                    main_storage
-                                        .lookup_vote_acc(first_block_slot.unwrap(), &reward.pubkey)
-                                        .await,
-                                    5
-                                );
-
-                                rewards_collector
-                                    .save_reward(
-                                        vote_acc.unwrap_or_default(),
-                                        epoch,
-                                        first_block_slot,
-                                        reward,
-                                        block_time,
-                                    )
-                                    .await;
-                            }
-                            Some(RewardType::Voting) => {
-                                rewards_collector
-                                    .save_reward(
-                                        String::from(""),
-                                        epoch,
-                                        first_block_slot,
-                                        reward,
-                                        block_time,
-                                    )
-                                    .await;
-                            }
-                            _ => {}
-                        }
-                    }
+        let concurrency = Register::current().configuration.rewards_concurrency();
+        let epochs_per_year = Register::current().configuration.rewards_epochs_per_year();
+
+        let released = EpochStorage::release_claimed_epochs().await?;
+        if released > 0 {
+            info!(
+                "{} unfinished epochs have been returned to the queue",
+                released
+            );
+        }
+
+        for worker in 0..concurrency {
+            let main_storage = connect_main_storage().await?;
+            tokio::spawn(Self::worker(worker, main_storage, epochs_per_year));
+        }
+
+        Ok(Self {})
+    }
+
+    async fn worker(worker: usize, mut main_storage: Box<dyn MainStorage>, epochs_per_year: f64) {
+        let worker_label = worker.to_string();
+
+        loop {
+            if let (Some(epoch), first_block_slot) =
+                repeat_until_ok!(EpochStorage::claim_not_parsed_first_block_epoch().await, 5)
+            {
+                CURRENT_EPOCH
+                    .with_label_values(&[&worker_label])
+                    .set(epoch as i64);
+
+                info!(
+                    actor = "rewards_worker",
+                    epoch = epoch;
+                    "Worker {} starts to analyze the rewards of {} epoch",
+                    worker, epoch
+                );
+                let (block_time, rewards) =
+                    repeat_until_ok!(EpochStorage::get_rewards_records(epoch).await, 5);
+                info!(
+                    actor = "rewards_worker",
+                    epoch = epoch;
+                    "The number of rewards is: {}",
+                    rewards.len()
+                );
+
+                Self::process_epoch(
+                    main_storage.as_mut(),
+                    epoch,
+                    first_block_slot,
+                    block_time,
+                    rewards,
+                    epochs_per_year,
+                    false,
+                )
+                .await;
+
+                info!(
+                    actor = "rewards_worker",
+                    epoch = epoch;
+                    "Complete analyze the rewards of {} epoch",
+                    epoch
+                );
 
-                    info!("Complete analyze the rewards of {} epoch", epoch);
+                repeat_until_ok!(EpochStorage::mark_rewards_parsed(epoch).await, 5);
+                Register::current()
+                    .health
+                    .write()
+                    .unwrap()
+                    .batch_processed();
+                continue;
+            }
+
+            // Nothing to analyze, the epoch storage still responds
+            Register::current()
+                .health
+                .write()
+                .unwrap()
+                .batch_processed();
+            sleep(Duration::from_secs(60)).await;
+        }
+    }
+
+    /// Stores the rewards of a single epoch along with the commissions of the vote accounts
+    /// observed in their voting rewards. Rows of the epoch left by a crashed run are removed
+    /// first and the rewards are inserted in their original order. The staking rewards and the
+    /// voting reward of a vote account get the yield of the stake delegated to it. The vote
+    /// accounts of the staking rewards are looked up in chunks of `LOOKUP_VOTE_ACCS_CHUNK`.
+    /// `provisional` rewards are of a first block which the epoch storage doesn't know yet
+    pub(crate) async fn process_epoch(
+        main_storage: &mut dyn MainStorage,
+        epoch: Epoch,
+        first_block_slot: Option<u64>,
+        block_time: i64,
+        rewards: Rewards,
+        epochs_per_year: f64,
+        provisional: bool,
+    ) {
+        info!("Call prepare_clean_unfinished");
+        repeat_until_ok!(main_storage.clean_unfinished(epoch).await, 5);
+
+        let mut reward_records = Vec::with_capacity(rewards.len());
+        let mut commissions = Vec::new();
+        let mut staking_lamports: HashMap<String, i64> = HashMap::new();
+
+        let stake_accs: Vec<String> = rewards
+            .iter()
+            .filter(|reward| matches!(reward.reward_type, Some(RewardType::Staking)))
+            .map(|reward| reward.pubkey.clone())
+            .collect();
+        let mut vote_accs: HashMap<String, Option<String>> =
+            HashMap::with_capacity(stake_accs.len());
 
-                    repeat_until_ok!(EpochStorage::mark_rewards_parsed(epoch).await, 5);
+        for stake_accs in stake_accs.chunks(LOOKUP_VOTE_ACCS_CHUNK) {
+            vote_accs.extend(repeat_until_ok!(
+                main_storage
+                    .lookup_vote_accs(first_block_slot.unwrap(), stake_accs)
+                    .await,
+                5
+            ));
+        }
+
+        for reward in rewards {
+            match reward.reward_type {
+                Some(RewardType::Staking) => {
+                    let vote_acc = vote_accs.get(&reward.pubkey).cloned().flatten();
+
+                    if let Some(vote_acc) = &vote_acc {
+                        *staking_lamports.entry(vote_acc.clone()).or_default() += reward.lamports;
+                    }
+
+                    reward_records.push((
+                        vote_acc.unwrap_or_default(),
+                        epoch,
+                        first_block_slot,
+                        reward,
+                        block_time,
+                        None,
+                    ));
+                }
+                Some(RewardType::Voting) => {
+                    commissions.push((reward.pubkey.clone(), epoch, reward.commission));
+                    reward_records.push((
+                        String::from(""),
+                        epoch,
+                        first_block_slot,
+                        reward,
+                        block_time,
+                        None,
+                    ));
                 }
+                _ => {}
+            }
+        }
 
-                sleep(Duration::from_secs(60)).await;
+        let yields: HashMap<String, RewardYield> = match first_block_slot {
+            Some(slot) if !staking_lamports.is_empty() => {
+                let stakes = repeat_until_ok!(main_storage.get_delegated_stakes(slot).await, 5);
+
+                staking_lamports
+                    .into_iter()
+                    .filter_map(|(vote_acc, lamports)| {
+                        let stake = stakes.get(&vote_acc).copied().unwrap_or_default();
+                        reward_yield(lamports, stake, epochs_per_year)
+                            .map(|reward_yield| (vote_acc, reward_yield))
+                    })
+                    .collect()
             }
+            _ => HashMap::new(),
+        };
+
+        for (vote_acc, _, _, reward, _, reward_yield) in reward_records.iter_mut() {
+            let vote_acc = match reward.reward_type {
+                Some(RewardType::Voting) => &reward.pubkey,
+                _ => vote_acc,
+            };
+            *reward_yield = yields.get(vote_acc).copied();
+        }
+
+        for reward_records in reward_records.chunks(BUFFER_SIZE) {
+            repeat_until_ok!(
+                main_storage
+                    .store_rewards_block(reward_records.to_vec(), provisional)
+                    .await,
+                5
+            );
+            info!(
+                actor = "rewards_worker",
+                epoch = epoch;
+                "Stored {} rewards of {} epoch",
+                reward_records.len(),
+                epoch
+            );
+        }
+
+        for commissions in commissions.chunks(BUFFER_SIZE) {
+            repeat_until_ok!(
+                main_storage
+                    .store_commissions_block(commissions.to_vec())
+                    .await,
+                5
+            );
+            info!(
+                actor = "rewards_worker",
+                epoch = epoch;
+                "Stored {} commissions of {} epoch",
+                commissions.len(),
+                epoch
+            );
+        }
+    }
+}
+
+/// Yield of the `lamports` rewarded within an epoch to the `stake`, none if the stake is unknown
+fn reward_yield(lamports: i64, stake: u64, epochs_per_year: f64) -> Option<RewardYield> {
+    if stake == 0 {
+        return None;
+    }
+
+    let epoch_rate = lamports as f64 / stake as f64;
+
+    Some(RewardYield {
+        epoch_rate,
+        apy: (1.0 + epoch_rate).powf(epochs_per_year) - 1.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use clickhouse_storage::{ClickhouseStorage, StorageError};
+    use solana_transaction_status::Reward;
+
+    use super::*;
+    use crate::{errors::MainStorageError, storage::main_storage::RewardRecResult};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        CleanUnfinished(Epoch),
+        /// Slot and number of the stake accounts
+        LookupVoteAccs(u64, usize),
+        StoreRewards(Vec<(Epoch, String)>),
+        StoreYields(Vec<(String, Option<RewardYield>)>),
+        StoreCommissions(Vec<(String, Epoch, Option<u8>)>),
+    }
+
+    struct MockMainStorage {
+        events: Arc<Mutex<Vec<Event>>>,
+        stakes: HashMap<String, u64>,
+    }
+
+    #[async_trait]
+    impl ClickhouseStorage for MockMainStorage {
+        async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl MainStorage for MockMainStorage {
+        async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::CleanUnfinished(epoch));
+            Ok(())
+        }
+
+        async fn lookup_vote_acc(
+            &mut self,
+            _slot: u64,
+            _stake_acc: &str,
+        ) -> Result<Option<String>, MainStorageError> {
+            unimplemented!()
+        }
+
+        async fn lookup_vote_accs(
+            &mut self,
+            slot: u64,
+            stake_accs: &[String],
+        ) -> Result<HashMap<String, Option<String>>, MainStorageError> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::LookupVoteAccs(slot, stake_accs.len()));
+            // Give the other epoch a chance to make progress
+            sleep(Duration::from_millis(5)).await;
+            Ok(stake_accs
+                .iter()
+                .map(|stake_acc| (stake_acc.clone(), Some(format!("vote_{}", stake_acc))))
+                .collect())
+        }
+
+        async fn store_rewards_block(
+            &mut self,
+            rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+            _provisional: bool,
+        ) -> Result<(), MainStorageError> {
+            let mut events = self.events.lock().unwrap();
+            events.push(Event::StoreRewards(
+                rewards
+                    .iter()
+                    .map(|(_, epoch, _, reward, _, _)| (*epoch, reward.pubkey.clone()))
+                    .collect(),
+            ));
+            events.push(Event::StoreYields(
+                rewards
+                    .into_iter()
+                    .map(|(_, _, _, reward, _, reward_yield)| (reward.pubkey, reward_yield))
+                    .collect(),
+            ));
+            Ok(())
+        }
+
+        async fn get_delegated_stakes(
+            &mut self,
+            _slot: u64,
+        ) -> Result<HashMap<String, u64>, MainStorageError> {
+            Ok(self.stakes.clone())
+        }
+
+        async fn store_commissions_block(
+            &mut self,
+            commissions: Vec<(String, Epoch, Option<u8>)>,
+        ) -> Result<(), MainStorageError> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(Event::StoreCommissions(commissions));
+            Ok(())
+        }
+
+        async fn get_rewards_with_empty_vote_acc(
+            &mut self,
+            _after_epoch: Epoch,
+            _after_pubkey: &str,
+            _limit: u64,
+        ) -> Result<Vec<RewardRecResult>, MainStorageError> {
+            Ok(Vec::new())
+        }
+
+        async fn update_reward(
+            &mut self,
+            _vote_acc: &str,
+            _epoch: Epoch,
+            _pubkey: &str,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn get_provisional_epochs(
+            &mut self,
+        ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError> {
+            Ok(Vec::new())
+        }
+
+        async fn confirm_provisional_epoch(
+            &mut self,
+            _epoch: Epoch,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+    }
+
+    fn rewards(epoch: Epoch) -> Rewards {
+        staking_rewards(epoch, 5)
+    }
+
+    fn staking_rewards(epoch: Epoch, count: usize) -> Rewards {
+        (0..count)
+            .map(|i| Reward {
+                pubkey: format!("stake_{}_{}", epoch, i),
+                lamports: 1000,
+                post_balance: 0,
+                reward_type: Some(RewardType::Staking),
+                commission: None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn epochs_are_processed_concurrently() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut first_storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::new(),
+        };
+        let mut second_storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::new(),
+        };
+
+        tokio::join!(
+            RewardsAnalyzer::process_epoch(
+                &mut first_storage,
+                300,
+                Some(300),
+                0,
+                rewards(300),
+                182.5,
+                false
+            ),
+            RewardsAnalyzer::process_epoch(
+                &mut second_storage,
+                301,
+                Some(301),
+                0,
+                rewards(301),
+                182.5,
+                false
+            ),
+        );
+
+        let events = events.lock().unwrap().clone();
+
+        // The second epoch has been started before the first one was finished
+        let first_epoch_store = events
+            .iter()
+            .position(|event| matches!(event, Event::StoreRewards(rows) if rows[0].0 == 300))
+            .unwrap();
+        let second_epoch_lookup = events
+            .iter()
+            .position(|event| *event == Event::LookupVoteAccs(301, 5))
+            .unwrap();
+        assert!(second_epoch_lookup < first_epoch_store);
+
+        // Every insert contains the rows of a single epoch in their original order
+        for epoch in [300, 301] {
+            let stored: Vec<_> = events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::StoreRewards(rows) if rows.iter().any(|row| row.0 == epoch) => {
+                        Some(rows.clone())
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(
+                stored,
+                vec![(0..5)
+                    .map(|i| (epoch, format!("stake_{}_{}", epoch, i)))
+                    .collect::<Vec<_>>()]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn vote_accounts_are_looked_up_in_chunks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::new(),
+        };
+
+        RewardsAnalyzer::process_epoch(
+            &mut storage,
+            300,
+            Some(300),
+            0,
+            staking_rewards(300, 2500),
+            182.5,
+            false,
+        )
+        .await;
+
+        let events = events.lock().unwrap().clone();
+        let lookups: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event, Event::LookupVoteAccs(..)))
+            .cloned()
+            .collect();
+        assert_eq!(
+            lookups,
+            vec![
+                Event::LookupVoteAccs(300, 1000),
+                Event::LookupVoteAccs(300, 1000),
+                Event::LookupVoteAccs(300, 500),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn commissions_of_voting_rewards_are_stored() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::new(),
+        };
+
+        let mut rewards = rewards(300);
+        rewards.push(Reward {
+            pubkey: String::from("vote_1"),
+            lamports: 100,
+            post_balance: 0,
+            reward_type: Some(RewardType::Voting),
+            commission: Some(7),
+        });
+        rewards.push(Reward {
+            pubkey: String::from("fee_collector"),
+            lamports: 5000,
+            post_balance: 0,
+            reward_type: Some(RewardType::Fee),
+            commission: None,
         });
 
-        Ok(Self {})
+        RewardsAnalyzer::process_epoch(&mut storage, 300, Some(300), 0, rewards, 182.5, false)
+            .await;
+
+        let events = events.lock().unwrap().clone();
+        assert_eq!(
+            events.last(),
+            Some(&Event::StoreCommissions(vec![(
+                String::from("vote_1"),
+                300,
+                Some(7)
+            )]))
+        );
+    }
+
+    #[test]
+    fn reward_yield_is_compounded_over_the_year() {
+        let reward_yield = reward_yield(1_000, 1_000_000, 182.5).unwrap();
+
+        assert_eq!(reward_yield.epoch_rate, 0.001);
+        assert!((reward_yield.apy - 0.200_105).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reward_yield_of_zero_stake_is_none() {
+        assert_eq!(reward_yield(1_000, 0, 182.5), None);
+    }
+
+    #[tokio::test]
+    async fn yields_of_rewards_are_stored() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::from([
+                (String::from("vote_stake_300_0"), 1_000_000),
+                (String::from("vote_stake_300_1"), 0),
+            ]),
+        };
+
+        let mut rewards = rewards(300);
+        rewards.push(Reward {
+            pubkey: String::from("vote_stake_300_0"),
+            lamports: 100,
+            post_balance: 0,
+            reward_type: Some(RewardType::Voting),
+            commission: Some(7),
+        });
+
+        RewardsAnalyzer::process_epoch(&mut storage, 300, Some(300), 0, rewards, 182.5, false)
+            .await;
+
+        let expected = reward_yield(1_000, 1_000_000, 182.5);
+        let events = events.lock().unwrap().clone();
+        let yields = events
+            .into_iter()
+            .find_map(|event| match event {
+                Event::StoreYields(yields) => Some(yields),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            yields,
+            vec![
+                (String::from("stake_300_0"), expected),
+                // Zero stake
+                (String::from("stake_300_1"), None),
+                // Unknown stake
+                (String::from("stake_300_2"), None),
+                (String::from("stake_300_3"), None),
+                (String::from("stake_300_4"), None),
+                (String::from("vote_stake_300_0"), expected),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn yields_are_not_looked_up_without_first_block_slot() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut storage = MockMainStorage {
+            events: events.clone(),
+            stakes: HashMap::from([(String::from("vote_stake_300_0"), 1_000_000)]),
+        };
+
+        RewardsAnalyzer::process_epoch(&mut storage, 300, None, 0, rewards(300), 182.5, false)
+            .await;
+
+        let events = events.lock().unwrap().clone();
+        assert!(events.iter().all(|event| match event {
+            Event::StoreYields(yields) => yields
+                .iter()
+                .all(|(_, reward_yield)| reward_yield.is_none()),
+            _ => true,
+        }));
     }
 }
@@ -0,0 +1,290 @@
+use std::sync::OnceLock;
+
+use serde_json::{json, Value};
+use solana_transaction_status::{RewardType, Rewards};
+
+use crate::{epoch_schedule::EpochSchedule, errors::RpcError, storage::epoch_storage::Epoch};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StakeDelegation {
+    pub voter: String,
+    pub activation_epoch: Epoch,
+    pub deactivation_epoch: Epoch,
+}
+
+impl StakeDelegation {
+    /// Vote account the stake was delegated to during the `epoch`
+    pub fn voter_for_epoch(&self, epoch: Epoch) -> Option<&str> {
+        if self.activation_epoch <= epoch && epoch < self.deactivation_epoch {
+            Some(self.voter.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Reads the delegation from the `jsonParsed` representation of a stake account
+    fn from_parsed_account(account: &Value) -> Option<Self> {
+        let delegation = &account["data"]["parsed"]["info"]["stake"]["delegation"];
+
+        Some(Self {
+            voter: delegation["voter"].as_str()?.to_string(),
+            activation_epoch: delegation["activationEpoch"].as_str()?.parse().ok()?,
+            deactivation_epoch: delegation["deactivationEpoch"].as_str()?.parse().ok()?,
+        })
+    }
+}
+
+/// The first block of an epoch, its rewards are the staking and voting ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirstBlock {
+    pub slot: u64,
+    pub block_time: i64,
+    pub rewards: Rewards,
+}
+
+pub struct RpcClient {
+    url: String,
+    client: reqwest::Client,
+    /// The schedule never changes for a cluster, it's requested once
+    epoch_schedule: OnceLock<EpochSchedule>,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+            epoch_schedule: OnceLock::new(),
+        }
+    }
+
+    pub async fn get_stake_delegation(
+        &self,
+        stake_acc: &str,
+    ) -> Result<Option<StakeDelegation>, RpcError> {
+        let result = self
+            .request(
+                "getAccountInfo",
+                json!([stake_acc, { "encoding": "jsonParsed" }]),
+            )
+            .await?;
+
+        Ok(StakeDelegation::from_parsed_account(&result["value"]))
+    }
+
+    pub async fn get_epoch_schedule(&self) -> Result<EpochSchedule, RpcError> {
+        if let Some(epoch_schedule) = self.epoch_schedule.get() {
+            return Ok(*epoch_schedule);
+        }
+
+        let result = self.request("getEpochSchedule", json!([])).await?;
+        let epoch_schedule: EpochSchedule = serde_json::from_value(result)?;
+
+        Ok(*self.epoch_schedule.get_or_init(|| epoch_schedule))
+    }
+
+    /// The latest finalized slot
+    pub async fn get_slot(&self) -> Result<u64, RpcError> {
+        let result = self
+            .request("getSlot", json!([{ "commitment": "finalized" }]))
+            .await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// The first finalized block within `first_slot..=last_slot`, none if there is no such block
+    /// yet
+    pub async fn get_first_block(
+        &self,
+        first_slot: u64,
+        last_slot: u64,
+    ) -> Result<Option<FirstBlock>, RpcError> {
+        let result = self
+            .request(
+                "getBlocksWithLimit",
+                json!([first_slot, 1, { "commitment": "finalized" }]),
+            )
+            .await?;
+        let slots: Vec<u64> = serde_json::from_value(result)?;
+
+        let slot = match slots.first() {
+            Some(&slot) if slot <= last_slot => slot,
+            _ => return Ok(None),
+        };
+
+        let block = self
+            .request(
+                "getBlock",
+                json!([slot, {
+                    "commitment": "finalized",
+                    "transactionDetails": "none",
+                    "rewards": true,
+                    "maxSupportedTransactionVersion": 0,
+                }]),
+            )
+            .await?;
+
+        Ok(Some(FirstBlock::from_block(slot, block)?))
+    }
+
+    /// Sends the JSON-RPC request and returns its result
+    async fn request(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let mut response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Response(error.to_string()));
+        }
+
+        Ok(response["result"].take())
+    }
+}
+
+impl FirstBlock {
+    /// Reads the block time and the rewards of the `getBlock` result
+    fn from_block(slot: u64, block: Value) -> Result<Self, RpcError> {
+        let block_time = block["blockTime"].as_i64().unwrap_or_default();
+        let rewards: Rewards = match block.get("rewards") {
+            Some(rewards) if !rewards.is_null() => serde_json::from_value(rewards.clone())?,
+            _ => Rewards::new(),
+        };
+
+        Ok(Self {
+            slot,
+            block_time,
+            rewards: rewards
+                .into_iter()
+                .filter(|reward| {
+                    matches!(
+                        reward.reward_type,
+                        Some(RewardType::Staking) | Some(RewardType::Voting)
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stake_delegation_from_parsed_account() {
+        let account = json!({
+            "data": {
+                "parsed": {
+                    "info": {
+                        "stake": {
+                            "delegation": {
+                                "activationEpoch": "300",
+                                "deactivationEpoch": "18446744073709551615",
+                                "stake": "1000000000",
+                                "voter": "CertusDeBmqN8ZawdkxK5kFGMwBXdudvWHYwtNgNhvLu",
+                                "warmupCooldownRate": 0.25
+                            },
+                            "creditsObserved": 0
+                        }
+                    },
+                    "type": "delegated"
+                },
+                "program": "stake",
+                "space": 200
+            },
+            "executable": false,
+            "lamports": 1002282880,
+            "owner": "Stake11111111111111111111111111111111111111"
+        });
+
+        let delegation = StakeDelegation::from_parsed_account(&account).unwrap();
+
+        assert_eq!(delegation.voter_for_epoch(299), None);
+        assert_eq!(
+            delegation.voter_for_epoch(300),
+            Some("CertusDeBmqN8ZawdkxK5kFGMwBXdudvWHYwtNgNhvLu")
+        );
+        assert_eq!(
+            delegation.voter_for_epoch(400),
+            Some("CertusDeBmqN8ZawdkxK5kFGMwBXdudvWHYwtNgNhvLu")
+        );
+    }
+
+    #[test]
+    fn stake_delegation_from_initialized_account() {
+        let account = json!({
+            "data": {
+                "parsed": {
+                    "info": {
+                        "meta": {
+                            "rentExemptReserve": "2282880"
+                        }
+                    },
+                    "type": "initialized"
+                },
+                "program": "stake",
+                "space": 200
+            }
+        });
+
+        assert_eq!(StakeDelegation::from_parsed_account(&account), None);
+        assert_eq!(StakeDelegation::from_parsed_account(&Value::Null), None);
+    }
+
+    #[test]
+    fn first_block_keeps_staking_and_voting_rewards() {
+        let block = json!({
+            "blockHeight": 230000000,
+            "blockTime": 1700000000,
+            "blockhash": "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N",
+            "parentSlot": 249695999,
+            "previousBlockhash": "6w6pRyrRQMJgqUgKSvoPmrjjRTmW8yvgRBCYzBBQJAHU",
+            "rewards": [
+                {
+                    "commission": null,
+                    "lamports": 1000,
+                    "postBalance": 1001000,
+                    "pubkey": "stake_1",
+                    "rewardType": "Staking"
+                },
+                {
+                    "commission": 7,
+                    "lamports": 100,
+                    "postBalance": 10100,
+                    "pubkey": "vote_1",
+                    "rewardType": "Voting"
+                },
+                {
+                    "commission": null,
+                    "lamports": 5000,
+                    "postBalance": 105000,
+                    "pubkey": "leader",
+                    "rewardType": "Fee"
+                }
+            ]
+        });
+
+        let first_block = FirstBlock::from_block(249696000, block).unwrap();
+
+        assert_eq!(first_block.slot, 249696000);
+        assert_eq!(first_block.block_time, 1700000000);
+        assert_eq!(
+            first_block
+                .rewards
+                .iter()
+                .map(|reward| (reward.pubkey.as_str(), reward.commission))
+                .collect::<Vec<_>>(),
+            vec![("stake_1", None), ("vote_1", Some(7))]
+        );
+    }
+}
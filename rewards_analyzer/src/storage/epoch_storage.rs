@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use solana_transaction_status::{Reward, RewardType, Rewards};
 use tokio_postgres::{types::Json, Client, NoTls};
 
@@ -8,43 +10,57 @@ pub type Epoch = u64;
 pub struct EpochStorage {}
 
 impl EpochStorage {
-    pub async fn get_not_parsed_first_block_epoch(
+    /// Claims the next epoch with not parsed rewards by moving it into the in-progress state,
+    /// so concurrent workers never get the same epoch
+    pub async fn claim_not_parsed_first_block_epoch(
     ) -> Result<(Option<Epoch>, Option<u64>), EpochStorageError> {
         let client = Self::connect().await?;
 
         let stmt = client
             .prepare(
                 "
-            select
-                epoch,
-                first_block
-            from
+            update
                 epochs
+            set
+                rewards_parsing_status=2
             where
-                first_block_json is not null and
-                rewards_parsing_status=0 and
-                epoch <= (
-                    select  
-                        least(max(slot/432000), 99999999999)
+                epoch = (
+                    select
+                        epoch
                     from
-                        signatures s,
-                        (
-                        select
-                            first_slot
-                        from
-                            epochs
-                        where
-                            first_block_json is not null and
-                            rewards_parsing_status=0
-                        order by
-                            epoch DESC LIMIT 1
-                        ) e
+                        epochs
                     where
-                        err='' and
-                        loading_status != 2 and
-                        slot < e.first_slot
+                        first_block_json is not null and
+                        rewards_parsing_status=0 and
+                        epoch <= (
+                            select  
+                                least(max(slot/432000), 99999999999)
+                            from
+                                signatures s,
+                                (
+                                select
+                                    first_slot
+                                from
+                                    epochs
+                                where
+                                    first_block_json is not null and
+                                    rewards_parsing_status=0
+                                order by
+                                    epoch DESC LIMIT 1
+                                ) e
+                            where
+                                err='' and
+                                loading_status != 2 and
+                                slot < e.first_slot
+                        )
+                    order by
+                        epoch
+                    LIMIT 1
+                    for update skip locked
                 )
-            LIMIT 1
+            returning
+                epoch,
+                first_block
             ",
             )
             .await?;
@@ -63,6 +79,36 @@ impl EpochStorage {
         }
     }
 
+    /// Returns epochs claimed by a previous run back to the queue. Their rows are removed
+    /// by `clean_unfinished` before they are inserted again
+    pub async fn release_claimed_epochs() -> Result<u64, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(
+                "UPDATE epochs SET rewards_parsing_status = 0 WHERE rewards_parsing_status = 2",
+            )
+            .await?;
+
+        Ok(client.execute(&stmt, &[]).await?)
+    }
+
+    /// Returns epochs which rewards are being analyzed right now
+    pub async fn get_claimed_epochs() -> Result<Vec<Epoch>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare("SELECT epoch FROM epochs WHERE rewards_parsing_status = 2")
+            .await?;
+
+        let response = client.query(&stmt, &[]).await?;
+
+        Ok(response
+            .iter()
+            .map(|row| row.get::<_, i32>(0) as Epoch)
+            .collect())
+    }
+
     pub async fn mark_rewards_parsed(epoch: Epoch) -> Result<(), EpochStorageError> {
         let client = Self::connect().await?;
 
@@ -130,6 +176,60 @@ impl EpochStorage {
         Ok((block_time, rewards))
     }
 
+    /// Returns the first block slot of every tracked epoch within `from..=to`, the epochs
+    /// whose first block isn't loaded yet are not in the map
+    pub async fn get_first_blocks(
+        from: Epoch,
+        to: Epoch,
+    ) -> Result<HashMap<Epoch, u64>, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(
+                "SELECT epoch, first_block FROM epochs \
+                WHERE epoch >= $1 AND epoch <= $2 AND first_block IS NOT NULL",
+            )
+            .await?;
+
+        let response = client.query(&stmt, &[&(from as i32), &(to as i32)]).await?;
+
+        Ok(response
+            .iter()
+            .map(|row| (row.get::<_, i32>(0) as Epoch, row.get::<_, i32>(1) as u64))
+            .collect())
+    }
+
+    /// Marks the rewards of the epoch parsed if the epoch has the `first_block`, the provisional
+    /// rewards of that block don't need to be analyzed again. Returns false when the epoch is
+    /// being analyzed by a worker or has another first block
+    pub async fn confirm_provisional(
+        epoch: Epoch,
+        first_block: u64,
+    ) -> Result<bool, EpochStorageError> {
+        let client = Self::connect().await?;
+
+        let stmt = client
+            .prepare(
+                "UPDATE epochs SET rewards_parsing_status = 1 \
+                WHERE epoch = $1 AND first_block = $2 AND rewards_parsing_status != 2",
+            )
+            .await?;
+
+        let updated = client
+            .execute(&stmt, &[&(epoch as i32), &(first_block as i32)])
+            .await?;
+
+        Ok(updated > 0)
+    }
+
+    /// Checks that the epoch storage accepts connections and queries
+    pub async fn ping() -> Result<(), EpochStorageError> {
+        let client = Self::connect().await?;
+        client.simple_query("SELECT 1").await?;
+
+        Ok(())
+    }
+
     async fn connect() -> Result<Client, EpochStorageError> {
         let register_current_state = Register::current().clone();
         let url = register_current_state.configuration.epoch_storage_url();
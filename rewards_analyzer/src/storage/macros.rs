@@ -6,6 +6,9 @@ macro_rules! repeat_until_ok {
                 Ok(result) => break result,
                 Err(err) => {
                     log::error!("Error in func {}: {}", stringify!($func), err);
+                    $crate::prometheus::ERRORS_COUNT
+                        .with_label_values(&[indexer_errors::Classify::classify(&err).as_str()])
+                        .inc();
                     tokio::time::sleep(std::time::Duration::from_secs($sleep_time)).await;
                 }
             }
@@ -1,71 +1,170 @@
 use super::{
-    super::epoch_storage::Epoch, LookupVoteAccRec, MainStorage, RewardRec, RewardRecResult,
+    super::epoch_storage::Epoch, CommissionRec, DelegatedStakeRec, LookupVoteAccRec,
+    LookupVoteAccsRec, MainStorage, ProvisionalEpochRec, RewardRec, RewardRecResult, RewardYield,
+    LOOKUP_VOTE_ACC_WINDOW,
 };
 use crate::errors::MainStorageError;
 use anyhow::Result;
 use async_trait::async_trait;
 use clickhouse_http::Client;
-use dsn::DSN;
+use clickhouse_storage::{schema::TableColumn, storage::http, ClickhouseStorage, StorageError};
 use log::info;
 use solana_transaction_status::{Reward, RewardType};
+use std::collections::HashMap;
 
 pub struct HttpClient {
     client: Client,
+    table_prefix: String,
 }
 
 impl HttpClient {
-    pub async fn new(db_creds: DSN) -> Result<Self, MainStorageError> {
-        let protocol = db_creds.driver;
-        let address = db_creds.address;
-
-        let mut client = if protocol == "https" {
-            Client::with_https_client().with_url(format!("{protocol}://{address}"))
-        } else {
-            Client::default().with_url(format!("{protocol}://{address}"))
-        };
-
-        if let Some(user_name) = db_creds.username {
-            client = client.with_user(user_name);
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            table_prefix: String::new(),
         }
-        if let Some(password) = db_creds.password {
-            client = client.with_password(password);
+    }
+
+    /// Name of the `table` with the prefix of the storage
+    fn table(&self, table: &str) -> String {
+        format!("{}{}", self.table_prefix, table)
+    }
+
+    /// The latest delegation or undelegation of the `stake_acc` within `from_slot..=to_slot`
+    async fn latest_delegation(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Option<LookupVoteAccRec>, MainStorageError> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "
+                SELECT * FROM (
+                    SELECT 
+                        slot, raw_instruction_idx, vote_acc, 1 as is_delegation
+                    FROM {delegations}
+                    WHERE stake_acc = ? AND slot >= ? AND slot <= ?
+                    ORDER BY slot DESC, raw_instruction_idx DESC
+                    LIMIT 1
+                    UNION ALL 
+                    SELECT 
+                        slot, raw_instruction_idx, vote_acc, 0 as is_delegation
+                    FROM {undelegations}
+                    WHERE stake_acc = ? AND slot >= ? AND slot <= ?
+                    ORDER BY slot DESC, raw_instruction_idx DESC
+                    LIMIT 1
+                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1
+                ",
+                delegations = self.table("delegations"),
+                undelegations = self.table("undelegations")
+            ))
+            .bind(stake_acc)
+            .bind(from_slot)
+            .bind(to_slot)
+            .bind(stake_acc)
+            .bind(from_slot)
+            .bind(to_slot)
+            .fetch::<LookupVoteAccRec>()?;
+
+        Ok(cursor.next().await?)
+    }
+
+    /// The latest delegation or undelegation of each of the `stake_accs` within
+    /// `from_slot..=to_slot`, the stake accounts without any are not in the map
+    async fn latest_delegations(
+        &mut self,
+        stake_accs: &[String],
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<HashMap<String, LookupVoteAccsRec>, MainStorageError> {
+        if stake_accs.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        if let Some(db) = db_creds.database {
-            client = client.with_database(db);
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "
+                SELECT stake_acc, vote_acc, is_delegation FROM (
+                    SELECT
+                        stake_acc, slot, raw_instruction_idx, vote_acc, 1 as is_delegation
+                    FROM {delegations}
+                    WHERE stake_acc IN ? AND slot >= ? AND slot <= ?
+                    UNION ALL
+                    SELECT
+                        stake_acc, slot, raw_instruction_idx, vote_acc, 0 as is_delegation
+                    FROM {undelegations}
+                    WHERE stake_acc IN ? AND slot >= ? AND slot <= ?
+                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1 BY stake_acc
+                ",
+                delegations = self.table("delegations"),
+                undelegations = self.table("undelegations")
+            ))
+            .bind(stake_accs)
+            .bind(from_slot)
+            .bind(to_slot)
+            .bind(stake_accs)
+            .bind(from_slot)
+            .bind(to_slot)
+            .fetch::<LookupVoteAccsRec>()?;
+
+        let mut latest = HashMap::new();
+
+        while let Some(row) = cursor.next().await? {
+            latest.insert(row.stake_acc.clone(), row);
         }
 
-        Ok(Self { client })
+        Ok(latest)
     }
 }
 
 #[async_trait]
-impl MainStorage for HttpClient {
-    async fn execute(&mut self, ddl: &str) -> Result<(), MainStorageError> {
-        let query = self.client.query(ddl);
-        query.execute().await?;
-        Ok(())
+impl ClickhouseStorage for HttpClient {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+        http::execute(&self.client, ddl).await
     }
 
-    async fn migration_exists(&mut self, version: &str) -> Result<bool, MainStorageError> {
-        let mut cursor = self
-            .client
-            .query("SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = ?")
-            .bind(version)
-            .fetch::<u64>()?;
-
-        if let Some(count) = cursor.next().await? {
-            Ok(count > 0)
-        } else {
-            Ok(false)
-        }
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        http::migrations_table_exists(&self.client).await
+    }
+
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+        http::migration_exists(&self.client, version).await
+    }
+
+    async fn migration_lock_holder(
+        &mut self,
+        _owner: &str,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        http::migration_lock_holder(&self.client, ttl_secs).await
+    }
+
+    async fn describe_table(
+        &mut self,
+        table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        Ok(Some(http::describe_table(&self.client, table).await?))
     }
+}
 
+#[async_trait]
+impl MainStorage for HttpClient {
     #[cfg(feature = "on_ch_cluster")]
     async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
         let ddl = format!(
-            "ALTER TABLE rewards ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
-            epoch - 1
+            "ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.query(&ddl).execute().await?;
+
+        let ddl = format!(
+            "ALTER TABLE {vote_account_commissions} ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
+            epoch - 1,
+            vote_account_commissions = self.table("vote_account_commissions")
         );
         self.client.query(&ddl).execute().await?;
 
@@ -74,7 +173,18 @@ impl MainStorage for HttpClient {
 
     #[cfg(not(feature = "on_ch_cluster"))]
     async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
-        let ddl = format!("ALTER TABLE rewards DELETE WHERE epoch = {}", epoch - 1);
+        let ddl = format!(
+            "ALTER TABLE {rewards} DELETE WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.query(&ddl).execute().await?;
+
+        let ddl = format!(
+            "ALTER TABLE {vote_account_commissions} DELETE WHERE epoch = {}",
+            epoch - 1,
+            vote_account_commissions = self.table("vote_account_commissions")
+        );
         self.client.query(&ddl).execute().await?;
 
         Ok(())
@@ -85,49 +195,62 @@ impl MainStorage for HttpClient {
         slot: u64,
         stake_acc: &str,
     ) -> Result<Option<String>, MainStorageError> {
-        let mut cursor = self
-            .client
-            .query(
-                "
-                SELECT * FROM (
-                    SELECT 
-                        slot, raw_instruction_idx, vote_acc, 1 as is_delegation
-                    FROM delegations
-                    WHERE stake_acc = ? AND slot <= ?
-                    ORDER BY slot DESC, raw_instruction_idx DESC
-                    LIMIT 1
-                    UNION ALL 
-                    SELECT 
-                        slot, raw_instruction_idx, vote_acc, 0 as is_delegation
-                    FROM undelegations
-                    WHERE stake_acc = ? AND slot <= ?
-                    ORDER BY slot DESC, raw_instruction_idx DESC
-                    LIMIT 1
-                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1
-                ",
-            )
-            .bind(stake_acc.clone())
-            .bind(slot)
-            .bind(stake_acc.clone())
-            .bind(slot)
-            .fetch::<LookupVoteAccRec>()?;
+        let window_start = slot.saturating_sub(LOOKUP_VOTE_ACC_WINDOW);
+        let mut latest = self
+            .latest_delegation(stake_acc, window_start, slot)
+            .await?;
 
-        if let Some(row) = cursor.next().await? {
-            if row.is_delegation {
-                Ok(row.vote_acc)
-            } else {
-                Ok(None)
-            }
-        } else {
-            Ok(None)
+        if latest.is_none() && window_start > 0 {
+            latest = self
+                .latest_delegation(stake_acc, 0, window_start - 1)
+                .await?;
         }
+
+        Ok(latest
+            .filter(|row| row.is_delegation)
+            .and_then(|row| row.vote_acc))
+    }
+
+    async fn lookup_vote_accs(
+        &mut self,
+        slot: u64,
+        stake_accs: &[String],
+    ) -> Result<HashMap<String, Option<String>>, MainStorageError> {
+        let window_start = slot.saturating_sub(LOOKUP_VOTE_ACC_WINDOW);
+        let mut latest = self
+            .latest_delegations(stake_accs, window_start, slot)
+            .await?;
+
+        let missing: Vec<String> = stake_accs
+            .iter()
+            .filter(|stake_acc| !latest.contains_key(*stake_acc))
+            .cloned()
+            .collect();
+        if !missing.is_empty() && window_start > 0 {
+            latest.extend(
+                self.latest_delegations(&missing, 0, window_start - 1)
+                    .await?,
+            );
+        }
+
+        Ok(stake_accs
+            .iter()
+            .map(|stake_acc| {
+                let vote_acc = latest
+                    .get(stake_acc)
+                    .filter(|row| row.is_delegation)
+                    .and_then(|row| row.vote_acc.clone());
+                (stake_acc.clone(), vote_acc)
+            })
+            .collect())
     }
 
     async fn store_rewards_block(
         &mut self,
-        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64)>,
+        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+        provisional: bool,
     ) -> Result<(), MainStorageError> {
-        let mut insert = self.client.insert("rewards")?;
+        let mut insert = self.client.insert(&self.table("rewards"))?;
 
         for reward in rewards {
             let reward_type = reward.3.reward_type.map(|rew_type| match rew_type {
@@ -148,6 +271,73 @@ impl MainStorage for HttpClient {
                     commission: reward.3.commission,
                     first_block_slot: reward.2,
                     block_time: reward.4 as u32,
+                    epoch_rate: reward.5.map(|reward_yield| reward_yield.epoch_rate),
+                    apy: reward.5.map(|reward_yield| reward_yield.apy),
+                    provisional: provisional as u8,
+                })
+                .await?;
+        }
+
+        insert.end().await?;
+
+        Ok(())
+    }
+
+    async fn get_delegated_stakes(
+        &mut self,
+        slot: u64,
+    ) -> Result<HashMap<String, u64>, MainStorageError> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "
+                SELECT assumeNotNull(latest.1) AS vote_acc, sum(latest.2) AS stake FROM (
+                    SELECT
+                        argMax((vote_acc, amount, is_delegation), (slot, raw_instruction_idx)) AS latest
+                    FROM (
+                        SELECT stake_acc, slot, raw_instruction_idx, vote_acc, amount, 1 AS is_delegation
+                        FROM {delegations}
+                        WHERE slot <= ?
+                        UNION ALL
+                        SELECT stake_acc, slot, raw_instruction_idx, vote_acc, amount, 0 AS is_delegation
+                        FROM {undelegations}
+                        WHERE slot <= ?
+                    )
+                    GROUP BY stake_acc
+                )
+                WHERE latest.3 = 1 AND latest.1 IS NOT NULL
+                GROUP BY vote_acc
+                ",
+                delegations = self.table("delegations"),
+                undelegations = self.table("undelegations")
+            ))
+            .bind(slot)
+            .bind(slot)
+            .fetch::<DelegatedStakeRec>()?;
+
+        let mut stakes = HashMap::new();
+
+        while let Some(row) = cursor.next().await? {
+            stakes.insert(row.vote_acc, row.stake);
+        }
+
+        Ok(stakes)
+    }
+
+    async fn store_commissions_block(
+        &mut self,
+        commissions: Vec<(String, Epoch, Option<u8>)>,
+    ) -> Result<(), MainStorageError> {
+        let mut insert = self
+            .client
+            .insert(&self.table("vote_account_commissions"))?;
+
+        for (vote_account, epoch, commission) in commissions {
+            insert
+                .write(&CommissionRec {
+                    epoch: epoch - 1,
+                    vote_account,
+                    commission,
                 })
                 .await?;
         }
@@ -159,10 +349,13 @@ impl MainStorage for HttpClient {
 
     async fn get_rewards_with_empty_vote_acc(
         &mut self,
+        after_epoch: Epoch,
+        after_pubkey: &str,
+        limit: u64,
     ) -> Result<Vec<RewardRecResult>, MainStorageError> {
         let mut cursor = self
             .client
-            .query(
+            .query(&format!(
                 "
         SELECT
             vote_account,
@@ -174,11 +367,20 @@ impl MainStorage for HttpClient {
             commission,
             first_block_slot,
             block_time
-        FROM rewards
+        FROM {rewards}
         WHERE
             vote_account = ''
-            and reward_type = 'staking'",
-            )
+            and reward_type = 'staking'
+            and epoch >= ?
+            and (epoch, pubkey) > (?, ?)
+        ORDER BY epoch, pubkey
+        LIMIT ?",
+                rewards = self.table("rewards")
+            ))
+            .bind(after_epoch)
+            .bind(after_epoch)
+            .bind(after_pubkey)
+            .bind(limit)
             .fetch::<RewardRecResult>()?;
 
         let mut reward_records: Vec<RewardRecResult> = Vec::new();
@@ -210,10 +412,12 @@ impl MainStorage for HttpClient {
         pubkey: &str,
     ) -> Result<(), MainStorageError> {
         let request = format!("
-        ALTER TABLE rewards ON CLUSTER '{{cluster}}' UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
+        ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
         vote_acc,
         epoch,
-        pubkey);
+        pubkey,
+        rewards = self.table("rewards")
+        );
 
         let ddl = request.as_str();
         self.client.query(ddl).execute().await?;
@@ -234,8 +438,11 @@ impl MainStorage for HttpClient {
     ) -> Result<(), MainStorageError> {
         let request = format!(
             "
-        ALTER TABLE rewards UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
-            vote_acc, epoch, pubkey
+        ALTER TABLE {rewards} UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
+            vote_acc,
+            epoch,
+            pubkey,
+            rewards = self.table("rewards")
         );
 
         let ddl = request.as_str();
@@ -247,4 +454,53 @@ impl MainStorage for HttpClient {
 
         Ok(())
     }
+
+    async fn get_provisional_epochs(
+        &mut self,
+    ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError> {
+        let mut cursor = self
+            .client
+            .query(&format!(
+                "SELECT DISTINCT epoch + 1 AS epoch, first_block_slot FROM {rewards} \
+                WHERE provisional = 1 ORDER BY epoch",
+                rewards = self.table("rewards")
+            ))
+            .fetch::<ProvisionalEpochRec>()?;
+
+        let mut epochs = Vec::new();
+
+        while let Some(row) = cursor.next().await? {
+            epochs.push((row.epoch, row.first_block_slot));
+        }
+
+        Ok(epochs)
+    }
+
+    #[cfg(feature = "on_ch_cluster")]
+    async fn confirm_provisional_epoch(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+        let ddl = format!(
+            "ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' UPDATE provisional = 0 WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.query(&ddl).execute().await?;
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "on_ch_cluster"))]
+    async fn confirm_provisional_epoch(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+        let ddl = format!(
+            "ALTER TABLE {rewards} UPDATE provisional = 0 WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.query(&ddl).execute().await?;
+
+        Ok(())
+    }
+
+    fn set_table_prefix(&mut self, table_prefix: &str) {
+        self.table_prefix = table_prefix.to_string();
+    }
 }
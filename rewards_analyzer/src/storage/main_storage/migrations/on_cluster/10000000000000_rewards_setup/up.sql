@@ -1,4 +1,4 @@
-CREATE TABLE IF NOT EXISTS rewards ON CLUSTER '{cluster}'
+CREATE TABLE IF NOT EXISTS {table_prefix}rewards ON CLUSTER '{cluster}'
 (
     `vote_account` String,
     `epoch` UInt64,
@@ -1,4 +1,4 @@
-CREATE TABLE IF NOT EXISTS rewards
+CREATE TABLE IF NOT EXISTS {table_prefix}rewards
 (
     `vote_account` String,
     `epoch` UInt64,
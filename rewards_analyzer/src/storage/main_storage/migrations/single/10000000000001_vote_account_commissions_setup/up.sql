@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS {table_prefix}vote_account_commissions
+(
+    `epoch` UInt64,
+    `vote_account` String,
+    `commission` Nullable(UInt8)
+)
+ENGINE = MergeTree()
+ORDER BY (epoch, vote_account)
+SETTINGS index_granularity = 8192;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE {table_prefix}rewards
+    ADD COLUMN IF NOT EXISTS `epoch_rate` Nullable(Float64) AFTER block_time,
+    ADD COLUMN IF NOT EXISTS `apy` Nullable(Float64) AFTER epoch_rate;
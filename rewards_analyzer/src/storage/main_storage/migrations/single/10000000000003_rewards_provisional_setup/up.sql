@@ -0,0 +1,2 @@
+ALTER TABLE {table_prefix}rewards
+    ADD COLUMN IF NOT EXISTS `provisional` UInt8 DEFAULT 0 AFTER apy;
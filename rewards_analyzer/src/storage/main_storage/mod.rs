@@ -1,17 +1,35 @@
 use super::epoch_storage::Epoch;
 use crate::{errors::MainStorageError, register::Register};
 use async_trait::async_trait;
+use clickhouse_storage::{ClickhouseStorage, Connect, StorageError};
 use serde::{Deserialize, Serialize};
 use solana_transaction_status::Reward;
+use std::collections::HashMap;
 
 pub mod http_client;
 pub mod migrations;
+pub mod schema;
 pub mod tcp_client;
 
+/// Delegations and undelegations are partitioned by `intDiv(slot, 1000000)`. The vote account
+/// lookup searches this number of the latest slots first, so only a few partitions are read
+/// unless the stake account wasn't (un)delegated recently
+pub const LOOKUP_VOTE_ACC_WINDOW: u64 = 10_000_000;
+
 #[derive(Row, Deserialize)]
 pub struct LookupVoteAccRec {
     pub slot: u64,
-    pub raw_instruction_idx: u16,
+    /// Position of the instruction in its transaction, `raw_idx` of `solana_instruction_parser`,
+    /// the latest of the (un)delegations of a slot has the highest one
+    pub raw_instruction_idx: u32,
+    pub vote_acc: Option<String>,
+    pub is_delegation: bool,
+}
+
+/// The latest delegation or undelegation of one of the stake accounts looked up at once
+#[derive(Row, Deserialize)]
+pub struct LookupVoteAccsRec {
+    pub stake_acc: String,
     pub vote_acc: Option<String>,
     pub is_delegation: bool,
 }
@@ -24,7 +42,23 @@ pub struct DelegationRec {
     pub vote_acc: Option<String>,
     pub tx_signature: String,
     pub amount: u64,
-    pub raw_instruction_idx: u16,
+    pub raw_instruction_idx: u32,
+}
+
+/// Total amount of the stake accounts delegated to the vote account
+#[derive(Row, Deserialize)]
+pub struct DelegatedStakeRec {
+    pub vote_acc: String,
+    pub stake: u64,
+}
+
+/// Inflation rewards of the stake delegated to a vote account relative to the stake
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardYield {
+    /// Rewards of the epoch divided by the stake
+    pub epoch_rate: f64,
+    /// The epoch rate compounded over a year of epochs
+    pub apy: f64,
 }
 
 #[derive(Row, Serialize)]
@@ -38,6 +72,19 @@ pub struct RewardRec<'a> {
     pub commission: Option<u8>,
     pub first_block_slot: Option<u64>,
     pub block_time: u32,
+    pub epoch_rate: Option<f64>,
+    pub apy: Option<f64>,
+    /// 1 while the first block of the epoch was taken from the RPC and not confirmed by the
+    /// epoch storage yet
+    pub provisional: u8,
+}
+
+/// Commission of the vote account observed in its voting reward of the epoch
+#[derive(Row, Serialize)]
+pub struct CommissionRec {
+    pub epoch: Epoch,
+    pub vote_account: String,
+    pub commission: Option<u8>,
 }
 
 #[derive(Default, Row, Deserialize)]
@@ -53,22 +100,56 @@ pub struct RewardRecResult {
     pub block_time: u32,
 }
 
+/// Analyzed epoch with provisional rewards and the first block they were taken from
+#[derive(Row, Deserialize)]
+pub struct ProvisionalEpochRec {
+    pub epoch: Epoch,
+    pub first_block_slot: Option<u64>,
+}
+
 #[async_trait]
-pub trait MainStorage: Send {
-    async fn execute(&mut self, ddl: &str) -> Result<(), MainStorageError>;
-    async fn migration_exists(&mut self, version: &str) -> Result<bool, MainStorageError>;
+pub trait MainStorage: ClickhouseStorage {
     async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError>;
     async fn lookup_vote_acc(
         &mut self,
         slot: u64,
         stake_acc: &str,
     ) -> Result<Option<String>, MainStorageError>;
+    /// `lookup_vote_acc` of every stake account with one query, plus one for the accounts
+    /// which weren't (un)delegated within the latest slots. Every stake account is in the
+    /// returned map, the callers keep the number of the accounts of a call bounded
+    async fn lookup_vote_accs(
+        &mut self,
+        slot: u64,
+        stake_accs: &[String],
+    ) -> Result<HashMap<String, Option<String>>, MainStorageError>;
+    /// Stores the rewards of the epoch, `provisional` ones are from a first block which the
+    /// epoch storage doesn't know yet
     async fn store_rewards_block(
         &mut self,
-        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64)>,
+        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+        provisional: bool,
     ) -> Result<(), MainStorageError>;
+    /// Returns the stake delegated to every vote account as of the `slot`: the sum of the
+    /// amounts of the stake accounts whose latest delegation or undelegation up to the slot is a
+    /// delegation to the vote account
+    async fn get_delegated_stakes(
+        &mut self,
+        slot: u64,
+    ) -> Result<HashMap<String, u64>, MainStorageError>;
+    /// Stores (vote account, epoch, commission) of the voting rewards, the epoch is the one the
+    /// rewards are paid in, as in `store_rewards_block`
+    async fn store_commissions_block(
+        &mut self,
+        commissions: Vec<(String, Epoch, Option<u8>)>,
+    ) -> Result<(), MainStorageError>;
+    /// Returns up to `limit` staking rewards without vote account which go after
+    /// (`after_epoch`, `after_pubkey`), ordered by epoch and pubkey
     async fn get_rewards_with_empty_vote_acc(
         &mut self,
+        after_epoch: Epoch,
+        after_pubkey: &str,
+        limit: u64,
     ) -> Result<Vec<RewardRecResult>, MainStorageError>;
     async fn update_reward(
         &mut self,
@@ -76,20 +157,36 @@ pub trait MainStorage: Send {
         epoch: Epoch,
         pubkey: &str,
     ) -> Result<(), MainStorageError>;
+    /// Returns (epoch, first block slot) of the epochs with provisional rewards, the epoch is
+    /// the analyzed one as in `clean_unfinished`
+    async fn get_provisional_epochs(
+        &mut self,
+    ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError>;
+    /// Marks the provisional rewards of the analyzed epoch as confirmed
+    async fn confirm_provisional_epoch(&mut self, epoch: Epoch) -> Result<(), MainStorageError>;
+    /// Names the tables the storage reads and writes with the `table_prefix`, a storage
+    /// without tables ignores it
+    fn set_table_prefix(&mut self, _table_prefix: &str) {}
 }
 
-pub async fn connect_main_storage() -> Result<Box<dyn MainStorage>, MainStorageError> {
-    let register_current_state = Register::current().clone();
-    let url = register_current_state.configuration.main_storage_url();
-    let dsn = dsn::parse(url).unwrap();
-
-    if dsn.driver == *"https" || dsn.driver == *"http" {
-        return Ok(Box::new(http_client::HttpClient::new(dsn).await?));
+#[async_trait]
+impl Connect for Box<dyn MainStorage> {
+    async fn from_http(client: clickhouse_http::Client) -> Result<Self, StorageError> {
+        Ok(Box::new(http_client::HttpClient::new(client)))
     }
 
-    if dsn.driver == *"tcp" {
-        return Ok(Box::new(tcp_client::TcpClient::new(dsn).await?));
+    async fn from_tcp(pool: clickhouse_rs::Pool) -> Result<Self, StorageError> {
+        Ok(Box::new(tcp_client::TcpClient::new(pool)))
     }
+}
+
+pub async fn connect_main_storage() -> Result<Box<dyn MainStorage>, MainStorageError> {
+    let register_current_state = Register::current().clone();
+    let configuration = &register_current_state.configuration;
+
+    let mut storage: Box<dyn MainStorage> =
+        clickhouse_storage::connect(configuration.main_storage_url()).await?;
+    storage.set_table_prefix(configuration.main_storage_table_prefix());
 
-    Err(MainStorageError::UnknownProtocol)
+    Ok(storage)
 }
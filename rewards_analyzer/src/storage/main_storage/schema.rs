@@ -0,0 +1,17 @@
+use clickhouse_http::Row;
+use clickhouse_storage::WrittenTable;
+
+use super::{CommissionRec, RewardRec};
+
+/// Tables the analyzer inserts into with the columns of the rows of the HTTP client. The TCP
+/// client writes the same columns, so a new field of a row is checked once it's added here
+pub const WRITTEN_TABLES: &[WrittenTable] = &[
+    WrittenTable {
+        table: "rewards",
+        columns: RewardRec::COLUMN_NAMES,
+    },
+    WrittenTable {
+        table: "vote_account_commissions",
+        columns: CommissionRec::COLUMN_NAMES,
+    },
+];
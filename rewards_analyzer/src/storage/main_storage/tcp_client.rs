@@ -1,4 +1,7 @@
-use super::{super::epoch_storage::Epoch, MainStorage, RewardRecResult};
+use super::{
+    super::epoch_storage::Epoch, LookupVoteAccRec, LookupVoteAccsRec, MainStorage, RewardRecResult,
+    RewardYield, LOOKUP_VOTE_ACC_WINDOW,
+};
 use crate::errors::MainStorageError;
 use async_trait::async_trait;
 use chrono_tz::Tz;
@@ -7,105 +10,231 @@ use clickhouse_rs::{
     types::{Block, Value},
     ClientHandle, Pool,
 };
-use dsn::DSN;
+use clickhouse_storage::{schema::TableColumn, storage::tcp, ClickhouseStorage, StorageError};
 use futures::future::Future;
 use futures::future::TryFuture;
 use futures::TryFutureExt;
 use log::info;
 
 use solana_transaction_status::{Reward, RewardType};
+use std::collections::HashMap;
 
 pub struct TcpClient {
     // client: ClientHandle,
     client: Pool,
+    table_prefix: String,
 }
 
 impl TcpClient {
-    pub async fn new(db_creds: DSN) -> Result<Self, MainStorageError> {
-        let mut database_url = format!("{}://", db_creds.driver);
-
-        if let Some(user_name) = db_creds.username {
-            database_url = format!("{database_url}{user_name}");
+    pub fn new(client: Pool) -> Self {
+        Self {
+            client,
+            table_prefix: String::new(),
         }
-        if let Some(password) = db_creds.password {
-            database_url = format!("{database_url}:{password}");
-        }
-
-        let address = db_creds.address;
-        database_url = format!("{database_url}@{address}");
-
-        if let Some(db) = db_creds.database {
-            database_url = format!("{database_url}/{db}");
-        }
-
-        let client = Self::connect(&database_url).await?;
-        Ok(Self { client })
     }
 
-    pub async fn connect(url: &str) -> Result<Pool, MainStorageError> {
-        let pool = Pool::new(url);
-        // let pool = pool.max_size(1).build();
-        // let client = pool.get_handle().await?;
-
-        Ok(pool)
+    /// Name of the `table` with the prefix of the storage
+    fn table(&self, table: &str) -> String {
+        format!("{}{}", self.table_prefix, table)
     }
-}
 
-#[async_trait]
-impl MainStorage for TcpClient {
-    async fn execute(&mut self, ddl: &str) -> Result<(), MainStorageError> {
-        // self.client
-        //     .get_handle()
-        //     .and_then(move |mut c| c.execute(ddl))
-        //     .await?;
+    /// The latest delegation or undelegation of the `stake_acc` within `from_slot..=to_slot`
+    async fn latest_delegation(
+        &mut self,
+        stake_acc: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Option<LookupVoteAccRec>, MainStorageError> {
+        let ddl = format!(
+            "
+                SELECT * FROM (
+                    SELECT 
+                        slot, raw_instruction_idx, vote_acc, 1 as is_delegation
+                    FROM {delegations}
+                    WHERE stake_acc = '{}' AND slot >= {} AND slot <= {}
+                    ORDER BY slot DESC, raw_instruction_idx DESC
+                    LIMIT 1
+                    UNION ALL 
+                    SELECT 
+                        slot, raw_instruction_idx, vote_acc, 0 as is_delegation
+                    FROM {undelegations}
+                    WHERE stake_acc = '{}' AND slot >= {} AND slot <= {}
+                    ORDER BY slot DESC, raw_instruction_idx DESC
+                    LIMIT 1
+                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1
+            ",
+            stake_acc,
+            from_slot,
+            to_slot,
+            stake_acc,
+            from_slot,
+            to_slot,
+            delegations = self.table("delegations"),
+            undelegations = self.table("undelegations")
+        );
 
-        self.client.get_handle().await?.execute(ddl).await?;
+        let block = self
+            .client
+            .get_handle()
+            .await?
+            .query(ddl)
+            .fetch_all()
+            .await?;
 
-        Ok(())
+        let row = match block.rows().next() {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let is_delegation: u8 = row.get(3)?;
+
+        Ok(Some(LookupVoteAccRec {
+            slot: row.get(0)?,
+            raw_instruction_idx: row.get(1)?,
+            vote_acc: row.get(2)?,
+            is_delegation: is_delegation != 0,
+        }))
     }
 
-    async fn migration_exists(&mut self, version: &str) -> Result<bool, MainStorageError> {
-        let query = &format!(
-            "SELECT COUNT(*) AS count FROM __schema_migrations WHERE version = '{}'",
-            version
+    /// The latest delegation or undelegation of each of the `stake_accs` within
+    /// `from_slot..=to_slot`, the stake accounts without any are not in the map
+    async fn latest_delegations(
+        &mut self,
+        stake_accs: &[String],
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<HashMap<String, LookupVoteAccsRec>, MainStorageError> {
+        if stake_accs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let stake_accs = stake_accs
+            .iter()
+            .map(|stake_acc| format!("'{}'", stake_acc))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let ddl = format!(
+            "
+                SELECT stake_acc, vote_acc, is_delegation FROM (
+                    SELECT
+                        stake_acc, slot, raw_instruction_idx, vote_acc, 1 as is_delegation
+                    FROM {delegations}
+                    WHERE stake_acc IN ({}) AND slot >= {} AND slot <= {}
+                    UNION ALL
+                    SELECT
+                        stake_acc, slot, raw_instruction_idx, vote_acc, 0 as is_delegation
+                    FROM {undelegations}
+                    WHERE stake_acc IN ({}) AND slot >= {} AND slot <= {}
+                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1 BY stake_acc
+            ",
+            stake_accs,
+            from_slot,
+            to_slot,
+            stake_accs,
+            from_slot,
+            to_slot,
+            delegations = self.table("delegations"),
+            undelegations = self.table("undelegations")
         );
 
-        // let block = self.client.query(query).fetch_all().await?;
         let block = self
             .client
             .get_handle()
             .await?
-            .query(query)
+            .query(ddl)
             .fetch_all()
             .await?;
 
-        return if let Some(row) = block.rows().next() {
-            let count: u64 = row.get("count")?;
-            Ok(count > 0)
-        } else {
-            Ok(false)
-        };
+        let mut latest = HashMap::new();
+        for row in block.rows() {
+            let stake_acc: String = row.get(0)?;
+            let is_delegation: u8 = row.get(2)?;
+
+            latest.insert(
+                stake_acc.clone(),
+                LookupVoteAccsRec {
+                    stake_acc,
+                    vote_acc: row.get(1)?,
+                    is_delegation: is_delegation != 0,
+                },
+            );
+        }
+
+        Ok(latest)
     }
+}
 
+#[async_trait]
+impl ClickhouseStorage for TcpClient {
+    async fn execute(&mut self, ddl: &str) -> Result<(), StorageError> {
+        tcp::execute(&mut self.client.get_handle().await?, ddl).await
+    }
+
+    async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+        tcp::migrations_table_exists(&mut self.client.get_handle().await?).await
+    }
+
+    async fn migration_exists(&mut self, version: &str) -> Result<bool, StorageError> {
+        tcp::migration_exists(&mut self.client.get_handle().await?, version).await
+    }
+
+    async fn migration_lock_holder(
+        &mut self,
+        _owner: &str,
+        ttl_secs: u64,
+    ) -> Result<Option<String>, StorageError> {
+        tcp::migration_lock_holder(&mut self.client.get_handle().await?, ttl_secs).await
+    }
+
+    async fn describe_table(
+        &mut self,
+        table: &str,
+    ) -> Result<Option<Vec<TableColumn>>, StorageError> {
+        Ok(Some(
+            tcp::describe_table(&mut self.client.get_handle().await?, table).await?,
+        ))
+    }
+}
+
+#[async_trait]
+impl MainStorage for TcpClient {
     #[cfg(feature = "on_ch_cluster")]
     async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
         let ddl = format!(
-            "ALTER TABLE rewards ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
-            epoch - 1
+            "ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
         );
         // self.client.execute(ddl).await?;
 
         self.client.get_handle().await?.execute(ddl).await?;
 
+        let ddl = format!(
+            "ALTER TABLE {vote_account_commissions} ON CLUSTER '{{cluster}}' DELETE WHERE epoch = {}",
+            epoch - 1,
+            vote_account_commissions = self.table("vote_account_commissions")
+        );
+        self.client.get_handle().await?.execute(ddl).await?;
+
         Ok(())
     }
 
     #[cfg(not(feature = "on_ch_cluster"))]
     async fn clean_unfinished(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
-        let ddl = format!("ALTER TABLE rewards DELETE WHERE epoch = {}", epoch - 1);
+        let ddl = format!(
+            "ALTER TABLE {rewards} DELETE WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
         // self.client.execute(ddl).await?;
         self.client.get_handle().await?.execute(ddl).await?;
 
+        let ddl = format!(
+            "ALTER TABLE {vote_account_commissions} DELETE WHERE epoch = {}",
+            epoch - 1,
+            vote_account_commissions = self.table("vote_account_commissions")
+        );
+        self.client.get_handle().await?.execute(ddl).await?;
+
         Ok(())
     }
 
@@ -114,50 +243,60 @@ impl MainStorage for TcpClient {
         slot: u64,
         stake_acc: &str,
     ) -> Result<Option<String>, MainStorageError> {
-        let ddl = format!(
-            "
-                SELECT * FROM (
-                    SELECT 
-                        slot, raw_instruction_idx, vote_acc, 1 as is_delegation
-                    FROM delegations
-                    WHERE stake_acc = '{}' AND slot <= {}
-                    ORDER BY slot DESC, raw_instruction_idx DESC
-                    LIMIT 1
-                    UNION ALL 
-                    SELECT 
-                        slot, raw_instruction_idx, vote_acc, 0 as is_delegation
-                    FROM undelegations
-                    WHERE stake_acc = '{}' AND slot <= {}
-                    ORDER BY slot DESC, raw_instruction_idx DESC
-                    LIMIT 1
-                ) ORDER BY slot DESC, raw_instruction_idx DESC LIMIT 1
-            ",
-            stake_acc, slot, stake_acc, slot
-        );
+        let window_start = slot.saturating_sub(LOOKUP_VOTE_ACC_WINDOW);
+        let mut latest = self
+            .latest_delegation(stake_acc, window_start, slot)
+            .await?;
 
-        // let block = self.client.query(&ddl).fetch_all().await?;
+        if latest.is_none() && window_start > 0 {
+            latest = self
+                .latest_delegation(stake_acc, 0, window_start - 1)
+                .await?;
+        }
 
-        let block = self
-            .client
-            .get_handle()
-            .await?
-            .query(ddl)
-            .fetch_all()
+        Ok(latest
+            .filter(|row| row.is_delegation)
+            .and_then(|row| row.vote_acc))
+    }
+
+    async fn lookup_vote_accs(
+        &mut self,
+        slot: u64,
+        stake_accs: &[String],
+    ) -> Result<HashMap<String, Option<String>>, MainStorageError> {
+        let window_start = slot.saturating_sub(LOOKUP_VOTE_ACC_WINDOW);
+        let mut latest = self
+            .latest_delegations(stake_accs, window_start, slot)
             .await?;
 
-        if let Some(row) = block.rows().next() {
-            let is_delegation: u8 = row.get(3)?;
-            if is_delegation != 0 {
-                return Ok(row.get(2)?);
-            }
+        let missing: Vec<String> = stake_accs
+            .iter()
+            .filter(|stake_acc| !latest.contains_key(*stake_acc))
+            .cloned()
+            .collect();
+        if !missing.is_empty() && window_start > 0 {
+            latest.extend(
+                self.latest_delegations(&missing, 0, window_start - 1)
+                    .await?,
+            );
         }
 
-        Ok(None)
+        Ok(stake_accs
+            .iter()
+            .map(|stake_acc| {
+                let vote_acc = latest
+                    .get(stake_acc)
+                    .filter(|row| row.is_delegation)
+                    .and_then(|row| row.vote_acc.clone());
+                (stake_acc.clone(), vote_acc)
+            })
+            .collect())
     }
 
     async fn store_rewards_block(
         &mut self,
-        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64)>,
+        rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+        provisional: bool,
     ) -> Result<(), MainStorageError> {
         let block_size = rewards.len();
 
@@ -181,6 +320,9 @@ impl MainStorage for TcpClient {
                 commission: reward.3.commission,
                 first_block_slot: reward.2,
                 block_time: Value::DateTime(reward.4 as u32, Tz::UTC),
+                epoch_rate: reward.5.map(|reward_yield| reward_yield.epoch_rate),
+                apy: reward.5.map(|reward_yield| reward_yield.apy),
+                provisional: provisional as u8,
             })?;
         }
 
@@ -189,7 +331,77 @@ impl MainStorage for TcpClient {
         self.client
             .get_handle()
             .await?
-            .insert("rewards", block)
+            .insert(self.table("rewards"), block)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_delegated_stakes(
+        &mut self,
+        slot: u64,
+    ) -> Result<HashMap<String, u64>, MainStorageError> {
+        let ddl = format!(
+            "
+                SELECT assumeNotNull(latest.1) AS vote_acc, sum(latest.2) AS stake FROM (
+                    SELECT
+                        argMax((vote_acc, amount, is_delegation), (slot, raw_instruction_idx)) AS latest
+                    FROM (
+                        SELECT stake_acc, slot, raw_instruction_idx, vote_acc, amount, 1 AS is_delegation
+                        FROM {delegations}
+                        WHERE slot <= {}
+                        UNION ALL
+                        SELECT stake_acc, slot, raw_instruction_idx, vote_acc, amount, 0 AS is_delegation
+                        FROM {undelegations}
+                        WHERE slot <= {}
+                    )
+                    GROUP BY stake_acc
+                )
+                WHERE latest.3 = 1 AND latest.1 IS NOT NULL
+                GROUP BY vote_acc
+            ",
+            slot, slot,
+            delegations = self.table("delegations"),
+            undelegations = self.table("undelegations")
+        );
+
+        let block = self
+            .client
+            .get_handle()
+            .await?
+            .query(ddl)
+            .fetch_all()
+            .await?;
+
+        let mut stakes = HashMap::new();
+
+        for row in block.rows() {
+            stakes.insert(row.get(0)?, row.get(1)?);
+        }
+
+        Ok(stakes)
+    }
+
+    async fn store_commissions_block(
+        &mut self,
+        commissions: Vec<(String, Epoch, Option<u8>)>,
+    ) -> Result<(), MainStorageError> {
+        let block_size = commissions.len();
+
+        let mut block = Block::with_capacity(block_size);
+
+        for (vote_account, epoch, commission) in commissions {
+            block.push(row! {
+                epoch: epoch - 1,
+                vote_account,
+                commission,
+            })?;
+        }
+
+        self.client
+            .get_handle()
+            .await?
+            .insert(self.table("vote_account_commissions"), block)
             .await?;
 
         Ok(())
@@ -197,8 +409,11 @@ impl MainStorage for TcpClient {
 
     async fn get_rewards_with_empty_vote_acc(
         &mut self,
+        after_epoch: Epoch,
+        after_pubkey: &str,
+        limit: u64,
     ) -> Result<Vec<RewardRecResult>, MainStorageError> {
-        let ddl = String::from(
+        let ddl = format!(
             "
             SELECT
             vote_account,
@@ -210,10 +425,19 @@ impl MainStorage for TcpClient {
             commission,
             first_block_slot,
             block_time
-        FROM rewards
+        FROM {rewards}
         WHERE
             vote_account = ''
-            and reward_type = 'staking'",
+            and reward_type = 'staking'
+            and epoch >= {}
+            and (epoch, pubkey) > ({}, '{}')
+        ORDER BY epoch, pubkey
+        LIMIT {}",
+            after_epoch,
+            after_epoch,
+            after_pubkey,
+            limit,
+            rewards = self.table("rewards")
         );
 
         // let block = self.client.query(&ddl).fetch_all().await?;
@@ -265,10 +489,12 @@ impl MainStorage for TcpClient {
         pubkey: &str,
     ) -> Result<(), MainStorageError> {
         let request = format!("
-        ALTER TABLE rewards ON CLUSTER '{{cluster}}' UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
+        ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
         vote_acc,
         epoch,
-        pubkey);
+        pubkey,
+        rewards = self.table("rewards")
+        );
 
         let ddl = request.as_str();
 
@@ -292,8 +518,11 @@ impl MainStorage for TcpClient {
     ) -> Result<(), MainStorageError> {
         let request = format!(
             "
-        ALTER TABLE rewards UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
-            vote_acc, epoch, pubkey
+        ALTER TABLE {rewards} UPDATE vote_account = '{}' WHERE epoch = {} AND pubkey = '{}'",
+            vote_acc,
+            epoch,
+            pubkey,
+            rewards = self.table("rewards")
         );
 
         let ddl = request.as_str();
@@ -308,4 +537,172 @@ impl MainStorage for TcpClient {
 
         Ok(())
     }
+
+    async fn get_provisional_epochs(
+        &mut self,
+    ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError> {
+        let block = self
+            .client
+            .get_handle()
+            .await?
+            .query(format!(
+                "SELECT DISTINCT epoch + 1 AS epoch, first_block_slot FROM {rewards} \
+                WHERE provisional = 1 ORDER BY epoch",
+                rewards = self.table("rewards")
+            ))
+            .fetch_all()
+            .await?;
+
+        let mut epochs = Vec::new();
+
+        for row in block.rows() {
+            epochs.push((row.get(0)?, row.get(1)?));
+        }
+
+        Ok(epochs)
+    }
+
+    #[cfg(feature = "on_ch_cluster")]
+    async fn confirm_provisional_epoch(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+        let ddl = format!(
+            "ALTER TABLE {rewards} ON CLUSTER '{{cluster}}' UPDATE provisional = 0 WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.get_handle().await?.execute(ddl).await?;
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "on_ch_cluster"))]
+    async fn confirm_provisional_epoch(&mut self, epoch: Epoch) -> Result<(), MainStorageError> {
+        let ddl = format!(
+            "ALTER TABLE {rewards} UPDATE provisional = 0 WHERE epoch = {}",
+            epoch - 1,
+            rewards = self.table("rewards")
+        );
+        self.client.get_handle().await?.execute(ddl).await?;
+
+        Ok(())
+    }
+
+    fn set_table_prefix(&mut self, table_prefix: &str) {
+        self.table_prefix = table_prefix.to_string();
+    }
+}
+
+#[cfg(test)]
+mod clickhouse_server_tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    const STAKE_ACCOUNTS: u64 = 100;
+    const DELEGATIONS_PER_STAKE_ACC: u64 = 1000;
+
+    /// Seeds 100 partitions of delegations and compares the lookup over the whole history with
+    /// the one over the latest slots. Needs a local ClickHouse server, run it with `--ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn lookup_vote_acc_partition_pruning() -> Result<(), MainStorageError> {
+        let mut main_storage = TcpClient::new(Pool::new("tcp://localhost:9000"));
+        let mut client = main_storage.client.get_handle().await?;
+
+        for table in ["delegations", "undelegations"] {
+            client
+                .execute(format!("DROP TABLE IF EXISTS {}", table))
+                .await?;
+            client
+                .execute(format!(
+                    "CREATE TABLE {}
+                    (
+                        slot UInt64,
+                        block_time UInt64,
+                        stake_acc String,
+                        vote_acc Nullable(String),
+                        tx_signature String,
+                        amount UInt64,
+                        raw_instruction_idx UInt32
+                    ) ENGINE = MergeTree()
+                    PARTITION BY intDiv(slot, 1000000)
+                    ORDER BY (stake_acc, slot)",
+                    table
+                ))
+                .await?;
+        }
+
+        let rows = STAKE_ACCOUNTS * DELEGATIONS_PER_STAKE_ACC;
+        let slot = |row: u64| {
+            (row % DELEGATIONS_PER_STAKE_ACC) * 100_000 + row / DELEGATIONS_PER_STAKE_ACC
+        };
+        let stake_acc = |row: u64| format!("stake_{}", row / DELEGATIONS_PER_STAKE_ACC);
+
+        let block = Block::new()
+            .column("slot", (0..rows).map(slot).collect::<Vec<u64>>())
+            .column("block_time", vec![0_u64; rows as usize])
+            .column(
+                "stake_acc",
+                (0..rows).map(stake_acc).collect::<Vec<String>>(),
+            )
+            .column(
+                "vote_acc",
+                (0..rows)
+                    .map(|row| Some(format!("vote_{}", slot(row))))
+                    .collect::<Vec<Option<String>>>(),
+            )
+            .column("tx_signature", vec![String::new(); rows as usize])
+            .column("amount", vec![0_u64; rows as usize])
+            .column("raw_instruction_idx", vec![0_u32; rows as usize]);
+        client.insert("delegations", block).await?;
+
+        let last_slot = slot(DELEGATIONS_PER_STAKE_ACC - 1) + STAKE_ACCOUNTS;
+
+        let started = Instant::now();
+        let mut whole_history = Vec::new();
+        for stake_acc_idx in 0..STAKE_ACCOUNTS {
+            let stake_acc = format!("stake_{}", stake_acc_idx);
+            let latest = main_storage
+                .latest_delegation(&stake_acc, 0, last_slot)
+                .await?;
+            whole_history.push(latest.and_then(|row| row.vote_acc));
+        }
+        let whole_history_time = started.elapsed();
+
+        let started = Instant::now();
+        let mut latest_slots = Vec::new();
+        for stake_acc_idx in 0..STAKE_ACCOUNTS {
+            let stake_acc = format!("stake_{}", stake_acc_idx);
+            latest_slots.push(main_storage.lookup_vote_acc(last_slot, &stake_acc).await?);
+        }
+        let latest_slots_time = started.elapsed();
+
+        let started = Instant::now();
+        let stake_accs: Vec<String> = (0..STAKE_ACCOUNTS)
+            .map(|stake_acc_idx| format!("stake_{}", stake_acc_idx))
+            .collect();
+        let batched = main_storage
+            .lookup_vote_accs(last_slot, &stake_accs)
+            .await?;
+        let batched_time = started.elapsed();
+
+        println!(
+            "{} lookups, whole history: {:?}, latest slots: {:?}, batched: {:?}",
+            STAKE_ACCOUNTS, whole_history_time, latest_slots_time, batched_time
+        );
+
+        let batched: Vec<_> = stake_accs
+            .iter()
+            .map(|stake_acc| batched[stake_acc].clone())
+            .collect();
+        assert_eq!(batched, latest_slots);
+
+        assert_eq!(whole_history, latest_slots);
+        assert_eq!(latest_slots[1], Some(format!("vote_{}", slot(1999))));
+
+        for table in ["delegations", "undelegations"] {
+            client.execute(format!("DROP TABLE {}", table)).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,49 +1,331 @@
-use log::info;
+use log::{error, info, warn};
 use std::time::Duration;
 use tokio::time::sleep;
 
 use crate::{
-    errors::VoteAccountResolverError, repeat_until_ok, storage::main_storage::connect_main_storage,
+    errors::VoteAccountResolverError,
+    prometheus::{RESOLVED_VOTE_ACCOUNTS_COUNT, UNRESOLVED_VOTE_ACCOUNTS},
+    register::Register,
+    repeat_until_ok,
+    rpc_client::RpcClient,
+    storage::{
+        epoch_storage::{Epoch, EpochStorage},
+        main_storage::{connect_main_storage, MainStorage},
+    },
 };
 
-#[allow(dead_code)]
+/// Fills the vote account of staking rewards which could not be resolved while their epoch
+/// was analyzed, e.g. because the delegation had been loaded later than the rewards
 pub(crate) struct VoteAccountResolver {}
 
 impl VoteAccountResolver {
-    #[allow(dead_code)]
     pub async fn run() -> Result<(), VoteAccountResolverError> {
+        let configuration = &Register::current().configuration;
+
+        if !configuration.vote_accounts_resolver_enabled() {
+            info!("vote_account_resolver is disabled");
+            return Ok(());
+        }
+
         info!("Starting vote_account_resolver");
+        let interval = Duration::from_secs(configuration.vote_accounts_resolver_interval());
+        let batch_size = configuration.vote_accounts_resolver_batch_size();
+        let rpc_client = configuration
+            .vote_accounts_resolver_rpc_url()
+            .map(RpcClient::new);
+
         let mut main_storage = connect_main_storage().await?;
 
         tokio::spawn(async move {
             loop {
-                let rewards = main_storage.get_rewards_with_empty_vote_acc().await;
-                if let Ok(rewards) = rewards {
-                    for reward in rewards {
-                        let vote_account = repeat_until_ok!(
-                            main_storage
-                                .lookup_vote_acc(
-                                    reward.first_block_slot.unwrap(),
-                                    reward.pubkey.as_str(),
-                                )
-                                .await,
-                            5
-                        )
-                        .unwrap_or_default();
+                // Rows of the epochs being analyzed right now may be removed by `clean_unfinished`
+                let claimed_epochs = repeat_until_ok!(EpochStorage::get_claimed_epochs().await, 5);
+
+                let unresolved = Self::resolve(
+                    main_storage.as_mut(),
+                    rpc_client.as_ref(),
+                    batch_size,
+                    &claimed_epochs,
+                )
+                .await;
+
+                UNRESOLVED_VOTE_ACCOUNTS.set(unresolved as i64);
+                info!(
+                    actor = "vote_accounts_resolver";
+                    "vote_account_resolver pass is finished, {} rewards are still unresolved",
+                    unresolved
+                );
+
+                sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
 
+    /// Makes a single pass over the rewards with an empty vote account and returns
+    /// the number of rewards which are still unresolved
+    async fn resolve(
+        main_storage: &mut dyn MainStorage,
+        rpc_client: Option<&RpcClient>,
+        batch_size: u64,
+        claimed_epochs: &[Epoch],
+    ) -> u64 {
+        let mut unresolved = 0;
+        let mut after_epoch = 0;
+        let mut after_pubkey = String::new();
+
+        loop {
+            let rewards = match main_storage
+                .get_rewards_with_empty_vote_acc(after_epoch, &after_pubkey, batch_size)
+                .await
+            {
+                Ok(rewards) => rewards,
+                Err(err) => {
+                    error!("Failed to get rewards with empty vote account: {}", err);
+                    return unresolved;
+                }
+            };
+
+            match rewards.last() {
+                Some(last) => {
+                    after_epoch = last.epoch;
+                    after_pubkey = last.pubkey.clone();
+                }
+                None => return unresolved,
+            }
+            let is_last_page = (rewards.len() as u64) < batch_size;
+
+            for reward in rewards {
+                // Rewards are stored with the epoch they were earned in, which is the one
+                // preceding the analyzed epoch
+                if claimed_epochs.contains(&(reward.epoch + 1)) {
+                    continue;
+                }
+
+                let mut vote_account = match reward.first_block_slot {
+                    Some(first_block_slot) => repeat_until_ok!(
+                        main_storage
+                            .lookup_vote_acc(first_block_slot, &reward.pubkey)
+                            .await,
+                        5
+                    ),
+                    None => None,
+                };
+
+                if vote_account.is_none() {
+                    if let Some(rpc_client) = rpc_client {
+                        match rpc_client.get_stake_delegation(&reward.pubkey).await {
+                            Ok(delegation) => {
+                                vote_account = delegation.and_then(|delegation| {
+                                    delegation.voter_for_epoch(reward.epoch).map(String::from)
+                                })
+                            }
+                            Err(err) => warn!(
+                                "Failed to get stake delegation of {}: {}",
+                                reward.pubkey, err
+                            ),
+                        }
+                    }
+                }
+
+                match vote_account {
+                    Some(vote_account) => {
                         repeat_until_ok!(
                             main_storage
                                 .update_reward(&vote_account, reward.epoch, &reward.pubkey)
                                 .await,
                             5
                         );
+                        RESOLVED_VOTE_ACCOUNTS_COUNT.inc();
                     }
+                    None => unresolved += 1,
                 }
+            }
 
-                sleep(Duration::from_secs(10)).await;
+            if is_last_page {
+                return unresolved;
             }
-        });
+        }
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+    use clickhouse_storage::{ClickhouseStorage, StorageError};
+    use solana_transaction_status::Reward;
+
+    use super::*;
+    use crate::{
+        errors::MainStorageError,
+        storage::main_storage::{RewardRecResult, RewardYield},
+    };
+
+    struct MockMainStorage {
+        rewards: Vec<RewardRecResult>,
+        delegations: HashMap<String, String>,
+        updated: Vec<(String, Epoch, String)>,
+    }
+
+    #[async_trait]
+    impl ClickhouseStorage for MockMainStorage {
+        async fn execute(&mut self, _ddl: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        async fn migrations_table_exists(&mut self) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+
+        async fn migration_exists(&mut self, _version: &str) -> Result<bool, StorageError> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl MainStorage for MockMainStorage {
+        async fn clean_unfinished(&mut self, _epoch: Epoch) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn lookup_vote_acc(
+            &mut self,
+            _slot: u64,
+            stake_acc: &str,
+        ) -> Result<Option<String>, MainStorageError> {
+            Ok(self.delegations.get(stake_acc).cloned())
+        }
+
+        async fn lookup_vote_accs(
+            &mut self,
+            _slot: u64,
+            stake_accs: &[String],
+        ) -> Result<HashMap<String, Option<String>>, MainStorageError> {
+            Ok(stake_accs
+                .iter()
+                .map(|stake_acc| (stake_acc.clone(), self.delegations.get(stake_acc).cloned()))
+                .collect())
+        }
+
+        async fn store_rewards_block(
+            &mut self,
+            _rewards: Vec<(String, Epoch, Option<u64>, Reward, i64, Option<RewardYield>)>,
+            _provisional: bool,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn get_delegated_stakes(
+            &mut self,
+            _slot: u64,
+        ) -> Result<HashMap<String, u64>, MainStorageError> {
+            Ok(HashMap::new())
+        }
+
+        async fn store_commissions_block(
+            &mut self,
+            _commissions: Vec<(String, Epoch, Option<u8>)>,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+
+        async fn get_rewards_with_empty_vote_acc(
+            &mut self,
+            after_epoch: Epoch,
+            after_pubkey: &str,
+            limit: u64,
+        ) -> Result<Vec<RewardRecResult>, MainStorageError> {
+            Ok(self
+                .rewards
+                .iter()
+                .filter(|reward| {
+                    (reward.epoch, reward.pubkey.as_str()) > (after_epoch, after_pubkey)
+                })
+                .take(limit as usize)
+                .map(|reward| RewardRecResult {
+                    vote_account: reward.vote_account.clone(),
+                    epoch: reward.epoch,
+                    pubkey: reward.pubkey.clone(),
+                    lamports: reward.lamports,
+                    post_balance: reward.post_balance,
+                    reward_type: reward.reward_type.clone(),
+                    commission: reward.commission,
+                    first_block_slot: reward.first_block_slot,
+                    block_time: reward.block_time,
+                })
+                .collect())
+        }
+
+        async fn update_reward(
+            &mut self,
+            vote_acc: &str,
+            epoch: Epoch,
+            pubkey: &str,
+        ) -> Result<(), MainStorageError> {
+            self.updated
+                .push((vote_acc.to_string(), epoch, pubkey.to_string()));
+            Ok(())
+        }
+
+        async fn get_provisional_epochs(
+            &mut self,
+        ) -> Result<Vec<(Epoch, Option<u64>)>, MainStorageError> {
+            Ok(Vec::new())
+        }
+
+        async fn confirm_provisional_epoch(
+            &mut self,
+            _epoch: Epoch,
+        ) -> Result<(), MainStorageError> {
+            Ok(())
+        }
+    }
+
+    fn reward(epoch: Epoch, pubkey: &str) -> RewardRecResult {
+        RewardRecResult {
+            vote_account: String::new(),
+            epoch,
+            pubkey: pubkey.to_string(),
+            lamports: 1000,
+            post_balance: 0,
+            reward_type: Some("Staking".to_string()),
+            commission: None,
+            first_block_slot: Some(epoch * 432000),
+            block_time: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_pages_through_rewards() {
+        let mut main_storage = MockMainStorage {
+            rewards: vec![
+                reward(300, "stake_a"),
+                reward(300, "stake_b"),
+                reward(300, "stake_c"),
+                reward(301, "stake_a"),
+                reward(302, "stake_a"),
+            ],
+            delegations: HashMap::from([
+                ("stake_a".to_string(), "vote_a".to_string()),
+                ("stake_b".to_string(), "vote_b".to_string()),
+            ]),
+            updated: Vec::new(),
+        };
+
+        // Epoch 303 is being analyzed, so the rewards of 302 must not be touched
+        let unresolved = VoteAccountResolver::resolve(&mut main_storage, None, 2, &[303]).await;
+
+        assert_eq!(unresolved, 1);
+        assert_eq!(
+            main_storage.updated,
+            vec![
+                ("vote_a".to_string(), 300, "stake_a".to_string()),
+                ("vote_b".to_string(), 300, "stake_b".to_string()),
+                ("vote_a".to_string(), 301, "stake_a".to_string()),
+            ]
+        );
     }
 }
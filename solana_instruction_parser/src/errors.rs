@@ -0,0 +1,149 @@
+use indexer_errors::{impl_into_indexer_error, Classify, ErrorClass};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ParseInstructionError {
+    #[error("Failed to convert to serde_json: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Failed to get sighash of instruction: {0}")]
+    SighashFromSliceError(#[from] std::array::TryFromSliceError),
+
+    #[error("Failed to deserialize instruction: {0}")]
+    DeserializeError(#[from] std::io::Error),
+
+    #[error("Failed to deserialize in {instruction}: {err}")]
+    DeserializeInInstructionError {
+        instruction: String,
+        err: std::io::Error,
+    },
+
+    #[error("Failed to limited_deserialize in {instruction}: {err}")]
+    LimDeserializeInInstructionError {
+        instruction: String,
+        err: solana_program::instruction::InstructionError,
+    },
+
+    #[error("Failed to deserialize instruction from base58")]
+    DeserializeFromBase58Error,
+
+    #[error("Failed to parse instruction: {0}")]
+    ParseError(String),
+
+    #[error("Invalid index in {site}: {index}, when length is {max_len}")]
+    InvalidIndex {
+        site: String,
+        index: usize,
+        max_len: usize,
+    },
+
+    #[error("Invalid inner instructions index in {site}: {index}, when length is {max_len}")]
+    InvalidInnerIndex {
+        site: String,
+        index: usize,
+        max_len: usize,
+    },
+
+    #[error("{site} has invalid length: {len} instead of {expected_len}")]
+    InvalidLength {
+        site: String,
+        len: usize,
+        expected_len: usize,
+    },
+
+    #[error("Converting Error: {0}")]
+    ConvertingError(#[from] ConvertingError),
+
+    #[error("Cannot get instruction name")]
+    InvalidInstructionName,
+
+    #[error("Given hash doesn't match any sighash in {0}")]
+    SighashMatchError(String),
+
+    #[error("Address doesn't match any program")]
+    ProgramAddressMatchError,
+
+    #[error("{0} is unsupported")]
+    Unsupported(String),
+
+    #[error("Failed to decode instruction of {program}: {err}")]
+    InstructionDecodeError {
+        program: String,
+        discriminant: Option<u8>,
+        err: Box<ParseInstructionError>,
+    },
+}
+
+impl ParseInstructionError {
+    /// Name of the variant, used as a metrics label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::SerdeError(_) => "SerdeError",
+            Self::SighashFromSliceError(_) => "SighashFromSliceError",
+            Self::DeserializeError(_) => "DeserializeError",
+            Self::DeserializeInInstructionError { .. } => "DeserializeInInstructionError",
+            Self::LimDeserializeInInstructionError { .. } => "LimDeserializeInInstructionError",
+            Self::DeserializeFromBase58Error => "DeserializeFromBase58Error",
+            Self::ParseError(_) => "ParseError",
+            Self::InvalidIndex { .. } => "InvalidIndex",
+            Self::InvalidInnerIndex { .. } => "InvalidInnerIndex",
+            Self::InvalidLength { .. } => "InvalidLength",
+            Self::ConvertingError(_) => "ConvertingError",
+            Self::InvalidInstructionName => "InvalidInstructionName",
+            Self::SighashMatchError(_) => "SighashMatchError",
+            Self::ProgramAddressMatchError => "ProgramAddressMatchError",
+            Self::Unsupported(_) => "Unsupported",
+            Self::InstructionDecodeError { .. } => "InstructionDecodeError",
+        }
+    }
+
+    /// Errors of decoding the data of a single instruction, e.g. a variant unknown to the parser.
+    /// They don't fail the whole transaction unlike the structural ones
+    pub fn is_decode_error(&self) -> bool {
+        matches!(
+            self,
+            Self::SighashFromSliceError(_)
+                | Self::DeserializeError(_)
+                | Self::DeserializeInInstructionError { .. }
+                | Self::LimDeserializeInInstructionError { .. }
+                | Self::SighashMatchError(_)
+                | Self::InstructionDecodeError { .. }
+        )
+    }
+}
+
+impl From<rust_base58::base58::FromBase58Error> for ParseInstructionError {
+    fn from(_: rust_base58::base58::FromBase58Error) -> Self {
+        Self::DeserializeFromBase58Error
+    }
+}
+
+/// Instructions which fail to decode are data the parser can't read, retrying doesn't help
+impl Classify for ParseInstructionError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::DataCorruption
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConvertingError {
+    #[error("Cannot get {0} field")]
+    EmptyField(String),
+
+    #[error("Types has different lengths")]
+    DifferentLengths,
+
+    #[error("{0} is unsupported")]
+    Unsupported(String),
+
+    #[error("Failed to deserialize: {0}")]
+    DeserializeError(#[from] serde_json::error::Error),
+}
+
+impl Classify for ConvertingError {
+    fn classify(&self) -> ErrorClass {
+        ErrorClass::DataCorruption
+    }
+}
+
+impl_into_indexer_error!(ParseInstructionError, ConvertingError);
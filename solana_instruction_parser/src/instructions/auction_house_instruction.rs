@@ -1,5 +1,5 @@
 use crate::errors::ParseInstructionError;
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::Serialize;
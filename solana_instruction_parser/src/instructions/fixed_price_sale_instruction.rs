@@ -1,13 +1,12 @@
 use crate::errors::ParseInstructionError;
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
-use chrono::format::Fixed;
 use serde::Serialize;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::address_lookup_table::instruction;
+use std::io;
 
-#[derive(Debug, Serialize, BorshDeserialize, BorshSerialize)]
+#[derive(Debug, PartialEq, Serialize, BorshDeserialize, BorshSerialize)]
 #[instr_args_parse(InstrRoot)]
 pub enum FixedPriceSaleInstruction {
     InitSellingResource {
@@ -110,7 +109,30 @@ struct CreateMarket {
     gating_config: Option<GatingConfig>,
 }
 
-#[derive(Debug, Serialize, BorshDeserialize, BorshSerialize)]
+impl CreateMarket {
+    /// The markets created by the program versions before the gating have no `gating_config`
+    fn decode(mut data: &[u8]) -> io::Result<Self> {
+        let data = &mut data;
+
+        Ok(Self {
+            treasury_owner_bump: u8::deserialize(data)?,
+            name: String::deserialize(data)?,
+            description: String::deserialize(data)?,
+            mutable: bool::deserialize(data)?,
+            price: u64::deserialize(data)?,
+            pieces_in_one_wallet: Option::<u64>::deserialize(data)?,
+            start_date: u64::deserialize(data)?,
+            end_date: Option::<u64>::deserialize(data)?,
+            gating_config: if data.is_empty() {
+                None
+            } else {
+                Option::<GatingConfig>::deserialize(data)?
+            },
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, BorshDeserialize, BorshSerialize)]
 #[instr_args_parse]
 pub struct GatingConfig {
     pub collection: Pubkey,
@@ -133,7 +155,7 @@ struct SavePrimaryMetadataCreators {
 }
 
 // mpl_token_metadata::state::Creator
-#[derive(Debug, Serialize, BorshDeserialize, BorshSerialize)]
+#[derive(Debug, PartialEq, Serialize, BorshDeserialize, BorshSerialize)]
 #[instr_args_parse]
 pub struct Creator {
     pub address: Pubkey,
@@ -142,6 +164,12 @@ pub struct Creator {
     pub share: u8,
 }
 
+/// Anchor ignores the bytes after the arguments, so the instructions sent with the arguments of
+/// a later program version are decoded as well
+fn decode<T: BorshDeserialize>(mut data: &[u8]) -> io::Result<T> {
+    T::deserialize(&mut data)
+}
+
 impl FixedPriceSaleInstruction {
     pub fn match_sighash(
         sighash: [u8; 8],
@@ -149,7 +177,7 @@ impl FixedPriceSaleInstruction {
     ) -> Result<FixedPriceSaleInstruction, ParseInstructionError> {
         match sighash {
             [56, 15, 222, 211, 147, 205, 4, 145] => {
-                let init_selling_resource = InitSellingResource::try_from_slice(data)?;
+                let init_selling_resource = decode::<InitSellingResource>(data)?;
                 Ok(FixedPriceSaleInstruction::InitSellingResource {
                     master_edition_bump: init_selling_resource.master_edition_bump,
                     vault_owner_bump: init_selling_resource.vault_owner_bump,
@@ -157,14 +185,14 @@ impl FixedPriceSaleInstruction {
                 })
             }
             [132, 152, 9, 27, 112, 19, 95, 83] => {
-                let create_store = CreateStore::try_from_slice(data)?;
+                let create_store = decode::<CreateStore>(data)?;
                 Ok(FixedPriceSaleInstruction::CreateStore {
                     name: create_store.name,
                     description: create_store.description,
                 })
             }
             [102, 6, 61, 18, 1, 218, 235, 234] => {
-                let buy = Buy::try_from_slice(data)?;
+                let buy = decode::<Buy>(data)?;
                 Ok(FixedPriceSaleInstruction::Buy {
                     trade_history_bump: buy.trade_history_bump,
                     vault_owner_bump: buy.vault_owner_bump,
@@ -173,7 +201,7 @@ impl FixedPriceSaleInstruction {
             [88, 154, 248, 186, 48, 14, 123, 244] => Ok(FixedPriceSaleInstruction::CloseMarket),
             [246, 27, 129, 46, 10, 196, 165, 118] => Ok(FixedPriceSaleInstruction::SuspendMarket),
             [130, 59, 109, 101, 85, 226, 37, 88] => {
-                let change_market = ChangeMarket::try_from_slice(data)?;
+                let change_market = decode::<ChangeMarket>(data)?;
                 Ok(FixedPriceSaleInstruction::ChangeMarket {
                     new_name: change_market.new_name,
                     new_description: change_market.new_description,
@@ -184,14 +212,14 @@ impl FixedPriceSaleInstruction {
             }
             [198, 120, 104, 87, 44, 103, 108, 143] => Ok(FixedPriceSaleInstruction::ResumeMarket),
             [183, 18, 70, 156, 148, 109, 161, 34] => {
-                let withdraw = Withdraw::try_from_slice(data)?;
+                let withdraw = decode::<Withdraw>(data)?;
                 Ok(FixedPriceSaleInstruction::Withdraw {
                     treasury_owner_bump: withdraw.treasury_owner_bump,
                     payout_ticket_bump: withdraw.payout_ticket_bump,
                 })
             }
             [103, 226, 97, 235, 200, 188, 251, 254] => {
-                let create_market = CreateMarket::try_from_slice(data)?;
+                let create_market = CreateMarket::decode(data)?;
                 Ok(FixedPriceSaleInstruction::CreateMarket {
                     treasury_owner_bump: create_market.treasury_owner_bump,
                     name: create_market.name,
@@ -205,14 +233,13 @@ impl FixedPriceSaleInstruction {
                 })
             }
             [0, 160, 164, 96, 237, 118, 74, 27] => {
-                let claim_resource = ClaimResource::try_from_slice(data)?;
+                let claim_resource = decode::<ClaimResource>(data)?;
                 Ok(FixedPriceSaleInstruction::ClaimResource {
                     vault_owner_bump: claim_resource.vault_owner_bump,
                 })
             }
             [66, 240, 213, 46, 185, 60, 192, 254] => {
-                let save_primary_metadata_creators =
-                    SavePrimaryMetadataCreators::try_from_slice(data)?;
+                let save_primary_metadata_creators = decode::<SavePrimaryMetadataCreators>(data)?;
                 Ok(FixedPriceSaleInstruction::SavePrimaryMetadataCreators {
                     primary_metadata_creators_bump: save_primary_metadata_creators
                         .primary_metadata_creators_bump,
@@ -248,3 +275,94 @@ impl FixedPriceSaleInstruction {
         Ok((json, instruction_arguments))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::bs58;
+    use std::str::FromStr;
+
+    use super::*;
+
+    const COLLECTION: &str = "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs";
+
+    fn match_base58(data: &str) -> FixedPriceSaleInstruction {
+        let data = bs58::decode(data).into_vec().unwrap();
+        FixedPriceSaleInstruction::match_sighash(data[..8].try_into().unwrap(), &data[8..]).unwrap()
+    }
+
+    #[test]
+    fn save_primary_metadata_creators() {
+        assert_eq!(
+            match_base58("ZGWtgCVwWLfb7JU6zVMTDtE6L72WsPPXiU5YpcahKtu5MqJTv69QYgdeMFAvFFfq"),
+            FixedPriceSaleInstruction::SavePrimaryMetadataCreators {
+                primary_metadata_creators_bump: 254,
+                creators: vec![Creator {
+                    address: Pubkey::from_str(COLLECTION).unwrap(),
+                    verified: false,
+                    share: 100,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn claim_resource_and_withdraw() {
+        assert_eq!(
+            match_base58("1TsRvJPFBxUt"),
+            FixedPriceSaleInstruction::ClaimResource {
+                vault_owner_bump: 253
+            }
+        );
+        assert_eq!(
+            match_base58("BHYhA9U8oiSitu"),
+            FixedPriceSaleInstruction::Withdraw {
+                treasury_owner_bump: 255,
+                payout_ticket_bump: 254,
+            }
+        );
+    }
+
+    #[test]
+    fn create_market_with_and_without_gating() {
+        let create_market = |gating_config| FixedPriceSaleInstruction::CreateMarket {
+            treasury_owner_bump: 252,
+            name: "Drop".to_string(),
+            description: "First drop".to_string(),
+            mutable: true,
+            price: 1_000_000_000,
+            pieces_in_one_wallet: Some(2),
+            start_date: 1_650_000_000,
+            end_date: None,
+            gating_config,
+        };
+
+        // Created before the gating was added to the program
+        assert_eq!(
+            match_base58(
+                "wosLCa94pKeHiKofvD5g6441ytRnpzSqCJ154WyWcyt6CrjDnhqHNeN4AaAL9pz7sRQCaHBgZ4qckvT"
+            ),
+            create_market(None)
+        );
+        assert_eq!(
+            match_base58(
+                "JpZz8i7ahiVMnHS6268DbyfqMfkqQWjNmjMztavA9qr3sUtW79oT1ntS2mSWy3cwZq9mkFYiYBWnXk2UiTpYbn43mAq4vbBjqiqH5P6JBfokbFknSYeFeDT4RQSF7kcnMzWLrtyRkK"
+            ),
+            create_market(Some(GatingConfig {
+                collection: Pubkey::from_str(COLLECTION).unwrap(),
+                expire_on_use: true,
+                gating_time: Some(86400),
+            }))
+        );
+    }
+
+    #[test]
+    fn trailing_bytes_are_ignored() {
+        assert_eq!(
+            match_base58("eWSf3SvGBWRi6Sqq5xT"),
+            FixedPriceSaleInstruction::Buy {
+                trade_history_bump: 251,
+                vault_owner_bump: 250,
+            }
+        );
+    }
+}
@@ -1,11 +1,11 @@
 use crate::errors::ParseInstructionError;
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::Serialize;
 use solana_program::pubkey::Pubkey;
 
-#[derive(Debug, Serialize, BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, BorshDeserialize, BorshSerialize)]
 #[instr_args_parse(InstrRoot)]
 pub enum GumdropInstruction {
     NewDistributor {
@@ -230,10 +230,11 @@ impl GumdropInstruction {
         }
     }
 
+    /// The decoded instruction is returned along with its JSON and arguments for the claim events
     pub fn parse_instruction(
         sighash: [u8; 8],
         data: &[u8],
-    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+    ) -> Result<(String, Vec<InstructionArgument>, GumdropInstruction), ParseInstructionError> {
         let instruction = Self::match_sighash(sighash, data);
 
         let instruction = match instruction {
@@ -248,8 +249,68 @@ impl GumdropInstruction {
 
         let json = serde_json::to_string(&instruction)?;
 
-        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+        let instruction_arguments = instruction.clone().get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments, instruction))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use solana_sdk::bs58;
+    use std::str::FromStr;
+
+    use super::*;
+
+    const CLAIMANT: &str = "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs";
+
+    pub const CLAIM: &str = "5LKSK58b8iK8PgAuAQcmuNZDgYGgXCviHVWYnuxrYjkypbdyjGZyTVZ9jv9XaTx6EmeRbYWh4V2griJUke1Lsg99gGTCe75NcaPWcUMPJz2ht5UjcKb5rTMHRG8qf2eXaas1dvnGdsWCy51KVAV92jmUP5jwgxXwh3mU42JVH7P";
+    pub const CLAIM_CANDY: &str = "2Yw39zZRQnR1AhxsQd4P1g16TK6B9RS9NLMF6XCwwf2X2ag3b2xRS3xm1czcesExLB32uydAiCovtVmEB7bzhPTpTryYkDE8wDnYzjjGdMhZLeYu4onURp1Psbvqp3bHS";
+
+    fn match_base58(data: &str) -> GumdropInstruction {
+        let data = bs58::decode(data).into_vec().unwrap();
+        GumdropInstruction::match_sighash(data[..8].try_into().unwrap(), &data[8..]).unwrap()
+    }
+
+    #[test]
+    fn claim() {
+        assert_eq!(
+            match_base58(CLAIM),
+            GumdropInstruction::Claim {
+                bump: 254,
+                index: 3,
+                amount: 500_000_000,
+                claimant_secret: Pubkey::from_str(CLAIMANT).unwrap(),
+                proof: vec![[1; 32], [2; 32]],
+            }
+        );
+    }
+
+    #[test]
+    fn claim_candy() {
+        assert_eq!(
+            match_base58(CLAIM_CANDY),
+            GumdropInstruction::ClaimCandy {
+                wallet_bump: 255,
+                claim_bump: 253,
+                index: 7,
+                amount: 2,
+                claimant_secret: Pubkey::from_str(CLAIMANT).unwrap(),
+                proof: vec![[1; 32]],
+            }
+        );
+    }
+
+    #[test]
+    fn claim_arguments() {
+        let data = bs58::decode(CLAIM).into_vec().unwrap();
+
+        let (json, arguments, instruction) =
+            GumdropInstruction::parse_instruction(data[..8].try_into().unwrap(), &data[8..])
+                .unwrap();
 
-        Ok((json, instruction_arguments))
+        assert_eq!(instruction, match_base58(CLAIM));
+        assert!(json.starts_with("{\"Claim\":{\"bump\":254,\"index\":3,\"amount\":500000000,"));
+        assert!(!arguments.is_empty());
     }
 }
@@ -0,0 +1,74 @@
+use crate::errors::ParseInstructionError;
+use crate::InstructionArgument;
+use serde::Serialize;
+
+pub const MEMO_PROGRAM: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+pub const MEMO_V1_PROGRAM: &str = "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo";
+
+/// The Memo program has the only instruction, its data is the memo text itself
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub enum MemoInstruction {
+    Memo(String),
+}
+
+impl MemoInstruction {
+    /// The memo is decoded lossily, so invalid UTF-8 never fails the transaction,
+    /// and is cut to the first `max_length` characters
+    pub fn parse_instruction(
+        data: &[u8],
+        max_length: usize,
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let memo: String = String::from_utf8_lossy(data)
+            .chars()
+            .take(max_length)
+            .collect();
+
+        let instruction_argument = InstructionArgument {
+            arg_path: "/memo".to_string(),
+            string_value: Some(memo.clone()),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&MemoInstruction::Memo(memo))?;
+
+        Ok((json, vec![instruction_argument]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_byte_memo() {
+        let memo = "Привіт, 世界 🚀";
+
+        let (json, arguments) = MemoInstruction::parse_instruction(memo.as_bytes(), 100).unwrap();
+
+        assert_eq!(json, format!("{{\"Memo\":\"{}\"}}", memo));
+        assert_eq!(arguments.len(), 1);
+        assert_eq!(arguments[0].arg_path, "/memo");
+        assert_eq!(arguments[0].string_value.as_deref(), Some(memo));
+    }
+
+    #[test]
+    fn oversized_memo() {
+        let memo = "🚀".repeat(10);
+
+        let (_, arguments) = MemoInstruction::parse_instruction(memo.as_bytes(), 4).unwrap();
+
+        // Truncated by characters, so a multi-byte one is never split
+        assert_eq!(arguments[0].string_value.as_deref(), Some("🚀🚀🚀🚀"));
+    }
+
+    #[test]
+    fn invalid_utf8_memo() {
+        let (_, arguments) =
+            MemoInstruction::parse_instruction(&[b'o', b'k', 0xff, 0xfe], 100).unwrap();
+
+        assert_eq!(
+            arguments[0].string_value.as_deref(),
+            Some("ok\u{fffd}\u{fffd}")
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_program::pubkey::Pubkey;
@@ -10,6 +10,8 @@ pub mod fixed_price_sale_instruction;
 pub mod gumdrop_instruction;
 pub mod token_entangler_instruction;
 
+pub mod memo_instruction;
 pub mod stake_instruction;
+pub mod stake_pool_instruction;
 pub mod system_instruction;
 pub mod vote_instruction;
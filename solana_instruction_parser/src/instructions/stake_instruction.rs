@@ -1,4 +1,4 @@
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use serde_derive::{Deserialize, Serialize};
 use solana_program::{
     clock::{Epoch, UnixTimestamp},
@@ -175,7 +175,7 @@ pub enum StakeInstruction {
     ///   2. Optional: `[SIGNER]` New lockup authority
     SetLockupChecked(LockupCheckedArgs),
 
-        /// Get the minimum stake delegation, in lamports
+    /// Get the minimum stake delegation, in lamports
     ///
     /// # Account references
     ///   None
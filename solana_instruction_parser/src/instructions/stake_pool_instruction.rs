@@ -0,0 +1,481 @@
+use crate::{instr_args_parse, InstructionArgument, PathTree};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+/////////////////////////stake-pool/program/src/state.rs//////////////////////////////////////////////
+/// Fee rate as a ratio, minted on `UpdateStakePoolBalance` as a proportion of the rewards
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[instr_args_parse]
+pub struct Fee {
+    pub denominator: u64,
+    pub numerator: u64,
+}
+
+/// The type of fees that can be set on the stake pool
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[instr_args_parse]
+pub enum FeeType {
+    /// Referral fees for SOL deposits
+    SolReferral(u8),
+    /// Referral fees for stake deposits
+    StakeReferral(u8),
+    /// Management fee paid per epoch
+    Epoch(Fee),
+    /// Stake withdrawal fee
+    StakeWithdrawal(Fee),
+    /// Deposit fee for SOL deposits
+    SolDeposit(Fee),
+    /// Deposit fee for stake deposits
+    StakeDeposit(Fee),
+    /// SOL withdrawal fee
+    SolWithdrawal(Fee),
+}
+
+/////////////////////////stake-pool/program/src/instruction.rs////////////////////////////////////////
+/// Defines which validator vote account is set during the `SetPreferredValidator` instruction
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[instr_args_parse]
+pub enum PreferredValidatorType {
+    /// Set preferred validator for deposits
+    Deposit,
+    /// Set preferred validator for withdraws
+    Withdraw,
+}
+
+/// Defines which authority to update in the `SetFundingAuthority` instruction
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[instr_args_parse]
+pub enum FundingType {
+    /// Sets the stake deposit authority
+    StakeDeposit,
+    /// Sets the SOL deposit authority
+    SolDeposit,
+    /// Sets the SOL withdraw authority
+    SolWithdraw,
+}
+
+/// Instructions supported by the StakePool program.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[instr_args_parse(InstrRoot)]
+pub enum StakePoolInstruction {
+    ///   Initializes a new StakePool.
+    ///
+    ///   0. `[w]` New StakePool to create.
+    ///   1. `[s]` Manager
+    ///   2. `[]` Staker
+    ///   3. `[]` Stake pool withdraw authority
+    ///   4. `[w]` Uninitialized validator stake list storage account
+    ///   5. `[]` Reserve stake account must be initialized, have zero balance,
+    ///       and staker / withdrawer authority set to pool withdraw authority.
+    ///   6. `[]` Pool token mint. Must have zero supply, owned by withdraw authority.
+    ///   7. `[]` Pool account to deposit the generated fee for manager.
+    ///   8. `[]` Token program id
+    ///   9. `[]` (Optional) Deposit authority that must sign all deposits.
+    Initialize {
+        /// Fee assessed as percentage of perceived rewards
+        fee: Fee,
+        /// Fee charged per withdrawal as percentage of withdrawal
+        withdrawal_fee: Fee,
+        /// Fee charged per deposit as percentage of deposit
+        deposit_fee: Fee,
+        /// Percentage [0-100] of deposit_fee that goes to referrer
+        referral_fee: u8,
+        /// Maximum expected number of validators
+        max_validators: u32,
+    },
+
+    ///   (Staker only) Adds stake account delegated to validator to the pool's
+    ///   list of managed validators.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[s]` Staker
+    ///   2. `[w]` Reserve stake account
+    ///   3. `[]` Stake pool withdraw authority
+    ///   4. `[w]` Validator stake list storage account
+    ///   5. `[w]` Stake account to add to the pool
+    ///   6. `[]` Validator this stake account will be delegated to
+    ///   7-13. Rent, clock, stake history and stake config sysvars, system and stake programs
+    ///
+    ///   userdata: optional non-zero u32 seed used for generating the validator stake address
+    AddValidatorToPool(u32),
+
+    ///   (Staker only) Removes validator from the pool, deactivating its stake
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[s]` Staker
+    ///   2. `[]` Stake pool withdraw authority
+    ///   3. `[w]` Validator stake list storage account
+    ///   4. `[w]` Stake account to remove from the pool
+    ///   5. `[w]` Transient stake account, to deactivate if necessary
+    ///   6. `[]` Sysvar clock
+    ///   7. `[]` Stake program id,
+    RemoveValidatorFromPool,
+
+    /// (Staker only) Decrease active stake on a validator, eventually moving it to the reserve
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Canonical stake account to split from
+    ///  5. `[w]` Transient stake account to receive split
+    ///  6-9. Clock and rent sysvars, system and stake programs
+    DecreaseValidatorStake {
+        /// amount of lamports to split into the transient stake account
+        lamports: u64,
+        /// seed used to create transient stake account
+        transient_stake_seed: u64,
+    },
+
+    /// (Staker only) Increase stake on a validator from the reserve account
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Stake pool reserve stake
+    ///  5. `[w]` Transient stake account
+    ///  6. `[]` Validator stake account
+    ///  7. `[]` Validator vote account to delegate to
+    ///  8-13. Clock, rent, stake history and stake config sysvars, system and stake programs
+    IncreaseValidatorStake {
+        /// amount of lamports to increase on the given validator
+        lamports: u64,
+        /// seed used to create transient stake account
+        transient_stake_seed: u64,
+    },
+
+    /// (Staker only) Set the preferred deposit or withdraw stake account for the
+    /// stake pool
+    ///
+    ///  0. `[w]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Validator list
+    SetPreferredValidator {
+        /// Affected operation (deposit or withdraw)
+        validator_type: PreferredValidatorType,
+        /// Validator vote account that deposits or withdraws must go through,
+        /// unset with None
+        validator_vote_address: Option<Pubkey>,
+    },
+
+    ///  Updates balances of validator and transient stake accounts in the pool
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[]` Stake pool withdraw authority
+    ///  2. `[w]` Validator stake list storage account
+    ///  3. `[w]` Reserve stake account
+    ///  4-6. Clock and stake history sysvars, stake program
+    ///  7. ..7+2N ` [] N pairs of validator and transient stake accounts
+    UpdateValidatorListBalance {
+        /// Index to start updating on the validator list
+        start_index: u32,
+        /// If true, don't try merging transient stake accounts into the reserve or
+        /// validator stake account
+        no_merge: bool,
+    },
+
+    ///   Updates total pool balance based on balances in the reserve and validator list
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[]` Stake pool withdraw authority
+    ///   2. `[w]` Validator stake list storage account
+    ///   3. `[]` Reserve stake account
+    ///   4. `[w]` Account to receive pool fee tokens
+    ///   5. `[w]` Pool mint account
+    ///   6. `[]` Pool token program
+    UpdateStakePoolBalance,
+
+    ///   Cleans up validator stake account entries marked as `ReadyForRemoval`
+    ///
+    ///   0. `[]` Stake pool
+    ///   1. `[w]` Validator stake list storage account
+    CleanupRemovedValidatorEntries,
+
+    ///   Deposit some stake into the pool. The output is a "pool" token representing ownership
+    ///   into the pool. Inputs are converted to the current ratio.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[w]` Validator stake list storage account
+    ///   2. `[s]/[]` Stake pool deposit authority
+    ///   3. `[]` Stake pool withdraw authority
+    ///   4. `[w]` Stake account to join the pool (withdraw authority for the stake account
+    ///      should be first set to the stake pool deposit authority)
+    ///   5. `[w]` Validator stake account for the stake account to be merged with
+    ///   6. `[w]` Reserve stake account, to withdraw rent exempt reserve
+    ///   7. `[w]` User account to receive pool tokens
+    ///   8. `[w]` Account to receive pool fee tokens
+    ///   9. `[w]` Account to receive a portion of pool fee tokens as referral fees
+    ///   10. `[w]` Pool token mint account
+    ///   11-14. Clock and stake history sysvars, token and stake programs
+    DepositStake,
+
+    ///   Withdraw the token from the pool at the current ratio.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[w]` Validator stake list storage account
+    ///   2. `[]` Stake pool withdraw authority
+    ///   3. `[w]` Validator or reserve stake account to split
+    ///   4. `[w]` Unitialized stake account to receive withdrawal
+    ///   5. `[]` User account to set as a new withdraw authority
+    ///   6. `[s]` User transfer authority, for pool token account
+    ///   7. `[w]` User account with pool tokens to burn from
+    ///   8. `[w]` Account to receive pool fee tokens
+    ///   9. `[w]` Pool token mint account
+    ///   10-12. Clock sysvar, token and stake programs
+    ///
+    ///   userdata: amount of pool tokens to withdraw
+    WithdrawStake(u64),
+
+    ///  (Manager only) Update manager
+    ///
+    ///  0. `[w]` StakePool
+    ///  1. `[s]` Manager
+    ///  2. `[s]` New manager
+    ///  3. `[]` New manager fee account
+    SetManager,
+
+    ///  (Manager only) Update fee
+    ///
+    ///  0. `[w]` StakePool
+    ///  1. `[s]` Manager
+    SetFee {
+        /// Type of fee to update and value to update it to
+        fee: FeeType,
+    },
+
+    ///  (Manager or staker only) Update staker
+    ///
+    ///  0. `[w]` StakePool
+    ///  1. `[s]` Manager or current staker
+    ///  2. '[]` New staker pubkey
+    SetStaker,
+
+    ///   Deposit SOL directly into the pool's reserve account. The output is a "pool" token
+    ///   representing ownership into the pool. Inputs are converted to the current ratio.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[]` Stake pool withdraw authority
+    ///   2. `[w]` Reserve stake account, to deposit SOL
+    ///   3. `[s]` Account providing the lamports to be deposited into the pool
+    ///   4. `[w]` User account to receive pool tokens
+    ///   5. `[w]` Account to receive fee tokens
+    ///   6. `[w]` Account to receive a portion of fee as referral fees
+    ///   7. `[w]` Pool token mint account
+    ///   8. `[]` System program account
+    ///   9. `[]` Token program id
+    ///  10. `[s]` (Optional) Stake pool sol deposit authority.
+    DepositSol(u64),
+
+    ///  (Manager only) Update SOL deposit, stake deposit, or SOL withdrawal authority.
+    ///
+    ///  0. `[w]` StakePool
+    ///  1. `[s]` Manager
+    ///  2. '[]` New authority pubkey or none
+    SetFundingAuthority(FundingType),
+
+    ///   Withdraw SOL directly from the pool's reserve account. Fails if the
+    ///   reserve does not have enough SOL.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[]` Stake pool withdraw authority
+    ///   2. `[s]` User transfer authority, for pool token account
+    ///   3. `[w]` User account to burn pool tokens
+    ///   4. `[w]` Reserve stake account, to withdraw SOL
+    ///   5. `[w]` Account receiving the lamports from the reserve, must be a system account
+    ///   6. `[w]` Account to receive pool fee tokens
+    ///   7. `[w]` Pool token mint account
+    ///   8-11. Clock and stake history sysvars, stake and token programs
+    ///  12. `[s]` (Optional) Stake pool sol withdraw authority
+    WithdrawSol(u64),
+
+    /// Create token metadata for the stake-pool token in the
+    /// metaplex-token program
+    ///
+    /// 0. `[]` Stake pool
+    /// 1. `[s]` Manager
+    /// 2. `[]` Stake pool withdraw authority
+    /// 3. `[]` Pool token mint account
+    /// 4. `[s, w]` Payer for creation of token metadata account
+    /// 5. `[w]` Token metadata account
+    /// 6. `[]` Metadata program id
+    /// 7. `[]` System program id
+    CreateTokenMetadata {
+        /// Token name
+        name: String,
+        /// Token symbol e.g. stkSOL
+        symbol: String,
+        /// URI of the uploaded metadata of the spl-token
+        uri: String,
+    },
+
+    /// Update token metadata for the stake-pool token in the
+    /// metaplex-token program
+    ///
+    /// 0. `[]` Stake pool
+    /// 1. `[s]` Manager
+    /// 2. `[]` Stake pool withdraw authority
+    /// 3. `[w]` Token metadata account
+    /// 4. `[]` Metadata program id
+    UpdateTokenMetadata {
+        /// Token name
+        name: String,
+        /// Token symbol e.g. stkSOL
+        symbol: String,
+        /// URI of the uploaded metadata of the spl-token
+        uri: String,
+    },
+
+    /// (Staker only) Increase stake on a validator again in an epoch.
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Stake pool reserve stake
+    ///  5. `[w]` Uninitialized ephemeral stake account to receive stake
+    ///  6. `[w]` Transient stake account
+    ///  7. `[]` Validator stake account
+    ///  8. `[]` Validator vote account to delegate to
+    ///  9-13. Clock and stake history sysvars, stake config, system and stake programs
+    IncreaseAdditionalValidatorStake {
+        /// amount of lamports to increase on the given validator
+        lamports: u64,
+        /// seed used to create transient stake account
+        transient_stake_seed: u64,
+        /// seed used to create ephemeral account.
+        ephemeral_stake_seed: u64,
+    },
+
+    /// (Staker only) Decrease active stake again from a validator, eventually
+    /// moving it to the reserve
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Reserve stake account, to fund rent exempt reserve
+    ///  5. `[w]` Canonical stake account to split from
+    ///  6. `[w]` Uninitialized ephemeral stake account to receive stake
+    ///  7. `[w]` Transient stake account
+    ///  8-11. Clock and stake history sysvars, system and stake programs
+    DecreaseAdditionalValidatorStake {
+        /// amount of lamports to split into the transient stake account
+        lamports: u64,
+        /// seed used to create transient stake account
+        transient_stake_seed: u64,
+        /// seed used to create ephemeral account.
+        ephemeral_stake_seed: u64,
+    },
+
+    /// (Staker only) Decrease active stake on a validator, eventually moving it to the reserve
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Reserve stake account, to fund rent exempt reserve
+    ///  5. `[w]` Canonical stake account to split from
+    ///  6. `[w]` Transient stake account to receive split
+    ///  7-10. Clock and stake history sysvars, system and stake programs
+    DecreaseValidatorStakeWithReserve {
+        /// amount of lamports to split into the transient stake account
+        lamports: u64,
+        /// seed used to create transient stake account
+        transient_stake_seed: u64,
+    },
+
+    /// (Staker only) Redelegate active stake on a validator, eventually moving it to another
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Reserve stake account, to withdraw rent exempt reserve
+    ///  5. `[w]` Source canonical stake account to split from
+    ///  6. `[w]` Source transient stake account to receive split and be redelegated
+    ///  7. `[w]` Uninitialized ephemeral stake account to receive redelegation
+    ///  8. `[w]` Destination transient stake account to receive ephemeral stake by merge
+    ///  9. `[]` Destination stake account to receive transient stake after activation
+    ///  10. `[]` Destination validator vote account
+    ///  11-15. Clock and stake history sysvars, stake config, system and stake programs
+    Redelegate {
+        /// Amount of lamports to redelegate
+        lamports: u64,
+        /// Seed used to create source transient stake account
+        source_transient_stake_seed: u64,
+        /// Seed used to create destination ephemeral account.
+        ephemeral_stake_seed: u64,
+        /// Seed used to create destination transient stake account. If there is
+        /// already transient stake, this must match the current seed, otherwise
+        /// it can be anything
+        destination_transient_stake_seed: u64,
+    },
+
+    ///   Deposit some stake into the pool, with a specified slippage constraint.
+    ///   Accounts are the same as in `DepositStake`
+    DepositStakeWithSlippage {
+        /// Minimum amount of pool tokens that must be received
+        minimum_pool_tokens_out: u64,
+    },
+
+    ///   Withdraw the token from the pool at the current ratio, specifying a
+    ///   minimum expected output lamport amount. Accounts are the same as in `WithdrawStake`
+    WithdrawStakeWithSlippage {
+        /// Pool tokens to burn in exchange for lamports
+        pool_tokens_in: u64,
+        /// Minimum amount of lamports that must be received
+        minimum_lamports_out: u64,
+    },
+
+    ///   Deposit SOL directly into the pool's reserve account, with a
+    ///   specified slippage constraint. Accounts are the same as in `DepositSol`
+    DepositSolWithSlippage {
+        /// Amount of lamports to deposit into the reserve
+        lamports_in: u64,
+        /// Minimum amount of pool tokens that must be received
+        minimum_pool_tokens_out: u64,
+    },
+
+    ///   Withdraw SOL directly from the pool's reserve account, with a
+    ///   specified slippage constraint. Accounts are the same as in `WithdrawSol`
+    WithdrawSolWithSlippage {
+        /// Pool tokens to burn in exchange for lamports
+        pool_tokens_in: u64,
+        /// Minimum amount of lamports that must be received
+        minimum_lamports_out: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::bs58;
+
+    use super::*;
+
+    #[test]
+    fn deposit_sol() {
+        let data = bs58::decode("BLdTjjrauNto").into_vec().unwrap();
+
+        assert_eq!(
+            StakePoolInstruction::try_from_slice(&data).unwrap(),
+            StakePoolInstruction::DepositSol(5_000_000_000)
+        );
+    }
+
+    #[test]
+    fn withdraw_stake() {
+        let data = bs58::decode("8zKZwMKvMXzo").into_vec().unwrap();
+
+        let instruction = StakePoolInstruction::try_from_slice(&data).unwrap();
+        assert_eq!(
+            instruction,
+            StakePoolInstruction::WithdrawStake(1_234_567_890)
+        );
+
+        let arguments = instruction.get_arguments("", 0, None, "");
+        assert_eq!(arguments.len(), 1);
+        assert_eq!(arguments[0].unsigned_value, Some(1_234_567_890));
+    }
+}
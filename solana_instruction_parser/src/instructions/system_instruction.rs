@@ -0,0 +1,370 @@
+use crate::{instr_args_parse, InstructionArgument, PathTree};
+#[allow(deprecated)]
+use serde_derive::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[instr_args_parse(InstrRoot)]
+pub enum SystemInstruction {
+    /// Create a new account
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` Funding account
+    ///   1. `[WRITE, SIGNER]` New account
+    CreateAccount {
+        /// Number of lamports to transfer to the new account
+        lamports: u64,
+
+        /// Number of bytes of memory to allocate
+        space: u64,
+
+        /// Address of program that will own the new account
+        owner: Pubkey,
+    },
+
+    /// Assign account to a program
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` Assigned account public key
+    Assign {
+        /// Owner program account
+        owner: Pubkey,
+    },
+
+    /// Transfer lamports
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` Funding account
+    ///   1. `[WRITE]` Recipient account
+    Transfer { lamports: u64 },
+
+    /// Create a new account at an address derived from a base pubkey and a seed
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` Funding account
+    ///   1. `[WRITE]` Created account
+    ///   2. `[SIGNER]` (optional) Base account; the account matching the base Pubkey below must be
+    ///                          provided as a signer, but may be the same as the funding account
+    ///                          and provided as account 0
+    CreateAccountWithSeed {
+        /// Base public key
+        base: Pubkey,
+
+        /// String of ASCII chars, no longer than `Pubkey::MAX_SEED_LEN`
+        seed: String,
+
+        /// Number of lamports to transfer to the new account
+        lamports: u64,
+
+        /// Number of bytes of memory to allocate
+        space: u64,
+
+        /// Owner program account address
+        owner: Pubkey,
+    },
+
+    /// Consumes a stored nonce, replacing it with a successor
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Nonce account
+    ///   1. `[]` RecentBlockhashes sysvar
+    ///   2. `[SIGNER]` Nonce authority
+    AdvanceNonceAccount,
+
+    /// Withdraw funds from a nonce account
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Nonce account
+    ///   1. `[WRITE]` Recipient account
+    ///   2. `[]` RecentBlockhashes sysvar
+    ///   3. `[]` Rent sysvar
+    ///   4. `[SIGNER]` Nonce authority
+    ///
+    /// Named rather than a tuple like in `solana_program`, the layout is the same and the
+    /// arguments get the `/lamports` path instead of `/0`
+    WithdrawNonceAccount {
+        /// Lamports to withdraw, which must leave the account balance above the rent exempt
+        /// reserve or at zero
+        lamports: u64,
+    },
+
+    /// Drive state of Uninitialized nonce account to Initialized, setting the nonce value
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Nonce account
+    ///   1. `[]` RecentBlockhashes sysvar
+    ///   2. `[]` Rent sysvar
+    ///
+    /// No signatures are required to execute this instruction, enabling derived
+    /// nonce account addresses
+    InitializeNonceAccount {
+        /// Entity authorized to execute nonce instructions on the account
+        authority: Pubkey,
+    },
+
+    /// Change the entity authorized to execute nonce instructions on the account
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Nonce account
+    ///   1. `[SIGNER]` Nonce authority
+    AuthorizeNonceAccount {
+        /// Entity to authorize
+        authority: Pubkey,
+    },
+
+    /// Allocate space in a (possibly new) account without funding
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` New account
+    Allocate {
+        /// Number of bytes of memory to allocate
+        space: u64,
+    },
+
+    /// Allocate space for and assign an account at an address
+    ///    derived from a base public key and a seed
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Allocated account
+    ///   1. `[SIGNER]` Base account
+    AllocateWithSeed {
+        /// Base public key
+        base: Pubkey,
+
+        /// String of ASCII chars, no longer than `pubkey::MAX_SEED_LEN`
+        seed: String,
+
+        /// Number of bytes of memory to allocate
+        space: u64,
+
+        /// Owner program account
+        owner: Pubkey,
+    },
+
+    /// Assign account to a program based on a seed
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Assigned account
+    ///   1. `[SIGNER]` Base account
+    AssignWithSeed {
+        /// Base public key
+        base: Pubkey,
+
+        /// String of ASCII chars, no longer than `pubkey::MAX_SEED_LEN`
+        seed: String,
+
+        /// Owner program account
+        owner: Pubkey,
+    },
+
+    /// Transfer lamports from a derived address
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Funding account
+    ///   1. `[SIGNER]` Base for funding account
+    ///   2. `[WRITE]` Recipient account
+    TransferWithSeed {
+        /// Amount to transfer
+        lamports: u64,
+
+        /// Seed to use to derive the funding account address
+        from_seed: String,
+
+        /// Owner to use to derive the funding account address
+        from_owner: Pubkey,
+    },
+
+    /// One-time idempotent upgrade of legacy nonce versions in order to bump
+    /// them out of chain blockhash domain
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Nonce account
+    UpgradeNonceAccount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_transaction, ParseOptions, TransactionParser, TransactionParsingResult};
+    use rust_base58::FromBase58;
+    use solana_program::{stake, system_instruction, system_program};
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    const ADVANCE_NONCE_ACCOUNT: &str = "6vx8P";
+    /// 2282880 lamports from the account derived with the "stake:0" seed for the stake program
+    const TRANSFER_WITH_SEED: &str =
+        "ScgpP2NVxwHQihEdj5rUPTeWktVJUPPgBre4mXkUTCiFrMEswF8gZ58AJF4aRjikxv3MQSZX9XXzope3";
+
+    fn decode(data: &str) -> (String, Vec<InstructionArgument>) {
+        let (json, arguments, _) = TransactionParser::parse_instruction(
+            &system_program::id().to_string(),
+            &data.from_base58().unwrap(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        (json, arguments)
+    }
+
+    fn argument<'a>(
+        arguments: &'a [InstructionArgument],
+        arg_path: &str,
+    ) -> &'a InstructionArgument {
+        arguments
+            .iter()
+            .find(|argument| argument.arg_path == arg_path)
+            .unwrap_or_else(|| panic!("No {} argument", arg_path))
+    }
+
+    #[test]
+    fn advance_nonce_account() {
+        let (json, arguments) = decode(ADVANCE_NONCE_ACCOUNT);
+
+        assert_eq!(json, "\"AdvanceNonceAccount\"");
+        assert!(arguments.is_empty());
+    }
+
+    #[test]
+    fn transfer_with_seed() {
+        let from_owner = stake::program::id();
+        let expected = system_instruction::transfer_with_seed(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            "stake:0".to_string(),
+            &from_owner,
+            &Pubkey::new_unique(),
+            2282880,
+        );
+        assert_eq!(TRANSFER_WITH_SEED.from_base58().unwrap(), expected.data);
+
+        let (json, arguments) = decode(TRANSFER_WITH_SEED);
+
+        assert!(json.starts_with("{\"TransferWithSeed\":"));
+        assert_eq!(
+            argument(&arguments, "/lamports").unsigned_value,
+            Some(2282880)
+        );
+        assert_eq!(
+            argument(&arguments, "/from_seed").string_value.as_deref(),
+            Some("stake:0")
+        );
+        assert_eq!(
+            argument(&arguments, "/from_owner").string_value,
+            Some(from_owner.to_string())
+        );
+    }
+
+    #[test]
+    fn nonce_arguments_are_named() {
+        let authority = Pubkey::new_unique();
+        let data = system_instruction::authorize_nonce_account(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &authority,
+        )
+        .data;
+
+        let (_, arguments, _) = TransactionParser::parse_instruction(
+            &system_program::id().to_string(),
+            &data,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(arguments.len(), 1);
+        assert_eq!(arguments[0].arg_path, "/authority");
+        assert_eq!(arguments[0].string_value, Some(authority.to_string()));
+
+        let data = system_instruction::withdraw_nonce_account(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1000,
+        )
+        .data;
+
+        let (_, arguments, _) = TransactionParser::parse_instruction(
+            &system_program::id().to_string(),
+            &data,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(argument(&arguments, "/lamports").unsigned_value, Some(1000));
+    }
+
+    #[test]
+    fn inner_instructions_are_decoded() {
+        // A durable nonce transaction whose program invokes the system program
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 2
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "11111111111111111111111111111111",
+                        "H6FEUafrGDeQsGnCerFomtzG3B3TctUaue8yM7heLi8W"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [
+                        { "programIdIndex": 2, "accounts": [1, 0], "data": ADVANCE_NONCE_ACCOUNT },
+                        { "programIdIndex": 3, "accounts": [0, 1, 2], "data": "guFfuH" }
+                    ]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [{
+                    "index": 1,
+                    "instructions": [
+                        { "programIdIndex": 2, "accounts": [1, 0, 0], "data": TRANSFER_WITH_SEED }
+                    ]
+                }],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        let TransactionParsingResult {
+            instructions,
+            instruction_arguments,
+            ..
+        } = parse_transaction(EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        })
+        .unwrap();
+
+        let names: Vec<_> = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.inner_instructions_set,
+                    instruction.instruction_name.as_str(),
+                )
+            })
+            .collect();
+        assert!(names.contains(&(None, "AdvanceNonceAccount")));
+        assert!(names.contains(&(Some(1), "TransferWithSeed")));
+
+        let inner_lamports = instruction_arguments.iter().find(|argument| {
+            argument.inner_instructions_set == Some(1) && argument.arg_path == "/lamports"
+        });
+        assert_eq!(
+            inner_lamports.and_then(|argument| argument.unsigned_value),
+            Some(2282880)
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use crate::errors::ParseInstructionError;
-use crate::storages::main_storage::{instr_args_parse, InstructionArgument, PathTree};
+use crate::{instr_args_parse, InstructionArgument, PathTree};
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::Serialize;
 
-#[derive(Debug, Serialize, BorshDeserialize, BorshSerialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, BorshDeserialize, BorshSerialize)]
 #[instr_args_parse(InstrRoot)]
 pub enum TokenEntanglerInstruction {
     CreateEntangledPair {
@@ -71,10 +71,12 @@ impl TokenEntanglerInstruction {
         }
     }
 
+    /// The decoded instruction is returned along with its JSON and arguments for the swaps
     pub fn parse_instruction(
         sighash: [u8; 8],
         data: &[u8],
-    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+    ) -> Result<(String, Vec<InstructionArgument>, TokenEntanglerInstruction), ParseInstructionError>
+    {
         let instruction = Self::match_sighash(sighash, data);
 
         let instruction = match instruction {
@@ -89,8 +91,8 @@ impl TokenEntanglerInstruction {
 
         let json = serde_json::to_string(&instruction)?;
 
-        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+        let instruction_arguments = instruction.clone().get_arguments("", 0, None, "");
 
-        Ok((json, instruction_arguments))
+        Ok((json, instruction_arguments, instruction))
     }
 }
@@ -0,0 +1,586 @@
+use std::collections::VecDeque;
+
+use crate::{instr_args_parse, InstructionArgument, PathTree};
+use compact::{serde_compact_vote_state_update, serde_tower_sync};
+use serde_derive::{Deserialize, Serialize};
+use solana_program::{
+    clock::{Slot, UnixTimestamp},
+    pubkey::Pubkey,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[instr_args_parse(InstrRoot)]
+pub enum VoteInstruction {
+    /// Initialize a vote account
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Uninitialized vote account
+    ///   1. `[]` Rent sysvar
+    ///   2. `[]` Clock sysvar
+    ///   3. `[SIGNER]` New validator identity (node_pubkey)
+    InitializeAccount(VoteInit),
+
+    /// Authorize a key to send votes or issue a withdrawal
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to be updated with the Pubkey for authorization
+    ///   1. `[]` Clock sysvar
+    ///   2. `[SIGNER]` Vote or withdraw authority
+    Authorize(Pubkey, VoteAuthorize),
+
+    /// A Vote instruction with recent votes
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to vote with
+    ///   1. `[]` Slot hashes sysvar
+    ///   2. `[]` Clock sysvar
+    ///   3. `[SIGNER]` Vote authority
+    Vote(Vote),
+
+    /// Withdraw some amount of funds
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to withdraw from
+    ///   1. `[WRITE]` Recipient account
+    ///   2. `[SIGNER]` Withdraw authority
+    Withdraw(u64),
+
+    /// Update the vote account's validator identity (node_pubkey)
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to be updated with the given authority public key
+    ///   1. `[SIGNER]` New validator identity (node_pubkey)
+    ///   2. `[SIGNER]` Withdraw authority
+    UpdateValidatorIdentity,
+
+    /// Update the commission for the vote account
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to be updated
+    ///   1. `[SIGNER]` Withdraw authority
+    UpdateCommission(u8),
+
+    /// A Vote instruction with recent votes
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to vote with
+    ///   1. `[]` Slot hashes sysvar
+    ///   2. `[]` Clock sysvar
+    ///   3. `[SIGNER]` Vote authority
+    VoteSwitch(Vote, Hash),
+
+    /// Authorize a key to send votes or issue a withdrawal
+    ///
+    /// This instruction behaves like `Authorize` with the additional requirement that the new vote
+    /// or withdraw authority must also be a signer.
+    ///
+    /// # Account references
+    ///   0. `[WRITE]` Vote account to be updated with the Pubkey for authorization
+    ///   1. `[]` Clock sysvar
+    ///   2. `[SIGNER]` Vote or withdraw authority
+    ///   3. `[SIGNER]` New vote or withdraw authority
+    AuthorizeChecked(VoteAuthorize),
+
+    /// Update the onchain vote state for the signer.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    UpdateVoteState(VoteStateUpdate),
+
+    /// Update the onchain vote state for the signer along with a switching proof.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    UpdateVoteStateSwitch(VoteStateUpdate, Hash),
+
+    /// Given that the current Voter or Withdrawer authority is a derived key,
+    /// this instruction allows someone who can sign for that derived key's
+    /// base key to authorize a new Voter or Withdrawer for a vote account.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to be updated
+    ///   1. `[]` Clock sysvar
+    ///   2. `[SIGNER]` Base key of current Voter or Withdrawer authority's derived key
+    AuthorizeWithSeed(VoteAuthorizeWithSeedArgs),
+
+    /// Given that the current Voter or Withdrawer authority is a derived key,
+    /// this instruction allows someone who can sign for that derived key's
+    /// base key to authorize a new Voter or Withdrawer for a vote account.
+    ///
+    /// This instruction behaves like `AuthorizeWithSeed` with the additional requirement
+    /// that the new vote or withdraw authority must also be a signer.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to be updated
+    ///   1. `[]` Clock sysvar
+    ///   2. `[SIGNER]` Base key of current Voter or Withdrawer authority's derived key
+    ///   3. `[SIGNER]` New vote or withdraw authority
+    AuthorizeCheckedWithSeed(VoteAuthorizeCheckedWithSeedArgs),
+
+    /// Update the onchain vote state for the signer.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    #[serde(with = "serde_compact_vote_state_update")]
+    CompactUpdateVoteState(VoteStateUpdate),
+
+    /// Update the onchain vote state for the signer along with a switching proof.
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    CompactUpdateVoteStateSwitch(
+        #[serde(with = "serde_compact_vote_state_update")] VoteStateUpdate,
+        Hash,
+    ),
+
+    /// Sync the onchain vote state with local tower
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    #[serde(with = "serde_tower_sync")]
+    TowerSync(TowerSync),
+
+    /// Sync the onchain vote state with local tower along with a switching proof
+    ///
+    /// # Account references
+    ///   0. `[Write]` Vote account to vote with
+    ///   1. `[SIGNER]` Vote authority
+    TowerSyncSwitch(#[serde(with = "serde_tower_sync")] TowerSync, Hash),
+}
+
+#[derive(Serialize, Default, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct VoteStateUpdate {
+    /// The proposed tower
+    pub lockouts: VecDeque<Lockout>,
+    /// The proposed root
+    pub root: Option<Slot>,
+    /// signature of the bank's state at the last slot
+    pub hash: Hash,
+    /// processing timestamp of last slot
+    pub timestamp: Option<UnixTimestamp>,
+}
+
+#[derive(Serialize, Default, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct TowerSync {
+    /// The proposed tower
+    pub lockouts: VecDeque<Lockout>,
+    /// The proposed root
+    pub root: Option<Slot>,
+    /// signature of the bank's state at the last slot
+    pub hash: Hash,
+    /// processing timestamp of last slot
+    pub timestamp: Option<UnixTimestamp>,
+    /// the unique identifier for the chain up to and including this block. Does not require
+    /// replaying in order to compute.
+    pub block_id: Hash,
+}
+
+#[derive(Serialize, Default, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+#[instr_args_parse]
+pub struct Lockout {
+    pub slot: Slot,
+    pub confirmation_count: u32,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[instr_args_parse]
+pub struct VoteInit {
+    pub node_pubkey: Pubkey,
+    pub authorized_voter: Pubkey,
+    pub authorized_withdrawer: Pubkey,
+    pub commission: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[instr_args_parse]
+pub enum VoteAuthorize {
+    Voter,
+    Withdrawer,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct VoteAuthorizeWithSeedArgs {
+    pub authorization_type: VoteAuthorize,
+    pub current_authority_derived_key_owner: Pubkey,
+    pub current_authority_derived_key_seed: String,
+    pub new_authority: Pubkey,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct VoteAuthorizeCheckedWithSeedArgs {
+    pub authorization_type: VoteAuthorize,
+    pub current_authority_derived_key_owner: Pubkey,
+    pub current_authority_derived_key_seed: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct BlockTimestamp {
+    pub slot: Slot,
+    pub timestamp: UnixTimestamp,
+}
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash,
+)]
+#[instr_args_parse]
+pub struct Hash(pub(crate) [u8; 32]);
+
+#[derive(Serialize, Default, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[instr_args_parse]
+pub struct Vote {
+    /// A stack of votes starting with the oldest vote
+    pub slots: Vec<Slot>,
+    /// signature of the bank's state at the last slot
+    pub hash: Hash,
+    /// processing timestamp of last slot
+    pub timestamp: Option<UnixTimestamp>,
+}
+
+impl VoteInstruction {
+    /// Keeps only the `max_lockouts` newest lockouts of a vote state update or a tower sync,
+    /// the other instructions are left as they are
+    pub fn truncate_lockouts(&mut self, max_lockouts: usize) {
+        let lockouts = match self {
+            VoteInstruction::UpdateVoteState(update)
+            | VoteInstruction::UpdateVoteStateSwitch(update, _)
+            | VoteInstruction::CompactUpdateVoteState(update)
+            | VoteInstruction::CompactUpdateVoteStateSwitch(update, _) => &mut update.lockouts,
+            VoteInstruction::TowerSync(tower_sync)
+            | VoteInstruction::TowerSyncSwitch(tower_sync, _) => &mut tower_sync.lockouts,
+            _ => return,
+        };
+
+        let excess = lockouts.len().saturating_sub(max_lockouts);
+        lockouts.drain(..excess);
+    }
+}
+
+/// Compact serialization of the vote states the `Compact*` and `TowerSync*` instructions carry:
+/// the root is `u64::MAX` when there is none, and the slot of every lockout is its varint offset
+/// from the previous one, the first one is the offset from the root. JSON gets the full lockouts
+mod compact {
+    use serde_derive::{Deserialize, Serialize};
+    use solana_program::{serde_varint, short_vec};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct LockoutOffset {
+        #[serde(with = "serde_varint")]
+        offset: Slot,
+        confirmation_count: u8,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct CompactVoteStateUpdate {
+        root: Slot,
+        #[serde(with = "short_vec")]
+        lockout_offsets: Vec<LockoutOffset>,
+        hash: Hash,
+        timestamp: Option<UnixTimestamp>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct CompactTowerSync {
+        root: Slot,
+        #[serde(with = "short_vec")]
+        lockout_offsets: Vec<LockoutOffset>,
+        hash: Hash,
+        timestamp: Option<UnixTimestamp>,
+        block_id: Hash,
+    }
+
+    fn lockout_offsets(
+        root: Option<Slot>,
+        lockouts: &VecDeque<Lockout>,
+    ) -> Result<Vec<LockoutOffset>, &'static str> {
+        let mut slot = root.unwrap_or_default();
+
+        lockouts
+            .iter()
+            .map(|lockout| {
+                let offset = lockout
+                    .slot
+                    .checked_sub(slot)
+                    .ok_or("Lockouts are not ordered by slot")?;
+                let confirmation_count = u8::try_from(lockout.confirmation_count)
+                    .map_err(|_| "Invalid confirmation count")?;
+                slot = lockout.slot;
+
+                Ok(LockoutOffset {
+                    offset,
+                    confirmation_count,
+                })
+            })
+            .collect()
+    }
+
+    fn lockouts(
+        root: Slot,
+        lockout_offsets: Vec<LockoutOffset>,
+    ) -> Result<(Option<Slot>, VecDeque<Lockout>), &'static str> {
+        let root = (root != Slot::MAX).then_some(root);
+        let mut slot = root.unwrap_or_default();
+
+        let lockouts = lockout_offsets
+            .into_iter()
+            .map(|lockout_offset| {
+                slot = slot
+                    .checked_add(lockout_offset.offset)
+                    .ok_or("Invalid lockout offset")?;
+
+                Ok(Lockout {
+                    slot,
+                    confirmation_count: u32::from(lockout_offset.confirmation_count),
+                })
+            })
+            .collect::<Result<_, &'static str>>()?;
+
+        Ok((root, lockouts))
+    }
+
+    pub mod serde_compact_vote_state_update {
+        use serde::{de::Error as _, ser::Error as _};
+
+        use super::*;
+
+        pub fn serialize<S>(update: &VoteStateUpdate, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            if serializer.is_human_readable() {
+                return serde::Serialize::serialize(update, serializer);
+            }
+
+            let compact = CompactVoteStateUpdate {
+                root: update.root.unwrap_or(Slot::MAX),
+                lockout_offsets: lockout_offsets(update.root, &update.lockouts)
+                    .map_err(S::Error::custom)?,
+                hash: update.hash,
+                timestamp: update.timestamp,
+            };
+
+            serde::Serialize::serialize(&compact, serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<VoteStateUpdate, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                return serde::Deserialize::deserialize(deserializer);
+            }
+
+            let compact: CompactVoteStateUpdate = serde::Deserialize::deserialize(deserializer)?;
+            let (root, lockouts) =
+                lockouts(compact.root, compact.lockout_offsets).map_err(D::Error::custom)?;
+
+            Ok(VoteStateUpdate {
+                lockouts,
+                root,
+                hash: compact.hash,
+                timestamp: compact.timestamp,
+            })
+        }
+    }
+
+    pub mod serde_tower_sync {
+        use serde::{de::Error as _, ser::Error as _};
+
+        use super::*;
+
+        pub fn serialize<S>(tower_sync: &TowerSync, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            if serializer.is_human_readable() {
+                return serde::Serialize::serialize(tower_sync, serializer);
+            }
+
+            let compact = CompactTowerSync {
+                root: tower_sync.root.unwrap_or(Slot::MAX),
+                lockout_offsets: lockout_offsets(tower_sync.root, &tower_sync.lockouts)
+                    .map_err(S::Error::custom)?,
+                hash: tower_sync.hash,
+                timestamp: tower_sync.timestamp,
+                block_id: tower_sync.block_id,
+            };
+
+            serde::Serialize::serialize(&compact, serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<TowerSync, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            if deserializer.is_human_readable() {
+                return serde::Deserialize::deserialize(deserializer);
+            }
+
+            let compact: CompactTowerSync = serde::Deserialize::deserialize(deserializer)?;
+            let (root, lockouts) =
+                lockouts(compact.root, compact.lockout_offsets).map_err(D::Error::custom)?;
+
+            Ok(TowerSync {
+                lockouts,
+                root,
+                hash: compact.hash,
+                timestamp: compact.timestamp,
+                block_id: compact.block_id,
+            })
+        }
+    }
+}
+
+// this is how many epochs a voter can be remembered for slashing
+const MAX_ITEMS: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CircBuf<I> {
+    buf: [I; MAX_ITEMS],
+    /// next pointer
+    idx: usize,
+    is_empty: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, ProgramInstruction, TransactionParser};
+    use solana_program::{instruction::Instruction, vote};
+
+    const ROOT: Slot = 1000;
+
+    /// Arguments of the lockout slots of a decoded instruction
+    fn lockout_slots(arguments: &[InstructionArgument]) -> Vec<u64> {
+        arguments
+            .iter()
+            .filter(|argument| {
+                argument.arg_path.contains("lockouts") && argument.arg_path.ends_with("/slot")
+            })
+            .filter_map(|argument| argument.unsigned_value)
+            .collect()
+    }
+
+    #[test]
+    fn compact_vote_state_update() {
+        // A full tower of 31 lockouts above the root, as a validator votes with it
+        let mut update = vote::state::VoteStateUpdate::from(
+            (1..=31)
+                .map(|idx| (ROOT + idx * 2, (32 - idx) as u32))
+                .collect::<Vec<_>>(),
+        );
+        update.root = Some(ROOT);
+        update.timestamp = Some(1700000000);
+        let data = vote::instruction::compact_update_vote_state(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            update,
+        )
+        .data;
+
+        let (json, arguments, program_instruction) = TransactionParser::parse_instruction(
+            &vote::program::id().to_string(),
+            &data,
+            &ParseOptions {
+                max_vote_lockouts: 4,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap();
+
+        let Some(ProgramInstruction::Vote(VoteInstruction::CompactUpdateVoteState(update))) =
+            program_instruction
+        else {
+            panic!("The instruction isn't a compact vote state update");
+        };
+        assert_eq!(update.root, Some(ROOT));
+        assert_eq!(update.timestamp, Some(1700000000));
+        assert_eq!(update.lockouts.len(), 31);
+        assert_eq!(
+            update.lockouts.back(),
+            Some(&Lockout {
+                slot: ROOT + 62,
+                confirmation_count: 1,
+            })
+        );
+        // The JSON has the slots, not their offsets
+        assert!(json.contains(&format!("\"slot\":{}", ROOT + 62)));
+
+        // Only the newest lockouts are flattened into the arguments
+        assert_eq!(
+            lockout_slots(&arguments),
+            vec![ROOT + 56, ROOT + 58, ROOT + 60, ROOT + 62]
+        );
+        assert!(arguments
+            .iter()
+            .any(|argument| argument.arg_path.ends_with("/root")
+                && argument.unsigned_value == Some(ROOT)));
+    }
+
+    #[test]
+    fn tower_sync() {
+        let tower_sync = TowerSync {
+            lockouts: VecDeque::from([
+                Lockout {
+                    slot: 5,
+                    confirmation_count: 2,
+                },
+                Lockout {
+                    slot: 9,
+                    confirmation_count: 1,
+                },
+            ]),
+            root: None,
+            hash: Hash([1; 32]),
+            timestamp: None,
+            block_id: Hash([2; 32]),
+        };
+        let data = Instruction::new_with_bincode(
+            vote::program::id(),
+            &VoteInstruction::TowerSync(tower_sync.clone()),
+            vec![],
+        )
+        .data;
+
+        // The variant index, the missing root and the offsets of the two lockouts
+        assert_eq!(data[..4], [14, 0, 0, 0]);
+        assert_eq!(data[4..12], u64::MAX.to_le_bytes());
+        assert_eq!(data[12..17], [2, 5, 2, 4, 1]);
+
+        let (_, arguments, program_instruction) = TransactionParser::parse_instruction(
+            &vote::program::id().to_string(),
+            &data,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        let Some(ProgramInstruction::Vote(instruction)) = program_instruction else {
+            panic!("The instruction isn't a vote instruction");
+        };
+        assert_eq!(instruction, VoteInstruction::TowerSync(tower_sync));
+        assert_eq!(lockout_slots(&arguments), vec![5, 9]);
+    }
+
+    #[test]
+    fn lockouts_of_other_instructions_are_kept() {
+        let mut instruction = VoteInstruction::Vote(Vote {
+            slots: vec![1, 2, 3],
+            ..Vote::default()
+        });
+
+        instruction.truncate_lockouts(1);
+
+        assert!(matches!(instruction, VoteInstruction::Vote(vote) if vote.slots.len() == 3));
+    }
+}
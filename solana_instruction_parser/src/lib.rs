@@ -0,0 +1,72 @@
+//! Decoding of Solana transactions into the rows stored by `data_analyzer`: instructions,
+//! their flattened arguments, balances, token transfers, NFT events, commission changes,
+//! Gumdrop claims, Token Entangler swaps, NFT sales, NFT pack events, candy machine mints, stake
+//! account events, auction bid events, SOL transfers and the raw events of anchor programs.
+//!
+//! The crate has no storage or runtime dependencies, `clickhouse::Row` is derived for the row
+//! types only with the `clickhouse` feature.
+
+pub mod errors;
+pub mod instructions;
+mod path_tree;
+mod rows;
+mod transaction_parser;
+
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+pub use errors::{ConvertingError, ParseInstructionError};
+pub use path_tree::PathTree;
+pub use rows::{
+    account_role, raw_idx, AnchorEvent, Balance, BidEvent, CandyMint, ClaimEvent, CommissionChange,
+    EntanglerSwap, Instruction, InstructionArgument, NftEvent, PackEvent, SaleEvent, SolTransfer,
+    StakeAccountEvent, TokenTransfer, TxStatus, ACCOUNTS_ARRAY_SIZE, RAW_IDX_STRIDE,
+};
+pub use solana_instruction_parser_macros::{implement_path_tree, instr_args_parse};
+pub use transaction_parser::{
+    duplicate_inner_instruction_sets, ProgramInstruction, TransactionParser,
+    TransactionParsingResult,
+};
+
+/// Memos longer than this number of characters are truncated
+pub const DEFAULT_MAX_MEMO_LENGTH: usize = 1024;
+
+/// Only this number of the newest lockouts of a vote state update or a tower sync is flattened
+/// into the instruction arguments, a full tower has 31 of them
+pub const DEFAULT_MAX_VOTE_LOCKOUTS: usize = 8;
+
+/// Name of the instructions of the supported programs whose data failed to decode
+pub const UNKNOWN_INSTRUCTION_NAME: &str = "Unknown";
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    pub max_memo_length: usize,
+    pub max_vote_lockouts: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_memo_length: DEFAULT_MAX_MEMO_LENGTH,
+            max_vote_lockouts: DEFAULT_MAX_VOTE_LOCKOUTS,
+        }
+    }
+}
+
+/// Parses a transaction with the default options
+pub fn parse_transaction(
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<TransactionParsingResult, ParseInstructionError> {
+    parse_transaction_with_options(transaction, &ParseOptions::default())
+}
+
+pub fn parse_transaction_with_options(
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+    options: &ParseOptions,
+) -> Result<TransactionParsingResult, ParseInstructionError> {
+    TransactionParser::parse_transactions(transaction, options)
+}
+
+/// Human readable name of the program, "other" for the programs which are not parsed
+pub fn program_name(program_address: &str) -> &'static str {
+    TransactionParser::program_name(program_address)
+}
@@ -0,0 +1,1043 @@
+use crate::InstructionArgument;
+use solana_instruction_parser_macros::implement_path_tree;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+
+/// PathTree represents a tree of paths to arguments for some instruction.
+/// We can iterate through the tree and get vector if InstructionArgument objects.
+#[implement_path_tree(Array(2, 3, 4, 8, 32), Tuple(2))]
+pub enum PathTree {
+    String(String),
+    Int(i64),
+    Unsigned(u64),
+    /// u128 over u64::MAX
+    UnsignedOverflow(u128),
+    /// i128 out of the range of i64
+    IntOverflow(i128),
+    Float(f64),
+    Path(Vec<(String, Box<PathTree>)>),
+    None,
+}
+
+impl<T: Into<PathTree> + Clone> From<HashMap<String, T>> for PathTree {
+    fn from(hash_map: HashMap<String, T>) -> Self {
+        let mut path_vec = Vec::new();
+        hash_map.into_iter().for_each(|(key, val)| {
+            path_vec.push((key, Box::new(val.clone().into())));
+        });
+
+        Self::Path(path_vec)
+    }
+}
+
+impl PathTree {
+    /// Returns a vector of InstructionArgument objects.
+    pub fn get_instruction_args_vec(
+        self,
+        instruction_arguments: &mut Vec<InstructionArgument>,
+        default_instruction_argument: InstructionArgument,
+        arg_idx: &mut u16,
+    ) {
+        match self {
+            Self::String(string_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    string_value: Some(string_value),
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::Int(int_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    int_value: Some(int_value),
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::Unsigned(unsigned_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    unsigned_value: Some(unsigned_value),
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::UnsignedOverflow(unsigned_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    unsigned_value: Some(u64::MAX),
+                    string_value: Some(unsigned_value.to_string()),
+                    overflow: true,
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::IntOverflow(int_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    int_value: Some(if int_value < 0 { i64::MIN } else { i64::MAX }),
+                    string_value: Some(int_value.to_string()),
+                    overflow: true,
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::Float(float_value) => {
+                instruction_arguments.push(InstructionArgument {
+                    float_value: Some(float_value),
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::None => {
+                instruction_arguments.push(InstructionArgument {
+                    arg_idx: *arg_idx,
+                    ..default_instruction_argument
+                });
+                *arg_idx += 1;
+            }
+            Self::Path(path) => {
+                path.into_iter().for_each(|(field_name, path_tree)| {
+                    let mut mock = default_instruction_argument.clone();
+
+                    // This if statement is to avoid adding '/' to the end of the path, but for to the beginning.
+                    if !field_name.is_empty() || *arg_idx == 0 {
+                        mock.arg_path = format!("{}/{}", mock.arg_path, field_name);
+                    }
+
+                    path_tree.get_instruction_args_vec(instruction_arguments, mock, arg_idx);
+                });
+            }
+        };
+    }
+}
+
+// From<..> implementation of basic types for PathTree
+impl<T> From<&std::option::Option<T>> for PathTree
+where
+    T: Into<PathTree> + Clone,
+{
+    fn from(opt: &std::option::Option<T>) -> Self {
+        if let Some(val) = opt {
+            val.clone().into()
+        } else {
+            Self::None
+        }
+    }
+}
+
+impl<T> From<std::option::Option<T>> for PathTree
+where
+    T: Into<PathTree>,
+{
+    fn from(opt: std::option::Option<T>) -> Self {
+        if let Some(val) = opt {
+            val.into()
+        } else {
+            Self::None
+        }
+    }
+}
+
+impl<T> From<&[T]> for PathTree
+where
+    T: Into<PathTree> + Clone,
+{
+    fn from(slice: &[T]) -> Self {
+        let mut path_vec = Vec::new();
+        slice.iter().enumerate().for_each(|(i, val)| {
+            path_vec.push((i.to_string(), Box::new(val.clone().into())));
+        });
+
+        Self::Path(path_vec)
+    }
+}
+
+impl From<solana_program::hash::Hash> for PathTree {
+    fn from(hash: solana_program::hash::Hash) -> Self {
+        hash.as_ref().into()
+    }
+}
+
+impl<T> From<Vec<T>> for PathTree
+where
+    T: Into<PathTree>,
+{
+    fn from(mut vec: Vec<T>) -> Self {
+        let mut path_vec = Vec::new();
+        vec.drain(..).into_iter().enumerate().for_each(|(i, val)| {
+            path_vec.push((i.to_string(), Box::new(val.into())));
+        });
+
+        Self::Path(path_vec)
+    }
+}
+
+impl<T> From<VecDeque<T>> for PathTree
+where
+    T: Into<PathTree>,
+{
+    fn from(mut vec: VecDeque<T>) -> Self {
+        let mut path_vec = Vec::new();
+        vec.drain(..).into_iter().enumerate().for_each(|(i, val)| {
+            path_vec.push((i.to_string(), Box::new(val.into())));
+        });
+
+        Self::Path(path_vec)
+    }
+}
+
+impl From<&str> for PathTree {
+    fn from(string: &str) -> Self {
+        PathTree::String(string.to_string())
+    }
+}
+
+impl From<String> for PathTree {
+    fn from(string: String) -> Self {
+        PathTree::String(string)
+    }
+}
+
+impl From<Pubkey> for PathTree {
+    fn from(pubkey: Pubkey) -> Self {
+        PathTree::String(pubkey.to_string())
+    }
+}
+
+impl From<i128> for PathTree {
+    fn from(int: i128) -> Self {
+        i64::try_from(int).map_or(PathTree::IntOverflow(int), PathTree::Int)
+    }
+}
+
+impl From<i64> for PathTree {
+    fn from(int: i64) -> Self {
+        PathTree::Int(int)
+    }
+}
+
+impl From<i32> for PathTree {
+    fn from(int: i32) -> Self {
+        PathTree::Int(int.into())
+    }
+}
+
+impl From<i16> for PathTree {
+    fn from(int: i16) -> Self {
+        PathTree::Int(int.into())
+    }
+}
+
+impl From<u128> for PathTree {
+    fn from(unsigned: u128) -> Self {
+        u64::try_from(unsigned).map_or(PathTree::UnsignedOverflow(unsigned), PathTree::Unsigned)
+    }
+}
+
+impl From<u64> for PathTree {
+    fn from(unsigned: u64) -> Self {
+        PathTree::Unsigned(unsigned)
+    }
+}
+
+impl From<u32> for PathTree {
+    fn from(unsigned: u32) -> Self {
+        PathTree::Unsigned(unsigned.into())
+    }
+}
+
+impl From<u16> for PathTree {
+    fn from(unsigned: u16) -> Self {
+        PathTree::Unsigned(unsigned.into())
+    }
+}
+
+impl From<u8> for PathTree {
+    fn from(unsigned: u8) -> Self {
+        PathTree::Unsigned(unsigned.into())
+    }
+}
+
+impl From<usize> for PathTree {
+    fn from(usz: usize) -> Self {
+        PathTree::Unsigned(usz.try_into().unwrap())
+    }
+}
+
+impl From<f64> for PathTree {
+    fn from(float: f64) -> Self {
+        PathTree::Float(float)
+    }
+}
+
+impl From<f32> for PathTree {
+    fn from(float: f32) -> Self {
+        PathTree::Float(float.into())
+    }
+}
+
+impl From<bool> for PathTree {
+    fn from(bl: bool) -> Self {
+        PathTree::Int(i64::from(bl))
+    }
+}
+
+#[cfg(test)]
+mod inst_args_parser_tests {
+    use super::*;
+    use crate::instr_args_parse;
+    use std::str::FromStr;
+
+    #[derive(Debug, PartialEq)]
+    #[instr_args_parse]
+    pub enum EnumTest {
+        Variant1,
+        Variant2(f32),
+        Variant3 { field1: i32, field2: Option<String> },
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct NestedPubkeyTest {
+        pubkey: Pubkey,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct NestedTest {
+        field1: Option<Option<u64>>,
+        field2: NestedPubkeyTest,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct ArrayTest {
+        array: [i32; 3],
+        tuple: Option<(i32, String)>,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct TestUnnamed(i32, [i32; 2]);
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct TestUnit;
+
+    #[derive(Debug, PartialEq)]
+    #[instr_args_parse]
+    pub struct Test {
+        field1: u64,
+        field2: std::option::Option<String>,
+        field3: Option<NestedTest>,
+        field4: TestUnnamed,
+        field5: TestUnit,
+        field6: EnumTest,
+        field7: ArrayTest,
+    }
+
+    #[derive(Debug, PartialEq)]
+    #[instr_args_parse(InstrRoot)]
+    enum RootInstr {
+        BoolVariant(bool),
+        EnumVariant(EnumTest, EnumTest),
+    }
+
+    #[test]
+    fn test_root_instr() {
+        let _test1 = RootInstr::EnumVariant(
+            EnumTest::Variant2(1.1),
+            EnumTest::Variant3 {
+                field1: 2,
+                field2: None,
+            },
+        );
+
+        let test1 = RootInstr::EnumVariant(
+            EnumTest::Variant2(1.1),
+            EnumTest::Variant3 {
+                field1: 2,
+                field2: None,
+            },
+        );
+
+        assert_eq!(
+            test1.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/0/variant_2".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/0/variant_2/0".to_string(),
+                    float_value: Some(1.1f32 as f64), // WARNING: precision issues!
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/1/variant_3".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 3,
+                    arg_path: "/1/variant_3/field1".to_string(),
+                    int_value: Some(2),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 4,
+                    arg_path: "/1/variant_3/field2".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simple_fields() {
+        let test1 = EnumTest::Variant1;
+        assert_eq!(
+            test1.get_arguments("123", 0, None, "program"),
+            vec![InstructionArgument {
+                tx_signature: "123".to_string(),
+                instruction_idx: 0,
+                inner_instructions_set: None,
+                program: "program".to_string(),
+                arg_idx: 0,
+                arg_path: "/variant_1".to_string(),
+                ..Default::default()
+            }]
+        );
+
+        let test2 = TestUnit;
+        assert_eq!(
+            test2.get_arguments("123", 0, None, "program"),
+            vec![InstructionArgument {
+                tx_signature: "123".to_string(),
+                instruction_idx: 0,
+                inner_instructions_set: None,
+                program: "program".to_string(),
+                arg_idx: 0,
+                arg_path: "/test_unit".to_string(),
+                ..Default::default()
+            }]
+        );
+
+        let test3 = TestUnnamed(1, [2, 4]);
+        assert_eq!(
+            test3.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/0".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/1/0".to_string(),
+                    int_value: Some(2),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/1/1".to_string(),
+                    int_value: Some(4),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let test4 = EnumTest::Variant2(228.1337);
+        assert_eq!(
+            test4.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/variant_2".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/variant_2/0".to_string(),
+                    float_value: Some(228.1337f32 as f64), // WARNING: precision issues!
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let test5 = RootInstr::BoolVariant(true);
+
+        assert_eq!(
+            test5.get_arguments("123", 0, None, "program"),
+            vec![InstructionArgument {
+                tx_signature: "123".to_string(),
+                instruction_idx: 0,
+                inner_instructions_set: None,
+                program: "program".to_string(),
+                arg_idx: 0,
+                arg_path: "/0".to_string(),
+                int_value: Some(1),
+                ..Default::default()
+            },]
+        );
+    }
+
+    #[test]
+    fn test_advanced_fields() {
+        let test1 = ArrayTest {
+            array: [1, 2, 3],
+            tuple: Some((4, "5".to_string())),
+        };
+        assert_eq!(
+            test1.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/array/0".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/array/1".to_string(),
+                    int_value: Some(2),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/array/2".to_string(),
+                    int_value: Some(3),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 3,
+                    arg_path: "/tuple/0".to_string(),
+                    int_value: Some(4),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 4,
+                    arg_path: "/tuple/1".to_string(),
+                    string_value: Some("5".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let test2 = EnumTest::Variant3 {
+            field1: 228,
+            field2: Some("TestString".to_string()),
+        };
+
+        assert_eq!(
+            test2.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/variant_3".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/variant_3/field1".to_string(),
+                    int_value: Some(228),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/variant_3/field2".to_string(),
+                    string_value: Some("TestString".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_fields() {
+        let test1 = Test {
+            field1: 100,
+            field2: None,
+            field3: Some(NestedTest {
+                field1: Some(Some(1337)),
+                field2: NestedPubkeyTest {
+                    pubkey: Pubkey::from_str("11111111111111111111111111111111").unwrap(),
+                },
+            }),
+            field4: TestUnnamed(32, [64, 128]),
+            field5: TestUnit,
+            field6: EnumTest::Variant3 {
+                field1: 1,
+                field2: Some("TestField".to_string()),
+            },
+            field7: ArrayTest {
+                array: [1, 2, 3],
+                tuple: Some((4, "5".to_string())),
+            },
+        };
+
+        assert_eq!(
+            test1.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/field1".to_string(),
+                    unsigned_value: Some(100),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/field2".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/field3/field1".to_string(),
+                    unsigned_value: Some(1337),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 3,
+                    arg_path: "/field3/field2/pubkey".to_string(),
+                    string_value: Some("11111111111111111111111111111111".to_string()),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 4,
+                    arg_path: "/field4/0".to_string(),
+                    int_value: Some(32),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 5,
+                    arg_path: "/field4/1/0".to_string(),
+                    int_value: Some(64),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 6,
+                    arg_path: "/field4/1/1".to_string(),
+                    int_value: Some(128),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 7,
+                    arg_path: "/field5/test_unit".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 8,
+                    arg_path: "/field6/variant_3".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 9,
+                    arg_path: "/field6/variant_3/field1".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 10,
+                    arg_path: "/field6/variant_3/field2".to_string(),
+                    string_value: Some("TestField".to_string()),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 11,
+                    arg_path: "/field7/array/0".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 12,
+                    arg_path: "/field7/array/1".to_string(),
+                    int_value: Some(2),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 13,
+                    arg_path: "/field7/array/2".to_string(),
+                    int_value: Some(3),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 14,
+                    arg_path: "/field7/tuple/0".to_string(),
+                    int_value: Some(4),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 15,
+                    arg_path: "/field7/tuple/1".to_string(),
+                    string_value: Some("5".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let test2 = Test {
+            field1: 100,
+            field2: None,
+            field3: None,
+            field4: TestUnnamed(32, [64, 128]),
+            field5: TestUnit,
+            field6: EnumTest::Variant3 {
+                field1: 1,
+                field2: Some("TestField".to_string()),
+            },
+            field7: ArrayTest {
+                array: [1, 2, 3],
+                tuple: Some((4, "5".to_string())),
+            },
+        };
+
+        assert_eq!(
+            test2.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 0,
+                    arg_path: "/field1".to_string(),
+                    unsigned_value: Some(100),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 1,
+                    arg_path: "/field2".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 2,
+                    arg_path: "/field3".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 3,
+                    arg_path: "/field4/0".to_string(),
+                    int_value: Some(32),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 4,
+                    arg_path: "/field4/1/0".to_string(),
+                    int_value: Some(64),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 5,
+                    arg_path: "/field4/1/1".to_string(),
+                    int_value: Some(128),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 6,
+                    arg_path: "/field5/test_unit".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 7,
+                    arg_path: "/field6/variant_3".to_string(),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 8,
+                    arg_path: "/field6/variant_3/field1".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 9,
+                    arg_path: "/field6/variant_3/field2".to_string(),
+                    string_value: Some("TestField".to_string()),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 10,
+                    arg_path: "/field7/array/0".to_string(),
+                    int_value: Some(1),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 11,
+                    arg_path: "/field7/array/1".to_string(),
+                    int_value: Some(2),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 12,
+                    arg_path: "/field7/array/2".to_string(),
+                    int_value: Some(3),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 13,
+                    arg_path: "/field7/tuple/0".to_string(),
+                    int_value: Some(4),
+                    ..Default::default()
+                },
+                InstructionArgument {
+                    tx_signature: "123".to_string(),
+                    instruction_idx: 0,
+                    inner_instructions_set: None,
+                    program: "program".to_string(),
+                    arg_idx: 14,
+                    arg_path: "/field7/tuple/1".to_string(),
+                    string_value: Some("5".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[instr_args_parse]
+    pub struct WideIntegersTest {
+        small_unsigned: u128,
+        large_unsigned: u128,
+        small_int: i128,
+        large_int: i128,
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        let large_unsigned = u64::MAX as u128 + 1;
+        let large_int = i64::MIN as i128 - 1;
+        let test = WideIntegersTest {
+            small_unsigned: 1,
+            large_unsigned,
+            small_int: -1,
+            large_int,
+        };
+
+        let argument = |arg_idx: u16, arg_path: &str| InstructionArgument {
+            tx_signature: "123".to_string(),
+            program: "program".to_string(),
+            arg_idx,
+            arg_path: arg_path.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            test.get_arguments("123", 0, None, "program"),
+            vec![
+                InstructionArgument {
+                    unsigned_value: Some(1),
+                    ..argument(0, "/small_unsigned")
+                },
+                InstructionArgument {
+                    unsigned_value: Some(u64::MAX),
+                    string_value: Some("18446744073709551616".to_string()),
+                    overflow: true,
+                    ..argument(1, "/large_unsigned")
+                },
+                InstructionArgument {
+                    int_value: Some(-1),
+                    ..argument(2, "/small_int")
+                },
+                InstructionArgument {
+                    int_value: Some(i64::MIN),
+                    string_value: Some("-9223372036854775809".to_string()),
+                    overflow: true,
+                    ..argument(3, "/large_int")
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,629 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::cmp::Ordering;
+
+/// Number of the account columns of an instruction, the most accounts an instruction may have.
+/// The storages pad the accounts of an instruction with None to it
+pub const ACCOUNTS_ARRAY_SIZE: usize = 256;
+
+/// Numbers `raw_idx` reserves for an outer instruction: the instruction itself and up to 256
+/// inner ones
+pub const RAW_IDX_STRIDE: u32 = 512;
+
+/// Position of an instruction in its transaction as one number, stored as `raw_instruction_idx`.
+/// `outer` is the index of the top-level instruction and `inner` the index of the inner
+/// instruction it invoked, if any: `outer * 512` for a top-level instruction and
+/// `outer * 512 + inner + 1` for an inner one, so the top-level index takes the bits above the
+/// lowest 9. Every pair gets its own number and the numbers follow the execution order, a
+/// top-level instruction before its inner ones and those before the next top-level instruction.
+/// The highest number, 130816, needs a `u32`
+pub fn raw_idx(outer: u8, inner: Option<u8>) -> u32 {
+    u32::from(outer) * RAW_IDX_STRIDE + inner.map_or(0, |inner| u32::from(inner) + 1)
+}
+
+/// Flags of an account as they are packed in `Instruction::account_roles`: `W` for a writable
+/// signer, `R` for a readonly signer, `w` and `r` for the accounts which don't sign
+pub fn account_role(signer: bool, writable: bool) -> char {
+    match (signer, writable) {
+        (true, true) => 'W',
+        (true, false) => 'R',
+        (false, true) => 'w',
+        (false, false) => 'r',
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize_repr, Serialize_repr)]
+#[repr(u8)]
+pub enum TxStatus {
+    Failed = 0,
+    Success = 1,
+    Undefined = 2,
+    /// The instruction comes after the failed one, so it was never executed
+    NotExecuted = 3,
+}
+
+impl From<TxStatus> for i8 {
+    fn from(tx_status: TxStatus) -> Self {
+        match tx_status {
+            TxStatus::Failed => 0,
+            TxStatus::Success => 1,
+            TxStatus::Undefined => 2,
+            TxStatus::NotExecuted => 3,
+        }
+    }
+}
+
+/// Status of the stored `tx_status` value, Undefined for an unknown one
+impl From<i8> for TxStatus {
+    fn from(tx_status: i8) -> Self {
+        match tx_status {
+            0 => TxStatus::Failed,
+            1 => TxStatus::Success,
+            3 => TxStatus::NotExecuted,
+            _ => TxStatus::Undefined,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct Instruction {
+    pub program: String,
+    pub tx_signature: String,
+    pub tx_status: TxStatus,
+    /// Index of the top-level instruction which failed the transaction
+    pub failed_instruction_idx: Option<u8>,
+    pub slot: u64,
+    /// None when neither the transaction nor the analyzer could tell the time of its block
+    pub block_time: Option<u64>,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub transaction_instruction_idx: Option<u8>,
+    /// Program which invoked the inner instruction, None for the top-level ones
+    pub parent_program: Option<String>,
+    /// CPI depth of the inner instruction, the top-level instructions are at height 1
+    pub stack_height: Option<u8>,
+    pub instruction_name: String,
+    /// Accounts the instruction uses in their order, at most `ACCOUNTS_ARRAY_SIZE` of them
+    pub accounts: Vec<String>,
+    /// Signer and writable flags of `accounts` in the transaction, one `account_role` per account
+    pub account_roles: String,
+    /// Version number of the transaction, None for the legacy ones
+    pub tx_version: Option<u8>,
+    /// Length of the signature list of the transaction
+    pub num_signatures: u8,
+    pub data: String,
+}
+
+impl Instruction {
+    /// Account of the instruction at the position, None past its accounts as in the `account_N`
+    /// columns
+    pub fn account(&self, idx: usize) -> Option<String> {
+        self.accounts.get(idx).cloned()
+    }
+
+    /// `raw_idx` of the instruction, an inner instruction is located by the index of the
+    /// top-level one in `transaction_instruction_idx`
+    pub fn get_raw_instruction_idx(&self) -> u32 {
+        match self.transaction_instruction_idx {
+            Some(outer) => raw_idx(outer, Some(self.instruction_idx)),
+            None => raw_idx(self.instruction_idx, None),
+        }
+    }
+}
+
+/// Instructions are ordered by slot, then by transaction and in the execution order within one:
+/// every top-level instruction followed by its inner ones in their order, the canonical order
+/// `TransactionParser::parse_transactions` returns them in. The transactions of a slot are
+/// ordered by signature, as their position in the block is not known, so two instructions are
+/// only equal at the same position of the same transaction
+impl Ord for Instruction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ord = self
+            .slot
+            .cmp(&other.slot)
+            .then_with(|| self.tx_signature.cmp(&other.tx_signature));
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+
+        let raw_instruction_idx1 = self.get_raw_instruction_idx();
+        let raw_instruction_idx2 = other.get_raw_instruction_idx();
+
+        raw_instruction_idx1.cmp(&raw_instruction_idx2)
+    }
+}
+
+impl PartialOrd for Instruction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+#[allow(unused)]
+impl Instruction {
+    pub fn new(program: &Pubkey, tx_signature: &Signature) -> Self {
+        Self {
+            program: program.to_string(),
+            tx_signature: tx_signature.to_string(),
+            tx_status: TxStatus::Undefined,
+            failed_instruction_idx: None,
+            slot: 0,
+            block_time: None,
+            instruction_idx: 0,
+            inner_instructions_set: None,
+            transaction_instruction_idx: None,
+            parent_program: None,
+            stack_height: None,
+            instruction_name: String::from(""),
+            accounts: Vec::new(),
+            account_roles: String::new(),
+            tx_version: None,
+            num_signatures: 0,
+            data: String::from(""),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Balance {
+    pub tx_signature: String,
+    pub account: String,
+    pub pre_balance: Option<u64>,
+    pub post_balance: Option<u64>,
+    pub pre_token_balance_mint: Option<String>,
+    pub pre_token_balance_owner: Option<String>,
+    pub pre_token_balance_amount: Option<f64>,
+    pub pre_token_balance_program_id: Option<String>,
+    pub post_token_balance_mint: Option<String>,
+    pub post_token_balance_owner: Option<String>,
+    pub post_token_balance_amount: Option<f64>,
+    pub post_token_balance_program_id: Option<String>,
+    /// Inner instructions set of the SPL Token transfer the row is attributed to, None for the
+    /// row of the whole transaction
+    pub inner_instructions_set: Option<u8>,
+    /// Tokens moved by the attributed transfer in the mint's base units, None for the row of the
+    /// whole transaction
+    pub attributed_amount: Option<u64>,
+}
+
+/// Net movement of a mint between two owners within a transaction, derived from the difference
+/// of pre and post token balances. Flows are netted per (mint, owner), so a multi-hop transfer
+/// A -> B -> C within one transaction is stored as A -> C. `source_owner` is None for minted
+/// tokens, `destination_owner` is None for burned ones. `amount` is in the mint's base units
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct TokenTransfer {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub block_time: u64,
+    pub mint: String,
+    pub source_owner: Option<String>,
+    pub destination_owner: Option<String>,
+    pub amount: u64,
+    pub decimals: u8,
+    pub program_id: Option<String>,
+}
+
+/// Token Metadata instruction creating, updating, verifying or transferring an NFT, with the
+/// fields analysts look for taken from the decoded instruction. `mint` is None for `Verify`
+/// whose accounts don't include it, `collection_key` is the collection mint for a collection
+/// verification. `name` and `uri` are only set by the instructions which carry the data
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct NftEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub mint: Option<String>,
+    pub collection_key: Option<String>,
+    pub token_standard: Option<String>,
+    pub event_type: String,
+    pub name: Option<String>,
+    pub uri: Option<String>,
+}
+
+/// Commission of a vote account set by a vote program `UpdateCommission` instruction of a
+/// successful transaction. `authority` is the withdraw authority which signed it.
+/// The instruction carries only the new commission, so `old_commission` is known only if an
+/// earlier `UpdateCommission` of the same transaction changed the vote account
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct CommissionChange {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub vote_account: String,
+    pub authority: Option<String>,
+    pub old_commission: Option<u8>,
+    pub new_commission: u8,
+}
+
+/// Claim from a Gumdrop distributor by a `Claim` or `ClaimCandy` instruction of a successful
+/// transaction. `claimant` is the key the claim was issued to in the distributor's merkle tree,
+/// `amount` is the number of tokens of `Claim` or the number of mints allowed by `ClaimCandy`.
+/// `mint` is the mint of the token account receiving the tokens, known from the token balances,
+/// or the candy machine mint of `ClaimCandy`
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct ClaimEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub distributor: String,
+    pub claimant: String,
+    pub amount: u64,
+    pub mint: Option<String>,
+}
+
+/// Swap of a token for its entangled one by a Token Entangler `Swap` instruction of a successful
+/// transaction. `mint_a` and `mint_b` are the mints of the pair in the order the entangled pair
+/// account was derived from, `direction` is "AToB" when the token of `mint_a` is given for the one
+/// of `mint_b`, "BToA" for the reverse and "Unknown" when the accounts match no known layout of the
+/// program. `price` is not passed to `Swap`, so it is left empty; the price of the pair is in the
+/// arguments of its `CreateEntangledPair` and `UpdateEntangledPair` instructions
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct EntanglerSwap {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub payer: String,
+    pub mint_a: String,
+    pub mint_b: String,
+    pub direction: String,
+    pub price: Option<u64>,
+}
+
+/// Event an anchor program emitted by a `Program data: <base64>` log line of a successful
+/// transaction, or the data its last instruction returned with `sol_set_return_data`. `source` is
+/// "log" or "return_data". The event is stored undecoded: `data` is the base64 payload as logged
+/// and `discriminator` is the hex of its first 8 bytes, which anchor derives from the event name.
+/// `instruction_idx` and `stack_height` are those of the invocation the line was logged in, known
+/// from the preceding `Program <id> invoke [<height>]` lines; `event_idx` orders the events of the
+/// transaction
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct AnchorEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub program: String,
+    pub source: String,
+    pub event_idx: u16,
+    pub instruction_idx: Option<u8>,
+    pub stack_height: Option<u8>,
+    pub discriminator: String,
+    pub data: String,
+}
+
+/// Sale of an NFT by an Auction House `ExecuteSale`, `ExecutePartialSale` or one of their
+/// auctioneer variants, a Fixed Price Sale `Buy` or a Token Entangler `Swap` instruction of a
+/// successful transaction. `instruction_idx` and `inner_instructions_set` locate the instruction,
+/// so the sales of one transaction are told apart. `price_lamports` is the price of the whole
+/// `token_size` in the base units of `currency_mint`, lamports for native SOL; it is taken from
+/// the instruction arguments, so it is empty for `Buy` and `Swap` which don't carry it. `seller`
+/// is empty when the tokens come from the program rather than an account owner
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct SaleEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub block_time: u64,
+    pub marketplace_program: String,
+    pub instruction_name: String,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub mint: String,
+    pub seller: Option<String>,
+    pub buyer: String,
+    pub price_lamports: Option<u64>,
+    pub token_size: u64,
+    pub currency_mint: Option<String>,
+}
+
+/// Pack of the NFT Packs program opened, claimed or deactivated by a successful transaction.
+/// `event_type` is "Open" for `RequestCardForRedeem`, which draws the card the user may claim,
+/// "Claim" for `ClaimPack` and "Deactivate" for `Deactivate`. `claimer` is the user wallet of
+/// the opened or claimed pack; `pack_card` is only known to `ClaimPack`, and `new_mint` is the
+/// mint of the edition it prints, taken from its Token Metadata
+/// `MintNewEditionFromMasterEditionViaToken` inner instruction
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct PackEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub pack_set: String,
+    pub pack_card: Option<String>,
+    pub claimer: Option<String>,
+    pub new_mint: Option<String>,
+    pub event_type: String,
+}
+
+/// NFT minted by a Candy Machine v2 `MintNft` instruction of a successful transaction, located by
+/// the instruction index and inner instructions set. `minter` is the payer of the mint and `mint`
+/// the new mint initialized or minted to by an SPL Token `InitializeMint` or `MintTo` instruction
+/// of the transaction. `price_lamports` is the amount the minter paid to the wallet of the
+/// machine in the base units of `currency_mint`, lamports when it is empty, taken from the
+/// balances of the whole transaction; `collection` is the collection mint set by the
+/// `SetCollectionDuringMint` instruction of the mint
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct CandyMint {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub candy_machine: String,
+    pub minter: String,
+    pub mint: Option<String>,
+    pub price_lamports: Option<u64>,
+    pub currency_mint: Option<String>,
+    pub collection: Option<String>,
+}
+
+/// Change of the lifecycle of a stake account by a stake program instruction of a successful
+/// transaction. `event_type` is one of `Initialize`, `Authorize`, `SetLockup`, `Deactivate`
+/// and `Withdraw`, an `Initialize` has a row per authority. `authority_type` is `Staker`,
+/// `Withdrawer` or `Custodian` for the events setting an authority, `lockup_expiration` is the
+/// unix timestamp of the lockup set by `Initialize` and `SetLockup`. The instructions carry only
+/// the new authority, so `old_authority` is always None
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct StakeAccountEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub stake_acc: String,
+    pub event_type: String,
+    pub authority_type: Option<String>,
+    pub old_authority: Option<String>,
+    pub new_authority: Option<String>,
+    pub lockup_expiration: Option<i64>,
+}
+
+/// Bid of an Auction program auction placed, cancelled, refunded or won by a successful
+/// transaction. `event_type` is "Place" for `PlaceBid` with the bid amount taken from its
+/// arguments, "Cancel" and "Refund" for `CancelBid`, the refund carrying the amount of the pot
+/// sent back to the bidder, and "Win" for `ClaimBid`, also when it is invoked by the Metaplex
+/// `ClaimBid`, with the amount of the pot paid to the auction. The settled amounts are taken
+/// from the SPL Token transfers invoked by the instruction and are empty without them. An
+/// increased bid is a new `Place` row, the current bid of a bidder is the greatest one
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct BidEvent {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub auction: String,
+    pub bidder: String,
+    pub amount: Option<u64>,
+    pub event_type: String,
+}
+
+/// Lamports moved by a system program instruction of a successful transaction, a top-level one
+/// or one invoked by another program. `transfer_kind` is "Transfer" and "TransferWithSeed" for
+/// the transfers, "CreateAccountFunding" for the lamports `CreateAccount` and
+/// `CreateAccountWithSeed` fund the new account with and "WithdrawNonce" for
+/// `WithdrawNonceAccount`. The fee and the rewards are not moved by an instruction, so they have
+/// no rows, and neither have the instructions moving no lamports
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "clickhouse", derive(clickhouse::Row))]
+pub struct SolTransfer {
+    pub tx_signature: String,
+    pub slot: u64,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub source: String,
+    pub destination: String,
+    pub lamports: u64,
+    pub transfer_kind: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct InstructionArgument {
+    pub tx_signature: String,
+    pub instruction_idx: u8,
+    pub inner_instructions_set: Option<u8>,
+    pub program: String,
+    pub arg_idx: u16,
+    pub arg_path: String,
+    pub int_value: Option<i64>,
+    pub unsigned_value: Option<u64>,
+    pub float_value: Option<f64>,
+    pub string_value: Option<String>,
+    /// The integer is out of the range of `int_value` or `unsigned_value`. The column holds the
+    /// saturated value and `string_value` the exact one
+    pub overflow: bool,
+}
+
+impl InstructionArgument {
+    pub fn new(
+        tx_signature: &str,
+        instruction_idx: u8,
+        inner_instructions_set: Option<u8>,
+        program: &str,
+    ) -> Self {
+        Self {
+            tx_signature: tx_signature.to_string(),
+            instruction_idx,
+            inner_instructions_set,
+            program: program.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn tx_status_variants() {
+        let cases = [
+            (0, TxStatus::Failed),
+            (1, TxStatus::Success),
+            (2, TxStatus::Undefined),
+            (3, TxStatus::NotExecuted),
+        ];
+
+        for (i, status) in cases {
+            let status: i8 = status.into();
+            assert_eq!(i as i8, status);
+        }
+    }
+
+    #[test]
+    fn test_new_instruction() {
+        let pkey = Pubkey::from_str("SaLeTjyUa5wXHnGuewUSyJ5JWZaHwz3TxqUntCE9czo").unwrap();
+        let signature = Signature::from_str(
+            "3o3WMi2xfsyt9GhJt1z8XbcauANLFtpLbgH9wvpwQDFiQ3H2MLyMtXVHrZi3wX5UXZEENnAFUFnTLu7G8ybjiR4x",
+        )
+        .unwrap();
+
+        let instruction = Instruction::new(&pkey, &signature);
+
+        assert_eq!(pkey.to_string(), instruction.program);
+        assert_eq!(signature.to_string(), instruction.tx_signature);
+        assert_eq!(TxStatus::Undefined, instruction.tx_status);
+
+        for account in instruction.accounts {
+            assert_eq!(None, account);
+        }
+
+        assert_eq!("", instruction.data);
+    }
+
+    /// Every top-level index with no inner instruction and each of the inner ones, in the
+    /// execution order
+    fn all_positions() -> Vec<(u8, Option<u8>)> {
+        (0..=u8::MAX)
+            .flat_map(|outer| {
+                std::iter::once((outer, None))
+                    .chain((0..=u8::MAX).map(move |inner| (outer, Some(inner))))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn raw_idx_is_injective() {
+        let positions = all_positions();
+        let raw_idxs: HashSet<u32> = positions
+            .iter()
+            .map(|(outer, inner)| raw_idx(*outer, *inner))
+            .collect();
+
+        assert_eq!(raw_idxs.len(), positions.len());
+    }
+
+    #[test]
+    fn raw_idx_preserves_execution_order() {
+        let raw_idxs: Vec<u32> = all_positions()
+            .into_iter()
+            .map(|(outer, inner)| raw_idx(outer, inner))
+            .collect();
+
+        assert!(raw_idxs.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(raw_idxs.first(), Some(&0));
+        assert_eq!(raw_idxs.last(), Some(&130816));
+    }
+
+    #[test]
+    fn raw_idx_of_the_last_inner_instruction() {
+        // The old encoding gave both of them 256
+        assert_eq!(raw_idx(0, Some(255)), 256);
+        assert_eq!(raw_idx(1, None), 512);
+    }
+
+    #[test]
+    fn instructions_are_ordered_by_raw_idx() {
+        let signature = Signature::default();
+        let instruction = |outer: u8, inner: Option<u8>| {
+            let mut instruction = Instruction::new(&Pubkey::default(), &signature);
+            match inner {
+                Some(inner) => {
+                    instruction.transaction_instruction_idx = Some(outer);
+                    instruction.instruction_idx = inner;
+                }
+                None => instruction.instruction_idx = outer,
+            }
+            instruction
+        };
+
+        let mut instructions = vec![
+            instruction(1, None),
+            instruction(0, Some(255)),
+            instruction(255, Some(255)),
+            instruction(0, None),
+            instruction(255, None),
+        ];
+        instructions.sort();
+
+        let raw_idxs: Vec<u32> = instructions
+            .iter()
+            .map(Instruction::get_raw_instruction_idx)
+            .collect();
+        assert_eq!(raw_idxs, vec![0, 256, 512, 130560, 130816]);
+    }
+
+    #[test]
+    fn shuffled_instructions_sort_into_the_execution_order() {
+        let instruction = |tx_signature: &str, outer: u8, inner: Option<u8>| {
+            let mut instruction = Instruction::new(&Pubkey::default(), &Signature::default());
+            instruction.tx_signature = tx_signature.to_string();
+            instruction.slot = 100;
+            match inner {
+                Some(inner) => {
+                    instruction.transaction_instruction_idx = Some(outer);
+                    instruction.inner_instructions_set = Some(outer);
+                    instruction.instruction_idx = inner;
+                }
+                None => instruction.instruction_idx = outer,
+            }
+            instruction
+        };
+        let position = |instruction: &Instruction| {
+            (
+                instruction.tx_signature.clone(),
+                instruction.transaction_instruction_idx,
+                instruction.instruction_idx,
+            )
+        };
+
+        // Two transactions of the same slot, both with the same positions
+        let execution_order: Vec<Instruction> = ["a", "b"]
+            .into_iter()
+            .flat_map(|tx_signature| {
+                [
+                    instruction(tx_signature, 0, None),
+                    instruction(tx_signature, 0, Some(0)),
+                    instruction(tx_signature, 0, Some(1)),
+                    instruction(tx_signature, 1, None),
+                    instruction(tx_signature, 2, None),
+                    instruction(tx_signature, 2, Some(0)),
+                ]
+            })
+            .collect();
+        let expected: Vec<_> = execution_order.iter().map(position).collect();
+
+        // Every rotation of the reversed instructions
+        for rotation in 0..execution_order.len() {
+            let mut instructions: Vec<Instruction> =
+                execution_order.iter().rev().cloned().collect();
+            instructions.rotate_left(rotation);
+            instructions.sort();
+
+            assert_eq!(
+                instructions.iter().map(position).collect::<Vec<_>>(),
+                expected
+            );
+        }
+
+        assert_ne!(execution_order[0], execution_order[6]);
+    }
+}
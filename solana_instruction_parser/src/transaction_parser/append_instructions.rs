@@ -0,0 +1,857 @@
+use crate::errors::ParseInstructionError;
+use crate::{
+    ClaimEvent, CommissionChange, EntanglerSwap, Instruction, InstructionArgument, NftEvent,
+    ParseOptions, SolTransfer, StakeAccountEvent, TxStatus, ACCOUNTS_ARRAY_SIZE,
+    UNKNOWN_INSTRUCTION_NAME,
+};
+
+use log::debug;
+use rust_base58::FromBase58;
+use solana_transaction_status::{UiCompiledInstruction, UiInnerInstructions, UiInstruction};
+use std::collections::{BTreeSet, HashMap};
+
+use super::{ProgramInstruction, TransactionParser};
+
+/// Data of an instruction as it is stored along with the name taken from its JSON
+struct DecodedInstruction {
+    name: String,
+    data: String,
+    arguments: Vec<InstructionArgument>,
+    program_instruction: Option<ProgramInstruction>,
+}
+
+impl TransactionParser {
+    pub fn append_instructions(
+        instructions: Vec<UiCompiledInstruction>,
+        inner_instructions: Option<Vec<UiInnerInstructions>>,
+        log_messages: Option<Vec<String>>,
+        accounts: Vec<String>,
+        account_roles: Vec<char>,
+        tx_signature: String,
+        slot: u64,
+        block_time: Option<u64>,
+        tx_status: TxStatus,
+        tx_version: Option<u8>,
+        num_signatures: u8,
+        instructions_set: &mut BTreeSet<Instruction>,
+        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
+        nft_events: &mut Vec<NftEvent>,
+        commission_changes: &mut Vec<CommissionChange>,
+        claim_events: &mut Vec<ClaimEvent>,
+        entangler_swaps: &mut Vec<EntanglerSwap>,
+        stake_account_events: &mut Vec<StakeAccountEvent>,
+        sol_transfers: &mut Vec<SolTransfer>,
+        token_mints: &HashMap<String, String>,
+        options: &ParseOptions,
+    ) -> Result<(), ParseInstructionError> {
+        let outer_programs: Vec<String> = instructions
+            .iter()
+            .map(|instruction| {
+                accounts
+                    .get(instruction.program_id_index as usize)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        Self::append_outer_instruction(
+            instructions,
+            accounts.clone(),
+            &account_roles,
+            tx_signature.clone(),
+            slot,
+            block_time,
+            tx_status,
+            tx_version,
+            num_signatures,
+            instructions_set,
+            parsed_instruction_arguments,
+            nft_events,
+            commission_changes,
+            claim_events,
+            entangler_swaps,
+            stake_account_events,
+            sol_transfers,
+            token_mints,
+            options,
+        )?;
+
+        Self::append_inner_instruction(
+            inner_instructions,
+            log_messages,
+            &outer_programs,
+            accounts.clone(),
+            &account_roles,
+            tx_signature.clone(),
+            slot,
+            block_time,
+            tx_status,
+            tx_version,
+            num_signatures,
+            instructions_set,
+            parsed_instruction_arguments,
+            nft_events,
+            commission_changes,
+            claim_events,
+            entangler_swaps,
+            stake_account_events,
+            sol_transfers,
+            token_mints,
+            options,
+        )?;
+
+        Ok(())
+    }
+
+    /// A failed transaction changes nothing, so its NFT events, commission changes, claim events,
+    /// entangler swaps, stake account events and SOL transfers are not emitted
+    fn append_program_rows(
+        program_instruction: Option<&ProgramInstruction>,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+        instruction_idx: u8,
+        inner_instructions_set: Option<u8>,
+        tx_status: TxStatus,
+        nft_events: &mut Vec<NftEvent>,
+        commission_changes: &mut Vec<CommissionChange>,
+        claim_events: &mut Vec<ClaimEvent>,
+        entangler_swaps: &mut Vec<EntanglerSwap>,
+        stake_account_events: &mut Vec<StakeAccountEvent>,
+        sol_transfers: &mut Vec<SolTransfer>,
+        token_mints: &HashMap<String, String>,
+    ) {
+        if tx_status == TxStatus::Failed {
+            return;
+        }
+
+        match program_instruction {
+            Some(ProgramInstruction::Metadata(instruction)) => {
+                nft_events.extend(Self::parse_nft_event(
+                    instruction,
+                    accounts,
+                    tx_signature,
+                    slot,
+                ));
+            }
+            Some(ProgramInstruction::Vote(instruction)) => {
+                let commission_change = Self::parse_commission_change(
+                    instruction,
+                    accounts,
+                    tx_signature,
+                    slot,
+                    commission_changes,
+                );
+                commission_changes.extend(commission_change);
+            }
+            Some(ProgramInstruction::Gumdrop(instruction)) => {
+                claim_events.extend(Self::parse_claim_event(
+                    instruction,
+                    accounts,
+                    token_mints,
+                    tx_signature,
+                    slot,
+                ));
+            }
+            Some(ProgramInstruction::TokenEntangler(instruction)) => {
+                entangler_swaps.extend(Self::parse_entangler_swap(
+                    instruction,
+                    accounts,
+                    tx_signature,
+                    slot,
+                ));
+            }
+            Some(ProgramInstruction::Stake(instruction)) => {
+                stake_account_events.extend(Self::parse_stake_account_events(
+                    instruction,
+                    accounts,
+                    tx_signature,
+                    slot,
+                ));
+            }
+            Some(ProgramInstruction::System(instruction)) => {
+                sol_transfers.extend(Self::parse_sol_transfer(
+                    instruction,
+                    accounts,
+                    tx_signature,
+                    slot,
+                    instruction_idx,
+                    inner_instructions_set,
+                ));
+            }
+            None => {}
+        }
+    }
+
+    /// Roles of the accounts of an instruction packed in the order of the accounts
+    fn instruction_account_roles(account_indexes: &[u8], account_roles: &[char]) -> String {
+        account_indexes
+            .iter()
+            .filter_map(|account_idx| account_roles.get(*account_idx as usize))
+            .collect()
+    }
+
+    /// Instructions of the programs which are not parsed keep the raw data. The ones of the
+    /// supported programs whose data failed to decode, e.g. a variant added to the program after
+    /// the parser, are named "Unknown" and get the discriminant byte as the only argument
+    fn decode_instruction(
+        program_address: &str,
+        data: &str,
+        options: &ParseOptions,
+    ) -> Result<DecodedInstruction, ParseInstructionError> {
+        let parsed_data =
+            TransactionParser::parse_instruction(program_address, &data.from_base58()?, options);
+
+        let (json, arguments, program_instruction) = match parsed_data {
+            Ok(parsed_data) => parsed_data,
+            Err(ParseInstructionError::ProgramAddressMatchError) => {
+                return Ok(DecodedInstruction {
+                    name: Default::default(),
+                    data: data.to_string(),
+                    arguments: Vec::new(),
+                    program_instruction: None,
+                })
+            }
+            Err(ParseInstructionError::InstructionDecodeError {
+                program,
+                discriminant,
+                err,
+            }) => {
+                debug!("Failed to decode instruction of {}: {}", program, err);
+
+                return Ok(DecodedInstruction {
+                    name: UNKNOWN_INSTRUCTION_NAME.to_string(),
+                    data: data.to_string(),
+                    arguments: vec![InstructionArgument {
+                        arg_path: "/discriminant".to_string(),
+                        unsigned_value: discriminant.map(u64::from),
+                        ..Default::default()
+                    }],
+                    program_instruction: None,
+                });
+            }
+            Err(err) => return Err(err),
+        };
+
+        let splitted_data = json.split('\"').collect::<Vec<&str>>();
+
+        let name = if splitted_data.len() > 2 {
+            splitted_data[1].to_string()
+        } else if splitted_data.len() == 1 {
+            std::default::Default::default()
+        } else {
+            return Err(ParseInstructionError::InvalidInstructionName);
+        };
+
+        Ok(DecodedInstruction {
+            name,
+            data: json,
+            arguments,
+            program_instruction,
+        })
+    }
+
+    fn append_inner_instruction(
+        inner_instructions: Option<Vec<UiInnerInstructions>>,
+        log_messages: Option<Vec<String>>,
+        outer_programs: &[String],
+        accounts: Vec<String>,
+        account_roles: &[char],
+        tx_signature: String,
+        slot: u64,
+        block_time: Option<u64>,
+        tx_status: TxStatus,
+        tx_version: Option<u8>,
+        num_signatures: u8,
+        instructions_set: &mut BTreeSet<Instruction>,
+        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
+        nft_events: &mut Vec<NftEvent>,
+        commission_changes: &mut Vec<CommissionChange>,
+        claim_events: &mut Vec<ClaimEvent>,
+        entangler_swaps: &mut Vec<EntanglerSwap>,
+        stake_account_events: &mut Vec<StakeAccountEvent>,
+        sol_transfers: &mut Vec<SolTransfer>,
+        token_mints: &HashMap<String, String>,
+        options: &ParseOptions,
+    ) -> Result<(), ParseInstructionError> {
+        if let Some(inner_instructions) = inner_instructions {
+            // Older payloads have no stackHeight, the logs are the only source of it then
+            let log_stack_heights = log_messages.and_then(|log_messages| {
+                Self::stack_heights_from_logs(
+                    &log_messages,
+                    outer_programs,
+                    &inner_instructions,
+                    &accounts,
+                )
+            });
+
+            for (inner_instructions_set, instruction) in inner_instructions.iter().enumerate() {
+                let index = instruction.index;
+                let outer_program = outer_programs.get(index as usize).cloned();
+                // Programs of the current CPI chain, the parent is the last one below the height
+                let mut invocation_stack: Vec<String> = outer_program.iter().cloned().collect();
+
+                for (instruction_idx, instruction) in instruction.instructions.iter().enumerate() {
+                    if let UiInstruction::Compiled(instruction) = instruction {
+                        let inner_program_address =
+                            accounts.get(instruction.program_id_index as usize);
+                        if inner_program_address.is_none() {
+                            return Err(ParseInstructionError::ParseError(
+                                "Failed to get inner_program_address".to_string(),
+                            ));
+                        }
+                        let inner_program_address = inner_program_address.unwrap();
+
+                        if instruction.accounts.len() > ACCOUNTS_ARRAY_SIZE {
+                            return Err(ParseInstructionError::InvalidLength {
+                                site: "inner_instruction".to_string(),
+                                len: instruction.accounts.len(),
+                                expected_len: ACCOUNTS_ARRAY_SIZE,
+                            });
+                        }
+
+                        let mut inner_instruction_accounts =
+                            Vec::with_capacity(instruction.accounts.len());
+                        let inner_instruction_account_roles =
+                            Self::instruction_account_roles(&instruction.accounts, account_roles);
+
+                        for account_idx in instruction.accounts.iter() {
+                            let inner_instruction_account = accounts.get(*account_idx as usize);
+                            if let Some(inner_instruction_account) = inner_instruction_account {
+                                inner_instruction_accounts
+                                    .push(inner_instruction_account.to_owned());
+                            } else {
+                                return Err(ParseInstructionError::InvalidIndex {
+                                    site: "inner_instruction".to_string(),
+                                    index: *account_idx as usize,
+                                    max_len: accounts.len(),
+                                });
+                            };
+                        }
+
+                        let mut decoded_instruction = Self::decode_instruction(
+                            inner_program_address,
+                            &instruction.data,
+                            options,
+                        )?;
+
+                        let accounts = inner_instruction_accounts;
+
+                        let stack_height = instruction
+                            .stack_height
+                            .and_then(|stack_height| u8::try_from(stack_height).ok())
+                            .or_else(|| {
+                                log_stack_heights.as_ref().and_then(|stack_heights| {
+                                    stack_heights[inner_instructions_set]
+                                        .get(instruction_idx)
+                                        .copied()
+                                })
+                            });
+
+                        let parent_program = match stack_height {
+                            Some(stack_height) if stack_height > 1 => {
+                                invocation_stack.truncate(stack_height as usize - 1);
+                                let parent_program = invocation_stack.last().cloned();
+                                invocation_stack.push(inner_program_address.clone());
+                                parent_program
+                            }
+                            _ => outer_program.clone(),
+                        };
+
+                        Self::append_program_rows(
+                            decoded_instruction.program_instruction.as_ref(),
+                            &accounts,
+                            &tx_signature,
+                            slot,
+                            instruction_idx as u8,
+                            Some(inner_instructions_set as u8),
+                            tx_status,
+                            nft_events,
+                            commission_changes,
+                            claim_events,
+                            entangler_swaps,
+                            stake_account_events,
+                            sol_transfers,
+                            token_mints,
+                        );
+
+                        let instr = Instruction {
+                            program: inner_program_address.clone(),
+                            tx_signature: tx_signature.clone(),
+                            slot,
+                            block_time,
+                            tx_status,
+                            tx_version,
+                            num_signatures,
+                            failed_instruction_idx: None,
+                            instruction_idx: instruction_idx as u8,
+                            inner_instructions_set: Some(inner_instructions_set as u8),
+                            transaction_instruction_idx: Some(index),
+                            parent_program,
+                            stack_height,
+                            accounts,
+                            account_roles: inner_instruction_account_roles,
+                            instruction_name: decoded_instruction.name,
+                            data: decoded_instruction.data,
+                        };
+
+                        instructions_set.insert(instr);
+
+                        for instruction_argument in decoded_instruction.arguments.iter_mut() {
+                            instruction_argument.tx_signature = tx_signature.clone();
+                            instruction_argument.instruction_idx = instruction_idx as u8;
+                            instruction_argument.inner_instructions_set =
+                                Some(inner_instructions_set as u8);
+                            instruction_argument.program = inner_program_address.clone();
+                        }
+
+                        parsed_instruction_arguments.append(&mut decoded_instruction.arguments);
+                    } else {
+                        return Err(ParseInstructionError::Unsupported(
+                            "UiInstruction::Compiled in Inner instruction".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn append_outer_instruction(
+        instructions: Vec<UiCompiledInstruction>,
+        accounts: Vec<String>,
+        account_roles: &[char],
+        tx_signature: String,
+        slot: u64,
+        block_time: Option<u64>,
+        tx_status: TxStatus,
+        tx_version: Option<u8>,
+        num_signatures: u8,
+        instructions_set: &mut BTreeSet<Instruction>,
+        parsed_instruction_arguments: &mut Vec<InstructionArgument>,
+        nft_events: &mut Vec<NftEvent>,
+        commission_changes: &mut Vec<CommissionChange>,
+        claim_events: &mut Vec<ClaimEvent>,
+        entangler_swaps: &mut Vec<EntanglerSwap>,
+        stake_account_events: &mut Vec<StakeAccountEvent>,
+        sol_transfers: &mut Vec<SolTransfer>,
+        token_mints: &HashMap<String, String>,
+        options: &ParseOptions,
+    ) -> Result<(), ParseInstructionError> {
+        for (instruction_idx, instruction) in instructions.iter().enumerate() {
+            let program_address = accounts.get(instruction.program_id_index as usize);
+
+            if program_address.is_none() {
+                return Err(ParseInstructionError::ParseError(
+                    "Failed to get program_address".to_string(),
+                ));
+            }
+            let program_address = program_address.unwrap();
+
+            if instruction.accounts.len() > ACCOUNTS_ARRAY_SIZE {
+                return Err(ParseInstructionError::InvalidLength {
+                    site: "instruction".to_string(),
+                    len: instruction.accounts.len(),
+                    expected_len: ACCOUNTS_ARRAY_SIZE,
+                });
+            }
+
+            let mut instruction_accounts = Vec::with_capacity(instruction.accounts.len());
+            let instruction_account_roles =
+                Self::instruction_account_roles(&instruction.accounts, account_roles);
+
+            for account_idx in instruction.accounts.iter() {
+                let instruction_account = accounts.get(*account_idx as usize);
+                if let Some(instruction_account) = instruction_account {
+                    instruction_accounts.push(instruction_account.to_owned());
+                } else {
+                    return Err(ParseInstructionError::InvalidIndex {
+                        site: "instruction".to_string(),
+                        index: *account_idx as usize,
+                        max_len: accounts.len(),
+                    });
+                };
+            }
+
+            // if program_address == "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk" {
+            //     log::error!("DATA: {:?}, tx: {}", instruction.data, tx_signature)
+            // }
+            let mut decoded_instruction =
+                Self::decode_instruction(program_address, &instruction.data, options)?;
+
+            let accounts = instruction_accounts;
+
+            Self::append_program_rows(
+                decoded_instruction.program_instruction.as_ref(),
+                &accounts,
+                &tx_signature,
+                slot,
+                instruction_idx as u8,
+                None,
+                tx_status,
+                nft_events,
+                commission_changes,
+                claim_events,
+                entangler_swaps,
+                stake_account_events,
+                sol_transfers,
+                token_mints,
+            );
+
+            let instr = Instruction {
+                program: program_address.clone(),
+                tx_signature: tx_signature.clone(),
+                slot,
+                block_time,
+                tx_status,
+                tx_version,
+                num_signatures,
+                failed_instruction_idx: None,
+                instruction_idx: instruction_idx as u8,
+                inner_instructions_set: None,
+                transaction_instruction_idx: None,
+                parent_program: None,
+                stack_height: None,
+                accounts,
+                account_roles: instruction_account_roles,
+                instruction_name: decoded_instruction.name,
+                data: decoded_instruction.data,
+            };
+
+            instructions_set.insert(instr);
+
+            for instruction_argument in decoded_instruction.arguments.iter_mut() {
+                instruction_argument.tx_signature = tx_signature.clone();
+                instruction_argument.instruction_idx = instruction_idx as u8;
+                instruction_argument.inner_instructions_set = None;
+                instruction_argument.program = program_address.clone();
+            }
+
+            parsed_instruction_arguments.append(&mut decoded_instruction.arguments);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::ParseInstructionError;
+    use crate::{
+        parse_transaction, program_name, ParseOptions, TransactionParser, TransactionParsingResult,
+        ACCOUNTS_ARRAY_SIZE,
+    };
+    use rust_base58::ToBase58;
+    use solana_transaction_status::option_serializer::OptionSerializer;
+    use solana_transaction_status::{
+        EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiLoadedAddresses,
+    };
+
+    /// ClaimPack of the NFT packs program, which mints an edition through the token metadata
+    /// program. The payload has no stackHeight, so the heights come from the logs
+    fn claim_pack_transaction() -> EncodedConfirmedTransactionWithStatusMeta {
+        let system = "Program 11111111111111111111111111111111";
+        let token = "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+        let metadata = "Program metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+        let packs = "Program packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu";
+
+        let mut log_messages = vec![
+            format!("{} invoke [1]", packs),
+            format!("{} invoke [2]", metadata),
+        ];
+        for _ in 0..6 {
+            log_messages.push(format!("{} invoke [3]", system));
+            log_messages.push(format!("{} success", system));
+        }
+        for _ in 0..2 {
+            log_messages.push(format!("{} invoke [3]", token));
+            log_messages.push(format!("{} success", token));
+        }
+        log_messages.push(format!("{} success", metadata));
+        log_messages.push(format!("{} success", packs));
+
+        let inner_instruction = |program_id_index: u8, accounts: &[u8], data: &str| {
+            serde_json::json!({
+                "programIdIndex": program_id_index,
+                "accounts": accounts,
+                "data": data
+            })
+        };
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [
+                    "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                ],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 9
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "JB4vdpYFSG4xCqeZbMC8r96H81nB7oi2xBdMmVBGWWyy",
+                        "Aurdw9mjPnBMQCiczdN4H7qfSoHF8K915GfSi364SZgA",
+                        "DV2rLHZsXZLTJzfQ3iUQoKxqX8phM8hR4qjgxtqRV81W",
+                        "6DnkBtW5UmsWRFCZBkihS1yZzUWWKpUZiHUwMPDx6c9C",
+                        "Eozy2f2NoxvuRJcFdif8ma3rAuWvHJte937NEWH3Fhwr",
+                        "CG18v8fAZusKkMzZp7kLbCpsYrDkLVDmqhbXu5v7hHwZ",
+                        "FwGMDsTRbf6fNTb9YSN6HorTPEPhcLCG7H9zFEicm61u",
+                        "8mkxhojbDFkzofuPjesqaakcGZvfA72GaSVEXXFsEemq",
+                        "BNFSDxJuDPM6EYKKZGs5pcR9HYu8t2UjSe18ZUTaBkgM",
+                        "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs",
+                        "SysvarRent111111111111111111111111111111111",
+                        "11111111111111111111111111111111",
+                        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                        "H6FEUafrGDeQsGnCerFomtzG3B3TctUaue8yM7heLi8W",
+                        "4wawb6MxhWmANe4nDYB7Hy5tdFY3A5s1MyNSJHShnjz",
+                        "rndshKFf48HhGaPbaCd3WQYtgCNKzRgVQ3U2we4Cvf9",
+                        "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s",
+                        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+                        "packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [
+                        inner_instruction(
+                            20,
+                            &[15, 3, 0, 16, 4, 5, 6, 7, 8, 1, 0, 9, 10, 11, 12, 17, 18, 14, 13],
+                            "guFfuH"
+                        )
+                    ]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [
+                        inner_instruction(
+                            18,
+                            &[6, 7, 8, 1, 11, 0, 0, 16, 5, 0, 9, 14, 13, 12],
+                            "9D2mNcMSmYR5"
+                        ),
+                        inner_instruction(13, &[0, 6], "3Bxs4EMbRQoDyoj5"),
+                        inner_instruction(13, &[6], "9krTDUMpjBo4wxLP"),
+                        inner_instruction(13, &[6], "SYXsBkG6yKW2wWDcW8EDHR6D3P82bKxJGPpM65DD8nHqBfMP"),
+                        inner_instruction(13, &[0, 7], "3Bxs48v9NdVhakdd"),
+                        inner_instruction(13, &[7], "9krTDgje7Fnho7ps"),
+                        inner_instruction(13, &[7], "SYXsBkG6yKW2wWDcW8EDHR6D3P82bKxJGPpM65DD8nHqBfMP"),
+                        inner_instruction(14, &[1, 0, 0], "biy3SZviff8JK2ske48JhXBfLVA8SeCDLcf1rQfY8uouBdD"),
+                        inner_instruction(14, &[1, 0, 0], "bkH6Deonc6hYPobmkX4Tcy5Bqpg6sNvvcgrptbusxEJ72dq")
+                    ]
+                }],
+                "logMessages": log_messages,
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        }
+    }
+
+    #[test]
+    fn accounts_take_only_the_used_slots() {
+        let instructions = parse_transaction(claim_pack_transaction())
+            .unwrap()
+            .instructions;
+
+        let lens: Vec<_> = instructions
+            .iter()
+            .map(|instruction| instruction.accounts.len())
+            .collect();
+
+        assert_eq!(lens, [19, 14, 2, 1, 1, 2, 1, 1, 3, 3]);
+        assert!(instructions
+            .iter()
+            .all(|instruction| instruction.accounts.capacity() == instruction.accounts.len()));
+
+        // Past its accounts an instruction has no account, as its padded `account_N` columns
+        assert_eq!(
+            instructions[2].account(1).as_deref(),
+            Some("Eozy2f2NoxvuRJcFdif8ma3rAuWvHJte937NEWH3Fhwr")
+        );
+        assert_eq!(instructions[2].account(2), None);
+        assert_eq!(instructions[2].account(ACCOUNTS_ARRAY_SIZE - 1), None);
+    }
+
+    #[test]
+    fn parent_programs_of_claim_pack() {
+        let instructions = parse_transaction(claim_pack_transaction())
+            .unwrap()
+            .instructions;
+
+        let invocations: Vec<_> = instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    program_name(&instruction.program),
+                    instruction.parent_program.as_deref().map(program_name),
+                    instruction.stack_height,
+                )
+            })
+            .collect();
+
+        let mut expected = vec![
+            ("nft_packs", None, None),
+            ("token_metadata", Some("nft_packs"), Some(2)),
+        ];
+        expected.extend([("system", Some("token_metadata"), Some(3)); 6]);
+        expected.extend([("other", Some("token_metadata"), Some(3)); 2]);
+
+        assert_eq!(invocations, expected);
+    }
+
+    #[test]
+    fn parent_program_without_stack_height() {
+        let mut transaction = claim_pack_transaction();
+        if let Some(meta) = transaction.transaction.meta.as_mut() {
+            meta.log_messages = None.into();
+        }
+
+        let instructions = parse_transaction(transaction).unwrap().instructions;
+
+        // Only the top-level instruction of the set is known to be the invoker
+        assert!(instructions[1..].iter().all(|instruction| {
+            instruction.stack_height.is_none()
+                && instruction.parent_program.as_deref()
+                    == Some("packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu")
+        }));
+    }
+
+    /// ClaimPack whose Token Metadata inner instruction has the given data
+    fn claim_pack_with_metadata_data(data: &[u8]) -> EncodedConfirmedTransactionWithStatusMeta {
+        let mut transaction = claim_pack_transaction();
+
+        if let Some(meta) = transaction.transaction.meta.as_mut() {
+            if let OptionSerializer::Some(inner_instructions) = &mut meta.inner_instructions {
+                if let UiInstruction::Compiled(instruction) =
+                    &mut inner_instructions[0].instructions[0]
+                {
+                    instruction.data = data.to_base58();
+                }
+            }
+        }
+
+        transaction
+    }
+
+    #[test]
+    fn unknown_variant_degrades_to_unknown_instruction() {
+        let TransactionParsingResult {
+            instructions,
+            instruction_arguments,
+            ..
+        } = parse_transaction(claim_pack_with_metadata_data(&[255, 1, 2])).unwrap();
+
+        assert_eq!(instructions.len(), 10);
+        assert_eq!(instructions[0].instruction_name, "ClaimPack");
+        assert_eq!(instructions[1].instruction_name, "Unknown");
+        assert_eq!(instructions[1].data, [255, 1, 2].to_base58());
+
+        let metadata_arguments: Vec<_> = instruction_arguments
+            .iter()
+            .filter(|argument| program_name(&argument.program) == "token_metadata")
+            .collect();
+
+        assert_eq!(metadata_arguments.len(), 1);
+        assert_eq!(metadata_arguments[0].arg_path, "/discriminant");
+        assert_eq!(metadata_arguments[0].unsigned_value, Some(255));
+        assert_eq!(metadata_arguments[0].inner_instructions_set, Some(0));
+        assert_eq!(metadata_arguments[0].instruction_idx, 0);
+    }
+
+    #[test]
+    fn account_roles_of_static_and_loaded_accounts() {
+        let mut transaction = claim_pack_transaction();
+
+        if let Some(meta) = transaction.transaction.meta.as_mut() {
+            meta.loaded_addresses = OptionSerializer::Some(UiLoadedAddresses {
+                writable: vec!["Eozy2f2NoxvuRJcFdif8ma3rAuWvHJte937NEWH3Fhwr".to_string()],
+                readonly: vec!["CG18v8fAZusKkMzZp7kLbCpsYrDkLVDmqhbXu5v7hHwZ".to_string()],
+            });
+
+            if let OptionSerializer::Some(inner_instructions) = &mut meta.inner_instructions {
+                if let UiInstruction::Compiled(instruction) =
+                    &mut inner_instructions[0].instructions[1]
+                {
+                    instruction.accounts = vec![0, 21, 22];
+                }
+            }
+        }
+
+        let instructions = parse_transaction(transaction).unwrap().instructions;
+
+        // The fee payer signs, 9 of the 21 static accounts are readonly
+        assert_eq!(&instructions[0].account_roles[..3], "rwW");
+        assert_eq!(instructions[0].account_roles.len(), 19);
+        // The addresses loaded from the lookup tables don't sign
+        assert_eq!(instructions[2].account_roles, "Wwr");
+    }
+
+    #[test]
+    fn unknown_variant_is_a_decode_error() {
+        let result = TransactionParser::parse_instruction(
+            "11111111111111111111111111111111",
+            &[255, 0, 0, 0],
+            &ParseOptions::default(),
+        );
+
+        match result {
+            Err(
+                err @ ParseInstructionError::InstructionDecodeError {
+                    discriminant: Some(255),
+                    ..
+                },
+            ) => assert!(err.is_decode_error()),
+            _ => panic!("Value is not \"ParseInstructionError::InstructionDecodeError\""),
+        }
+    }
+
+    #[test]
+    fn truncated_sighash_is_a_decode_error() {
+        let result = TransactionParser::parse_instruction(
+            "cndy3Z4yapfJBmL3ShUp5exZKqR3z33thTzeNMm2gRZ",
+            &[1, 2, 3],
+            &ParseOptions::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParseInstructionError::InstructionDecodeError {
+                discriminant: Some(1),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_base58_fails_the_transaction() {
+        let mut transaction = claim_pack_transaction();
+
+        if let Some(meta) = transaction.transaction.meta.as_mut() {
+            if let OptionSerializer::Some(inner_instructions) = &mut meta.inner_instructions {
+                if let UiInstruction::Compiled(instruction) =
+                    &mut inner_instructions[0].instructions[0]
+                {
+                    instruction.data = "0OIl".to_string();
+                }
+            }
+        }
+
+        assert!(matches!(
+            parse_transaction(transaction),
+            Err(ParseInstructionError::DeserializeFromBase58Error)
+        ));
+    }
+}
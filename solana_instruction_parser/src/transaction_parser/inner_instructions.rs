@@ -0,0 +1,236 @@
+use log::warn;
+use solana_transaction_status::UiInnerInstructions;
+
+use crate::errors::ParseInstructionError;
+
+use super::TransactionParser;
+
+/// Number of the inner sets whose `index` is already taken by a preceding set of the
+/// transaction, well-formed RPC responses have none
+pub fn duplicate_inner_instruction_sets(inner_instructions: &[UiInnerInstructions]) -> usize {
+    inner_instructions
+        .iter()
+        .enumerate()
+        .filter(|(position, set)| {
+            inner_instructions[..*position]
+                .iter()
+                .any(|preceding| preceding.index == set.index)
+        })
+        .count()
+}
+
+impl TransactionParser {
+    /// One inner set per top-level instruction, in the order of the top-level instructions.
+    /// Malformed RPC responses may carry several sets with the same `index`, their instructions
+    /// are appended to the first of them in order, so the remaining sets keep distinct
+    /// `inner_instructions_set` numbers instead of the later instructions colliding with the
+    /// earlier ones. The sets are numbered by their position, and the Geyser payloads don't
+    /// keep them sorted the way RPC does, so they are sorted by `index` for a transaction to get
+    /// the same numbers from both. The sets of missing top-level instructions fail the
+    /// transaction with `InvalidInnerIndex`
+    pub(super) fn merge_inner_instructions(
+        tx_signature: &str,
+        inner_instructions: Vec<UiInnerInstructions>,
+        outer_instructions: usize,
+    ) -> Result<Vec<UiInnerInstructions>, ParseInstructionError> {
+        let mut merged: Vec<UiInnerInstructions> = Vec::with_capacity(inner_instructions.len());
+        let mut duplicates = 0;
+
+        for set in inner_instructions {
+            if set.index as usize >= outer_instructions {
+                return Err(ParseInstructionError::InvalidInnerIndex {
+                    site: "inner_instructions".to_string(),
+                    index: set.index as usize,
+                    max_len: outer_instructions,
+                });
+            }
+
+            match merged.iter_mut().find(|merged| merged.index == set.index) {
+                Some(merged) => {
+                    duplicates += 1;
+                    merged.instructions.extend(set.instructions);
+                }
+                None => merged.push(set),
+            }
+        }
+
+        if duplicates > 0 {
+            warn!(
+                actor = "transaction_parser", tx_signature = tx_signature;
+                "Merged {} inner instruction sets with duplicate indices", duplicates
+            );
+        }
+
+        merged.sort_by_key(|set| set.index);
+
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    const SYSTEM_TRANSFER: &str = "3Bxs4EMbRQoDyoj5";
+
+    /// Two top-level instructions of an unknown program and the given inner sets of system
+    /// transfers as (index, number of transfers)
+    fn transaction_with_inner_sets(
+        sets: &[(u8, usize)],
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let inner_instructions: Vec<_> = sets
+            .iter()
+            .map(|(index, transfers)| {
+                serde_json::json!({
+                    "index": index,
+                    "instructions": vec![
+                        serde_json::json!({
+                            "programIdIndex": 2,
+                            "accounts": [0, 1],
+                            "data": SYSTEM_TRANSFER
+                        });
+                        *transfers
+                    ]
+                })
+            })
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": [
+                    "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU"
+                ],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 2
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "11111111111111111111111111111111",
+                        "H6FEUafrGDeQsGnCerFomtzG3B3TctUaue8yM7heLi8W"
+                    ],
+                    "recentBlockhash": "2JpSV2YKxT9dhMtHCcEVPFQi4WMVNDSL8QW9Xqb4Jrd4",
+                    "instructions": [
+                        { "programIdIndex": 3, "accounts": [0, 1, 2], "data": "guFfuH" },
+                        { "programIdIndex": 3, "accounts": [0, 1, 2], "data": "guFfuH" }
+                    ]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": inner_instructions,
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 117946133,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1643213404),
+        }
+    }
+
+    #[test]
+    fn duplicate_index_sets_are_merged_in_order() {
+        let instructions =
+            parse_transaction(transaction_with_inner_sets(&[(0, 2), (1, 1), (0, 1)]))
+                .unwrap()
+                .instructions;
+
+        let inner: Vec<_> = instructions
+            .iter()
+            .filter(|instruction| instruction.transaction_instruction_idx.is_some())
+            .map(|instruction| {
+                (
+                    instruction.transaction_instruction_idx,
+                    instruction.inner_instructions_set,
+                    instruction.instruction_idx,
+                )
+            })
+            .collect();
+
+        // The transfer of the third set isn't lost under the raw index of the first one
+        assert_eq!(
+            inner,
+            [
+                (Some(0), Some(0), 0),
+                (Some(0), Some(0), 1),
+                (Some(0), Some(0), 2),
+                (Some(1), Some(1), 0),
+            ]
+        );
+        assert_eq!(instructions.len(), 6);
+    }
+
+    #[test]
+    fn inner_sets_are_numbered_in_the_order_of_the_top_level_instructions() {
+        let positions = |sets: &[(u8, usize)]| -> Vec<_> {
+            let instructions = parse_transaction(transaction_with_inner_sets(sets))
+                .unwrap()
+                .instructions;
+
+            instructions
+                .iter()
+                .map(|instruction| {
+                    (
+                        instruction.transaction_instruction_idx,
+                        instruction.inner_instructions_set,
+                        instruction.instruction_idx,
+                    )
+                })
+                .collect()
+        };
+
+        let expected = [
+            (None, None, 0),
+            (Some(0), Some(0), 0),
+            (Some(0), Some(0), 1),
+            (None, None, 1),
+            (Some(1), Some(1), 0),
+        ];
+        assert_eq!(positions(&[(0, 2), (1, 1)]), expected);
+        assert_eq!(positions(&[(1, 1), (0, 2)]), expected);
+    }
+
+    #[test]
+    fn duplicate_inner_instruction_sets_are_counted() {
+        let transaction = transaction_with_inner_sets(&[(0, 2), (1, 1), (0, 1), (0, 1)]);
+        let inner_instructions: Option<Vec<UiInnerInstructions>> = transaction
+            .transaction
+            .meta
+            .unwrap()
+            .inner_instructions
+            .into();
+
+        assert_eq!(
+            duplicate_inner_instruction_sets(&inner_instructions.unwrap()),
+            2
+        );
+    }
+
+    #[test]
+    fn out_of_range_index_is_invalid_inner_index() {
+        let err = parse_transaction(transaction_with_inner_sets(&[(0, 1), (2, 1)])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ParseInstructionError::InvalidInnerIndex {
+                ref site,
+                index: 2,
+                max_len: 2,
+            } if site == "inner_instructions"
+        ));
+        assert_eq!(err.kind(), "InvalidInnerIndex");
+    }
+}
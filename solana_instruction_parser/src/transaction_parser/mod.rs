@@ -0,0 +1,67 @@
+use crate::instructions::{
+    gumdrop_instruction::GumdropInstruction, stake_instruction::StakeInstruction,
+    system_instruction::SystemInstruction, token_entangler_instruction::TokenEntanglerInstruction,
+    token_metadata_instruction::MetadataInstruction, vote_instruction::VoteInstruction,
+};
+use crate::{
+    AnchorEvent, Balance, BidEvent, CandyMint, ClaimEvent, CommissionChange, EntanglerSwap,
+    Instruction, InstructionArgument, NftEvent, PackEvent, SaleEvent, SolTransfer,
+    StakeAccountEvent, TokenTransfer,
+};
+
+mod append_instructions;
+mod inner_instructions;
+mod parse_anchor_events;
+mod parse_attributed_balances;
+mod parse_bid_events;
+mod parse_candy_mints;
+mod parse_claim_events;
+mod parse_commission_changes;
+mod parse_entangler_swaps;
+mod parse_instructions;
+mod parse_nft_events;
+mod parse_pack_events;
+mod parse_sale_events;
+mod parse_sol_transfers;
+mod parse_stake_account_events;
+mod parse_token_transfers;
+mod stack_heights;
+
+pub use inner_instructions::duplicate_inner_instruction_sets;
+
+pub struct TransactionParser;
+
+/// Rows of a parsed transaction, each of the tables they are stored in
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+pub struct TransactionParsingResult {
+    pub instructions: Vec<Instruction>,
+    pub balances: Vec<Balance>,
+    pub instruction_arguments: Vec<InstructionArgument>,
+    pub token_transfers: Vec<TokenTransfer>,
+    pub nft_events: Vec<NftEvent>,
+    pub commission_changes: Vec<CommissionChange>,
+    pub claim_events: Vec<ClaimEvent>,
+    pub entangler_swaps: Vec<EntanglerSwap>,
+    pub anchor_events: Vec<AnchorEvent>,
+    pub sale_events: Vec<SaleEvent>,
+    pub pack_events: Vec<PackEvent>,
+    pub candy_mints: Vec<CandyMint>,
+    pub stake_account_events: Vec<StakeAccountEvent>,
+    pub bid_events: Vec<BidEvent>,
+    pub sol_transfers: Vec<SolTransfer>,
+}
+
+/// Decoded instruction of the programs whose dedicated rows are built from it: NFT events from
+/// the Token Metadata instructions, commission changes from the vote ones, claim events from the
+/// Gumdrop ones, swaps from the Token Entangler ones, stake account events from the stake ones
+/// and SOL transfers from the system ones
+#[derive(Clone)]
+pub enum ProgramInstruction {
+    Metadata(Box<MetadataInstruction>),
+    Vote(VoteInstruction),
+    Gumdrop(Box<GumdropInstruction>),
+    TokenEntangler(TokenEntanglerInstruction),
+    Stake(StakeInstruction),
+    System(SystemInstruction),
+}
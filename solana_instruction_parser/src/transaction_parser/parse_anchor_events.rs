@@ -0,0 +1,345 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_transaction_status::UiTransactionReturnData;
+
+use crate::AnchorEvent;
+
+use super::TransactionParser;
+
+/// Prefix of the lines `sol_log_data` logs, anchor's `emit!` logs an event with it
+const PROGRAM_DATA: &str = "Program data: ";
+/// Length of the discriminator anchor prefixes the serialized event with
+const DISCRIMINATOR_LEN: usize = 8;
+
+impl TransactionParser {
+    /// Events logged by `Program data:` lines of a successful transaction followed by its return
+    /// data. A line is attributed to the program on top of the invocations opened by the
+    /// `Program <id> invoke [<height>]` lines and not yet closed by the `success` or `failed`
+    /// ones; the lines logged outside of an invocation or after the logs were truncated are
+    /// skipped, as are the payloads which are not base64 or are shorter than a discriminator
+    pub(super) fn parse_anchor_events(
+        tx_signature: &str,
+        slot: u64,
+        log_messages: &[String],
+        return_data: Option<&UiTransactionReturnData>,
+    ) -> Vec<AnchorEvent> {
+        let mut anchor_events = Vec::new();
+        // Programs of the open invocations with their heights, the last one is executing
+        let mut invocations: Vec<(&str, u8)> = Vec::new();
+        let mut instruction_idx: Option<u8> = None;
+
+        for line in log_messages {
+            if line == "Log truncated" {
+                break;
+            }
+
+            if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+                let Some((program, stack_height)) = invocations.last() else {
+                    continue;
+                };
+                let Some(discriminator) = discriminator(data) else {
+                    continue;
+                };
+
+                anchor_events.push(AnchorEvent {
+                    tx_signature: tx_signature.to_string(),
+                    slot,
+                    program: program.to_string(),
+                    source: "log".to_string(),
+                    event_idx: anchor_events.len() as u16,
+                    instruction_idx,
+                    stack_height: Some(*stack_height),
+                    discriminator,
+                    data: data.to_string(),
+                });
+                continue;
+            }
+
+            let Some(line) = line.strip_prefix("Program ") else {
+                continue;
+            };
+            if let Some((program, height)) = line
+                .strip_suffix(']')
+                .and_then(|line| line.split_once(" invoke ["))
+            {
+                let Ok(height) = height.parse::<u8>() else {
+                    continue;
+                };
+                if height == 1 {
+                    instruction_idx = Some(instruction_idx.map_or(0, |idx| idx.wrapping_add(1)));
+                }
+                invocations.push((program, height));
+            } else if is_closing(line) {
+                invocations.pop();
+            }
+        }
+
+        if let Some(return_data) = return_data.filter(|return_data| !return_data.data.0.is_empty())
+        {
+            anchor_events.push(AnchorEvent {
+                tx_signature: tx_signature.to_string(),
+                slot,
+                program: return_data.program_id.clone(),
+                source: "return_data".to_string(),
+                event_idx: anchor_events.len() as u16,
+                instruction_idx: None,
+                stack_height: None,
+                discriminator: String::new(),
+                data: return_data.data.0.clone(),
+            });
+        }
+
+        anchor_events
+    }
+}
+
+/// `<id> success` or `<id> failed: <error>`, the line of the program logging the end of its
+/// invocation. Program ids are base58, unlike the `log: <message>` lines a program logs itself
+fn is_closing(line: &str) -> bool {
+    let program = line
+        .strip_suffix(" success")
+        .or_else(|| line.split_once(" failed: ").map(|(program, _)| program));
+
+    program.map_or(false, |program| {
+        !program.is_empty() && program.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Hex of the first bytes of the payload, which is logged as space separated base64 chunks of
+/// which anchor writes one
+fn discriminator(data: &str) -> Option<String> {
+    let chunk = data.split(' ').next()?;
+    let bytes = STANDARD.decode(chunk).ok()?;
+
+    bytes.get(..DISCRIMINATOR_LEN).map(|discriminator| {
+        discriminator
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use solana_program::pubkey::Pubkey;
+    use solana_transaction_status::{
+        EncodedConfirmedTransactionWithStatusMeta, UiReturnDataEncoding,
+    };
+
+    /// Base64 of the bytes 1..=8 followed by 42
+    const EVENT: &str = "AQIDBAUGBwgq";
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn anchor_events(lines: &[&str]) -> Vec<AnchorEvent> {
+        TransactionParser::parse_anchor_events("signature", 100, &logs(lines), None)
+    }
+
+    /// A transaction of one instruction of `program` which logs an event and returns data
+    fn event_transaction(
+        program: &str,
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [Pubkey::new_unique().to_string(), program],
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": "" }]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [
+                    format!("Program {} invoke [1]", program),
+                    format!("Program data: {}", EVENT),
+                    format!("Program {} success", program),
+                ],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": [],
+                "returnData": { "programId": program, "data": ["AQID", "base64"] }
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    #[test]
+    fn event_of_outer_instruction() {
+        let events = anchor_events(&[
+            "Program hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk invoke [1]",
+            "Program log: Instruction: PrintPurchaseReceipt",
+            "Program data: AQIDBAUGBwgq",
+            "Program hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk consumed 9000 of 200000 compute units",
+            "Program hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk success",
+        ]);
+
+        assert_eq!(
+            events,
+            vec![AnchorEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                program: "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk".to_string(),
+                source: "log".to_string(),
+                event_idx: 0,
+                instruction_idx: Some(0),
+                stack_height: Some(1),
+                discriminator: "0102030405060708".to_string(),
+                data: EVENT.to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn events_are_attributed_to_their_invocation() {
+        let events = anchor_events(&[
+            "Program outer invoke [1]",
+            "Program outer success",
+            "Program router invoke [1]",
+            "Program data: AQIDBAUGBwgq",
+            "Program cpi invoke [2]",
+            "Program nested invoke [3]",
+            "Program data: AQIDBAUGBwgq",
+            "Program nested success",
+            "Program data: AQIDBAUGBwgq AQID",
+            "Program cpi success",
+            "Program data: AQIDBAUGBwgq",
+            "Program router success",
+        ]);
+
+        let context: Vec<(&str, u16, Option<u8>, Option<u8>)> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.program.as_str(),
+                    event.event_idx,
+                    event.instruction_idx,
+                    event.stack_height,
+                )
+            })
+            .collect();
+        assert_eq!(
+            context,
+            vec![
+                ("router", 0, Some(1), Some(1)),
+                ("nested", 1, Some(1), Some(3)),
+                ("cpi", 2, Some(1), Some(2)),
+                ("router", 3, Some(1), Some(1)),
+            ]
+        );
+        assert_eq!(events[2].data, "AQIDBAUGBwgq AQID");
+        assert_eq!(events[2].discriminator, "0102030405060708");
+    }
+
+    #[test]
+    fn failed_invocation_is_closed() {
+        let events = anchor_events(&[
+            "Program outer invoke [1]",
+            "Program log: Transfer success",
+            "Program cpi invoke [2]",
+            "Program cpi failed: custom program error: 0x1",
+            "Program data: AQIDBAUGBwgq",
+            "Program outer success",
+        ]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].program, "outer");
+        assert_eq!(events[0].stack_height, Some(1));
+    }
+
+    #[test]
+    fn undecodable_and_unattributed_lines_are_skipped() {
+        let events = anchor_events(&[
+            "Program data: AQIDBAUGBwgq",
+            "Program outer invoke [1]",
+            // The bytes 1..=4, shorter than a discriminator
+            "Program data: AQIDBA==",
+            "Program data: not base64!",
+            "Log truncated",
+            "Program data: AQIDBAUGBwgq",
+        ]);
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn return_data_follows_the_logged_events() {
+        let return_data = UiTransactionReturnData {
+            program_id: "program".to_string(),
+            data: ("AQID".to_string(), UiReturnDataEncoding::Base64),
+        };
+
+        let events = TransactionParser::parse_anchor_events(
+            "signature",
+            100,
+            &logs(&[
+                "Program program invoke [1]",
+                "Program data: AQIDBAUGBwgq",
+                "Program return: program AQID",
+                "Program program success",
+            ]),
+            Some(&return_data),
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].source, "return_data");
+        assert_eq!(events[1].program, "program");
+        assert_eq!(events[1].event_idx, 1);
+        assert_eq!(events[1].instruction_idx, None);
+        assert_eq!(events[1].discriminator, "");
+        assert_eq!(events[1].data, "AQID");
+    }
+
+    #[test]
+    fn events_of_parsed_transaction() {
+        let program = Pubkey::new_unique().to_string();
+
+        let anchor_events = parse_transaction(event_transaction(&program, serde_json::Value::Null))
+            .unwrap()
+            .anchor_events;
+
+        let sources: Vec<(&str, &str)> = anchor_events
+            .iter()
+            .map(|event| (event.program.as_str(), event.source.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![(program.as_str(), "log"), (program.as_str(), "return_data")]
+        );
+    }
+
+    #[test]
+    fn failed_transaction_has_no_events() {
+        let anchor_events = parse_transaction(event_transaction(
+            &Pubkey::new_unique().to_string(),
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .anchor_events;
+
+        assert!(anchor_events.is_empty());
+    }
+}
@@ -0,0 +1,422 @@
+use rust_base58::FromBase58;
+use solana_transaction_status::{
+    UiCompiledInstruction, UiInnerInstructions, UiInstruction, UiTransactionTokenBalance,
+};
+use std::collections::HashMap;
+
+use crate::Balance;
+
+use super::TransactionParser;
+
+const TOKEN_PROGRAMS: [&str; 2] = [
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+];
+/// SPL Token is not decoded, `Transfer` and `TransferChecked` are told by the first byte of the
+/// data which is followed by the amount
+const TRANSFER_TAG: u8 = 3;
+const TRANSFER_CHECKED_TAG: u8 = 12;
+/// Log lines of the token program executing the transfers
+const TRANSFER_LOGS: [&str; 2] = [
+    "Program log: Instruction: Transfer",
+    "Program log: Instruction: TransferChecked",
+];
+
+/// SPL Token transfer between the token accounts at the positions `source` and `destination`
+/// of the transaction, `inner_instructions_set` is None for a top-level one
+struct TokenTransferInstruction {
+    inner_instructions_set: Option<u8>,
+    source: usize,
+    destination: usize,
+    amount: u64,
+}
+
+/// Token balance of an account while the transfers are replayed
+struct RunningBalance<'a> {
+    /// The post token balance, or the pre one of a closed account, for the mint and the owner
+    token_balance: &'a UiTransactionTokenBalance,
+    amount: u64,
+    post_amount: u64,
+}
+
+impl TransactionParser {
+    /// Balance rows of the token accounts per SPL Token transfer invoked by another program, in
+    /// the order the transfers were executed: `inner_instructions_set` and `attributed_amount`
+    /// tell the transfer and the token amounts are the balance of the account before and after
+    /// it. The transfers are replayed over the pre token balances, top-level ones included, so
+    /// an account hopping tokens within one transaction gets a row per hop. Nothing is
+    /// attributed unless the log messages show the token program executing every transfer, and
+    /// an account whose replayed balance doesn't end at its post token balance, e.g. because
+    /// tokens were minted or burned in between, has no attributed rows
+    pub(super) fn parse_attributed_balances(
+        tx_signature: &str,
+        accounts: &[String],
+        instructions: &[UiCompiledInstruction],
+        inner_instructions: &[UiInnerInstructions],
+        log_messages: &[String],
+        pre_token_balances: &[UiTransactionTokenBalance],
+        post_token_balances: &[UiTransactionTokenBalance],
+    ) -> Vec<Balance> {
+        let transfers = token_transfer_instructions(accounts, instructions, inner_instructions);
+
+        if transfers
+            .iter()
+            .all(|transfer| transfer.inner_instructions_set.is_none())
+            || logged_transfers(log_messages) != transfers.len()
+        {
+            return Vec::new();
+        }
+
+        let mut running_balances: HashMap<usize, RunningBalance> = HashMap::new();
+        for token_balance in pre_token_balances {
+            if let Ok(amount) = token_balance.ui_token_amount.amount.parse() {
+                running_balances.insert(
+                    token_balance.account_index as usize,
+                    RunningBalance {
+                        token_balance,
+                        amount,
+                        post_amount: 0,
+                    },
+                );
+            }
+        }
+        for token_balance in post_token_balances {
+            if let Ok(post_amount) = token_balance.ui_token_amount.amount.parse() {
+                let running_balance = running_balances
+                    .entry(token_balance.account_index as usize)
+                    .or_insert(RunningBalance {
+                        token_balance,
+                        amount: 0,
+                        post_amount,
+                    });
+                running_balance.token_balance = token_balance;
+                running_balance.post_amount = post_amount;
+            }
+        }
+
+        // (account, inner instructions set, amount, balance before, balance after)
+        let mut hops: Vec<(usize, u8, u64, u64, u64)> = Vec::new();
+
+        for transfer in &transfers {
+            for (account, sign) in [(transfer.source, -1), (transfer.destination, 1)] {
+                let running_balance = match running_balances.get_mut(&account) {
+                    Some(running_balance) => running_balance,
+                    None => return Vec::new(),
+                };
+                let before = running_balance.amount;
+                let after = if sign < 0 {
+                    before.checked_sub(transfer.amount)
+                } else {
+                    before.checked_add(transfer.amount)
+                };
+                running_balance.amount = match after {
+                    Some(after) => after,
+                    None => return Vec::new(),
+                };
+
+                if let Some(inner_instructions_set) = transfer.inner_instructions_set {
+                    hops.push((
+                        account,
+                        inner_instructions_set,
+                        transfer.amount,
+                        before,
+                        running_balance.amount,
+                    ));
+                }
+            }
+        }
+
+        hops.into_iter()
+            .filter_map(|(account, inner_instructions_set, amount, before, after)| {
+                let running_balance = &running_balances[&account];
+                if running_balance.amount != running_balance.post_amount {
+                    return None;
+                }
+
+                let token_balance = running_balance.token_balance;
+                let ui_amount = |amount: u64| {
+                    amount as f64 / 10_f64.powi(token_balance.ui_token_amount.decimals as i32)
+                };
+                let owner: Option<String> = token_balance.owner.clone().into();
+                let program_id: Option<String> = token_balance.program_id.clone().into();
+
+                Some(Balance {
+                    tx_signature: tx_signature.to_string(),
+                    account: accounts.get(account)?.clone(),
+                    pre_balance: None,
+                    post_balance: None,
+                    pre_token_balance_mint: Some(token_balance.mint.clone()),
+                    pre_token_balance_owner: owner.clone(),
+                    pre_token_balance_amount: Some(ui_amount(before)),
+                    pre_token_balance_program_id: program_id.clone(),
+                    post_token_balance_mint: Some(token_balance.mint.clone()),
+                    post_token_balance_owner: owner,
+                    post_token_balance_amount: Some(ui_amount(after)),
+                    post_token_balance_program_id: program_id,
+                    inner_instructions_set: Some(inner_instructions_set),
+                    attributed_amount: Some(amount),
+                })
+            })
+            .collect()
+    }
+}
+
+/// SPL Token transfers of the transaction in the order of their execution: every top-level
+/// instruction followed by its inner set
+fn token_transfer_instructions(
+    accounts: &[String],
+    instructions: &[UiCompiledInstruction],
+    inner_instructions: &[UiInnerInstructions],
+) -> Vec<TokenTransferInstruction> {
+    let mut transfers = Vec::new();
+
+    for (instruction_idx, instruction) in instructions.iter().enumerate() {
+        transfers.extend(token_transfer(accounts, instruction, None));
+
+        let inner_set = inner_instructions
+            .iter()
+            .filter(|inner_set| inner_set.index as usize == instruction_idx)
+            .flat_map(|inner_set| inner_set.instructions.iter());
+
+        for inner_instruction in inner_set {
+            if let UiInstruction::Compiled(inner_instruction) = inner_instruction {
+                transfers.extend(token_transfer(
+                    accounts,
+                    inner_instruction,
+                    Some(instruction_idx as u8),
+                ));
+            }
+        }
+    }
+
+    transfers
+}
+
+fn token_transfer(
+    accounts: &[String],
+    instruction: &UiCompiledInstruction,
+    inner_instructions_set: Option<u8>,
+) -> Option<TokenTransferInstruction> {
+    let program = accounts.get(instruction.program_id_index as usize)?;
+    if !TOKEN_PROGRAMS.contains(&program.as_str()) {
+        return None;
+    }
+
+    let data = instruction.data.from_base58().ok()?;
+    // source, destination, authority or source, mint, destination, authority
+    let destination_position = match data.first()? {
+        &TRANSFER_TAG => 1,
+        &TRANSFER_CHECKED_TAG => 2,
+        _ => return None,
+    };
+    let amount = u64::from_le_bytes(data.get(1..9)?.try_into().ok()?);
+
+    Some(TokenTransferInstruction {
+        inner_instructions_set,
+        source: *instruction.accounts.first()? as usize,
+        destination: *instruction.accounts.get(destination_position)? as usize,
+        amount,
+    })
+}
+
+/// Transfers the token program logged executing, the `Program log:` lines are attributed to the
+/// innermost invoked program
+fn logged_transfers(log_messages: &[String]) -> usize {
+    let mut programs: Vec<&str> = Vec::new();
+    let mut transfers = 0;
+
+    for line in log_messages {
+        if TRANSFER_LOGS.contains(&line.as_str()) {
+            if programs
+                .last()
+                .map_or(false, |program| TOKEN_PROGRAMS.contains(program))
+            {
+                transfers += 1;
+            }
+        } else if let Some((program, _)) = line
+            .strip_prefix("Program ")
+            .and_then(|line| line.split_once(" invoke ["))
+        {
+            programs.push(program);
+        } else if line.starts_with("Program ")
+            && !line.contains(':')
+            && (line.ends_with(" success") || line.contains(" failed"))
+        {
+            programs.pop();
+        }
+    }
+
+    transfers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_transaction, TransactionParsingResult, TxStatus};
+    use rust_base58::ToBase58;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    const TOKEN_PROGRAM: &str = TOKEN_PROGRAMS[0];
+    const MARKETPLACE_PROGRAM: &str = "M2mx93ekt1fmXSVkTrUL9xVFHkmME8HTUi5Cyc5aF7K";
+
+    fn token_balance(account_index: u8, mint: &str, owner: &str, amount: u64) -> serde_json::Value {
+        serde_json::json!({
+            "accountIndex": account_index,
+            "mint": mint,
+            "owner": owner,
+            "programId": TOKEN_PROGRAM,
+            "uiTokenAmount": {
+                "uiAmount": amount as f64 / 1e6,
+                "decimals": 6,
+                "amount": amount.to_string(),
+                "uiAmountString": (amount as f64 / 1e6).to_string()
+            }
+        })
+    }
+
+    fn transfer_data(amount: u64) -> String {
+        let mut data = vec![TRANSFER_TAG];
+        data.extend(amount.to_le_bytes());
+        data.to_base58()
+    }
+
+    /// Marketplace instruction moving 1000 tokens from the buyer into the escrow and then 600 of
+    /// them to the seller with two SPL Token transfer CPIs, the escrow keeps 400. Returns the
+    /// transaction and the escrow token account
+    fn escrow_hops_transaction(
+        log_messages: Vec<&str>,
+    ) -> (EncodedConfirmedTransactionWithStatusMeta, String) {
+        let mint = Pubkey::new_unique().to_string();
+        let seller = Pubkey::new_unique().to_string();
+        // buyer, buyer token account, escrow token account, seller token account, escrow
+        // authority, token program, marketplace program
+        let mut account_keys: Vec<String> =
+            (0..5).map(|_| Pubkey::new_unique().to_string()).collect();
+        account_keys.extend([TOKEN_PROGRAM.to_string(), MARKETPLACE_PROGRAM.to_string()]);
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 2
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": 6,
+                        "accounts": [0, 1, 2, 3, 4, 5],
+                        "data": ""
+                    }]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1_000_000_000_u64, 2_039_280, 2_039_280, 2_039_280, 0, 1, 1],
+                "postBalances": [999_995_000_u64, 2_039_280, 2_039_280, 2_039_280, 0, 1, 1],
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [
+                        { "programIdIndex": 5, "accounts": [1, 2, 0], "data": transfer_data(1000) },
+                        { "programIdIndex": 5, "accounts": [2, 3, 4], "data": transfer_data(600) }
+                    ]
+                }],
+                "logMessages": log_messages,
+                "preTokenBalances": [
+                    token_balance(1, &mint, &account_keys[0], 5000),
+                    token_balance(2, &mint, &account_keys[4], 0),
+                    token_balance(3, &mint, &seller, 100)
+                ],
+                "postTokenBalances": [
+                    token_balance(1, &mint, &account_keys[0], 4000),
+                    token_balance(2, &mint, &account_keys[4], 400),
+                    token_balance(3, &mint, &seller, 700)
+                ],
+                "rewards": []
+            }
+        });
+
+        let transaction = EncodedConfirmedTransactionWithStatusMeta {
+            slot: 150000000,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        };
+
+        (transaction, account_keys[2].clone())
+    }
+
+    fn executed_logs() -> Vec<&'static str> {
+        vec![
+            "Program M2mx93ekt1fmXSVkTrUL9xVFHkmME8HTUi5Cyc5aF7K invoke [1]",
+            "Program log: Instruction: Buy",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: Transfer",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: Transfer",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+            "Program M2mx93ekt1fmXSVkTrUL9xVFHkmME8HTUi5Cyc5aF7K success",
+        ]
+    }
+
+    #[test]
+    fn escrow_hops_are_attributed_to_their_transfers() {
+        let (transaction, escrow_account) = escrow_hops_transaction(executed_logs());
+
+        let TransactionParsingResult {
+            instructions,
+            balances,
+            ..
+        } = parse_transaction(transaction).unwrap();
+        assert_eq!(instructions[0].tx_status, TxStatus::Success);
+
+        // The transaction-level row of the escrow keeps only the net change
+        let escrow_rows: Vec<_> = balances
+            .iter()
+            .filter(|balance| balance.account == escrow_account)
+            .map(|balance| {
+                (
+                    balance.inner_instructions_set,
+                    balance.attributed_amount,
+                    balance.pre_token_balance_amount,
+                    balance.post_token_balance_amount,
+                )
+            })
+            .collect();
+        assert_eq!(
+            escrow_rows,
+            vec![
+                (None, None, Some(0.0), Some(0.0004)),
+                (Some(0), Some(1000), Some(0.0), Some(0.001)),
+                (Some(0), Some(600), Some(0.001), Some(0.0004)),
+            ]
+        );
+
+        // buyer -> escrow, escrow -> seller
+        let attributed: Vec<_> = balances
+            .iter()
+            .filter_map(|balance| balance.attributed_amount)
+            .collect();
+        assert_eq!(attributed, vec![1000, 1000, 600, 600]);
+    }
+
+    #[test]
+    fn transfers_missing_from_the_logs_are_not_attributed() {
+        // The logs were truncated before the second transfer
+        let mut logs = executed_logs();
+        logs.truncate(5);
+        logs.push("Log truncated");
+
+        let (transaction, _) = escrow_hops_transaction(logs);
+        let balances = parse_transaction(transaction).unwrap().balances;
+
+        assert!(balances
+            .iter()
+            .all(|balance| balance.inner_instructions_set.is_none()));
+    }
+}
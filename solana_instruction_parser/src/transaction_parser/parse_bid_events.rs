@@ -0,0 +1,294 @@
+use rust_base58::FromBase58;
+
+use crate::{BidEvent, Instruction, InstructionArgument};
+
+use super::TransactionParser;
+
+const AUCTION_PROGRAM: &str = "auctxRXPeJoc4817jDhf4HbjnhEcr1cCXenosMhK5R8";
+const TOKEN_PROGRAMS: [&str; 2] = [
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+];
+/// Tags of `Transfer` and `TransferChecked`, both followed by the amount. SPL Token is not
+/// decoded, so they are told by the first byte of the data
+const TRANSFER_INSTRUCTION_TAGS: [u8; 2] = [3, 12];
+
+impl TransactionParser {
+    /// Bid events of the decoded Auction program instructions of a successful transaction, in
+    /// the order of the instructions. The bidder and the auction are taken from the positions of
+    /// the accounts, the amount of a placed bid from its arguments and the settled amounts from
+    /// the SPL Token transfers invoked by the instruction. The Metaplex `ClaimBid` invokes the
+    /// Auction `ClaimBid`, so the win is taken from the inner one only
+    pub(super) fn parse_bid_events(
+        instructions: &[Instruction],
+        instruction_arguments: &[InstructionArgument],
+    ) -> Vec<BidEvent> {
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program == AUCTION_PROGRAM)
+            .flat_map(|instruction| {
+                Self::parse_bid_event(instruction, instructions, instruction_arguments)
+            })
+            .collect()
+    }
+
+    fn parse_bid_event(
+        instruction: &Instruction,
+        instructions: &[Instruction],
+        instruction_arguments: &[InstructionArgument],
+    ) -> Vec<BidEvent> {
+        let account = |idx: usize| instruction.accounts.get(idx).cloned();
+        let event = |event_type: &str, auction: Option<String>, bidder, amount| {
+            Some(BidEvent {
+                tx_signature: instruction.tx_signature.clone(),
+                slot: instruction.slot,
+                instruction_idx: instruction.instruction_idx,
+                inner_instructions_set: instruction.inner_instructions_set,
+                auction: auction?,
+                bidder: bidder?,
+                amount,
+                event_type: event_type.to_string(),
+            })
+        };
+
+        let events = match instruction.instruction_name.as_str() {
+            // bidder, bidder_token, bidder_pot, bidder_pot_token, bidder_meta, auction, ...
+            "PlaceBid" => {
+                let amount = instruction_arguments
+                    .iter()
+                    .find(|argument| {
+                        argument.instruction_idx == instruction.instruction_idx
+                            && argument.inner_instructions_set == instruction.inner_instructions_set
+                            && argument.arg_path == "/0/amount"
+                    })
+                    .and_then(|argument| argument.unsigned_value);
+
+                vec![event("Place", account(5), account(0), amount)]
+            }
+            // bidder, bidder_token, bidder_pot, bidder_pot_token, bidder_meta, auction, ... The
+            // pot is refunded to the bidder token account
+            "CancelBid" => {
+                let refund = Self::transferred_amount(instruction, instructions);
+
+                vec![
+                    event("Cancel", account(5), account(0), None),
+                    refund.and_then(|refund| event("Refund", account(5), account(0), Some(refund))),
+                ]
+            }
+            // destination, bidder_pot_token, bidder_pot, authority, auction, bidder, mint, ...
+            // The pot is paid to the destination of the auction
+            "ClaimBid" => vec![event(
+                "Win",
+                account(4),
+                account(5),
+                Self::transferred_amount(instruction, instructions),
+            )],
+            _ => Vec::new(),
+        };
+
+        events.into_iter().flatten().collect()
+    }
+
+    /// Amount of the first SPL Token transfer in the inner instruction set of `instruction`:
+    /// among the inner instructions of a top-level one, or following an inner one
+    fn transferred_amount(instruction: &Instruction, instructions: &[Instruction]) -> Option<u64> {
+        instructions
+            .iter()
+            .filter(|transfer| match instruction.inner_instructions_set {
+                None => transfer.transaction_instruction_idx == Some(instruction.instruction_idx),
+                Some(inner_instructions_set) => {
+                    transfer.inner_instructions_set == Some(inner_instructions_set)
+                        && transfer.instruction_idx > instruction.instruction_idx
+                }
+            })
+            .find_map(transfer_amount)
+    }
+}
+
+/// Amount of an SPL Token `Transfer` or `TransferChecked` instruction
+fn transfer_amount(instruction: &Instruction) -> Option<u64> {
+    if !TOKEN_PROGRAMS.contains(&instruction.program.as_str()) {
+        return None;
+    }
+
+    let data = instruction.data.from_base58().ok()?;
+    if !TRANSFER_INSTRUCTION_TAGS.contains(data.first()?) {
+        return None;
+    }
+
+    Some(u64::from_le_bytes(data.get(1..9)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_base58::ToBase58;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+    const METAPLEX_PROGRAM: &str = "p1exdMJcjVao65QdewkaZRUnU6VPSXhus9n2GzWfh98";
+
+    /// Instruction of `program` named `name` whose accounts are named after their positions,
+    /// an inner one of the top-level `outer` when it is given
+    fn instruction(
+        program: &str,
+        name: &str,
+        instruction_idx: u8,
+        outer: Option<u8>,
+        accounts: usize,
+    ) -> Instruction {
+        let mut instruction = Instruction::new(&Pubkey::default(), &Signature::default());
+        instruction.program = program.to_string();
+        instruction.tx_signature = "signature".to_string();
+        instruction.slot = 100;
+        instruction.instruction_idx = instruction_idx;
+        instruction.inner_instructions_set = outer;
+        instruction.transaction_instruction_idx = outer;
+        instruction.instruction_name = name.to_string();
+        instruction.accounts = (0..accounts)
+            .map(|idx| format!("{}_{}", name, idx))
+            .collect();
+
+        instruction
+    }
+
+    /// SPL Token `Transfer` of `amount`, an inner instruction of the top-level `outer`
+    fn transfer(instruction_idx: u8, outer: u8, amount: u64) -> Instruction {
+        let mut transfer = instruction(
+            TOKEN_PROGRAMS[0],
+            "Unknown",
+            instruction_idx,
+            Some(outer),
+            3,
+        );
+        transfer.data = [&[3][..], &amount.to_le_bytes()].concat().to_base58();
+
+        transfer
+    }
+
+    fn place_bid_amount(instruction_idx: u8, amount: u64) -> InstructionArgument {
+        let mut argument =
+            InstructionArgument::new("signature", instruction_idx, None, AUCTION_PROGRAM);
+        argument.arg_path = "/0/amount".to_string();
+        argument.unsigned_value = Some(amount);
+
+        argument
+    }
+
+    /// Event type, auction, bidder and amount of the bid events
+    fn parse(
+        instructions: &[Instruction],
+        instruction_arguments: &[InstructionArgument],
+    ) -> Vec<(String, String, String, Option<u64>)> {
+        TransactionParser::parse_bid_events(instructions, instruction_arguments)
+            .into_iter()
+            .map(|bid_event| {
+                (
+                    bid_event.event_type,
+                    bid_event.auction,
+                    bid_event.bidder,
+                    bid_event.amount,
+                )
+            })
+            .collect()
+    }
+
+    fn bid_event(
+        event_type: &str,
+        name: &str,
+        auction_idx: usize,
+        bidder_idx: usize,
+        amount: Option<u64>,
+    ) -> (String, String, String, Option<u64>) {
+        (
+            event_type.to_string(),
+            format!("{}_{}", name, auction_idx),
+            format!("{}_{}", name, bidder_idx),
+            amount,
+        )
+    }
+
+    #[test]
+    fn place_then_cancel() {
+        let place = vec![
+            instruction(AUCTION_PROGRAM, "PlaceBid", 0, None, 13),
+            transfer(0, 0, 1000),
+        ];
+        let cancel = vec![
+            instruction(AUCTION_PROGRAM, "CancelBid", 0, None, 11),
+            transfer(0, 0, 1000),
+        ];
+
+        assert_eq!(
+            parse(&place, &[place_bid_amount(0, 1000)]),
+            vec![bid_event("Place", "PlaceBid", 5, 0, Some(1000))]
+        );
+        assert_eq!(
+            parse(&cancel, &[]),
+            vec![
+                bid_event("Cancel", "CancelBid", 5, 0, None),
+                bid_event("Refund", "CancelBid", 5, 0, Some(1000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn place_then_win() {
+        let place = vec![
+            instruction(AUCTION_PROGRAM, "PlaceBid", 0, None, 13),
+            transfer(0, 0, 1000),
+        ];
+        // The Metaplex `ClaimBid` invokes the Auction one, which pays the pot
+        let claim = vec![
+            instruction(METAPLEX_PROGRAM, "ClaimBid", 0, None, 13),
+            instruction(AUCTION_PROGRAM, "ClaimBid", 0, Some(0), 10),
+            transfer(1, 0, 1000),
+        ];
+
+        assert_eq!(
+            parse(&place, &[place_bid_amount(0, 1000)]),
+            vec![bid_event("Place", "PlaceBid", 5, 0, Some(1000))]
+        );
+        assert_eq!(
+            TransactionParser::parse_bid_events(&claim, &[]),
+            vec![BidEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                instruction_idx: 0,
+                inner_instructions_set: Some(0),
+                auction: "ClaimBid_4".to_string(),
+                bidder: "ClaimBid_5".to_string(),
+                amount: Some(1000),
+                event_type: "Win".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn increased_bid_has_a_row_per_bid() {
+        let instructions = vec![
+            instruction(AUCTION_PROGRAM, "PlaceBid", 0, None, 13),
+            instruction(AUCTION_PROGRAM, "PlaceBid", 1, None, 13),
+        ];
+
+        assert_eq!(
+            parse(
+                &instructions,
+                &[place_bid_amount(0, 1000), place_bid_amount(1, 1500)]
+            ),
+            vec![
+                bid_event("Place", "PlaceBid", 5, 0, Some(1000)),
+                bid_event("Place", "PlaceBid", 5, 0, Some(1500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancel_without_a_transfer_has_no_refund() {
+        let instructions = vec![instruction(AUCTION_PROGRAM, "CancelBid", 0, None, 11)];
+
+        assert_eq!(
+            parse(&instructions, &[]),
+            vec![bid_event("Cancel", "CancelBid", 5, 0, None)]
+        );
+    }
+}
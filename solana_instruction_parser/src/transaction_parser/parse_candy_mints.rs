@@ -0,0 +1,399 @@
+use rust_base58::FromBase58;
+
+use crate::{Balance, CandyMint, Instruction, TokenTransfer};
+
+use super::TransactionParser;
+
+const CANDY_MACHINE_PROGRAM: &str = "cndy3Z4yapfJBmL3ShUp5exZKqR3z33thTzeNMm2gRZ";
+const TOKEN_PROGRAMS: [&str; 2] = [
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+];
+/// Tags of `InitializeMint`, `MintTo`, `MintToChecked` and `InitializeMint2`, whose first
+/// account is the mint. SPL Token is not decoded, so they are told by the first byte of the data
+const MINT_INSTRUCTION_TAGS: [u8; 4] = [0, 7, 14, 20];
+
+impl TransactionParser {
+    /// Candy machine mints of the decoded instructions of a successful transaction, in the order
+    /// of the instructions. The machine and the minter are taken from the positions of the
+    /// accounts of `MintNft`, the paid amount from the balances and the token transfers
+    pub(super) fn parse_candy_mints(
+        instructions: &[Instruction],
+        balances: &[Balance],
+        token_transfers: &[TokenTransfer],
+    ) -> Vec<CandyMint> {
+        let new_mints: Vec<&str> = instructions
+            .iter()
+            .filter_map(|instruction| new_mint(instruction))
+            .collect();
+
+        instructions
+            .iter()
+            .filter(|instruction| {
+                instruction.program == CANDY_MACHINE_PROGRAM
+                    && instruction.instruction_name == "MintNft"
+            })
+            .filter_map(|instruction| {
+                Self::parse_candy_mint(
+                    instruction,
+                    instructions,
+                    &new_mints,
+                    balances,
+                    token_transfers,
+                )
+            })
+            .collect()
+    }
+
+    fn parse_candy_mint(
+        mint_nft: &Instruction,
+        instructions: &[Instruction],
+        new_mints: &[&str],
+        balances: &[Balance],
+        token_transfers: &[TokenTransfer],
+    ) -> Option<CandyMint> {
+        // candy_machine, candy_machine_creator, payer, wallet, metadata, mint, mint_authority,
+        // update_authority, master_edition, ...
+        let account = |idx: usize| mint_nft.accounts.get(idx).cloned();
+        let candy_machine = account(0)?;
+        let minter = account(2)?;
+
+        // The mint account of `MintNft` when the transaction creates it, the only mint it
+        // creates otherwise
+        let mint = match account(5) {
+            Some(mint) if new_mints.contains(&mint.as_str()) => Some(mint),
+            _ if new_mints.len() == 1 => Some(new_mints[0].to_string()),
+            _ => None,
+        };
+
+        let (price_lamports, currency_mint) =
+            match spl_price(&minter, mint.as_deref(), token_transfers) {
+                Some((price, currency_mint)) => (Some(price), Some(currency_mint)),
+                None => (
+                    account(3).and_then(|wallet| sol_price(&minter, &wallet, balances)),
+                    None,
+                ),
+            };
+
+        Some(CandyMint {
+            tx_signature: mint_nft.tx_signature.clone(),
+            slot: mint_nft.slot,
+            instruction_idx: mint_nft.instruction_idx,
+            inner_instructions_set: mint_nft.inner_instructions_set,
+            candy_machine,
+            minter,
+            mint,
+            price_lamports,
+            currency_mint,
+            collection: collection(&account(0), &account(4), instructions),
+        })
+    }
+}
+
+/// Mint of an SPL Token instruction creating or minting to it
+fn new_mint(instruction: &Instruction) -> Option<&str> {
+    if !TOKEN_PROGRAMS.contains(&instruction.program.as_str()) {
+        return None;
+    }
+
+    let tag = *instruction.data.from_base58().ok()?.first()?;
+    if !MINT_INSTRUCTION_TAGS.contains(&tag) {
+        return None;
+    }
+
+    instruction.accounts.first().map(String::as_str)
+}
+
+/// Tokens the minter sent to another owner, the price of the machines priced in an SPL token.
+/// The minted NFT and the burned whitelist tokens are not a payment
+fn spl_price(
+    minter: &str,
+    mint: Option<&str>,
+    token_transfers: &[TokenTransfer],
+) -> Option<(u64, String)> {
+    token_transfers
+        .iter()
+        .find(|token_transfer| {
+            token_transfer.source_owner.as_deref() == Some(minter)
+                && token_transfer.destination_owner.is_some()
+                && Some(token_transfer.mint.as_str()) != mint
+        })
+        .map(|token_transfer| (token_transfer.amount, token_transfer.mint.clone()))
+}
+
+/// Lamports the minter lost, which also paid the fee and the rent of the new accounts, up to
+/// the lamports the wallet of the machine got
+fn sol_price(minter: &str, wallet: &str, balances: &[Balance]) -> Option<u64> {
+    let delta = |account: &str| {
+        balances
+            .iter()
+            .find(|balance| balance.account == account)
+            .and_then(|balance| Some(balance.post_balance? as i128 - balance.pre_balance? as i128))
+    };
+
+    let paid = -delta(minter)?;
+    let received = delta(wallet)?;
+
+    Some(paid.min(received).max(0) as u64)
+}
+
+/// Collection mint set by the `SetCollectionDuringMint` instruction of the machine and the
+/// metadata of the minted NFT
+fn collection(
+    candy_machine: &Option<String>,
+    metadata: &Option<String>,
+    instructions: &[Instruction],
+) -> Option<String> {
+    // candy_machine, metadata, payer, collection_pda, token_metadata_program, instructions,
+    // collection_mint, ...
+    instructions
+        .iter()
+        .find(|instruction| {
+            instruction.program == CANDY_MACHINE_PROGRAM
+                && instruction.instruction_name == "SetCollectionDuringMint"
+                && instruction.accounts.first() == candy_machine.as_ref()
+                && instruction.accounts.get(1) == metadata.as_ref()
+        })
+        .and_then(|instruction| instruction.accounts.get(6).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use rust_base58::ToBase58;
+    use solana_program::pubkey::Pubkey;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    const MINT_NFT: [u8; 8] = [211, 57, 6, 167, 15, 219, 35, 251];
+    const SET_COLLECTION_DURING_MINT: [u8; 8] = [103, 17, 200, 25, 118, 95, 125, 61];
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    /// Accounts of a candy machine mint
+    struct Keys {
+        minter: String,
+        mint: String,
+        nft_account: String,
+        candy_machine: String,
+        wallet: String,
+        metadata: String,
+        collection_mint: String,
+        payment_account: String,
+    }
+
+    impl Keys {
+        fn new() -> Self {
+            let key = || Pubkey::new_unique().to_string();
+
+            Self {
+                minter: key(),
+                mint: key(),
+                nft_account: key(),
+                candy_machine: key(),
+                wallet: key(),
+                metadata: key(),
+                collection_mint: key(),
+                payment_account: key(),
+            }
+        }
+    }
+
+    /// Token balance of `account` holding `amount` of `mint` owned by `owner`
+    fn token_balance(
+        account_index: usize,
+        mint: &str,
+        owner: &str,
+        amount: u64,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "accountIndex": account_index,
+            "mint": mint,
+            "owner": owner,
+            "programId": TOKEN_PROGRAMS[0],
+            "uiTokenAmount": {
+                "uiAmount": amount as f64,
+                "decimals": 0,
+                "amount": amount.to_string(),
+                "uiAmountString": amount.to_string()
+            }
+        })
+    }
+
+    /// Transaction as sent by the candy machine UI: the new mint is initialized and minted to
+    /// the minter before `MintNft`, which is followed by `SetCollectionDuringMint`. The machine
+    /// priced in USDC gets the token account of the minter paying it and its wallet is a USDC
+    /// token account
+    fn mint_transaction(
+        keys: &Keys,
+        usdc_price: Option<u64>,
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let mut account_keys = vec![
+            keys.minter.clone(),
+            keys.mint.clone(),
+            keys.nft_account.clone(),
+            keys.candy_machine.clone(),
+            keys.wallet.clone(),
+            keys.metadata.clone(),
+            keys.collection_mint.clone(),
+            keys.payment_account.clone(),
+            TOKEN_PROGRAMS[0].to_string(),
+            CANDY_MACHINE_PROGRAM.to_string(),
+        ];
+        // candy_machine_creator, master_edition, collection_pda, collection_metadata,
+        // collection_master_edition, collection authority and the programs and sysvars
+        account_keys.extend((0..11).map(|_| Pubkey::new_unique().to_string()));
+
+        let mut mint_nft_accounts = vec![3, 10, 0, 4, 5, 1, 0, 0, 11, 16, 8, 17, 18, 19, 20, 12];
+        if usdc_price.is_some() {
+            mint_nft_accounts.extend([7, 0]);
+        }
+
+        let mut mint_nft_data = MINT_NFT.to_vec();
+        mint_nft_data.push(254);
+
+        let instruction = |program_id_index: usize, accounts: &[usize], data: &[u8]| {
+            serde_json::json!({
+                "programIdIndex": program_id_index,
+                "accounts": accounts,
+                "data": data.to_base58()
+            })
+        };
+
+        let (pre_balances, post_balances, pre_token_balances, post_token_balances) =
+            match usdc_price {
+                // The minter pays 1 SOL and the rent of the new accounts, the wallet gets 1 SOL
+                None => (
+                    vec![3_000_000_000_u64, 0, 0, 0, 5_000_000],
+                    vec![1_984_000_000_u64, 1_461_600, 2_039_280, 0, 1_005_000_000],
+                    vec![],
+                    vec![token_balance(2, &keys.mint, &keys.minter, 1)],
+                ),
+                Some(price) => (
+                    vec![3_000_000_000_u64, 0, 0, 0, 2_039_280],
+                    vec![2_996_000_000_u64, 1_461_600, 2_039_280, 0, 2_039_280],
+                    vec![
+                        token_balance(4, USDC, &keys.candy_machine, 0),
+                        token_balance(7, USDC, &keys.minter, 2 * price),
+                    ],
+                    vec![
+                        token_balance(2, &keys.mint, &keys.minter, 1),
+                        token_balance(4, USDC, &keys.candy_machine, price),
+                        token_balance(7, USDC, &keys.minter, price),
+                    ],
+                ),
+            };
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 2,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [
+                        // InitializeMint with 0 decimals
+                        instruction(8, &[1, 18], &[0, 0]),
+                        // MintTo of 1 token
+                        instruction(8, &[1, 2, 0], &[7, 1, 0, 0, 0, 0, 0, 0, 0]),
+                        instruction(9, &mint_nft_accounts, &mint_nft_data),
+                        instruction(
+                            9,
+                            &[3, 5, 0, 13, 16, 20, 6, 14, 15, 12],
+                            &SET_COLLECTION_DURING_MINT
+                        )
+                    ]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": pre_balances,
+                "postBalances": post_balances,
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": pre_token_balances,
+                "postTokenBalances": post_token_balances,
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 150000000,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    #[test]
+    fn mint_priced_in_sol() {
+        let keys = Keys::new();
+
+        let candy_mints = parse_transaction(mint_transaction(&keys, None, serde_json::Value::Null))
+            .unwrap()
+            .candy_mints;
+
+        assert_eq!(candy_mints.len(), 1);
+        let candy_mint = &candy_mints[0];
+        assert_eq!(
+            (
+                candy_mint.instruction_idx,
+                candy_mint.candy_machine.as_str(),
+                candy_mint.minter.as_str(),
+                candy_mint.mint.as_deref(),
+                candy_mint.price_lamports,
+                candy_mint.currency_mint.as_deref(),
+                candy_mint.collection.as_deref(),
+            ),
+            (
+                2,
+                keys.candy_machine.as_str(),
+                keys.minter.as_str(),
+                Some(keys.mint.as_str()),
+                Some(1_000_000_000),
+                None,
+                Some(keys.collection_mint.as_str()),
+            )
+        );
+    }
+
+    #[test]
+    fn mint_priced_in_spl_token() {
+        let keys = Keys::new();
+
+        let candy_mints = parse_transaction(mint_transaction(
+            &keys,
+            Some(5_000_000),
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .candy_mints;
+
+        assert_eq!(candy_mints.len(), 1);
+        assert_eq!(candy_mints[0].mint.as_deref(), Some(keys.mint.as_str()));
+        assert_eq!(candy_mints[0].price_lamports, Some(5_000_000));
+        assert_eq!(candy_mints[0].currency_mint.as_deref(), Some(USDC));
+    }
+
+    #[test]
+    fn failed_transaction_has_no_mints() {
+        let candy_mints = parse_transaction(mint_transaction(
+            &Keys::new(),
+            None,
+            serde_json::json!({ "InstructionError": [2, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .candy_mints;
+
+        assert!(candy_mints.is_empty());
+    }
+}
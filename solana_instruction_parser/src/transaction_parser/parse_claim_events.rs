@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::instructions::gumdrop_instruction::GumdropInstruction;
+use crate::ClaimEvent;
+
+use super::TransactionParser;
+
+impl TransactionParser {
+    /// Builds the claim event of a `Claim` or `ClaimCandy` instruction, None is returned for the
+    /// other Gumdrop instructions. The distributor is the first account of both, the mint of
+    /// `Claim` is the one of the receiving token account found in `token_mints`, the mint of
+    /// `ClaimCandy` is the candy machine mint account
+    pub fn parse_claim_event(
+        instruction: &GumdropInstruction,
+        accounts: &[String],
+        token_mints: &HashMap<String, String>,
+        tx_signature: &str,
+        slot: u64,
+    ) -> Option<ClaimEvent> {
+        let account = |idx: usize| accounts.get(idx).cloned();
+
+        let (claimant_secret, amount, mint) = match instruction {
+            GumdropInstruction::Claim {
+                claimant_secret,
+                amount,
+                ..
+            } => (
+                claimant_secret,
+                amount,
+                account(3).and_then(|to| token_mints.get(&to).cloned()),
+            ),
+            GumdropInstruction::ClaimCandy {
+                claimant_secret,
+                amount,
+                ..
+            } => (claimant_secret, amount, account(8)),
+            _ => return None,
+        };
+
+        Some(ClaimEvent {
+            tx_signature: tx_signature.to_string(),
+            slot,
+            distributor: account(0)?,
+            claimant: claimant_secret.to_string(),
+            amount: *amount,
+            mint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::gumdrop_instruction::tests::{CLAIM, CLAIM_CANDY};
+    use crate::parse_transaction;
+    use solana_program::pubkey::Pubkey;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    const GUMDROP_PROGRAM: &str = "gdrpGjVffourzkdDRrQmySw4aTHr8a3xmQzzxSwFD1a";
+    const CLAIMANT: &str = "9XQJeiCUAN4oZyBrG8x6kAHi4cszz6L4kjnGZGR2fsWs";
+
+    /// A transaction of one Gumdrop instruction, the accounts are passed in their order and the
+    /// token balances are given as (account index, mint)
+    fn gumdrop_transaction(
+        data: &str,
+        accounts: &[String],
+        token_balances: &[(u8, &str)],
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let account_keys: Vec<String> = accounts
+            .iter()
+            .cloned()
+            .chain([GUMDROP_PROGRAM.to_string()])
+            .collect();
+        let token_balances: Vec<_> = token_balances
+            .iter()
+            .map(|(account_index, mint)| {
+                serde_json::json!({
+                    "accountIndex": account_index,
+                    "mint": mint,
+                    "uiTokenAmount": {
+                        "uiAmount": 1.0,
+                        "decimals": 0,
+                        "amount": "1",
+                        "uiAmountString": "1"
+                    }
+                })
+            })
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": accounts.len(),
+                        "accounts": (0..accounts.len()).collect::<Vec<_>>(),
+                        "data": data,
+                    }]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": token_balances,
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    fn unique_accounts(len: usize) -> Vec<String> {
+        (0..len).map(|_| Pubkey::new_unique().to_string()).collect()
+    }
+
+    #[test]
+    fn claim_event_has_mint_of_receiving_token_account() {
+        // distributor, claim_status, from, to, temporal, payer
+        let accounts = unique_accounts(6);
+        let mint = Pubkey::new_unique().to_string();
+
+        let claim_events = parse_transaction(gumdrop_transaction(
+            CLAIM,
+            &accounts,
+            &[(2, &mint), (3, &mint)],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .claim_events;
+
+        assert_eq!(
+            claim_events,
+            vec![ClaimEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                distributor: accounts[0].clone(),
+                claimant: CLAIMANT.to_string(),
+                amount: 500_000_000,
+                mint: Some(mint),
+            }]
+        );
+    }
+
+    #[test]
+    fn claim_candy_event_has_candy_machine_mint() {
+        // distributor, distributor_wallet, claim_count, temporal, payer, candy_machine_config,
+        // candy_machine, candy_machine_wallet, candy_machine_mint
+        let accounts = unique_accounts(9);
+
+        let claim_events = parse_transaction(gumdrop_transaction(
+            CLAIM_CANDY,
+            &accounts,
+            &[],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .claim_events;
+
+        assert_eq!(
+            claim_events,
+            vec![ClaimEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                distributor: accounts[0].clone(),
+                claimant: CLAIMANT.to_string(),
+                amount: 2,
+                mint: Some(accounts[8].clone()),
+            }]
+        );
+    }
+
+    #[test]
+    fn failed_transaction_has_no_claim_events() {
+        let claim_events = parse_transaction(gumdrop_transaction(
+            CLAIM,
+            &unique_accounts(6),
+            &[],
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .claim_events;
+
+        assert!(claim_events.is_empty());
+    }
+
+    #[test]
+    fn other_gumdrop_instructions_are_not_claims() {
+        assert_eq!(
+            TransactionParser::parse_claim_event(
+                &GumdropInstruction::CloseDistributor {
+                    _bump: 1,
+                    _wallet_bump: 2
+                },
+                &["distributor".to_string()],
+                &HashMap::new(),
+                "signature",
+                1
+            ),
+            None
+        );
+    }
+}
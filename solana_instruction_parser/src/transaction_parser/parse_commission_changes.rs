@@ -0,0 +1,191 @@
+use crate::instructions::vote_instruction::VoteInstruction;
+use crate::CommissionChange;
+
+use super::TransactionParser;
+
+impl TransactionParser {
+    /// Builds the commission change of an `UpdateCommission` instruction, None is returned for
+    /// the other vote instructions. The vote account and the withdraw authority are taken from
+    /// the accounts of the instruction, the old commission from the latest of `previous_changes`
+    /// of the same vote account
+    pub fn parse_commission_change(
+        instruction: &VoteInstruction,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+        previous_changes: &[CommissionChange],
+    ) -> Option<CommissionChange> {
+        let new_commission = match instruction {
+            VoteInstruction::UpdateCommission(commission) => *commission,
+            _ => return None,
+        };
+
+        let vote_account = accounts.first().cloned()?;
+        let old_commission = previous_changes
+            .iter()
+            .rev()
+            .find(|change| change.vote_account == vote_account)
+            .map(|change| change.new_commission);
+
+        Some(CommissionChange {
+            tx_signature: tx_signature.to_string(),
+            slot,
+            vote_account,
+            authority: accounts.get(1).cloned(),
+            old_commission,
+            new_commission,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_transaction, ParseOptions, ProgramInstruction};
+    use rust_base58::ToBase58;
+    use solana_program::{pubkey::Pubkey, vote};
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    /// A transaction of `UpdateCommission` instructions built by the vote program helper
+    fn update_commission_transaction(
+        vote_account: &Pubkey,
+        withdrawer: &Pubkey,
+        commissions: &[u8],
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let instructions: Vec<_> = commissions
+            .iter()
+            .map(|commission| {
+                let instruction =
+                    vote::instruction::update_commission(vote_account, withdrawer, *commission);
+
+                serde_json::json!({
+                    "programIdIndex": 2,
+                    "accounts": [1, 0],
+                    "data": instruction.data.to_base58(),
+                })
+            })
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        withdrawer.to_string(),
+                        vote_account.to_string(),
+                        vote::program::id().to_string()
+                    ],
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": instructions
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    #[test]
+    fn update_commission_is_decoded() {
+        let data =
+            vote::instruction::update_commission(&Pubkey::new_unique(), &Pubkey::new_unique(), 7)
+                .data;
+
+        let (json, _, program_instruction) = TransactionParser::parse_instruction(
+            &vote::program::id().to_string(),
+            &data,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(json, "{\"UpdateCommission\":7}");
+        assert!(matches!(
+            program_instruction,
+            Some(ProgramInstruction::Vote(VoteInstruction::UpdateCommission(
+                7
+            )))
+        ));
+    }
+
+    #[test]
+    fn commission_changes_are_emitted() {
+        let vote_account = Pubkey::new_unique();
+        let withdrawer = Pubkey::new_unique();
+
+        let commission_changes = parse_transaction(update_commission_transaction(
+            &vote_account,
+            &withdrawer,
+            &[10, 5],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .commission_changes;
+
+        let commission_change = |old_commission, new_commission| CommissionChange {
+            tx_signature: "signature".to_string(),
+            slot: 100,
+            vote_account: vote_account.to_string(),
+            authority: Some(withdrawer.to_string()),
+            old_commission,
+            new_commission,
+        };
+
+        assert_eq!(
+            commission_changes,
+            vec![commission_change(None, 10), commission_change(Some(10), 5)]
+        );
+    }
+
+    #[test]
+    fn failed_transaction_changes_no_commission() {
+        let commission_changes = parse_transaction(update_commission_transaction(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[10],
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .commission_changes;
+
+        assert!(commission_changes.is_empty());
+    }
+
+    #[test]
+    fn other_vote_instructions_are_not_changes() {
+        assert_eq!(
+            TransactionParser::parse_commission_change(
+                &VoteInstruction::Withdraw(1),
+                &["vote".to_string()],
+                "signature",
+                1,
+                &[],
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::str::FromStr;
+
+use solana_program::pubkey::Pubkey;
+
+use crate::instructions::token_entangler_instruction::TokenEntanglerInstruction;
+use crate::EntanglerSwap;
+
+use super::TransactionParser;
+
+pub(super) const TOKEN_ENTANGLER_PROGRAM: &str = "qntmGodpGkrM42mN68VCZHXnKqDCT8rdY23wFcXCLPd";
+/// Seed prefix of the entangled pair account, derived from the prefix and the two mints
+const PAIR_PREFIX: &[u8] = b"token_entangler";
+
+/// Positions of the `Swap` accounts a swap is built from in one version of the program
+struct SwapLayout {
+    payer: usize,
+    token_mint: usize,
+    replacement_token_mint: usize,
+    entangled_pair: usize,
+}
+
+/// Known layouts of `Swap`, newest first. The layout of a swap is the first one whose entangled
+/// pair account is derived from its two mints, so the accounts moved by a new version of the
+/// program are recognized once its layout is added here
+const SWAP_LAYOUTS: &[SwapLayout] = &[
+    // treasury_mint, payer, payment_account, payment_transfer_authority, token, token_mint,
+    // replacement_token_metadata, replacement_token_mint, replacement_token, transfer_authority,
+    // token_a_escrow, token_b_escrow, entangled_pair, token_program, system_program,
+    // ata_program, rent
+    SwapLayout {
+        payer: 1,
+        token_mint: 5,
+        replacement_token_mint: 7,
+        entangled_pair: 12,
+    },
+];
+
+impl TransactionParser {
+    /// Builds the swap of a `Swap` instruction, None is returned for the other Token Entangler
+    /// instructions. When no layout matches, the mints are taken from the newest layout in the
+    /// order they are swapped and the direction is "Unknown"
+    pub fn parse_entangler_swap(
+        instruction: &TokenEntanglerInstruction,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+    ) -> Option<EntanglerSwap> {
+        if *instruction != TokenEntanglerInstruction::Swap {
+            return None;
+        }
+
+        let account = |idx: usize| accounts.get(idx).cloned();
+        let swap = |layout: &SwapLayout, mint_a, mint_b, direction: &str| {
+            Some(EntanglerSwap {
+                tx_signature: tx_signature.to_string(),
+                slot,
+                payer: account(layout.payer)?,
+                mint_a,
+                mint_b,
+                direction: direction.to_string(),
+                price: None,
+            })
+        };
+
+        for layout in SWAP_LAYOUTS {
+            let (Some(token_mint), Some(replacement_token_mint), Some(entangled_pair)) = (
+                account(layout.token_mint),
+                account(layout.replacement_token_mint),
+                account(layout.entangled_pair),
+            ) else {
+                continue;
+            };
+
+            if is_entangled_pair(&token_mint, &replacement_token_mint, &entangled_pair) {
+                return swap(layout, token_mint, replacement_token_mint, "AToB");
+            }
+            if is_entangled_pair(&replacement_token_mint, &token_mint, &entangled_pair) {
+                return swap(layout, replacement_token_mint, token_mint, "BToA");
+            }
+        }
+
+        let layout = &SWAP_LAYOUTS[0];
+        swap(
+            layout,
+            account(layout.token_mint)?,
+            account(layout.replacement_token_mint)?,
+            "Unknown",
+        )
+    }
+}
+
+fn is_entangled_pair(mint_a: &str, mint_b: &str, entangled_pair: &str) -> bool {
+    let (Ok(mint_a), Ok(mint_b), Ok(program)) = (
+        Pubkey::from_str(mint_a),
+        Pubkey::from_str(mint_b),
+        Pubkey::from_str(TOKEN_ENTANGLER_PROGRAM),
+    ) else {
+        return false;
+    };
+
+    let (pair, _bump) =
+        Pubkey::find_program_address(&[PAIR_PREFIX, mint_a.as_ref(), mint_b.as_ref()], &program);
+
+    pair.to_string() == entangled_pair
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    /// Sighash of `Swap`, it has no arguments
+    const SWAP: &str = "icSNZP7U1uh";
+
+    /// A transaction of one `Swap` instruction, the accounts are passed in their order
+    fn swap_transaction(
+        accounts: &[String],
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let account_keys: Vec<String> = accounts
+            .iter()
+            .cloned()
+            .chain([TOKEN_ENTANGLER_PROGRAM.to_string()])
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": accounts.len(),
+                        "accounts": (0..accounts.len()).collect::<Vec<_>>(),
+                        "data": SWAP,
+                    }]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    /// Accounts of the current layout swapping a token of `token_mint` for one of
+    /// `replacement_token_mint`, the pair is derived from `mint_a` and `mint_b`
+    fn swap_accounts(
+        token_mint: &Pubkey,
+        replacement_token_mint: &Pubkey,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+    ) -> Vec<String> {
+        let program = Pubkey::from_str(TOKEN_ENTANGLER_PROGRAM).unwrap();
+        let (entangled_pair, _bump) = Pubkey::find_program_address(
+            &[PAIR_PREFIX, mint_a.as_ref(), mint_b.as_ref()],
+            &program,
+        );
+
+        let mut accounts: Vec<String> = (0..17).map(|_| Pubkey::new_unique().to_string()).collect();
+        accounts[5] = token_mint.to_string();
+        accounts[7] = replacement_token_mint.to_string();
+        accounts[12] = entangled_pair.to_string();
+        accounts
+    }
+
+    #[test]
+    fn swap_of_token_a_for_token_b() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = swap_accounts(&mint_a, &mint_b, &mint_a, &mint_b);
+
+        let entangler_swaps =
+            parse_transaction(swap_transaction(&accounts, serde_json::Value::Null))
+                .unwrap()
+                .entangler_swaps;
+
+        assert_eq!(
+            entangler_swaps,
+            vec![EntanglerSwap {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                payer: accounts[1].clone(),
+                mint_a: mint_a.to_string(),
+                mint_b: mint_b.to_string(),
+                direction: "AToB".to_string(),
+                price: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn swap_of_token_b_for_token_a() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let accounts = swap_accounts(&mint_b, &mint_a, &mint_a, &mint_b);
+
+        let entangler_swaps =
+            parse_transaction(swap_transaction(&accounts, serde_json::Value::Null))
+                .unwrap()
+                .entangler_swaps;
+
+        assert_eq!(entangler_swaps.len(), 1);
+        assert_eq!(entangler_swaps[0].mint_a, mint_a.to_string());
+        assert_eq!(entangler_swaps[0].mint_b, mint_b.to_string());
+        assert_eq!(entangler_swaps[0].direction, "BToA");
+    }
+
+    #[test]
+    fn swap_of_unknown_layout() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = swap_accounts(&mint_a, &mint_b, &mint_a, &mint_b);
+        accounts[12] = Pubkey::new_unique().to_string();
+
+        let entangler_swaps =
+            parse_transaction(swap_transaction(&accounts, serde_json::Value::Null))
+                .unwrap()
+                .entangler_swaps;
+
+        assert_eq!(entangler_swaps.len(), 1);
+        assert_eq!(entangler_swaps[0].mint_a, mint_a.to_string());
+        assert_eq!(entangler_swaps[0].mint_b, mint_b.to_string());
+        assert_eq!(entangler_swaps[0].direction, "Unknown");
+    }
+
+    #[test]
+    fn failed_transaction_has_no_swaps() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let entangler_swaps = parse_transaction(swap_transaction(
+            &swap_accounts(&mint_a, &mint_b, &mint_a, &mint_b),
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .entangler_swaps;
+
+        assert!(entangler_swaps.is_empty());
+    }
+
+    #[test]
+    fn other_entangler_instructions_are_not_swaps() {
+        assert_eq!(
+            TransactionParser::parse_entangler_swap(
+                &TokenEntanglerInstruction::UpdateEntangledPair {
+                    price: 1,
+                    pays_every_time: false
+                },
+                &["treasury_mint".to_string()],
+                "signature",
+                1
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,789 @@
+use crate::instructions::memo_instruction::{MemoInstruction, MEMO_PROGRAM, MEMO_V1_PROGRAM};
+use crate::instructions::system_instruction::SystemInstruction;
+use crate::instructions::token_metadata_instruction::MetadataInstruction;
+use crate::instructions::vote_instruction::VoteInstruction;
+use crate::instructions::{
+    auction_house_instruction::AuctionHouseInstruction, auction_instruction::AuctionInstruction,
+    candy_machine_instruction::CandyMachineInstruction,
+    fixed_price_sale_instruction::FixedPriceSaleInstruction,
+    gumdrop_instruction::GumdropInstruction, metaplex_instruction::MetaplexInstruction,
+    nft_packs_instruction::NFTPacksInstruction, stake_instruction::StakeInstruction,
+    stake_pool_instruction::StakePoolInstruction,
+    token_entangler_instruction::TokenEntanglerInstruction,
+    token_vault_instruction::VaultInstruction,
+};
+
+use crate::errors::ParseInstructionError;
+use crate::{
+    account_role, AnchorEvent, Balance, BidEvent, CandyMint, ClaimEvent, CommissionChange,
+    EntanglerSwap, Instruction, InstructionArgument, NftEvent, PackEvent, ParseOptions, SaleEvent,
+    SolTransfer, StakeAccountEvent, TokenTransfer, TxStatus,
+};
+
+use anyhow::Result;
+use borsh::BorshDeserialize;
+use log::debug;
+use solana_sdk::message::MessageHeader;
+use solana_sdk::program_utils::limited_deserialize;
+use solana_sdk::transaction::{TransactionError, TransactionVersion};
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInnerInstructions,
+    UiLoadedAddresses, UiMessage, UiTransactionReturnData, UiTransactionTokenBalance,
+};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryInto;
+
+use super::{ProgramInstruction, TransactionParser, TransactionParsingResult};
+
+impl TransactionParser {
+    pub fn parse_transactions(
+        confirmed_transaction: EncodedConfirmedTransactionWithStatusMeta,
+        options: &ParseOptions,
+    ) -> Result<TransactionParsingResult, ParseInstructionError> {
+        let transaction = confirmed_transaction.transaction.transaction;
+        // Legacy transactions and the payloads loaded without the version have no version number
+        let tx_version = match confirmed_transaction.transaction.version {
+            Some(TransactionVersion::Number(version)) => Some(version),
+            Some(TransactionVersion::Legacy(_)) | None => None,
+        };
+        let slot = confirmed_transaction.slot;
+        let block_time = confirmed_transaction
+            .block_time
+            .map(|block_time| block_time as u64);
+        let mut parsed_instruction_arguments = Vec::new();
+        let mut balances = Vec::new();
+        let mut token_transfers: Vec<TokenTransfer> = Vec::new();
+        let mut nft_events: Vec<NftEvent> = Vec::new();
+        let mut commission_changes: Vec<CommissionChange> = Vec::new();
+        let mut claim_events: Vec<ClaimEvent> = Vec::new();
+        let mut entangler_swaps: Vec<EntanglerSwap> = Vec::new();
+        let mut stake_account_events: Vec<StakeAccountEvent> = Vec::new();
+        let mut sol_transfers: Vec<SolTransfer> = Vec::new();
+        let mut anchor_events: Vec<AnchorEvent> = Vec::new();
+        // Mints of the token accounts, the Gumdrop claims are sent to one of them
+        let mut token_mints: HashMap<String, String> = HashMap::new();
+        let mut pre_balances_map = HashMap::new();
+        let mut inner_instructions = OptionSerializer::None;
+        let mut log_messages = OptionSerializer::None;
+        let mut instructions_set: BTreeSet<Instruction> = BTreeSet::new();
+
+        // ToDo: remove this deprecated field. Look at https://github.com/solana-labs/solana/issues/9302
+        let mut tx_status = TxStatus::Success;
+        let mut failed_instruction_idx = None;
+
+        if let EncodedTransaction::Json(transaction_json) = transaction {
+            let message = transaction_json.message;
+            let tx_signature = &transaction_json.signatures[0];
+            let num_signatures = transaction_json.signatures.len() as u8;
+
+            if let UiMessage::Raw(message_raw) = message {
+                let mut account_roles =
+                    Self::static_account_roles(&message_raw.header, message_raw.account_keys.len());
+                let mut accounts = message_raw.account_keys;
+                let instructions = message_raw.instructions;
+
+                //////////////////////////Balances////////////////////////////////////////////
+                if let Some(transaction_meta) = confirmed_transaction.transaction.meta {
+                    let loaded_addresses =
+                        <OptionSerializer<_> as Into<Option<UiLoadedAddresses>>>::into(
+                            transaction_meta.loaded_addresses,
+                        )
+                        .unwrap_or_default();
+
+                    // The addresses loaded from lookup tables never sign
+                    account_roles.extend(
+                        loaded_addresses
+                            .writable
+                            .iter()
+                            .map(|_| account_role(false, true))
+                            .chain(
+                                loaded_addresses
+                                    .readonly
+                                    .iter()
+                                    .map(|_| account_role(false, false)),
+                            ),
+                    );
+                    accounts.extend(loaded_addresses.writable.into_iter());
+                    accounts.extend(loaded_addresses.readonly.into_iter());
+
+                    inner_instructions = match transaction_meta.inner_instructions {
+                        OptionSerializer::Some(inner_instructions) => {
+                            OptionSerializer::Some(Self::merge_inner_instructions(
+                                tx_signature,
+                                inner_instructions,
+                                instructions.len(),
+                            )?)
+                        }
+                        inner_instructions => inner_instructions,
+                    };
+                    log_messages = transaction_meta.log_messages;
+                    let mut pre_balances = vec![Default::default(); accounts.len()];
+                    let mut post_balances = vec![Default::default(); accounts.len()];
+                    let mut pre_token_balance_mint = vec![Default::default(); accounts.len()];
+                    let mut pre_token_balance_owner: Vec<Option<String>> =
+                        vec![Default::default(); accounts.len()];
+                    let mut pre_token_balance_amount = vec![Default::default(); accounts.len()];
+                    let mut pre_token_balance_program_id: Vec<Option<String>> =
+                        vec![Default::default(); accounts.len()];
+                    let mut post_token_balance_mint = vec![Default::default(); accounts.len()];
+                    let mut post_token_balance_owner: Vec<Option<String>> =
+                        vec![Default::default(); accounts.len()];
+                    let mut post_token_balance_amount = vec![Default::default(); accounts.len()];
+                    let mut post_token_balance_program_id: Vec<Option<String>> =
+                        vec![Default::default(); accounts.len()];
+                    tx_status = if transaction_meta.status.is_ok() {
+                        TxStatus::Success
+                    } else {
+                        TxStatus::Failed
+                    };
+
+                    if let Some(TransactionError::InstructionError(instruction_idx, _)) =
+                        transaction_meta
+                            .err
+                            .as_ref()
+                            .or_else(|| transaction_meta.status.as_ref().err())
+                    {
+                        failed_instruction_idx = Some(*instruction_idx);
+                    }
+
+                    if transaction_meta.pre_balances.len() > accounts.len() {
+                        return Err(ParseInstructionError::InvalidLength {
+                            site: "pre_balances".to_string(),
+                            len: transaction_meta.pre_balances.len(),
+                            expected_len: accounts.len(),
+                        });
+                    }
+                    transaction_meta
+                        .pre_balances
+                        .iter()
+                        .enumerate()
+                        .for_each(|(i, pre_balance)| pre_balances[i] = Some(*pre_balance));
+
+                    if transaction_meta.post_balances.len() > accounts.len() {
+                        return Err(ParseInstructionError::InvalidLength {
+                            site: "post_balances".to_string(),
+                            len: transaction_meta.post_balances.len(),
+                            expected_len: accounts.len(),
+                        });
+                    }
+
+                    transaction_meta
+                        .post_balances
+                        .iter()
+                        .enumerate()
+                        .for_each(|(i, post_balance)| post_balances[i] = Some(*post_balance));
+
+                    let pre_token_balances: Option<Vec<UiTransactionTokenBalance>> =
+                        transaction_meta.pre_token_balances.into();
+
+                    for pre_token_balance in pre_token_balances.iter().flatten() {
+                        let indx = pre_token_balance.account_index as usize;
+
+                        if indx >= accounts.len() {
+                            return Err(ParseInstructionError::InvalidIndex {
+                                site: "pre_token_balance".to_string(),
+                                index: indx,
+                                max_len: accounts.len(),
+                            });
+                        }
+
+                        pre_token_balance_mint[indx] = Some(pre_token_balance.mint.clone());
+                        pre_token_balance_owner[indx] = pre_token_balance.owner.clone().into();
+                        pre_token_balance_amount[indx] =
+                            pre_token_balance.ui_token_amount.ui_amount;
+                        pre_token_balance_program_id[indx] =
+                            pre_token_balance.program_id.clone().into();
+                    }
+
+                    let post_token_balances: Option<Vec<UiTransactionTokenBalance>> =
+                        transaction_meta.post_token_balances.into();
+
+                    for post_token_balance in post_token_balances.iter().flatten() {
+                        let indx = post_token_balance.account_index as usize;
+
+                        if indx >= accounts.len() {
+                            return Err(ParseInstructionError::InvalidIndex {
+                                site: "post_token_balance".to_string(),
+                                index: indx,
+                                max_len: accounts.len(),
+                            });
+                        }
+
+                        post_token_balance_mint[indx] = Some(post_token_balance.mint.clone());
+                        post_token_balance_owner[indx] = post_token_balance.owner.clone().into();
+                        post_token_balance_amount[indx] =
+                            post_token_balance.ui_token_amount.ui_amount;
+                        post_token_balance_program_id[indx] =
+                            post_token_balance.program_id.clone().into();
+                    }
+
+                    token_transfers = Self::parse_token_transfers(
+                        tx_signature,
+                        slot,
+                        block_time.unwrap_or_default(),
+                        &accounts,
+                        pre_token_balances.as_deref().unwrap_or_default(),
+                        post_token_balances.as_deref().unwrap_or_default(),
+                    );
+
+                    // The events of a failed transaction were rolled back with its changes
+                    if tx_status == TxStatus::Success {
+                        let return_data: Option<UiTransactionReturnData> =
+                            transaction_meta.return_data.into();
+
+                        anchor_events = Self::parse_anchor_events(
+                            tx_signature,
+                            slot,
+                            Option::<&Vec<String>>::from(log_messages.as_ref())
+                                .map(Vec::as_slice)
+                                .unwrap_or_default(),
+                            return_data.as_ref(),
+                        );
+                    }
+
+                    accounts.iter().enumerate().for_each(|(i, account)| {
+                        if let Some(pre_balance) = pre_balances[i] {
+                            pre_balances_map.insert(account.clone(), pre_balance);
+                        }
+                        if let Some(mint) = post_token_balance_mint[i]
+                            .as_ref()
+                            .or(pre_token_balance_mint[i].as_ref())
+                        {
+                            token_mints.insert(account.clone(), mint.clone());
+                        }
+                        balances.push(Balance {
+                            tx_signature: tx_signature.clone(),
+                            account: account.clone(),
+                            pre_balance: pre_balances[i],
+                            post_balance: post_balances[i],
+                            pre_token_balance_mint: pre_token_balance_mint[i].clone(),
+                            pre_token_balance_owner: pre_token_balance_owner[i].clone(),
+                            pre_token_balance_amount: pre_token_balance_amount[i],
+                            pre_token_balance_program_id: pre_token_balance_program_id[i].clone(),
+                            post_token_balance_mint: post_token_balance_mint[i].clone(),
+                            post_token_balance_owner: post_token_balance_owner[i].clone(),
+                            post_token_balance_amount: post_token_balance_amount[i],
+                            post_token_balance_program_id: post_token_balance_program_id[i].clone(),
+                            inner_instructions_set: None,
+                            attributed_amount: None,
+                        });
+                    });
+
+                    // The token movements of a failed transaction were rolled back
+                    if tx_status == TxStatus::Success {
+                        balances.extend(Self::parse_attributed_balances(
+                            tx_signature,
+                            &accounts,
+                            &instructions,
+                            Option::<&Vec<UiInnerInstructions>>::from(inner_instructions.as_ref())
+                                .map(Vec::as_slice)
+                                .unwrap_or_default(),
+                            Option::<&Vec<String>>::from(log_messages.as_ref())
+                                .map(Vec::as_slice)
+                                .unwrap_or_default(),
+                            pre_token_balances.as_deref().unwrap_or_default(),
+                            post_token_balances.as_deref().unwrap_or_default(),
+                        ));
+                    }
+                }
+
+                //////////////////////////Instructions////////////////////////////////////////////
+
+                Self::append_instructions(
+                    instructions,
+                    inner_instructions.into(),
+                    log_messages.into(),
+                    accounts,
+                    account_roles,
+                    tx_signature.clone(),
+                    slot,
+                    block_time,
+                    tx_status,
+                    tx_version,
+                    num_signatures,
+                    &mut instructions_set,
+                    &mut parsed_instruction_arguments,
+                    &mut nft_events,
+                    &mut commission_changes,
+                    &mut claim_events,
+                    &mut entangler_swaps,
+                    &mut stake_account_events,
+                    &mut sol_transfers,
+                    &token_mints,
+                    options,
+                )?;
+            } else {
+                return Err(ParseInstructionError::Unsupported(
+                    "UiMessage::Raw in message".to_string(),
+                ));
+            }
+        } else {
+            return Err(ParseInstructionError::Unsupported(
+                "EncodedTransaction::Json in message".to_string(),
+            ));
+        }
+
+        let mut instructions: Vec<Instruction> = instructions_set.into_iter().collect();
+
+        // The sales, the pack claims, the candy machine mints and the bids of a failed
+        // transaction were rolled back with its changes
+        let (sale_events, pack_events, candy_mints, bid_events): (
+            Vec<SaleEvent>,
+            Vec<PackEvent>,
+            Vec<CandyMint>,
+            Vec<BidEvent>,
+        ) = if tx_status == TxStatus::Success {
+            (
+                Self::parse_sale_events(&instructions, &parsed_instruction_arguments, &token_mints),
+                Self::parse_pack_events(&instructions),
+                Self::parse_candy_mints(&instructions, &balances, &token_transfers),
+                Self::parse_bid_events(&instructions, &parsed_instruction_arguments),
+            )
+        } else {
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new())
+        };
+
+        if let Some(failed_instruction_idx) = failed_instruction_idx {
+            Self::attribute_instruction_failure(&mut instructions, failed_instruction_idx);
+        }
+
+        Ok(TransactionParsingResult {
+            instructions,
+            balances,
+            instruction_arguments: parsed_instruction_arguments,
+            token_transfers,
+            nft_events,
+            commission_changes,
+            claim_events,
+            entangler_swaps,
+            anchor_events,
+            sale_events,
+            pack_events,
+            candy_mints,
+            stake_account_events,
+            bid_events,
+            sol_transfers,
+        })
+    }
+
+    /// Only the instruction pointed by `InstructionError` is marked as failed (along with its
+    /// inner instructions), the preceding ones succeeded and the following ones never ran
+    fn attribute_instruction_failure(instructions: &mut [Instruction], failed_instruction_idx: u8) {
+        for instruction in instructions {
+            let top_level_idx = instruction
+                .transaction_instruction_idx
+                .unwrap_or(instruction.instruction_idx);
+
+            instruction.failed_instruction_idx = Some(failed_instruction_idx);
+            instruction.tx_status = match top_level_idx.cmp(&failed_instruction_idx) {
+                Ordering::Less => TxStatus::Success,
+                Ordering::Equal => TxStatus::Failed,
+                Ordering::Greater => TxStatus::NotExecuted,
+            };
+        }
+    }
+
+    /// Roles of the account keys of the message, which are ordered as writable signers, readonly
+    /// signers, writable and readonly accounts which don't sign
+    fn static_account_roles(header: &MessageHeader, static_accounts: usize) -> Vec<char> {
+        let signers = header.num_required_signatures as usize;
+        let writable_signers = signers.saturating_sub(header.num_readonly_signed_accounts as usize);
+        let writable_accounts =
+            static_accounts.saturating_sub(header.num_readonly_unsigned_accounts as usize);
+
+        (0..static_accounts)
+            .map(|idx| {
+                if idx < signers {
+                    account_role(true, idx < writable_signers)
+                } else {
+                    account_role(false, idx < writable_accounts)
+                }
+            })
+            .collect()
+    }
+
+    /// Human readable name of the program, "other" for the programs which are not parsed
+    pub fn program_name(program_address: &str) -> &'static str {
+        match program_address {
+            "packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu" => "nft_packs",
+            "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s" => "token_metadata",
+            "vau1zxA2LbssAUEF7Gpw91zMM1LvXrvpzJtmZ58rPsn" => "token_vault",
+            "p1exdMJcjVao65QdewkaZRUnU6VPSXhus9n2GzWfh98" => "metaplex",
+            "auctxRXPeJoc4817jDhf4HbjnhEcr1cCXenosMhK5R8" => "auction",
+            "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk" => "auction_house",
+            "cndy3Z4yapfJBmL3ShUp5exZKqR3z33thTzeNMm2gRZ" => "candy_machine",
+            "SaLeTjyUa5wXHnGuewUSyJ5JWZaHwz3TxqUntCE9czo" => "fixed_price_sale",
+            "gdrpGjVffourzkdDRrQmySw4aTHr8a3xmQzzxSwFD1a" => "gumdrop",
+            "qntmGodpGkrM42mN68VCZHXnKqDCT8rdY23wFcXCLPd" => "token_entangler",
+            "Stake11111111111111111111111111111111111111" => "stake",
+            "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy" => "stake_pool",
+            "Vote111111111111111111111111111111111111111" => "vote",
+            MEMO_PROGRAM | MEMO_V1_PROGRAM => "memo",
+            "11111111111111111111111111111111" => "system",
+            _ => "other",
+        }
+    }
+
+    /// Returns the JSON of the instruction and its arguments, the decoded Token Metadata, vote,
+    /// Gumdrop, Token Entangler, stake and system instructions are returned as well since NFT
+    /// events, commission changes, claim events, entangler swaps, stake account events and SOL
+    /// transfers are built from them. Failures to decode the data are returned as
+    /// `InstructionDecodeError`
+    pub fn parse_instruction(
+        program_address: &str,
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(String, Vec<InstructionArgument>, Option<ProgramInstruction>), ParseInstructionError>
+    {
+        debug!(actor = "transaction_parser", program = program_address; "{}", program_address);
+        let mut program_instruction = None;
+        let (instruction_raw, instruction_arguments) = match program_address {
+            "packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu" => {
+                TransactionParser::parse_nft_packs_instruction(data)
+            }
+            "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s" => {
+                TransactionParser::parse_token_metadata_instruction(data).map(
+                    |(instruction_raw, instruction_arguments, instruction)| {
+                        program_instruction =
+                            Some(ProgramInstruction::Metadata(Box::new(instruction)));
+                        (instruction_raw, instruction_arguments)
+                    },
+                )
+            }
+            "vau1zxA2LbssAUEF7Gpw91zMM1LvXrvpzJtmZ58rPsn" => {
+                TransactionParser::parse_token_vault_instruction(data)
+            }
+            "p1exdMJcjVao65QdewkaZRUnU6VPSXhus9n2GzWfh98" => {
+                TransactionParser::parse_metaplex_instruction(data)
+            }
+            "auctxRXPeJoc4817jDhf4HbjnhEcr1cCXenosMhK5R8" => {
+                TransactionParser::parse_auction_instruction(data)
+            }
+            "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk" => {
+                let res = TransactionParser::parse_auction_house_instruction(data);
+                if res.is_err() {
+                    // log::error!("AAAA: {:#?}", res);
+                    // todo!();
+                }
+
+                res
+            }
+            "cndy3Z4yapfJBmL3ShUp5exZKqR3z33thTzeNMm2gRZ" => {
+                TransactionParser::parse_candy_machine_instraction(data)
+            }
+            "SaLeTjyUa5wXHnGuewUSyJ5JWZaHwz3TxqUntCE9czo" => {
+                TransactionParser::parse_fixed_price_sale_instruction(data)
+            }
+            "gdrpGjVffourzkdDRrQmySw4aTHr8a3xmQzzxSwFD1a" => {
+                TransactionParser::parse_gumdrop_instruction(data).map(
+                    |(instruction_raw, instruction_arguments, instruction)| {
+                        program_instruction =
+                            Some(ProgramInstruction::Gumdrop(Box::new(instruction)));
+                        (instruction_raw, instruction_arguments)
+                    },
+                )
+            }
+            "qntmGodpGkrM42mN68VCZHXnKqDCT8rdY23wFcXCLPd" => {
+                TransactionParser::parse_tokent_entangler_instruction(data).map(
+                    |(instruction_raw, instruction_arguments, instruction)| {
+                        program_instruction = Some(ProgramInstruction::TokenEntangler(instruction));
+                        (instruction_raw, instruction_arguments)
+                    },
+                )
+            }
+            "Stake11111111111111111111111111111111111111" => {
+                TransactionParser::parse_stake_instruction(data).map(
+                    |(instruction_raw, instruction_arguments, instruction)| {
+                        program_instruction = Some(ProgramInstruction::Stake(instruction));
+                        (instruction_raw, instruction_arguments)
+                    },
+                )
+            }
+            "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy" => {
+                TransactionParser::parse_stake_pool_instruction(data)
+            }
+            "Vote111111111111111111111111111111111111111" => {
+                TransactionParser::parse_vote_instruction(data, options.max_vote_lockouts).map(
+                    |(instruction_raw, instruction_arguments, instruction)| {
+                        program_instruction = Some(ProgramInstruction::Vote(instruction));
+                        (instruction_raw, instruction_arguments)
+                    },
+                )
+            }
+            "11111111111111111111111111111111" => TransactionParser::parse_system_instruction(data)
+                .map(|(instruction_raw, instruction_arguments, instruction)| {
+                    program_instruction = Some(ProgramInstruction::System(instruction));
+                    (instruction_raw, instruction_arguments)
+                }),
+            MEMO_PROGRAM | MEMO_V1_PROGRAM => {
+                MemoInstruction::parse_instruction(data, options.max_memo_length)
+            }
+
+            _ => Err(ParseInstructionError::ProgramAddressMatchError),
+        }
+        .map_err(|err| {
+            if err.is_decode_error() {
+                ParseInstructionError::InstructionDecodeError {
+                    program: program_address.to_string(),
+                    discriminant: data.first().copied(),
+                    err: Box::new(err),
+                }
+            } else {
+                err
+            }
+        })?;
+
+        Ok((instruction_raw, instruction_arguments, program_instruction))
+    }
+
+    fn parse_tokent_entangler_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>, TokenEntanglerInstruction), ParseInstructionError>
+    {
+        let sighash: [u8; 8] = data.get(..8).unwrap_or(data).try_into()?;
+        let data = &data[8..];
+        TokenEntanglerInstruction::parse_instruction(sighash, data)
+    }
+
+    fn parse_gumdrop_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>, GumdropInstruction), ParseInstructionError> {
+        let sighash: [u8; 8] = data.get(..8).unwrap_or(data).try_into()?;
+        let data = &data[8..];
+        GumdropInstruction::parse_instruction(sighash, data)
+    }
+
+    fn parse_fixed_price_sale_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let sighash: [u8; 8] = data.get(..8).unwrap_or(data).try_into()?;
+        let data = &data[8..];
+        FixedPriceSaleInstruction::parse_instruction(sighash, data)
+    }
+
+    fn parse_candy_machine_instraction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let sighash: [u8; 8] = data.get(..8).unwrap_or(data).try_into()?;
+        let data = &data[8..];
+        CandyMachineInstruction::parse_instruction(sighash, data)
+    }
+
+    fn parse_auction_house_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let sighash: [u8; 8] = data.get(..8).unwrap_or(data).try_into()?;
+        let data = &data[8..];
+        AuctionHouseInstruction::parse_instruction(sighash, data)
+    }
+
+    fn parse_nft_packs_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let instruction = NFTPacksInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Nft Packs".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments))
+    }
+
+    fn parse_token_metadata_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>, MetadataInstruction), ParseInstructionError>
+    {
+        let instruction = MetadataInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                let err = Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Token Metadata".to_string(),
+                    err,
+                });
+
+                return err;
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        // The decoded instruction is kept for the NFT events
+        let instruction_arguments = instruction.clone().get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments, instruction))
+    }
+
+    fn parse_token_vault_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let instruction = VaultInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Token Vault".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments))
+    }
+
+    fn parse_metaplex_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let instruction = MetaplexInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Metaplex".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments))
+    }
+
+    fn parse_auction_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let instruction = AuctionInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Auction".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments))
+    }
+
+    /// The arguments get only the `max_lockouts` newest lockouts of a tower, the JSON keeps all
+    /// of them
+    fn parse_vote_instruction(
+        data: &[u8],
+        max_lockouts: usize,
+    ) -> Result<(String, Vec<InstructionArgument>, VoteInstruction), ParseInstructionError> {
+        let instruction = limited_deserialize::<VoteInstruction>(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::LimDeserializeInInstructionError {
+                    instruction: "Vote instruction".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        // The decoded instruction is kept for the commission changes
+        let mut truncated_instruction = instruction.clone();
+        truncated_instruction.truncate_lockouts(max_lockouts);
+        let instruction_arguments = truncated_instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments, instruction))
+    }
+
+    fn parse_stake_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>, StakeInstruction), ParseInstructionError> {
+        let instruction = limited_deserialize::<StakeInstruction>(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::LimDeserializeInInstructionError {
+                    instruction: "Stake instruction".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments, instruction))
+    }
+
+    fn parse_stake_pool_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>), ParseInstructionError> {
+        let instruction = StakePoolInstruction::try_from_slice(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::DeserializeInInstructionError {
+                    instruction: "Stake Pool".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments))
+    }
+
+    fn parse_system_instruction(
+        data: &[u8],
+    ) -> Result<(String, Vec<InstructionArgument>, SystemInstruction), ParseInstructionError> {
+        let instruction = limited_deserialize::<SystemInstruction>(data);
+
+        let instruction = match instruction {
+            Err(err) => {
+                return Err(ParseInstructionError::LimDeserializeInInstructionError {
+                    instruction: "SystemInstruction".to_string(),
+                    err,
+                })
+            }
+            Ok(val) => val,
+        };
+
+        let json = serde_json::to_string(&instruction)?;
+
+        let instruction_arguments = instruction.get_arguments("", 0, None, "");
+
+        Ok((json, instruction_arguments, instruction))
+    }
+}
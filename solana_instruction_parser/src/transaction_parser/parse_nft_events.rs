@@ -0,0 +1,210 @@
+use crate::instructions::token_metadata_instruction::{
+    Collection, CollectionToggle, CreateArgs, MetadataInstruction, TokenStandard, UpdateArgs,
+    VerificationArgs,
+};
+use crate::NftEvent;
+
+use super::TransactionParser;
+
+fn collection_key(collection: &Option<Collection>) -> Option<String> {
+    collection
+        .as_ref()
+        .map(|collection| collection.key.to_string())
+}
+
+fn toggled_collection_key(collection: &CollectionToggle) -> Option<String> {
+    match collection {
+        CollectionToggle::Set(collection) => Some(collection.key.to_string()),
+        CollectionToggle::None | CollectionToggle::Clear => None,
+    }
+}
+
+fn token_standard_name(token_standard: &TokenStandard) -> Option<String> {
+    Some(format!("{:?}", token_standard))
+}
+
+/// Names and uris are stored in fixed size buffers padded with zeros
+fn unpadded(value: &str) -> Option<String> {
+    Some(value.trim_end_matches('\0').to_string())
+}
+
+impl TransactionParser {
+    /// The event is built from the already decoded instruction, only the mint and the collection
+    /// mint of `Verify` are taken from the accounts of the instruction. None is returned for the
+    /// instructions which are not NFT events
+    pub fn parse_nft_event(
+        instruction: &MetadataInstruction,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+    ) -> Option<NftEvent> {
+        let account = |idx: usize| accounts.get(idx).cloned();
+
+        let nft_event = NftEvent {
+            tx_signature: tx_signature.to_string(),
+            slot,
+            ..Default::default()
+        };
+
+        let nft_event = match instruction {
+            MetadataInstruction::Create(CreateArgs::V1 { asset_data, .. }) => NftEvent {
+                mint: account(2),
+                collection_key: collection_key(&asset_data.collection),
+                token_standard: token_standard_name(&asset_data.token_standard),
+                event_type: "Create".to_string(),
+                name: unpadded(&asset_data.name),
+                uri: unpadded(&asset_data.uri),
+                ..nft_event
+            },
+            MetadataInstruction::CreateMetadataAccountV3(args) => NftEvent {
+                mint: account(1),
+                collection_key: collection_key(&args.data.collection),
+                event_type: "CreateMetadataAccountV3".to_string(),
+                name: unpadded(&args.data.name),
+                uri: unpadded(&args.data.uri),
+                ..nft_event
+            },
+            MetadataInstruction::Update(args) => {
+                let (data, collection, standard) = match args {
+                    UpdateArgs::V1 {
+                        data, collection, ..
+                    } => (data, Some(collection), None),
+                    UpdateArgs::AsUpdateAuthorityV2 {
+                        data,
+                        collection,
+                        token_standard,
+                        ..
+                    } => (data, Some(collection), token_standard.as_ref()),
+                    UpdateArgs::AsAuthorityItemDelegateV2 { token_standard, .. } => {
+                        (&None, None, token_standard.as_ref())
+                    }
+                    UpdateArgs::AsCollectionDelegateV2 { collection, .. }
+                    | UpdateArgs::AsCollectionItemDelegateV2 { collection, .. } => {
+                        (&None, Some(collection), None)
+                    }
+                    UpdateArgs::AsDataDelegateV2 { data, .. }
+                    | UpdateArgs::AsDataItemDelegateV2 { data, .. } => (data, None, None),
+                    UpdateArgs::AsProgrammableConfigDelegateV2 { .. }
+                    | UpdateArgs::AsProgrammableConfigItemDelegateV2 { .. } => (&None, None, None),
+                };
+
+                NftEvent {
+                    mint: account(3),
+                    collection_key: collection.and_then(toggled_collection_key),
+                    token_standard: standard.and_then(token_standard_name),
+                    event_type: "Update".to_string(),
+                    name: data.as_ref().and_then(|data| unpadded(&data.name)),
+                    uri: data.as_ref().and_then(|data| unpadded(&data.uri)),
+                    ..nft_event
+                }
+            }
+            MetadataInstruction::Verify(args) => NftEvent {
+                collection_key: match args {
+                    VerificationArgs::CollectionV1 => account(3),
+                    VerificationArgs::CreatorV1 => None,
+                },
+                event_type: "Verify".to_string(),
+                ..nft_event
+            },
+            MetadataInstruction::Transfer(_) => NftEvent {
+                mint: account(4),
+                event_type: "Transfer".to_string(),
+                ..nft_event
+            },
+            _ => return None,
+        };
+
+        Some(nft_event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::token_metadata_instruction::{CreateMetadataAccountArgsV3, DataV2};
+    use crate::{ParseOptions, ProgramInstruction};
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+
+    const TOKEN_METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+    /// Decodes CreateMetadataAccountV3 the way transactions are parsed and builds its event
+    fn create_metadata_account_v3_event(collection: Option<Collection>) -> Option<NftEvent> {
+        let data = MetadataInstruction::CreateMetadataAccountV3(CreateMetadataAccountArgsV3 {
+            data: DataV2 {
+                name: "Degen Ape #1\0\0\0\0".to_string(),
+                symbol: "DAPE".to_string(),
+                uri: "https://arweave.net/ape".to_string(),
+                seller_fee_basis_points: 500,
+                creators: None,
+                collection,
+                uses: None,
+            },
+            is_mutable: true,
+            collection_details: None,
+        })
+        .try_to_vec()
+        .unwrap();
+
+        let (_, _, program_instruction) = TransactionParser::parse_instruction(
+            TOKEN_METADATA_PROGRAM,
+            &data,
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        let accounts = ["metadata".to_string(), "mint".to_string()];
+
+        match program_instruction {
+            Some(ProgramInstruction::Metadata(metadata_instruction)) => {
+                TransactionParser::parse_nft_event(&metadata_instruction, &accounts, "signature", 1)
+            }
+            _ => panic!("A Token Metadata instruction is expected"),
+        }
+    }
+
+    #[test]
+    fn create_metadata_account_v3_with_collection() {
+        let collection_key = Pubkey::new_unique();
+
+        let nft_event = create_metadata_account_v3_event(Some(Collection {
+            verified: false,
+            key: collection_key,
+        }));
+
+        assert_eq!(
+            nft_event,
+            Some(NftEvent {
+                tx_signature: "signature".to_string(),
+                slot: 1,
+                mint: Some("mint".to_string()),
+                collection_key: Some(collection_key.to_string()),
+                token_standard: None,
+                event_type: "CreateMetadataAccountV3".to_string(),
+                name: Some("Degen Ape #1".to_string()),
+                uri: Some("https://arweave.net/ape".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn create_metadata_account_v3_without_collection() {
+        let nft_event = create_metadata_account_v3_event(None).unwrap();
+
+        assert_eq!(nft_event.mint.as_deref(), Some("mint"));
+        assert_eq!(nft_event.collection_key, None);
+        assert_eq!(nft_event.event_type, "CreateMetadataAccountV3");
+    }
+
+    #[test]
+    fn other_instructions_are_not_events() {
+        assert_eq!(
+            TransactionParser::parse_nft_event(
+                &MetadataInstruction::SignMetadata,
+                &[],
+                "signature",
+                1
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{Instruction, PackEvent};
+
+use super::TransactionParser;
+
+const NFT_PACKS_PROGRAM: &str = "packFeFNZzMfD9aVWL7QbGz1WcU7R9zpf6pvNsw2BLu";
+const TOKEN_METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+/// Token Metadata instruction printing the edition of the claimed card
+const MINT_NEW_EDITION: &str = "MintNewEditionFromMasterEditionViaToken";
+/// Position of `new_mint` in the accounts of `MintNewEditionFromMasterEditionViaToken`
+const MINT_NEW_EDITION_MINT_IDX: usize = 3;
+
+impl TransactionParser {
+    /// Pack events of the decoded instructions of a successful transaction, in the order of the
+    /// instructions. The pack, the card and the user are taken from the positions of the
+    /// accounts of the NFT Packs instruction
+    pub(super) fn parse_pack_events(instructions: &[Instruction]) -> Vec<PackEvent> {
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program == NFT_PACKS_PROGRAM)
+            .filter_map(|instruction| Self::parse_pack_event(instruction, instructions))
+            .collect()
+    }
+
+    fn parse_pack_event(
+        instruction: &Instruction,
+        instructions: &[Instruction],
+    ) -> Option<PackEvent> {
+        let account = |idx: usize| instruction.accounts.get(idx).cloned();
+
+        let (event_type, pack_card, claimer, new_mint) = match instruction.instruction_name.as_str()
+        {
+            // pack_set, pack_config, store, edition, edition_mint, pack_voucher, proving_process,
+            // user_wallet, ...
+            "RequestCardForRedeem" => ("Open", None, account(7), None),
+            // pack_set, proving_process, user_wallet, pack_card, ...
+            "ClaimPack" => (
+                "Claim",
+                account(3),
+                account(2),
+                Self::claimed_mint(instruction, instructions),
+            ),
+            // pack_set, authority
+            "Deactivate" => ("Deactivate", None, None, None),
+            _ => return None,
+        };
+
+        Some(PackEvent {
+            tx_signature: instruction.tx_signature.clone(),
+            slot: instruction.slot,
+            pack_set: account(0)?,
+            pack_card,
+            claimer,
+            new_mint,
+            event_type: event_type.to_string(),
+        })
+    }
+
+    /// New mint of the first edition printed by the Token Metadata program after `claim_pack`
+    /// in its inner instruction set: among the inner instructions of a top-level `ClaimPack`,
+    /// or following an inner one
+    fn claimed_mint(claim_pack: &Instruction, instructions: &[Instruction]) -> Option<String> {
+        instructions
+            .iter()
+            .filter(|instruction| match claim_pack.inner_instructions_set {
+                None => instruction.transaction_instruction_idx == Some(claim_pack.instruction_idx),
+                Some(inner_instructions_set) => {
+                    instruction.inner_instructions_set == Some(inner_instructions_set)
+                        && instruction.instruction_idx > claim_pack.instruction_idx
+                }
+            })
+            .find(|instruction| {
+                instruction.program == TOKEN_METADATA_PROGRAM
+                    && instruction.instruction_name == MINT_NEW_EDITION
+            })
+            .and_then(|instruction| instruction.accounts.get(MINT_NEW_EDITION_MINT_IDX).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+    /// Instruction of `program` named `name` whose accounts are named after their positions,
+    /// an inner one when `outer` is given
+    fn instruction(
+        program: &str,
+        name: &str,
+        instruction_idx: u8,
+        outer: Option<u8>,
+        accounts: usize,
+    ) -> Instruction {
+        let mut instruction = Instruction::new(&Pubkey::default(), &Signature::default());
+        instruction.program = program.to_string();
+        instruction.tx_signature = "signature".to_string();
+        instruction.slot = 100;
+        instruction.instruction_idx = instruction_idx;
+        instruction.inner_instructions_set = outer.map(|_| 0);
+        instruction.transaction_instruction_idx = outer;
+        instruction.instruction_name = name.to_string();
+        instruction.accounts = (0..accounts)
+            .map(|idx| format!("{}_{}", name, idx))
+            .collect();
+
+        instruction
+    }
+
+    #[test]
+    fn claim_pack_with_printed_edition() {
+        let instructions = vec![
+            instruction(NFT_PACKS_PROGRAM, "ClaimPack", 0, None, 19),
+            instruction(TOKEN_METADATA_PROGRAM, MINT_NEW_EDITION, 0, Some(0), 14),
+        ];
+
+        assert_eq!(
+            TransactionParser::parse_pack_events(&instructions),
+            vec![PackEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                pack_set: "ClaimPack_0".to_string(),
+                pack_card: Some("ClaimPack_3".to_string()),
+                claimer: Some("ClaimPack_2".to_string()),
+                new_mint: Some(format!("{}_3", MINT_NEW_EDITION)),
+                event_type: "Claim".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn edition_of_another_instruction_is_not_claimed() {
+        let instructions = vec![
+            instruction(NFT_PACKS_PROGRAM, "ClaimPack", 0, None, 19),
+            instruction(NFT_PACKS_PROGRAM, "ClaimPack", 1, None, 19),
+            instruction(TOKEN_METADATA_PROGRAM, MINT_NEW_EDITION, 0, Some(1), 14),
+        ];
+
+        let new_mints: Vec<_> = TransactionParser::parse_pack_events(&instructions)
+            .into_iter()
+            .map(|pack_event| pack_event.new_mint)
+            .collect();
+        assert_eq!(
+            new_mints,
+            vec![None, Some(format!("{}_3", MINT_NEW_EDITION))]
+        );
+    }
+
+    #[test]
+    fn inner_claim_pack_is_followed_by_its_edition() {
+        let instructions = vec![
+            // The edition of a preceding instruction of the set has no mint account
+            instruction(TOKEN_METADATA_PROGRAM, MINT_NEW_EDITION, 0, Some(0), 3),
+            instruction(NFT_PACKS_PROGRAM, "ClaimPack", 1, Some(0), 19),
+            instruction(TOKEN_METADATA_PROGRAM, MINT_NEW_EDITION, 2, Some(0), 14),
+        ];
+
+        let pack_events = TransactionParser::parse_pack_events(&instructions);
+
+        assert_eq!(pack_events.len(), 1);
+        assert_eq!(
+            pack_events[0].new_mint,
+            Some(format!("{}_3", MINT_NEW_EDITION))
+        );
+    }
+
+    #[test]
+    fn open_and_deactivate() {
+        let instructions = vec![
+            instruction(NFT_PACKS_PROGRAM, "RequestCardForRedeem", 0, None, 13),
+            instruction(NFT_PACKS_PROGRAM, "Deactivate", 1, None, 2),
+            instruction(NFT_PACKS_PROGRAM, "Activate", 2, None, 2),
+        ];
+
+        let pack_events: Vec<_> = TransactionParser::parse_pack_events(&instructions)
+            .into_iter()
+            .map(|pack_event| {
+                (
+                    pack_event.event_type,
+                    pack_event.pack_set,
+                    pack_event.claimer,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            pack_events,
+            vec![
+                (
+                    "Open".to_string(),
+                    "RequestCardForRedeem_0".to_string(),
+                    Some("RequestCardForRedeem_7".to_string())
+                ),
+                ("Deactivate".to_string(), "Deactivate_0".to_string(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+
+use crate::{Instruction, InstructionArgument, SaleEvent};
+
+use super::parse_entangler_swaps::TOKEN_ENTANGLER_PROGRAM;
+use super::TransactionParser;
+
+const AUCTION_HOUSE_PROGRAM: &str = "hausS13jsjafwWwGqZTUQRmWyvyxn9EQpqMwV1PBBmk";
+const FIXED_PRICE_SALE_PROGRAM: &str = "SaLeTjyUa5wXHnGuewUSyJ5JWZaHwz3TxqUntCE9czo";
+/// Mint of wrapped SOL, the currency of the sales paid in lamports
+const NATIVE_MINT: &str = "So11111111111111111111111111111111111111112";
+
+impl TransactionParser {
+    /// Sales of the decoded instructions of a successful transaction, in the order of the
+    /// instructions. The price and the size are taken from the flattened arguments of the
+    /// instruction, the parties and the mints from the positions of its accounts
+    pub(super) fn parse_sale_events(
+        instructions: &[Instruction],
+        instruction_arguments: &[InstructionArgument],
+        token_mints: &HashMap<String, String>,
+    ) -> Vec<SaleEvent> {
+        instructions
+            .iter()
+            .filter_map(|instruction| {
+                Self::parse_sale_event(instruction, instruction_arguments, token_mints)
+            })
+            .collect()
+    }
+
+    fn parse_sale_event(
+        instruction: &Instruction,
+        instruction_arguments: &[InstructionArgument],
+        token_mints: &HashMap<String, String>,
+    ) -> Option<SaleEvent> {
+        let account = |idx: usize| instruction.accounts.get(idx).cloned();
+        let argument = |arg_path: &str| {
+            instruction_arguments
+                .iter()
+                .find(|argument| {
+                    argument.instruction_idx == instruction.instruction_idx
+                        && argument.inner_instructions_set == instruction.inner_instructions_set
+                        && argument.arg_path == arg_path
+                })
+                .and_then(|argument| argument.unsigned_value)
+        };
+
+        let (mint, seller, buyer, price_lamports, token_size, currency_mint) = match (
+            instruction.program.as_str(),
+            instruction.instruction_name.as_str(),
+        ) {
+            // buyer, seller, token_account, token_mint, metadata, treasury_mint, ... The partial
+            // sales fill a part of the order, at its own price when it is given
+            (
+                AUCTION_HOUSE_PROGRAM,
+                "ExecuteSale"
+                | "AuctioneerExecuteSale"
+                | "ExecutePartialSale"
+                | "AuctioneerExecutePartialSale",
+            ) => (
+                account(3)?,
+                account(1),
+                account(0)?,
+                argument("/partial_order_price").or_else(|| argument("/buyer_price")),
+                argument("/partial_order_size").or_else(|| argument("/token_size"))?,
+                account(5),
+            ),
+            // market, selling_resource, user_token_account, user_wallet, trade_history,
+            // treasury_holder, new_metadata, new_edition, master_edition, new_mint, ... The user
+            // pays from the wallet itself for a market priced in SOL
+            (FIXED_PRICE_SALE_PROGRAM, "Buy") => {
+                let user_token_account = account(2)?;
+                let user_wallet = account(3)?;
+                let currency_mint = if user_token_account == user_wallet {
+                    Some(NATIVE_MINT.to_string())
+                } else {
+                    token_mints.get(&user_token_account).cloned()
+                };
+
+                (account(9)?, None, user_wallet, None, 1, currency_mint)
+            }
+            // treasury_mint, payer, ..., replacement_token_mint, ... The payer gets the
+            // replacement token from the escrow of the entangled pair
+            (TOKEN_ENTANGLER_PROGRAM, "Swap") => {
+                (account(7)?, None, account(1)?, None, 1, account(0))
+            }
+            _ => return None,
+        };
+
+        Some(SaleEvent {
+            tx_signature: instruction.tx_signature.clone(),
+            slot: instruction.slot,
+            block_time: instruction.block_time.unwrap_or_default(),
+            marketplace_program: instruction.program.clone(),
+            instruction_name: instruction.instruction_name.clone(),
+            instruction_idx: instruction.instruction_idx,
+            inner_instructions_set: instruction.inner_instructions_set,
+            mint,
+            seller,
+            buyer,
+            price_lamports,
+            token_size,
+            currency_mint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use borsh::BorshSerialize;
+    use rust_base58::ToBase58;
+    use solana_program::pubkey::Pubkey;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    /// Sighashes of the instructions, the arguments follow them
+    const EXECUTE_SALE: [u8; 8] = [37, 74, 217, 157, 79, 49, 35, 6];
+    const EXECUTE_PARTIAL_SALE: [u8; 8] = [163, 18, 35, 157, 49, 164, 203, 133];
+    const BUY: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+    const SWAP: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+    /// escrow_payment_bump, free_trade_state_bump, program_as_signer_bump, buyer_price,
+    /// token_size and, for a partial sale, partial_order_size and partial_order_price
+    fn execute_sale_data(
+        sighash: [u8; 8],
+        buyer_price: u64,
+        token_size: u64,
+        partial_order: Option<(Option<u64>, Option<u64>)>,
+    ) -> String {
+        let mut data = sighash.to_vec();
+        data.extend([254, 253, 255]);
+        data.extend(buyer_price.to_le_bytes());
+        data.extend(token_size.to_le_bytes());
+        if let Some(partial_order) = partial_order {
+            data.extend(partial_order.try_to_vec().unwrap());
+        }
+
+        data.to_base58()
+    }
+
+    /// A transaction of instructions of `program` given as their data and accounts. The same
+    /// key is passed once to the message, the token balances are given as (account, mint)
+    fn sale_transaction(
+        program: &str,
+        instructions: &[(String, Vec<String>)],
+        token_balances: &[(&str, &str)],
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let mut account_keys: Vec<String> = Vec::new();
+        let mut key_idx = |key: &str| match account_keys.iter().position(|k| k == key) {
+            Some(idx) => idx,
+            None => {
+                account_keys.push(key.to_string());
+                account_keys.len() - 1
+            }
+        };
+
+        let instructions: Vec<_> = instructions
+            .iter()
+            .map(|(data, accounts)| {
+                let accounts: Vec<usize> = accounts.iter().map(|key| key_idx(key)).collect();
+                (data, accounts)
+            })
+            .collect();
+        let token_balances: Vec<_> = token_balances
+            .iter()
+            .map(|(account, mint)| {
+                serde_json::json!({
+                    "accountIndex": key_idx(account),
+                    "mint": mint,
+                    "uiTokenAmount": {
+                        "uiAmount": 1.0,
+                        "decimals": 0,
+                        "amount": "1",
+                        "uiAmountString": "1"
+                    }
+                })
+            })
+            .collect();
+        let program_idx = key_idx(program);
+
+        let instructions: Vec<_> = instructions
+            .into_iter()
+            .map(|(data, accounts)| {
+                serde_json::json!({
+                    "programIdIndex": program_idx,
+                    "accounts": accounts,
+                    "data": data,
+                })
+            })
+            .collect();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": instructions
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": token_balances,
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    fn unique_accounts(len: usize) -> Vec<String> {
+        (0..len).map(|_| Pubkey::new_unique().to_string()).collect()
+    }
+
+    #[test]
+    fn execute_sale_of_auction_house() {
+        // buyer, seller, token_account, token_mint, metadata, treasury_mint,
+        // escrow_payment_account, seller_payment_receipt_account, buyer_receipt_token_account,
+        // authority, auction_house, auction_house_fee_account, auction_house_treasury,
+        // buyer_trade_state, seller_trade_state, free_trade_state, token_program,
+        // system_program, ata_program, program_as_signer, rent
+        let mut accounts = unique_accounts(21);
+        accounts[5] = NATIVE_MINT.to_string();
+
+        let sale_events = parse_transaction(sale_transaction(
+            AUCTION_HOUSE_PROGRAM,
+            &[(
+                execute_sale_data(EXECUTE_SALE, 2_500_000_000, 1, None),
+                accounts.clone(),
+            )],
+            &[],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .sale_events;
+
+        assert_eq!(
+            sale_events,
+            vec![SaleEvent {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                block_time: 1700000000,
+                marketplace_program: AUCTION_HOUSE_PROGRAM.to_string(),
+                instruction_name: "ExecuteSale".to_string(),
+                instruction_idx: 0,
+                inner_instructions_set: None,
+                mint: accounts[3].clone(),
+                seller: Some(accounts[1].clone()),
+                buyer: accounts[0].clone(),
+                price_lamports: Some(2_500_000_000),
+                token_size: 1,
+                currency_mint: Some(NATIVE_MINT.to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn partial_sale_at_order_price() {
+        let accounts = unique_accounts(21);
+
+        let sale_events = parse_transaction(sale_transaction(
+            AUCTION_HOUSE_PROGRAM,
+            &[
+                (
+                    execute_sale_data(EXECUTE_PARTIAL_SALE, 1000, 10, Some((Some(4), Some(400)))),
+                    accounts.clone(),
+                ),
+                (
+                    execute_sale_data(EXECUTE_PARTIAL_SALE, 1000, 10, Some((None, None))),
+                    accounts.clone(),
+                ),
+            ],
+            &[],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .sale_events;
+
+        let orders: Vec<(Option<u64>, u64)> = sale_events
+            .iter()
+            .map(|sale_event| (sale_event.price_lamports, sale_event.token_size))
+            .collect();
+        assert_eq!(orders, vec![(Some(400), 4), (Some(1000), 10)]);
+        assert_eq!(sale_events[0].currency_mint, Some(accounts[5].clone()));
+    }
+
+    #[test]
+    fn sales_of_one_transaction_are_told_apart() {
+        let (first, second) = (unique_accounts(21), unique_accounts(21));
+
+        let sale_events = parse_transaction(sale_transaction(
+            AUCTION_HOUSE_PROGRAM,
+            &[
+                (execute_sale_data(EXECUTE_SALE, 100, 1, None), first.clone()),
+                (
+                    execute_sale_data(EXECUTE_SALE, 200, 1, None),
+                    second.clone(),
+                ),
+            ],
+            &[],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .sale_events;
+
+        let sales: Vec<(u8, &str, Option<u64>)> = sale_events
+            .iter()
+            .map(|sale_event| {
+                (
+                    sale_event.instruction_idx,
+                    sale_event.mint.as_str(),
+                    sale_event.price_lamports,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sales,
+            vec![
+                (0, first[3].as_str(), Some(100)),
+                (1, second[3].as_str(), Some(200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixed_price_sale_buy() {
+        let buy_data = |accounts: &[String]| {
+            let mut data = BUY.to_vec();
+            data.extend([255, 254]);
+            (data.to_base58(), accounts.to_vec())
+        };
+        // market, selling_resource, user_token_account, user_wallet, trade_history,
+        // treasury_holder, new_metadata, new_edition, master_edition, new_mint, edition_marker,
+        // vault, owner, new_token_account, ...
+        let token_market = unique_accounts(19);
+        let mut sol_market = unique_accounts(19);
+        sol_market[2] = sol_market[3].clone();
+        let currency = Pubkey::new_unique().to_string();
+
+        let sale_events = parse_transaction(sale_transaction(
+            FIXED_PRICE_SALE_PROGRAM,
+            &[buy_data(&token_market), buy_data(&sol_market)],
+            &[(&token_market[2], &currency)],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .sale_events;
+
+        assert_eq!(sale_events.len(), 2);
+        assert_eq!(sale_events[0].instruction_name, "Buy");
+        assert_eq!(sale_events[0].mint, token_market[9]);
+        assert_eq!(sale_events[0].buyer, token_market[3]);
+        assert_eq!(sale_events[0].seller, None);
+        assert_eq!(sale_events[0].price_lamports, None);
+        assert_eq!(sale_events[0].currency_mint, Some(currency));
+        assert_eq!(sale_events[1].currency_mint, Some(NATIVE_MINT.to_string()));
+    }
+
+    #[test]
+    fn entangler_swap_buys_the_replacement_token() {
+        let accounts = unique_accounts(17);
+
+        let sale_events = parse_transaction(sale_transaction(
+            TOKEN_ENTANGLER_PROGRAM,
+            &[(SWAP.to_base58(), accounts.clone())],
+            &[],
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .sale_events;
+
+        assert_eq!(sale_events.len(), 1);
+        assert_eq!(sale_events[0].mint, accounts[7]);
+        assert_eq!(sale_events[0].buyer, accounts[1]);
+        assert_eq!(sale_events[0].currency_mint, Some(accounts[0].clone()));
+        assert_eq!(sale_events[0].token_size, 1);
+    }
+
+    #[test]
+    fn failed_transaction_has_no_sales() {
+        let sale_events = parse_transaction(sale_transaction(
+            AUCTION_HOUSE_PROGRAM,
+            &[(
+                execute_sale_data(EXECUTE_SALE, 100, 1, None),
+                unique_accounts(21),
+            )],
+            &[],
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .sale_events;
+
+        assert!(sale_events.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::instructions::system_instruction::SystemInstruction;
+use crate::SolTransfer;
+
+use super::TransactionParser;
+
+impl TransactionParser {
+    /// SOL transfer of a system instruction, None for the instructions which move no lamports.
+    /// The source and the destination are taken from the positions of the accounts
+    pub fn parse_sol_transfer(
+        instruction: &SystemInstruction,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+        instruction_idx: u8,
+        inner_instructions_set: Option<u8>,
+    ) -> Option<SolTransfer> {
+        let account = |idx: usize| accounts.get(idx).cloned();
+
+        let (source, destination, lamports, transfer_kind) = match instruction {
+            // funding account, recipient
+            SystemInstruction::Transfer { lamports } => (0, 1, *lamports, "Transfer"),
+            // funding account, base, recipient
+            SystemInstruction::TransferWithSeed { lamports, .. } => {
+                (0, 2, *lamports, "TransferWithSeed")
+            }
+            // funding account, new account
+            SystemInstruction::CreateAccount { lamports, .. }
+            | SystemInstruction::CreateAccountWithSeed { lamports, .. } => {
+                (0, 1, *lamports, "CreateAccountFunding")
+            }
+            // nonce account, recipient
+            SystemInstruction::WithdrawNonceAccount { lamports } => {
+                (0, 1, *lamports, "WithdrawNonce")
+            }
+            _ => return None,
+        };
+
+        if lamports == 0 {
+            return None;
+        }
+
+        Some(SolTransfer {
+            tx_signature: tx_signature.to_string(),
+            slot,
+            instruction_idx,
+            inner_instructions_set,
+            source: account(source)?,
+            destination: account(destination)?,
+            lamports,
+            transfer_kind: transfer_kind.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_transaction;
+    use rust_base58::ToBase58;
+    use solana_program::pubkey::Pubkey;
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    /// Accounts named after their positions
+    fn accounts(len: usize) -> Vec<String> {
+        (0..len).map(|idx| format!("account_{}", idx)).collect()
+    }
+
+    /// Source, destination, lamports and kind of the transfer of the instruction
+    fn parse(instruction: SystemInstruction) -> Option<(String, String, u64, String)> {
+        TransactionParser::parse_sol_transfer(&instruction, &accounts(5), "signature", 100, 0, None)
+            .map(|transfer| {
+                (
+                    transfer.source,
+                    transfer.destination,
+                    transfer.lamports,
+                    transfer.transfer_kind,
+                )
+            })
+    }
+
+    fn transfer(
+        source: usize,
+        destination: usize,
+        lamports: u64,
+        transfer_kind: &str,
+    ) -> Option<(String, String, u64, String)> {
+        Some((
+            format!("account_{}", source),
+            format!("account_{}", destination),
+            lamports,
+            transfer_kind.to_string(),
+        ))
+    }
+
+    #[test]
+    fn every_kind_of_transfer() {
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            parse(SystemInstruction::Transfer { lamports: 10 }),
+            transfer(0, 1, 10, "Transfer")
+        );
+        assert_eq!(
+            parse(SystemInstruction::TransferWithSeed {
+                lamports: 20,
+                from_seed: "seed".to_string(),
+                from_owner: owner,
+            }),
+            transfer(0, 2, 20, "TransferWithSeed")
+        );
+        assert_eq!(
+            parse(SystemInstruction::CreateAccount {
+                lamports: 30,
+                space: 165,
+                owner,
+            }),
+            transfer(0, 1, 30, "CreateAccountFunding")
+        );
+        assert_eq!(
+            parse(SystemInstruction::CreateAccountWithSeed {
+                base: Pubkey::new_unique(),
+                seed: "seed".to_string(),
+                lamports: 40,
+                space: 165,
+                owner,
+            }),
+            transfer(0, 1, 40, "CreateAccountFunding")
+        );
+        assert_eq!(
+            parse(SystemInstruction::WithdrawNonceAccount { lamports: 50 }),
+            transfer(0, 1, 50, "WithdrawNonce")
+        );
+    }
+
+    #[test]
+    fn instructions_moving_no_lamports_have_no_transfer() {
+        assert_eq!(parse(SystemInstruction::Transfer { lamports: 0 }), None);
+        assert_eq!(parse(SystemInstruction::Allocate { space: 165 }), None);
+        assert_eq!(parse(SystemInstruction::AdvanceNonceAccount), None);
+    }
+
+    /// Transfer of 1000 lamports between two wallets, failed by `err` when it isn't null
+    fn transfer_transaction(err: serde_json::Value) -> EncodedConfirmedTransactionWithStatusMeta {
+        // Discriminant 2 followed by the lamports
+        let data = [&2u32.to_le_bytes()[..], &1000u64.to_le_bytes()].concat();
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1
+                    },
+                    "accountKeys": [
+                        "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm",
+                        "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8",
+                        "11111111111111111111111111111111"
+                    ],
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": 2,
+                        "accounts": [0, 1],
+                        "data": data.to_base58(),
+                    }]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    #[test]
+    fn top_level_transfer_is_parsed() {
+        let sol_transfers = parse_transaction(transfer_transaction(serde_json::Value::Null))
+            .unwrap()
+            .sol_transfers;
+
+        assert_eq!(
+            sol_transfers,
+            vec![SolTransfer {
+                tx_signature: "signature".to_string(),
+                slot: 100,
+                instruction_idx: 0,
+                inner_instructions_set: None,
+                source: "GXzqybrSAbDmALLJQFKZMMdib7QPBTavyGatoAGtEmPm".to_string(),
+                destination: "E29Nen991Z4Gin11wxNV3Nq8xJh5a1nYbGAYBgZDLCB8".to_string(),
+                lamports: 1000,
+                transfer_kind: "Transfer".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn failed_transaction_transfers_nothing() {
+        let sol_transfers = parse_transaction(transfer_transaction(
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .sol_transfers;
+
+        assert!(sol_transfers.is_empty());
+    }
+}
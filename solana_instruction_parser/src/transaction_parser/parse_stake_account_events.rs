@@ -0,0 +1,320 @@
+use crate::instructions::stake_instruction::{StakeAuthorize, StakeInstruction};
+use crate::StakeAccountEvent;
+
+use super::TransactionParser;
+
+impl TransactionParser {
+    /// Stake account events of a stake instruction, empty for the instructions which don't change
+    /// the authorities, the lockup or the activation of the account. The stake account is the first
+    /// account of every instruction, the new authorities of the checked instructions are taken
+    /// from their signer accounts
+    pub fn parse_stake_account_events(
+        instruction: &StakeInstruction,
+        accounts: &[String],
+        tx_signature: &str,
+        slot: u64,
+    ) -> Vec<StakeAccountEvent> {
+        let account = |idx: usize| accounts.get(idx).cloned();
+        let stake_acc = match account(0) {
+            Some(stake_acc) => stake_acc,
+            None => return Vec::new(),
+        };
+        let event = |event_type: &str,
+                     authority_type: Option<&str>,
+                     new_authority: Option<String>,
+                     lockup_expiration: Option<i64>| StakeAccountEvent {
+            tx_signature: tx_signature.to_string(),
+            slot,
+            stake_acc: stake_acc.clone(),
+            event_type: event_type.to_string(),
+            authority_type: authority_type.map(str::to_string),
+            old_authority: None,
+            new_authority,
+            lockup_expiration,
+        };
+
+        match instruction {
+            StakeInstruction::Initialize(authorized, lockup) => {
+                // A zero timestamp is no lockup
+                let lockup_expiration =
+                    Some(lockup.unix_timestamp).filter(|unix_timestamp| *unix_timestamp != 0);
+
+                vec![
+                    event(
+                        "Initialize",
+                        Some(authority_type(&StakeAuthorize::Staker)),
+                        Some(authorized.staker.to_string()),
+                        lockup_expiration,
+                    ),
+                    event(
+                        "Initialize",
+                        Some(authority_type(&StakeAuthorize::Withdrawer)),
+                        Some(authorized.withdrawer.to_string()),
+                        lockup_expiration,
+                    ),
+                ]
+            }
+            // stake, rent sysvar, staker, withdrawer
+            StakeInstruction::InitializeChecked => vec![
+                event(
+                    "Initialize",
+                    Some(authority_type(&StakeAuthorize::Staker)),
+                    account(2),
+                    None,
+                ),
+                event(
+                    "Initialize",
+                    Some(authority_type(&StakeAuthorize::Withdrawer)),
+                    account(3),
+                    None,
+                ),
+            ],
+            StakeInstruction::Authorize(new_authority, stake_authorize) => vec![event(
+                "Authorize",
+                Some(authority_type(stake_authorize)),
+                Some(new_authority.to_string()),
+                None,
+            )],
+            StakeInstruction::AuthorizeWithSeed(args) => vec![event(
+                "Authorize",
+                Some(authority_type(&args.stake_authorize)),
+                Some(args.new_authorized_pubkey.to_string()),
+                None,
+            )],
+            // stake, clock sysvar, authority, new authority
+            StakeInstruction::AuthorizeChecked(stake_authorize) => vec![event(
+                "Authorize",
+                Some(authority_type(stake_authorize)),
+                account(3),
+                None,
+            )],
+            // stake, base, clock sysvar, new authority
+            StakeInstruction::AuthorizeCheckedWithSeed(args) => vec![event(
+                "Authorize",
+                Some(authority_type(&args.stake_authorize)),
+                account(3),
+                None,
+            )],
+            StakeInstruction::SetLockup(args) => vec![event(
+                "SetLockup",
+                args.custodian.map(|_| "Custodian"),
+                args.custodian.map(|custodian| custodian.to_string()),
+                args.unix_timestamp,
+            )],
+            // stake, lockup or withdraw authority, new custodian
+            StakeInstruction::SetLockupChecked(args) => {
+                let custodian = account(2);
+
+                vec![event(
+                    "SetLockup",
+                    custodian.as_ref().map(|_| "Custodian"),
+                    custodian,
+                    args.unix_timestamp,
+                )]
+            }
+            StakeInstruction::Deactivate | StakeInstruction::DeactivateDelinquent => {
+                vec![event("Deactivate", None, None, None)]
+            }
+            StakeInstruction::Withdraw(_) => vec![event("Withdraw", None, None, None)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn authority_type(stake_authorize: &StakeAuthorize) -> &'static str {
+    match stake_authorize {
+        StakeAuthorize::Staker => "Staker",
+        StakeAuthorize::Withdrawer => "Withdrawer",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::stake_instruction::{Authorized, Lockup};
+    use crate::parse_transaction;
+    use rust_base58::ToBase58;
+    use solana_program::{pubkey::Pubkey, stake, sysvar};
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    /// A transaction of an `Authorize` instruction built by the stake program helper
+    fn authorize_transaction(
+        stake_acc: &Pubkey,
+        authorized: &Pubkey,
+        new_authority: &Pubkey,
+        stake_authorize: stake::state::StakeAuthorize,
+        err: serde_json::Value,
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        let instruction = stake::instruction::authorize(
+            stake_acc,
+            authorized,
+            new_authority,
+            stake_authorize,
+            None,
+        );
+
+        let transaction = serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 2
+                    },
+                    "accountKeys": [
+                        authorized.to_string(),
+                        stake_acc.to_string(),
+                        sysvar::clock::id().to_string(),
+                        stake::program::id().to_string()
+                    ],
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": 3,
+                        "accounts": [1, 2, 0],
+                        "data": instruction.data.to_base58(),
+                    }]
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": if err.is_null() {
+                    serde_json::json!({ "Ok": null })
+                } else {
+                    serde_json::json!({ "Err": err })
+                },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        });
+
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 100,
+            transaction: serde_json::from_value(transaction).unwrap(),
+            block_time: Some(1700000000),
+        }
+    }
+
+    fn authorize_event(
+        stake_acc: &Pubkey,
+        authority_type: &str,
+        new_authority: &Pubkey,
+    ) -> StakeAccountEvent {
+        StakeAccountEvent {
+            tx_signature: "signature".to_string(),
+            slot: 100,
+            stake_acc: stake_acc.to_string(),
+            event_type: "Authorize".to_string(),
+            authority_type: Some(authority_type.to_string()),
+            old_authority: None,
+            new_authority: Some(new_authority.to_string()),
+            lockup_expiration: None,
+        }
+    }
+
+    #[test]
+    fn staker_is_authorized() {
+        let stake_acc = Pubkey::new_unique();
+        let new_staker = Pubkey::new_unique();
+
+        let stake_account_events = parse_transaction(authorize_transaction(
+            &stake_acc,
+            &Pubkey::new_unique(),
+            &new_staker,
+            stake::state::StakeAuthorize::Staker,
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .stake_account_events;
+
+        assert_eq!(
+            stake_account_events,
+            vec![authorize_event(&stake_acc, "Staker", &new_staker)]
+        );
+    }
+
+    #[test]
+    fn withdrawer_is_authorized() {
+        let stake_acc = Pubkey::new_unique();
+        let new_withdrawer = Pubkey::new_unique();
+
+        let stake_account_events = parse_transaction(authorize_transaction(
+            &stake_acc,
+            &Pubkey::new_unique(),
+            &new_withdrawer,
+            stake::state::StakeAuthorize::Withdrawer,
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+        .stake_account_events;
+
+        assert_eq!(
+            stake_account_events,
+            vec![authorize_event(&stake_acc, "Withdrawer", &new_withdrawer)]
+        );
+    }
+
+    #[test]
+    fn failed_transaction_authorizes_nobody() {
+        let stake_account_events = parse_transaction(authorize_transaction(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            stake::state::StakeAuthorize::Staker,
+            serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] }),
+        ))
+        .unwrap()
+        .stake_account_events;
+
+        assert!(stake_account_events.is_empty());
+    }
+
+    #[test]
+    fn initialize_has_an_event_per_authority() {
+        let staker = Pubkey::new_unique();
+        let withdrawer = Pubkey::new_unique();
+        let instruction = StakeInstruction::Initialize(
+            Authorized { staker, withdrawer },
+            Lockup {
+                unix_timestamp: 1800000000,
+                ..Default::default()
+            },
+        );
+
+        let events = TransactionParser::parse_stake_account_events(
+            &instruction,
+            &[Some("stake".to_string())],
+            "signature",
+            1,
+        );
+
+        let authorities: Vec<_> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.authority_type.as_deref(),
+                    event.new_authority.clone(),
+                    event.lockup_expiration,
+                )
+            })
+            .collect();
+        assert_eq!(
+            authorities,
+            [
+                (Some("Staker"), Some(staker.to_string()), Some(1800000000)),
+                (
+                    Some("Withdrawer"),
+                    Some(withdrawer.to_string()),
+                    Some(1800000000)
+                ),
+            ]
+        );
+        assert!(events.iter().all(|event| event.event_type == "Initialize"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::TokenTransfer;
+
+use solana_transaction_status::UiTransactionTokenBalance;
+use std::collections::{BTreeMap, VecDeque};
+
+use super::TransactionParser;
+
+/// Net flow of a single mint within a transaction
+#[derive(Default)]
+struct MintFlows {
+    decimals: u8,
+    program_id: Option<String>,
+    owners: BTreeMap<String, i128>,
+}
+
+impl TransactionParser {
+    /// Token transfers are derived from the pre and post token balances rather than from
+    /// instructions. Changes are netted per (mint, owner), so a multi-hop transfer is seen as
+    /// a single flow from the owners who lost tokens to the owners who got them. Senders and
+    /// receivers are paired greedily in the owner order, whatever is left on the receiving side
+    /// is treated as minted and on the sending side as burned
+    pub fn parse_token_transfers(
+        tx_signature: &str,
+        slot: u64,
+        block_time: u64,
+        accounts: &[String],
+        pre_token_balances: &[UiTransactionTokenBalance],
+        post_token_balances: &[UiTransactionTokenBalance],
+    ) -> Vec<TokenTransfer> {
+        let mut mints: BTreeMap<String, MintFlows> = BTreeMap::new();
+
+        for (token_balances, sign) in [(pre_token_balances, -1), (post_token_balances, 1)] {
+            for token_balance in token_balances {
+                let amount: i128 = match token_balance.ui_token_amount.amount.parse() {
+                    Ok(amount) => amount,
+                    Err(_) => continue,
+                };
+
+                // Token balances of old transactions have no owner, the token account itself
+                // is used then
+                let owner = match Option::<String>::from(token_balance.owner.clone())
+                    .or_else(|| accounts.get(token_balance.account_index as usize).cloned())
+                {
+                    Some(owner) => owner,
+                    None => continue,
+                };
+
+                let flows = mints.entry(token_balance.mint.clone()).or_default();
+                flows.decimals = token_balance.ui_token_amount.decimals;
+                if flows.program_id.is_none() {
+                    flows.program_id = token_balance.program_id.clone().into();
+                }
+                *flows.owners.entry(owner).or_default() += sign * amount;
+            }
+        }
+
+        let mut token_transfers = Vec::new();
+
+        for (mint, flows) in mints {
+            let mut sources: VecDeque<(String, u128)> = flows
+                .owners
+                .iter()
+                .filter(|(_, flow)| **flow < 0)
+                .map(|(owner, flow)| (owner.clone(), flow.unsigned_abs()))
+                .collect();
+            let mut destinations: VecDeque<(String, u128)> = flows
+                .owners
+                .iter()
+                .filter(|(_, flow)| **flow > 0)
+                .map(|(owner, flow)| (owner.clone(), flow.unsigned_abs()))
+                .collect();
+
+            loop {
+                let (source_owner, destination_owner, amount) =
+                    match (sources.front_mut(), destinations.front_mut()) {
+                        (Some(source), Some(destination)) => {
+                            let amount = source.1.min(destination.1);
+                            source.1 -= amount;
+                            destination.1 -= amount;
+                            (Some(source.0.clone()), Some(destination.0.clone()), amount)
+                        }
+                        (Some(source), None) => {
+                            let amount = std::mem::take(&mut source.1);
+                            (Some(source.0.clone()), None, amount)
+                        }
+                        (None, Some(destination)) => {
+                            let amount = std::mem::take(&mut destination.1);
+                            (None, Some(destination.0.clone()), amount)
+                        }
+                        (None, None) => break,
+                    };
+
+                if matches!(sources.front(), Some((_, 0))) {
+                    sources.pop_front();
+                }
+                if matches!(destinations.front(), Some((_, 0))) {
+                    destinations.pop_front();
+                }
+
+                token_transfers.push(TokenTransfer {
+                    tx_signature: tx_signature.to_string(),
+                    slot,
+                    block_time,
+                    mint: mint.clone(),
+                    source_owner,
+                    destination_owner,
+                    amount: amount as u64,
+                    decimals: flows.decimals,
+                    program_id: flows.program_id.clone(),
+                });
+            }
+        }
+
+        token_transfers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_account_decoder::parse_token::UiTokenAmount;
+    use solana_transaction_status::option_serializer::OptionSerializer;
+
+    fn token_balance(account_index: u8, owner: &str, amount: u64) -> UiTransactionTokenBalance {
+        UiTransactionTokenBalance {
+            account_index,
+            mint: "mint".to_string(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: None,
+                decimals: 6,
+                amount: amount.to_string(),
+                ui_amount_string: amount.to_string(),
+            },
+            owner: OptionSerializer::Some(owner.to_string()),
+            program_id: OptionSerializer::None,
+        }
+    }
+
+    #[test]
+    fn multi_hop_transfer() {
+        // A sends 100 to B which forwards 60 to C, so B keeps 40
+        let pre_token_balances = [token_balance(0, "A", 100), token_balance(1, "B", 0)];
+        let post_token_balances = [
+            token_balance(0, "A", 0),
+            token_balance(1, "B", 40),
+            token_balance(2, "C", 60),
+        ];
+
+        let token_transfers = TransactionParser::parse_token_transfers(
+            "signature",
+            1,
+            2,
+            &[],
+            &pre_token_balances,
+            &post_token_balances,
+        );
+
+        let flows: Vec<_> = token_transfers
+            .iter()
+            .map(|transfer| {
+                (
+                    transfer.source_owner.as_deref(),
+                    transfer.destination_owner.as_deref(),
+                    transfer.amount,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            flows,
+            [(Some("A"), Some("B"), 40), (Some("A"), Some("C"), 60)]
+        );
+        assert!(token_transfers
+            .iter()
+            .all(|transfer| transfer.decimals == 6));
+    }
+
+    #[test]
+    fn burn() {
+        let pre_token_balances = [token_balance(0, "A", 100)];
+        let post_token_balances = [token_balance(0, "A", 75)];
+
+        let token_transfers = TransactionParser::parse_token_transfers(
+            "signature",
+            1,
+            2,
+            &[],
+            &pre_token_balances,
+            &post_token_balances,
+        );
+
+        assert_eq!(token_transfers.len(), 1);
+        assert_eq!(token_transfers[0].source_owner.as_deref(), Some("A"));
+        assert_eq!(token_transfers[0].destination_owner, None);
+        assert_eq!(token_transfers[0].amount, 25);
+    }
+}
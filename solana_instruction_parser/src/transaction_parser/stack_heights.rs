@@ -0,0 +1,132 @@
+use solana_transaction_status::{UiInnerInstructions, UiInstruction};
+
+use super::TransactionParser;
+
+impl TransactionParser {
+    /// Stack heights of the inner instructions recovered from the `Program <id> invoke [<height>]`
+    /// log lines, for the payloads which don't carry `stackHeight`. Every executed instruction
+    /// logs its invocation, so the lines follow the outer instructions each one followed by
+    /// its inner set. The heights are returned per inner set in the order of `inner_instructions`,
+    /// None if the logs don't match the instructions, e.g. because they were truncated
+    pub(super) fn stack_heights_from_logs(
+        log_messages: &[String],
+        outer_programs: &[String],
+        inner_instructions: &[UiInnerInstructions],
+        accounts: &[String],
+    ) -> Option<Vec<Vec<u8>>> {
+        let mut invocations = log_messages.iter().filter_map(|line| {
+            let (program, height) = line
+                .strip_prefix("Program ")?
+                .strip_suffix(']')?
+                .split_once(" invoke [")?;
+            Some((program, height.parse::<u8>().ok()?))
+        });
+
+        let mut stack_heights = vec![Vec::new(); inner_instructions.len()];
+        let mut next = invocations.next();
+
+        for (outer_idx, outer_program) in outer_programs.iter().enumerate() {
+            // The instructions after the failed one are never invoked
+            let Some((program, 1)) = next else {
+                break;
+            };
+            if program != outer_program {
+                return None;
+            }
+
+            let set = inner_instructions
+                .iter()
+                .position(|set| set.index as usize == outer_idx);
+            let mut inner_programs = set
+                .map(|set| inner_instructions[set].instructions.iter())
+                .into_iter()
+                .flatten()
+                .map(|instruction| match instruction {
+                    UiInstruction::Compiled(instruction) => {
+                        accounts.get(instruction.program_id_index as usize)
+                    }
+                    _ => None,
+                });
+
+            next = invocations.next();
+            while let Some((program, height)) = next.filter(|(_, height)| *height > 1) {
+                if inner_programs.next()?? != program {
+                    return None;
+                }
+                stack_heights[set?].push(height);
+                next = invocations.next();
+            }
+
+            if inner_programs.next().is_some() {
+                return None;
+            }
+        }
+
+        next.is_none().then_some(stack_heights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::UiCompiledInstruction;
+
+    fn inner_set(index: u8, program_id_indexes: &[u8]) -> UiInnerInstructions {
+        UiInnerInstructions {
+            index,
+            instructions: program_id_indexes
+                .iter()
+                .map(|program_id_index| {
+                    UiInstruction::Compiled(UiCompiledInstruction {
+                        program_id_index: *program_id_index,
+                        accounts: Vec::new(),
+                        data: String::new(),
+                        stack_height: None,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn nested_invocations() {
+        let accounts = ["outer", "cpi", "nested"].map(String::from);
+        let stack_heights = TransactionParser::stack_heights_from_logs(
+            &logs(&[
+                "Program outer invoke [1]",
+                "Program log: Instruction: Outer",
+                "Program cpi invoke [2]",
+                "Program nested invoke [3]",
+                "Program nested success",
+                "Program cpi success",
+                "Program nested invoke [2]",
+                "Program nested success",
+                "Program outer success",
+                "Program nested invoke [1]",
+                "Program nested success",
+            ]),
+            &["outer", "nested"].map(String::from),
+            &[inner_set(0, &[1, 2, 2])],
+            &accounts,
+        );
+
+        assert_eq!(stack_heights, Some(vec![vec![2, 3, 2]]));
+    }
+
+    #[test]
+    fn truncated_logs() {
+        let accounts = ["outer", "cpi"].map(String::from);
+        let stack_heights = TransactionParser::stack_heights_from_logs(
+            &logs(&["Program outer invoke [1]", "Log truncated"]),
+            &["outer"].map(String::from),
+            &[inner_set(0, &[1])],
+            &accounts,
+        );
+
+        assert_eq!(stack_heights, None);
+    }
+}
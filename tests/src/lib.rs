@@ -0,0 +1,167 @@
+//! PostgreSQL and ClickHouse started in Docker for the integration tests of the services. The
+//! containers are removed when the returned handles are dropped, so every test gets empty
+//! databases. The data_loader runs the tests as ignored ones, the analyzers enable them with the
+//! `integration-tests` feature
+
+use anyhow::{anyhow, Result};
+use diesel::{Connection, PgConnection};
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+use testcontainers::{clients::Cli, Container, GenericImage};
+
+/// Host the ports of the containers are published on
+const HOST: &str = "127.0.0.1";
+const USER: &str = "indexer";
+const PASSWORD: &str = "indexer";
+const DATABASE: &str = "indexer";
+
+/// Attempts to connect to a started container, one per `RETRY_DELAY`
+const RETRY_ATTEMPTS: usize = 60;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Transaction in the JSON of `getTransaction`, the way the data_loader stores it
+pub const TRANSACTION: &str = include_str!("../fixtures/transaction.json");
+pub const TRANSACTION_SIGNATURE: &str =
+    "3gDkTVuedWyYiqaZMhZE7axGZMnWS6Jaha62SJuf67HY6D3hgZZ2qmUwwh4qEZZhCCYETHjFXDMzayJGqwHW1ChU";
+
+/// Client of the Docker CLI the containers are run with. They borrow it, so it's leaked to live
+/// as long as the tests
+fn docker() -> &'static Cli {
+    Box::leak(Box::new(Cli::default()))
+}
+
+pub struct Postgres {
+    _container: Container<'static, GenericImage>,
+    /// `postgresql://` url of the database
+    pub url: String,
+}
+
+impl Postgres {
+    pub async fn start() -> Result<Self> {
+        let container = docker().run(
+            GenericImage::new("postgres", "15-alpine")
+                .with_exposed_port(5432)
+                .with_env_var("POSTGRES_USER", USER)
+                .with_env_var("POSTGRES_PASSWORD", PASSWORD)
+                .with_env_var("POSTGRES_DB", DATABASE),
+        );
+
+        let url = format!(
+            "postgresql://{}:{}@{}:{}/{}",
+            USER,
+            PASSWORD,
+            HOST,
+            container.get_host_port_ipv4(5432),
+            DATABASE
+        );
+
+        // The server is restarted by the image after the database is created
+        retry(|| async { PgConnection::establish(&url).map(|_| ()) }).await?;
+
+        Ok(Self {
+            _container: container,
+            url,
+        })
+    }
+
+    pub fn connection(&self) -> Result<PgConnection> {
+        Ok(PgConnection::establish(&self.url)?)
+    }
+
+    /// Creates the queue tables the data_loader fills and the analyzers read
+    pub fn run_queue_migrations(&self) -> Result<()> {
+        let migrations = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../data_loader/src/storages/queue_storage/migrations");
+
+        diesel_migrations::run_pending_migrations_in_directory(
+            &self.connection()?,
+            &migrations,
+            &mut std::io::sink(),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Single node cluster, so the services can be tested with and without the `on_ch_cluster`
+/// feature
+pub struct Clickhouse {
+    _container: Container<'static, GenericImage>,
+    tcp_port: u16,
+    http_port: u16,
+}
+
+impl Clickhouse {
+    pub async fn start() -> Result<Self> {
+        let cluster_config =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/clickhouse_cluster.xml");
+        let container = docker().run(
+            GenericImage::new("clickhouse/clickhouse-server", "23.8")
+                .with_exposed_port(9000)
+                .with_exposed_port(8123)
+                .with_env_var("CLICKHOUSE_USER", USER)
+                .with_env_var("CLICKHOUSE_PASSWORD", PASSWORD)
+                .with_env_var("CLICKHOUSE_DB", DATABASE)
+                .with_volume(
+                    cluster_config.to_str().unwrap(),
+                    "/etc/clickhouse-server/config.d/cluster.xml",
+                ),
+        );
+
+        let clickhouse = Self {
+            tcp_port: container.get_host_port_ipv4(9000),
+            http_port: container.get_host_port_ipv4(8123),
+            _container: container,
+        };
+
+        let pool = clickhouse_rs::Pool::new(clickhouse.clickhouse_rs_url());
+        retry(|| async { pool.get_handle().await?.ping().await }).await?;
+
+        Ok(clickhouse)
+    }
+
+    /// `DATABASE_URL` of the native protocol, the way the services are configured
+    pub fn tcp_url(&self) -> String {
+        format!(
+            "tcp://{}:{}@tcp({}:{})/{}",
+            USER, PASSWORD, HOST, self.tcp_port, DATABASE
+        )
+    }
+
+    /// `DATABASE_URL` of the HTTP interface, the way the services are configured
+    pub fn http_url(&self) -> String {
+        format!(
+            "http://{}:{}@http({}:{})/{}",
+            USER, PASSWORD, HOST, self.http_port, DATABASE
+        )
+    }
+
+    /// Url of the form `clickhouse_rs` expects, for the assertions on the stored rows
+    pub fn clickhouse_rs_url(&self) -> String {
+        format!(
+            "tcp://{}:{}@{}:{}/{}",
+            USER, PASSWORD, HOST, self.tcp_port, DATABASE
+        )
+    }
+}
+
+/// Polls `check` until it succeeds, the containers accept connections a bit after they start
+pub async fn retry<F, Fut, T, E>(mut check: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut last_error = String::new();
+
+    for _ in 0..RETRY_ATTEMPTS {
+        match check().await {
+            Ok(value) => return Ok(value),
+            Err(err) => last_error = err.to_string(),
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+
+    Err(anyhow!("Gave up waiting: {}", last_error))
+}